@@ -0,0 +1,164 @@
+//! Heap allocations per execution for the interpreter and the compiled executor
+//!
+//! Counts allocations through a wrapping global allocator, prints the
+//! per-execution averages, then runs the timing benchmarks.
+//!
+//! Run with: cargo bench -p ordo-core --bench alloc_bench
+
+use criterion::{criterion_group, BatchSize, Criterion};
+use ordo_core::prelude::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A ruleset setting variables in action steps and building a multi-key output
+fn create_ruleset() -> RuleSet {
+    let mut ruleset = RuleSet::new("alloc", "score");
+    ruleset.add_step(Step::action(
+        "score",
+        "Score",
+        vec![
+            Action::set_var("base", Expr::field("user.level")),
+            Action::set_var("bonus", ExprParser::parse("order.total * 0.1").unwrap()),
+            Action::set_var("score", ExprParser::parse("$base * 10 + $bonus").unwrap()),
+        ],
+        "check",
+    ));
+    ruleset.add_step(
+        Step::decision("check", "Check")
+            .branch(Condition::from_string("$score >= 50"), "approve")
+            .default("reject")
+            .build(),
+    );
+    ruleset.add_step(Step::terminal(
+        "approve",
+        "Approve",
+        TerminalResult::new("APPROVED")
+            .with_message("approved")
+            .with_output("score", Expr::field("$score"))
+            .with_output("bonus", Expr::field("$bonus"))
+            .with_output("level", Expr::field("user.level"))
+            .with_output("country", Expr::field("user.country")),
+    ));
+    ruleset.add_step(Step::terminal(
+        "reject",
+        "Reject",
+        TerminalResult::new("REJECTED").with_output("score", Expr::field("$score")),
+    ));
+    ruleset.compile().unwrap();
+    ruleset
+}
+
+fn create_input() -> Value {
+    serde_json::from_str(r#"{"user": {"level": 5, "country": "NL"}, "order": {"total": 120.0}}"#)
+        .unwrap()
+}
+
+/// Average (allocations, bytes) per call of `f`, after a warm-up
+fn measure(iterations: usize, mut f: impl FnMut()) -> (f64, f64) {
+    for _ in 0..100 {
+        f();
+    }
+    let count = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    for _ in 0..iterations {
+        f();
+    }
+    let count = ALLOCATIONS.load(Ordering::Relaxed) - count;
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes;
+    (
+        count as f64 / iterations as f64,
+        bytes as f64 / iterations as f64,
+    )
+}
+
+fn report_allocations() {
+    const ITERATIONS: usize = 10_000;
+    let ruleset = create_ruleset();
+    let compiled = RuleSetCompiler::compile(&ruleset).unwrap();
+    let executor = RuleExecutor::new();
+    let compiled_executor = CompiledRuleExecutor::new();
+    let input = create_input();
+
+    // Input cloning is measured separately and subtracted
+    let (input_allocs, input_bytes) = measure(ITERATIONS, || {
+        black_box(input.clone());
+    });
+    let (interp_allocs, interp_bytes) = measure(ITERATIONS, || {
+        black_box(executor.execute(&ruleset, input.clone()).unwrap());
+    });
+    let (compiled_allocs, compiled_bytes) = measure(ITERATIONS, || {
+        black_box(compiled_executor.execute(&compiled, input.clone()).unwrap());
+    });
+
+    println!("allocations per execution (excluding input clone):");
+    println!(
+        "  interpreter: {:>6.2} allocs {:>8.1} bytes",
+        interp_allocs - input_allocs,
+        interp_bytes - input_bytes
+    );
+    println!(
+        "  compiled:    {:>6.2} allocs {:>8.1} bytes",
+        compiled_allocs - input_allocs,
+        compiled_bytes - input_bytes
+    );
+}
+
+fn bench_execution(c: &mut Criterion) {
+    let ruleset = create_ruleset();
+    let compiled = RuleSetCompiler::compile(&ruleset).unwrap();
+    let executor = RuleExecutor::new();
+    let compiled_executor = CompiledRuleExecutor::new();
+    let input = create_input();
+
+    let mut group = c.benchmark_group("alloc_execution");
+    group.bench_function("interpreter", |b| {
+        b.iter_batched(
+            || input.clone(),
+            |input| black_box(executor.execute(&ruleset, input).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("compiled", |b| {
+        b.iter_batched(
+            || input.clone(),
+            |input| black_box(compiled_executor.execute(&compiled, input).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_execution);
+
+fn main() {
+    report_allocations();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
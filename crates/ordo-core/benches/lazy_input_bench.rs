@@ -0,0 +1,126 @@
+//! Full input materialization vs. field-usage driven tape projection
+//!
+//! Run with: cargo bench -p ordo-core --features lazy-input --bench lazy_input_bench
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use ordo_core::prelude::*;
+use std::hint::black_box;
+
+fn create_ruleset() -> RuleSet {
+    let mut ruleset = RuleSet::new("lazy_input", "check");
+    ruleset.add_step(
+        Step::decision("check", "Check")
+            .branch(
+                Condition::from_string("user.age >= 18 && order.total > 100"),
+                "approve",
+            )
+            .default("reject")
+            .build(),
+    );
+    ruleset.add_step(Step::terminal(
+        "approve",
+        "Approve",
+        TerminalResult::new("APPROVED").with_output("country", Expr::field("user.country")),
+    ));
+    ruleset.add_step(Step::terminal(
+        "reject",
+        "Reject",
+        TerminalResult::new("REJECTED"),
+    ));
+    ruleset.compile().unwrap();
+    ruleset
+}
+
+/// A request payload with `items` line items and as many metadata keys
+fn create_payload(items: usize) -> Vec<u8> {
+    let line_items: Vec<serde_json::Value> = (0..items)
+        .map(|i| {
+            serde_json::json!({
+                "sku": format!("SKU-{:06}", i),
+                "name": format!("Item number {}", i),
+                "price": i as f64 * 1.25,
+                "quantity": i % 7,
+                "tags": ["a", "b", "c"],
+            })
+        })
+        .collect();
+    let metadata: serde_json::Map<String, serde_json::Value> = (0..items)
+        .map(|i| {
+            (
+                format!("key_{}", i),
+                serde_json::json!(format!("value {}", i)),
+            )
+        })
+        .collect();
+    serde_json::to_vec(&serde_json::json!({
+        "user": { "age": 30, "country": "NL", "name": "Alice", "history": line_items.clone() },
+        "order": { "total": 250.0, "items": line_items },
+        "metadata": metadata,
+    }))
+    .unwrap()
+}
+
+fn bench_lazy_input(c: &mut Criterion) {
+    let ruleset = create_ruleset();
+    let usage = ruleset.field_usage();
+    let executor = RuleExecutor::new();
+
+    let mut group = c.benchmark_group("input_materialization");
+    for items in [10, 1_000, 10_000] {
+        let payload = create_payload(items);
+        group.throughput(Throughput::Bytes(payload.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("full_serde_json", items),
+            &payload,
+            |b, p| b.iter(|| black_box(serde_json::from_slice::<Value>(p).unwrap())),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("full_simd_json", items),
+            &payload,
+            |b, p| {
+                b.iter_batched(
+                    || p.clone(),
+                    |mut buf| black_box(simd_json::from_slice::<Value>(&mut buf).unwrap()),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("projected_tape", items),
+            &payload,
+            |b, p| {
+                b.iter_batched(
+                    || p.clone(),
+                    |mut buf| {
+                        let tape = simd_json::to_tape(&mut buf).unwrap();
+                        black_box(usage.project_tape(tape.as_value()))
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("projected_tape_execute", items),
+            &payload,
+            |b, p| {
+                b.iter_batched(
+                    || p.clone(),
+                    |mut buf| {
+                        let tape = simd_json::to_tape(&mut buf).unwrap();
+                        let input = usage.project_tape(tape.as_value());
+                        black_box(executor.execute(&ruleset, input).unwrap())
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lazy_input);
+criterion_main!(benches);
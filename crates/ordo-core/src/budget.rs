@@ -0,0 +1,382 @@
+//! Per-execution resource budgets
+//!
+//! A [`ResourceBudget`] caps the work one rule execution may do:
+//! - Instruction fuel: one unit per expression evaluated (condition, action
+//!   or output) and per function call, plus one unit per element (or 64 bytes
+//!   of string) handed to a built-in function. The interpreter, bytecode VM
+//!   and JIT charge per expression and call alike, so a budget runs out at
+//!   the same point on every tier (JIT code reads packed inputs and charges
+//!   no per-element work)
+//! - Memory: bytes of `Value`s produced by operators and functions
+//! - Sizes: the longest array and string any operation may produce
+//! - Regex complexity: the compiled size of a regex program and of its lazy
+//!   DFA cache
+//!
+//! The budget is activated for the current thread with [`BudgetScope`]
+//! (`RuleExecutor` does this for `ExecutionOptions::budget`). Operations that
+//! could allocate a lot check the limits before allocating. Without an active
+//! budget, metering costs one thread-local flag check.
+
+use crate::context::Value;
+use crate::error::{OrdoError, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+
+/// Per-execution resource limits (`None` = unlimited)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceBudget {
+    /// Instruction fuel
+    #[serde(default)]
+    pub fuel: Option<u64>,
+    /// Bytes of values allocated during the execution
+    #[serde(default)]
+    pub max_memory_bytes: Option<u64>,
+    /// Longest array an operation may produce
+    #[serde(default)]
+    pub max_array_len: Option<usize>,
+    /// Longest string (in bytes) an operation may produce
+    #[serde(default)]
+    pub max_string_len: Option<usize>,
+    /// Largest compiled regex program, in bytes
+    #[serde(default)]
+    pub max_regex_size: Option<usize>,
+    /// Largest lazy DFA cache of a regex, in bytes
+    #[serde(default)]
+    pub max_regex_dfa_size: Option<usize>,
+}
+
+impl ResourceBudget {
+    /// A budget without limits
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Whether no limit is set
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Set the instruction fuel
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Set the memory limit in bytes
+    pub fn with_max_memory_bytes(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Set the maximum array length
+    pub fn with_max_array_len(mut self, len: usize) -> Self {
+        self.max_array_len = Some(len);
+        self
+    }
+
+    /// Set the maximum string length in bytes
+    pub fn with_max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = Some(len);
+        self
+    }
+
+    /// Set the maximum compiled regex size in bytes
+    pub fn with_max_regex_size(mut self, bytes: usize) -> Self {
+        self.max_regex_size = Some(bytes);
+        self
+    }
+
+    /// Set the maximum regex DFA cache size in bytes
+    pub fn with_max_regex_dfa_size(mut self, bytes: usize) -> Self {
+        self.max_regex_dfa_size = Some(bytes);
+        self
+    }
+}
+
+/// Resources consumed by the active budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BudgetUsage {
+    /// Fuel consumed
+    pub fuel: u64,
+    /// Bytes of values allocated
+    pub memory_bytes: u64,
+}
+
+/// Active budget of the current thread
+struct Meter {
+    budget: ResourceBudget,
+    usage: BudgetUsage,
+}
+
+thread_local! {
+    static METER: RefCell<Option<Meter>> = const { RefCell::new(None) };
+    /// Whether `METER` holds a budget, checked before borrowing it
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Activates a budget on the current thread until dropped
+///
+/// Scopes do not nest: while a budget is active (e.g. a sub-ruleset called
+/// from a rule), entering another scope is a no-op and the outer budget keeps
+/// being charged.
+#[must_use = "the budget is only active while the scope is alive"]
+pub struct BudgetScope {
+    owner: bool,
+}
+
+impl BudgetScope {
+    /// Activate `budget` on the current thread
+    pub fn enter(budget: &ResourceBudget) -> Self {
+        let owner = METER.with(|meter| {
+            let mut meter = meter.borrow_mut();
+            if meter.is_some() {
+                return false;
+            }
+            *meter = Some(Meter {
+                budget: budget.clone(),
+                usage: BudgetUsage::default(),
+            });
+            true
+        });
+        if owner {
+            ACTIVE.with(|active| active.set(true));
+        }
+        Self { owner }
+    }
+
+    /// Resources consumed so far
+    pub fn usage(&self) -> BudgetUsage {
+        usage().unwrap_or_default()
+    }
+}
+
+impl Drop for BudgetScope {
+    fn drop(&mut self) {
+        if self.owner {
+            ACTIVE.with(|active| active.set(false));
+            METER.with(|meter| meter.borrow_mut().take());
+        }
+    }
+}
+
+/// Resources consumed by the budget active on this thread, if any
+pub fn usage() -> Option<BudgetUsage> {
+    METER.with(|meter| meter.borrow().as_ref().map(|meter| meter.usage))
+}
+
+#[inline]
+fn active() -> bool {
+    ACTIVE.with(Cell::get)
+}
+
+#[inline]
+fn with_meter(f: impl FnOnce(&mut Meter) -> Result<()>) -> Result<()> {
+    if !active() {
+        return Ok(());
+    }
+    METER.with(|meter| match &mut *meter.borrow_mut() {
+        Some(meter) => f(meter),
+        None => Ok(()),
+    })
+}
+
+/// Consume instruction fuel
+#[inline]
+pub(crate) fn charge_fuel(units: u64) -> Result<()> {
+    with_meter(|meter| meter.consume_fuel(units))
+}
+
+/// Consume fuel for the work of processing a function's arguments
+#[inline]
+pub(crate) fn charge_args(args: &[Value]) -> Result<()> {
+    with_meter(|meter| {
+        let work: u64 = args
+            .iter()
+            .map(|arg| match arg {
+                Value::String(s) => s.len() as u64 / 64,
+                Value::Array(items) => items.len() as u64,
+                Value::Object(map) => map.len() as u64,
+                _ => 0,
+            })
+            .sum();
+        meter.consume_fuel(1 + work)
+    })
+}
+
+/// Check that a string of `len` bytes may be produced (nothing is charged)
+#[inline]
+pub(crate) fn check_string_len(len: usize) -> Result<()> {
+    with_meter(|meter| {
+        if let Some(limit) = meter.budget.max_string_len {
+            if len > limit {
+                return Err(OrdoError::budget_exceeded("string_length", limit as u64));
+            }
+        }
+        meter.check_memory(len as u64)
+    })
+}
+
+/// Check that an array of `len` elements may be produced (nothing is charged)
+#[inline]
+pub(crate) fn check_array_len(len: usize) -> Result<()> {
+    with_meter(|meter| {
+        if let Some(limit) = meter.budget.max_array_len {
+            if len > limit {
+                return Err(OrdoError::budget_exceeded("array_length", limit as u64));
+            }
+        }
+        meter.check_memory((len as u64).saturating_mul(std::mem::size_of::<Value>() as u64))
+    })
+}
+
+/// Charge a newly produced value against the memory and size limits
+pub(crate) fn charge_value(value: &Value) -> Result<()> {
+    with_meter(|meter| {
+        let bytes = meter.measure(value)?;
+        meter.check_memory(bytes)?;
+        meter.usage.memory_bytes += bytes;
+        Ok(())
+    })
+}
+
+/// Compiled size and DFA cache limits for regexes set by the active budget
+#[inline]
+pub(crate) fn regex_limits() -> (Option<usize>, Option<usize>) {
+    if !active() {
+        return (None, None);
+    }
+    METER.with(|meter| match &*meter.borrow() {
+        Some(meter) => (meter.budget.max_regex_size, meter.budget.max_regex_dfa_size),
+        None => (None, None),
+    })
+}
+
+impl Meter {
+    fn consume_fuel(&mut self, units: u64) -> Result<()> {
+        self.usage.fuel = self.usage.fuel.saturating_add(units);
+        match self.budget.fuel {
+            Some(limit) if self.usage.fuel > limit => {
+                Err(OrdoError::budget_exceeded("fuel", limit))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_memory(&self, bytes: u64) -> Result<()> {
+        match self.budget.max_memory_bytes {
+            Some(limit) if self.usage.memory_bytes.saturating_add(bytes) > limit => {
+                Err(OrdoError::budget_exceeded("memory", limit))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Approximate heap footprint of a value, checking its lengths on the way
+    fn measure(&self, value: &Value) -> Result<u64> {
+        let own = std::mem::size_of::<Value>() as u64;
+        match value {
+            Value::String(s) => {
+                if let Some(limit) = self.budget.max_string_len {
+                    if s.len() > limit {
+                        return Err(OrdoError::budget_exceeded("string_length", limit as u64));
+                    }
+                }
+                Ok(own + s.len() as u64)
+            }
+            Value::Array(items) => {
+                if let Some(limit) = self.budget.max_array_len {
+                    if items.len() > limit {
+                        return Err(OrdoError::budget_exceeded("array_length", limit as u64));
+                    }
+                }
+                items.iter().try_fold(own, |total, item| {
+                    Ok(total.saturating_add(self.measure(item)?))
+                })
+            }
+            Value::Object(map) => map.iter().try_fold(own, |total, (key, item)| {
+                Ok(total
+                    .saturating_add(key.len() as u64)
+                    .saturating_add(self.measure(item)?))
+            }),
+            _ => Ok(own),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_budget_is_free() {
+        assert!(charge_fuel(u64::MAX).is_ok());
+        assert!(check_array_len(usize::MAX).is_ok());
+        assert!(usage().is_none());
+    }
+
+    #[test]
+    fn test_fuel_and_memory_limits() {
+        let budget = ResourceBudget::unlimited()
+            .with_fuel(10)
+            .with_max_memory_bytes(1024);
+        let scope = BudgetScope::enter(&budget);
+
+        assert!(charge_fuel(10).is_ok());
+        assert!(matches!(
+            charge_fuel(1),
+            Err(OrdoError::BudgetExceeded { ref resource, limit: 10 }) if resource == "fuel"
+        ));
+
+        assert!(charge_value(&Value::string("x".repeat(512))).is_ok());
+        assert!(charge_value(&Value::string("x".repeat(512))).is_err());
+        assert_eq!(scope.usage().fuel, 11);
+
+        // Nested scopes keep charging the outer budget
+        {
+            let _inner = BudgetScope::enter(&ResourceBudget::unlimited());
+            assert!(charge_fuel(1).is_err());
+        }
+        drop(scope);
+        assert!(usage().is_none());
+        assert!(!active());
+    }
+
+    #[test]
+    fn test_interpreter_and_vm_charge_the_same_fuel() {
+        use crate::context::Context;
+        use crate::expr::{BytecodeVM, Evaluator, ExprCompiler, ExprParser};
+
+        let expr = ExprParser::parse("len(items) + abs(x) > 3 && x < 10").unwrap();
+        let ctx = Context::new(serde_json::from_str(r#"{"items": [1, 2, 3], "x": -2}"#).unwrap());
+        let budget = ResourceBudget::unlimited();
+
+        let interpreted = {
+            let scope = BudgetScope::enter(&budget);
+            Evaluator::new().eval(&expr, &ctx).unwrap();
+            scope.usage().fuel
+        };
+        let compiled = ExprCompiler::new().compile(&expr);
+        let vm = {
+            let scope = BudgetScope::enter(&budget);
+            BytecodeVM::new().execute(&compiled, &ctx).unwrap();
+            scope.usage().fuel
+        };
+        // One for the expression, 1 + 3 elements for `len`, 1 for `abs`
+        assert_eq!(interpreted, 6);
+        assert_eq!(vm, interpreted);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let budget = ResourceBudget::unlimited()
+            .with_max_array_len(3)
+            .with_max_string_len(4);
+        let _scope = BudgetScope::enter(&budget);
+
+        assert!(check_array_len(3).is_ok());
+        assert!(check_array_len(4).is_err());
+        assert!(check_string_len(5).is_err());
+        let nested = Value::array(vec![Value::array(vec![Value::int(1); 4])]);
+        assert!(charge_value(&nested).is_err());
+    }
+}
@@ -0,0 +1,658 @@
+//! JSON Schema to MessageSchema conversion
+//!
+//! Derives [`MessageSchema`]s from a JSON Schema document describing a rule
+//! input, so the editor, the server and Schema-Aware JIT work from the same
+//! input shape.
+//!
+//! Supported keywords:
+//! - `type` (including `["T", "null"]`), with `format` selecting integer and
+//!   float widths (`int32`, `uint32`, `uint64`, `float`)
+//! - `properties` and `required`: each object becomes a message; properties
+//!   that are not required, or are nullable, become `optional` fields
+//! - `items`: arrays become repeated fields
+//! - `enum` / `const`: the allowed values are kept per field; the field type is
+//!   inferred from the values when `type` is absent
+//! - `nullable: true`, and `anyOf` / `oneOf` of one schema and `{"type": "null"}`
+//! - local `$ref`s (`#/$defs/Name`, `#/definitions/Name`, any `#/...` pointer);
+//!   objects behind a `$ref` become a shared message named after the last
+//!   pointer segment
+//!
+//! Properties without a single determinable type (no `type`, mixed `anyOf`
+//! variants) and object properties that would contain themselves are left out
+//! of the message.
+
+use super::schema::{FieldSchema, FieldType, MessageSchema, SchemaRegistry};
+use super::Value;
+use crate::error::{OrdoError, Result};
+use hashbrown::{HashMap, HashSet};
+use serde::Serialize;
+use serde_json::{Map, Value as Json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Maximum nesting depth of schemas and `$ref` chains
+const MAX_DEPTH: usize = 64;
+
+/// Messages derived from a JSON Schema document
+///
+/// The root message takes the name given at conversion; nested objects are
+/// named `Parent.property` and referenced definitions by their key.
+#[derive(Debug, Serialize)]
+pub struct JsonSchemaSet {
+    /// Name of the root message
+    root: String,
+    /// All messages, serialized sorted by name
+    #[serde(serialize_with = "serialize_messages")]
+    messages: HashMap<String, Arc<MessageSchema>>,
+    /// Allowed values per message and field, from `enum` / `const`
+    enums: BTreeMap<String, BTreeMap<String, Vec<Value>>>,
+    #[serde(skip)]
+    document: Json,
+}
+
+impl JsonSchemaSet {
+    /// Convert a JSON Schema document whose root describes an object
+    pub fn from_document(name: &str, document: Json) -> Result<Self> {
+        let mut converter = Converter {
+            document: &document,
+            root_name: name,
+            messages: HashMap::new(),
+            by_pointer: HashMap::new(),
+            visiting: HashSet::new(),
+            enums: BTreeMap::new(),
+        };
+        let root = converter
+            .message_at("", name, 0)?
+            .ok_or_else(|| OrdoError::parse_error("JSON Schema root must not reference itself"))?;
+        let (messages, enums) = (converter.messages, converter.enums);
+        Ok(Self {
+            root: root.name.clone(),
+            messages,
+            enums,
+            document,
+        })
+    }
+
+    /// Parse and convert a JSON Schema document
+    pub fn from_json(name: &str, json: &str) -> Result<Self> {
+        let document = serde_json::from_str(json)
+            .map_err(|e| OrdoError::parse_error(format!("invalid JSON Schema: {}", e)))?;
+        Self::from_document(name, document)
+    }
+
+    /// The source document
+    pub fn document(&self) -> &Json {
+        &self.document
+    }
+
+    /// Schema of the root message
+    pub fn root(&self) -> Arc<MessageSchema> {
+        self.messages[&self.root].clone()
+    }
+
+    /// Schema of a message by name
+    pub fn schema(&self, name: &str) -> Option<Arc<MessageSchema>> {
+        self.messages.get(name).cloned()
+    }
+
+    /// Names of all messages, sorted
+    pub fn message_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.messages.keys().map(|s| s.as_str()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Number of messages
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether the set has no messages (never true for a converted document)
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Allowed values of the field at a dotted path from the root message
+    pub fn enum_values(&self, path: &str) -> Option<&[Value]> {
+        let (parent, field) = match path.rsplit_once('.') {
+            Some((parent, field)) => (self.root().resolve_field_path(parent)?.field_type, field),
+            None => (FieldType::Message(self.root()), path),
+        };
+        let FieldType::Message(message) = parent else {
+            return None;
+        };
+        self.enums
+            .get(&message.name)?
+            .get(field)
+            .map(|values| values.as_slice())
+    }
+
+    /// Register all messages
+    pub fn register_all(&self, registry: &mut SchemaRegistry) {
+        for schema in self.messages.values() {
+            registry.register(schema.as_ref().clone());
+        }
+    }
+}
+
+fn serialize_messages<S: serde::Serializer>(
+    messages: &HashMap<String, Arc<MessageSchema>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let mut sorted: Vec<&MessageSchema> = messages.values().map(|m| m.as_ref()).collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    serializer.collect_seq(sorted)
+}
+
+/// A property type resolved from a schema node
+struct Resolved {
+    field_type: FieldType,
+    nullable: bool,
+    enum_values: Option<Vec<Value>>,
+}
+
+impl Resolved {
+    fn new(field_type: FieldType) -> Self {
+        Self {
+            field_type,
+            nullable: false,
+            enum_values: None,
+        }
+    }
+}
+
+struct Converter<'a> {
+    document: &'a Json,
+    root_name: &'a str,
+    /// Messages by name
+    messages: HashMap<String, Arc<MessageSchema>>,
+    /// Messages by the JSON pointer of their object schema
+    by_pointer: HashMap<String, Arc<MessageSchema>>,
+    /// Pointers of messages being built (for recursion detection)
+    visiting: HashSet<String>,
+    enums: BTreeMap<String, BTreeMap<String, Vec<Value>>>,
+}
+
+impl<'a> Converter<'a> {
+    fn node(&self, pointer: &str) -> Result<&'a Json> {
+        self.document.pointer(pointer).ok_or_else(|| {
+            OrdoError::parse_error(format!("JSON Schema reference '#{}' not found", pointer))
+        })
+    }
+
+    /// Build the message for the object schema at `pointer`
+    ///
+    /// Returns `None` when the message is already being built (recursion).
+    fn message_at(
+        &mut self,
+        pointer: &str,
+        name: &str,
+        depth: usize,
+    ) -> Result<Option<Arc<MessageSchema>>> {
+        if depth >= MAX_DEPTH {
+            return Err(OrdoError::parse_error("JSON Schema nested too deeply"));
+        }
+        if let Some(schema) = self.by_pointer.get(pointer) {
+            return Ok(Some(schema.clone()));
+        }
+        if !self.visiting.insert(pointer.to_string()) {
+            return Ok(None);
+        }
+
+        let node = self.node(pointer)?;
+        // The root may itself be a reference
+        if let Some(target) = reference(node)? {
+            let schema = self.message_at(target, name, depth + 1)?;
+            self.visiting.remove(pointer);
+            return Ok(schema);
+        }
+        if !is_object_schema(node) {
+            return Err(OrdoError::parse_error(format!(
+                "JSON Schema at '#{}' does not describe an object",
+                pointer
+            )));
+        }
+
+        let properties = node.get("properties").and_then(Json::as_object);
+        let required: HashSet<&str> = node
+            .get("required")
+            .and_then(Json::as_array)
+            .map(|names| names.iter().filter_map(Json::as_str).collect())
+            .unwrap_or_default();
+
+        let mut fields = Vec::new();
+        let mut offset = 0usize;
+        for (property, _) in properties.into_iter().flatten() {
+            let property_pointer = format!("{}/properties/{}", pointer, escape(property));
+            let nested_name = format!("{}.{}", name, property);
+            let Some(resolved) = self.resolve(&property_pointer, &nested_name, depth + 1)? else {
+                continue;
+            };
+
+            let is_required = required.contains(property.as_str());
+            let field_type = match resolved.field_type {
+                scalar if scalar.is_primitive() || matches!(scalar, FieldType::String) => {
+                    if resolved.nullable || !is_required {
+                        FieldType::Optional(Box::new(scalar))
+                    } else {
+                        scalar
+                    }
+                }
+                other => other,
+            };
+            if let Some(values) = resolved.enum_values {
+                self.enums
+                    .entry(name.to_string())
+                    .or_default()
+                    .insert(property.clone(), values);
+            }
+
+            let (size, align) = field_type.slot_layout();
+            offset = offset.next_multiple_of(align);
+            let mut field = FieldSchema::new(property.as_str(), field_type, offset).with_size(size);
+            if is_required {
+                field = field.required();
+            }
+            fields.push(field);
+            offset += size;
+        }
+
+        self.visiting.remove(pointer);
+        let schema = Arc::new(MessageSchema::new(name, fields));
+        if self.messages.contains_key(name) {
+            return Err(OrdoError::parse_error(format!(
+                "JSON Schema defines message '{}' more than once",
+                name
+            )));
+        }
+        self.messages.insert(name.to_string(), schema.clone());
+        self.by_pointer.insert(pointer.to_string(), schema.clone());
+        Ok(Some(schema))
+    }
+
+    /// Resolve the type of the schema at `pointer`
+    ///
+    /// `name` is used if the schema is an inline object.
+    fn resolve(&mut self, pointer: &str, name: &str, depth: usize) -> Result<Option<Resolved>> {
+        if depth >= MAX_DEPTH {
+            return Err(OrdoError::parse_error("JSON Schema nested too deeply"));
+        }
+        let node = self.node(pointer)?;
+        let Some(object) = node.as_object() else {
+            // `true` / `false` schemas carry no type
+            return Ok(None);
+        };
+        let nullable = object.get("nullable").and_then(Json::as_bool) == Some(true);
+
+        let mut resolved = if let Some(target) = reference(node)? {
+            let target_node = self.node(target)?;
+            if is_object_schema(target_node) && reference(target_node)?.is_none() {
+                let message_name = if target.is_empty() {
+                    self.root_name.to_string()
+                } else {
+                    unescape(target.rsplit('/').next().unwrap_or(target))
+                };
+                self.message_at(target, &message_name, depth + 1)?
+                    .map(|schema| Resolved::new(FieldType::Message(schema)))
+            } else {
+                self.resolve(target, name, depth + 1)?
+            }
+        } else if let Some(variants) = object
+            .get("anyOf")
+            .or_else(|| object.get("oneOf"))
+            .and_then(Json::as_array)
+        {
+            self.resolve_variants(pointer, variants, name, depth)?
+        } else if object
+            .get("allOf")
+            .and_then(Json::as_array)
+            .is_some_and(|members| members.len() == 1)
+        {
+            self.resolve(&format!("{}/allOf/0", pointer), name, depth + 1)?
+        } else {
+            self.resolve_typed(pointer, object, name, depth)?
+        };
+
+        if let Some(resolved) = resolved.as_mut() {
+            resolved.nullable |= nullable;
+        }
+        Ok(resolved)
+    }
+
+    /// `anyOf` / `oneOf`: all non-null variants must resolve to the same type
+    fn resolve_variants(
+        &mut self,
+        pointer: &str,
+        variants: &[Json],
+        name: &str,
+        depth: usize,
+    ) -> Result<Option<Resolved>> {
+        let keyword = if self.node(pointer)?.get("anyOf").is_some() {
+            "anyOf"
+        } else {
+            "oneOf"
+        };
+        let mut nullable = false;
+        let mut result: Option<Resolved> = None;
+        for (i, variant) in variants.iter().enumerate() {
+            if variant.get("type").and_then(Json::as_str) == Some("null") {
+                nullable = true;
+                continue;
+            }
+            let variant_pointer = format!("{}/{}/{}", pointer, keyword, i);
+            let Some(resolved) = self.resolve(&variant_pointer, name, depth + 1)? else {
+                return Ok(None);
+            };
+            nullable |= resolved.nullable;
+            match &result {
+                None => result = Some(resolved),
+                Some(existing)
+                    if existing.field_type.to_string() == resolved.field_type.to_string() => {}
+                Some(_) => return Ok(None),
+            }
+        }
+        Ok(result.map(|mut resolved| {
+            resolved.nullable |= nullable;
+            resolved
+        }))
+    }
+
+    /// Schemas with `type`, `enum` / `const`, `properties` or `items`
+    fn resolve_typed(
+        &mut self,
+        pointer: &str,
+        object: &Map<String, Json>,
+        name: &str,
+        depth: usize,
+    ) -> Result<Option<Resolved>> {
+        let enum_values: Option<Vec<&Json>> = match (object.get("enum"), object.get("const")) {
+            (Some(Json::Array(values)), _) => Some(values.iter().collect()),
+            (_, Some(value)) => Some(vec![value]),
+            _ => None,
+        };
+
+        let (type_name, mut nullable) = match object.get("type") {
+            Some(Json::String(ty)) => (Some(ty.as_str()), false),
+            Some(Json::Array(types)) => {
+                let non_null: Vec<&str> = types
+                    .iter()
+                    .filter_map(Json::as_str)
+                    .filter(|ty| *ty != "null")
+                    .collect();
+                let nullable = non_null.len() < types.len();
+                match non_null.as_slice() {
+                    [ty] => (Some(*ty), nullable),
+                    _ => return Ok(None),
+                }
+            }
+            Some(other) => {
+                return Err(OrdoError::parse_error(format!(
+                    "invalid JSON Schema type at '#{}': {}",
+                    pointer, other
+                )))
+            }
+            None if object.contains_key("properties") => (Some("object"), false),
+            None if object.contains_key("items") => (Some("array"), false),
+            None => match enum_values.as_deref().map(infer_enum_type) {
+                Some(Some((ty, has_null))) => (Some(ty), has_null),
+                _ => (None, false),
+            },
+        };
+        let Some(type_name) = type_name else {
+            return Ok(None);
+        };
+        let format = object.get("format").and_then(Json::as_str);
+
+        let field_type = match type_name {
+            "string" => FieldType::String,
+            "boolean" => FieldType::Bool,
+            "integer" => match format {
+                Some("int32") => FieldType::Int32,
+                Some("uint32") => FieldType::UInt32,
+                Some("uint64") => FieldType::UInt64,
+                _ => FieldType::Int64,
+            },
+            "number" => match format {
+                Some("float") => FieldType::Float32,
+                _ => FieldType::Float64,
+            },
+            "object" => match self.message_at(pointer, name, depth + 1)? {
+                Some(schema) => FieldType::Message(schema),
+                None => return Ok(None),
+            },
+            "array" => {
+                if !object.contains_key("items") {
+                    return Ok(None);
+                }
+                let items_pointer = format!("{}/items", pointer);
+                match self.resolve(&items_pointer, name, depth + 1)? {
+                    Some(items) => {
+                        let mut resolved =
+                            Resolved::new(FieldType::Repeated(Box::new(items.field_type)));
+                        resolved.enum_values = items.enum_values;
+                        return Ok(Some(resolved));
+                    }
+                    None => return Ok(None),
+                }
+            }
+            "null" => return Ok(None),
+            other => {
+                return Err(OrdoError::parse_error(format!(
+                    "unknown JSON Schema type '{}' at '#{}'",
+                    other, pointer
+                )))
+            }
+        };
+
+        let enum_values = match enum_values {
+            Some(values) => {
+                nullable |= values.iter().any(|v| v.is_null());
+                Some(
+                    values
+                        .into_iter()
+                        .filter(|v| !v.is_null())
+                        .map(|v| serde_json::from_value(v.clone()))
+                        .collect::<std::result::Result<Vec<Value>, _>>()
+                        .map_err(|e| OrdoError::parse_error(e.to_string()))?,
+                )
+            }
+            None => None,
+        };
+        Ok(Some(Resolved {
+            field_type,
+            nullable,
+            enum_values,
+        }))
+    }
+}
+
+/// Target pointer of a `$ref` (without the leading `#`)
+fn reference(node: &Json) -> Result<Option<&str>> {
+    match node.get("$ref") {
+        None => Ok(None),
+        Some(Json::String(target)) => target.strip_prefix('#').map(Some).ok_or_else(|| {
+            OrdoError::parse_error(format!(
+                "only local JSON Schema references are supported, got '{}'",
+                target
+            ))
+        }),
+        Some(other) => Err(OrdoError::parse_error(format!(
+            "invalid JSON Schema reference: {}",
+            other
+        ))),
+    }
+}
+
+fn is_object_schema(node: &Json) -> bool {
+    match node.get("type") {
+        Some(Json::String(ty)) => ty == "object",
+        Some(Json::Array(types)) => types.iter().any(|t| t == "object"),
+        _ => node.get("properties").is_some(),
+    }
+}
+
+/// Type shared by all `enum` values, and whether `null` is among them
+fn infer_enum_type(values: &[&Json]) -> Option<(&'static str, bool)> {
+    let has_null = values.iter().any(|v| v.is_null());
+    let mut types = values.iter().filter(|v| !v.is_null()).map(|v| match v {
+        Json::String(_) => Some("string"),
+        Json::Bool(_) => Some("boolean"),
+        Json::Number(n) if n.is_i64() || n.is_u64() => Some("integer"),
+        Json::Number(_) => Some("number"),
+        _ => None,
+    });
+    let first = types.next()??;
+    let mut ty = first;
+    for next in types {
+        match (ty, next?) {
+            (a, b) if a == b => {}
+            ("integer", "number") | ("number", "integer") => ty = "number",
+            _ => return None,
+        }
+    }
+    Some((ty, has_null))
+}
+
+/// Escape a property name for use in a JSON pointer
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn loan_schema() -> Json {
+        json!({
+            "type": "object",
+            "required": ["amount", "applicant"],
+            "properties": {
+                "amount": { "type": "number" },
+                "term": { "type": "integer", "format": "int32" },
+                "status": { "enum": ["new", "review", null] },
+                "applicant": { "$ref": "#/$defs/Person" },
+                "cosigner": { "anyOf": [{ "$ref": "#/$defs/Person" }, { "type": "null" }] },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "meta": {
+                    "type": "object",
+                    "properties": { "source": { "type": ["string", "null"] } }
+                },
+                "anything": {}
+            },
+            "$defs": {
+                "Person": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "age": { "type": "integer" },
+                        "referrer": { "$ref": "#/$defs/Person" }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_json_schema_conversion() {
+        let set = JsonSchemaSet::from_document("Loan", loan_schema()).unwrap();
+        assert_eq!(set.message_names(), vec!["Loan", "Loan.meta", "Person"]);
+
+        let root = set.root();
+        let amount = root.get_field("amount").unwrap();
+        assert!(amount.required);
+        assert!(matches!(amount.field_type, FieldType::Float64));
+        assert_eq!(
+            root.get_field("term").unwrap().field_type.to_string(),
+            "optional int32"
+        );
+        assert_eq!(
+            root.get_field("status").unwrap().field_type.to_string(),
+            "optional string"
+        );
+        assert_eq!(
+            root.get_field("tags").unwrap().field_type.to_string(),
+            "repeated string"
+        );
+        assert!(root.get_field("anything").is_none());
+
+        // `$ref`s to the same definition share one message
+        let applicant = root.resolve_field_path("applicant.name").unwrap();
+        assert!(matches!(applicant.field_type, FieldType::String));
+        let cosigner = root.get_field("cosigner").unwrap();
+        match (
+            &root.get_field("applicant").unwrap().field_type,
+            &cosigner.field_type,
+        ) {
+            (FieldType::Message(a), FieldType::Message(b)) => assert!(Arc::ptr_eq(a, b)),
+            other => panic!("expected messages, got {:?}", other),
+        }
+        // Recursive property is left out
+        assert!(set
+            .schema("Person")
+            .unwrap()
+            .get_field("referrer")
+            .is_none());
+        assert_eq!(
+            root.resolve_field_path("meta.source")
+                .unwrap()
+                .field_type
+                .to_string(),
+            "optional string"
+        );
+
+        assert_eq!(
+            set.enum_values("status").unwrap(),
+            &[Value::string("new"), Value::string("review")]
+        );
+        assert!(set.enum_values("applicant.name").is_none());
+
+        let mut registry = SchemaRegistry::new();
+        set.register_all(&mut registry);
+        assert!(registry.contains("Person"));
+    }
+
+    #[test]
+    fn test_json_schema_layout() {
+        let set = JsonSchemaSet::from_document("Loan", loan_schema()).unwrap();
+        let root = set.root();
+        for field in &root.fields {
+            let (size, align) = field.field_type.slot_layout();
+            assert_eq!(field.size, size);
+            assert_eq!(field.offset % align, 0);
+        }
+        let serialized = serde_json::to_value(&set).unwrap();
+        assert_eq!(serialized["root"], "Loan");
+        assert_eq!(serialized["messages"][0]["name"], "Loan");
+        assert_eq!(
+            serialized["enums"]["Loan"]["status"],
+            json!(["new", "review"])
+        );
+    }
+
+    #[test]
+    fn test_json_schema_errors() {
+        assert!(JsonSchemaSet::from_json("X", "{").is_err());
+        assert!(JsonSchemaSet::from_document("X", json!({ "type": "string" })).is_err());
+        assert!(JsonSchemaSet::from_document(
+            "X",
+            json!({ "properties": { "a": { "$ref": "other.json#/A" } } })
+        )
+        .is_err());
+        assert!(JsonSchemaSet::from_document(
+            "X",
+            json!({ "properties": { "a": { "$ref": "#/$defs/Missing" } } })
+        )
+        .is_err());
+        assert!(JsonSchemaSet::from_document(
+            "X",
+            json!({ "properties": { "a": { "type": "decimal" } } })
+        )
+        .is_err());
+    }
+}
@@ -4,11 +4,22 @@
 //! - Value type system (Value)
 //! - Context storage (Context)
 //! - Schema system for typed contexts (Schema)
+//! - Per-thread context pooling and key interning
+//! - JSON Schema conversion to message schemas
+//! - Protobuf decoding from descriptor sets (`protobuf` feature)
 
+mod json_schema;
+mod pool;
+#[cfg(feature = "protobuf")]
+mod proto;
 mod schema;
 mod store;
 mod value;
 
+pub use json_schema::JsonSchemaSet;
+pub use pool::{intern, pooled_context_count, PooledContext};
+#[cfg(feature = "protobuf")]
+pub use proto::{ProtoSchemaSet, TypedBuffer};
 pub use schema::{FieldSchema, FieldType, MessageSchema, ResolvedField, SchemaRegistry};
-pub use store::Context;
+pub use store::{Context, VariableLayout};
 pub use value::{IString, SmallArray, Value};
@@ -0,0 +1,152 @@
+//! Per-thread reuse of execution state
+//!
+//! Every rule execution needs a [`Context`] and builds an output object. To
+//! keep allocations off the hot path:
+//! - [`PooledContext`] takes a context from a thread-local pool and returns it
+//!   on drop, so its slot and variable storage keep their capacity across
+//!   executions.
+//! - [`intern`] maps object keys to shared [`IString`]s, so output keys are
+//!   reference-counted clones instead of fresh allocations.
+
+use super::store::VariableLayout;
+use super::{Context, IString, Value};
+use hashbrown::HashSet;
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Maximum number of idle contexts kept per thread
+///
+/// Nested `CallRuleSet` executions hold one context per level.
+const MAX_POOLED_CONTEXTS: usize = 16;
+
+/// Maximum number of interned strings per thread before the table is reset
+const MAX_INTERNED: usize = 4096;
+
+/// Longest string worth interning, in bytes
+const MAX_INTERNED_LEN: usize = 64;
+
+thread_local! {
+    static CONTEXTS: RefCell<Vec<Context>> = const { RefCell::new(Vec::new()) };
+    static INTERNED: RefCell<HashSet<IString>> = RefCell::new(HashSet::new());
+}
+
+/// A [`Context`] borrowed from the thread-local pool
+///
+/// Dereferences to the context; dropping it resets the context and returns
+/// it to the pool.
+#[derive(Debug)]
+pub struct PooledContext {
+    ctx: Option<Context>,
+}
+
+impl PooledContext {
+    /// Take a context from the pool for `data` (a new one if the pool is empty)
+    pub fn acquire(data: Value, layout: Option<Arc<VariableLayout>>) -> Self {
+        let mut ctx = CONTEXTS
+            .try_with(|pool| pool.borrow_mut().pop())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        ctx.reset(data);
+        ctx.set_layout(layout);
+        Self { ctx: Some(ctx) }
+    }
+
+    /// Detach the context from the pool
+    pub fn into_inner(mut self) -> Context {
+        self.ctx.take().unwrap_or_default()
+    }
+}
+
+impl Deref for PooledContext {
+    type Target = Context;
+
+    #[inline]
+    fn deref(&self) -> &Context {
+        self.ctx.as_ref().expect("pooled context taken")
+    }
+}
+
+impl DerefMut for PooledContext {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Context {
+        self.ctx.as_mut().expect("pooled context taken")
+    }
+}
+
+impl Drop for PooledContext {
+    fn drop(&mut self) {
+        if let Some(mut ctx) = self.ctx.take() {
+            ctx.reset(Value::Null);
+            // The pool may already be gone during thread teardown
+            let _ = CONTEXTS.try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                if pool.len() < MAX_POOLED_CONTEXTS {
+                    pool.push(ctx);
+                }
+            });
+        }
+    }
+}
+
+/// Number of idle contexts in this thread's pool
+pub fn pooled_context_count() -> usize {
+    CONTEXTS.with(|pool| pool.borrow().len())
+}
+
+/// Shared [`IString`] for `s`, allocated once per thread
+///
+/// Long strings are not interned and get a fresh allocation.
+pub fn intern(s: &str) -> IString {
+    if s.len() > MAX_INTERNED_LEN {
+        return Arc::from(s);
+    }
+    INTERNED
+        .try_with(|table| {
+            let mut table = table.borrow_mut();
+            if let Some(interned) = table.get(s) {
+                return interned.clone();
+            }
+            if table.len() >= MAX_INTERNED {
+                table.clear();
+            }
+            let interned: IString = Arc::from(s);
+            table.insert(interned.clone());
+            interned
+        })
+        .unwrap_or_else(|_| Arc::from(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pooled_context_is_reused() {
+        let layout = Arc::new(VariableLayout::new(["score"]));
+        {
+            let mut ctx = PooledContext::acquire(Value::int(1), Some(layout.clone()));
+            ctx.set_variable("score", Value::int(10));
+            ctx.set_variable("extra", Value::int(20));
+        }
+        let idle = pooled_context_count();
+        assert!(idle >= 1);
+
+        let ctx = PooledContext::acquire(Value::int(2), Some(layout));
+        assert_eq!(pooled_context_count(), idle - 1);
+        assert_eq!(ctx.data(), &Value::int(2));
+        assert_eq!(ctx.get("$score"), None);
+        assert_eq!(ctx.get("$extra"), None);
+    }
+
+    #[test]
+    fn test_intern_shares_allocation() {
+        let a = intern("result_key");
+        let b = intern("result_key");
+        assert!(Arc::ptr_eq(&a, &b));
+
+        let long = "k".repeat(MAX_INTERNED_LEN + 1);
+        assert!(!Arc::ptr_eq(&intern(&long), &intern(&long)));
+    }
+}
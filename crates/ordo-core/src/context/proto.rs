@@ -0,0 +1,1039 @@
+//! Protobuf decoding driven by descriptor sets
+//!
+//! Builds [`MessageSchema`]s from a protobuf `FileDescriptorSet` and decodes
+//! wire-format messages of those types without generated code or JSON:
+//! - [`ProtoSchemaSet::decode`] produces a [`Value`] in the shape of the proto3
+//!   JSON mapping (enums as numbers, bytes as base64 strings, maps as objects,
+//!   unset implicit-presence fields filled with their defaults)
+//! - [`ProtoSchemaSet::decode_typed`] fills a [`TypedBuffer`] laid out by the
+//!   message's [`MessageSchema`], for direct field access by Schema-Aware JIT
+//!
+//! Repeated, map and bytes fields have no slot in the typed layout, and a
+//! message field that would contain itself (directly or through other
+//! messages) is left out of it; these fields are only available through
+//! `decode`.
+
+use super::schema::{FieldSchema, FieldType, MessageSchema, SchemaRegistry};
+use super::{IString, Value};
+use crate::error::{OrdoError, Result};
+use base64::Engine;
+use hashbrown::{HashMap, HashSet};
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FileDescriptorSet};
+use std::sync::Arc;
+
+/// Maximum nesting depth of decoded messages
+const MAX_DEPTH: usize = 64;
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+/// Scalar kind of a protobuf field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Double,
+    Float,
+    Int64,
+    UInt64,
+    Int32,
+    UInt32,
+    Fixed64,
+    Fixed32,
+    SFixed32,
+    SFixed64,
+    SInt32,
+    SInt64,
+    Bool,
+    Enum,
+    String,
+    Bytes,
+    Message,
+}
+
+impl Kind {
+    fn from_type(ty: Type) -> Option<Self> {
+        Some(match ty {
+            Type::Double => Kind::Double,
+            Type::Float => Kind::Float,
+            Type::Int64 => Kind::Int64,
+            Type::Uint64 => Kind::UInt64,
+            Type::Int32 => Kind::Int32,
+            Type::Uint32 => Kind::UInt32,
+            Type::Fixed64 => Kind::Fixed64,
+            Type::Fixed32 => Kind::Fixed32,
+            Type::Sfixed32 => Kind::SFixed32,
+            Type::Sfixed64 => Kind::SFixed64,
+            Type::Sint32 => Kind::SInt32,
+            Type::Sint64 => Kind::SInt64,
+            Type::Bool => Kind::Bool,
+            Type::Enum => Kind::Enum,
+            Type::String => Kind::String,
+            Type::Bytes => Kind::Bytes,
+            Type::Message => Kind::Message,
+            Type::Group => return None,
+        })
+    }
+
+    /// Wire type of a single (unpacked) value
+    fn wire_type(self) -> u8 {
+        match self {
+            Kind::Double | Kind::Fixed64 | Kind::SFixed64 => WIRE_FIXED64,
+            Kind::Float | Kind::Fixed32 | Kind::SFixed32 => WIRE_FIXED32,
+            Kind::String | Kind::Bytes | Kind::Message => WIRE_LEN,
+            _ => WIRE_VARINT,
+        }
+    }
+
+    /// Schema type of a singular field of this kind
+    fn field_type(self, type_name: &str) -> FieldType {
+        match self {
+            Kind::Double => FieldType::Float64,
+            Kind::Float => FieldType::Float32,
+            Kind::Int64 | Kind::SFixed64 | Kind::SInt64 => FieldType::Int64,
+            Kind::UInt64 | Kind::Fixed64 => FieldType::UInt64,
+            Kind::Int32 | Kind::SFixed32 | Kind::SInt32 => FieldType::Int32,
+            Kind::UInt32 | Kind::Fixed32 => FieldType::UInt32,
+            Kind::Bool => FieldType::Bool,
+            Kind::Enum => FieldType::Enum(type_name.to_string()),
+            Kind::String => FieldType::String,
+            // Messages are resolved by the layout builder
+            Kind::Bytes | Kind::Message => FieldType::Bytes,
+        }
+    }
+
+    /// Default value of an unset implicit-presence field
+    fn default_value(self) -> Value {
+        match self {
+            Kind::Double | Kind::Float => Value::Float(0.0),
+            Kind::Bool => Value::Bool(false),
+            Kind::String | Kind::Bytes => Value::string(""),
+            Kind::Message => Value::Null,
+            _ => Value::Int(0),
+        }
+    }
+}
+
+/// A decoded scalar
+enum Scalar {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl Scalar {
+    fn into_value(self) -> Value {
+        match self {
+            Scalar::Int(v) => Value::Int(v),
+            Scalar::UInt(v) => Value::from_u64(v),
+            Scalar::Float(v) => Value::Float(v),
+            Scalar::Bool(v) => Value::Bool(v),
+            Scalar::Str(v) => Value::string(v),
+            Scalar::Bytes(v) => Value::string(base64::engine::general_purpose::STANDARD.encode(v)),
+        }
+    }
+
+    fn as_i64(&self) -> i64 {
+        match *self {
+            Scalar::Int(v) => v,
+            Scalar::UInt(v) => v as i64,
+            Scalar::Float(v) => v as i64,
+            Scalar::Bool(v) => v as i64,
+            Scalar::Str(_) | Scalar::Bytes(_) => 0,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match *self {
+            Scalar::Float(v) => v,
+            Scalar::UInt(v) => v as f64,
+            _ => self.as_i64() as f64,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ProtoField {
+    name: IString,
+    tag: u32,
+    kind: Kind,
+    /// Fully-qualified message or enum type (without leading dot)
+    type_name: String,
+    repeated: bool,
+    /// Unset fields stay absent instead of taking the default value
+    has_presence: bool,
+}
+
+#[derive(Debug)]
+struct ProtoMessage {
+    fields: Vec<ProtoField>,
+    by_tag: HashMap<u32, usize>,
+    map_entry: bool,
+}
+
+/// Typed layout of a message
+#[derive(Debug)]
+struct MessageLayout {
+    schema: Arc<MessageSchema>,
+    /// Index into `schema.fields` per message field (`None` = no slot)
+    slots: Vec<Option<usize>>,
+}
+
+/// Message types of a protobuf `FileDescriptorSet`
+///
+/// Messages are addressed by their fully-qualified name (`package.Message`,
+/// `package.Outer.Inner`); a leading dot is accepted.
+#[derive(Debug)]
+pub struct ProtoSchemaSet {
+    messages: HashMap<String, ProtoMessage>,
+    layouts: HashMap<String, MessageLayout>,
+}
+
+impl ProtoSchemaSet {
+    /// Parse a serialized `FileDescriptorSet` (e.g. from `protoc --descriptor_set_out`)
+    pub fn from_descriptor_set(bytes: &[u8]) -> Result<Self> {
+        let set = FileDescriptorSet::decode(bytes)
+            .map_err(|e| OrdoError::parse_error(format!("invalid descriptor set: {}", e)))?;
+
+        let mut messages = HashMap::new();
+        for file in &set.file {
+            let proto3 = file.syntax() == "proto3";
+            let prefix = file.package().to_string();
+            for message in &file.message_type {
+                collect_message(&prefix, message, proto3, &mut messages)?;
+            }
+        }
+
+        // Every referenced message type must be part of the set
+        for message in messages.values() {
+            for field in &message.fields {
+                if field.kind == Kind::Message && !messages.contains_key(&field.type_name) {
+                    return Err(OrdoError::parse_error(format!(
+                        "field '{}' references unknown message type '{}'",
+                        field.name, field.type_name
+                    )));
+                }
+            }
+        }
+
+        let mut set = Self {
+            messages,
+            layouts: HashMap::new(),
+        };
+        let mut names: Vec<String> = set.messages.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            set.build_layout(&name, &mut HashSet::new());
+        }
+        Ok(set)
+    }
+
+    /// Fully-qualified names of all message types, sorted
+    pub fn message_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.messages.keys().map(|s| s.as_str()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Number of message types
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether the set has no message types
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Typed layout schema of a message type
+    pub fn schema(&self, name: &str) -> Option<Arc<MessageSchema>> {
+        self.layouts
+            .get(normalize(name))
+            .map(|layout| layout.schema.clone())
+    }
+
+    /// Register the schemas of all message types
+    pub fn register_all(&self, registry: &mut SchemaRegistry) {
+        for layout in self.layouts.values() {
+            registry.register(layout.schema.as_ref().clone());
+        }
+    }
+
+    /// Decode a wire-format message of type `name` into a `Value`
+    pub fn decode(&self, name: &str, bytes: &[u8]) -> Result<Value> {
+        let message = self.message(name)?;
+        self.decode_message(message, bytes, 0)
+            .map(Value::object_optimized)
+    }
+
+    /// Decode a wire-format message of type `name` into its typed layout
+    pub fn decode_typed(&self, name: &str, bytes: &[u8]) -> Result<TypedBuffer> {
+        let name = normalize(name);
+        self.message(name)?;
+        let mut buffer = TypedBuffer::new(self.layouts[name].schema.clone());
+        self.decode_into(name, bytes, buffer.as_mut_ptr(), 0)?;
+        Ok(buffer)
+    }
+
+    fn message(&self, name: &str) -> Result<&ProtoMessage> {
+        self.messages
+            .get(normalize(name))
+            .ok_or_else(|| OrdoError::eval_error(format!("unknown message type '{}'", name)))
+    }
+
+    /// Build (and cache) the typed layout of `name`
+    ///
+    /// Returns `None` while `name` is being built, i.e. for recursive fields.
+    fn build_layout(
+        &mut self,
+        name: &str,
+        visiting: &mut HashSet<String>,
+    ) -> Option<Arc<MessageSchema>> {
+        if let Some(layout) = self.layouts.get(name) {
+            return Some(layout.schema.clone());
+        }
+        if !visiting.insert(name.to_string()) {
+            return None;
+        }
+
+        let specs: Vec<(IString, u32, Kind, String, bool, bool)> = self.messages[name]
+            .fields
+            .iter()
+            .map(|f| {
+                (
+                    f.name.clone(),
+                    f.tag,
+                    f.kind,
+                    f.type_name.clone(),
+                    f.repeated,
+                    f.has_presence,
+                )
+            })
+            .collect();
+
+        let mut fields = Vec::with_capacity(specs.len());
+        let mut slots = vec![None; specs.len()];
+        let mut offset = 0usize;
+        for (i, (field_name, tag, kind, type_name, repeated, has_presence)) in
+            specs.into_iter().enumerate()
+        {
+            let (field_type, stored) = if repeated {
+                let inner = match kind {
+                    Kind::Message => FieldType::Bytes,
+                    _ => kind.field_type(&type_name),
+                };
+                (FieldType::Repeated(Box::new(inner)), false)
+            } else {
+                match kind {
+                    Kind::Message => match self.build_layout(&type_name, visiting) {
+                        Some(nested) => (FieldType::Message(nested), true),
+                        None => continue,
+                    },
+                    Kind::Bytes => (FieldType::Bytes, false),
+                    _ if has_presence => (
+                        FieldType::Optional(Box::new(kind.field_type(&type_name))),
+                        true,
+                    ),
+                    _ => (kind.field_type(&type_name), true),
+                }
+            };
+
+            let (size, align) = field_type.slot_layout();
+            offset = offset.next_multiple_of(align);
+            if stored {
+                slots[i] = Some(fields.len());
+            }
+            fields.push(
+                FieldSchema::new(field_name.as_ref(), field_type, offset)
+                    .with_proto_tag(tag)
+                    .with_size(size),
+            );
+            offset += size;
+        }
+
+        visiting.remove(name);
+        let schema = Arc::new(MessageSchema::new(name, fields));
+        self.layouts.insert(
+            name.to_string(),
+            MessageLayout {
+                schema: schema.clone(),
+                slots,
+            },
+        );
+        Some(schema)
+    }
+
+    fn decode_message(
+        &self,
+        message: &ProtoMessage,
+        bytes: &[u8],
+        depth: usize,
+    ) -> Result<HashMap<IString, Value>> {
+        if depth >= MAX_DEPTH {
+            return Err(OrdoError::eval_error("protobuf message nested too deeply"));
+        }
+
+        let mut object: HashMap<IString, Value> = HashMap::with_capacity(message.fields.len());
+        let mut reader = Reader::new(bytes);
+        while !reader.is_empty() {
+            let (tag, wire_type) = reader.key()?;
+            let Some(&index) = message.by_tag.get(&tag) else {
+                reader.skip(wire_type)?;
+                continue;
+            };
+            let field = &message.fields[index];
+
+            if field.kind == Kind::Message {
+                let data = reader.len_delimited(wire_type, field)?;
+                let nested = &self.messages[&field.type_name];
+                let mut decoded = self.decode_message(nested, data, depth + 1)?;
+                if nested.map_entry && field.repeated {
+                    let key = match decoded.remove("key") {
+                        Some(Value::String(s)) => s,
+                        Some(key) => IString::from(key.to_string()),
+                        None => IString::from(""),
+                    };
+                    let value = decoded.remove("value").unwrap_or(Value::Null);
+                    if let Value::Object(map) = object
+                        .entry(field.name.clone())
+                        .or_insert_with(|| Value::object_optimized(HashMap::new()))
+                    {
+                        map.insert(key, value);
+                    }
+                } else if field.repeated {
+                    push(&mut object, field, Value::object_optimized(decoded));
+                } else {
+                    // Repeated occurrences of a singular message are merged
+                    match object.get_mut(&field.name) {
+                        Some(Value::Object(existing)) => existing.extend(decoded),
+                        _ => {
+                            object.insert(field.name.clone(), Value::object_optimized(decoded));
+                        }
+                    }
+                }
+            } else if field.repeated && wire_type == WIRE_LEN && field.kind.wire_type() != WIRE_LEN
+            {
+                // Packed scalars
+                let mut packed = Reader::new(reader.len_delimited(wire_type, field)?);
+                while !packed.is_empty() {
+                    let value = packed.scalar(field.kind.wire_type(), field)?;
+                    push(&mut object, field, value.into_value());
+                }
+            } else {
+                let value = reader.scalar(wire_type, field)?.into_value();
+                if field.repeated {
+                    push(&mut object, field, value);
+                } else {
+                    object.insert(field.name.clone(), value);
+                }
+            }
+        }
+
+        for field in &message.fields {
+            if object.contains_key(&field.name) {
+                continue;
+            }
+            let default = if field.repeated {
+                if self
+                    .messages
+                    .get(&field.type_name)
+                    .is_some_and(|m| m.map_entry)
+                {
+                    Value::object_optimized(HashMap::new())
+                } else {
+                    Value::array(Vec::new())
+                }
+            } else if field.has_presence || field.kind == Kind::Message {
+                continue;
+            } else {
+                field.kind.default_value()
+            };
+            object.insert(field.name.clone(), default);
+        }
+
+        Ok(object)
+    }
+
+    fn decode_into(&self, name: &str, bytes: &[u8], base: *mut u8, depth: usize) -> Result<()> {
+        if depth >= MAX_DEPTH {
+            return Err(OrdoError::eval_error("protobuf message nested too deeply"));
+        }
+
+        let message = &self.messages[name];
+        let layout = &self.layouts[name];
+        let mut reader = Reader::new(bytes);
+        while !reader.is_empty() {
+            let (tag, wire_type) = reader.key()?;
+            let Some(&index) = message.by_tag.get(&tag) else {
+                reader.skip(wire_type)?;
+                continue;
+            };
+            let Some(slot) = layout.slots[index] else {
+                reader.skip(wire_type)?;
+                continue;
+            };
+            let field = &message.fields[index];
+            let schema_field = &layout.schema.fields[slot];
+            // SAFETY: the slot lies inside the buffer allocated for this
+            // layout, at an offset aligned for its type
+            let ptr = unsafe { base.add(schema_field.offset) };
+            if let FieldType::Message(_) = schema_field.field_type {
+                let data = reader.len_delimited(wire_type, field)?;
+                self.decode_into(&field.type_name, data, ptr, depth + 1)?;
+            } else {
+                let value = reader.scalar(wire_type, field)?;
+                // SAFETY: as above; the slot was initialized for its type
+                unsafe { store(ptr, &schema_field.field_type, value) };
+            }
+        }
+        Ok(())
+    }
+}
+
+fn normalize(name: &str) -> &str {
+    name.strip_prefix('.').unwrap_or(name)
+}
+
+fn push(object: &mut HashMap<IString, Value>, field: &ProtoField, value: Value) {
+    if let Value::Array(items) = object
+        .entry(field.name.clone())
+        .or_insert_with(|| Value::array(Vec::new()))
+    {
+        items.push(value);
+    }
+}
+
+fn collect_message(
+    prefix: &str,
+    message: &DescriptorProto,
+    proto3: bool,
+    out: &mut HashMap<String, ProtoMessage>,
+) -> Result<()> {
+    let name = if prefix.is_empty() {
+        message.name().to_string()
+    } else {
+        format!("{}.{}", prefix, message.name())
+    };
+
+    let mut fields = Vec::with_capacity(message.field.len());
+    let mut by_tag = HashMap::with_capacity(message.field.len());
+    for field in &message.field {
+        let kind = Kind::from_type(field.r#type()).ok_or_else(|| {
+            OrdoError::parse_error(format!(
+                "field '{}.{}': groups are not supported",
+                name,
+                field.name()
+            ))
+        })?;
+        let repeated = field.label() == Label::Repeated;
+        let has_presence = !repeated
+            && if proto3 {
+                field.proto3_optional()
+            } else {
+                field.label() == Label::Optional
+            };
+        by_tag.insert(field.number() as u32, fields.len());
+        fields.push(ProtoField {
+            name: IString::from(field.name()),
+            tag: field.number() as u32,
+            kind,
+            type_name: normalize(field.type_name()).to_string(),
+            repeated,
+            has_presence,
+        });
+    }
+
+    for nested in &message.nested_type {
+        collect_message(&name, nested, proto3, out)?;
+    }
+
+    let map_entry = message
+        .options
+        .as_ref()
+        .is_some_and(|options| options.map_entry());
+    out.insert(
+        name,
+        ProtoMessage {
+            fields,
+            by_tag,
+            map_entry,
+        },
+    );
+    Ok(())
+}
+
+/// Write `value` into the slot at `ptr`
+///
+/// # Safety
+///
+/// `ptr` must point to an initialized, aligned slot of `field_type`.
+unsafe fn store(ptr: *mut u8, field_type: &FieldType, value: Scalar) {
+    match field_type {
+        FieldType::Bool => *(ptr as *mut bool) = value.as_i64() != 0,
+        FieldType::Int32 | FieldType::Enum(_) => *(ptr as *mut i32) = value.as_i64() as i32,
+        FieldType::UInt32 => *(ptr as *mut u32) = value.as_i64() as u32,
+        FieldType::Float32 => *(ptr as *mut f32) = value.as_f64() as f32,
+        FieldType::Int64 => *(ptr as *mut i64) = value.as_i64(),
+        FieldType::UInt64 => *(ptr as *mut u64) = value.as_i64() as u64,
+        FieldType::Float64 => *(ptr as *mut f64) = value.as_f64(),
+        FieldType::String => {
+            if let Scalar::Str(s) = value {
+                *(ptr as *mut String) = s;
+            }
+        }
+        FieldType::Optional(inner) => match inner.as_ref() {
+            FieldType::Bool => *(ptr as *mut Option<bool>) = Some(value.as_i64() != 0),
+            FieldType::Int32 | FieldType::Enum(_) => {
+                *(ptr as *mut Option<i32>) = Some(value.as_i64() as i32)
+            }
+            FieldType::UInt32 => *(ptr as *mut Option<u32>) = Some(value.as_i64() as u32),
+            FieldType::Float32 => *(ptr as *mut Option<f32>) = Some(value.as_f64() as f32),
+            FieldType::Int64 => *(ptr as *mut Option<i64>) = Some(value.as_i64()),
+            FieldType::UInt64 => *(ptr as *mut Option<u64>) = Some(value.as_i64() as u64),
+            FieldType::Float64 => *(ptr as *mut Option<f64>) = Some(value.as_f64()),
+            _ => {
+                if let Scalar::Str(s) = value {
+                    *(ptr as *mut Option<String>) = Some(s);
+                }
+            }
+        },
+        FieldType::Message(_) | FieldType::Bytes | FieldType::Repeated(_) => {}
+    }
+}
+
+/// A message decoded into the memory layout of its [`MessageSchema`]
+///
+/// Scalars are stored as their Rust types, strings as `String` and fields with
+/// presence as `Option<T>`, at the offsets of the schema fields. Nested
+/// messages are stored inline; unset fields hold their default (or `None`).
+pub struct TypedBuffer {
+    words: Vec<u64>,
+    schema: Arc<MessageSchema>,
+}
+
+impl TypedBuffer {
+    fn new(schema: Arc<MessageSchema>) -> Self {
+        let mut buffer = Self {
+            words: vec![0; schema.struct_size.div_ceil(8).max(1)],
+            schema,
+        };
+        let base = buffer.as_mut_ptr();
+        // SAFETY: the buffer covers the schema's struct size and the slots of
+        // non-trivial types are written before anything reads them
+        unsafe { for_each_owned_slot(&buffer.schema, base, &mut |ptr, ty| init_slot(ptr, ty)) };
+        buffer
+    }
+
+    /// Schema describing the buffer layout
+    pub fn schema(&self) -> &Arc<MessageSchema> {
+        &self.schema
+    }
+
+    /// Pointer to the start of the buffer
+    pub fn as_ptr(&self) -> *const u8 {
+        self.words.as_ptr() as *const u8
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.words.as_mut_ptr() as *mut u8
+    }
+
+    /// View the buffer as a typed context for Schema-Aware JIT
+    #[cfg(feature = "jit")]
+    pub fn typed_context(&self) -> crate::expr::jit::DynamicTypedContext<'_> {
+        // SAFETY: the buffer is laid out by its schema and outlives the context
+        unsafe { crate::expr::jit::DynamicTypedContext::new(self.as_ptr(), self.schema.clone()) }
+    }
+}
+
+impl Drop for TypedBuffer {
+    fn drop(&mut self) {
+        let base = self.as_mut_ptr();
+        let schema = self.schema.clone();
+        // SAFETY: every owned slot was initialized in `new`
+        unsafe {
+            for_each_owned_slot(&schema, base, &mut |ptr, ty| match ty {
+                FieldType::String => std::ptr::drop_in_place(ptr as *mut String),
+                FieldType::Optional(inner) if matches!(inner.as_ref(), FieldType::String) => {
+                    std::ptr::drop_in_place(ptr as *mut Option<String>)
+                }
+                _ => {}
+            })
+        };
+    }
+}
+
+impl std::fmt::Debug for TypedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedBuffer")
+            .field("schema", &self.schema.name)
+            .field("size", &self.schema.struct_size)
+            .finish()
+    }
+}
+
+/// Call `f` for every slot holding a string or an optional, including those of
+/// nested messages
+unsafe fn for_each_owned_slot(
+    schema: &MessageSchema,
+    base: *mut u8,
+    f: &mut impl FnMut(*mut u8, &FieldType),
+) {
+    for field in &schema.fields {
+        let ptr = base.add(field.offset);
+        match &field.field_type {
+            FieldType::String | FieldType::Optional(_) => f(ptr, &field.field_type),
+            FieldType::Message(nested) => for_each_owned_slot(nested, ptr, f),
+            _ => {}
+        }
+    }
+}
+
+/// Write the default value of a slot
+unsafe fn init_slot(ptr: *mut u8, field_type: &FieldType) {
+    match field_type {
+        FieldType::String => std::ptr::write(ptr as *mut String, String::new()),
+        FieldType::Optional(inner) => match inner.as_ref() {
+            FieldType::Bool => std::ptr::write(ptr as *mut Option<bool>, None),
+            FieldType::Int32 | FieldType::Enum(_) => std::ptr::write(ptr as *mut Option<i32>, None),
+            FieldType::UInt32 => std::ptr::write(ptr as *mut Option<u32>, None),
+            FieldType::Float32 => std::ptr::write(ptr as *mut Option<f32>, None),
+            FieldType::Int64 => std::ptr::write(ptr as *mut Option<i64>, None),
+            FieldType::UInt64 => std::ptr::write(ptr as *mut Option<u64>, None),
+            FieldType::Float64 => std::ptr::write(ptr as *mut Option<f64>, None),
+            _ => std::ptr::write(ptr as *mut Option<String>, None),
+        },
+        _ => {}
+    }
+}
+
+/// Protobuf wire-format reader
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn truncated() -> OrdoError {
+        OrdoError::eval_error("truncated protobuf message")
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for (i, &byte) in self.buf.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.buf = &self.buf[i + 1..];
+                return Ok(value);
+            }
+        }
+        Err(OrdoError::eval_error("invalid protobuf varint"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(Self::truncated());
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn fixed32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn fixed64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn key(&mut self) -> Result<(u32, u8)> {
+        let key = self.varint()?;
+        let tag = u32::try_from(key >> 3)
+            .ok()
+            .filter(|&tag| tag > 0)
+            .ok_or_else(|| OrdoError::eval_error("invalid protobuf field tag"))?;
+        Ok((tag, (key & 0x7) as u8))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = usize::try_from(self.varint()?).map_err(|_| Self::truncated())?;
+        self.take(len)
+    }
+
+    fn skip(&mut self, wire_type: u8) -> Result<()> {
+        match wire_type {
+            WIRE_VARINT => self.varint().map(drop),
+            WIRE_FIXED64 => self.take(8).map(drop),
+            WIRE_LEN => self.bytes().map(drop),
+            WIRE_FIXED32 => self.take(4).map(drop),
+            _ => Err(OrdoError::eval_error(format!(
+                "unsupported protobuf wire type {}",
+                wire_type
+            ))),
+        }
+    }
+
+    fn check(wire_type: u8, expected: u8, field: &ProtoField) -> Result<()> {
+        if wire_type == expected {
+            Ok(())
+        } else {
+            Err(OrdoError::eval_error(format!(
+                "protobuf field '{}' (tag {}) has wire type {}, expected {}",
+                field.name, field.tag, wire_type, expected
+            )))
+        }
+    }
+
+    fn len_delimited(&mut self, wire_type: u8, field: &ProtoField) -> Result<&'a [u8]> {
+        Self::check(wire_type, WIRE_LEN, field)?;
+        self.bytes()
+    }
+
+    fn scalar(&mut self, wire_type: u8, field: &ProtoField) -> Result<Scalar> {
+        Self::check(wire_type, field.kind.wire_type(), field)?;
+        Ok(match field.kind {
+            Kind::Double => Scalar::Float(f64::from_bits(self.fixed64()?)),
+            Kind::Float => Scalar::Float(f32::from_bits(self.fixed32()?) as f64),
+            Kind::Fixed64 => Scalar::UInt(self.fixed64()?),
+            Kind::SFixed64 => Scalar::Int(self.fixed64()? as i64),
+            Kind::Fixed32 => Scalar::UInt(self.fixed32()? as u64),
+            Kind::SFixed32 => Scalar::Int(self.fixed32()? as i32 as i64),
+            Kind::Int64 => Scalar::Int(self.varint()? as i64),
+            Kind::UInt64 => Scalar::UInt(self.varint()?),
+            Kind::Int32 | Kind::Enum => Scalar::Int(self.varint()? as i32 as i64),
+            Kind::UInt32 => Scalar::UInt(self.varint()? as u32 as u64),
+            Kind::SInt32 => {
+                let v = self.varint()? as u32;
+                Scalar::Int(((v >> 1) as i32 ^ -((v & 1) as i32)) as i64)
+            }
+            Kind::SInt64 => {
+                let v = self.varint()?;
+                Scalar::Int((v >> 1) as i64 ^ -((v & 1) as i64))
+            }
+            Kind::Bool => Scalar::Bool(self.varint()? != 0),
+            Kind::String => {
+                let bytes = self.bytes()?;
+                let s = std::str::from_utf8(bytes).map_err(|_| {
+                    OrdoError::eval_error(format!(
+                        "protobuf field '{}' is not valid UTF-8",
+                        field.name
+                    ))
+                })?;
+                Scalar::Str(s.to_string())
+            }
+            Kind::Bytes => Scalar::Bytes(self.bytes()?.to_vec()),
+            Kind::Message => unreachable!("messages are decoded by the caller"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{FieldDescriptorProto, FileDescriptorProto, MessageOptions};
+
+    fn field(name: &str, number: i32, ty: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(ty as i32),
+            label: Some(label as i32),
+            ..Default::default()
+        }
+    }
+
+    fn message_field(
+        name: &str,
+        number: i32,
+        type_name: &str,
+        label: Label,
+    ) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            type_name: Some(type_name.to_string()),
+            ..field(name, number, Type::Message, label)
+        }
+    }
+
+    /// `loan.Applicant { string name = 1; int32 age = 2; optional double score = 3; }`
+    /// `loan.Loan { double amount = 1; Applicant applicant = 2; repeated int64 terms = 3;
+    ///              map<string, int32> limits = 4; Loan parent = 5; bytes blob = 6; }`
+    fn descriptor_set() -> Vec<u8> {
+        let applicant = DescriptorProto {
+            name: Some("Applicant".to_string()),
+            field: vec![
+                field("name", 1, Type::String, Label::Optional),
+                field("age", 2, Type::Int32, Label::Optional),
+                FieldDescriptorProto {
+                    proto3_optional: Some(true),
+                    ..field("score", 3, Type::Double, Label::Optional)
+                },
+            ],
+            ..Default::default()
+        };
+        let limits_entry = DescriptorProto {
+            name: Some("LimitsEntry".to_string()),
+            field: vec![
+                field("key", 1, Type::String, Label::Optional),
+                field("value", 2, Type::Int32, Label::Optional),
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let loan = DescriptorProto {
+            name: Some("Loan".to_string()),
+            field: vec![
+                field("amount", 1, Type::Double, Label::Optional),
+                message_field("applicant", 2, ".loan.Applicant", Label::Optional),
+                field("terms", 3, Type::Int64, Label::Repeated),
+                message_field("limits", 4, ".loan.Loan.LimitsEntry", Label::Repeated),
+                message_field("parent", 5, ".loan.Loan", Label::Optional),
+                field("blob", 6, Type::Bytes, Label::Optional),
+            ],
+            nested_type: vec![limits_entry],
+            ..Default::default()
+        };
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("loan.proto".to_string()),
+                package: Some("loan".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![applicant, loan],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    fn varint(out: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            out.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn len_field(out: &mut Vec<u8>, tag: u32, data: &[u8]) {
+        varint(out, ((tag << 3) | 2) as u64);
+        varint(out, data.len() as u64);
+        out.extend_from_slice(data);
+    }
+
+    fn loan_bytes() -> Vec<u8> {
+        let mut applicant = Vec::new();
+        len_field(&mut applicant, 1, b"Alice");
+        varint(&mut applicant, 2 << 3);
+        varint(&mut applicant, 30);
+
+        let mut entry = Vec::new();
+        len_field(&mut entry, 1, b"daily");
+        varint(&mut entry, 2 << 3);
+        varint(&mut entry, 500);
+
+        let mut packed = Vec::new();
+        varint(&mut packed, 12);
+        varint(&mut packed, 24);
+
+        let mut loan = Vec::new();
+        varint(&mut loan, (1 << 3) | 1);
+        loan.extend_from_slice(&25000.5f64.to_le_bytes());
+        len_field(&mut loan, 2, &applicant);
+        len_field(&mut loan, 3, &packed);
+        len_field(&mut loan, 4, &entry);
+        len_field(&mut loan, 6, &[1, 2, 3]);
+        // Unknown field is skipped
+        varint(&mut loan, 99 << 3);
+        varint(&mut loan, 7);
+        loan
+    }
+
+    #[test]
+    fn test_schemas_from_descriptor_set() {
+        let set = ProtoSchemaSet::from_descriptor_set(&descriptor_set()).unwrap();
+        assert_eq!(
+            set.message_names(),
+            vec!["loan.Applicant", "loan.Loan", "loan.Loan.LimitsEntry"]
+        );
+
+        let loan = set.schema(".loan.Loan").unwrap();
+        assert_eq!(loan.get_field("amount").unwrap().proto_tag, Some(1));
+        assert!(matches!(
+            loan.get_field("terms").unwrap().field_type,
+            FieldType::Repeated(_)
+        ));
+        // Recursive field has no typed slot
+        assert!(loan.get_field("parent").is_none());
+        let score = loan.resolve_field_path("applicant.score").unwrap();
+        assert!(matches!(score.field_type, FieldType::Optional(_)));
+
+        let mut registry = SchemaRegistry::new();
+        set.register_all(&mut registry);
+        assert!(registry.contains("loan.Applicant"));
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let set = ProtoSchemaSet::from_descriptor_set(&descriptor_set()).unwrap();
+        let value = set.decode("loan.Loan", &loan_bytes()).unwrap();
+        let expected: Value = serde_json::from_str(
+            r#"{"amount": 25000.5,
+                "applicant": {"name": "Alice", "age": 30},
+                "terms": [12, 24],
+                "limits": {"daily": 500},
+                "blob": "AQID"}"#,
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+
+        // Implicit-presence fields take their defaults, optional ones stay unset
+        let empty = set.decode("loan.Applicant", &[]).unwrap();
+        assert_eq!(
+            empty,
+            serde_json::from_str::<Value>(r#"{"name": "", "age": 0}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let set = ProtoSchemaSet::from_descriptor_set(&descriptor_set()).unwrap();
+        assert!(set.decode("loan.Missing", &[]).is_err());
+        // Truncated length-delimited field
+        assert!(set.decode("loan.Loan", &[0x12, 0x05, 0x01]).is_err());
+        // Wrong wire type for `amount`
+        assert!(set.decode("loan.Loan", &[0x08, 0x01]).is_err());
+        assert!(ProtoSchemaSet::from_descriptor_set(&[0xff, 0xff]).is_err());
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_decode_typed() {
+        let set = ProtoSchemaSet::from_descriptor_set(&descriptor_set()).unwrap();
+        let buffer = set.decode_typed("loan.Loan", &loan_bytes()).unwrap();
+        let ctx = buffer.typed_context();
+        unsafe {
+            assert_eq!(ctx.read_field_as_f64("amount"), Some(25000.5));
+            assert_eq!(
+                ctx.read_field_value("applicant.name"),
+                Some(Value::string("Alice"))
+            );
+            assert_eq!(ctx.read_field_value("applicant.age"), Some(Value::Int(30)));
+            assert_eq!(ctx.read_field_value("applicant.score"), Some(Value::Null));
+        }
+    }
+}
@@ -30,6 +30,7 @@
 //! ```
 
 use hashbrown::HashMap;
+use serde::{Serialize, Serializer};
 use std::sync::Arc;
 
 /// Field type information for schema-aware compilation
@@ -63,7 +64,69 @@ pub enum FieldType {
     Enum(String),
 }
 
+impl std::fmt::Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldType::Bool => write!(f, "bool"),
+            FieldType::Int32 => write!(f, "int32"),
+            FieldType::Int64 => write!(f, "int64"),
+            FieldType::UInt32 => write!(f, "uint32"),
+            FieldType::UInt64 => write!(f, "uint64"),
+            FieldType::Float32 => write!(f, "float"),
+            FieldType::Float64 => write!(f, "double"),
+            FieldType::String => write!(f, "string"),
+            FieldType::Bytes => write!(f, "bytes"),
+            FieldType::Message(schema) => write!(f, "{}", schema.name),
+            FieldType::Repeated(inner) => write!(f, "repeated {}", inner),
+            FieldType::Optional(inner) => write!(f, "optional {}", inner),
+            FieldType::Enum(name) => write!(f, "enum {}", name),
+        }
+    }
+}
+
+/// Serialized as its display name (e.g. `"optional int64"`)
+impl Serialize for FieldType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl FieldType {
+    /// Size and alignment of this type in a typed context layout
+    ///
+    /// Strings are stored as `String` and optionals as `Option<T>`; nested
+    /// messages are stored inline. Repeated and bytes fields have no slot.
+    pub(crate) fn slot_layout(&self) -> (usize, usize) {
+        use std::mem::{align_of, size_of};
+        macro_rules! of {
+            ($t:ty) => {
+                (size_of::<$t>(), align_of::<$t>())
+            };
+        }
+        match self {
+            FieldType::Bool => of!(bool),
+            FieldType::Int32 | FieldType::Enum(_) => of!(i32),
+            FieldType::UInt32 => of!(u32),
+            FieldType::Float32 => of!(f32),
+            FieldType::Int64 => of!(i64),
+            FieldType::UInt64 => of!(u64),
+            FieldType::Float64 => of!(f64),
+            FieldType::String => of!(String),
+            FieldType::Optional(inner) => match inner.as_ref() {
+                FieldType::Bool => of!(Option<bool>),
+                FieldType::Int32 | FieldType::Enum(_) => of!(Option<i32>),
+                FieldType::UInt32 => of!(Option<u32>),
+                FieldType::Float32 => of!(Option<f32>),
+                FieldType::Int64 => of!(Option<i64>),
+                FieldType::UInt64 => of!(Option<u64>),
+                FieldType::Float64 => of!(Option<f64>),
+                _ => of!(Option<String>),
+            },
+            FieldType::Message(schema) => (schema.struct_size.next_multiple_of(8), 8),
+            FieldType::Bytes | FieldType::Repeated(_) => (0, 1),
+        }
+    }
+
     /// Get the size of this field type in bytes (for primitive types)
     pub fn primitive_size(&self) -> Option<usize> {
         match self {
@@ -98,13 +161,15 @@ impl FieldType {
 }
 
 /// Schema for a single field
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FieldSchema {
     /// Field name
     pub name: String,
     /// Field type
+    #[serde(rename = "type")]
     pub field_type: FieldType,
     /// Protobuf tag number (if from protobuf)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proto_tag: Option<u32>,
     /// Byte offset within the struct (calculated at compile time)
     pub offset: usize,
@@ -148,13 +213,14 @@ impl FieldSchema {
 }
 
 /// Schema for a message/struct type
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MessageSchema {
     /// Message type name
     pub name: String,
     /// All fields in this message
     pub fields: Vec<FieldSchema>,
     /// Field name to index lookup
+    #[serde(skip)]
     field_index: HashMap<String, usize>,
     /// Total struct size in bytes
     pub struct_size: usize,
@@ -4,9 +4,78 @@
 //! - Input fact data
 //! - Intermediate variables
 //! - Execution state
+//!
+//! Variables known when a ruleset is compiled are laid out in a
+//! [`VariableLayout`] and stored in indexed slots; names outside the layout
+//! (dynamic variables, iteration state) fall back to a map.
 
-use super::Value;
+use super::{IString, Value};
 use hashbrown::HashMap;
+use std::sync::Arc;
+
+/// Layouts up to this size are searched linearly instead of hashed
+const LINEAR_LOOKUP_MAX: usize = 16;
+
+/// Slot assignment for the variables of a compiled ruleset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariableLayout {
+    names: Vec<IString>,
+    index: HashMap<IString, usize>,
+}
+
+impl VariableLayout {
+    /// Assign slots to `names` in order (duplicates share a slot)
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut layout = Self::default();
+        for name in names {
+            layout.insert(name.as_ref());
+        }
+        layout
+    }
+
+    /// Slot of `name`, adding it if missing
+    pub fn insert(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.slot(name) {
+            return slot;
+        }
+        let name: IString = Arc::from(name);
+        self.index.insert(name.clone(), self.names.len());
+        self.names.push(name);
+        self.names.len() - 1
+    }
+
+    /// Slot of `name`, if it is part of the layout
+    #[inline]
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        if self.names.len() <= LINEAR_LOOKUP_MAX {
+            self.names.iter().position(|n| n.as_ref() == name)
+        } else {
+            self.index.get(name).copied()
+        }
+    }
+
+    /// Variable names by slot
+    #[inline]
+    pub fn names(&self) -> &[IString] {
+        &self.names
+    }
+
+    /// Number of slots
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether the layout has no slots
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
 
 /// Execution context
 ///
@@ -15,7 +84,11 @@ use hashbrown::HashMap;
 pub struct Context {
     /// Root data (input fact data)
     data: Value,
-    /// Variable storage (variables set during execution)
+    /// Slot layout of the running ruleset's variables
+    layout: Option<Arc<VariableLayout>>,
+    /// Slot storage for variables in `layout`
+    slots: Vec<Option<Value>>,
+    /// Variables outside the layout
     variables: HashMap<String, Value>,
     /// Current iteration item (used in batch mode)
     current_item: Option<Value>,
@@ -28,12 +101,47 @@ impl Context {
     pub fn new(data: Value) -> Self {
         Self {
             data,
+            layout: None,
+            slots: Vec::new(),
             variables: HashMap::new(),
             current_item: None,
             current_index: None,
         }
     }
 
+    /// Create a context storing the variables of `layout` in slots
+    pub fn with_layout(data: Value, layout: Arc<VariableLayout>) -> Self {
+        let mut ctx = Self::new(data);
+        ctx.set_layout(Some(layout));
+        ctx
+    }
+
+    /// Replace the slot layout (clears all variables)
+    pub fn set_layout(&mut self, layout: Option<Arc<VariableLayout>>) {
+        self.slots.clear();
+        self.variables.clear();
+        if let Some(layout) = &layout {
+            self.slots.resize(layout.len(), None);
+        }
+        self.layout = layout;
+    }
+
+    /// Slot layout in use, if any
+    #[inline]
+    pub fn layout(&self) -> Option<&Arc<VariableLayout>> {
+        self.layout.as_ref()
+    }
+
+    /// Clear all state for reuse, keeping allocated capacity
+    pub fn reset(&mut self, data: Value) {
+        self.data = data;
+        self.layout = None;
+        self.slots.clear();
+        self.variables.clear();
+        self.current_item = None;
+        self.current_index = None;
+    }
+
     /// Create context from JSON string
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
         let data: Value = serde_json::from_str(json)?;
@@ -62,7 +170,7 @@ impl Context {
     pub fn get(&self, path: &str) -> Option<&Value> {
         if let Some(var_name) = path.strip_prefix('$') {
             // Variable reference
-            self.variables.get(var_name)
+            self.get_variable(var_name)
         } else if let Some(item_path) = path.strip_prefix("item.") {
             // Current iteration item field
             self.current_item.as_ref()?.get_path(item_path)
@@ -71,7 +179,7 @@ impl Context {
             self.current_item.as_ref()
         } else if path == "_index" {
             // Special handling for index - backed by an internal variable
-            self.variables.get("_index")
+            self.get_variable("_index")
         } else if let Some(data_path) = path.strip_prefix("data.") {
             // Explicit data prefix
             self.data.get_path(data_path)
@@ -84,25 +192,63 @@ impl Context {
     /// Get variable value
     #[inline]
     pub fn get_variable(&self, name: &str) -> Option<&Value> {
-        self.variables.get(name)
+        match self.layout.as_ref().and_then(|layout| layout.slot(name)) {
+            Some(slot) => self.slots[slot].as_ref(),
+            None => self.variables.get(name),
+        }
     }
 
     /// Set variable
     #[inline]
-    pub fn set_variable(&mut self, name: impl Into<String>, value: Value) {
-        self.variables.insert(name.into(), value);
+    pub fn set_variable(&mut self, name: impl AsRef<str> + Into<String>, value: Value) {
+        if let Some(slot) = self
+            .layout
+            .as_ref()
+            .and_then(|layout| layout.slot(name.as_ref()))
+        {
+            self.slots[slot] = Some(value);
+        } else if let Some(existing) = self.variables.get_mut(name.as_ref()) {
+            *existing = value;
+        } else {
+            self.variables.insert(name.into(), value);
+        }
+    }
+
+    /// Get the variable in `slot` of the current layout
+    #[inline]
+    pub fn slot(&self, slot: usize) -> Option<&Value> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    /// Set the variable in `slot` of the current layout
+    ///
+    /// # Panics
+    /// Panics if `slot` is out of range for the layout.
+    #[inline]
+    pub fn set_slot(&mut self, slot: usize, value: Value) {
+        self.slots[slot] = Some(value);
     }
 
     /// Remove variable
     #[inline]
     pub fn remove_variable(&mut self, name: &str) -> Option<Value> {
-        self.variables.remove(name)
+        match self.layout.as_ref().and_then(|layout| layout.slot(name)) {
+            Some(slot) => self.slots[slot].take(),
+            None => self.variables.remove(name),
+        }
     }
 
-    /// Get all variables
-    #[inline]
-    pub fn variables(&self) -> &HashMap<String, Value> {
-        &self.variables
+    /// Snapshot of all variables
+    pub fn variables(&self) -> HashMap<String, Value> {
+        let mut variables = self.variables.clone();
+        if let Some(layout) = &self.layout {
+            for (name, value) in layout.names().iter().zip(&self.slots) {
+                if let Some(value) = value {
+                    variables.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+        variables
     }
 
     /// Set current iteration item
@@ -111,8 +257,7 @@ impl Context {
         self.current_item = Some(item);
         self.current_index = Some(index);
         // Expose the current index via a special `_index` variable so it can be accessed from expressions.
-        self.variables
-            .insert("_index".to_string(), Value::int(index as i64));
+        self.set_variable("_index", Value::int(index as i64));
     }
 
     /// Clear current iteration item
@@ -121,7 +266,7 @@ impl Context {
         self.current_item = None;
         self.current_index = None;
         // Remove the special index variable when iteration ends.
-        self.variables.remove("_index");
+        self.remove_variable("_index");
     }
 
     /// Get current iteration item
@@ -138,8 +283,8 @@ impl Context {
 
     /// Merge variables from another context
     pub fn merge_variables(&mut self, other: &Context) {
-        for (k, v) in &other.variables {
-            self.variables.insert(k.clone(), v.clone());
+        for (k, v) in other.variables() {
+            self.set_variable(k, v);
         }
     }
 
@@ -147,6 +292,8 @@ impl Context {
     pub fn child(&self) -> Self {
         Self {
             data: self.data.clone(),
+            layout: self.layout.clone(),
+            slots: self.slots.clone(),
             variables: self.variables.clone(),
             current_item: self.current_item.clone(),
             current_index: self.current_index,
@@ -197,4 +344,29 @@ mod tests {
         // `_index` should be available as a special variable reflecting the current index.
         assert_eq!(ctx.get("_index"), Some(&Value::int(0)));
     }
+
+    #[test]
+    fn test_context_layout_slots() {
+        let layout = Arc::new(VariableLayout::new(["score", "tier", "score"]));
+        assert_eq!(layout.len(), 2);
+        assert_eq!(layout.slot("tier"), Some(1));
+
+        let mut ctx = Context::with_layout(Value::Null, layout);
+        ctx.set_slot(0, Value::int(100));
+        ctx.set_variable("tier", Value::string("gold"));
+        ctx.set_variable("other", Value::Bool(true));
+
+        assert_eq!(ctx.get("$score"), Some(&Value::int(100)));
+        assert_eq!(ctx.slot(1), Some(&Value::string("gold")));
+        assert_eq!(ctx.get("$other"), Some(&Value::Bool(true)));
+        assert_eq!(ctx.variables().len(), 3);
+
+        assert_eq!(ctx.remove_variable("score"), Some(Value::int(100)));
+        assert_eq!(ctx.get("$score"), None);
+
+        ctx.reset(Value::int(1));
+        assert!(ctx.layout().is_none());
+        assert!(ctx.variables().is_empty());
+        assert_eq!(ctx.data(), &Value::int(1));
+    }
 }
@@ -105,7 +105,7 @@ impl<'de> Deserialize<'de> for Value {
             }
 
             fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
-                Ok(Value::Int(v as i64))
+                Ok(Value::from_u64(v))
             }
 
             fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
@@ -155,6 +155,38 @@ impl<'de> Deserialize<'de> for Value {
     }
 }
 
+#[cfg(feature = "lazy-input")]
+impl Value {
+    /// Convert a value of a simd-json tape (same mapping as `Deserialize`)
+    pub fn from_tape(value: simd_json::tape::Value<'_, '_>) -> Self {
+        use simd_json::prelude::*;
+
+        match value.value_type() {
+            ValueType::Bool => Value::Bool(value.as_bool().unwrap_or_default()),
+            ValueType::I64 => Value::Int(value.as_i64().unwrap_or_default()),
+            ValueType::U64 => Value::from_u64(value.as_u64().unwrap_or_default()),
+            ValueType::F64 => Value::Float(value.as_f64().unwrap_or_default()),
+            ValueType::String => Value::String(Arc::from(value.as_str().unwrap_or_default())),
+            ValueType::Array => Value::Array(
+                value
+                    .as_array()
+                    .map(|arr| arr.iter().map(Value::from_tape).collect())
+                    .unwrap_or_default(),
+            ),
+            ValueType::Object => {
+                let mut obj = HashMap::new();
+                if let Some(map) = value.as_object() {
+                    for (key, item) in map.iter() {
+                        obj.insert(Arc::from(key), Value::from_tape(item));
+                    }
+                }
+                Value::Object(obj)
+            }
+            _ => Value::Null,
+        }
+    }
+}
+
 impl Value {
     // ==================== Constructors ====================
 
@@ -176,6 +208,14 @@ impl Value {
         Self::Int(v)
     }
 
+    /// Create a number from an unsigned integer
+    ///
+    /// Values above `i64::MAX` become floats instead of wrapping around.
+    #[inline]
+    pub fn from_u64(v: u64) -> Self {
+        i64::try_from(v).map_or(Self::Float(v as f64), Self::Int)
+    }
+
     /// Create a float value
     #[inline]
     pub fn float(v: f64) -> Self {
@@ -351,30 +391,13 @@ impl Value {
     /// Supports dot-separated paths like "user.profile.name"
     /// Supports array indices like "items.0.price"
     pub fn get_path(&self, path: &str) -> Option<&Value> {
-        let parts: Vec<&str> = path.split('.').collect();
-        self.get_path_parts(&parts)
-    }
-
-    /// Get field value by path parts
-    fn get_path_parts(&self, parts: &[&str]) -> Option<&Value> {
-        if parts.is_empty() {
-            return Some(self);
-        }
-
-        let key = parts[0];
-        let rest = &parts[1..];
-
-        match self {
-            Self::Object(map) => map.get(key).and_then(|v| v.get_path_parts(rest)),
-            Self::Array(arr) => {
-                // Try to parse as array index
-                key.parse::<usize>()
-                    .ok()
-                    .and_then(|idx| arr.get(idx))
-                    .and_then(|v| v.get_path_parts(rest))
-            }
+        // Walk the segments in place: field reads run on every execution
+        path.split('.').try_fold(self, |value, key| match value {
+            Self::Object(map) => map.get(key),
+            // Try to parse as array index
+            Self::Array(arr) => key.parse::<usize>().ok().and_then(|idx| arr.get(idx)),
             _ => None,
-        }
+        })
     }
 
     /// Set value at path (if path exists)
@@ -632,6 +655,12 @@ mod tests {
 
         let parsed: Value = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed, value);
+
+        // Integers beyond i64 keep their sign
+        let parsed: Value = serde_json::from_str(&u64::MAX.to_string()).unwrap();
+        assert_eq!(parsed, Value::Float(u64::MAX as f64));
+        let parsed: Value = serde_json::from_str(&i64::MAX.to_string()).unwrap();
+        assert_eq!(parsed, Value::Int(i64::MAX));
     }
 
     #[test]
@@ -66,6 +66,13 @@ pub enum OrdoError {
     #[error("Max execution depth exceeded: {max_depth}")]
     MaxDepthExceeded { max_depth: usize },
 
+    /// Resource budget exhausted
+    #[error("Resource budget exceeded: {resource} (limit {limit})")]
+    BudgetExceeded {
+        resource: Cow<'static, str>,
+        limit: u64,
+    },
+
     /// Configuration error
     #[error("Config error: {message}")]
     ConfigError { message: Cow<'static, str> },
@@ -156,6 +163,14 @@ impl OrdoError {
         Self::FunctionNotFound { name: name.into() }
     }
 
+    /// Create a budget exceeded error
+    pub fn budget_exceeded(resource: impl Into<Cow<'static, str>>, limit: u64) -> Self {
+        Self::BudgetExceeded {
+            resource: resource.into(),
+            limit,
+        }
+    }
+
     /// Create a config error from a static string (no allocation)
     #[inline]
     pub fn config_error_static(message: &'static str) -> Self {
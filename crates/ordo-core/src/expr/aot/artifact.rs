@@ -0,0 +1,547 @@
+//! Emitted files: object code, C header, Rust wrapper and static library
+
+use super::compiler::{AotPlan, AotTerminal};
+use super::AotErrorCode;
+use crate::context::{FieldType, MessageSchema};
+use crate::error::{OrdoError, Result};
+use crate::expr::ShapeKind;
+use std::fmt::Write;
+
+/// Output of the ahead-of-time compiler
+#[derive(Debug, Clone)]
+pub struct AotArtifact {
+    /// Ruleset name
+    pub ruleset: String,
+    /// Entry point symbol (`ordo_<symbol>_execute`)
+    pub entry_symbol: String,
+    /// Terminal steps, indexed by the entry point's return value
+    pub terminals: Vec<AotTerminal>,
+    /// Size of the `outputs` buffer the entry point writes to, in `f64`s
+    pub max_outputs: usize,
+    /// Relocatable object file for the target
+    pub object: Vec<u8>,
+    /// C header declaring the context struct, entry point and terminal table
+    pub header: String,
+    /// Rust module wrapping the entry point
+    pub rust: String,
+}
+
+impl AotArtifact {
+    pub(super) fn new(plan: &AotPlan, schema: &MessageSchema, object: Vec<u8>) -> Result<Self> {
+        let layout = ContextLayout::new(schema)?;
+        let prefix = plan.entry_symbol.trim_end_matches("_execute").to_string();
+        let artifact = Self {
+            ruleset: plan.name.clone(),
+            entry_symbol: plan.entry_symbol.clone(),
+            terminals: plan.terminals.clone(),
+            max_outputs: plan.max_outputs(),
+            object,
+            header: String::new(),
+            rust: String::new(),
+        };
+        Ok(Self {
+            header: artifact.render_header(&prefix, &layout),
+            rust: artifact.render_rust(&layout),
+            ..artifact
+        })
+    }
+
+    /// Package the object as a static library (GNU `ar` format with a symbol index)
+    pub fn static_library(&self) -> Vec<u8> {
+        const MEMBER_NAME: &str = "ruleset.o/";
+
+        let mut index = Vec::new();
+        index.extend_from_slice(&1u32.to_be_bytes());
+        let offset_at = index.len();
+        index.extend_from_slice(&0u32.to_be_bytes());
+        index.extend_from_slice(self.entry_symbol.as_bytes());
+        index.push(0);
+        let padded = |len: usize| len + len % 2;
+
+        // The index points at the header of the object member
+        let member_offset = 8 + 60 + padded(index.len());
+        index[offset_at..offset_at + 4].copy_from_slice(&(member_offset as u32).to_be_bytes());
+
+        let mut archive = b"!<arch>\n".to_vec();
+        for (name, data) in [("/", &index), (MEMBER_NAME, &self.object)] {
+            let header = format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                name,
+                0,
+                0,
+                0,
+                644,
+                data.len()
+            );
+            archive.extend_from_slice(header.as_bytes());
+            archive.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                archive.push(b'\n');
+            }
+        }
+        archive
+    }
+
+    fn render_header(&self, prefix: &str, layout: &ContextLayout) -> String {
+        let upper = prefix.to_uppercase();
+        let ctx_type = format!("{}_ctx", prefix);
+        let terminal_type = format!("{}_terminal", prefix);
+        let mut h = String::new();
+
+        let _ = writeln!(
+            h,
+            "/* Generated by ordo from ruleset {}. Do not edit. */",
+            comment(&self.ruleset)
+        );
+        let _ = writeln!(h, "#ifndef {}_H", upper);
+        let _ = writeln!(h, "#define {}_H\n", upper);
+        let _ = writeln!(h, "#include <stdint.h>\n");
+        let _ = writeln!(h, "#ifdef __cplusplus\nextern \"C\" {{\n#endif\n");
+
+        let _ = writeln!(h, "#ifndef ORDO_AOT_STATUS_CODES");
+        let _ = writeln!(h, "#define ORDO_AOT_STATUS_CODES");
+        for (name, code) in [
+            ("NO_MATCH", AotErrorCode::NoMatch),
+            ("MAX_DEPTH_EXCEEDED", AotErrorCode::MaxDepthExceeded),
+            ("OUT_OF_RANGE", AotErrorCode::OutOfRange),
+        ] {
+            let _ = writeln!(h, "#define ORDO_AOT_{} ({})", name, code as i32);
+        }
+        let _ = writeln!(h, "#define ORDO_AOT_OUTPUT_INT 0");
+        let _ = writeln!(h, "#define ORDO_AOT_OUTPUT_FLOAT 1");
+        let _ = writeln!(h, "#define ORDO_AOT_OUTPUT_BOOL 2");
+        let _ = writeln!(h, "#endif\n");
+
+        let _ = writeln!(h, "#define {}_TERMINALS {}", upper, self.terminals.len());
+        let _ = writeln!(h, "#define {}_MAX_OUTPUTS {}\n", upper, self.max_outputs);
+
+        let _ = writeln!(h, "/* Input layout of {} */", comment(&layout.name));
+        let _ = writeln!(h, "typedef struct {} {{", ctx_type);
+        for member in &layout.members {
+            match member {
+                Member::Field { name, field_type } => {
+                    let _ = writeln!(h, "    {} {};", c_type(field_type), name);
+                }
+                Member::Padding { name, len } => {
+                    let _ = writeln!(h, "    uint8_t {}[{}];", name, len);
+                }
+            }
+        }
+        let _ = writeln!(h, "}} {};\n", ctx_type);
+
+        let _ = writeln!(h, "typedef struct {} {{", terminal_type);
+        let _ = writeln!(h, "    const char *step_id;");
+        let _ = writeln!(h, "    const char *code;");
+        let _ = writeln!(h, "    const char *message;");
+        let _ = writeln!(h, "    const char *data_json;");
+        let _ = writeln!(h, "    uint32_t output_count;");
+        let _ = writeln!(h, "    const char *const *output_names;");
+        let _ = writeln!(h, "    const uint8_t *output_kinds;");
+        let _ = writeln!(h, "}} {};\n", terminal_type);
+
+        for (i, terminal) in self.terminals.iter().enumerate() {
+            if terminal.outputs.is_empty() {
+                continue;
+            }
+            let names: Vec<String> = terminal.outputs.iter().map(|o| c_string(&o.name)).collect();
+            let kinds: Vec<&str> = terminal
+                .outputs
+                .iter()
+                .map(|o| match o.kind {
+                    ShapeKind::Int => "ORDO_AOT_OUTPUT_INT",
+                    ShapeKind::Bool => "ORDO_AOT_OUTPUT_BOOL",
+                    _ => "ORDO_AOT_OUTPUT_FLOAT",
+                })
+                .collect();
+            let _ = writeln!(
+                h,
+                "static const char *const {}_t{}_output_names[] = {{{}}};",
+                prefix,
+                i,
+                names.join(", ")
+            );
+            let _ = writeln!(
+                h,
+                "static const uint8_t {}_t{}_output_kinds[] = {{{}}};",
+                prefix,
+                i,
+                kinds.join(", ")
+            );
+        }
+
+        let _ = writeln!(
+            h,
+            "\n/* Indexed by the return value of {} */",
+            self.entry_symbol
+        );
+        let _ = writeln!(
+            h,
+            "static const {} {}_TERMINAL_TABLE[{}_TERMINALS] = {{",
+            terminal_type, upper, upper
+        );
+        for (i, terminal) in self.terminals.iter().enumerate() {
+            let (names, kinds) = if terminal.outputs.is_empty() {
+                ("0".to_string(), "0".to_string())
+            } else {
+                (
+                    format!("{}_t{}_output_names", prefix, i),
+                    format!("{}_t{}_output_kinds", prefix, i),
+                )
+            };
+            let _ = writeln!(
+                h,
+                "    {{{}, {}, {}, {}, {}, {}, {}}},",
+                c_string(&terminal.step_id),
+                c_string(&terminal.code),
+                c_string(&terminal.message),
+                c_string(&data_json(terminal)),
+                terminal.outputs.len(),
+                names,
+                kinds
+            );
+        }
+        let _ = writeln!(h, "}};\n");
+
+        let _ = writeln!(
+            h,
+            "/* Returns a terminal index, or a negative ORDO_AOT_* code.\n   \
+             Writes the terminal's outputs to `outputs` (may be NULL). */"
+        );
+        let _ = writeln!(
+            h,
+            "int32_t {}(const {} *ctx, double *outputs);\n",
+            self.entry_symbol, ctx_type
+        );
+
+        let _ = writeln!(h, "#ifdef __cplusplus\n}}\n#endif\n");
+        let _ = writeln!(h, "#endif /* {}_H */", upper);
+        h
+    }
+
+    fn render_rust(&self, layout: &ContextLayout) -> String {
+        let mut r = String::new();
+
+        let _ = writeln!(
+            r,
+            "//! Generated by ordo from ruleset {:?}. Do not edit.",
+            self.ruleset
+        );
+        let _ = writeln!(r, "//!");
+        let _ = writeln!(
+            r,
+            "//! Link the static library (e.g. `cargo:rustc-link-lib=static=...`) and\n\
+             //! include this file as a module."
+        );
+        let _ = writeln!(r);
+
+        let _ = writeln!(r, "/// Input layout of `{}`", layout.name);
+        let _ = writeln!(r, "#[repr(C)]");
+        let _ = writeln!(r, "#[derive(Debug, Clone, Copy)]");
+        let _ = writeln!(r, "pub struct Context {{");
+        for member in &layout.members {
+            match member {
+                Member::Field { name, field_type } => {
+                    let _ = writeln!(r, "    pub {}: {},", name, rust_type(field_type));
+                }
+                Member::Padding { name, len } => {
+                    let _ = writeln!(r, "    pub {}: [u8; {}],", name, len);
+                }
+            }
+        }
+        let _ = writeln!(r, "}}\n");
+
+        let _ = writeln!(r, "/// Size of the outputs buffer");
+        let _ = writeln!(r, "pub const MAX_OUTPUTS: usize = {};\n", self.max_outputs);
+
+        let _ = writeln!(r, "/// Type of an output value");
+        let _ = writeln!(r, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]");
+        let _ = writeln!(
+            r,
+            "pub enum OutputKind {{\n    Int,\n    Float,\n    Bool,\n}}\n"
+        );
+
+        let _ = writeln!(r, "/// A terminal step of the ruleset");
+        let _ = writeln!(r, "#[derive(Debug)]");
+        let _ = writeln!(r, "pub struct Terminal {{");
+        let _ = writeln!(r, "    pub step_id: &'static str,");
+        let _ = writeln!(r, "    pub code: &'static str,");
+        let _ = writeln!(r, "    pub message: &'static str,");
+        let _ = writeln!(r, "    pub data_json: &'static str,");
+        let _ = writeln!(r, "    pub outputs: &'static [(&'static str, OutputKind)],");
+        let _ = writeln!(r, "}}\n");
+
+        let _ = writeln!(
+            r,
+            "/// Terminal steps, indexed by the entry point's return value"
+        );
+        let _ = writeln!(
+            r,
+            "pub static TERMINALS: [Terminal; {}] = [",
+            self.terminals.len()
+        );
+        for terminal in &self.terminals {
+            let outputs: Vec<String> = terminal
+                .outputs
+                .iter()
+                .map(|o| {
+                    let kind = match o.kind {
+                        ShapeKind::Int => "Int",
+                        ShapeKind::Bool => "Bool",
+                        _ => "Float",
+                    };
+                    format!("({:?}, OutputKind::{})", o.name, kind)
+                })
+                .collect();
+            let _ = writeln!(r, "    Terminal {{");
+            let _ = writeln!(r, "        step_id: {:?},", terminal.step_id);
+            let _ = writeln!(r, "        code: {:?},", terminal.code);
+            let _ = writeln!(r, "        message: {:?},", terminal.message);
+            let _ = writeln!(r, "        data_json: {:?},", data_json(terminal));
+            let _ = writeln!(r, "        outputs: &[{}],", outputs.join(", "));
+            let _ = writeln!(r, "    }},");
+        }
+        let _ = writeln!(r, "];\n");
+
+        let _ = writeln!(r, "/// Execution failure");
+        let _ = writeln!(r, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]");
+        let _ = writeln!(r, "pub enum Error {{");
+        let _ = writeln!(
+            r,
+            "    /// A decision step matched no branch and has no default"
+        );
+        let _ = writeln!(r, "    NoMatch,");
+        let _ = writeln!(r, "    /// The step limit was reached");
+        let _ = writeln!(r, "    MaxDepthExceeded,");
+        let _ = writeln!(
+            r,
+            "    /// A 64-bit integer input is beyond ±2^53; use the interpreter"
+        );
+        let _ = writeln!(r, "    OutOfRange,");
+        let _ = writeln!(r, "}}\n");
+
+        let _ = writeln!(r, "extern \"C\" {{");
+        let _ = writeln!(
+            r,
+            "    fn {}(ctx: *const Context, outputs: *mut f64) -> i32;",
+            self.entry_symbol
+        );
+        let _ = writeln!(r, "}}\n");
+
+        let _ = writeln!(
+            r,
+            "/// Run the ruleset, returning the terminal reached and its outputs"
+        );
+        let _ = writeln!(
+            r,
+            "pub fn execute(ctx: &Context) -> Result<(&'static Terminal, [f64; MAX_OUTPUTS]), Error> {{"
+        );
+        let _ = writeln!(r, "    let mut outputs = [0.0; MAX_OUTPUTS];");
+        let _ = writeln!(
+            r,
+            "    // Safety: the entry point only reads `ctx` and writes at most MAX_OUTPUTS values"
+        );
+        let _ = writeln!(
+            r,
+            "    let code = unsafe {{ {}(ctx, outputs.as_mut_ptr()) }};",
+            self.entry_symbol
+        );
+        let _ = writeln!(r, "    match code {{");
+        let _ = writeln!(
+            r,
+            "        0.. => Ok((&TERMINALS[code as usize], outputs)),"
+        );
+        let _ = writeln!(
+            r,
+            "        {} => Err(Error::MaxDepthExceeded),",
+            AotErrorCode::MaxDepthExceeded as i32
+        );
+        let _ = writeln!(
+            r,
+            "        {} => Err(Error::OutOfRange),",
+            AotErrorCode::OutOfRange as i32
+        );
+        let _ = writeln!(r, "        _ => Err(Error::NoMatch),");
+        let _ = writeln!(r, "    }}");
+        let _ = writeln!(r, "}}");
+        r
+    }
+}
+
+/// A member of the generated context struct
+enum Member {
+    Field { name: String, field_type: FieldType },
+    Padding { name: String, len: usize },
+}
+
+/// The schema as a flat `repr(C)` struct: scalar leaves at their offsets,
+/// everything else (strings, repeated fields, gaps) as padding
+struct ContextLayout {
+    name: String,
+    members: Vec<Member>,
+}
+
+impl ContextLayout {
+    fn new(schema: &MessageSchema) -> Result<Self> {
+        let mut leaves: Vec<(usize, String, FieldType)> = schema
+            .all_field_paths()
+            .into_iter()
+            .filter_map(|path| {
+                let resolved = schema.resolve_field_path(&path)?;
+                resolved.field_type.is_primitive().then_some((
+                    resolved.offset,
+                    path,
+                    resolved.field_type,
+                ))
+            })
+            .collect();
+        leaves.sort_by_key(|(offset, _, _)| *offset);
+
+        let mut members = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        let mut cursor = 0;
+        for (offset, path, field_type) in leaves {
+            let size = field_type.primitive_size().unwrap_or(1);
+            if offset % size != 0 || offset < cursor {
+                return Err(OrdoError::eval_error(format!(
+                    "Field '{}' at offset {} cannot be expressed as a C struct member",
+                    path, offset
+                )));
+            }
+            if offset > cursor {
+                members.push(Member::Padding {
+                    name: format!("_pad{}", cursor),
+                    len: offset - cursor,
+                });
+            }
+            let name = member_name(&path);
+            if names.contains(&name) {
+                return Err(OrdoError::eval_error(format!(
+                    "Fields map to the same member name '{}'",
+                    name
+                )));
+            }
+            names.push(name.clone());
+            members.push(Member::Field { name, field_type });
+            cursor = offset + size;
+        }
+        if schema.struct_size > cursor {
+            members.push(Member::Padding {
+                name: format!("_pad{}", cursor),
+                len: schema.struct_size - cursor,
+            });
+        }
+
+        Ok(Self {
+            name: schema.name.clone(),
+            members,
+        })
+    }
+}
+
+/// Identifier for a field path that is valid in both C and Rust
+fn member_name(path: &str) -> String {
+    const RESERVED: &[&str] = &[
+        "as", "async", "auto", "await", "bool", "box", "break", "case", "char", "const",
+        "continue", "crate", "default", "do", "double", "dyn", "else", "enum", "extern", "false",
+        "float", "fn", "for", "goto", "if", "impl", "in", "inline", "int", "let", "long", "loop",
+        "match", "mod", "move", "mut", "pub", "ref", "register", "restrict", "return", "self",
+        "short", "signed", "sizeof", "static", "struct", "super", "switch", "trait", "true", "try",
+        "type", "typedef", "typeof", "union", "unsafe", "unsigned", "use", "void", "volatile",
+        "where", "while", "yield",
+    ];
+    let mut name: String = path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.starts_with('_') {
+        name.insert(0, 'f');
+    }
+    if RESERVED.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+fn c_type(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Bool => "uint8_t",
+        FieldType::Int32 | FieldType::Enum(_) => "int32_t",
+        FieldType::Int64 => "int64_t",
+        FieldType::UInt32 => "uint32_t",
+        FieldType::UInt64 => "uint64_t",
+        FieldType::Float32 => "float",
+        _ => "double",
+    }
+}
+
+fn rust_type(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Bool => "bool",
+        FieldType::Int32 | FieldType::Enum(_) => "i32",
+        FieldType::Int64 => "i64",
+        FieldType::UInt32 => "u32",
+        FieldType::UInt64 => "u64",
+        FieldType::Float32 => "f32",
+        _ => "f64",
+    }
+}
+
+fn data_json(terminal: &AotTerminal) -> String {
+    serde_json::to_string(&terminal.data).unwrap_or_else(|_| "null".to_string())
+}
+
+/// Quote a name for a C comment
+fn comment(s: &str) -> String {
+    format!("{:?}", s).replace("*/", "*\\/")
+}
+
+/// Quote a string as a C literal
+fn c_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for byte in s.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            0x20..=0x7e => out.push(byte as char),
+            // Octal escapes keep UTF-8 bytes intact and never run into the next character
+            _ => {
+                let _ = write!(out, "\\{:03o}", byte);
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_layout_pads_non_scalars() {
+        let schema = MessageSchema::builder("Order")
+            .field("amount", FieldType::Float64)
+            .field("country", FieldType::String)
+            .field("count", FieldType::Int32)
+            .field("type", FieldType::Bool)
+            .build();
+        let layout = ContextLayout::new(&schema).unwrap();
+        let members: Vec<String> = layout
+            .members
+            .iter()
+            .map(|m| match m {
+                Member::Field { name, .. } => name.clone(),
+                Member::Padding { name, len } => format!("{}:{}", name, len),
+            })
+            .collect();
+        assert_eq!(members, ["amount", "_pad8:40", "count", "type_"]);
+    }
+
+    #[test]
+    fn test_c_string_escapes() {
+        assert_eq!(c_string("a\"b\\c"), r#""a\"b\\c""#);
+        assert_eq!(c_string("é"), r#""\303\251""#);
+    }
+}
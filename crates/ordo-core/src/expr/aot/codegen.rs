@@ -0,0 +1,240 @@
+//! Native code generation for a lowered ruleset
+//!
+//! Generic over the Cranelift module, so the object emitted to disk and the
+//! code loaded in-process are built by the same function.
+
+use super::compiler::{AotPlan, PlanStep};
+use super::AotErrorCode;
+use crate::context::FieldType;
+use crate::error::{OrdoError, Result};
+use crate::expr::jit::{compile_expr_value, LiteralPool, SchemaCompileContext};
+use crate::expr::ShapeKind;
+
+use cranelift::prelude::*;
+use cranelift_module::{FuncId, Linkage, Module};
+
+/// Define `int32_t <entry>(const void *ctx, double *outputs)` in `module`
+pub(super) fn define_execute<M: Module>(
+    module: &mut M,
+    plan: &AotPlan,
+    linkage: Linkage,
+) -> Result<FuncId> {
+    let ptr_type = module.target_config().pointer_type();
+    let mut sig = module.make_signature();
+    sig.params.push(AbiParam::new(ptr_type)); // ctx
+    sig.params.push(AbiParam::new(ptr_type)); // outputs
+    sig.returns.push(AbiParam::new(types::I32)); // terminal index or error code
+
+    let func_id = module
+        .declare_function(&plan.entry_symbol, linkage, &sig)
+        .map_err(|e| OrdoError::eval_error(format!("Failed to declare function: {}", e)))?;
+
+    let mut ctx = module.make_context();
+    ctx.func.signature = sig;
+    ctx.func.name = codegen::ir::UserFuncName::user(0, func_id.as_u32());
+    let mut func_ctx = FunctionBuilderContext::new();
+    // Never used: without runtime helpers no string can be compiled
+    let mut literals = LiteralPool::default();
+
+    {
+        let mut builder = FunctionBuilder::new(&mut ctx.func, &mut func_ctx);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        builder.seal_block(entry);
+        let ctx_ptr = builder.block_params(entry)[0];
+        let outputs_ptr = builder.block_params(entry)[1];
+
+        let mut compile_ctx = SchemaCompileContext {
+            field_offsets: &plan.fields,
+            ptr_type,
+            helpers: None,
+            literals: &mut literals,
+        };
+
+        emit_range_guards(&mut builder, plan, ctx_ptr);
+
+        // One block per step, taking the number of steps taken so far
+        let blocks: Vec<Block> = plan
+            .steps
+            .iter()
+            .map(|_| {
+                let block = builder.create_block();
+                builder.append_block_param(block, types::I32);
+                block
+            })
+            .collect();
+        let depth = builder.ins().iconst(types::I32, 0);
+        builder.ins().jump(blocks[0], &[depth]);
+
+        for (step, &block) in plan.steps.iter().zip(&blocks) {
+            builder.switch_to_block(block);
+            let depth = builder.block_params(block)[0];
+
+            // Same limit as RuleExecutor: checked before running each step
+            let exceeded = builder.ins().icmp_imm(
+                IntCC::SignedGreaterThanOrEqual,
+                depth,
+                plan.max_depth.min(i32::MAX as usize) as i64,
+            );
+            let body = builder.create_block();
+            let bail = builder.create_block();
+            builder.ins().brif(exceeded, bail, &[], body, &[]);
+            builder.switch_to_block(bail);
+            builder.seal_block(bail);
+            return_code(&mut builder, AotErrorCode::MaxDepthExceeded as i64);
+            builder.switch_to_block(body);
+            builder.seal_block(body);
+            let next_depth = builder.ins().iadd_imm(depth, 1);
+
+            match step {
+                PlanStep::Decision { branches, default } => {
+                    let mut open = true;
+                    for (condition, target) in branches {
+                        let Some(expr) = condition else {
+                            // Later branches are unreachable
+                            builder.ins().jump(blocks[*target], &[next_depth]);
+                            open = false;
+                            break;
+                        };
+                        let value =
+                            compile_expr_value(&mut builder, expr, ctx_ptr, &mut compile_ctx)?;
+                        let zero = builder.ins().f64const(0.0);
+                        let truthy = builder.ins().fcmp(FloatCC::NotEqual, value, zero);
+                        let next = builder.create_block();
+                        builder
+                            .ins()
+                            .brif(truthy, blocks[*target], &[next_depth], next, &[]);
+                        builder.switch_to_block(next);
+                        builder.seal_block(next);
+                    }
+                    if open {
+                        match default {
+                            Some(target) => {
+                                builder.ins().jump(blocks[*target], &[next_depth]);
+                            }
+                            None => return_code(&mut builder, AotErrorCode::NoMatch as i64),
+                        }
+                    }
+                }
+                PlanStep::Goto(target) => {
+                    builder.ins().jump(blocks[*target], &[next_depth]);
+                }
+                PlanStep::Terminal(index) => {
+                    let outputs = &plan.outputs[*index];
+                    if !outputs.is_empty() {
+                        // `outputs` may be null when the caller only wants the code
+                        let write = builder.create_block();
+                        let done = builder.create_block();
+                        builder.ins().brif(outputs_ptr, write, &[], done, &[]);
+                        builder.switch_to_block(write);
+                        builder.seal_block(write);
+                        for (slot, expr) in outputs.iter().enumerate() {
+                            let value =
+                                compile_expr_value(&mut builder, expr, ctx_ptr, &mut compile_ctx)?;
+                            builder.ins().store(
+                                MemFlags::new(),
+                                value,
+                                outputs_ptr,
+                                (slot * 8) as i32,
+                            );
+                        }
+                        builder.ins().jump(done, &[]);
+                        builder.switch_to_block(done);
+                        builder.seal_block(done);
+                    }
+                    return_code(&mut builder, *index as i64);
+                }
+            }
+        }
+
+        builder.seal_all_blocks();
+        builder.finalize();
+    }
+
+    module
+        .define_function(func_id, &mut ctx)
+        .map_err(|e| OrdoError::eval_error(format!("Failed to define function: {}", e)))?;
+    module.clear_context(&mut ctx);
+    Ok(func_id)
+}
+
+/// Return `OutOfRange` if a 64-bit integer field holds a value beyond ±2^53
+///
+/// Such values lose precision in `f64`, where the interpreter would compare
+/// them exactly.
+fn emit_range_guards(builder: &mut FunctionBuilder, plan: &AotPlan, ctx_ptr: Value) {
+    let limit = ShapeKind::MAX_EXACT_INT;
+    let mut wide: Vec<_> = plan
+        .fields
+        .values()
+        .filter(|f| matches!(f.field_type, FieldType::Int64 | FieldType::UInt64))
+        .collect();
+    wide.sort_by_key(|f| f.offset);
+
+    for field in wide {
+        let value = builder
+            .ins()
+            .load(types::I64, MemFlags::new(), ctx_ptr, field.offset as i32);
+        let in_range = match field.field_type {
+            // -2^53 <= v <= 2^53  ⇔  (v + 2^53) as u64 <= 2^54
+            FieldType::Int64 => {
+                let biased = builder.ins().iadd_imm(value, limit);
+                builder
+                    .ins()
+                    .icmp_imm(IntCC::UnsignedLessThanOrEqual, biased, 2 * limit)
+            }
+            _ => builder
+                .ins()
+                .icmp_imm(IntCC::UnsignedLessThanOrEqual, value, limit),
+        };
+        let cont = builder.create_block();
+        let bail = builder.create_block();
+        builder.ins().brif(in_range, cont, &[], bail, &[]);
+        builder.switch_to_block(bail);
+        builder.seal_block(bail);
+        return_code(builder, AotErrorCode::OutOfRange as i64);
+        builder.switch_to_block(cont);
+        builder.seal_block(cont);
+    }
+}
+
+fn return_code(builder: &mut FunctionBuilder, code: i64) {
+    // `iconst.i32` takes its immediate zero-extended: negative error codes
+    // must be passed as their 32-bit pattern or the verifier rejects them
+    let code = builder.ins().iconst(types::I32, code as i32 as u32 as i64);
+    builder.ins().return_(&[code]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cranelift::codegen::ir::{Function, InstructionData, UserFuncName};
+
+    #[test]
+    fn test_return_code_immediates_fit_i32() {
+        let mut sig = Signature::new(isa::CallConv::SystemV);
+        sig.returns.push(AbiParam::new(types::I32));
+        let mut func = Function::with_name_signature(UserFuncName::default(), sig);
+        let mut fn_ctx = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut func, &mut fn_ctx);
+        let block = builder.create_block();
+        builder.switch_to_block(block);
+        return_code(&mut builder, AotErrorCode::OutOfRange as i64);
+        builder.seal_all_blocks();
+        builder.finalize();
+
+        let imm = func
+            .layout
+            .blocks()
+            .flat_map(|block| func.layout.block_insts(block))
+            .find_map(|inst| match func.dfg.insts[inst] {
+                InstructionData::UnaryImm { imm, .. } => Some(imm.bits()),
+                _ => None,
+            })
+            .unwrap();
+        // The verifier requires the zero-extended 32-bit pattern
+        assert_eq!(imm, 0xffff_fffd);
+        assert_eq!(imm as i32, AotErrorCode::OutOfRange as i32);
+    }
+}
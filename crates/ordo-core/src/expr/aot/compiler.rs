@@ -0,0 +1,460 @@
+//! RuleSet validation and lowering for ahead-of-time compilation
+
+use super::artifact::AotArtifact;
+use super::codegen::define_execute;
+use super::runtime::AotRuleSet;
+use crate::context::{FieldType, MessageSchema, ResolvedField, Value};
+use crate::error::{OrdoError, Result};
+use crate::expr::{Expr, ExprParser, SchemaJITCompiler, ShapeKind};
+use crate::rule::{Condition, RuleSet, StepKind};
+
+use cranelift_codegen::isa::OwnedTargetIsa;
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::Linkage;
+use cranelift_object::{ObjectBuilder, ObjectModule};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+/// A terminal step of a compiled ruleset
+///
+/// Terminals are numbered in the order they are reached from the entry step;
+/// the entry point returns this index.
+#[derive(Debug, Clone, Serialize)]
+pub struct AotTerminal {
+    /// Step ID
+    pub step_id: String,
+    /// Result code
+    pub code: String,
+    /// Result message
+    pub message: String,
+    /// Output values, in the order they are written to `outputs`
+    pub outputs: Vec<AotOutput>,
+    /// Static data merged into the output
+    pub data: Value,
+}
+
+/// An output value of a terminal step
+#[derive(Debug, Clone, Serialize)]
+pub struct AotOutput {
+    /// Output key
+    pub name: String,
+    /// Type of the value (the entry point writes it as `f64`)
+    pub kind: ShapeKind,
+}
+
+impl AotOutput {
+    /// Convert the raw `f64` written by native code to a `Value`
+    pub fn to_value(&self, raw: f64) -> Value {
+        match self.kind {
+            ShapeKind::Int => Value::Int(raw as i64),
+            ShapeKind::Bool => Value::Bool(raw != 0.0),
+            _ => Value::Float(raw),
+        }
+    }
+}
+
+/// A step of the lowered decision graph (targets are step indices)
+pub(super) enum PlanStep {
+    /// Branches in order (`None` is an unconditional branch), then the default
+    Decision {
+        branches: Vec<(Option<Expr>, usize)>,
+        default: Option<usize>,
+    },
+    /// Continue to another step
+    Goto(usize),
+    /// Return a terminal index
+    Terminal(usize),
+}
+
+/// A ruleset lowered for code generation
+pub(super) struct AotPlan {
+    /// Ruleset name
+    pub(super) name: String,
+    /// Entry point symbol (`ordo_<symbol>_execute`)
+    pub(super) entry_symbol: String,
+    /// Reachable steps; index 0 is the entry step
+    pub(super) steps: Vec<PlanStep>,
+    /// Terminal metadata
+    pub(super) terminals: Vec<AotTerminal>,
+    /// Output expressions per terminal
+    pub(super) outputs: Vec<Vec<Expr>>,
+    /// Every field read, resolved against the schema
+    pub(super) fields: HashMap<String, ResolvedField>,
+    /// Step limit, as enforced by `RuleExecutor`
+    pub(super) max_depth: usize,
+}
+
+impl AotPlan {
+    /// Largest number of outputs of any terminal
+    pub(super) fn max_outputs(&self) -> usize {
+        self.terminals
+            .iter()
+            .map(|t| t.outputs.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Ahead-of-time compiler for rulesets
+///
+/// # Example
+///
+/// ```ignore
+/// let artifact = AotCompiler::new()
+///     .with_symbol("loan")
+///     .compile(&ruleset, &schema)?;
+/// std::fs::write("libloan.a", artifact.static_library())?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AotCompiler {
+    /// Symbol prefix (default: derived from the ruleset name)
+    symbol: Option<String>,
+    /// Target triple (default: the host, including its CPU features)
+    target: Option<String>,
+}
+
+impl AotCompiler {
+    /// Create a compiler for the host
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the symbol prefix, so the entry point is `ordo_<symbol>_execute`
+    pub fn with_symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.symbol = Some(symbol.into());
+        self
+    }
+
+    /// Compile for a target triple (e.g. `x86_64-unknown-linux-gnu`)
+    ///
+    /// An explicit target uses the baseline CPU features of that architecture.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Check whether a ruleset can be compiled against a schema
+    pub fn can_compile(ruleset: &RuleSet, schema: &MessageSchema) -> bool {
+        Self::new().plan(ruleset, schema).is_ok()
+    }
+
+    /// Compile a ruleset into an object file, header and Rust wrapper
+    pub fn compile(&self, ruleset: &RuleSet, schema: &MessageSchema) -> Result<AotArtifact> {
+        let plan = self.plan(ruleset, schema)?;
+
+        let builder = ObjectBuilder::new(
+            self.object_isa()?,
+            plan.entry_symbol.as_bytes().to_vec(),
+            cranelift_module::default_libcall_names(),
+        )
+        .map_err(|e| OrdoError::eval_error(format!("Failed to create object module: {}", e)))?;
+        let mut module = ObjectModule::new(builder);
+        define_execute(&mut module, &plan, Linkage::Export)?;
+        let object = module
+            .finish()
+            .emit()
+            .map_err(|e| OrdoError::eval_error(format!("Failed to emit object: {}", e)))?;
+
+        AotArtifact::new(&plan, schema, object)
+    }
+
+    /// Compile a ruleset and load the same code into this process
+    ///
+    /// This runs exactly what [`compile`](Self::compile) emits, which makes it
+    /// the reference for checking native code against the interpreter.
+    pub fn load(&self, ruleset: &RuleSet, schema: Arc<MessageSchema>) -> Result<AotRuleSet> {
+        let plan = self.plan(ruleset, &schema)?;
+
+        let mut flags = settings::builder();
+        set_flag(&mut flags, "use_colocated_libcalls", "false")?;
+        set_flag(&mut flags, "is_pic", "false")?;
+        let isa = cranelift_native::builder()
+            .map_err(|e| OrdoError::eval_error(format!("Failed to create ISA builder: {}", e)))?
+            .finish(settings::Flags::new(flags))
+            .map_err(|e| OrdoError::eval_error(format!("Failed to create ISA: {}", e)))?;
+
+        let mut module = JITModule::new(JITBuilder::with_isa(
+            isa,
+            cranelift_module::default_libcall_names(),
+        ));
+        let func_id = define_execute(&mut module, &plan, Linkage::Local)?;
+        module
+            .finalize_definitions()
+            .map_err(|e| OrdoError::eval_error(format!("Failed to finalize definitions: {}", e)))?;
+        let func_ptr = module.get_finalized_function(func_id);
+
+        Ok(AotRuleSet::new(
+            module,
+            func_ptr,
+            plan,
+            ruleset.clone(),
+            schema,
+        ))
+    }
+
+    /// ISA for object emission: position-independent, optimized for speed
+    fn object_isa(&self) -> Result<OwnedTargetIsa> {
+        let mut flags = settings::builder();
+        set_flag(&mut flags, "is_pic", "true")?;
+        set_flag(&mut flags, "opt_level", "speed")?;
+
+        let isa_builder = match &self.target {
+            Some(target) => cranelift_codegen::isa::lookup_by_name(target).map_err(|e| {
+                OrdoError::eval_error(format!("Unsupported target '{}': {}", target, e))
+            })?,
+            None => cranelift_native::builder().map_err(|e| {
+                OrdoError::eval_error(format!("Failed to create ISA builder: {}", e))
+            })?,
+        };
+        isa_builder
+            .finish(settings::Flags::new(flags))
+            .map_err(|e| OrdoError::eval_error(format!("Failed to create ISA: {}", e)))
+    }
+
+    /// Validate a ruleset and lower it to an indexed decision graph
+    pub(super) fn plan(&self, ruleset: &RuleSet, schema: &MessageSchema) -> Result<AotPlan> {
+        let name = &ruleset.config.name;
+        let symbol = sanitize_symbol(self.symbol.as_deref().unwrap_or(name));
+        let unsupported =
+            |what: String| OrdoError::eval_error(format!("RuleSet '{}': {}", name, what));
+
+        // Number steps in the order they are reached from the entry step
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut order: Vec<&str> = Vec::new();
+        let mut queue = VecDeque::from([ruleset.config.entry_step.as_str()]);
+        while let Some(id) = queue.pop_front() {
+            if index.contains_key(id) {
+                continue;
+            }
+            let step = ruleset
+                .get_step(id)
+                .ok_or_else(|| OrdoError::StepNotFound {
+                    step_id: id.to_string(),
+                })?;
+            index.insert(id, order.len());
+            order.push(id);
+            match &step.kind {
+                StepKind::Decision {
+                    branches,
+                    default_next,
+                } => {
+                    queue.extend(branches.iter().map(|b| b.next_step.as_str()));
+                    queue.extend(default_next.as_deref());
+                }
+                StepKind::Action { next_step, .. } => queue.push_back(next_step),
+                StepKind::Terminal { .. } => {}
+            }
+        }
+
+        let mut fields = HashMap::new();
+        let mut check = |expr: &Expr, what: &str| -> Result<ShapeKind> {
+            let kind = expr_kind(expr, schema)
+                .ok_or_else(|| unsupported(format!("{} cannot be compiled natively", what)))?;
+            let mut paths = Vec::new();
+            SchemaJITCompiler::collect_field_accesses(expr, &mut paths);
+            for path in paths {
+                if let Some(resolved) = schema.resolve_field_path(&path) {
+                    fields.insert(path, resolved);
+                }
+            }
+            Ok(kind)
+        };
+
+        let mut steps = Vec::with_capacity(order.len());
+        let mut terminals = Vec::new();
+        let mut outputs = Vec::new();
+        for id in &order {
+            let step = &ruleset.steps[*id];
+            let plan_step = match &step.kind {
+                StepKind::Decision {
+                    branches,
+                    default_next,
+                } => {
+                    let mut lowered = Vec::with_capacity(branches.len());
+                    for (i, branch) in branches.iter().enumerate() {
+                        if !branch.actions.is_empty() {
+                            return Err(unsupported(format!(
+                                "branch {} of step '{}' has actions",
+                                i, step.id
+                            )));
+                        }
+                        let expr = match &branch.condition {
+                            Condition::Always => None,
+                            Condition::Expression(expr) => Some(expr.clone()),
+                            Condition::ExpressionString(s) => Some(ExprParser::parse(s)?),
+                        };
+                        if let Some(expr) = &expr {
+                            check(expr, &format!("condition {} of step '{}'", i, step.id))?;
+                        }
+                        lowered.push((expr, index[branch.next_step.as_str()]));
+                    }
+                    PlanStep::Decision {
+                        branches: lowered,
+                        default: default_next.as_deref().map(|next| index[next]),
+                    }
+                }
+                StepKind::Action { actions, next_step } => {
+                    if !actions.is_empty() {
+                        return Err(unsupported(format!("step '{}' has actions", step.id)));
+                    }
+                    PlanStep::Goto(index[next_step.as_str()])
+                }
+                StepKind::Terminal { result } => {
+                    let mut terminal_outputs = Vec::with_capacity(result.output.len());
+                    for (key, expr) in &result.output {
+                        let kind = check(expr, &format!("output '{}' of step '{}'", key, step.id))?;
+                        terminal_outputs.push(AotOutput {
+                            name: key.clone(),
+                            kind,
+                        });
+                    }
+                    outputs.push(result.output.iter().map(|(_, e)| e.clone()).collect());
+                    terminals.push(AotTerminal {
+                        step_id: step.id.clone(),
+                        code: result.code.clone(),
+                        message: result.message.clone(),
+                        outputs: terminal_outputs,
+                        data: result.data.clone(),
+                    });
+                    PlanStep::Terminal(terminals.len() - 1)
+                }
+            };
+            steps.push(plan_step);
+        }
+
+        Ok(AotPlan {
+            name: name.clone(),
+            entry_symbol: format!("ordo_{}_execute", symbol),
+            steps,
+            terminals,
+            outputs,
+            fields,
+            max_depth: ruleset.config.max_depth,
+        })
+    }
+}
+
+/// Kind of a field as native code sees it, or `None` if it is not a scalar
+pub(super) fn field_kind(field_type: &FieldType) -> Option<ShapeKind> {
+    match field_type {
+        FieldType::Bool => Some(ShapeKind::Bool),
+        FieldType::Float32 | FieldType::Float64 => Some(ShapeKind::Float),
+        FieldType::Int32
+        | FieldType::Int64
+        | FieldType::UInt32
+        | FieldType::UInt64
+        | FieldType::Enum(_) => Some(ShapeKind::Int),
+        _ => None,
+    }
+}
+
+/// Kind of an expression if native code evaluates it exactly like the interpreter
+fn expr_kind(expr: &Expr, schema: &MessageSchema) -> Option<ShapeKind> {
+    let kind = ShapeKind::of_expr(expr, &|path| {
+        field_kind(&schema.resolve_field_path(path)?.field_type)
+    })?;
+    (kind != ShapeKind::String && SchemaJITCompiler::can_compile_with_schema(expr, schema))
+        .then_some(kind)
+}
+
+/// Turn a ruleset name into a C identifier fragment
+fn sanitize_symbol(name: &str) -> String {
+    let mut symbol: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if symbol.is_empty() || symbol.starts_with(|c: char| c.is_ascii_digit()) {
+        symbol.insert(0, '_');
+    }
+    symbol
+}
+
+fn set_flag(flags: &mut settings::Builder, name: &str, value: &str) -> Result<()> {
+    flags
+        .set(name, value)
+        .map_err(|e| OrdoError::eval_error(format!("Failed to set codegen flag: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{Action, Step, TerminalResult};
+
+    fn schema() -> MessageSchema {
+        MessageSchema::builder("Order")
+            .field("amount", FieldType::Float64)
+            .field("count", FieldType::Int32)
+            .field("country", FieldType::String)
+            .build()
+    }
+
+    fn ruleset(condition: &str) -> RuleSet {
+        let mut ruleset = RuleSet::new("orders", "check");
+        ruleset.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string(condition), "yes")
+                .default("no")
+                .build(),
+        );
+        ruleset.add_step(Step::terminal("yes", "Yes", TerminalResult::new("YES")));
+        ruleset.add_step(Step::terminal("no", "No", TerminalResult::new("NO")));
+        ruleset
+    }
+
+    #[test]
+    fn test_plan_numbers_reachable_steps() {
+        let mut rules = ruleset("amount > 10.0 && count >= 2");
+        rules.add_step(Step::terminal("orphan", "Orphan", TerminalResult::new("X")));
+
+        let plan = AotCompiler::new().plan(&rules, &schema()).unwrap();
+        assert_eq!(plan.steps.len(), 3);
+        assert_eq!(plan.entry_symbol, "ordo_orders_execute");
+        let codes: Vec<&str> = plan.terminals.iter().map(|t| t.code.as_str()).collect();
+        assert_eq!(codes, ["YES", "NO"]);
+        assert_eq!(plan.fields.len(), 2);
+    }
+
+    #[test]
+    fn test_rejects_what_native_code_cannot_match() {
+        let schema = schema();
+        // Strings need runtime helpers
+        assert!(!AotCompiler::can_compile(
+            &ruleset(r#"country == "US""#),
+            &schema
+        ));
+        // Integer arithmetic is not exact in f64
+        assert!(!AotCompiler::can_compile(
+            &ruleset("count * 2 > 5"),
+            &schema
+        ));
+        // Fields outside the schema
+        assert!(!AotCompiler::can_compile(&ruleset("missing > 1"), &schema));
+        // Actions need the runtime
+        let mut rules = ruleset("amount > 1.0");
+        rules.add_step(
+            Step::decision("check", "Check")
+                .branch_with_actions(
+                    Condition::from_string("amount > 1.0"),
+                    "yes",
+                    vec![Action::set_var("flag", Expr::Literal(Value::Bool(true)))],
+                )
+                .default("no")
+                .build(),
+        );
+        assert!(!AotCompiler::can_compile(&rules, &schema));
+    }
+
+    #[test]
+    fn test_sanitize_symbol() {
+        assert_eq!(sanitize_symbol("Loan-Approval v2"), "loan_approval_v2");
+        assert_eq!(sanitize_symbol("2fa"), "_2fa");
+    }
+}
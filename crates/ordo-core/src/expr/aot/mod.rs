@@ -0,0 +1,296 @@
+//! Ahead-of-Time Compilation of RuleSets
+//!
+//! Compiles a [`RuleSet`](crate::rule::RuleSet) against a [`MessageSchema`](crate::context::MessageSchema)
+//! into a relocatable object or static library with a C ABI entry point, for
+//! services that cannot start a JIT or map executable memory at runtime.
+//!
+//! # Architecture
+//!
+//! ```text
+//! RuleSet + Schema → AotCompiler → AotArtifact
+//!                                    ├── object / static library   (ordo_<name>_execute)
+//!                                    ├── C header                  (context struct, terminal table)
+//!                                    └── Rust wrapper              (extern block, safe entry point)
+//! ```
+//!
+//! Conditions are compiled by the same code generator as the Schema JIT, and
+//! the decision graph becomes native control flow:
+//!
+//! ```c
+//! int32_t ordo_<name>_execute(const ordo_<name>_ctx *ctx, double *outputs);
+//! ```
+//!
+//! The function returns the index of the terminal step that was reached and
+//! writes that terminal's output values to `outputs`, or returns a negative
+//! [`AotErrorCode`].
+//!
+//! # Supported RuleSets
+//!
+//! Object code cannot call back into the engine, so a ruleset is only accepted
+//! when native code gives exactly the result of [`RuleExecutor`](crate::rule::RuleExecutor):
+//!
+//! - Fields read by conditions and outputs are numeric or boolean
+//! - Conditions and outputs evaluate identically in `f64` (no strings, no
+//!   integer arithmetic, no function calls beyond the JIT's math functions)
+//! - Steps carry no actions, since variables, metrics, logs and sub-rulesets
+//!   need the runtime
+//!
+//! 64-bit integer inputs beyond ±2^53 make the entry point return
+//! [`AotErrorCode::OutOfRange`]; [`AotRuleSet`] then falls back to the interpreter.
+//!
+//! # Usage
+//!
+//! ```ignore
+//! use ordo_core::expr::aot::AotCompiler;
+//!
+//! let artifact = AotCompiler::new().compile(&ruleset, &schema)?;
+//! std::fs::write("loan.o", &artifact.object)?;
+//! std::fs::write("loan.h", &artifact.header)?;
+//!
+//! // The same code, loaded in-process
+//! let native = AotCompiler::new().load(&ruleset, Arc::new(schema))?;
+//! let result = unsafe { native.execute_ptr(&ctx as *const _ as *const u8)? };
+//! ```
+
+mod artifact;
+mod codegen;
+mod compiler;
+mod runtime;
+
+pub use artifact::AotArtifact;
+pub use compiler::{AotCompiler, AotOutput, AotTerminal};
+pub use runtime::AotRuleSet;
+
+/// Negative status codes returned by an ahead-of-time entry point
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AotErrorCode {
+    /// A decision step matched no branch and has no default
+    NoMatch = -1,
+    /// The step limit (`max_depth`) was reached
+    MaxDepthExceeded = -2,
+    /// A 64-bit integer input cannot be represented exactly; use the interpreter
+    OutOfRange = -3,
+}
+
+impl AotErrorCode {
+    /// Convert a negative return value to an error code
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            -1 => Some(Self::NoMatch),
+            -2 => Some(Self::MaxDepthExceeded),
+            -3 => Some(Self::OutOfRange),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{FieldType, MessageSchema, Value};
+    use crate::expr::TypedContext;
+    use crate::rule::{Condition, RuleExecutor, RuleSet, Step, TerminalResult};
+    use std::mem::offset_of;
+    use std::sync::{Arc, OnceLock};
+
+    #[repr(C)]
+    struct Loan {
+        amount: f64,
+        score: i64,
+        months: i32,
+        rate: f32,
+        vip: bool,
+    }
+
+    impl TypedContext for Loan {
+        fn schema() -> &'static MessageSchema {
+            static SCHEMA: OnceLock<MessageSchema> = OnceLock::new();
+            SCHEMA.get_or_init(|| {
+                MessageSchema::builder("Loan")
+                    .field_at("amount", FieldType::Float64, offset_of!(Loan, amount))
+                    .field_at("score", FieldType::Int64, offset_of!(Loan, score))
+                    .field_at("months", FieldType::Int32, offset_of!(Loan, months))
+                    .field_at("rate", FieldType::Float32, offset_of!(Loan, rate))
+                    .field_at("vip", FieldType::Bool, offset_of!(Loan, vip))
+                    .build()
+            })
+        }
+
+        unsafe fn field_ptr(&self, field_name: &str) -> Option<(*const u8, FieldType)> {
+            let resolved = Self::schema().resolve_field_path(field_name)?;
+            Some((
+                (self as *const Self as *const u8).add(resolved.offset),
+                resolved.field_type,
+            ))
+        }
+    }
+
+    fn expr(s: &str) -> crate::expr::Expr {
+        crate::expr::ExprParser::parse(s).unwrap()
+    }
+
+    fn loan_rules() -> RuleSet {
+        let mut ruleset = RuleSet::new("loans", "vip_check");
+        ruleset.add_step(
+            Step::decision("vip_check", "VIP")
+                .branch(
+                    Condition::from_string("vip && amount < 50000.0"),
+                    "approve_vip",
+                )
+                .branch(
+                    Condition::from_string("score > 700 && months <= 36"),
+                    "tier",
+                )
+                .default("review")
+                .build(),
+        );
+        ruleset.add_step(Step::action("tier", "Tier", vec![], "rate_check"));
+        ruleset.add_step(
+            Step::decision("rate_check", "Rate")
+                .branch(
+                    Condition::from_string("rate < 0.05 || amount * 1.5 > 100000.0"),
+                    "approve",
+                )
+                .branch(
+                    Condition::from_string("if score >= 800 then amount > 1000.0 else false"),
+                    "approve",
+                )
+                .default("reject")
+                .build(),
+        );
+        ruleset.add_step(Step::terminal(
+            "approve_vip",
+            "Approve VIP",
+            TerminalResult::new("APPROVED")
+                .with_message("vip")
+                .with_output("limit", expr("amount * 2.0"))
+                .with_output("vip", expr("vip")),
+        ));
+        ruleset.add_step(Step::terminal(
+            "approve",
+            "Approve",
+            TerminalResult::new("APPROVED")
+                .with_output("score", expr("score"))
+                .with_output("monthly", expr("amount * 0.1 + months")),
+        ));
+        ruleset.add_step(Step::terminal(
+            "review",
+            "Review",
+            TerminalResult::new("REVIEW").with_data(Value::object(
+                [("queue".to_string(), Value::string("manual"))].into(),
+            )),
+        ));
+        ruleset.add_step(Step::terminal(
+            "reject",
+            "Reject",
+            TerminalResult::new("REJECTED").with_message("rate too high"),
+        ));
+        ruleset.compile().unwrap();
+        ruleset
+    }
+
+    fn schema() -> Arc<MessageSchema> {
+        Arc::new(Loan::schema().clone())
+    }
+
+    /// Native results must match the interpreter on every input, including
+    /// the ones native code hands back (out-of-range integers)
+    #[test]
+    fn test_matches_rule_executor() {
+        let ruleset = loan_rules();
+        let native = AotCompiler::new().load(&ruleset, schema()).unwrap();
+        let executor = RuleExecutor::new();
+
+        let mut checked = 0;
+        for amount in [0.0, 1000.5, 49_999.0, 80_000.0] {
+            for score in [600, 750, 800, 1 << 60] {
+                for months in [12, 48] {
+                    for rate in [0.03, 0.09] {
+                        for vip in [false, true] {
+                            let loan = Loan {
+                                amount,
+                                score,
+                                months,
+                                rate,
+                                vip,
+                            };
+                            let input =
+                                unsafe { native.read_input(&loan as *const _ as *const u8) };
+                            let expected = executor.execute(&ruleset, input).unwrap();
+                            let actual = native.execute(&loan).unwrap();
+                            assert_eq!(actual.code, expected.code);
+                            assert_eq!(actual.message, expected.message);
+                            assert_eq!(actual.output, expected.output, "amount={amount} score={score} months={months} rate={rate} vip={vip}");
+                            checked += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(checked, 128);
+    }
+
+    #[test]
+    fn test_errors_match_rule_executor() {
+        let mut ruleset = RuleSet::new("loop", "check");
+        ruleset.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string("amount > 0.0"), "again")
+                .branch(Condition::from_string("amount < -10.0"), "done")
+                .build(),
+        );
+        ruleset.add_step(Step::action("again", "Again", vec![], "check"));
+        ruleset.add_step(Step::terminal("done", "Done", TerminalResult::new("DONE")));
+        ruleset.config.max_depth = 10;
+        ruleset.compile().unwrap();
+
+        let native = AotCompiler::new().load(&ruleset, schema()).unwrap();
+        let executor = RuleExecutor::new();
+        for amount in [1.0, -1.0, -20.0] {
+            let loan = Loan {
+                amount,
+                score: 0,
+                months: 0,
+                rate: 0.0,
+                vip: false,
+            };
+            let input = unsafe { native.read_input(&loan as *const _ as *const u8) };
+            let expected = executor.execute(&ruleset, input).map(|r| r.code);
+            let actual = native.execute(&loan).map(|r| r.code);
+            match (expected, actual) {
+                (Ok(e), Ok(a)) => assert_eq!(e, a),
+                (Err(e), Err(a)) => assert_eq!(e.to_string(), a.to_string()),
+                (e, a) => panic!("amount={amount}: expected {e:?}, got {a:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_compile_emits_object_header_and_wrapper() {
+        let artifact = AotCompiler::new()
+            .compile(&loan_rules(), Loan::schema())
+            .unwrap();
+
+        assert_eq!(artifact.entry_symbol, "ordo_loans_execute");
+        assert_eq!(artifact.terminals.len(), 4);
+        assert_eq!(artifact.max_outputs, 2);
+        let contains =
+            |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(&artifact.object, b"ordo_loans_execute"));
+
+        let library = artifact.static_library();
+        assert!(library.starts_with(b"!<arch>\n/ "));
+        assert!(contains(&library, b"ordo_loans_execute\0"));
+        assert_eq!(library.len() % 2, 0);
+
+        assert!(artifact
+            .header
+            .contains("int32_t ordo_loans_execute(const ordo_loans_ctx *ctx, double *outputs);"));
+        assert!(artifact.header.contains("    int64_t score;"));
+        assert!(artifact.rust.contains("pub score: i64,"));
+        assert!(artifact
+            .rust
+            .contains("fn ordo_loans_execute(ctx: *const Context, outputs: *mut f64) -> i32;"));
+    }
+}
@@ -0,0 +1,168 @@
+//! In-process execution of ahead-of-time compiled code
+
+use super::compiler::{AotPlan, AotTerminal};
+use super::AotErrorCode;
+use crate::context::{FieldType, IString, MessageSchema, Value};
+use crate::error::{OrdoError, Result};
+use crate::expr::jit::read_value_at;
+use crate::expr::TypedContext;
+use crate::rule::{ExecutionResult, RuleExecutor, RuleSet};
+
+use cranelift_jit::JITModule;
+use std::sync::Arc;
+use std::time::Instant;
+
+type EntryPoint = unsafe extern "C" fn(*const u8, *mut f64) -> i32;
+
+/// A ruleset compiled ahead of time and loaded into this process
+///
+/// Runs the code [`AotCompiler::compile`](super::AotCompiler::compile) writes
+/// to an object file and turns its result into an [`ExecutionResult`], so it
+/// can be compared with [`RuleExecutor`] directly. Inputs the native code
+/// rejects as out of range, or that match no branch, are handed to the
+/// interpreter.
+pub struct AotRuleSet {
+    /// Keeps the code alive
+    _module: JITModule,
+    entry: EntryPoint,
+    terminals: Vec<AotTerminal>,
+    max_outputs: usize,
+    max_depth: usize,
+    schema: Arc<MessageSchema>,
+    ruleset: RuleSet,
+    fallback: RuleExecutor,
+}
+
+impl AotRuleSet {
+    pub(super) fn new(
+        module: JITModule,
+        func_ptr: *const u8,
+        plan: AotPlan,
+        ruleset: RuleSet,
+        schema: Arc<MessageSchema>,
+    ) -> Self {
+        Self {
+            _module: module,
+            // Safety: `define_execute` declared the function with this signature
+            entry: unsafe { std::mem::transmute::<*const u8, EntryPoint>(func_ptr) },
+            max_outputs: plan.max_outputs(),
+            terminals: plan.terminals,
+            max_depth: plan.max_depth,
+            schema,
+            ruleset,
+            fallback: RuleExecutor::new(),
+        }
+    }
+
+    /// Terminal steps, indexed by the entry point's return value
+    pub fn terminals(&self) -> &[AotTerminal] {
+        &self.terminals
+    }
+
+    /// Schema the code was compiled for
+    pub fn schema(&self) -> &MessageSchema {
+        &self.schema
+    }
+
+    /// Call the entry point, returning its raw result and writing outputs
+    ///
+    /// # Safety
+    ///
+    /// `ctx_ptr` must point to memory laid out as described by the schema.
+    pub unsafe fn call_raw(&self, ctx_ptr: *const u8, outputs: &mut [f64]) -> i32 {
+        assert!(
+            outputs.len() >= self.max_outputs,
+            "outputs buffer too small"
+        );
+        (self.entry)(ctx_ptr, outputs.as_mut_ptr())
+    }
+
+    /// Execute against a typed context
+    ///
+    /// The context type must use the schema this ruleset was compiled for.
+    pub fn execute<T: TypedContext>(&self, ctx: &T) -> Result<ExecutionResult> {
+        let schema = T::schema();
+        if schema.name != self.schema.name || schema.struct_size != self.schema.struct_size {
+            return Err(OrdoError::eval_error(format!(
+                "Context '{}' does not match compiled schema '{}'",
+                schema.name, self.schema.name
+            )));
+        }
+        // Safety: the layout was checked above
+        unsafe { self.execute_ptr(ctx as *const T as *const u8) }
+    }
+
+    /// Execute against a raw context pointer
+    ///
+    /// # Safety
+    ///
+    /// `ctx_ptr` must point to memory laid out as described by the schema.
+    pub unsafe fn execute_ptr(&self, ctx_ptr: *const u8) -> Result<ExecutionResult> {
+        let start = Instant::now();
+        let mut outputs = vec![0.0; self.max_outputs];
+        let code = self.call_raw(ctx_ptr, &mut outputs);
+
+        let terminal = match usize::try_from(code) {
+            Ok(index) => &self.terminals[index],
+            Err(_) => {
+                return match AotErrorCode::from_code(code) {
+                    Some(AotErrorCode::MaxDepthExceeded) => Err(OrdoError::MaxDepthExceeded {
+                        max_depth: self.max_depth,
+                    }),
+                    // Rare paths: let the interpreter produce the exact result or error
+                    _ => self
+                        .fallback
+                        .execute(&self.ruleset, self.read_input(ctx_ptr)),
+                };
+            }
+        };
+
+        let data_len = match &terminal.data {
+            Value::Object(map) => map.len(),
+            _ => 0,
+        };
+        let mut output: hashbrown::HashMap<IString, Value> =
+            hashbrown::HashMap::with_capacity(terminal.outputs.len() + data_len);
+        for (spec, raw) in terminal.outputs.iter().zip(&outputs) {
+            output.insert(Arc::from(spec.name.as_str()), spec.to_value(*raw));
+        }
+        if let Value::Object(data) = &terminal.data {
+            for (k, v) in data {
+                output.insert(k.clone(), v.clone());
+            }
+        }
+
+        Ok(ExecutionResult {
+            code: terminal.code.clone(),
+            message: terminal.message.clone(),
+            output: Value::object_optimized(output),
+            trace: None,
+            duration_us: start.elapsed().as_micros() as u64,
+        })
+    }
+
+    /// Read the scalar fields of a context into the input `RuleExecutor` would see
+    ///
+    /// # Safety
+    ///
+    /// `ctx_ptr` must point to memory laid out as described by the schema.
+    pub unsafe fn read_input(&self, ctx_ptr: *const u8) -> Value {
+        read_message(ctx_ptr, &self.schema)
+    }
+}
+
+unsafe fn read_message(ptr: *const u8, schema: &MessageSchema) -> Value {
+    let mut fields = hashbrown::HashMap::with_capacity(schema.fields.len());
+    for field in &schema.fields {
+        let field_ptr = ptr.add(field.offset);
+        let value = match &field.field_type {
+            FieldType::Message(nested) => Some(read_message(field_ptr, nested)),
+            t if t.is_primitive() => read_value_at(field_ptr, t),
+            _ => None,
+        };
+        if let Some(value) = value {
+            fields.insert(Arc::from(field.name.as_str()), value);
+        }
+    }
+    Value::object_optimized(fields)
+}
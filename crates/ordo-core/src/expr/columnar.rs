@@ -0,0 +1,861 @@
+//! Columnar expression evaluation over Arrow record batches
+//!
+//! [`ColumnarEvaluator`] evaluates an expression for a selection of rows of a
+//! [`RecordBatch`] one column at a time instead of one row at a time:
+//!
+//! - Numeric, boolean and string columns are gathered into dense vectors
+//! - Arithmetic and comparisons run as branch-free loops over those vectors,
+//!   which the compiler lowers to SIMD instructions
+//! - Conditions produce selection bitmaps ([`BooleanBuffer`])
+//!
+//! Results are exactly those of [`Evaluator`] on the row converted with
+//! [`row_value`]. Rows the vector kernels cannot decide (nulls, missing nested
+//! fields, integer overflow, division by zero, NaN in comparisons) are marked
+//! and re-evaluated by the interpreter, as are expressions with no vector form
+//! (function calls, `coalesce`, variables).
+//!
+//! # Type mapping
+//!
+//! | Arrow                          | Value                                           |
+//! |--------------------------------|-------------------------------------------------|
+//! | Null, null entries             | `Null`                                          |
+//! | Boolean                        | `Bool`                                          |
+//! | Int8-64, UInt8-32              | `Int`                                           |
+//! | UInt64                         | `Int`, `Float` above `i64::MAX` (as JSON input) |
+//! | Float32, Float64               | `Float`                                         |
+//! | Utf8, LargeUtf8                | `String`                                        |
+//! | List, LargeList                | `Array`                                         |
+//! | Struct                         | `Object`                                        |
+
+use super::ast::{BinaryOp, Expr, UnaryOp};
+use super::eval::Evaluator;
+use super::functions::FunctionRegistry;
+use crate::context::{Context, IString, Value};
+use crate::error::{OrdoError, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{Array, ArrowPrimitiveType, RecordBatch};
+use arrow_buffer::{BooleanBuffer, BooleanBufferBuilder};
+use arrow_schema::{DataType, Schema};
+use std::collections::HashSet;
+
+/// Result of a condition over a selection of rows
+#[derive(Debug, Clone)]
+pub struct ConditionMask {
+    /// Rows (positions in the selection) where the condition is truthy
+    pub matched: BooleanBuffer,
+    /// Rows where evaluation failed, with the interpreter's error
+    pub errors: Vec<(usize, OrdoError)>,
+}
+
+/// Column-at-a-time expression evaluator for Arrow record batches
+pub struct ColumnarEvaluator {
+    /// Interpreter for rows and expressions without a vector form
+    evaluator: Evaluator,
+}
+
+impl Default for ColumnarEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColumnarEvaluator {
+    /// Create a new columnar evaluator
+    pub fn new() -> Self {
+        Self {
+            evaluator: Evaluator::new(),
+        }
+    }
+
+    /// Create a columnar evaluator with custom functions
+    pub fn with_functions(functions: FunctionRegistry) -> Self {
+        Self {
+            evaluator: Evaluator::with_functions(functions),
+        }
+    }
+
+    /// Check whether an expression has a vector form for this batch
+    ///
+    /// Expressions without one are still evaluated, row by row.
+    pub fn can_vectorize(&self, expr: &Expr, batch: &RecordBatch) -> bool {
+        Lanes::new(batch, &[]).eval(expr).is_some()
+    }
+
+    /// Evaluate a condition for the selected rows of a batch
+    ///
+    /// `rows` are row indices into `batch`; the mask and errors are indexed by
+    /// position in `rows`.
+    pub fn eval_condition(&self, expr: &Expr, batch: &RecordBatch, rows: &[u32]) -> ConditionMask {
+        let n = rows.len();
+        let mut errors = Vec::new();
+        let Some(lane) = Lanes::new(batch, rows).eval(expr) else {
+            let matched =
+                BooleanBuffer::collect_bool(n, |i| match self.eval_row(expr, batch, rows[i]) {
+                    Ok(value) => value.is_truthy(),
+                    Err(e) => {
+                        errors.push((i, e));
+                        false
+                    }
+                });
+            return ConditionMask { matched, errors };
+        };
+
+        let truthy = lane.values.truthy();
+        if lane.slow.count_set_bits() == 0 {
+            return ConditionMask {
+                matched: truthy,
+                errors,
+            };
+        }
+        let mut matched = BooleanBufferBuilder::new(n);
+        matched.append_buffer(&(&truthy & &!&lane.slow));
+        for i in lane.slow.set_indices() {
+            match self.eval_row(expr, batch, rows[i]) {
+                Ok(value) => matched.set_bit(i, value.is_truthy()),
+                Err(e) => errors.push((i, e)),
+            }
+        }
+        ConditionMask {
+            matched: matched.finish(),
+            errors,
+        }
+    }
+
+    /// Evaluate an expression for the selected rows of a batch
+    pub fn eval_values(
+        &self,
+        expr: &Expr,
+        batch: &RecordBatch,
+        rows: &[u32],
+    ) -> Vec<Result<Value>> {
+        match Lanes::new(batch, rows).eval(expr) {
+            Some(lane) => (0..rows.len())
+                .map(|i| {
+                    if lane.slow.value(i) {
+                        self.eval_row(expr, batch, rows[i])
+                    } else {
+                        Ok(lane.values.value(i))
+                    }
+                })
+                .collect(),
+            None => rows
+                .iter()
+                .map(|&row| self.eval_row(expr, batch, row))
+                .collect(),
+        }
+    }
+
+    /// Evaluate an expression for one row with the interpreter
+    fn eval_row(&self, expr: &Expr, batch: &RecordBatch, row: u32) -> Result<Value> {
+        let ctx = Context::new(row_value(batch, row as usize)?);
+        self.evaluator.eval(expr, &ctx)
+    }
+}
+
+/// Check that every column of a schema can be converted to [`Value`]s
+pub fn check_schema(schema: &Schema) -> Result<()> {
+    for field in schema.fields() {
+        check_type(field.name(), field.data_type())?;
+    }
+    Ok(())
+}
+
+fn check_type(name: &str, data_type: &DataType) -> Result<()> {
+    match data_type {
+        DataType::Null
+        | DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Utf8
+        | DataType::LargeUtf8 => Ok(()),
+        DataType::List(item) | DataType::LargeList(item) => check_type(name, item.data_type()),
+        DataType::Struct(fields) => {
+            for field in fields {
+                check_type(&format!("{}.{}", name, field.name()), field.data_type())?;
+            }
+            Ok(())
+        }
+        other => Err(OrdoError::type_error(
+            "Arrow column convertible to a value",
+            format!("{} for column '{}'", other, name),
+        )),
+    }
+}
+
+/// Convert one row of a batch to the object input [`RuleExecutor`](crate::rule::RuleExecutor) takes
+pub fn row_value(batch: &RecordBatch, row: usize) -> Result<Value> {
+    let schema = batch.schema_ref();
+    let mut fields = hashbrown::HashMap::with_capacity(schema.fields().len());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        fields.insert(
+            IString::from(field.name().as_str()),
+            array_value(column.as_ref(), row)?,
+        );
+    }
+    Ok(Value::object_optimized(fields))
+}
+
+fn array_value(array: &dyn Array, i: usize) -> Result<Value> {
+    if array.is_null(i) {
+        return Ok(Value::Null);
+    }
+    Ok(match array.data_type() {
+        DataType::Null => Value::Null,
+        DataType::Boolean => Value::Bool(array.as_boolean().value(i)),
+        DataType::Int8 => Value::Int(array.as_primitive::<Int8Type>().value(i) as i64),
+        DataType::Int16 => Value::Int(array.as_primitive::<Int16Type>().value(i) as i64),
+        DataType::Int32 => Value::Int(array.as_primitive::<Int32Type>().value(i) as i64),
+        DataType::Int64 => Value::Int(array.as_primitive::<Int64Type>().value(i)),
+        DataType::UInt8 => Value::Int(array.as_primitive::<UInt8Type>().value(i) as i64),
+        DataType::UInt16 => Value::Int(array.as_primitive::<UInt16Type>().value(i) as i64),
+        DataType::UInt32 => Value::Int(array.as_primitive::<UInt32Type>().value(i) as i64),
+        DataType::UInt64 => Value::from_u64(array.as_primitive::<UInt64Type>().value(i)),
+        DataType::Float32 => Value::Float(array.as_primitive::<Float32Type>().value(i) as f64),
+        DataType::Float64 => Value::Float(array.as_primitive::<Float64Type>().value(i)),
+        DataType::Utf8 => Value::string(array.as_string::<i32>().value(i)),
+        DataType::LargeUtf8 => Value::string(array.as_string::<i64>().value(i)),
+        DataType::List(_) => list_value(array.as_list::<i32>().value(i).as_ref())?,
+        DataType::LargeList(_) => list_value(array.as_list::<i64>().value(i).as_ref())?,
+        DataType::Struct(fields) => {
+            let array = array.as_struct();
+            let mut map = hashbrown::HashMap::with_capacity(fields.len());
+            for (field, column) in fields.iter().zip(array.columns()) {
+                map.insert(
+                    IString::from(field.name().as_str()),
+                    array_value(column.as_ref(), i)?,
+                );
+            }
+            Value::object_optimized(map)
+        }
+        other => {
+            return Err(OrdoError::type_error(
+                "Arrow column convertible to a value",
+                other.to_string(),
+            ))
+        }
+    })
+}
+
+fn list_value(items: &dyn Array) -> Result<Value> {
+    (0..items.len())
+        .map(|i| array_value(items, i))
+        .collect::<Result<Vec<_>>>()
+        .map(Value::array)
+}
+
+// ==================== Vector kernels ====================
+
+/// Values of an expression for every selected row
+enum Vector<'a> {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(BooleanBuffer),
+    Str(Vec<&'a str>),
+}
+
+impl Vector<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Int(v) => v.len(),
+            Self::Float(v) => v.len(),
+            Self::Bool(v) => v.len(),
+            Self::Str(v) => v.len(),
+        }
+    }
+
+    /// `Value::is_truthy` for every row
+    fn truthy(&self) -> BooleanBuffer {
+        match self {
+            Self::Int(v) => BooleanBuffer::collect_bool(v.len(), |i| v[i] != 0),
+            Self::Float(v) => BooleanBuffer::collect_bool(v.len(), |i| v[i] != 0.0),
+            Self::Bool(v) => v.clone(),
+            Self::Str(v) => BooleanBuffer::collect_bool(v.len(), |i| !v[i].is_empty()),
+        }
+    }
+
+    fn value(&self, i: usize) -> Value {
+        match self {
+            Self::Int(v) => Value::Int(v[i]),
+            Self::Float(v) => Value::Float(v[i]),
+            Self::Bool(v) => Value::Bool(v.value(i)),
+            Self::Str(v) => Value::string(v[i]),
+        }
+    }
+
+    /// Widen integers for mixed arithmetic and comparison
+    fn into_floats(self) -> Option<Vec<f64>> {
+        match self {
+            Self::Int(v) => Some(v.into_iter().map(|x| x as f64).collect()),
+            Self::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// A vector plus the rows whose value must come from the interpreter
+struct Lane<'a> {
+    values: Vector<'a>,
+    slow: BooleanBuffer,
+}
+
+/// Evaluates expressions into lanes for a row selection
+///
+/// Every method returns `None` when the expression has no vector form, in
+/// which case the caller evaluates it row by row.
+struct Lanes<'a> {
+    batch: &'a RecordBatch,
+    rows: &'a [u32],
+}
+
+impl<'a> Lanes<'a> {
+    fn new(batch: &'a RecordBatch, rows: &'a [u32]) -> Self {
+        Self { batch, rows }
+    }
+
+    fn fast(&self, values: Vector<'a>) -> Lane<'a> {
+        Lane {
+            slow: BooleanBuffer::new_unset(values.len()),
+            values,
+        }
+    }
+
+    fn eval(&self, expr: &'a Expr) -> Option<Lane<'a>> {
+        match expr {
+            Expr::Literal(value) => self.literal(value).map(|v| self.fast(v)),
+            Expr::Field(path) => self.field(path),
+            Expr::Exists(path) => {
+                let (_, parents) = resolve_column(self.batch, path)?;
+                let missing = self.null_rows(&parents);
+                Some(self.fast(Vector::Bool(!&missing)))
+            }
+            Expr::Binary { op, left, right } => self.binary(*op, left, right),
+            Expr::Unary { op, operand } => {
+                let lane = self.eval(operand)?;
+                match op {
+                    UnaryOp::Not => Some(Lane {
+                        values: Vector::Bool(!&lane.values.truthy()),
+                        slow: lane.slow,
+                    }),
+                    UnaryOp::Neg => match lane.values {
+                        Vector::Int(v) => {
+                            let (values, failed) = checked_ints(&v, &v, |x, _| x.checked_neg());
+                            Some(Lane {
+                                values: Vector::Int(values),
+                                slow: &lane.slow | &failed,
+                            })
+                        }
+                        Vector::Float(v) => Some(Lane {
+                            values: Vector::Float(v.into_iter().map(|x| -x).collect()),
+                            slow: lane.slow,
+                        }),
+                        _ => None,
+                    },
+                }
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let cond = self.eval(condition)?;
+                let then_lane = self.eval(then_branch)?;
+                let else_lane = self.eval(else_branch)?;
+                let pick = cond.values.truthy();
+                let slow =
+                    &(&cond.slow | &(&pick & &then_lane.slow)) | &(&!&pick & &else_lane.slow);
+                let n = pick.len();
+                let values = match (then_lane.values, else_lane.values) {
+                    (Vector::Int(a), Vector::Int(b)) => Vector::Int(
+                        (0..n)
+                            .map(|i| if pick.value(i) { a[i] } else { b[i] })
+                            .collect(),
+                    ),
+                    (Vector::Float(a), Vector::Float(b)) => Vector::Float(
+                        (0..n)
+                            .map(|i| if pick.value(i) { a[i] } else { b[i] })
+                            .collect(),
+                    ),
+                    (Vector::Bool(a), Vector::Bool(b)) => {
+                        Vector::Bool(&(&pick & &a) | &(&!&pick & &b))
+                    }
+                    (Vector::Str(a), Vector::Str(b)) => Vector::Str(
+                        (0..n)
+                            .map(|i| if pick.value(i) { a[i] } else { b[i] })
+                            .collect(),
+                    ),
+                    _ => return None,
+                };
+                Some(Lane { values, slow })
+            }
+            Expr::Call { .. } | Expr::Array(_) | Expr::Object(_) | Expr::Coalesce(_) => None,
+        }
+    }
+
+    fn literal(&self, value: &'a Value) -> Option<Vector<'a>> {
+        let n = self.rows.len();
+        Some(match value {
+            Value::Int(v) => Vector::Int(vec![*v; n]),
+            Value::Float(v) => Vector::Float(vec![*v; n]),
+            Value::Bool(true) => Vector::Bool(BooleanBuffer::new_set(n)),
+            Value::Bool(false) => Vector::Bool(BooleanBuffer::new_unset(n)),
+            Value::String(s) => Vector::Str(vec![s.as_ref(); n]),
+            _ => return None,
+        })
+    }
+
+    fn field(&self, path: &str) -> Option<Lane<'a>> {
+        let (array, mut parents) = resolve_column(self.batch, path)?;
+        let rows = self.rows;
+        let n = rows.len();
+        let mut floats = None;
+        let values = match array.data_type() {
+            DataType::Boolean => {
+                let a = array.as_boolean();
+                Vector::Bool(BooleanBuffer::collect_bool(n, |i| {
+                    a.value(rows[i] as usize)
+                }))
+            }
+            DataType::Int8 => Vector::Int(gather::<Int8Type, _>(array, rows, |x| x as i64)),
+            DataType::Int16 => Vector::Int(gather::<Int16Type, _>(array, rows, |x| x as i64)),
+            DataType::Int32 => Vector::Int(gather::<Int32Type, _>(array, rows, |x| x as i64)),
+            DataType::Int64 => Vector::Int(gather::<Int64Type, _>(array, rows, |x| x)),
+            DataType::UInt8 => Vector::Int(gather::<UInt8Type, _>(array, rows, |x| x as i64)),
+            DataType::UInt16 => Vector::Int(gather::<UInt16Type, _>(array, rows, |x| x as i64)),
+            DataType::UInt32 => Vector::Int(gather::<UInt32Type, _>(array, rows, |x| x as i64)),
+            DataType::UInt64 => {
+                // Values above `i64::MAX` are floats for the interpreter
+                let a = array.as_primitive::<UInt64Type>().values();
+                floats = Some(BooleanBuffer::collect_bool(n, |i| {
+                    a[rows[i] as usize] > i64::MAX as u64
+                }));
+                Vector::Int(gather::<UInt64Type, _>(array, rows, |x| x as i64))
+            }
+            DataType::Float32 => Vector::Float(gather::<Float32Type, _>(array, rows, |x| x as f64)),
+            DataType::Float64 => Vector::Float(gather::<Float64Type, _>(array, rows, |x| x)),
+            DataType::Utf8 => {
+                let a = array.as_string::<i32>();
+                Vector::Str(rows.iter().map(|&r| a.value(r as usize)).collect())
+            }
+            DataType::LargeUtf8 => {
+                let a = array.as_string::<i64>();
+                Vector::Str(rows.iter().map(|&r| a.value(r as usize)).collect())
+            }
+            _ => return None,
+        };
+        // Null leaves and missing parents need the interpreter's semantics
+        parents.push(array);
+        let nulls = self.null_rows(&parents);
+        Some(Lane {
+            values,
+            slow: match floats {
+                Some(floats) => &nulls | &floats,
+                None => nulls,
+            },
+        })
+    }
+
+    /// Selected rows where any of the arrays is null
+    fn null_rows(&self, arrays: &[&dyn Array]) -> BooleanBuffer {
+        let rows = self.rows;
+        let mut nulls = BooleanBuffer::new_unset(rows.len());
+        for array in arrays.iter().filter(|a| a.null_count() > 0) {
+            let column =
+                BooleanBuffer::collect_bool(rows.len(), |i| array.is_null(rows[i] as usize));
+            nulls = &nulls | &column;
+        }
+        nulls
+    }
+
+    fn binary(&self, op: BinaryOp, left: &'a Expr, right: &'a Expr) -> Option<Lane<'a>> {
+        match op {
+            BinaryOp::In => return self.membership(left, right, false),
+            BinaryOp::NotIn => return self.membership(left, right, true),
+            BinaryOp::Contains => return self.membership(right, left, false),
+            _ => {}
+        }
+
+        let l = self.eval(left)?;
+        let r = self.eval(right)?;
+        match op {
+            // Short-circuit: the right side only matters (and only fails)
+            // where the left side does not decide the result
+            BinaryOp::And => {
+                let lt = l.values.truthy();
+                let rt = r.values.truthy();
+                Some(Lane {
+                    values: Vector::Bool(&lt & &rt),
+                    slow: &l.slow | &(&lt & &r.slow),
+                })
+            }
+            BinaryOp::Or => {
+                let lt = l.values.truthy();
+                let rt = r.values.truthy();
+                Some(Lane {
+                    values: Vector::Bool(&lt | &rt),
+                    slow: &l.slow | &(&!&lt & &r.slow),
+                })
+            }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                arithmetic(op, l, r)
+            }
+            _ => compare(op, l, r),
+        }
+    }
+
+    /// `value in [literals]`, with the interpreter's strict equality
+    fn membership(&self, value: &'a Expr, collection: &'a Expr, negate: bool) -> Option<Lane<'a>> {
+        let items: Vec<&Value> = match collection {
+            Expr::Array(elements) => elements
+                .iter()
+                .map(|e| match e {
+                    Expr::Literal(v) => Some(v),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?,
+            Expr::Literal(Value::Array(items)) => items.iter().collect(),
+            _ => return None,
+        };
+        let lane = self.eval(value)?;
+        let n = lane.values.len();
+        let hits = match &lane.values {
+            Vector::Int(v) => {
+                let set: HashSet<i64> = items
+                    .iter()
+                    .filter_map(|x| match x {
+                        Value::Int(v) => Some(*v),
+                        _ => None,
+                    })
+                    .collect();
+                BooleanBuffer::collect_bool(n, |i| set.contains(&v[i]))
+            }
+            Vector::Float(v) => {
+                let floats: Vec<f64> = items
+                    .iter()
+                    .filter_map(|x| match x {
+                        Value::Float(f) => Some(*f),
+                        _ => None,
+                    })
+                    .collect();
+                BooleanBuffer::collect_bool(n, |i| floats.contains(&v[i]))
+            }
+            Vector::Bool(v) => {
+                let has_true = items.contains(&&Value::Bool(true));
+                let has_false = items.contains(&&Value::Bool(false));
+                BooleanBuffer::collect_bool(n, |i| if v.value(i) { has_true } else { has_false })
+            }
+            Vector::Str(v) => {
+                let set: HashSet<&str> = items.iter().filter_map(|x| x.as_str()).collect();
+                BooleanBuffer::collect_bool(n, |i| set.contains(v[i]))
+            }
+        };
+        Some(Lane {
+            values: Vector::Bool(if negate { !&hits } else { hits }),
+            slow: lane.slow,
+        })
+    }
+}
+
+/// Resolve a field path to its column and the struct columns above it
+fn resolve_column<'a>(
+    batch: &'a RecordBatch,
+    path: &str,
+) -> Option<(&'a dyn Array, Vec<&'a dyn Array>)> {
+    // Variables and iteration items do not exist in a batch row
+    if path.starts_with('$') || path == "item" || path.starts_with("item.") || path == "_index" {
+        return None;
+    }
+    let path = path.strip_prefix("data.").unwrap_or(path);
+    let mut parts = path.split('.');
+    let mut array: &'a dyn Array = batch.column_by_name(parts.next()?)?.as_ref();
+    let mut parents = Vec::new();
+    for part in parts {
+        let parent = array.as_struct_opt()?;
+        parents.push(array);
+        array = parent.column_by_name(part)?.as_ref();
+    }
+    Some((array, parents))
+}
+
+fn gather<T: ArrowPrimitiveType, U>(
+    array: &dyn Array,
+    rows: &[u32],
+    convert: impl Fn(T::Native) -> U,
+) -> Vec<U> {
+    let values = array.as_primitive::<T>().values();
+    rows.iter().map(|&r| convert(values[r as usize])).collect()
+}
+
+/// Apply a checked integer operation, reporting the rows it failed for
+fn checked_ints(
+    a: &[i64],
+    b: &[i64],
+    op: impl Fn(i64, i64) -> Option<i64>,
+) -> (Vec<i64>, BooleanBuffer) {
+    let results: Vec<Option<i64>> = a.iter().zip(b).map(|(&x, &y)| op(x, y)).collect();
+    let failed = BooleanBuffer::collect_bool(results.len(), |i| results[i].is_none());
+    (
+        results.into_iter().map(|v| v.unwrap_or(0)).collect(),
+        failed,
+    )
+}
+
+fn arithmetic<'a>(op: BinaryOp, l: Lane<'a>, r: Lane<'a>) -> Option<Lane<'a>> {
+    let slow = &l.slow | &r.slow;
+    let (values, failed) = match (l.values, r.values) {
+        (Vector::Int(a), Vector::Int(b)) => {
+            let (values, failed) = match op {
+                BinaryOp::Add => checked_ints(&a, &b, i64::checked_add),
+                BinaryOp::Sub => checked_ints(&a, &b, i64::checked_sub),
+                BinaryOp::Mul => checked_ints(&a, &b, i64::checked_mul),
+                // Zero divisors are errors; `i64::MIN / -1` is left to the interpreter
+                BinaryOp::Div => checked_ints(&a, &b, i64::checked_div),
+                BinaryOp::Mod => checked_ints(&a, &b, i64::checked_rem),
+                _ => return None,
+            };
+            (Vector::Int(values), failed)
+        }
+        (a @ (Vector::Int(_) | Vector::Float(_)), b @ (Vector::Int(_) | Vector::Float(_))) => {
+            let a = a.into_floats()?;
+            let b = b.into_floats()?;
+            let n = a.len();
+            let zip = |f: fn(f64, f64) -> f64| -> Vec<f64> {
+                a.iter().zip(&b).map(|(&x, &y)| f(x, y)).collect()
+            };
+            let (values, failed) = match op {
+                BinaryOp::Add => (zip(|x, y| x + y), BooleanBuffer::new_unset(n)),
+                BinaryOp::Sub => (zip(|x, y| x - y), BooleanBuffer::new_unset(n)),
+                BinaryOp::Mul => (zip(|x, y| x * y), BooleanBuffer::new_unset(n)),
+                BinaryOp::Div => (
+                    zip(|x, y| x / y),
+                    BooleanBuffer::collect_bool(n, |i| b[i] == 0.0),
+                ),
+                // Modulo is integer-only
+                _ => return None,
+            };
+            (Vector::Float(values), failed)
+        }
+        _ => return None,
+    };
+    Some(Lane {
+        values,
+        slow: &slow | &failed,
+    })
+}
+
+fn compare<'a>(op: BinaryOp, l: Lane<'a>, r: Lane<'a>) -> Option<Lane<'a>> {
+    let n = l.values.len();
+    let slow = &l.slow | &r.slow;
+    let ordering = !matches!(op, BinaryOp::Eq | BinaryOp::Ne);
+    let (values, failed) = match (l.values, r.values) {
+        (Vector::Int(a), Vector::Int(b)) => (compare_slices(op, &a, &b), None),
+        (Vector::Str(a), Vector::Str(b)) => (compare_slices(op, &a, &b), None),
+        (Vector::Bool(a), Vector::Bool(b)) => {
+            let a: Vec<bool> = a.iter().collect();
+            let b: Vec<bool> = b.iter().collect();
+            (compare_slices(op, &a, &b), None)
+        }
+        (Vector::Float(a), Vector::Float(b)) => {
+            // NaN has no ordering: `<` and friends fail, `==` is false
+            let nan = ordering
+                .then(|| BooleanBuffer::collect_bool(n, |i| a[i].is_nan() || b[i].is_nan()));
+            (compare_slices(op, &a, &b), nan)
+        }
+        (a @ Vector::Int(_), b @ Vector::Float(_)) | (a @ Vector::Float(_), b @ Vector::Int(_)) => {
+            if !ordering {
+                // `Int(1) == Float(1.0)` is false: values of different types never match
+                let equal = op == BinaryOp::Eq;
+                let values = if equal {
+                    BooleanBuffer::new_unset(n)
+                } else {
+                    BooleanBuffer::new_set(n)
+                };
+                (values, None)
+            } else {
+                let a = a.into_floats()?;
+                let b = b.into_floats()?;
+                let nan = BooleanBuffer::collect_bool(n, |i| a[i].is_nan() || b[i].is_nan());
+                (compare_slices(op, &a, &b), Some(nan))
+            }
+        }
+        _ if !ordering => {
+            let values = if op == BinaryOp::Eq {
+                BooleanBuffer::new_unset(n)
+            } else {
+                BooleanBuffer::new_set(n)
+            };
+            (values, None)
+        }
+        _ => return None,
+    };
+    Some(Lane {
+        values: Vector::Bool(values),
+        slow: match failed {
+            Some(failed) => &slow | &failed,
+            None => slow,
+        },
+    })
+}
+
+fn compare_slices<T: PartialOrd>(op: BinaryOp, a: &[T], b: &[T]) -> BooleanBuffer {
+    let n = a.len();
+    match op {
+        BinaryOp::Eq => BooleanBuffer::collect_bool(n, |i| a[i] == b[i]),
+        BinaryOp::Ne => BooleanBuffer::collect_bool(n, |i| a[i] != b[i]),
+        BinaryOp::Lt => BooleanBuffer::collect_bool(n, |i| a[i] < b[i]),
+        BinaryOp::Le => BooleanBuffer::collect_bool(n, |i| a[i] <= b[i]),
+        BinaryOp::Gt => BooleanBuffer::collect_bool(n, |i| a[i] > b[i]),
+        BinaryOp::Ge => BooleanBuffer::collect_bool(n, |i| a[i] >= b[i]),
+        _ => unreachable!("not a comparison: {:?}", op),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray, StructArray, UInt64Array,
+    };
+    use arrow_schema::Field;
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        let score: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(700),
+            Some(500),
+            None,
+            Some(i64::MAX),
+            Some(0),
+        ]));
+        let balance: ArrayRef =
+            Arc::new(Float64Array::from(vec![1200.5, -3.0, 0.0, f64::NAN, 50.0]));
+        let status: ArrayRef = Arc::new(StringArray::from(vec![
+            "active", "frozen", "active", "", "closed",
+        ]));
+        let vip: ArrayRef = Arc::new(BooleanArray::from(vec![true, false, false, true, false]));
+        let count: ArrayRef = Arc::new(UInt64Array::from(vec![
+            3,
+            u64::MAX,
+            i64::MAX as u64,
+            i64::MAX as u64 + 1,
+            0,
+        ]));
+        let tier: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3, 1, 2]));
+        let profile: ArrayRef = Arc::new(StructArray::new(
+            vec![Field::new("tier", arrow_schema::DataType::Int64, false)].into(),
+            vec![tier],
+            Some(vec![true, true, true, false, true].into()),
+        ));
+        RecordBatch::try_from_iter(vec![
+            ("score", score),
+            ("balance", balance),
+            ("status", status),
+            ("vip", vip),
+            ("count", count),
+            ("profile", profile),
+        ])
+        .unwrap()
+    }
+
+    /// Every expression must give what the interpreter gives on each row
+    #[test]
+    fn test_matches_interpreter() {
+        let batch = batch();
+        let evaluator = ColumnarEvaluator::new();
+        let interpreter = Evaluator::new();
+        let rows: Vec<u32> = (0..batch.num_rows() as u32).collect();
+
+        let exprs = [
+            "score > 600",
+            "score >= 500 && balance > 0.0",
+            "score + 1 > 0",
+            "balance / score > 1.0",
+            "score / (score - 700) == 0",
+            "balance < 100.0 || vip",
+            "!vip && status != \"closed\"",
+            "status in [\"active\", \"frozen\"]",
+            "status not in [\"active\"]",
+            "score in [700, 0]",
+            "score == 700.0",
+            "score < balance",
+            "if vip then score else tier",
+            "if vip then balance * 2.0 else -balance",
+            "profile.tier >= 2",
+            "exists(profile.tier)",
+            "score % 7",
+            "status",
+            "$missing > 1",
+            "missing > 1",
+            "len(status) > 0",
+            "status > 1",
+            "count > 2",
+            "count + 1 > 0",
+            "count == score",
+        ];
+        for source in exprs {
+            let expr = ExprParser::parse(source).unwrap();
+            let values = evaluator.eval_values(&expr, &batch, &rows);
+            let mask = evaluator.eval_condition(&expr, &batch, &rows);
+            for (i, &row) in rows.iter().enumerate() {
+                let ctx = Context::new(row_value(&batch, row as usize).unwrap());
+                let expected = interpreter.eval(&expr, &ctx);
+                match (&expected, &values[i]) {
+                    (Ok(e), Ok(a)) => {
+                        assert_eq!(format!("{:?}", e), format!("{:?}", a), "{source} row {row}")
+                    }
+                    (Err(e), Err(a)) => assert_eq!(e.to_string(), a.to_string()),
+                    (e, a) => panic!("{source} row {row}: expected {e:?}, got {a:?}"),
+                }
+                let error = mask.errors.iter().find(|(pos, _)| *pos == i);
+                assert_eq!(expected.is_err(), error.is_some(), "{source} row {row}");
+                let truthy = expected.map(|v| v.is_truthy()).unwrap_or(false);
+                assert_eq!(mask.matched.value(i), truthy, "{source} row {row}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_selection_and_vector_forms() {
+        let batch = batch();
+        let evaluator = ColumnarEvaluator::new();
+        let expr = ExprParser::parse("balance > 10.0").unwrap();
+        let mask = evaluator.eval_condition(&expr, &batch, &[4, 0, 1]);
+        assert_eq!(mask.matched.iter().collect::<Vec<_>>(), [true, true, false]);
+
+        assert!(evaluator.can_vectorize(&expr, &batch));
+        let call = ExprParser::parse("len(status) > 0").unwrap();
+        assert!(!evaluator.can_vectorize(&call, &batch));
+    }
+
+    #[test]
+    fn test_row_value_and_schema_check() {
+        let batch = batch();
+        let row = row_value(&batch, 3).unwrap();
+        assert_eq!(row.get_path("score"), Some(&Value::Int(i64::MAX)));
+        assert_eq!(row.get_path("profile"), Some(&Value::Null));
+        // Large unsigned integers decode like the same number in JSON
+        let json: Value = serde_json::from_str("18446744073709551615").unwrap();
+        assert_eq!(row_value(&batch, 1).unwrap().get_path("count"), Some(&json));
+        assert_eq!(json, Value::Float(u64::MAX as f64));
+        assert_eq!(
+            row_value(&batch, 2).unwrap().get_path("profile.tier"),
+            Some(&Value::Int(3))
+        );
+
+        assert!(check_schema(batch.schema_ref()).is_ok());
+        let dates = Schema::new(vec![Field::new("day", DataType::Date32, false)]);
+        assert!(check_schema(&dates).is_err());
+    }
+}
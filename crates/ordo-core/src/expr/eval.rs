@@ -4,6 +4,7 @@
 
 use super::ast::{BinaryOp, Expr, UnaryOp};
 use super::functions::FunctionRegistry;
+use crate::budget;
 use crate::context::{Context, Value};
 use crate::error::{OrdoError, Result};
 use std::collections::HashMap;
@@ -40,6 +41,12 @@ impl Evaluator {
 
     /// Evaluate an expression
     pub fn eval(&self, expr: &Expr, ctx: &Context) -> Result<Value> {
+        // One unit per expression, as the bytecode VM and JIT charge
+        budget::charge_fuel(1)?;
+        self.eval_node(expr, ctx)
+    }
+
+    fn eval_node(&self, expr: &Expr, ctx: &Context) -> Result<Value> {
         match expr {
             Expr::Literal(v) => Ok(v.clone()),
 
@@ -57,7 +64,7 @@ impl Evaluator {
             Expr::Call { name, args } => {
                 let arg_values: Vec<Value> = args
                     .iter()
-                    .map(|arg| self.eval(arg, ctx))
+                    .map(|arg| self.eval_node(arg, ctx))
                     .collect::<Result<_>>()?;
                 self.functions.call(name, &arg_values)
             }
@@ -67,29 +74,33 @@ impl Evaluator {
                 then_branch,
                 else_branch,
             } => {
-                let cond = self.eval(condition, ctx)?;
+                let cond = self.eval_node(condition, ctx)?;
                 if cond.is_truthy() {
-                    self.eval(then_branch, ctx)
+                    self.eval_node(then_branch, ctx)
                 } else {
-                    self.eval(else_branch, ctx)
+                    self.eval_node(else_branch, ctx)
                 }
             }
 
             Expr::Array(elements) => {
                 let values: Vec<Value> = elements
                     .iter()
-                    .map(|e| self.eval(e, ctx))
+                    .map(|e| self.eval_node(e, ctx))
                     .collect::<Result<_>>()?;
-                Ok(Value::array(values))
+                let array = Value::array(values);
+                budget::charge_value(&array)?;
+                Ok(array)
             }
 
             Expr::Object(pairs) => {
                 let mut map = HashMap::new();
                 for (key, value_expr) in pairs {
-                    let value = self.eval(value_expr, ctx)?;
+                    let value = self.eval_node(value_expr, ctx)?;
                     map.insert(key.clone(), value);
                 }
-                Ok(Value::object(map))
+                let object = Value::object(map);
+                budget::charge_value(&object)?;
+                Ok(object)
             }
 
             Expr::Exists(path) => Ok(Value::bool(ctx.get(path).is_some())),
@@ -97,7 +108,7 @@ impl Evaluator {
             Expr::Coalesce(exprs) => {
                 for expr in exprs {
                     // Try to evaluate, treating FieldNotFound as null
-                    match self.eval(expr, ctx) {
+                    match self.eval_node(expr, ctx) {
                         Ok(v) if !v.is_null() => return Ok(v),
                         Ok(_) => continue, // null, try next
                         Err(OrdoError::FieldNotFound { .. }) => continue, // field not found, try next
@@ -113,26 +124,26 @@ impl Evaluator {
     fn eval_binary(&self, op: BinaryOp, left: &Expr, right: &Expr, ctx: &Context) -> Result<Value> {
         // Short-circuit evaluation for logical operators
         if op == BinaryOp::And {
-            let left_val = self.eval(left, ctx)?;
+            let left_val = self.eval_node(left, ctx)?;
             if !left_val.is_truthy() {
                 return Ok(Value::bool(false));
             }
-            let right_val = self.eval(right, ctx)?;
+            let right_val = self.eval_node(right, ctx)?;
             return Ok(Value::bool(right_val.is_truthy()));
         }
 
         if op == BinaryOp::Or {
-            let left_val = self.eval(left, ctx)?;
+            let left_val = self.eval_node(left, ctx)?;
             if left_val.is_truthy() {
                 return Ok(Value::bool(true));
             }
-            let right_val = self.eval(right, ctx)?;
+            let right_val = self.eval_node(right, ctx)?;
             return Ok(Value::bool(right_val.is_truthy()));
         }
 
         // Evaluate both sides
-        let left_val = self.eval(left, ctx)?;
-        let right_val = self.eval(right, ctx)?;
+        let left_val = self.eval_node(left, ctx)?;
+        let right_val = self.eval_node(right, ctx)?;
 
         match op {
             // Arithmetic
@@ -164,7 +175,7 @@ impl Evaluator {
 
     /// Evaluate unary operation
     fn eval_unary(&self, op: UnaryOp, operand: &Expr, ctx: &Context) -> Result<Value> {
-        let val = self.eval(operand, ctx)?;
+        let val = self.eval_node(operand, ctx)?;
 
         match op {
             UnaryOp::Not => Ok(Value::bool(!val.is_truthy())),
@@ -187,7 +198,12 @@ impl Evaluator {
             (Value::Float(a), Value::Float(b)) => Ok(Value::float(a + b)),
             (Value::Int(a), Value::Float(b)) => Ok(Value::float(*a as f64 + b)),
             (Value::Float(a), Value::Int(b)) => Ok(Value::float(a + *b as f64)),
-            (Value::String(a), Value::String(b)) => Ok(Value::string(format!("{}{}", a, b))),
+            (Value::String(a), Value::String(b)) => {
+                budget::check_string_len(a.len() + b.len())?;
+                let joined = Value::string(format!("{}{}", a, b));
+                budget::charge_value(&joined)?;
+                Ok(joined)
+            }
             _ => Err(OrdoError::eval_error(format!(
                 "Cannot add {} and {}",
                 left.type_name(),
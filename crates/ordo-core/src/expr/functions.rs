@@ -7,6 +7,7 @@
 //! The default built-in functions are stored in a global singleton (`GLOBAL_BUILTIN_REGISTRY`)
 //! to avoid repeated registration overhead. Custom functions can still be added per-registry.
 
+use crate::budget;
 use crate::context::Value;
 use crate::error::{OrdoError, Result};
 use std::borrow::Cow;
@@ -333,12 +334,13 @@ impl FunctionRegistry {
         });
 
         // Date/time functions (basic)
+        // Clock readings go through `replay` so recordings can reproduce them
         self.register("now", |_args| {
-            Ok(Value::int(chrono::Utc::now().timestamp()))
+            crate::replay::read_clock(|| chrono::Utc::now().timestamp()).map(Value::int)
         });
 
         self.register("now_millis", |_args| {
-            Ok(Value::int(chrono::Utc::now().timestamp_millis()))
+            crate::replay::read_clock(|| chrono::Utc::now().timestamp_millis()).map(Value::int)
         });
 
         // ==================== Extended built-in functions ====================
@@ -348,7 +350,7 @@ impl FunctionRegistry {
             require_args("regex_match", args, 2)?;
             let pattern = require_string("regex_match", &args[0])?;
             let s = require_string("regex_match", &args[1])?;
-            let re = compile_regex(pattern)?;
+            let re = super::regex_cache::get(pattern)?;
             Ok(Value::bool(re.is_match(s)))
         });
 
@@ -356,7 +358,7 @@ impl FunctionRegistry {
             require_args("regex_find", args, 2)?;
             let pattern = require_string("regex_find", &args[0])?;
             let s = require_string("regex_find", &args[1])?;
-            let re = compile_regex(pattern)?;
+            let re = super::regex_cache::get(pattern)?;
             match re.find(s) {
                 Some(m) => Ok(Value::string(m.as_str())),
                 None => Ok(Value::Null),
@@ -368,8 +370,23 @@ impl FunctionRegistry {
             let pattern = require_string("regex_replace", &args[0])?;
             let s = require_string("regex_replace", &args[1])?;
             let replacement = require_string("regex_replace", &args[2])?;
-            let re = compile_regex(pattern)?;
-            Ok(Value::string(&*re.replace_all(s, replacement)))
+            let re = super::regex_cache::get(pattern)?;
+            // Stop expanding once the result could no longer fit the budget
+            let mut produced = s.len();
+            let mut exceeded = None;
+            let replaced = re.replace_all(s, |caps: &regex::Captures<'_>| {
+                let mut expanded = String::new();
+                if exceeded.is_none() {
+                    caps.expand(replacement, &mut expanded);
+                    produced += expanded.len();
+                    exceeded = budget::check_string_len(produced).err();
+                }
+                expanded
+            });
+            match exceeded {
+                Some(e) => Err(e),
+                None => Ok(Value::string(&*replaced)),
+            }
         });
 
         // --- Time/Date functions (6) ---
@@ -456,6 +473,14 @@ impl FunctionRegistry {
             let s = require_string("replace", &args[0])?;
             let old = require_string("replace", &args[1])?;
             let new = require_string("replace", &args[2])?;
+            if new.len() > old.len() {
+                let occurrences = if old.is_empty() {
+                    s.chars().count() + 1
+                } else {
+                    s.matches(old).count()
+                };
+                budget::check_string_len(s.len() + occurrences * (new.len() - old.len()))?;
+            }
             Ok(Value::string(s.replace(old, new)))
         });
 
@@ -463,6 +488,12 @@ impl FunctionRegistry {
             require_args("split", args, 2)?;
             let s = require_string("split", &args[0])?;
             let delim = require_string("split", &args[1])?;
+            let pieces = if delim.is_empty() {
+                s.chars().count() + 2
+            } else {
+                s.matches(delim).count() + 1
+            };
+            budget::check_array_len(pieces)?;
             let parts: Vec<Value> = s.split(delim).map(Value::string).collect();
             Ok(Value::array(parts))
         });
@@ -478,7 +509,11 @@ impl FunctionRegistry {
                         .ok_or_else(|| OrdoError::type_error("string", v.type_name()))
                 })
                 .collect();
-            Ok(Value::string(parts?.join(delim)))
+            let parts = parts?;
+            let len = parts.iter().map(|p| p.len()).sum::<usize>()
+                + delim.len() * parts.len().saturating_sub(1);
+            budget::check_string_len(len)?;
+            Ok(Value::string(parts.join(delim)))
         });
 
         self.register("pad_left", |args| {
@@ -494,6 +529,7 @@ impl FunctionRegistry {
             if s.len() >= width {
                 Ok(Value::string(s))
             } else {
+                budget::check_string_len(s.len() + (width - s.len()) * pad_char.len_utf8())?;
                 let padding: String = std::iter::repeat(pad_char).take(width - s.len()).collect();
                 Ok(Value::string(format!("{}{}", padding, s)))
             }
@@ -512,6 +548,7 @@ impl FunctionRegistry {
             if s.len() >= width {
                 Ok(Value::string(s))
             } else {
+                budget::check_string_len(s.len() + (width - s.len()) * pad_char.len_utf8())?;
                 let padding: String = std::iter::repeat(pad_char).take(width - s.len()).collect();
                 Ok(Value::string(format!("{}{}", s, padding)))
             }
@@ -953,11 +990,8 @@ impl FunctionRegistry {
             require_args("regex_find_all", args, 2)?;
             let pattern = require_string("regex_find_all", &args[0])?;
             let input = require_string("regex_find_all", &args[1])?;
-            let re = compile_regex(pattern)?;
-            let matches: Vec<Value> = re
-                .find_iter(input)
-                .map(|m| Value::string(m.as_str()))
-                .collect();
+            let re = super::regex_cache::get(pattern)?;
+            let matches = collect_bounded(re.find_iter(input).map(|m| Value::string(m.as_str())))?;
             Ok(Value::array(matches))
         });
 
@@ -965,8 +999,8 @@ impl FunctionRegistry {
             require_args("regex_split", args, 2)?;
             let pattern = require_string("regex_split", &args[0])?;
             let input = require_string("regex_split", &args[1])?;
-            let re = compile_regex(pattern)?;
-            let parts: Vec<Value> = re.split(input).map(Value::string).collect();
+            let re = super::regex_cache::get(pattern)?;
+            let parts = collect_bounded(re.split(input).map(Value::string))?;
             Ok(Value::array(parts))
         });
 
@@ -1016,6 +1050,7 @@ impl FunctionRegistry {
             require_args("array_concat", args, 2)?;
             let a = require_array("array_concat", &args[0])?;
             let b = require_array("array_concat", &args[1])?;
+            budget::check_array_len(a.len() + b.len())?;
             let mut result = a.to_vec();
             result.extend_from_slice(b);
             Ok(Value::array(result))
@@ -1024,7 +1059,12 @@ impl FunctionRegistry {
         self.register("flatten", |args| {
             require_args("flatten", args, 1)?;
             let arr = require_array("flatten", &args[0])?;
-            let mut result = Vec::new();
+            let len = arr
+                .iter()
+                .map(|item| item.as_array().map_or(1, |inner| inner.len()))
+                .sum();
+            budget::check_array_len(len)?;
+            let mut result = Vec::with_capacity(len);
             for item in arr {
                 if let Some(inner) = item.as_array() {
                     result.extend_from_slice(inner);
@@ -1039,6 +1079,9 @@ impl FunctionRegistry {
         self.register("sort", |args| {
             require_args("sort", args, 1)?;
             let arr = require_array("sort", &args[0])?;
+            budget::charge_fuel(
+                arr.len() as u64 * (usize::BITS - arr.len().leading_zeros()) as u64,
+            )?;
             let mut sorted = arr.to_vec();
             sorted.sort_by(|a, b| a.compare(b).unwrap_or(std::cmp::Ordering::Equal));
             Ok(Value::array(sorted))
@@ -1055,6 +1098,7 @@ impl FunctionRegistry {
         self.register("unique", |args| {
             require_args("unique", args, 1)?;
             let arr = require_array("unique", &args[0])?;
+            budget::charge_fuel((arr.len() as u64).saturating_mul(arr.len() as u64) / 2)?;
             let mut seen = Vec::with_capacity(arr.len());
             for v in arr {
                 if !seen.contains(v) {
@@ -1101,6 +1145,12 @@ impl FunctionRegistry {
             } else {
                 1
             };
+            let count = if step > 0 {
+                (end as i128 - start as i128 + step as i128 - 1) / step as i128
+            } else {
+                (start as i128 - end as i128 - step as i128 - 1) / -(step as i128)
+            };
+            budget::check_array_len(count.clamp(0, usize::MAX as i128) as usize)?;
             let mut result = Vec::new();
             let mut i = start;
             if step > 0 {
@@ -1225,6 +1275,11 @@ impl FunctionRegistry {
         self.functions.insert(name.into(), Arc::new(f));
     }
 
+    /// Check whether `name` is one of the built-in functions
+    pub fn is_builtin(name: &str) -> bool {
+        global_builtin_registry().functions.contains_key(name)
+    }
+
     /// Get a function by name
     pub fn get(&self, name: &str) -> Option<&FunctionFn> {
         self.functions.get(name)
@@ -1235,6 +1290,8 @@ impl FunctionRegistry {
     /// Uses fast path for common built-in functions to avoid HashMap lookup overhead.
     #[inline]
     pub fn call(&self, name: &str, args: &[Value]) -> Result<Value> {
+        budget::charge_args(args)?;
+
         // Fast path for most common functions - avoids HashMap lookup entirely
         match name {
             "len" => return Self::builtin_len_static(args),
@@ -1247,16 +1304,17 @@ impl FunctionRegistry {
             _ => {}
         }
 
-        // Check custom functions first (local registry)
-        if let Some(func) = self.functions.get(name) {
-            return func(args);
-        }
-
-        // Fall back to global registry for other built-in functions
-        if self.custom_only {
-            if let Some(func) = global_builtin_registry().functions.get(name) {
-                return func(args);
-            }
+        // Check custom functions first (local registry), then fall back to
+        // the global registry for other built-in functions
+        let func = self.functions.get(name).or_else(|| {
+            self.custom_only
+                .then(|| global_builtin_registry().functions.get(name))
+                .flatten()
+        });
+        if let Some(func) = func {
+            let result = func(args)?;
+            budget::charge_value(&result)?;
+            return Ok(result);
         }
 
         Err(OrdoError::function_not_found(name.to_string()))
@@ -1448,25 +1506,17 @@ fn require_object<'a>(
     }
 }
 
-/// Thread-local LRU cache for compiled regex patterns
-fn compile_regex(pattern: &str) -> Result<regex::Regex> {
-    use std::cell::RefCell;
-
-    thread_local! {
-        static REGEX_CACHE: RefCell<lru::LruCache<String, regex::Regex>> =
-            RefCell::new(lru::LruCache::new(std::num::NonZeroUsize::new(64).unwrap()));
-    }
-
-    REGEX_CACHE.with(|cache| {
-        let mut cache = cache.borrow_mut();
-        if let Some(re) = cache.get(pattern) {
-            return Ok(re.clone());
+/// Collect values into an array, failing as soon as it outgrows the budget
+fn collect_bounded(values: impl Iterator<Item = Value>) -> Result<Vec<Value>> {
+    let mut result = Vec::new();
+    for value in values {
+        result.push(value);
+        if result.len().is_power_of_two() {
+            budget::check_array_len(result.len())?;
         }
-        let re = regex::Regex::new(pattern)
-            .map_err(|e| OrdoError::eval_error(format!("invalid regex '{}': {}", pattern, e)))?;
-        cache.put(pattern.to_string(), re.clone());
-        Ok(re)
-    })
+    }
+    budget::check_array_len(result.len())?;
+    Ok(result)
 }
 
 #[cfg(test)]
@@ -18,7 +18,11 @@
 //! - Arithmetic: +, -, *, /, %
 //! - Comparison: ==, !=, <, <=, >, >=
 //! - Logical: &&, ||, !
+//! - Conditionals: if-then-else (including nested)
 //! - Field access: direct memory offset
+//! - Strings: ==, != against literals or other string fields, `starts_with`
+//! - Set membership: `in` / `not in` against literal string or numeric arrays
+//! - Optionals: `== null`, `!= null`, `exists()`; absent values fall back to the VM
 //! - Functions: abs, min, max, floor, ceil, round, sqrt
 //!
 //! # Usage
@@ -34,14 +38,26 @@
 //! let compiled = compiler.compile_with_schema(&expr, hash, MyContext::schema())?;
 //! let result = unsafe { compiled.call_typed(&ctx)? };
 //! ```
+//!
+//! For JSON inputs without a Rust struct, an [`InferredShape`] derives the
+//! schema from observed values and packs each input into a matching buffer.
 
 mod schema_compiler;
 mod schema_evaluator;
+mod shape;
 mod typed_context;
 
 // Schema-Aware JIT exports
 pub use schema_compiler::{
     SchemaCompiledFunction, SchemaJITCache, SchemaJITCompiler, SchemaJITErrorCode, SchemaJITStats,
+    SchemaResultType,
 };
 pub use schema_evaluator::{SchemaJITEvaluator, SchemaJITEvaluatorConfig, SchemaJITEvaluatorStats};
+pub use shape::{InferredShape, PackedInput, ShapeField, ShapeKind};
 pub use typed_context::{DynamicTypedContext, FieldAccessInfo, TypedContext};
+
+// Codegen shared with the ahead-of-time backend
+#[cfg(feature = "aot")]
+pub(crate) use schema_compiler::{compile_expr_value, LiteralPool, SchemaCompileContext};
+#[cfg(feature = "aot")]
+pub(crate) use typed_context::read_value_at;
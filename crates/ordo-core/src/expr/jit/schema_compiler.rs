@@ -10,7 +10,7 @@
 //!
 //! Where:
 //! - `ctx_ptr`: Pointer to a TypedContext struct (NOT serialized data)
-//! - `result_ptr`: Pointer to write the result value (f64)
+//! - `result_ptr`: Pointer to write the result value (f64, booleans as 1.0/0.0)
 //! - Returns: 0 on success, error code on failure
 //!
 //! # No Trampolines
@@ -23,16 +23,31 @@
 //! ```
 //!
 //! This provides ~15-30x faster field access compared to trampoline-based approach.
+//!
+//! # Strings, Sets and Optionals
+//!
+//! `String` and `Option<T>` fields have no stable Rust layout, so they are read
+//! through small `extern "C"` runtime helpers instead of raw loads:
+//!
+//! - String literals are interned at compile time; the generated code embeds
+//!   their address and length as constants.
+//! - `field in ["a", "b"]` against a literal string array is compiled to a
+//!   perfect-hash lookup; numeric arrays become a sorted compare chain.
+//! - Reading an absent `Optional` value returns [`SchemaJITErrorCode::NullValue`]
+//!   so the caller can fall back to the VM, while `field == null`,
+//!   `field != null` and `exists(field)` are compiled to presence checks.
 
 use crate::context::{FieldType, MessageSchema, ResolvedField, Value};
 use crate::error::{OrdoError, Result};
 use crate::expr::{BinaryOp, Expr, UnaryOp};
 
 use cranelift::prelude::*;
+use cranelift_codegen::ir::FuncRef;
 use cranelift_codegen::settings;
 use cranelift_jit::{JITBuilder, JITModule};
-use cranelift_module::{Linkage, Module};
+use cranelift_module::{FuncId, Linkage, Module};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Error codes returned by JIT-compiled functions
 #[repr(i32)]
@@ -45,6 +60,33 @@ pub enum SchemaJITErrorCode {
     FieldNotFound = 4,
     InvalidOperation = 5,
     Overflow = 6,
+    /// An `Optional` field was absent where a value was required (guard failure)
+    NullValue = 7,
+}
+
+impl SchemaJITErrorCode {
+    /// Convert a raw return code into an error code
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => Self::Success,
+            1 => Self::NullPointer,
+            2 => Self::TypeMismatch,
+            3 => Self::DivisionByZero,
+            4 => Self::FieldNotFound,
+            6 => Self::Overflow,
+            7 => Self::NullValue,
+            _ => Self::InvalidOperation,
+        }
+    }
+}
+
+/// Type of the value produced by a compiled function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaResultType {
+    /// Numeric result, returned as `Value::Float`
+    Number,
+    /// Boolean result (comparisons, logical operators, set membership)
+    Bool,
 }
 
 /// Result of a schema-aware JIT compilation
@@ -60,6 +102,8 @@ pub struct SchemaCompiledFunction {
     pub code_size: usize,
     /// Fields accessed by this function
     pub accessed_fields: Vec<String>,
+    /// Type of the produced value
+    pub result_type: SchemaResultType,
 }
 
 // Safety: The function pointer is valid for the lifetime of JITModule
@@ -75,22 +119,63 @@ impl SchemaCompiledFunction {
     /// - The struct has the same layout as when the function was compiled
     #[inline]
     pub unsafe fn call_typed<T>(&self, ctx: &T) -> Result<f64> {
+        self.call_typed_checked(ctx).map_err(|code| {
+            OrdoError::eval_error(format!(
+                "Schema JIT execution failed with code: {}",
+                code as i32
+            ))
+        })
+    }
+
+    /// Execute the compiled function, returning the raw error code on failure
+    ///
+    /// A `NullValue` error means a guard failed and the expression should be
+    /// re-evaluated by the VM.
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`call_typed`](Self::call_typed).
+    #[inline]
+    pub unsafe fn call_typed_checked<T>(
+        &self,
+        ctx: &T,
+    ) -> std::result::Result<f64, SchemaJITErrorCode> {
+        self.call_ptr_checked(ctx as *const T as *const u8)
+    }
+
+    /// Execute the compiled function against a raw context pointer
+    ///
+    /// # Safety
+    ///
+    /// `ctx_ptr` must point to memory laid out as described by the schema this
+    /// function was compiled for.
+    #[inline]
+    pub unsafe fn call_ptr_checked(
+        &self,
+        ctx_ptr: *const u8,
+    ) -> std::result::Result<f64, SchemaJITErrorCode> {
         let mut result_buf = [0u8; 8];
 
         let func: extern "C" fn(*const u8, *mut u8) -> i32 = std::mem::transmute(self.func_ptr);
 
-        let error_code = func(ctx as *const T as *const u8, result_buf.as_mut_ptr());
+        let error_code = func(ctx_ptr, result_buf.as_mut_ptr());
 
         if error_code != 0 {
-            return Err(OrdoError::eval_error(format!(
-                "Schema JIT execution failed with code: {}",
-                error_code
-            )));
+            return Err(SchemaJITErrorCode::from_code(error_code));
         }
 
         Ok(f64::from_le_bytes(result_buf))
     }
 
+    /// Convert a raw result into a `Value` according to the result type
+    #[inline]
+    pub fn to_value(&self, raw: f64) -> Value {
+        match self.result_type {
+            SchemaResultType::Number => Value::Float(raw),
+            SchemaResultType::Bool => Value::Bool(raw != 0.0),
+        }
+    }
+
     /// Execute and return as Value
     ///
     /// # Safety
@@ -99,7 +184,7 @@ impl SchemaCompiledFunction {
     /// the schema this function was compiled for.
     #[inline]
     pub unsafe fn call_typed_value<T>(&self, ctx: &T) -> Result<Value> {
-        self.call_typed(ctx).map(Value::Float)
+        self.call_typed(ctx).map(|raw| self.to_value(raw))
     }
 }
 
@@ -111,6 +196,10 @@ pub struct SchemaJITCompiler {
     ctx: codegen::Context,
     /// Function builder context (reusable)
     func_ctx: FunctionBuilderContext,
+    /// Imported runtime helpers
+    helpers: RuntimeHelpers,
+    /// Interned literals referenced by compiled code (must outlive the module)
+    literals: LiteralPool,
     /// Statistics
     stats: SchemaJITStats,
 }
@@ -132,6 +221,27 @@ pub struct SchemaJITStats {
     pub total_code_size: usize,
     /// Number of schema-aware compilations
     pub schema_compiles: u64,
+    /// Number of distinct string literals interned
+    pub interned_literals: usize,
+    /// Number of perfect-hash string sets built for `in` / `not in`
+    pub string_sets: usize,
+    /// Evaluations that fell back to the VM because the expression could not be compiled
+    pub vm_fallbacks: u64,
+    /// Evaluations that fell back to the VM because a runtime guard failed
+    pub guard_fallbacks: u64,
+}
+
+/// Cache key of `expr` compiled against `schema`
+///
+/// Covers the schema name and field layout: runtime schemas (e.g. from
+/// descriptor sets of different tenants) may share a name.
+pub(crate) fn schema_expr_hash(expr: &Expr, schema: &MessageSchema) -> u64 {
+    let layout: Vec<_> = schema
+        .fields
+        .iter()
+        .map(|f| (&f.name, f.offset, &f.field_type))
+        .collect();
+    crate::expr::hash_expr(&format!("{}:{:?}:{:?}", schema.name, layout, expr))
 }
 
 impl SchemaJITCompiler {
@@ -152,8 +262,10 @@ impl SchemaJITCompiler {
             .finish(settings::Flags::new(flag_builder))
             .map_err(|e| OrdoError::eval_error(format!("Failed to create ISA: {}", e)))?;
 
-        let builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
-        let module = JITModule::new(builder);
+        let mut builder = JITBuilder::with_isa(isa, cranelift_module::default_libcall_names());
+        RuntimeHelpers::register_symbols(&mut builder);
+        let mut module = JITModule::new(builder);
+        let helpers = RuntimeHelpers::declare(&mut module)?;
         let ctx = module.make_context();
         let func_ctx = FunctionBuilderContext::new();
 
@@ -161,6 +273,8 @@ impl SchemaJITCompiler {
             module,
             ctx,
             func_ctx,
+            helpers,
+            literals: LiteralPool::default(),
             stats: SchemaJITStats::default(),
         })
     }
@@ -169,33 +283,34 @@ impl SchemaJITCompiler {
     pub fn stats(&self) -> &SchemaJITStats {
         &self.stats
     }
-    pub fn can_compile_with_schema(expr: &Expr, schema: &MessageSchema) -> bool {
-        // Collect all field accesses
-        let mut fields = Vec::new();
-        Self::collect_field_accesses(expr, &mut fields);
-
-        // All fields must exist in schema and be numeric
-        for field in &fields {
-            if let Some(resolved) = schema.resolve_field_path(field) {
-                if !resolved.field_type.is_jit_numeric() {
-                    return false;
-                }
-            } else {
-                return false;
-            }
-        }
 
-        // Check that expression structure is supported
-        Self::is_supported_expr(expr)
+    /// Free the native code of every function compiled by this compiler
+    ///
+    /// # Safety
+    ///
+    /// None of its functions may be running, or be called afterwards.
+    pub(crate) unsafe fn free_memory(self) {
+        let Self { module, .. } = self;
+        module.free_memory();
+    }
+
+    /// Check whether an expression can be compiled against a schema
+    ///
+    /// The expression must type-check to a number or boolean, every field must
+    /// resolve in the schema, and strings may only appear in equality, prefix
+    /// and set-membership tests.
+    pub fn can_compile_with_schema(expr: &Expr, schema: &MessageSchema) -> bool {
+        matches!(
+            infer_type(expr, schema),
+            Some(JitType::Number) | Some(JitType::Bool)
+        )
     }
 
     /// Collect all field accesses from an expression
-    fn collect_field_accesses(expr: &Expr, fields: &mut Vec<String>) {
+    pub(crate) fn collect_field_accesses(expr: &Expr, fields: &mut Vec<String>) {
         match expr {
-            Expr::Field(name) => {
-                if !fields.contains(name) {
-                    fields.push(name.clone());
-                }
+            Expr::Field(name) | Expr::Exists(name) if !fields.contains(name) => {
+                fields.push(name.clone());
             }
             Expr::Binary { left, right, .. } => {
                 Self::collect_field_accesses(left, fields);
@@ -218,49 +333,10 @@ impl SchemaJITCompiler {
                     Self::collect_field_accesses(arg, fields);
                 }
             }
-            Expr::Exists(field_name) => {
-                if !fields.contains(field_name) {
-                    fields.push(field_name.clone());
-                }
-            }
             _ => {}
         }
     }
 
-    /// Check if expression structure is supported
-    fn is_supported_expr(expr: &Expr) -> bool {
-        match expr {
-            Expr::Literal(v) => matches!(
-                v,
-                Value::Null | Value::Bool(_) | Value::Int(_) | Value::Float(_)
-            ),
-            Expr::Field(_) => true,
-            Expr::Binary { left, right, op } => {
-                // String operations not supported
-                !matches!(op, BinaryOp::In | BinaryOp::NotIn | BinaryOp::Contains)
-                    && Self::is_supported_expr(left)
-                    && Self::is_supported_expr(right)
-            }
-            Expr::Unary { operand, .. } => Self::is_supported_expr(operand),
-            Expr::Conditional {
-                condition,
-                then_branch,
-                else_branch,
-            } => {
-                Self::is_supported_expr(condition)
-                    && Self::is_supported_expr(then_branch)
-                    && Self::is_supported_expr(else_branch)
-            }
-            Expr::Call { name, args } => {
-                // Only math functions are supported (no field-based array ops)
-                let supported = ["abs", "min", "max", "floor", "ceil", "round", "sqrt", "pow"];
-                supported.contains(&name.as_str()) && args.iter().all(Self::is_supported_expr)
-            }
-            // Not supported: Array, Object, Coalesce, Exists
-            _ => false,
-        }
-    }
-
     /// Compile an expression with a schema
     pub fn compile_with_schema(
         &mut self,
@@ -269,7 +345,7 @@ impl SchemaJITCompiler {
         schema: &MessageSchema,
         cache: &SchemaJITCache,
     ) -> Result<SchemaCompiledFunction> {
-        let schema_hash = crate::expr::hash_expr(&format!("{}:{:?}", schema.name, expr));
+        let schema_hash = schema_expr_hash(expr, schema);
 
         if let Some(compiled) = cache.get(&schema_hash) {
             return Ok(compiled.value().clone());
@@ -278,12 +354,16 @@ impl SchemaJITCompiler {
         let start = std::time::Instant::now();
 
         // Validate expression is compilable with this schema
-        if !Self::can_compile_with_schema(expr, schema) {
-            self.stats.failed_compiles += 1;
-            return Err(OrdoError::eval_error(
-                "Expression is not compilable with the given schema".to_string(),
-            ));
-        }
+        let result_type = match infer_type(expr, schema) {
+            Some(JitType::Number) => SchemaResultType::Number,
+            Some(JitType::Bool) => SchemaResultType::Bool,
+            _ => {
+                self.stats.failed_compiles += 1;
+                return Err(OrdoError::eval_error(
+                    "Expression is not compilable with the given schema".to_string(),
+                ));
+            }
+        };
 
         // Collect field accesses and resolve offsets
         let mut field_names = Vec::new();
@@ -318,6 +398,7 @@ impl SchemaJITCompiler {
 
         {
             let mut builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.func_ctx);
+            let helpers = self.helpers.import(&mut self.module, builder.func);
 
             // Create entry block
             let entry_block = builder.create_block();
@@ -330,13 +411,15 @@ impl SchemaJITCompiler {
             let result_ptr = builder.block_params(entry_block)[1];
 
             // Create compilation context
-            let compile_ctx = SchemaCompileContext {
+            let mut compile_ctx = SchemaCompileContext {
                 field_offsets: &field_offsets,
                 ptr_type,
+                helpers: Some(helpers),
+                literals: &mut self.literals,
             };
 
             // Compile expression to IR
-            match compile_expr_to_ir(&mut builder, expr, ctx_ptr, result_ptr, &compile_ctx) {
+            match compile_expr_to_ir(&mut builder, expr, ctx_ptr, result_ptr, &mut compile_ctx) {
                 Ok(()) => {
                     // Return success
                     let zero = builder.ins().iconst(types::I32, 0);
@@ -358,6 +441,10 @@ impl SchemaJITCompiler {
         self.module
             .define_function(func_id, &mut self.ctx)
             .map_err(|e| OrdoError::eval_error(format!("Failed to define function: {}", e)))?;
+        let code_size = self
+            .ctx
+            .compiled_code()
+            .map_or(0, |code| code.code_info().total_size as usize);
 
         self.module.clear_context(&mut self.ctx);
         self.module
@@ -371,14 +458,18 @@ impl SchemaJITCompiler {
             func_ptr,
             expr_hash: schema_hash,
             schema_name: schema.name.clone(),
-            code_size: 0, // TODO: Get actual code size
+            code_size,
             accessed_fields: field_names,
+            result_type,
         };
 
         let duration = start.elapsed();
         self.stats.successful_compiles += 1;
         self.stats.schema_compiles += 1;
         self.stats.total_compile_time_ns += duration.as_nanos() as u64;
+        self.stats.total_code_size += code_size;
+        self.stats.interned_literals = self.literals.strings.len();
+        self.stats.string_sets = self.literals.sets.len();
 
         cache.insert(schema_hash, compiled.clone());
 
@@ -397,13 +488,537 @@ impl Default for SchemaJITCompiler {
     }
 }
 
+// ==================== Static Typing ====================
+
+/// Static type of a sub-expression as seen by the JIT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JitType {
+    Number,
+    Bool,
+    Str,
+    Null,
+}
+
+impl JitType {
+    /// Number and Bool are both represented as f64 in generated code
+    fn is_scalar(self) -> bool {
+        matches!(self, JitType::Number | JitType::Bool)
+    }
+}
+
+/// Map a schema field type to its JIT type
+fn field_jit_type(field_type: &FieldType) -> Option<JitType> {
+    match field_type {
+        FieldType::Bool => Some(JitType::Bool),
+        FieldType::String => Some(JitType::Str),
+        FieldType::Optional(inner) => match inner.as_ref() {
+            FieldType::Optional(_) => None,
+            inner => field_jit_type(inner),
+        },
+        t if t.is_jit_numeric() => Some(JitType::Number),
+        _ => None,
+    }
+}
+
+/// Infer the JIT type of an expression, or `None` if it cannot be compiled
+fn infer_type(expr: &Expr, schema: &MessageSchema) -> Option<JitType> {
+    match expr {
+        Expr::Literal(v) => match v {
+            Value::Null => Some(JitType::Null),
+            Value::Bool(_) => Some(JitType::Bool),
+            Value::Int(_) | Value::Float(_) => Some(JitType::Number),
+            Value::String(_) => Some(JitType::Str),
+            _ => None,
+        },
+        Expr::Field(name) => field_jit_type(&schema.resolve_field_path(name)?.field_type),
+        Expr::Exists(name) => schema.resolve_field_path(name).map(|_| JitType::Bool),
+        Expr::Binary { op, left, right } => {
+            if matches!(op, BinaryOp::In | BinaryOp::NotIn) {
+                let left_ty = infer_type(left, schema)?;
+                let items = literal_set(right)?;
+                let homogeneous = match left_ty {
+                    JitType::Str => {
+                        matches!(left.as_ref(), Expr::Field(_))
+                            && items.iter().all(|v| matches!(v, Value::String(_)))
+                    }
+                    JitType::Number => items
+                        .iter()
+                        .all(|v| matches!(v, Value::Int(_) | Value::Float(_))),
+                    _ => false,
+                };
+                return homogeneous.then_some(JitType::Bool);
+            }
+
+            let left_ty = infer_type(left, schema)?;
+            let right_ty = infer_type(right, schema)?;
+            match op {
+                BinaryOp::Eq | BinaryOp::Ne => match (left_ty, right_ty) {
+                    (JitType::Str, JitType::Str) => {
+                        let is_field = |e: &Expr| matches!(e, Expr::Field(_));
+                        let is_operand = |e: &Expr| {
+                            matches!(e, Expr::Field(_) | Expr::Literal(Value::String(_)))
+                        };
+                        ((is_field(left) && is_operand(right))
+                            || (is_field(right) && is_operand(left)))
+                        .then_some(JitType::Bool)
+                    }
+                    (JitType::Null, _) => is_optional_field(right, schema).then_some(JitType::Bool),
+                    (_, JitType::Null) => is_optional_field(left, schema).then_some(JitType::Bool),
+                    (l, r) if l.is_scalar() && r.is_scalar() => Some(JitType::Bool),
+                    _ => None,
+                },
+                BinaryOp::Lt
+                | BinaryOp::Le
+                | BinaryOp::Gt
+                | BinaryOp::Ge
+                | BinaryOp::And
+                | BinaryOp::Or => {
+                    (left_ty.is_scalar() && right_ty.is_scalar()).then_some(JitType::Bool)
+                }
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                    (left_ty.is_scalar() && right_ty.is_scalar()).then_some(JitType::Number)
+                }
+                BinaryOp::In | BinaryOp::NotIn | BinaryOp::Contains => None,
+            }
+        }
+        Expr::Unary { op, operand } => {
+            let ty = infer_type(operand, schema)?;
+            if !ty.is_scalar() {
+                return None;
+            }
+            Some(match op {
+                UnaryOp::Not => JitType::Bool,
+                UnaryOp::Neg => JitType::Number,
+            })
+        }
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            let cond = infer_type(condition, schema)?;
+            let then_ty = infer_type(then_branch, schema)?;
+            let else_ty = infer_type(else_branch, schema)?;
+            if !(cond.is_scalar() && then_ty.is_scalar() && else_ty.is_scalar()) {
+                return None;
+            }
+            if then_ty == JitType::Bool && else_ty == JitType::Bool {
+                Some(JitType::Bool)
+            } else {
+                Some(JitType::Number)
+            }
+        }
+        Expr::Call { name, args } => match name.as_str() {
+            "starts_with" => {
+                let ok = args.len() == 2
+                    && matches!(&args[0], Expr::Field(_))
+                    && infer_type(&args[0], schema) == Some(JitType::Str)
+                    && matches!(&args[1], Expr::Literal(Value::String(_)));
+                ok.then_some(JitType::Bool)
+            }
+            "abs" | "floor" | "ceil" | "round" | "sqrt" | "min" | "max" => {
+                let arity = if matches!(name.as_str(), "min" | "max") {
+                    2
+                } else {
+                    1
+                };
+                let ok = args.len() == arity
+                    && args
+                        .iter()
+                        .all(|a| infer_type(a, schema).is_some_and(JitType::is_scalar));
+                ok.then_some(JitType::Number)
+            }
+            _ => None,
+        },
+        // Not supported: Array, Object, Coalesce
+        _ => None,
+    }
+}
+
+/// Check whether an expression is a reference to an `Optional` schema field
+fn is_optional_field(expr: &Expr, schema: &MessageSchema) -> bool {
+    match expr {
+        Expr::Field(name) => schema
+            .resolve_field_path(name)
+            .is_some_and(|f| matches!(f.field_type, FieldType::Optional(_))),
+        _ => false,
+    }
+}
+
+/// Extract the literal elements of the right-hand side of `in` / `not in`
+fn literal_set(expr: &Expr) -> Option<Vec<Value>> {
+    match expr {
+        Expr::Literal(Value::Array(items)) => Some(items.clone()),
+        Expr::Array(items) => items
+            .iter()
+            .map(|e| match e {
+                Expr::Literal(v) => Some(v.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+// ==================== Literal Interning ====================
+
+/// Owner of all literal data referenced by generated code
+///
+/// Compiled functions embed raw pointers into this pool, so it lives as long
+/// as the compiler (and therefore the JIT module).
+#[derive(Default)]
+pub(crate) struct LiteralPool {
+    /// Interned string literals
+    strings: hashbrown::HashSet<Arc<str>>,
+    /// Perfect-hash sets for string membership tests (boxed so addresses stay stable)
+    #[allow(clippy::vec_box)]
+    sets: Vec<Box<StringSet>>,
+}
+
+impl LiteralPool {
+    /// Intern a string literal, returning its stable address and length
+    fn intern(&mut self, s: &str) -> (*const u8, usize) {
+        if let Some(existing) = self.strings.get(s) {
+            return (existing.as_ptr(), existing.len());
+        }
+        let interned: Arc<str> = Arc::from(s);
+        let addr = (interned.as_ptr(), interned.len());
+        self.strings.insert(interned);
+        addr
+    }
+
+    /// Build and retain a string set, returning its stable address
+    fn intern_set(&mut self, items: &[Value]) -> *const StringSet {
+        let strings: Vec<Arc<str>> = items
+            .iter()
+            .filter_map(|v| match v {
+                Value::String(s) => Some(Arc::clone(s)),
+                _ => None,
+            })
+            .collect();
+        let set = Box::new(StringSet::build(&strings));
+        let ptr = &*set as *const StringSet;
+        self.sets.push(set);
+        ptr
+    }
+}
+
+/// Perfect-hash table used for `in` / `not in` against literal string arrays
+///
+/// The seed is searched at compile time so that every element lands in its
+/// own slot; a lookup is one hash, one mask and at most one string compare.
+pub(crate) struct StringSet {
+    seed: u64,
+    mask: usize,
+    slots: Vec<Option<Arc<str>>>,
+}
+
+impl StringSet {
+    /// Maximum number of seeds tried per table size before growing the table
+    const SEED_ATTEMPTS: u64 = 64;
+
+    fn build(items: &[Arc<str>]) -> Self {
+        let mut unique: Vec<Arc<str>> = items.to_vec();
+        unique.sort();
+        unique.dedup();
+
+        let mut size = (unique.len() * 2).next_power_of_two().max(2);
+        loop {
+            for seed in 0..Self::SEED_ATTEMPTS {
+                let mask = size - 1;
+                let mut slots: Vec<Option<Arc<str>>> = vec![None; size];
+                let collision_free = unique.iter().all(|item| {
+                    let slot = &mut slots[Self::hash(seed, item) as usize & mask];
+                    if slot.is_some() {
+                        return false;
+                    }
+                    *slot = Some(Arc::clone(item));
+                    true
+                });
+                if collision_free {
+                    return Self { seed, mask, slots };
+                }
+            }
+            size *= 2;
+        }
+    }
+
+    /// Seeded FNV-1a
+    #[inline]
+    fn hash(seed: u64, s: &str) -> u64 {
+        let mut h = 0xcbf2_9ce4_8422_2325u64 ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        for b in s.as_bytes() {
+            h ^= *b as u64;
+            h = h.wrapping_mul(0x0100_0000_01b3);
+        }
+        h
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, s: &str) -> bool {
+        match &self.slots[Self::hash(self.seed, s) as usize & self.mask] {
+            Some(item) => item.as_ref() == s,
+            None => false,
+        }
+    }
+}
+
+// ==================== Runtime Helpers ====================
+
+/// Returned by string helpers when an `Optional` string field is absent
+const GUARD_FAILED: u32 = 2;
+
+/// Scalar type tags passed to the optional-value helpers
+#[repr(u32)]
+#[derive(Debug, Clone, Copy)]
+enum ScalarTag {
+    Bool = 0,
+    Int32 = 1,
+    Int64 = 2,
+    UInt32 = 3,
+    UInt64 = 4,
+    Float32 = 5,
+    Float64 = 6,
+}
+
+impl ScalarTag {
+    fn of(field_type: &FieldType) -> Option<Self> {
+        Some(match field_type {
+            FieldType::Bool => Self::Bool,
+            FieldType::Int32 | FieldType::Enum(_) => Self::Int32,
+            FieldType::Int64 => Self::Int64,
+            FieldType::UInt32 => Self::UInt32,
+            FieldType::UInt64 => Self::UInt64,
+            FieldType::Float32 => Self::Float32,
+            FieldType::Float64 => Self::Float64,
+            _ => return None,
+        })
+    }
+}
+
+/// Read a `String` (or `Option<String>` when `optional != 0`) field
+///
+/// # Safety
+///
+/// `ptr` must point to a live value of the indicated type.
+#[inline]
+unsafe fn jit_str<'a>(ptr: *const u8, optional: u32) -> Option<&'a str> {
+    if optional != 0 {
+        (*(ptr as *const Option<String>)).as_deref()
+    } else {
+        Some((*(ptr as *const String)).as_str())
+    }
+}
+
+/// Rebuild an interned literal from its address and length
+///
+/// # Safety
+///
+/// `ptr`/`len` must come from [`LiteralPool::intern`].
+#[inline]
+unsafe fn jit_literal<'a>(ptr: *const u8, len: usize) -> &'a str {
+    std::str::from_utf8_unchecked(std::slice::from_raw_parts(ptr, len))
+}
+
+extern "C" fn ordo_jit_str_eq(field: *const u8, optional: u32, lit: *const u8, len: usize) -> u32 {
+    unsafe {
+        match jit_str(field, optional) {
+            Some(s) => (s == jit_literal(lit, len)) as u32,
+            None => GUARD_FAILED,
+        }
+    }
+}
+
+extern "C" fn ordo_jit_str_eq_field(a: *const u8, a_opt: u32, b: *const u8, b_opt: u32) -> u32 {
+    unsafe {
+        match (jit_str(a, a_opt), jit_str(b, b_opt)) {
+            (Some(a), Some(b)) => (a == b) as u32,
+            _ => GUARD_FAILED,
+        }
+    }
+}
+
+extern "C" fn ordo_jit_str_starts_with(
+    field: *const u8,
+    optional: u32,
+    lit: *const u8,
+    len: usize,
+) -> u32 {
+    unsafe {
+        match jit_str(field, optional) {
+            Some(s) => s.starts_with(jit_literal(lit, len)) as u32,
+            None => GUARD_FAILED,
+        }
+    }
+}
+
+extern "C" fn ordo_jit_str_in_set(field: *const u8, optional: u32, set: *const StringSet) -> u32 {
+    unsafe {
+        match jit_str(field, optional) {
+            Some(s) => (*set).contains(s) as u32,
+            None => GUARD_FAILED,
+        }
+    }
+}
+
+extern "C" fn ordo_jit_opt_is_some(ptr: *const u8, tag: u32) -> u32 {
+    unsafe {
+        let some = match tag {
+            t if t == ScalarTag::Bool as u32 => (*(ptr as *const Option<bool>)).is_some(),
+            t if t == ScalarTag::Int32 as u32 => (*(ptr as *const Option<i32>)).is_some(),
+            t if t == ScalarTag::Int64 as u32 => (*(ptr as *const Option<i64>)).is_some(),
+            t if t == ScalarTag::UInt32 as u32 => (*(ptr as *const Option<u32>)).is_some(),
+            t if t == ScalarTag::UInt64 as u32 => (*(ptr as *const Option<u64>)).is_some(),
+            t if t == ScalarTag::Float32 as u32 => (*(ptr as *const Option<f32>)).is_some(),
+            t if t == ScalarTag::Float64 as u32 => (*(ptr as *const Option<f64>)).is_some(),
+            _ => (*(ptr as *const Option<String>)).is_some(),
+        };
+        some as u32
+    }
+}
+
+extern "C" fn ordo_jit_opt_get(ptr: *const u8, tag: u32) -> f64 {
+    unsafe {
+        match tag {
+            t if t == ScalarTag::Bool as u32 => {
+                (*(ptr as *const Option<bool>)).map_or(0.0, |v| v as u8 as f64)
+            }
+            t if t == ScalarTag::Int32 as u32 => {
+                (*(ptr as *const Option<i32>)).map_or(0.0, |v| v as f64)
+            }
+            t if t == ScalarTag::Int64 as u32 => {
+                (*(ptr as *const Option<i64>)).map_or(0.0, |v| v as f64)
+            }
+            t if t == ScalarTag::UInt32 as u32 => {
+                (*(ptr as *const Option<u32>)).map_or(0.0, |v| v as f64)
+            }
+            t if t == ScalarTag::UInt64 as u32 => {
+                (*(ptr as *const Option<u64>)).map_or(0.0, |v| v as f64)
+            }
+            t if t == ScalarTag::Float32 as u32 => {
+                (*(ptr as *const Option<f32>)).map_or(0.0, |v| v as f64)
+            }
+            _ => (*(ptr as *const Option<f64>)).unwrap_or(0.0),
+        }
+    }
+}
+
+/// Runtime helpers declared in the JIT module
+struct RuntimeHelpers {
+    str_eq: FuncId,
+    str_eq_field: FuncId,
+    str_starts_with: FuncId,
+    str_in_set: FuncId,
+    opt_is_some: FuncId,
+    opt_get: FuncId,
+}
+
+/// Runtime helpers imported into the function being built
+#[derive(Clone, Copy)]
+pub(crate) struct HelperRefs {
+    str_eq: FuncRef,
+    str_eq_field: FuncRef,
+    str_starts_with: FuncRef,
+    str_in_set: FuncRef,
+    opt_is_some: FuncRef,
+    opt_get: FuncRef,
+}
+
+impl RuntimeHelpers {
+    /// Make the helper addresses resolvable by the JIT linker
+    fn register_symbols(builder: &mut JITBuilder) {
+        builder.symbol("ordo_jit_str_eq", ordo_jit_str_eq as *const u8);
+        builder.symbol("ordo_jit_str_eq_field", ordo_jit_str_eq_field as *const u8);
+        builder.symbol(
+            "ordo_jit_str_starts_with",
+            ordo_jit_str_starts_with as *const u8,
+        );
+        builder.symbol("ordo_jit_str_in_set", ordo_jit_str_in_set as *const u8);
+        builder.symbol("ordo_jit_opt_is_some", ordo_jit_opt_is_some as *const u8);
+        builder.symbol("ordo_jit_opt_get", ordo_jit_opt_get as *const u8);
+    }
+
+    /// Declare the helper signatures in the module
+    fn declare(module: &mut JITModule) -> Result<Self> {
+        let ptr = module.target_config().pointer_type();
+        let mut declare = |name: &str, params: &[Type], ret: Type| -> Result<FuncId> {
+            let mut sig = module.make_signature();
+            sig.params.extend(params.iter().map(|&t| AbiParam::new(t)));
+            sig.returns.push(AbiParam::new(ret));
+            module
+                .declare_function(name, Linkage::Import, &sig)
+                .map_err(|e| OrdoError::eval_error(format!("Failed to declare {}: {}", name, e)))
+        };
+
+        Ok(Self {
+            str_eq: declare("ordo_jit_str_eq", &[ptr, types::I32, ptr, ptr], types::I32)?,
+            str_eq_field: declare(
+                "ordo_jit_str_eq_field",
+                &[ptr, types::I32, ptr, types::I32],
+                types::I32,
+            )?,
+            str_starts_with: declare(
+                "ordo_jit_str_starts_with",
+                &[ptr, types::I32, ptr, ptr],
+                types::I32,
+            )?,
+            str_in_set: declare("ordo_jit_str_in_set", &[ptr, types::I32, ptr], types::I32)?,
+            opt_is_some: declare("ordo_jit_opt_is_some", &[ptr, types::I32], types::I32)?,
+            opt_get: declare("ordo_jit_opt_get", &[ptr, types::I32], types::F64)?,
+        })
+    }
+
+    /// Import the helpers into a function
+    fn import(&self, module: &mut JITModule, func: &mut codegen::ir::Function) -> HelperRefs {
+        HelperRefs {
+            str_eq: module.declare_func_in_func(self.str_eq, func),
+            str_eq_field: module.declare_func_in_func(self.str_eq_field, func),
+            str_starts_with: module.declare_func_in_func(self.str_starts_with, func),
+            str_in_set: module.declare_func_in_func(self.str_in_set, func),
+            opt_is_some: module.declare_func_in_func(self.opt_is_some, func),
+            opt_get: module.declare_func_in_func(self.opt_get, func),
+        }
+    }
+}
+
 // ==================== Compilation Context ====================
 
 /// Compilation context with schema information
-struct SchemaCompileContext<'a> {
-    field_offsets: &'a HashMap<String, ResolvedField>,
-    #[allow(dead_code)]
-    ptr_type: Type,
+pub(crate) struct SchemaCompileContext<'a> {
+    pub(crate) field_offsets: &'a HashMap<String, ResolvedField>,
+    pub(crate) ptr_type: Type,
+    /// Runtime helpers, absent when compiling code that must not call back
+    /// into this process (ahead-of-time objects)
+    pub(crate) helpers: Option<HelperRefs>,
+    pub(crate) literals: &'a mut LiteralPool,
+}
+
+impl SchemaCompileContext<'_> {
+    fn helpers(&self) -> Result<HelperRefs> {
+        self.helpers.ok_or_else(|| {
+            OrdoError::eval_error("Runtime helpers are not available to this code".to_string())
+        })
+    }
+
+    fn resolve(&self, field_name: &str) -> Result<&ResolvedField> {
+        self.field_offsets.get(field_name).ok_or_else(|| {
+            OrdoError::eval_error(format!("Field '{}' not found in schema", field_name))
+        })
+    }
+
+    /// Whether an operand is string-typed (string field or string literal)
+    fn is_string_operand(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Literal(Value::String(_)) => true,
+            Expr::Field(name) => {
+                self.field_offsets
+                    .get(name)
+                    .and_then(|f| field_jit_type(&f.field_type))
+                    == Some(JitType::Str)
+            }
+            _ => false,
+        }
+    }
 }
 
 // ==================== Expression Compilation ====================
@@ -414,34 +1029,55 @@ fn compile_expr_to_ir(
     expr: &Expr,
     ctx_ptr: cranelift::prelude::Value,
     result_ptr: cranelift::prelude::Value,
-    compile_ctx: &SchemaCompileContext,
+    compile_ctx: &mut SchemaCompileContext,
 ) -> Result<()> {
+    let val = compile_expr_value(builder, expr, ctx_ptr, compile_ctx)?;
+    builder.ins().store(MemFlags::new(), val, result_ptr, 0);
+    Ok(())
+}
+
+/// Compile an expression and return the value
+pub(crate) fn compile_expr_value(
+    builder: &mut FunctionBuilder,
+    expr: &Expr,
+    ctx_ptr: cranelift::prelude::Value,
+    compile_ctx: &mut SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
     match expr {
-        Expr::Literal(value) => {
-            let val = compile_literal(builder, value)?;
-            builder.ins().store(MemFlags::new(), val, result_ptr, 0);
-            Ok(())
-        }
+        Expr::Literal(value) => compile_literal(builder, value),
 
-        Expr::Binary { left, op, right } => {
-            let left_val = compile_expr_value(builder, left, ctx_ptr, compile_ctx)?;
-            let right_val = compile_expr_value(builder, right, ctx_ptr, compile_ctx)?;
-            let result = compile_binary_op(builder, *op, left_val, right_val)?;
-            builder.ins().store(MemFlags::new(), result, result_ptr, 0);
-            Ok(())
-        }
+        Expr::Binary { left, op, right } => match op {
+            BinaryOp::In | BinaryOp::NotIn => {
+                compile_set_membership(builder, *op, left, right, ctx_ptr, compile_ctx)
+            }
+            BinaryOp::Eq | BinaryOp::Ne
+                if matches!(left.as_ref(), Expr::Literal(Value::Null))
+                    || matches!(right.as_ref(), Expr::Literal(Value::Null)) =>
+            {
+                compile_null_check(builder, *op, left, right, ctx_ptr, compile_ctx)
+            }
+            BinaryOp::Eq | BinaryOp::Ne
+                if compile_ctx.is_string_operand(left) || compile_ctx.is_string_operand(right) =>
+            {
+                compile_string_eq(builder, *op, left, right, ctx_ptr, compile_ctx)
+            }
+            _ => {
+                let left_val = compile_expr_value(builder, left, ctx_ptr, compile_ctx)?;
+                let right_val = compile_expr_value(builder, right, ctx_ptr, compile_ctx)?;
+                compile_binary_op(builder, *op, left_val, right_val)
+            }
+        },
 
         Expr::Unary { op, operand } => {
             let val = compile_expr_value(builder, operand, ctx_ptr, compile_ctx)?;
-            let result = compile_unary_op(builder, *op, val)?;
-            builder.ins().store(MemFlags::new(), result, result_ptr, 0);
-            Ok(())
+            compile_unary_op(builder, *op, val)
         }
 
-        Expr::Field(name) => {
-            let val = compile_field_access_direct(builder, ctx_ptr, name, compile_ctx)?;
-            builder.ins().store(MemFlags::new(), val, result_ptr, 0);
-            Ok(())
+        Expr::Field(name) => compile_field_access_direct(builder, ctx_ptr, name, compile_ctx),
+
+        Expr::Exists(name) => {
+            let present = compile_presence(builder, ctx_ptr, name, compile_ctx)?;
+            Ok(flag_to_f64(builder, present))
         }
 
         Expr::Conditional {
@@ -454,43 +1090,13 @@ fn compile_expr_to_ir(
             then_branch,
             else_branch,
             ctx_ptr,
-            result_ptr,
             compile_ctx,
         ),
 
-        Expr::Call { name, args } => {
-            compile_math_function(builder, name, args, ctx_ptr, result_ptr, compile_ctx)
+        Expr::Call { name, args } if name == "starts_with" => {
+            compile_starts_with(builder, args, ctx_ptr, compile_ctx)
         }
 
-        _ => Err(OrdoError::eval_error(
-            "Unsupported expression for Schema JIT".to_string(),
-        )),
-    }
-}
-
-/// Compile an expression and return the value
-fn compile_expr_value(
-    builder: &mut FunctionBuilder,
-    expr: &Expr,
-    ctx_ptr: cranelift::prelude::Value,
-    compile_ctx: &SchemaCompileContext,
-) -> Result<cranelift::prelude::Value> {
-    match expr {
-        Expr::Literal(value) => compile_literal(builder, value),
-
-        Expr::Binary { left, op, right } => {
-            let left_val = compile_expr_value(builder, left, ctx_ptr, compile_ctx)?;
-            let right_val = compile_expr_value(builder, right, ctx_ptr, compile_ctx)?;
-            compile_binary_op(builder, *op, left_val, right_val)
-        }
-
-        Expr::Unary { op, operand } => {
-            let val = compile_expr_value(builder, operand, ctx_ptr, compile_ctx)?;
-            compile_unary_op(builder, *op, val)
-        }
-
-        Expr::Field(name) => compile_field_access_direct(builder, ctx_ptr, name, compile_ctx),
-
         Expr::Call { name, args } => {
             compile_math_function_value(builder, name, args, ctx_ptr, compile_ctx)
         }
@@ -524,9 +1130,7 @@ fn compile_field_access_direct(
     field_name: &str,
     compile_ctx: &SchemaCompileContext,
 ) -> Result<cranelift::prelude::Value> {
-    let resolved = compile_ctx.field_offsets.get(field_name).ok_or_else(|| {
-        OrdoError::eval_error(format!("Field '{}' not found in schema", field_name))
-    })?;
+    let resolved = compile_ctx.resolve(field_name)?;
 
     let offset = resolved.offset;
 
@@ -589,6 +1193,26 @@ fn compile_field_access_direct(
             let int64 = builder.ins().sextend(types::I64, int_val);
             Ok(builder.ins().fcvt_from_sint(types::F64, int64))
         }
+        FieldType::Optional(inner) => {
+            // Guard on presence, then read the value through the runtime helper
+            let tag = ScalarTag::of(inner).ok_or_else(|| {
+                OrdoError::eval_error(format!(
+                    "Optional field '{}' is not numeric in Schema JIT",
+                    field_name
+                ))
+            })?;
+            let field_ptr = builder.ins().iadd_imm(ctx_ptr, offset as i64);
+            let tag = builder.ins().iconst(types::I32, tag as i64);
+            let call = builder
+                .ins()
+                .call(compile_ctx.helpers()?.opt_is_some, &[field_ptr, tag]);
+            let present = builder.inst_results(call)[0];
+            emit_guard(builder, present);
+            let call = builder
+                .ins()
+                .call(compile_ctx.helpers()?.opt_get, &[field_ptr, tag]);
+            Ok(builder.inst_results(call)[0])
+        }
         _ => Err(OrdoError::eval_error(format!(
             "Field type {:?} not supported for Schema JIT",
             resolved.field_type
@@ -596,6 +1220,255 @@ fn compile_field_access_direct(
     }
 }
 
+/// Emit a guard: continue if `ok` is non-zero, otherwise return `NullValue`
+fn emit_guard(builder: &mut FunctionBuilder, ok: cranelift::prelude::Value) {
+    let cont_block = builder.create_block();
+    let bail_block = builder.create_block();
+    builder.ins().brif(ok, cont_block, &[], bail_block, &[]);
+
+    builder.switch_to_block(bail_block);
+    builder.seal_block(bail_block);
+    let code = builder
+        .ins()
+        .iconst(types::I32, SchemaJITErrorCode::NullValue as i64);
+    builder.ins().return_(&[code]);
+
+    builder.switch_to_block(cont_block);
+    builder.seal_block(cont_block);
+}
+
+/// Emit a guard on a string helper result and return it as a 0/1 flag
+fn guard_string_result(
+    builder: &mut FunctionBuilder,
+    result: cranelift::prelude::Value,
+) -> cranelift::prelude::Value {
+    let ok = builder
+        .ins()
+        .icmp_imm(IntCC::NotEqual, result, GUARD_FAILED as i64);
+    emit_guard(builder, ok);
+    result
+}
+
+/// Convert an integer flag (0 = false) to 1.0 / 0.0
+fn flag_to_f64(
+    builder: &mut FunctionBuilder,
+    flag: cranelift::prelude::Value,
+) -> cranelift::prelude::Value {
+    let is_set = builder.ins().icmp_imm(IntCC::NotEqual, flag, 0);
+    let one = builder.ins().f64const(1.0);
+    let zero = builder.ins().f64const(0.0);
+    builder.ins().select(is_set, one, zero)
+}
+
+/// Compute the address of a string field and whether it is optional
+fn string_field_operand(
+    builder: &mut FunctionBuilder,
+    ctx_ptr: cranelift::prelude::Value,
+    field_name: &str,
+    compile_ctx: &SchemaCompileContext,
+) -> Result<(cranelift::prelude::Value, cranelift::prelude::Value)> {
+    let resolved = compile_ctx.resolve(field_name)?;
+    let optional = match &resolved.field_type {
+        FieldType::String => 0,
+        FieldType::Optional(inner) if matches!(inner.as_ref(), FieldType::String) => 1,
+        other => {
+            return Err(OrdoError::eval_error(format!(
+                "Field '{}' of type {:?} is not a string",
+                field_name, other
+            )))
+        }
+    };
+    let field_ptr = builder.ins().iadd_imm(ctx_ptr, resolved.offset as i64);
+    let optional = builder.ins().iconst(types::I32, optional);
+    Ok((field_ptr, optional))
+}
+
+/// Materialize an interned string literal as (address, length) constants
+fn string_literal_operand(
+    builder: &mut FunctionBuilder,
+    literal: &str,
+    compile_ctx: &mut SchemaCompileContext,
+) -> (cranelift::prelude::Value, cranelift::prelude::Value) {
+    let (ptr, len) = compile_ctx.literals.intern(literal);
+    let ptr = builder.ins().iconst(compile_ctx.ptr_type, ptr as i64);
+    let len = builder.ins().iconst(compile_ctx.ptr_type, len as i64);
+    (ptr, len)
+}
+
+/// Compile `==` / `!=` where at least one side is a string
+fn compile_string_eq(
+    builder: &mut FunctionBuilder,
+    op: BinaryOp,
+    left: &Expr,
+    right: &Expr,
+    ctx_ptr: cranelift::prelude::Value,
+    compile_ctx: &mut SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
+    let result = match (left, right) {
+        (Expr::Field(a), Expr::Field(b)) => {
+            let (a_ptr, a_opt) = string_field_operand(builder, ctx_ptr, a, compile_ctx)?;
+            let (b_ptr, b_opt) = string_field_operand(builder, ctx_ptr, b, compile_ctx)?;
+            let call = builder.ins().call(
+                compile_ctx.helpers()?.str_eq_field,
+                &[a_ptr, a_opt, b_ptr, b_opt],
+            );
+            builder.inst_results(call)[0]
+        }
+        (Expr::Field(field), Expr::Literal(Value::String(lit)))
+        | (Expr::Literal(Value::String(lit)), Expr::Field(field)) => {
+            let (field_ptr, optional) = string_field_operand(builder, ctx_ptr, field, compile_ctx)?;
+            let (lit_ptr, lit_len) = string_literal_operand(builder, lit, compile_ctx);
+            let call = builder.ins().call(
+                compile_ctx.helpers()?.str_eq,
+                &[field_ptr, optional, lit_ptr, lit_len],
+            );
+            builder.inst_results(call)[0]
+        }
+        _ => {
+            return Err(OrdoError::eval_error(
+                "Unsupported string comparison for Schema JIT".to_string(),
+            ))
+        }
+    };
+
+    let flag = guard_string_result(builder, result);
+    let flag = if op == BinaryOp::Ne {
+        builder.ins().bxor_imm(flag, 1)
+    } else {
+        flag
+    };
+    Ok(flag_to_f64(builder, flag))
+}
+
+/// Compile `starts_with(field, "literal")`
+fn compile_starts_with(
+    builder: &mut FunctionBuilder,
+    args: &[Expr],
+    ctx_ptr: cranelift::prelude::Value,
+    compile_ctx: &mut SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
+    match args {
+        [Expr::Field(field), Expr::Literal(Value::String(prefix))] => {
+            let (field_ptr, optional) = string_field_operand(builder, ctx_ptr, field, compile_ctx)?;
+            let (lit_ptr, lit_len) = string_literal_operand(builder, prefix, compile_ctx);
+            let call = builder.ins().call(
+                compile_ctx.helpers()?.str_starts_with,
+                &[field_ptr, optional, lit_ptr, lit_len],
+            );
+            let result = builder.inst_results(call)[0];
+            let flag = guard_string_result(builder, result);
+            Ok(flag_to_f64(builder, flag))
+        }
+        _ => Err(OrdoError::eval_error(
+            "starts_with requires a string field and a string literal".to_string(),
+        )),
+    }
+}
+
+/// Compile `in` / `not in` against a literal array
+fn compile_set_membership(
+    builder: &mut FunctionBuilder,
+    op: BinaryOp,
+    left: &Expr,
+    right: &Expr,
+    ctx_ptr: cranelift::prelude::Value,
+    compile_ctx: &mut SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
+    let items = literal_set(right).ok_or_else(|| {
+        OrdoError::eval_error("Set membership requires a literal array".to_string())
+    })?;
+
+    let flag = if compile_ctx.is_string_operand(left) {
+        let Expr::Field(field) = left else {
+            return Err(OrdoError::eval_error(
+                "String set membership requires a field operand".to_string(),
+            ));
+        };
+        let (field_ptr, optional) = string_field_operand(builder, ctx_ptr, field, compile_ctx)?;
+        let set_ptr = compile_ctx.literals.intern_set(&items);
+        let set_ptr = builder.ins().iconst(compile_ctx.ptr_type, set_ptr as i64);
+        let call = builder.ins().call(
+            compile_ctx.helpers()?.str_in_set,
+            &[field_ptr, optional, set_ptr],
+        );
+        let result = builder.inst_results(call)[0];
+        guard_string_result(builder, result)
+    } else {
+        // Sorted compare chain over the distinct numeric constants
+        let mut constants: Vec<f64> = items.iter().filter_map(Value::as_float).collect();
+        constants.sort_by(|a, b| a.total_cmp(b));
+        constants.dedup();
+
+        let val = compile_expr_value(builder, left, ctx_ptr, compile_ctx)?;
+        let mut found = builder.ins().iconst(types::I8, 0);
+        for c in constants {
+            let c = builder.ins().f64const(c);
+            let eq = builder.ins().fcmp(FloatCC::Equal, val, c);
+            found = builder.ins().bor(found, eq);
+        }
+        builder.ins().uextend(types::I32, found)
+    };
+
+    let flag = if op == BinaryOp::NotIn {
+        builder.ins().bxor_imm(flag, 1)
+    } else {
+        flag
+    };
+    Ok(flag_to_f64(builder, flag))
+}
+
+/// Compile the presence test behind `exists(field)` and null comparisons
+fn compile_presence(
+    builder: &mut FunctionBuilder,
+    ctx_ptr: cranelift::prelude::Value,
+    field_name: &str,
+    compile_ctx: &SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
+    let resolved = compile_ctx.resolve(field_name)?;
+    match &resolved.field_type {
+        FieldType::Optional(inner) => {
+            // Strings use the (out-of-range) string tag
+            let tag = ScalarTag::of(inner).map_or(u32::MAX, |t| t as u32);
+            let field_ptr = builder.ins().iadd_imm(ctx_ptr, resolved.offset as i64);
+            let tag = builder.ins().iconst(types::I32, tag as i64);
+            let call = builder
+                .ins()
+                .call(compile_ctx.helpers()?.opt_is_some, &[field_ptr, tag]);
+            Ok(builder.inst_results(call)[0])
+        }
+        // Non-optional fields are always present
+        _ => Ok(builder.ins().iconst(types::I32, 1)),
+    }
+}
+
+/// Compile `field == null` / `field != null`
+fn compile_null_check(
+    builder: &mut FunctionBuilder,
+    op: BinaryOp,
+    left: &Expr,
+    right: &Expr,
+    ctx_ptr: cranelift::prelude::Value,
+    compile_ctx: &SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
+    let field = match (left, right) {
+        (Expr::Field(f), Expr::Literal(Value::Null))
+        | (Expr::Literal(Value::Null), Expr::Field(f)) => f,
+        _ => {
+            return Err(OrdoError::eval_error(
+                "Null comparison requires a field operand".to_string(),
+            ))
+        }
+    };
+    let present = compile_presence(builder, ctx_ptr, field, compile_ctx)?;
+    // `== null` is true when absent, `!= null` when present
+    let flag = if op == BinaryOp::Eq {
+        builder.ins().bxor_imm(present, 1)
+    } else {
+        present
+    };
+    Ok(flag_to_f64(builder, flag))
+}
+
 /// Compile a binary operation
 fn compile_binary_op(
     builder: &mut FunctionBuilder,
@@ -672,10 +1545,10 @@ fn compile_binary_op(
             builder.ins().select(or_result, one, zero)
         }
 
-        // String operations not supported
+        // Set operations are compiled separately (see `compile_set_membership`)
         BinaryOp::In | BinaryOp::NotIn | BinaryOp::Contains => {
             return Err(OrdoError::eval_error(
-                "String operations not supported in Schema JIT".to_string(),
+                "Set operation not supported on numeric operands in Schema JIT".to_string(),
             ))
         }
     };
@@ -709,15 +1582,15 @@ fn compile_conditional(
     then_expr: &Expr,
     else_expr: &Expr,
     ctx_ptr: cranelift::prelude::Value,
-    result_ptr: cranelift::prelude::Value,
-    compile_ctx: &SchemaCompileContext,
-) -> Result<()> {
+    compile_ctx: &mut SchemaCompileContext,
+) -> Result<cranelift::prelude::Value> {
     let cond_val = compile_expr_value(builder, condition, ctx_ptr, compile_ctx)?;
 
     // Create blocks
     let then_block = builder.create_block();
     let else_block = builder.create_block();
     let merge_block = builder.create_block();
+    builder.append_block_param(merge_block, types::F64);
 
     // Branch based on condition
     let zero = builder.ins().f64const(0.0);
@@ -730,39 +1603,19 @@ fn compile_conditional(
     builder.switch_to_block(then_block);
     builder.seal_block(then_block);
     let then_val = compile_expr_value(builder, then_expr, ctx_ptr, compile_ctx)?;
-    builder
-        .ins()
-        .store(MemFlags::new(), then_val, result_ptr, 0);
-    builder.ins().jump(merge_block, &[]);
+    builder.ins().jump(merge_block, &[then_val]);
 
     // Else block
     builder.switch_to_block(else_block);
     builder.seal_block(else_block);
     let else_val = compile_expr_value(builder, else_expr, ctx_ptr, compile_ctx)?;
-    builder
-        .ins()
-        .store(MemFlags::new(), else_val, result_ptr, 0);
-    builder.ins().jump(merge_block, &[]);
+    builder.ins().jump(merge_block, &[else_val]);
 
     // Merge block
     builder.switch_to_block(merge_block);
     builder.seal_block(merge_block);
 
-    Ok(())
-}
-
-/// Compile a math function call
-fn compile_math_function(
-    builder: &mut FunctionBuilder,
-    name: &str,
-    args: &[Expr],
-    ctx_ptr: cranelift::prelude::Value,
-    result_ptr: cranelift::prelude::Value,
-    compile_ctx: &SchemaCompileContext,
-) -> Result<()> {
-    let result = compile_math_function_value(builder, name, args, ctx_ptr, compile_ctx)?;
-    builder.ins().store(MemFlags::new(), result, result_ptr, 0);
-    Ok(())
+    Ok(builder.block_params(merge_block)[0])
 }
 
 /// Compile a math function and return the value
@@ -771,7 +1624,7 @@ fn compile_math_function_value(
     name: &str,
     args: &[Expr],
     ctx_ptr: cranelift::prelude::Value,
-    compile_ctx: &SchemaCompileContext,
+    compile_ctx: &mut SchemaCompileContext,
 ) -> Result<cranelift::prelude::Value> {
     match name {
         "abs" => {
@@ -844,23 +1697,6 @@ fn compile_math_function_value(
             Ok(builder.ins().fmax(a, b))
         }
 
-        // For pow, we need to use a runtime call since Cranelift doesn't have a native pow
-        // For now, we approximate using exp and log: pow(a, b) = exp(b * log(a))
-        "pow" => {
-            if args.len() != 2 {
-                return Err(OrdoError::eval_error(
-                    "pow requires 2 arguments".to_string(),
-                ));
-            }
-            // Note: pow is complex to implement in pure IR
-            // For a complete implementation, we would need to call a runtime function
-            // For now, return an error suggesting to use other approaches
-            Err(OrdoError::eval_error(
-                "pow function requires runtime support, not yet implemented in Schema JIT"
-                    .to_string(),
-            ))
-        }
-
         _ => Err(OrdoError::eval_error(format!(
             "Function '{}' not supported in Schema JIT",
             name
@@ -1041,4 +1877,191 @@ mod tests {
         assert_eq!(stats.schema_compiles, 1);
         assert_eq!(stats.failed_compiles, 0);
     }
+
+    #[repr(C)]
+    struct Customer {
+        score: f64,
+        tier: String,
+        region: Option<String>,
+        discount: Option<f64>,
+        active: bool,
+    }
+
+    fn customer_schema() -> MessageSchema {
+        MessageSchema::builder("Customer")
+            .field_at(
+                "score",
+                FieldType::Float64,
+                std::mem::offset_of!(Customer, score),
+            )
+            .field_at(
+                "tier",
+                FieldType::String,
+                std::mem::offset_of!(Customer, tier),
+            )
+            .field_at(
+                "region",
+                FieldType::Optional(Box::new(FieldType::String)),
+                std::mem::offset_of!(Customer, region),
+            )
+            .field_at(
+                "discount",
+                FieldType::Optional(Box::new(FieldType::Float64)),
+                std::mem::offset_of!(Customer, discount),
+            )
+            .field_at(
+                "active",
+                FieldType::Bool,
+                std::mem::offset_of!(Customer, active),
+            )
+            .build()
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            score: 720.0,
+            tier: "gold".to_string(),
+            region: Some("eu-west".to_string()),
+            discount: None,
+            active: true,
+        }
+    }
+
+    fn compile(compiler: &mut SchemaJITCompiler, source: &str) -> SchemaCompiledFunction {
+        let expr = crate::expr::ExprParser::parse(source).unwrap();
+        let cache = SchemaJITCache::default();
+        compiler
+            .compile_with_schema(&expr, 0, &customer_schema(), &cache)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_string_equality_and_prefix() {
+        let mut compiler = SchemaJITCompiler::new().unwrap();
+        let ctx = customer();
+
+        let cases = [
+            (r#"tier == "gold""#, true),
+            (r#""silver" == tier"#, false),
+            (r#"tier != "silver""#, true),
+            (r#"starts_with(region, "eu-")"#, true),
+            (r#"tier == "gold" && score >= 700"#, true),
+        ];
+        for (source, expected) in cases {
+            let compiled = compile(&mut compiler, source);
+            assert_eq!(compiled.result_type, SchemaResultType::Bool, "{}", source);
+            let result = unsafe { compiled.call_typed_value(&ctx).unwrap() };
+            assert_eq!(result, Value::Bool(expected), "{}", source);
+        }
+
+        // Identical literals share a single interned copy
+        compile(&mut compiler, r#"tier == "gold" || tier == "gold""#);
+        assert_eq!(compiler.stats().interned_literals, 3);
+    }
+
+    #[test]
+    fn test_set_membership() {
+        let mut compiler = SchemaJITCompiler::new().unwrap();
+        let ctx = customer();
+
+        let cases = [
+            (r#"tier in ["gold", "platinum", "diamond"]"#, true),
+            (r#"tier in ["bronze", "silver"]"#, false),
+            (r#"tier not in ["bronze", "silver"]"#, true),
+            (r#"region in ["us-east", "eu-west"]"#, true),
+            ("score in [650, 720, 800]", true),
+            ("score not in [650, 720, 800]", false),
+        ];
+        for (source, expected) in cases {
+            let compiled = compile(&mut compiler, source);
+            let result = unsafe { compiled.call_typed_value(&ctx).unwrap() };
+            assert_eq!(result, Value::Bool(expected), "{}", source);
+        }
+        assert_eq!(compiler.stats().string_sets, 4);
+    }
+
+    #[test]
+    fn test_string_set_lookup() {
+        let items: Vec<Arc<str>> = (0..100).map(|i| Arc::from(format!("item-{}", i))).collect();
+        let set = StringSet::build(&items);
+        assert!(items.iter().all(|item| set.contains(item)));
+        assert!(!set.contains("item-100"));
+        assert!(!set.contains(""));
+    }
+
+    #[test]
+    fn test_optional_fields() {
+        let mut compiler = SchemaJITCompiler::new().unwrap();
+        let mut ctx = customer();
+
+        let present = compile(&mut compiler, "region != null");
+        let absent = compile(&mut compiler, "discount == null");
+        let exists = compile(&mut compiler, "exists(discount)");
+        let read = compile(&mut compiler, "score - discount");
+
+        unsafe {
+            assert_eq!(present.call_typed_value(&ctx).unwrap(), Value::Bool(true));
+            assert_eq!(absent.call_typed_value(&ctx).unwrap(), Value::Bool(true));
+            assert_eq!(exists.call_typed_value(&ctx).unwrap(), Value::Bool(false));
+            // Reading an absent value fails the guard
+            assert_eq!(
+                read.call_typed_checked(&ctx),
+                Err(SchemaJITErrorCode::NullValue)
+            );
+
+            ctx.discount = Some(20.0);
+            assert_eq!(exists.call_typed_value(&ctx).unwrap(), Value::Bool(true));
+            assert_eq!(read.call_typed_value(&ctx).unwrap(), Value::Float(700.0));
+
+            ctx.region = None;
+            let compiled = compile(&mut compiler, r#"region == "eu-west""#);
+            assert_eq!(
+                compiled.call_typed_checked(&ctx),
+                Err(SchemaJITErrorCode::NullValue)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bool_result_and_nested_conditional() {
+        let mut compiler = SchemaJITCompiler::new().unwrap();
+        let ctx = customer();
+
+        let compiled = compile(&mut compiler, "active");
+        assert_eq!(compiled.result_type, SchemaResultType::Bool);
+        assert_eq!(
+            unsafe { compiled.call_typed_value(&ctx).unwrap() },
+            Value::Bool(true)
+        );
+
+        let compiled = compile(
+            &mut compiler,
+            r#"if tier == "gold" then (if score > 800 then 3 else 2) else 1"#,
+        );
+        assert_eq!(compiled.result_type, SchemaResultType::Number);
+        assert_eq!(
+            unsafe { compiled.call_typed_value(&ctx).unwrap() },
+            Value::Float(2.0)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_string_usage() {
+        let schema = customer_schema();
+        for source in [
+            "tier",
+            r#"tier > "a""#,
+            r#"tier + "x""#,
+            r#""a" == "b""#,
+            "pow(score, 2)",
+            "score == null",
+        ] {
+            let expr = crate::expr::ExprParser::parse(source).unwrap();
+            assert!(
+                !SchemaJITCompiler::can_compile_with_schema(&expr, &schema),
+                "{}",
+                source
+            );
+        }
+    }
 }
@@ -41,18 +41,27 @@
 //! let result = evaluator.eval_typed(&expr, &ctx)?;
 //! ```
 
-use super::schema_compiler::{SchemaJITCompiler, SchemaJITStats};
-use super::typed_context::TypedContext;
-use crate::context::{MessageSchema, SchemaRegistry, Value};
+use super::schema_compiler::{
+    schema_expr_hash, SchemaJITCompiler, SchemaJITErrorCode, SchemaJITStats,
+};
+use super::typed_context::{DynamicTypedContext, TypedContext};
+use crate::context::{Context, IString, MessageSchema, SchemaRegistry, Value};
 use crate::error::{OrdoError, Result};
-use crate::expr::profiler::{hash_expr, Profiler, ProfilerConfig};
+use crate::expr::profiler::{Profiler, ProfilerConfig};
 use crate::expr::{BytecodeVM, CompiledExpr, Evaluator, Expr, ExprCompiler, ExprOptimizer};
 
 use parking_lot::{Mutex, RwLock};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
+thread_local! {
+    /// Bytecode VM for non-JIT expressions; it reuses a register file, so each
+    /// thread gets its own and the evaluator can be shared across threads
+    static FALLBACK_VM: BytecodeVM = BytecodeVM::new();
+}
+
 /// Configuration for Schema-Aware JIT Evaluator
 #[derive(Debug, Clone)]
 pub struct SchemaJITEvaluatorConfig {
@@ -89,9 +98,6 @@ pub struct SchemaJITEvaluator {
     /// Standard evaluator (fallback for complex cases)
     #[allow(dead_code)]
     evaluator: Evaluator,
-    /// Bytecode VM (fallback for non-JIT expressions)
-    #[allow(dead_code)]
-    vm: BytecodeVM,
     /// Expression optimizer
     optimizer: Mutex<ExprOptimizer>,
     /// Profiler
@@ -100,6 +106,12 @@ pub struct SchemaJITEvaluator {
     schema_registry: RwLock<SchemaRegistry>,
     /// Compiled bytecode cache (for VM fallback)
     bytecode_cache: RwLock<HashMap<u64, CompiledExpr>>,
+    /// Number of JIT cache hits
+    jit_cache_hits: AtomicU64,
+    /// Evaluations of expressions the JIT cannot compile
+    vm_fallbacks: AtomicU64,
+    /// Evaluations where a JIT guard failed at runtime
+    guard_fallbacks: AtomicU64,
     /// Configuration
     config: SchemaJITEvaluatorConfig,
 }
@@ -115,11 +127,13 @@ impl SchemaJITEvaluator {
                 hashbrown::hash_map::DefaultHashBuilder::default(),
             )),
             evaluator: Evaluator::new(),
-            vm: BytecodeVM::new(),
             optimizer: Mutex::new(ExprOptimizer::new()),
             profiler: Arc::new(Profiler::with_config(config.profiler.clone())),
             schema_registry: RwLock::new(SchemaRegistry::new()),
             bytecode_cache: RwLock::new(HashMap::new()),
+            jit_cache_hits: AtomicU64::new(0),
+            vm_fallbacks: AtomicU64::new(0),
+            guard_fallbacks: AtomicU64::new(0),
             config,
         })
     }
@@ -144,88 +158,122 @@ impl SchemaJITEvaluator {
     /// This is the primary evaluation method for typed contexts.
     /// It uses Schema-Aware JIT when possible, falling back to BytecodeVM.
     pub fn eval_typed<C: TypedContext>(&self, expr: &Expr, ctx: &C) -> Result<Value> {
-        let schema = C::schema();
-        let hash = self.hash_expr_with_schema(expr, schema);
-        let schema_hash = hash; // `hash_expr_with_schema` already hashes the schema prefix
+        let ptr = ctx as *const C as *const u8;
+        self.eval_schema(expr, C::schema(), ptr, |path| unsafe {
+            ctx.read_field_value(path)
+        })
+        .map(|(value, _)| value)
+    }
+
+    /// Evaluate an expression with a context whose schema is known at runtime
+    ///
+    /// Used for messages decoded from descriptor sets. Returns the value and
+    /// whether it was computed by JIT code (as opposed to the VM fallback).
+    pub fn eval_dynamic(
+        &self,
+        expr: &Expr,
+        ctx: &DynamicTypedContext<'_>,
+    ) -> Result<(Value, bool)> {
+        self.eval_schema(expr, ctx.schema(), ctx.as_ptr(), |path| unsafe {
+            ctx.read_field_value(path)
+        })
+    }
+
+    /// Evaluate against memory at `ptr` laid out by `schema`
+    ///
+    /// `read_field` copies a field out for the VM fallback.
+    fn eval_schema(
+        &self,
+        expr: &Expr,
+        schema: &MessageSchema,
+        ptr: *const u8,
+        read_field: impl Fn(&str) -> Option<Value>,
+    ) -> Result<(Value, bool)> {
+        let hash = schema_expr_hash(expr, schema);
         let start = Instant::now();
 
         // Try to get cached JIT function (lock-free fast path)
-        if let Some(compiled) = self.jit_cache.get(&schema_hash) {
-            let result = unsafe { compiled.call_typed_value(ctx) };
-            if self.config.enable_profiling {
-                self.profiler.record_expr(hash, start.elapsed());
+        let cached = self.jit_cache.get(&hash).map(|c| c.value().clone());
+        let compiled = match cached {
+            Some(compiled) => {
+                self.jit_cache_hits.fetch_add(1, Ordering::Relaxed);
+                Some(compiled)
             }
-            return result;
-        }
-
-        // Try to compile with schema
-        if SchemaJITCompiler::can_compile_with_schema(expr, schema) {
-            let compiled = {
+            // Try to compile with schema
+            None if SchemaJITCompiler::can_compile_with_schema(expr, schema) => {
                 let mut compiler = self.compiler.lock();
-                compiler.compile_with_schema(expr, hash, schema, &self.jit_cache)?
-            };
+                Some(compiler.compile_with_schema(expr, hash, schema, &self.jit_cache)?)
+            }
+            None => None,
+        };
 
-            let result = unsafe { compiled.call_typed_value(ctx) };
-            if self.config.enable_profiling {
-                self.profiler.record_expr(hash, start.elapsed());
+        if let Some(compiled) = compiled {
+            match unsafe { compiled.call_ptr_checked(ptr) } {
+                Ok(raw) => {
+                    if self.config.enable_profiling {
+                        self.profiler.record_expr(hash, start.elapsed());
+                    }
+                    return Ok((compiled.to_value(raw), true));
+                }
+                // A runtime guard failed (e.g. an absent optional was read)
+                Err(SchemaJITErrorCode::NullValue) => {
+                    self.guard_fallbacks.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(code) => {
+                    return Err(OrdoError::eval_error(format!(
+                        "Schema JIT execution failed with code: {}",
+                        code as i32
+                    )))
+                }
             }
-            return result;
+        } else {
+            self.vm_fallbacks.fetch_add(1, Ordering::Relaxed);
         }
 
         // Fallback to BytecodeVM
-        self.eval_with_vm_typed(expr, ctx, hash, start)
+        self.eval_with_vm(expr, read_field, hash, start)
+            .map(|value| (value, false))
     }
 
     /// Evaluate with BytecodeVM (fallback path)
-    fn eval_with_vm_typed<C: TypedContext>(
+    ///
+    /// The fields referenced by the expression are copied out of the typed
+    /// context into a dynamic `Context`, so the VM sees the same types
+    /// (strings, integers, nulls) as the regular evaluator would.
+    fn eval_with_vm(
         &self,
         expr: &Expr,
-        ctx: &C,
+        read_field: impl Fn(&str) -> Option<Value>,
         hash: u64,
         start: Instant,
     ) -> Result<Value> {
-        // We need to convert TypedContext to a Context for the VM
-        // This is less efficient, but provides a fallback
-
-        // For now, we use the standard evaluator which can handle any expression
-        // In a real implementation, we might build a Value from the TypedContext
+        let context = Self::build_context(expr, read_field);
 
         // Check bytecode cache
-        {
-            let cache = self.bytecode_cache.read();
-            if let Some(_compiled) = cache.get(&hash) {
-                // We need a Context to execute, but we have a TypedContext
-                // For now, evaluate using the tree-walking evaluator
-                // This is a limitation of the fallback path
-                let result = self.eval_expr_tree_walk(expr, ctx)?;
-                if self.config.enable_profiling {
-                    self.profiler.record_expr(hash, start.elapsed());
-                }
-                return Ok(result);
-            }
-        }
-
-        // Optimize expression
-        let optimized = if self.config.constant_folding {
-            self.optimizer.lock().optimize(expr.clone())
-        } else {
-            expr.clone()
-        };
+        let cached = self.bytecode_cache.read().get(&hash).cloned();
+        let compiled = match cached {
+            Some(compiled) => compiled,
+            None => {
+                // Optimize expression
+                let optimized = if self.config.constant_folding {
+                    self.optimizer.lock().optimize(expr.clone())
+                } else {
+                    expr.clone()
+                };
 
-        // Compile to bytecode
-        let compiler = ExprCompiler::new();
-        let compiled = compiler.compile(&optimized);
+                // Compile to bytecode
+                let compiled = ExprCompiler::new().compile(&optimized);
 
-        // Cache it
-        {
-            let mut cache = self.bytecode_cache.write();
-            if cache.len() < self.config.max_cache_size {
-                cache.insert(hash, compiled);
+                // Cache it
+                let mut cache = self.bytecode_cache.write();
+                if cache.len() < self.config.max_cache_size {
+                    cache.insert(hash, compiled.clone());
+                }
+                compiled
             }
-        }
+        };
 
-        // Evaluate using tree-walking (since we don't have a Context)
-        let result = self.eval_expr_tree_walk(expr, ctx)?;
+        let result = FALLBACK_VM.with(|vm| vm.execute(&compiled, &context))?;
 
         if self.config.enable_profiling {
             self.profiler.record_expr(hash, start.elapsed());
@@ -234,216 +282,21 @@ impl SchemaJITEvaluator {
         Ok(result)
     }
 
-    /// Evaluate expression using tree-walking with TypedContext
-    fn eval_expr_tree_walk<C: TypedContext>(&self, expr: &Expr, ctx: &C) -> Result<Value> {
-        match expr {
-            Expr::Literal(v) => Ok(v.clone()),
-
-            Expr::Field(name) => {
-                // Use TypedContext to get field value
-                let value = unsafe { ctx.read_field_as_f64(name) };
-                match value {
-                    Some(v) => Ok(Value::Float(v)),
-                    None => Err(OrdoError::eval_error(format!(
-                        "Field '{}' not found or not numeric",
-                        name
-                    ))),
-                }
-            }
-
-            Expr::Binary { left, op, right } => {
-                let left_val = self.eval_expr_tree_walk(left, ctx)?;
-                let right_val = self.eval_expr_tree_walk(right, ctx)?;
-                self.apply_binary_op(*op, &left_val, &right_val)
-            }
-
-            Expr::Unary { op, operand } => {
-                let val = self.eval_expr_tree_walk(operand, ctx)?;
-                self.apply_unary_op(*op, &val)
-            }
+    /// Copy the fields accessed by `expr` into a dynamic context
+    fn build_context(expr: &Expr, read_field: impl Fn(&str) -> Option<Value>) -> Context {
+        let mut fields = Vec::new();
+        SchemaJITCompiler::collect_field_accesses(expr, &mut fields);
 
-            Expr::Conditional {
-                condition,
-                then_branch,
-                else_branch,
-            } => {
-                let cond_val = self.eval_expr_tree_walk(condition, ctx)?;
-                if cond_val.is_truthy() {
-                    self.eval_expr_tree_walk(then_branch, ctx)
-                } else {
-                    self.eval_expr_tree_walk(else_branch, ctx)
-                }
-            }
-
-            Expr::Call { name, args } => {
-                let arg_vals: Result<Vec<_>> = args
-                    .iter()
-                    .map(|a| self.eval_expr_tree_walk(a, ctx))
-                    .collect();
-                self.apply_function(name, &arg_vals?)
-            }
-
-            _ => Err(OrdoError::eval_error(
-                "Unsupported expression in tree-walk fallback".to_string(),
-            )),
+        let mut root: hashbrown::HashMap<IString, Value> = hashbrown::HashMap::new();
+        for path in &fields {
+            // Fields the typed context cannot provide are left missing
+            let Some(value) = read_field(path) else {
+                continue;
+            };
+            insert_path(&mut root, path, value);
         }
-    }
-
-    /// Apply binary operation
-    fn apply_binary_op(
-        &self,
-        op: crate::expr::BinaryOp,
-        left: &Value,
-        right: &Value,
-    ) -> Result<Value> {
-        use crate::expr::BinaryOp;
-
-        let left_f = left.as_float().unwrap_or(0.0);
-        let right_f = right.as_float().unwrap_or(0.0);
-
-        let result = match op {
-            BinaryOp::Add => left_f + right_f,
-            BinaryOp::Sub => left_f - right_f,
-            BinaryOp::Mul => left_f * right_f,
-            BinaryOp::Div => left_f / right_f,
-            BinaryOp::Mod => left_f % right_f,
-            BinaryOp::Eq => {
-                if (left_f - right_f).abs() < f64::EPSILON {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::Ne => {
-                if (left_f - right_f).abs() >= f64::EPSILON {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::Lt => {
-                if left_f < right_f {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::Le => {
-                if left_f <= right_f {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::Gt => {
-                if left_f > right_f {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::Ge => {
-                if left_f >= right_f {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::And => {
-                if left_f != 0.0 && right_f != 0.0 {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            BinaryOp::Or => {
-                if left_f != 0.0 || right_f != 0.0 {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            _ => {
-                return Err(OrdoError::eval_error(format!(
-                    "Unsupported binary op {:?}",
-                    op
-                )))
-            }
-        };
-
-        Ok(Value::Float(result))
-    }
-
-    /// Apply unary operation
-    fn apply_unary_op(&self, op: crate::expr::UnaryOp, val: &Value) -> Result<Value> {
-        use crate::expr::UnaryOp;
 
-        let v = val.as_float().unwrap_or(0.0);
-
-        let result = match op {
-            UnaryOp::Not => {
-                if v == 0.0 {
-                    1.0
-                } else {
-                    0.0
-                }
-            }
-            UnaryOp::Neg => -v,
-        };
-
-        Ok(Value::Float(result))
-    }
-
-    /// Apply function
-    fn apply_function(&self, name: &str, args: &[Value]) -> Result<Value> {
-        let result = match name {
-            "abs" => args.first().and_then(|v| v.as_float()).unwrap_or(0.0).abs(),
-            "floor" => args
-                .first()
-                .and_then(|v| v.as_float())
-                .unwrap_or(0.0)
-                .floor(),
-            "ceil" => args
-                .first()
-                .and_then(|v| v.as_float())
-                .unwrap_or(0.0)
-                .ceil(),
-            "round" => args
-                .first()
-                .and_then(|v| v.as_float())
-                .unwrap_or(0.0)
-                .round(),
-            "sqrt" => args
-                .first()
-                .and_then(|v| v.as_float())
-                .unwrap_or(0.0)
-                .sqrt(),
-            "min" => {
-                let a = args.first().and_then(|v| v.as_float()).unwrap_or(0.0);
-                let b = args.get(1).and_then(|v| v.as_float()).unwrap_or(0.0);
-                a.min(b)
-            }
-            "max" => {
-                let a = args.first().and_then(|v| v.as_float()).unwrap_or(0.0);
-                let b = args.get(1).and_then(|v| v.as_float()).unwrap_or(0.0);
-                a.max(b)
-            }
-            _ => {
-                return Err(OrdoError::eval_error(format!(
-                    "Unknown function '{}'",
-                    name
-                )))
-            }
-        };
-
-        Ok(Value::Float(result))
-    }
-
-    /// Compute expression hash with schema
-    fn hash_expr_with_schema(&self, expr: &Expr, schema: &MessageSchema) -> u64 {
-        // Include schema name in hash to differentiate same expression with different schemas
-        let source = format!("{}:{:?}", schema.name, expr);
-        hash_expr(&source)
+        Context::new(Value::object_optimized(root))
     }
 
     /// Get profiler statistics
@@ -453,7 +306,20 @@ impl SchemaJITEvaluator {
 
     /// Get JIT statistics
     pub fn jit_stats(&self) -> SchemaJITStats {
-        self.compiler.lock().stats().clone()
+        let mut stats = self.compiler.lock().stats().clone();
+        stats.vm_fallbacks = self.vm_fallbacks.load(Ordering::Relaxed);
+        stats.guard_fallbacks = self.guard_fallbacks.load(Ordering::Relaxed);
+        stats
+    }
+
+    /// Get evaluator statistics
+    pub fn stats(&self) -> SchemaJITEvaluatorStats {
+        SchemaJITEvaluatorStats {
+            jit_stats: self.jit_stats(),
+            jit_cache_hits: self.jit_cache_hits.load(Ordering::Relaxed),
+            vm_fallbacks: self.vm_fallbacks.load(Ordering::Relaxed),
+            bytecode_cache_size: self.bytecode_cache_size(),
+        }
     }
 
     /// Get bytecode cache size
@@ -473,6 +339,23 @@ impl SchemaJITEvaluator {
     }
 }
 
+/// Insert `value` at a dotted `path`, creating intermediate objects
+fn insert_path(root: &mut hashbrown::HashMap<IString, Value>, path: &str, value: Value) {
+    match path.split_once('.') {
+        None => {
+            root.insert(IString::from(path), value);
+        }
+        Some((head, rest)) => {
+            let child = root
+                .entry(IString::from(head))
+                .or_insert_with(|| Value::object_optimized(hashbrown::HashMap::new()));
+            if let Value::Object(map) = child {
+                insert_path(map, rest, value);
+            }
+        }
+    }
+}
+
 /// Statistics for the Schema JIT Evaluator
 #[derive(Debug, Clone, Default)]
 pub struct SchemaJITEvaluatorStats {
@@ -604,31 +487,49 @@ mod tests {
             right: Box::new(Expr::Literal(Value::Int(700))),
         };
 
-        // Test with score = 720 (should be true = 1.0)
+        // Test with score = 720 (should be true)
         let ctx1 = TestLoanContext {
             amount: 50000.0,
             credit_score: 720,
             approved: false,
         };
         let result1 = evaluator.eval_typed(&expr, &ctx1).unwrap();
-        if let Value::Float(v) = result1 {
-            assert!((v - 1.0).abs() < 0.001);
-        } else {
-            panic!("Expected Float");
-        }
+        assert_eq!(result1, Value::Bool(true));
 
-        // Test with score = 650 (should be false = 0.0)
+        // Test with score = 650 (should be false)
         let ctx2 = TestLoanContext {
             amount: 50000.0,
             credit_score: 650,
             approved: false,
         };
         let result2 = evaluator.eval_typed(&expr, &ctx2).unwrap();
-        if let Value::Float(v) = result2 {
-            assert!(v.abs() < 0.001);
-        } else {
-            panic!("Expected Float");
-        }
+        assert_eq!(result2, Value::Bool(false));
+    }
+
+    #[test]
+    fn test_schema_jit_evaluator_vm_fallback() {
+        let evaluator = SchemaJITEvaluator::simple().unwrap();
+        let ctx = TestLoanContext {
+            amount: 50000.0,
+            credit_score: 720,
+            approved: false,
+        };
+
+        // String results cannot be JIT-compiled and run on the VM instead
+        let expr =
+            crate::expr::ExprParser::parse(r#"if approved then "yes" else "review""#).unwrap();
+        let result = evaluator.eval_typed(&expr, &ctx).unwrap();
+        assert_eq!(result, Value::string("review"));
+
+        // Integer fields keep their type on the VM path
+        let expr = crate::expr::ExprParser::parse("to_int(amount) + credit_score").unwrap();
+        let result = evaluator.eval_typed(&expr, &ctx).unwrap();
+        assert_eq!(result, Value::Int(50720));
+
+        let stats = evaluator.stats();
+        assert_eq!(stats.vm_fallbacks, 2);
+        assert_eq!(stats.jit_stats.successful_compiles, 0);
+        assert_eq!(stats.bytecode_cache_size, 2);
     }
 
     #[test]
@@ -653,4 +554,32 @@ mod tests {
         // Should have only compiled once
         assert_eq!(stats1.successful_compiles, stats2.successful_compiles);
     }
+
+    #[test]
+    fn test_schema_jit_evaluator_dynamic() {
+        let evaluator = SchemaJITEvaluator::simple().unwrap();
+        let expr = Expr::Field("amount".to_string());
+
+        // Two runtime schemas with the same name but different layouts
+        let first: [f64; 2] = [1.5, 2.5];
+        let first_schema = Arc::new(
+            MessageSchema::builder("Dynamic")
+                .field_at("amount", FieldType::Float64, 0)
+                .build(),
+        );
+        let second_schema = Arc::new(
+            MessageSchema::builder("Dynamic")
+                .field_at("amount", FieldType::Float64, 8)
+                .build(),
+        );
+
+        let ctx = unsafe { DynamicTypedContext::new(first.as_ptr() as *const u8, first_schema) };
+        let (value, jit_used) = evaluator.eval_dynamic(&expr, &ctx).unwrap();
+        assert_eq!(value, Value::Float(1.5));
+        assert!(jit_used);
+
+        let ctx = unsafe { DynamicTypedContext::new(first.as_ptr() as *const u8, second_schema) };
+        let (value, _) = evaluator.eval_dynamic(&expr, &ctx).unwrap();
+        assert_eq!(value, Value::Float(2.5));
+    }
 }
@@ -0,0 +1,514 @@
+//! Shape inference for dynamic inputs
+//!
+//! Lets the Schema JIT run against plain JSON `Value`s. A [`InferredShape`] is
+//! built from the field types observed in sample inputs; each input is then
+//! packed into a flat buffer with that layout and exposed as a
+//! [`DynamicTypedContext`]. Packing doubles as the shape guard: an input
+//! whose field types differ from the shape is rejected, and the caller falls
+//! back to the VM.
+//!
+//! ```text
+//! {"amount": 12.5, "user": {"tier": "gold"}}
+//!        │ pack (type check per field)
+//!        ▼
+//! ┌──────────────┬──────────────────────────┐
+//! │ amount: f64  │ user.tier: String        │   ← offsets from InferredShape
+//! └──────────────┴──────────────────────────┘
+//!        │
+//!        ▼  JIT code: ldr d0, [ctx, #0]
+//! ```
+//!
+//! Shapes are append-only: adding a field never moves existing ones, so code
+//! compiled against an earlier version of a shape can run on buffers packed
+//! with a later one.
+
+use super::typed_context::DynamicTypedContext;
+use crate::context::{Context, FieldSchema, FieldType, MessageSchema, Value};
+use crate::expr::{BinaryOp, Expr, UnaryOp};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Size of every scalar slot (values are 8-byte aligned)
+const SLOT_SIZE: usize = 8;
+
+/// Type of a field in an inferred shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShapeKind {
+    /// Integer stored as `i64` (limited to ±2^53 so it converts to `f64` exactly)
+    Int,
+    /// Float stored as `f64`
+    Float,
+    /// Boolean stored as `bool`
+    Bool,
+    /// String stored as `String`
+    String,
+}
+
+impl ShapeKind {
+    /// Largest integer magnitude that JIT code (working in `f64`) represents exactly
+    pub const MAX_EXACT_INT: i64 = 1 << 53;
+
+    /// Kind of a value, or `None` if it cannot be packed
+    pub fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(i) if i.unsigned_abs() <= Self::MAX_EXACT_INT as u64 => Some(Self::Int),
+            Value::Float(_) => Some(Self::Float),
+            Value::Bool(_) => Some(Self::Bool),
+            Value::String(_) => Some(Self::String),
+            _ => None,
+        }
+    }
+
+    /// Whether the kind is numeric
+    pub fn is_number(self) -> bool {
+        matches!(self, Self::Int | Self::Float)
+    }
+
+    /// Schema field type for this kind
+    pub fn field_type(self) -> FieldType {
+        match self {
+            Self::Int => FieldType::Int64,
+            Self::Float => FieldType::Float64,
+            Self::Bool => FieldType::Bool,
+            Self::String => FieldType::String,
+        }
+    }
+
+    /// Infer the kind of `expr` given the kinds of its fields, or `None` if
+    /// JIT code could produce a different result than the interpreter
+    pub(crate) fn of_expr(expr: &Expr, field: &dyn Fn(&str) -> Option<Self>) -> Option<Self> {
+        use ShapeKind::*;
+
+        let is_field = |e: &Expr| matches!(e, Expr::Field(_));
+
+        match expr {
+            Expr::Literal(value) => Self::of(value),
+
+            Expr::Field(path) => field(path),
+
+            Expr::Unary { op, operand } => {
+                let kind = Self::of_expr(operand, field)?;
+                match op {
+                    UnaryOp::Not if kind != String => Some(Bool),
+                    UnaryOp::Neg if kind.is_number() => Some(kind),
+                    _ => None,
+                }
+            }
+
+            Expr::Binary { op, left, right } => {
+                let lk = Self::of_expr(left, field)?;
+
+                if matches!(op, BinaryOp::In | BinaryOp::NotIn) {
+                    // Membership uses value equality, so every item must have the exact kind
+                    let items: Vec<&Value> = match right.as_ref() {
+                        Expr::Literal(Value::Array(items)) => items.iter().collect(),
+                        Expr::Array(items) => items
+                            .iter()
+                            .map(|e| match e {
+                                Expr::Literal(v) => Some(v),
+                                _ => None,
+                            })
+                            .collect::<Option<_>>()?,
+                        _ => return None,
+                    };
+                    let supported = lk.is_number() || (lk == String && is_field(left));
+                    return (supported && items.iter().all(|v| Self::of(v) == Some(lk)))
+                        .then_some(Bool);
+                }
+
+                let rk = Self::of_expr(right, field)?;
+                match op {
+                    BinaryOp::And | BinaryOp::Or if lk != String && rk != String => Some(Bool),
+                    // Value equality never equates different types (1 != 1.0)
+                    BinaryOp::Eq | BinaryOp::Ne if lk == rk => {
+                        let operand = |e: &Expr| matches!(e, Expr::Field(_) | Expr::Literal(_));
+                        (lk != String
+                            || (is_field(left) && operand(right))
+                            || (is_field(right) && operand(left)))
+                        .then_some(Bool)
+                    }
+                    // Ordering compares int and float numerically
+                    BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+                        if lk.is_number() && rk.is_number() =>
+                    {
+                        Some(Bool)
+                    }
+                    // Only float arithmetic is exact in f64 and cannot fail
+                    BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul
+                        if lk.is_number() && rk.is_number() && (lk == Float || rk == Float) =>
+                    {
+                        Some(Float)
+                    }
+                    _ => None,
+                }
+            }
+
+            Expr::Call { name, args } if name == "starts_with" => match args.as_slice() {
+                [path @ Expr::Field(_), Expr::Literal(Value::String(_))] => {
+                    (Self::of_expr(path, field)? == String).then_some(Bool)
+                }
+                _ => None,
+            },
+
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = Self::of_expr(condition, field)?;
+                let then_kind = Self::of_expr(then_branch, field)?;
+                let else_kind = Self::of_expr(else_branch, field)?;
+                (condition != String && then_kind != String && then_kind == else_kind)
+                    .then_some(then_kind)
+            }
+
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::String => std::mem::size_of::<String>(),
+            _ => SLOT_SIZE,
+        }
+    }
+}
+
+/// A field of an inferred shape
+#[derive(Debug, Clone, Serialize)]
+pub struct ShapeField {
+    /// Field path as used in expressions (e.g. `user.tier`)
+    pub path: String,
+    /// Observed type
+    pub kind: ShapeKind,
+    /// Byte offset in the packed buffer
+    pub offset: usize,
+    /// Whether packing checks (and fills) this field
+    ///
+    /// Fields that turned out to be polymorphic are unguarded: they keep their
+    /// slot but are ignored when packing, and code reading them must not run.
+    pub guarded: bool,
+}
+
+/// A field layout inferred from observed inputs
+#[derive(Debug, Clone)]
+pub struct InferredShape {
+    name: String,
+    fields: Vec<ShapeField>,
+    schema: Arc<MessageSchema>,
+    size: usize,
+}
+
+impl InferredShape {
+    /// Create an empty shape
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            schema: Arc::new(MessageSchema::new(name.clone(), Vec::new())),
+            name,
+            fields: Vec::new(),
+            size: 0,
+        }
+    }
+
+    /// Infer a shape for `paths` from the types observed in `samples`
+    ///
+    /// Fields that are missing, null, non-scalar or typed differently across
+    /// samples are left out.
+    pub fn infer<'a>(
+        name: impl Into<String>,
+        paths: impl IntoIterator<Item = &'a str>,
+        samples: &[Context],
+    ) -> Self {
+        let mut shape = Self::new(name);
+        for path in paths {
+            let mut kinds = samples
+                .iter()
+                .map(|ctx| ctx.get(path).and_then(ShapeKind::of));
+            let Some(Some(kind)) = kinds.next() else {
+                continue;
+            };
+            if kinds.all(|k| k == Some(kind)) {
+                if let Some(extended) = shape.with_field(path, kind) {
+                    shape = extended;
+                }
+            }
+        }
+        shape
+    }
+
+    /// Shape name (used as the schema name)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// All fields, in layout order
+    pub fn fields(&self) -> &[ShapeField] {
+        &self.fields
+    }
+
+    /// Find a field by path
+    pub fn field(&self, path: &str) -> Option<(usize, &ShapeField)> {
+        self.fields.iter().enumerate().find(|(_, f)| f.path == path)
+    }
+
+    /// The schema describing the packed buffer
+    pub fn schema(&self) -> &Arc<MessageSchema> {
+        &self.schema
+    }
+
+    /// Size of a packed buffer in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Return a copy of this shape with `path` appended
+    ///
+    /// Returns `None` if the path already exists or conflicts with an existing
+    /// field (one is an object prefix of the other).
+    pub fn with_field(&self, path: &str, kind: ShapeKind) -> Option<Self> {
+        let conflicts = |a: &str, b: &str| {
+            a == b || (a.starts_with(b) && a.as_bytes().get(b.len()) == Some(&b'.'))
+        };
+        if path.is_empty()
+            || self
+                .fields
+                .iter()
+                .any(|f| conflicts(path, &f.path) || conflicts(&f.path, path))
+        {
+            return None;
+        }
+
+        let mut fields = self.fields.clone();
+        fields.push(ShapeField {
+            path: path.to_string(),
+            kind,
+            offset: self.size,
+            guarded: true,
+        });
+        Some(self.with_fields(fields, self.size + kind.size()))
+    }
+
+    /// Return a copy of this shape with the field at `index` unguarded
+    pub fn without_guard(&self, index: usize) -> Self {
+        let mut fields = self.fields.clone();
+        if let Some(field) = fields.get_mut(index) {
+            field.guarded = false;
+        }
+        self.with_fields(fields, self.size)
+    }
+
+    fn with_fields(&self, fields: Vec<ShapeField>, size: usize) -> Self {
+        let leaves: Vec<(Vec<&str>, &ShapeField)> = fields
+            .iter()
+            .map(|f| (f.path.split('.').collect(), f))
+            .collect();
+        let schema = Arc::new(build_schema(self.name.clone(), &leaves));
+        Self {
+            name: self.name.clone(),
+            fields,
+            schema,
+            size,
+        }
+    }
+
+    /// Pack the guarded fields of `ctx` into a buffer with this layout
+    ///
+    /// This is the shape guard: it fails with the index of the first field
+    /// that is missing or has a different type.
+    pub fn pack(&self, ctx: &Context) -> std::result::Result<PackedInput, usize> {
+        let mut packed = PackedInput {
+            buf: vec![0u64; self.size.div_ceil(SLOT_SIZE)],
+            strings: Vec::new(),
+            schema: Arc::clone(&self.schema),
+        };
+
+        for (index, field) in self.fields.iter().enumerate() {
+            let value = if field.guarded {
+                match ctx.get(&field.path) {
+                    Some(value) if ShapeKind::of(value) == Some(field.kind) => Some(value),
+                    _ => return Err(index),
+                }
+            } else {
+                None
+            };
+            packed.write(field, value);
+        }
+        Ok(packed)
+    }
+}
+
+/// Build a (possibly nested) schema; leaf offsets are absolute, so nested
+/// messages are placed at offset 0
+fn build_schema(name: String, leaves: &[(Vec<&str>, &ShapeField)]) -> MessageSchema {
+    let mut fields: Vec<FieldSchema> = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+
+    for (segments, field) in leaves {
+        let head = segments[0];
+        if segments.len() == 1 {
+            fields.push(
+                FieldSchema::new(head, field.kind.field_type(), field.offset)
+                    .with_size(field.kind.size()),
+            );
+        } else if !seen.contains(&head) {
+            seen.push(head);
+            let nested: Vec<(Vec<&str>, &ShapeField)> = leaves
+                .iter()
+                .filter(|(s, _)| s.len() > 1 && s[0] == head)
+                .map(|(s, f)| (s[1..].to_vec(), *f))
+                .collect();
+            let schema = build_schema(format!("{}.{}", name, head), &nested);
+            fields.push(FieldSchema::new(
+                head,
+                FieldType::Message(Arc::new(schema)),
+                0,
+            ));
+        }
+    }
+
+    MessageSchema::new(name, fields)
+}
+
+/// An input packed into the flat layout of an [`InferredShape`]
+pub struct PackedInput {
+    /// 8-byte aligned storage
+    buf: Vec<u64>,
+    /// Offsets of `String`s that must be dropped
+    strings: Vec<usize>,
+    schema: Arc<MessageSchema>,
+}
+
+impl PackedInput {
+    fn write(&mut self, field: &ShapeField, value: Option<&Value>) {
+        // Safety: offsets come from the shape the buffer was sized for, are
+        // 8-byte aligned, and each slot is written at most once
+        unsafe {
+            let ptr = (self.buf.as_mut_ptr() as *mut u8).add(field.offset);
+            match (field.kind, value) {
+                (ShapeKind::Int, Some(Value::Int(i))) => *(ptr as *mut i64) = *i,
+                (ShapeKind::Float, Some(Value::Float(f))) => *(ptr as *mut f64) = *f,
+                (ShapeKind::Bool, Some(Value::Bool(b))) => *(ptr as *mut bool) = *b,
+                (ShapeKind::String, value) => {
+                    let s = match value {
+                        Some(Value::String(s)) => s.to_string(),
+                        _ => String::new(),
+                    };
+                    std::ptr::write(ptr as *mut String, s);
+                    self.strings.push(field.offset);
+                }
+                // Unguarded scalars stay zeroed
+                _ => {}
+            }
+        }
+    }
+
+    /// Pointer to the packed data (pass to `SchemaCompiledFunction::call_ptr_checked`)
+    pub fn as_ptr(&self) -> *const u8 {
+        self.buf.as_ptr() as *const u8
+    }
+
+    /// The packed data as a typed context
+    pub fn typed_context(&self) -> DynamicTypedContext<'_> {
+        // Safety: the buffer matches the schema and lives as long as the borrow
+        unsafe { DynamicTypedContext::new(self.as_ptr(), Arc::clone(&self.schema)) }
+    }
+}
+
+impl Drop for PackedInput {
+    fn drop(&mut self) {
+        for &offset in &self.strings {
+            // Safety: a `String` was written at this offset by `write`
+            unsafe {
+                let ptr = (self.buf.as_mut_ptr() as *mut u8).add(offset);
+                std::ptr::drop_in_place(ptr as *mut String);
+            }
+        }
+    }
+}
+
+// Safety: the buffer owns its strings and is never mutated after packing
+unsafe impl Send for PackedInput {}
+unsafe impl Sync for PackedInput {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ctx(value: serde_json::Value) -> Context {
+        Context::new(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn test_infer_and_pack() {
+        let samples = [
+            ctx(json!({"amount": 10.5, "count": 3, "vip": true, "user": {"tier": "gold"}})),
+            ctx(json!({"amount": 7.0, "count": 1, "vip": false, "user": {"tier": "silver"}})),
+        ];
+        let shape = InferredShape::infer(
+            "orders",
+            ["amount", "count", "vip", "user.tier", "missing"],
+            &samples,
+        );
+        assert_eq!(shape.fields().len(), 4);
+        assert!(shape.field("missing").is_none());
+        assert_eq!(shape.field("user.tier").unwrap().1.kind, ShapeKind::String);
+
+        let packed = shape.pack(&samples[0]).unwrap();
+        let typed = packed.typed_context();
+        unsafe {
+            assert_eq!(typed.read_field_value("amount"), Some(Value::float(10.5)));
+            assert_eq!(typed.read_field_value("count"), Some(Value::int(3)));
+            assert_eq!(typed.read_field_value("vip"), Some(Value::bool(true)));
+            assert_eq!(
+                typed.read_field_value("user.tier"),
+                Some(Value::string("gold"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_polymorphic_fields_are_not_inferred() {
+        let samples = [ctx(json!({"score": 1})), ctx(json!({"score": 1.5}))];
+        let shape = InferredShape::infer("s", ["score"], &samples);
+        assert!(shape.fields().is_empty());
+    }
+
+    #[test]
+    fn test_pack_guards_shape() {
+        let shape = InferredShape::new("s")
+            .with_field("score", ShapeKind::Float)
+            .unwrap()
+            .with_field("name", ShapeKind::String)
+            .unwrap();
+
+        assert!(shape.pack(&ctx(json!({"score": 1.0, "name": "a"}))).is_ok());
+        assert_eq!(
+            shape.pack(&ctx(json!({"score": 1, "name": "a"}))).err(),
+            Some(0)
+        );
+        assert_eq!(shape.pack(&ctx(json!({"score": 1.0}))).err(), Some(1));
+
+        // Unguarded fields are skipped
+        let relaxed = shape.without_guard(0);
+        assert_eq!(relaxed.field("name").unwrap().1.offset, 8);
+        assert!(relaxed
+            .pack(&ctx(json!({"score": "x", "name": "a"})))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_layout_is_append_only() {
+        let shape = InferredShape::new("s")
+            .with_field("a", ShapeKind::String)
+            .unwrap();
+        let extended = shape.with_field("b", ShapeKind::Int).unwrap();
+        assert_eq!(extended.field("a").unwrap().1.offset, 0);
+        assert_eq!(extended.field("b").unwrap().1.offset, 24);
+
+        assert!(extended.with_field("a", ShapeKind::Int).is_none());
+        assert!(extended.with_field("a.x", ShapeKind::Int).is_none());
+    }
+}
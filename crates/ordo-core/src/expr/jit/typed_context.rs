@@ -29,7 +29,7 @@
 //! }
 //! ```
 
-use crate::context::{FieldType, MessageSchema};
+use crate::context::{FieldType, MessageSchema, Value};
 use std::sync::Arc;
 
 /// Trait for typed contexts that support direct field access
@@ -111,6 +111,64 @@ pub trait TypedContext: Send + Sync {
             _ => return None, // Non-numeric types
         })
     }
+
+    /// Read a field as a dynamic `Value`
+    ///
+    /// Unlike `read_field_as_f64`, this preserves the field's type (integers stay
+    /// `Int`, strings are copied, absent optionals become `Null`). It is used to
+    /// build a `Context` when an expression has to fall back to the interpreter.
+    ///
+    /// # Safety
+    ///
+    /// Same safety requirements as `field_ptr`.
+    unsafe fn read_field_value(&self, path: &str) -> Option<Value> {
+        let (ptr, field_type) = self.nested_field_ptr(path)?;
+        read_value_at(ptr, &field_type)
+    }
+}
+
+/// Read the value stored at `ptr` according to `field_type`
+///
+/// Strings are read as `String` and optionals as `Option<T>`, so the Rust layout
+/// of those types never has to be assumed.
+///
+/// # Safety
+///
+/// `ptr` must point to a live value of the Rust type described by `field_type`.
+pub(crate) unsafe fn read_value_at(ptr: *const u8, field_type: &FieldType) -> Option<Value> {
+    Some(match field_type {
+        FieldType::Bool => Value::Bool(*(ptr as *const bool)),
+        FieldType::Int32 => Value::Int(*(ptr as *const i32) as i64),
+        FieldType::Int64 => Value::Int(*(ptr as *const i64)),
+        FieldType::UInt32 => Value::Int(*(ptr as *const u32) as i64),
+        FieldType::UInt64 => Value::Int(*(ptr as *const u64) as i64),
+        FieldType::Float32 => Value::Float(*(ptr as *const f32) as f64),
+        FieldType::Float64 => Value::Float(*(ptr as *const f64)),
+        FieldType::Enum(_) => Value::Int(*(ptr as *const i32) as i64),
+        FieldType::String => Value::string((*(ptr as *const String)).as_str()),
+        FieldType::Optional(inner) => match inner.as_ref() {
+            FieldType::Bool => (*(ptr as *const Option<bool>)).map_or(Value::Null, Value::Bool),
+            FieldType::Int32 | FieldType::Enum(_) => {
+                (*(ptr as *const Option<i32>)).map_or(Value::Null, |v| Value::Int(v as i64))
+            }
+            FieldType::Int64 => (*(ptr as *const Option<i64>)).map_or(Value::Null, Value::Int),
+            FieldType::UInt32 => {
+                (*(ptr as *const Option<u32>)).map_or(Value::Null, |v| Value::Int(v as i64))
+            }
+            FieldType::UInt64 => {
+                (*(ptr as *const Option<u64>)).map_or(Value::Null, |v| Value::Int(v as i64))
+            }
+            FieldType::Float32 => {
+                (*(ptr as *const Option<f32>)).map_or(Value::Null, |v| Value::Float(v as f64))
+            }
+            FieldType::Float64 => (*(ptr as *const Option<f64>)).map_or(Value::Null, Value::Float),
+            FieldType::String => (*(ptr as *const Option<String>))
+                .as_deref()
+                .map_or(Value::Null, Value::string),
+            _ => return None,
+        },
+        _ => return None,
+    })
 }
 
 /// Computed field access information for JIT compilation
@@ -173,6 +231,11 @@ impl DynamicTypedContext<'_> {
         &self.schema
     }
 
+    /// Pointer to the context data
+    pub fn as_ptr(&self) -> *const u8 {
+        self.data_ptr
+    }
+
     /// Read a field as f64
     ///
     /// # Safety
@@ -201,6 +264,17 @@ impl DynamicTypedContext<'_> {
             _ => return None,
         })
     }
+
+    /// Read a field as a dynamic `Value`
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that `data_ptr` points to valid memory
+    /// that matches the schema layout for the requested field.
+    pub unsafe fn read_field_value(&self, field_name: &str) -> Option<Value> {
+        let resolved = self.schema.resolve_field_path(field_name)?;
+        read_value_at(self.data_ptr.add(resolved.offset), &resolved.field_type)
+    }
 }
 
 // Safety: The data pointer is only dereferenced with proper type checking
@@ -392,4 +466,57 @@ mod tests {
             assert_eq!(ctx.read_field_as_f64("flag"), Some(99.0));
         }
     }
+
+    #[test]
+    fn test_read_field_value() {
+        struct Account {
+            owner: String,
+            limit: Option<i64>,
+            nickname: Option<String>,
+        }
+
+        let data = Account {
+            owner: "alice".to_string(),
+            limit: None,
+            nickname: Some("al".to_string()),
+        };
+
+        let schema = Arc::new(
+            MessageSchema::builder("Account")
+                .field_at(
+                    "owner",
+                    FieldType::String,
+                    std::mem::offset_of!(Account, owner),
+                )
+                .field_at(
+                    "limit",
+                    FieldType::Optional(Box::new(FieldType::Int64)),
+                    std::mem::offset_of!(Account, limit),
+                )
+                .field_at(
+                    "nickname",
+                    FieldType::Optional(Box::new(FieldType::String)),
+                    std::mem::offset_of!(Account, nickname),
+                )
+                .build(),
+        );
+
+        unsafe {
+            let ctx = DynamicTypedContext::new(&data as *const _ as *const u8, schema);
+            assert_eq!(ctx.read_field_value("owner"), Some(Value::string("alice")));
+            assert_eq!(ctx.read_field_value("limit"), Some(Value::Null));
+            assert_eq!(ctx.read_field_value("nickname"), Some(Value::string("al")));
+            assert_eq!(ctx.read_field_value("missing"), None);
+        }
+
+        let ctx = TestContext {
+            amount: 1.5,
+            count: 7,
+            active: false,
+        };
+        unsafe {
+            assert_eq!(ctx.read_field_value("count"), Some(Value::Int(7)));
+            assert_eq!(ctx.read_field_value("active"), Some(Value::Bool(false)));
+        }
+    }
 }
@@ -8,9 +8,15 @@
 //! - Expression optimizer (constant folding, dead code elimination)
 //! - High-performance bytecode compiler and VM with superinstructions
 //! - Vectorized batch execution
+//! - Columnar evaluation over Arrow record batches (requires `arrow` feature)
 //! - JIT compilation for hot expressions (requires `jit` feature)
+//! - Ahead-of-time compilation of rulesets to native objects (requires `aot` feature)
 
+#[cfg(feature = "aot")]
+pub mod aot;
 mod ast;
+#[cfg(feature = "arrow")]
+mod columnar;
 mod compiler;
 mod eval;
 mod functions;
@@ -19,10 +25,13 @@ pub mod jit;
 mod optimizer;
 mod parser;
 mod profiler;
+pub(crate) mod regex_cache;
 mod vectorized;
 mod vm;
 
 pub use ast::{BinaryOp, Expr, UnaryOp};
+#[cfg(feature = "arrow")]
+pub use columnar::{check_schema, row_value, ColumnarEvaluator, ConditionMask};
 pub use compiler::ExprCompiler;
 pub use eval::Evaluator;
 pub use functions::FunctionRegistry;
@@ -30,9 +39,9 @@ pub use functions::FunctionRegistry;
 // Schema-Aware JIT exports (only available with `jit` feature)
 #[cfg(feature = "jit")]
 pub use jit::{
-    DynamicTypedContext, FieldAccessInfo, SchemaCompiledFunction, SchemaJITCompiler,
-    SchemaJITErrorCode, SchemaJITEvaluator, SchemaJITEvaluatorConfig, SchemaJITEvaluatorStats,
-    SchemaJITStats, TypedContext,
+    DynamicTypedContext, FieldAccessInfo, InferredShape, PackedInput, SchemaCompiledFunction,
+    SchemaJITCompiler, SchemaJITErrorCode, SchemaJITEvaluator, SchemaJITEvaluatorConfig,
+    SchemaJITEvaluatorStats, SchemaJITStats, SchemaResultType, ShapeField, ShapeKind, TypedContext,
 };
 pub use optimizer::{ExprOptimizer, OptimizationStats};
 pub use parser::ExprParser;
@@ -40,6 +49,7 @@ pub use profiler::{
     hash_expr, ExprProfile, JITDecision, JITPriority, Profiler, ProfilerConfig, ProfilerStats,
     RulePathProfile,
 };
+pub use regex_cache::{precompile_regexes, precompiled_regex_count, RegexLimits, REGEX_FUNCTIONS};
 pub use vectorized::{BatchStats, VectorizedEvaluator};
 pub use vm::{
     BytecodeVM, CompiledExpr, CompiledExprStats, Instruction, Opcode, RegisterValue, TraceLevel,
@@ -205,6 +205,8 @@ impl RulePathProfile {
 pub struct ProfilerConfig {
     /// Minimum hot score to trigger JIT (default: 10,000)
     pub hot_threshold: u64,
+    /// Number of interpreted executions before an expression is compiled to bytecode (default: 16)
+    pub bytecode_threshold: u64,
     /// Maximum number of expression profiles to keep
     pub max_expr_profiles: usize,
     /// Maximum number of rule path profiles to keep
@@ -217,6 +219,7 @@ impl Default for ProfilerConfig {
     fn default() -> Self {
         Self {
             hot_threshold: 10_000,
+            bytecode_threshold: 16,
             max_expr_profiles: 10_000,
             max_rule_profiles: 1_000,
             enabled: true,
@@ -275,6 +278,11 @@ impl Profiler {
         self.config.enabled
     }
 
+    /// Get the profiler configuration
+    pub fn config(&self) -> &ProfilerConfig {
+        &self.config
+    }
+
     /// Record an expression execution
     pub fn record_expr(&self, hash: u64, duration: Duration) {
         if !self.config.enabled {
@@ -332,8 +340,9 @@ impl Profiler {
             }
 
             let score = profile.hot_score();
-            let priority = JITPriority::from_score(score);
-            let should_jit = priority.is_some();
+            let should_jit = score >= self.config.hot_threshold;
+            let priority =
+                should_jit.then(|| JITPriority::from_score(score).unwrap_or(JITPriority::Low));
 
             JITDecision {
                 should_jit,
@@ -379,8 +388,9 @@ impl Profiler {
             }
 
             let score = profile.hot_score();
-            let priority = JITPriority::from_score(score);
-            let should_jit = priority.is_some();
+            let should_jit = score >= self.config.hot_threshold;
+            let priority =
+                should_jit.then(|| JITPriority::from_score(score).unwrap_or(JITPriority::Low));
 
             JITDecision {
                 should_jit,
@@ -407,6 +417,11 @@ impl Profiler {
         }
     }
 
+    /// Forget an expression's profile (e.g. when the expression was replaced)
+    pub fn remove_expr(&self, hash: u64) {
+        self.expressions.remove(&hash);
+    }
+
     /// Mark a rule path as JIT triggered
     pub fn mark_rule_path_jit_triggered(&self, step_ids: &[String]) {
         let path_hash = hash_step_ids(step_ids);
@@ -452,6 +467,21 @@ impl Profiler {
             .collect()
     }
 
+    /// Get every recorded path of a ruleset as (step IDs, execution count, avg duration ns)
+    pub fn get_rule_paths(&self, ruleset_name: &str) -> Vec<(Vec<String>, u64, u64)> {
+        self.rule_paths
+            .iter()
+            .filter(|entry| entry.ruleset_name == ruleset_name)
+            .map(|entry| {
+                (
+                    entry.step_ids.clone(),
+                    entry.execution_count(),
+                    entry.avg_duration_ns(),
+                )
+            })
+            .collect()
+    }
+
     /// Get profiler statistics
     pub fn stats(&self) -> ProfilerStats {
         ProfilerStats {
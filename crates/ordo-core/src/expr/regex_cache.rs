@@ -0,0 +1,327 @@
+//! Compiled regex caching for the `regex_*` built-in functions
+//!
+//! Patterns reach the regex functions as runtime arguments. Two caches avoid
+//! recompiling them on every call:
+//! - Literal patterns (`regex_match("^[a-z]+$", name)`) are compiled once when
+//!   a condition or ruleset is compiled ([`precompile_regexes`]) and shared by
+//!   all threads. An invalid or oversized literal pattern fails compilation,
+//!   i.e. at upload time instead of on every request. Past 4096 patterns the
+//!   least recently used one is evicted, so rulesets replaced by hot reload
+//!   don't pin theirs forever.
+//! - Dynamic patterns (computed from the input) go through a bounded,
+//!   thread-local LRU.
+//!
+//! Regexes are compiled under the size and DFA limits of the active
+//! [`ResourceBudget`](crate::budget::ResourceBudget), so each entry is keyed by
+//! pattern and limits. Lookups borrow the pattern and allocate only on a miss.
+
+use super::ast::Expr;
+use crate::budget;
+use crate::context::Value;
+use crate::error::{OrdoError, Result};
+use parking_lot::RwLock;
+use regex::{Regex, RegexBuilder};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Built-in functions taking a regex pattern as their first argument
+pub const REGEX_FUNCTIONS: &[&str] = &[
+    "regex_match",
+    "regex_find",
+    "regex_replace",
+    "regex_split",
+    "regex_find_all",
+];
+
+/// Maximum number of precompiled literal patterns kept in the shared cache
+const MAX_PRECOMPILED: usize = 4096;
+
+/// Capacity of the per-thread LRU for dynamic patterns
+const DYNAMIC_CACHE_CAPACITY: usize = 64;
+
+/// Compilation limits for a regex (`None` = regex crate default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RegexLimits {
+    /// Maximum size of the compiled program, in bytes
+    pub size_limit: Option<usize>,
+    /// Maximum size of the lazy DFA cache, in bytes
+    pub dfa_size_limit: Option<usize>,
+}
+
+impl RegexLimits {
+    /// Limits of the budget active on this thread
+    pub fn current() -> Self {
+        let (size_limit, dfa_size_limit) = budget::regex_limits();
+        Self {
+            size_limit,
+            dfa_size_limit,
+        }
+    }
+
+    /// Compile `pattern` under these limits
+    pub fn compile(&self, pattern: &str) -> Result<Regex> {
+        let mut builder = RegexBuilder::new(pattern);
+        if let Some(limit) = self.size_limit {
+            builder.size_limit(limit);
+        }
+        if let Some(limit) = self.dfa_size_limit {
+            builder.dfa_size_limit(limit);
+        }
+        builder.build().map_err(|e| match e {
+            regex::Error::CompiledTooBig(limit) => {
+                OrdoError::budget_exceeded("regex_size", limit as u64)
+            }
+            e => OrdoError::eval_error(format!("invalid regex '{}': {}", pattern, e)),
+        })
+    }
+}
+
+/// A precompiled regex and when it was last used
+#[derive(Debug)]
+struct Entry {
+    regex: Regex,
+    last_used: AtomicU64,
+}
+
+/// Shared cache of literal patterns, keyed by limits, then pattern
+///
+/// Hits only take the read lock and bump the entry's use stamp; inserting
+/// into a full cache evicts the entry with the oldest stamp.
+#[derive(Debug)]
+struct PrecompiledCache {
+    capacity: usize,
+    entries: HashMap<RegexLimits, HashMap<String, Entry>>,
+    len: usize,
+    clock: AtomicU64,
+}
+
+impl PrecompiledCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            len: 0,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn get(&self, limits: &RegexLimits, pattern: &str) -> Option<&Regex> {
+        let entry = self.entries.get(limits)?.get(pattern)?;
+        entry.last_used.store(self.tick(), Ordering::Relaxed);
+        Some(&entry.regex)
+    }
+
+    fn insert(&mut self, limits: RegexLimits, pattern: &str, regex: Regex) {
+        if self.get(&limits, pattern).is_some() {
+            return;
+        }
+        if self.len >= self.capacity {
+            self.evict_oldest();
+        }
+        let entry = Entry {
+            regex,
+            last_used: AtomicU64::new(self.tick()),
+        };
+        self.entries
+            .entry(limits)
+            .or_default()
+            .insert(pattern.to_string(), entry);
+        self.len += 1;
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .flat_map(|(limits, patterns)| {
+                patterns.iter().map(move |(pattern, entry)| {
+                    (entry.last_used.load(Ordering::Relaxed), *limits, pattern)
+                })
+            })
+            .min_by_key(|(last_used, _, _)| *last_used)
+            .map(|(_, limits, pattern)| (limits, pattern.clone()));
+        let Some((limits, pattern)) = oldest else {
+            return;
+        };
+        if let Some(patterns) = self.entries.get_mut(&limits) {
+            patterns.remove(&pattern);
+            if patterns.is_empty() {
+                self.entries.remove(&limits);
+            }
+            self.len -= 1;
+        }
+    }
+}
+
+fn precompiled() -> &'static RwLock<PrecompiledCache> {
+    static PRECOMPILED: OnceLock<RwLock<PrecompiledCache>> = OnceLock::new();
+    PRECOMPILED.get_or_init(|| RwLock::new(PrecompiledCache::new(MAX_PRECOMPILED)))
+}
+
+thread_local! {
+    /// Dynamic patterns and the limits they were compiled under
+    static DYNAMIC: RefCell<lru::LruCache<String, (RegexLimits, Regex)>> = RefCell::new(
+        lru::LruCache::new(NonZeroUsize::new(DYNAMIC_CACHE_CAPACITY).unwrap()),
+    );
+}
+
+/// Get the compiled regex for `pattern` under the active budget's limits
+pub(crate) fn get(pattern: &str) -> Result<Regex> {
+    let limits = RegexLimits::current();
+    if let Some(re) = precompiled().read().get(&limits, pattern) {
+        return Ok(re.clone());
+    }
+    DYNAMIC.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some((cached, re)) = cache.get(pattern) {
+            if *cached == limits {
+                return Ok(re.clone());
+            }
+        }
+        let re = limits.compile(pattern)?;
+        cache.put(pattern.to_string(), (limits, re.clone()));
+        Ok(re)
+    })
+}
+
+/// Compile the literal patterns of all regex calls in `expr`
+///
+/// Patterns are compiled under the active budget's limits and stored in the
+/// shared cache. Returns the number of literal patterns found; fails on the
+/// first invalid or oversized pattern.
+pub fn precompile_regexes(expr: &Expr) -> Result<usize> {
+    let limits = RegexLimits::current();
+    let mut count = 0;
+    visit_patterns(expr, &mut |pattern| {
+        count += 1;
+        if precompiled().read().get(&limits, pattern).is_some() {
+            return Ok(());
+        }
+        let re = limits.compile(pattern)?;
+        precompiled().write().insert(limits, pattern, re);
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+/// Number of patterns in the shared precompiled cache
+pub fn precompiled_regex_count() -> usize {
+    precompiled().read().len
+}
+
+fn visit_patterns(expr: &Expr, f: &mut impl FnMut(&str) -> Result<()>) -> Result<()> {
+    match expr {
+        Expr::Literal(_) | Expr::Field(_) | Expr::Exists(_) => Ok(()),
+        Expr::Binary { left, right, .. } => {
+            visit_patterns(left, f)?;
+            visit_patterns(right, f)
+        }
+        Expr::Unary { operand, .. } => visit_patterns(operand, f),
+        Expr::Call { name, args } => {
+            if REGEX_FUNCTIONS.contains(&name.as_str()) {
+                if let Some(Expr::Literal(Value::String(pattern))) = args.first() {
+                    f(pattern)?;
+                }
+            }
+            args.iter().try_for_each(|arg| visit_patterns(arg, f))
+        }
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visit_patterns(condition, f)?;
+            visit_patterns(then_branch, f)?;
+            visit_patterns(else_branch, f)
+        }
+        Expr::Array(items) | Expr::Coalesce(items) => {
+            items.iter().try_for_each(|item| visit_patterns(item, f))
+        }
+        Expr::Object(fields) => fields
+            .iter()
+            .try_for_each(|(_, value)| visit_patterns(value, f)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::{BudgetScope, ResourceBudget};
+    use crate::expr::ExprParser;
+
+    #[test]
+    fn test_precompile_literal_patterns() {
+        let expr = ExprParser::parse(
+            r#"regex_match("^precompile-[0-9]+$", code) && len(regex_split(sep, code)) > 1"#,
+        )
+        .unwrap();
+        assert_eq!(precompile_regexes(&expr).unwrap(), 1);
+
+        assert!(precompiled()
+            .read()
+            .get(&RegexLimits::default(), "^precompile-[0-9]+$")
+            .is_some());
+        assert!(get("^precompile-[0-9]+$")
+            .unwrap()
+            .is_match("precompile-42"));
+    }
+
+    #[test]
+    fn test_precompiled_cache_evicts_least_recently_used() {
+        let limits = RegexLimits::default();
+        let mut cache = PrecompiledCache::new(2);
+        for pattern in ["a", "b"] {
+            cache.insert(limits, pattern, limits.compile(pattern).unwrap());
+        }
+        assert!(cache.get(&limits, "a").is_some());
+
+        // "b" is the least recently used
+        cache.insert(limits, "c", limits.compile("c").unwrap());
+        assert_eq!(cache.len, 2);
+        assert!(cache.get(&limits, "a").is_some());
+        assert!(cache.get(&limits, "b").is_none());
+        assert!(cache.get(&limits, "c").is_some());
+
+        // Entries under other limits count towards the capacity
+        let budget = RegexLimits {
+            size_limit: Some(1 << 20),
+            dfa_size_limit: None,
+        };
+        assert!(cache.get(&limits, "a").is_some());
+        cache.insert(budget, "a", budget.compile("a").unwrap());
+        assert_eq!(cache.len, 2);
+        assert!(cache.get(&limits, "a").is_some());
+        assert!(cache.get(&budget, "a").is_some());
+        assert!(cache.get(&limits, "c").is_none());
+    }
+
+    #[test]
+    fn test_invalid_literal_pattern_fails_compilation() {
+        let expr = ExprParser::parse(r#"regex_find("([a-z", name)"#).unwrap();
+        assert!(matches!(
+            precompile_regexes(&expr),
+            Err(OrdoError::EvalError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_limits_from_budget() {
+        let expr = ExprParser::parse(r#"regex_match("[a-z]{100}[0-9]{100}", name)"#).unwrap();
+        assert!(precompile_regexes(&expr).is_ok());
+
+        let _scope = BudgetScope::enter(&ResourceBudget::unlimited().with_max_regex_size(1024));
+        assert!(matches!(
+            precompile_regexes(&expr),
+            Err(OrdoError::BudgetExceeded { ref resource, .. }) if resource == "regex_size"
+        ));
+        // A regex precompiled without limits is not reused under a budget
+        assert!(get("[a-z]{100}[0-9]{100}").is_err());
+    }
+}
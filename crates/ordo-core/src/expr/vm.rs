@@ -10,6 +10,7 @@
 //! 5. **Avoid cloning** - Uses indices and references where possible
 
 use super::functions::FunctionRegistry;
+use crate::budget;
 use crate::context::{Context, Value};
 use crate::error::{OrdoError, Result};
 use serde::Serialize;
@@ -525,6 +526,9 @@ impl BytecodeVM {
         // SAFETY: We have exclusive access during execution
         let regs = unsafe { &mut *self.registers.get() };
 
+        // One unit per expression, as the interpreter and JIT charge
+        budget::charge_fuel(1)?;
+
         let mut ip: usize = 0;
         let len = instructions.len();
         let mut instruction_count: u32 = 0;
@@ -872,6 +876,7 @@ impl BytecodeVM {
         }
 
         // Execute with tracing
+        budget::charge_fuel(1)?;
         let instructions = &compiled.instructions;
         let constants = &compiled.constants;
         let fields = &compiled.fields;
@@ -1278,7 +1283,12 @@ impl BytecodeVM {
             (Value::Float(a), Value::Float(b)) => Ok(Value::float(a + b)),
             (Value::Int(a), Value::Float(b)) => Ok(Value::float(*a as f64 + b)),
             (Value::Float(a), Value::Int(b)) => Ok(Value::float(a + *b as f64)),
-            (Value::String(a), Value::String(b)) => Ok(Value::string(format!("{}{}", a, b))),
+            (Value::String(a), Value::String(b)) => {
+                budget::check_string_len(a.len() + b.len())?;
+                let joined = Value::string(format!("{}{}", a, b));
+                budget::charge_value(&joined)?;
+                Ok(joined)
+            }
             _ => Err(OrdoError::eval_error(format!(
                 "Cannot add {} and {}",
                 left.type_name(),
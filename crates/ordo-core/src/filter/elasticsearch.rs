@@ -0,0 +1,727 @@
+//! Elasticsearch / OpenSearch Query DSL generator for filter compilation
+//!
+//! Converts filter paths into a query for the `query` field of a search
+//! request. Conditions within a path are combined with `bool.must`; multiple
+//! paths with `bool.should`.
+//!
+//! # Format
+//!
+//! ```json
+//! { "bool": { "should": [
+//!   { "term": { "owner_id": "alice" } },
+//!   { "term": { "visibility": "public" } }
+//! ], "minimum_should_match": 1 } }
+//! ```
+//!
+//! Fields under one of [`ElasticsearchOptions::nested_paths`] are wrapped in
+//! `nested` queries, one per condition: each condition may be satisfied by a
+//! different array element.
+//!
+//! An always-matches result is `{ "match_all": {} }`.
+//! A never-matches result is `{ "match_none": {} }`.
+//!
+//! # Functions
+//!
+//! `starts_with` becomes a `prefix` query, `ends_with` and `contains_str`
+//! `wildcard` queries and `regex_match` a `regexp` query. `lower(f) == "x"`
+//! and `upper(f) == "X"` become case-insensitive `term` queries, and range
+//! comparisons with `now()` or `date_add(now(), …)` use date math (`now-7d`),
+//! which requires a `date` field. [`ElasticsearchOptions::functions`] adds or
+//! overrides translations of calls used as conditions.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::context::Value;
+use crate::expr::{BinaryOp, Expr, UnaryOp};
+
+use super::path_collector::FilterPath;
+use super::pushdown::date_offset;
+
+/// Options for Elasticsearch output
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ElasticsearchOptions {
+    /// Index fields mapped as `nested` (after `field_mapping`), e.g. `["items"]`
+    #[serde(default)]
+    pub nested_paths: Vec<String>,
+    /// Custom function translations: a query per function name, in which the
+    /// strings `"{0}"`, `"{1}"`, … stand for the arguments (the mapped field
+    /// name, or the literal value). These take precedence over the built-in
+    /// translations.
+    #[serde(default)]
+    pub functions: HashMap<String, JsonValue>,
+}
+
+impl ElasticsearchOptions {
+    /// Translate calls of `name` with `template`, e.g.
+    /// `with_function("is_blank", json!({ "term": { "{0}": "" } }))`
+    pub fn with_function(mut self, name: impl Into<String>, template: JsonValue) -> Self {
+        self.functions.insert(name.into(), template);
+        self
+    }
+}
+
+/// Convert filter paths to an Elasticsearch query.
+pub fn to_elasticsearch(
+    paths: &[FilterPath],
+    mapping: &HashMap<String, String>,
+    options: &ElasticsearchOptions,
+) -> JsonValue {
+    if paths.is_empty() {
+        return json!({ "match_none": {} });
+    }
+
+    if paths.iter().any(|p| p.conditions.is_empty()) {
+        return json!({ "match_all": {} });
+    }
+
+    let builder = QueryBuilder::new(mapping, options);
+
+    if paths.len() == 1 {
+        return builder.path(&paths[0]);
+    }
+
+    let clauses: Vec<JsonValue> = paths.iter().map(|p| builder.path(p)).collect();
+    should(clauses)
+}
+
+/// Name of a function called in `expr` that has no Elasticsearch translation
+pub fn untranslated(
+    expr: &Expr,
+    mapping: &HashMap<String, String>,
+    options: &ElasticsearchOptions,
+) -> Option<String> {
+    let builder = QueryBuilder::new(mapping, options);
+    builder.expr(expr);
+    builder.untranslated.into_inner()
+}
+
+struct QueryBuilder<'a> {
+    mapping: &'a HashMap<String, String>,
+    options: &'a ElasticsearchOptions,
+    /// First function that could not be translated
+    untranslated: RefCell<Option<String>>,
+}
+
+impl<'a> QueryBuilder<'a> {
+    fn new(mapping: &'a HashMap<String, String>, options: &'a ElasticsearchOptions) -> Self {
+        Self {
+            mapping,
+            options,
+            untranslated: RefCell::new(None),
+        }
+    }
+
+    fn path(&self, path: &FilterPath) -> JsonValue {
+        match path.conditions.len() {
+            0 => json!({ "match_all": {} }),
+            1 => self.expr(&path.conditions[0]),
+            _ => must(path.conditions.iter().map(|c| self.expr(c)).collect()),
+        }
+    }
+
+    fn expr(&self, expr: &Expr) -> JsonValue {
+        match expr {
+            Expr::Binary { op, left, right } => self.binary(*op, left, right),
+            Expr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => self.not(operand),
+            Expr::Call { name, args } => self.call(name, args),
+            Expr::Exists(path) => self.exists(path),
+            _ => json!({ "match_none": {} }),
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, left: &Expr, right: &Expr) -> JsonValue {
+        // Comparisons with a function result
+        let call = [left, right]
+            .into_iter()
+            .find(|e| matches!(e, Expr::Call { .. }));
+        if let (Some(Expr::Call { name, .. }), false) =
+            (call, matches!(op, BinaryOp::And | BinaryOp::Or))
+        {
+            return self.computed(op, left, right).unwrap_or_else(|| {
+                self.untranslatable(name);
+                json!({ "match_none": {} })
+            });
+        }
+
+        match op {
+            BinaryOp::Eq | BinaryOp::Ne => {
+                let (path, value) = match (left, right) {
+                    (Expr::Field(path), Expr::Literal(value))
+                    | (Expr::Literal(value), Expr::Field(path)) => (path, value),
+                    _ => return json!({ "match_none": {} }),
+                };
+                // A missing field is the closest equivalent of NULL
+                let query = match value {
+                    Value::Null => self.exists(path),
+                    _ => self.term("term", path, value_to_json(value)),
+                };
+                match (op, value) {
+                    (BinaryOp::Eq, Value::Null) => must_not(query),
+                    (BinaryOp::Eq, _) | (BinaryOp::Ne, Value::Null) => query,
+                    _ => must_not(query),
+                }
+            }
+            BinaryOp::Lt => self.range("lt", "gt", left, right),
+            BinaryOp::Le => self.range("lte", "gte", left, right),
+            BinaryOp::Gt => self.range("gt", "lt", left, right),
+            BinaryOp::Ge => self.range("gte", "lte", left, right),
+            BinaryOp::And => must(vec![self.expr(left), self.expr(right)]),
+            BinaryOp::Or => should(vec![self.expr(left), self.expr(right)]),
+            BinaryOp::In | BinaryOp::NotIn => {
+                if let (Expr::Field(path), Some(values)) = (left, literal_array(right)) {
+                    let query = self.term("terms", path, JsonValue::Array(values));
+                    return if op == BinaryOp::In {
+                        query
+                    } else {
+                        must_not(query)
+                    };
+                }
+                json!({ "match_none": {} })
+            }
+            BinaryOp::Contains => {
+                if let (Expr::Field(path), Expr::Literal(Value::String(s))) = (left, right) {
+                    let pattern = format!("*{}*", wildcard_escape(s));
+                    return self.term("wildcard", path, JsonValue::String(pattern));
+                }
+                json!({ "match_none": {} })
+            }
+            _ => json!({ "match_none": {} }),
+        }
+    }
+
+    /// Range query, handling the field on either side.
+    /// If the field is on the right, the operator is flipped.
+    fn range(
+        &self,
+        op_field_left: &str,
+        op_field_right: &str,
+        left: &Expr,
+        right: &Expr,
+    ) -> JsonValue {
+        let (path, op, value) = match (left, right) {
+            (Expr::Field(path), Expr::Literal(value)) => (path, op_field_left, value),
+            (Expr::Literal(value), Expr::Field(path)) => (path, op_field_right, value),
+            _ => return json!({ "match_none": {} }),
+        };
+        self.term("range", path, obj1(op.to_string(), value_to_json(value)))
+    }
+
+    fn not(&self, operand: &Expr) -> JsonValue {
+        // NOT(is_null(field)) → exists
+        if let Expr::Call { name, args } = operand {
+            if name == "is_null" {
+                if let [Expr::Field(path)] = args.as_slice() {
+                    return self.exists(path);
+                }
+            }
+        }
+        must_not(self.expr(operand))
+    }
+
+    fn call(&self, name: &str, args: &[Expr]) -> JsonValue {
+        if let Some(template) = self.options.functions.get(name) {
+            return self.template(template, args).unwrap_or_else(|| {
+                self.untranslatable(name);
+                json!({ "match_none": {} })
+            });
+        }
+
+        match (name, args) {
+            ("is_null", [Expr::Field(path)]) => must_not(self.exists(path)),
+            ("starts_with", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
+                self.term("prefix", path, JsonValue::String(s.to_string()))
+            }
+            ("ends_with", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
+                let pattern = format!("*{}", wildcard_escape(s));
+                self.term("wildcard", path, JsonValue::String(pattern))
+            }
+            ("contains_str", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
+                let pattern = format!("*{}*", wildcard_escape(s));
+                self.term("wildcard", path, JsonValue::String(pattern))
+            }
+            // regex_match(pattern, text); Lucene patterns match the whole term
+            ("regex_match", [Expr::Literal(Value::String(pattern)), Expr::Field(path)]) => {
+                let (start, pattern) = match pattern.strip_prefix('^') {
+                    Some(rest) => ("", rest),
+                    None => (".*", pattern.as_ref()),
+                };
+                let (pattern, end) = match pattern.strip_suffix('$') {
+                    Some(rest) if !rest.ends_with('\\') => (rest, ""),
+                    _ => (pattern, ".*"),
+                };
+                let pattern = format!("{}({}){}", start, pattern, end);
+                self.term("regexp", path, json!({ "value": pattern, "flags": "NONE" }))
+            }
+            _ => {
+                self.untranslatable(name);
+                json!({ "match_none": {} })
+            }
+        }
+    }
+
+    /// Comparison of a function result with a literal
+    fn computed(&self, op: BinaryOp, left: &Expr, right: &Expr) -> Option<JsonValue> {
+        let (call, op, value) = match (left, right) {
+            (call @ Expr::Call { .. }, Expr::Literal(value)) => (call, op, value),
+            (Expr::Literal(value), call @ Expr::Call { .. }) => (call, flip(op)?, value),
+            (Expr::Field(path), Expr::Call { name, args }) => {
+                return self.time_range(path, op, name, args)
+            }
+            (Expr::Call { name, args }, Expr::Field(path)) => {
+                return self.time_range(path, flip(op)?, name, args)
+            }
+            _ => return None,
+        };
+        let Expr::Call { name, args } = call else {
+            return None;
+        };
+
+        // lower(f) == "x" and upper(f) == "X": case-insensitive term
+        match (name.as_str(), args.as_slice(), op, value) {
+            (
+                "lower" | "upper",
+                [Expr::Field(path)],
+                BinaryOp::Eq | BinaryOp::Ne,
+                Value::String(s),
+            ) => {
+                let cased = match name.as_str() {
+                    "lower" => s.to_lowercase(),
+                    _ => s.to_uppercase(),
+                };
+                if cased != s.as_ref() {
+                    return None;
+                }
+                let query = self.term(
+                    "term",
+                    path,
+                    json!({ "value": s.to_string(), "case_insensitive": true }),
+                );
+                Some(match op {
+                    BinaryOp::Eq => query,
+                    _ => must_not(query),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// `field <op> now()` (or `date_add(now(), …)`) as a date math range
+    fn time_range(&self, path: &str, op: BinaryOp, name: &str, args: &[Expr]) -> Option<JsonValue> {
+        let offset = match (name, args) {
+            ("now" | "now_millis", []) => 0,
+            (
+                "date_add",
+                [Expr::Call {
+                    name: now,
+                    args: none,
+                }, amount, unit],
+            ) if now == "now" && none.is_empty() => date_offset(amount, unit)?,
+            _ => return None,
+        };
+        let op = match op {
+            BinaryOp::Lt => "lt",
+            BinaryOp::Le => "lte",
+            BinaryOp::Gt => "gt",
+            BinaryOp::Ge => "gte",
+            _ => return None,
+        };
+        let value = match offset {
+            0 => "now".to_string(),
+            _ if offset < 0 => format!("now{}s", offset),
+            _ => format!("now+{}s", offset),
+        };
+        Some(self.term(
+            "range",
+            path,
+            obj1(op.to_string(), JsonValue::String(value)),
+        ))
+    }
+
+    /// Replace the `"{N}"` strings of a custom translation with arguments
+    fn template(&self, template: &JsonValue, args: &[Expr]) -> Option<JsonValue> {
+        let arg = |s: &str| {
+            let index = s
+                .strip_prefix('{')?
+                .strip_suffix('}')?
+                .parse::<usize>()
+                .ok()?;
+            Some(match args.get(index) {
+                Some(Expr::Field(path)) => Some(JsonValue::String(self.resolve_col(path))),
+                Some(Expr::Literal(value)) => Some(value_to_json(value)),
+                _ => None,
+            })
+        };
+        Some(match template {
+            JsonValue::String(s) => match arg(s) {
+                Some(value) => value?,
+                None => template.clone(),
+            },
+            JsonValue::Array(items) => JsonValue::Array(
+                items
+                    .iter()
+                    .map(|item| self.template(item, args))
+                    .collect::<Option<_>>()?,
+            ),
+            // Keys may stand for fields too: `{ "term": { "{0}": "{1}" } }`
+            JsonValue::Object(map) => {
+                let mut out = Map::new();
+                for (key, value) in map {
+                    let key = match arg(key) {
+                        Some(Some(JsonValue::String(field))) => field,
+                        Some(_) => return None,
+                        None => key.clone(),
+                    };
+                    out.insert(key, self.template(value, args)?);
+                }
+                JsonValue::Object(out)
+            }
+            other => other.clone(),
+        })
+    }
+
+    /// Record a call without translation
+    fn untranslatable(&self, name: &str) {
+        self.untranslated
+            .borrow_mut()
+            .get_or_insert_with(|| name.to_string());
+    }
+
+    fn exists(&self, path: &str) -> JsonValue {
+        let field = self.resolve_col(path);
+        self.nested(&field, json!({ "exists": { "field": field } }))
+    }
+
+    /// Build `{ kind: { field: body } }`, wrapped for nested fields
+    fn term(&self, kind: &str, path: &str, body: JsonValue) -> JsonValue {
+        let field = self.resolve_col(path);
+        let query = obj1(kind.to_string(), obj1(field.clone(), body));
+        self.nested(&field, query)
+    }
+
+    /// Wrap a leaf query in `nested` queries, innermost path first
+    fn nested(&self, field: &str, query: JsonValue) -> JsonValue {
+        let mut scopes: Vec<&str> = self
+            .options
+            .nested_paths
+            .iter()
+            .map(String::as_str)
+            .filter(|p| {
+                field
+                    .strip_prefix(p)
+                    .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .collect();
+        scopes.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        scopes.into_iter().fold(
+            query,
+            |query, path| json!({ "nested": { "path": path, "query": query } }),
+        )
+    }
+
+    fn resolve_col(&self, path: &str) -> String {
+        self.mapping
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| path.replace('.', "_"))
+    }
+}
+
+// --- helpers ---
+
+/// The operator with its operands swapped
+fn flip(op: BinaryOp) -> Option<BinaryOp> {
+    Some(match op {
+        BinaryOp::Eq | BinaryOp::Ne => op,
+        BinaryOp::Lt => BinaryOp::Gt,
+        BinaryOp::Le => BinaryOp::Ge,
+        BinaryOp::Gt => BinaryOp::Lt,
+        BinaryOp::Ge => BinaryOp::Le,
+        _ => return None,
+    })
+}
+
+fn must(clauses: Vec<JsonValue>) -> JsonValue {
+    json!({ "bool": { "must": clauses } })
+}
+
+fn should(clauses: Vec<JsonValue>) -> JsonValue {
+    json!({ "bool": { "should": clauses, "minimum_should_match": 1 } })
+}
+
+fn must_not(clause: JsonValue) -> JsonValue {
+    json!({ "bool": { "must_not": [clause] } })
+}
+
+/// Build `{ key: val }` with a dynamic key.
+fn obj1(key: String, val: JsonValue) -> JsonValue {
+    let mut map = Map::new();
+    map.insert(key, val);
+    JsonValue::Object(map)
+}
+
+/// Values of an array literal, folded or not
+fn literal_array(expr: &Expr) -> Option<Vec<JsonValue>> {
+    match expr {
+        Expr::Literal(Value::Array(arr)) => Some(arr.iter().map(value_to_json).collect()),
+        Expr::Array(elems) => elems
+            .iter()
+            .map(|e| match e {
+                Expr::Literal(v) => Some(value_to_json(v)),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Escape wildcard metacharacters (`*`, `?`, `\`) for use in a `wildcard` query.
+fn wildcard_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn value_to_json(val: &Value) -> JsonValue {
+    match val {
+        Value::Null => JsonValue::Null,
+        Value::Bool(b) => JsonValue::Bool(*b),
+        Value::Int(n) => JsonValue::Number((*n).into()),
+        Value::Float(f) => serde_json::Number::from_f64(*f)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        Value::String(s) => JsonValue::String(s.to_string()),
+        Value::Array(arr) => JsonValue::Array(arr.iter().map(value_to_json).collect()),
+        Value::Object(map) => {
+            let obj: serde_json::Map<String, JsonValue> = map
+                .iter()
+                .map(|(k, v)| (k.to_string(), value_to_json(v)))
+                .collect();
+            JsonValue::Object(obj)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
+
+    fn es(conditions: &[&str], mapping: &[(&str, &str)], nested: &[&str]) -> JsonValue {
+        let paths = vec![FilterPath {
+            conditions: conditions
+                .iter()
+                .map(|c| ExprParser::parse(c).unwrap())
+                .collect(),
+            result_code: "OK".to_string(),
+        }];
+        let mapping = mapping
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = ElasticsearchOptions {
+            nested_paths: nested.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        to_elasticsearch(&paths, &mapping, &options)
+    }
+
+    #[test]
+    fn test_es_never_and_always_matches() {
+        let options = ElasticsearchOptions::default();
+        assert_eq!(
+            to_elasticsearch(&[], &HashMap::new(), &options),
+            json!({ "match_none": {} })
+        );
+        let always = vec![FilterPath {
+            conditions: vec![],
+            result_code: "OK".to_string(),
+        }];
+        assert_eq!(
+            to_elasticsearch(&always, &HashMap::new(), &options),
+            json!({ "match_all": {} })
+        );
+    }
+
+    #[test]
+    fn test_es_term_and_field_mapping() {
+        assert_eq!(
+            es(
+                &[r#"resource.owner == "alice""#],
+                &[("resource.owner", "owner_id")],
+                &[]
+            ),
+            json!({ "term": { "owner_id": "alice" } })
+        );
+        assert_eq!(
+            es(&[r#"resource.owner != "alice""#], &[], &[]),
+            json!({ "bool": { "must_not": [{ "term": { "resource_owner": "alice" } }] } })
+        );
+    }
+
+    #[test]
+    fn test_es_multi_path_should() {
+        let paths: Vec<FilterPath> = [r#"owner == "alice""#, r#"visibility == "public""#]
+            .iter()
+            .map(|c| FilterPath {
+                conditions: vec![ExprParser::parse(c).unwrap()],
+                result_code: "OK".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            to_elasticsearch(&paths, &HashMap::new(), &ElasticsearchOptions::default()),
+            json!({ "bool": {
+                "should": [
+                    { "term": { "owner": "alice" } },
+                    { "term": { "visibility": "public" } }
+                ],
+                "minimum_should_match": 1
+            } })
+        );
+    }
+
+    #[test]
+    fn test_es_must_within_path() {
+        assert_eq!(
+            es(&["age >= 18", "10 > score"], &[], &[]),
+            json!({ "bool": { "must": [
+                { "range": { "age": { "gte": 18 } } },
+                { "range": { "score": { "lt": 10 } } }
+            ] } })
+        );
+    }
+
+    #[test]
+    fn test_es_terms() {
+        assert_eq!(
+            es(&[r#"status in ["active", "pending"]"#], &[], &[]),
+            json!({ "terms": { "status": ["active", "pending"] } })
+        );
+        assert_eq!(
+            es(&[r#"status not in ["closed"]"#], &[], &[]),
+            json!({ "bool": { "must_not": [{ "terms": { "status": ["closed"] } }] } })
+        );
+    }
+
+    #[test]
+    fn test_es_exists() {
+        assert_eq!(
+            es(&["deleted_at == null"], &[], &[]),
+            json!({ "bool": { "must_not": [{ "exists": { "field": "deleted_at" } }] } })
+        );
+        assert_eq!(
+            es(&["!is_null(email)"], &[], &[]),
+            json!({ "exists": { "field": "email" } })
+        );
+    }
+
+    #[test]
+    fn test_es_prefix_and_wildcard() {
+        assert_eq!(
+            es(&[r#"starts_with(code, "PRJ")"#], &[], &[]),
+            json!({ "prefix": { "code": "PRJ" } })
+        );
+        assert_eq!(
+            es(&[r#"ends_with(filename, ".rs")"#], &[], &[]),
+            json!({ "wildcard": { "filename": "*.rs" } })
+        );
+        assert_eq!(
+            es(&[r#"title contains "what?*""#], &[], &[]),
+            json!({ "wildcard": { "title": "*what\\?\\**" } })
+        );
+    }
+
+    #[test]
+    fn test_es_nested() {
+        let mapping = [
+            ("order.sku", "items.sku"),
+            ("order.option", "items.options.name"),
+        ];
+        assert_eq!(
+            es(&[r#"order.sku == "A-1""#], &mapping, &["items"]),
+            json!({ "nested": {
+                "path": "items",
+                "query": { "term": { "items.sku": "A-1" } }
+            } })
+        );
+        // Negation applies to the whole nested query: no element matches
+        assert_eq!(
+            es(
+                &[r#"order.option != "gift""#],
+                &mapping,
+                &["items", "items.options"]
+            ),
+            json!({ "bool": { "must_not": [{ "nested": {
+                "path": "items",
+                "query": { "nested": {
+                    "path": "items.options",
+                    "query": { "term": { "items.options.name": "gift" } }
+                } }
+            } }] } })
+        );
+    }
+
+    #[test]
+    fn test_es_function_translations() {
+        assert_eq!(
+            es(&[r#"lower(owner) == "alice""#], &[], &[]),
+            json!({ "term": { "owner": { "value": "alice", "case_insensitive": true } } })
+        );
+        assert_eq!(
+            es(&[r#"regex_match("^PRJ-[0-9]+", code)"#], &[], &[]),
+            json!({ "regexp": { "code": { "value": "(PRJ-[0-9]+).*", "flags": "NONE" } } })
+        );
+        assert_eq!(
+            es(
+                &[r#"created >= date_add(now(), -7, "d")"#, "now() > expires"],
+                &[],
+                &[]
+            ),
+            json!({ "bool": { "must": [
+                { "range": { "created": { "gte": "now-604800s" } } },
+                { "range": { "expires": { "lt": "now" } } }
+            ] } })
+        );
+
+        // An upper-case value can never equal a lowered field
+        let options = ElasticsearchOptions::default();
+        let lower = ExprParser::parse(r#"lower(owner) == "Alice""#).unwrap();
+        assert_eq!(
+            untranslated(&lower, &HashMap::new(), &options),
+            Some("lower".to_string())
+        );
+        let len = ExprParser::parse("len(tags) > 2").unwrap();
+        assert_eq!(
+            untranslated(&len, &HashMap::new(), &options),
+            Some("len".to_string())
+        );
+    }
+
+    #[test]
+    fn test_es_custom_translations() {
+        let options = ElasticsearchOptions::default().with_function(
+            "fuzzy_match",
+            json!({ "match": { "{0}": { "query": "{1}", "fuzziness": "AUTO" } } }),
+        );
+        let paths = vec![FilterPath {
+            conditions: vec![ExprParser::parse(r#"fuzzy_match(name, "jon")"#).unwrap()],
+            result_code: "OK".to_string(),
+        }];
+        let mapping = HashMap::from([("name".to_string(), "full_name".to_string())]);
+        assert_eq!(
+            to_elasticsearch(&paths, &mapping, &options),
+            json!({ "match": { "full_name": { "query": "jon", "fuzziness": "AUTO" } } })
+        );
+    }
+}
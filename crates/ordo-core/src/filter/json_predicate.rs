@@ -86,6 +86,7 @@ fn expr_to_json(expr: &Expr, mapping: &HashMap<String, String>) -> JsonValue {
             let items: Vec<JsonValue> = elems.iter().map(|e| expr_to_json(e, mapping)).collect();
             json!({ "type": "array", "items": items })
         }
+        Expr::Coalesce(exprs) => call_to_json("coalesce", exprs, mapping),
         _ => json!({ "type": "unsupported" }),
     }
 }
@@ -1,10 +1,15 @@
 //! Data Filter API — partial evaluation for database predicate generation
 //!
-//! Converts rule sets into SQL WHERE clauses or JSON predicates by:
+//! Converts rule sets into SQL WHERE clauses, JSON predicates, MongoDB `$match`
+//! stages or Elasticsearch queries by:
 //! 1. Substituting known input fields (folding constants)
 //! 2. Traversing the rule graph (DFS) to collect all paths to target results
 //! 3. Each path's conditions are ANDed; multiple paths are ORed
-//! 4. The combined expression is rendered as SQL or JSON
+//! 4. Simplifying the disjunction (shared conditions, redundant paths,
+//!    contradictions)
+//! 5. Leaving out conditions with functions the format cannot translate,
+//!    where that only narrows the filter
+//! 6. The combined expression is rendered in the requested format
 //!
 //! # Typical use case
 //!
@@ -15,14 +20,25 @@
 //!
 //! This WHERE clause can be pushed directly to the database, avoiding a
 //! full table scan + row-by-row rule execution.
+//!
+//! The same partial evaluation can instead produce a residual ruleset
+//! ([`FilterCompiler::residual`]): the rule graph specialized to the known
+//! input, to be executed later with the remaining fields.
 
+pub mod elasticsearch;
 pub mod json_predicate;
 pub mod mongo;
 pub mod partial_eval;
 pub mod path_collector;
+pub mod pushdown;
+pub mod residual;
+pub mod simplify;
 pub mod sql;
+#[cfg(feature = "filter-verify")]
+pub mod verify;
 
 use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
@@ -30,10 +46,16 @@ use serde_json::{json, Value as JsonValue};
 use crate::context::Value;
 use crate::error::Result;
 use crate::expr::Expr;
-use crate::rule::RuleSet;
+use crate::rule::{RuleSet, RuleSetResolver};
 
+use elasticsearch::ElasticsearchOptions;
+use mongo::MongoOptions;
 use partial_eval::PartialEvaluator;
 use path_collector::collect_paths;
+use pushdown::{push_down, PushDown};
+use residual::{residual_ruleset, ResidualRuleSet};
+use simplify::{clause_count, simplify};
+use sql::SqlOptions;
 
 /// Output format for the generated filter
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -44,6 +66,9 @@ pub enum FilterFormat {
     Json,
     /// MongoDB aggregation pipeline `$match` stage
     Mongo,
+    /// Elasticsearch / OpenSearch Query DSL
+    #[serde(alias = "opensearch")]
+    Elasticsearch,
 }
 
 /// Request for filter compilation
@@ -68,6 +93,18 @@ pub struct FilterRequest {
     /// Prevents exponential blowup on highly branched rule graphs.
     #[serde(default = "default_max_paths")]
     pub max_paths: usize,
+
+    /// SQL dialect, parameterization and strict column mapping (sql format only)
+    #[serde(default)]
+    pub sql: SqlOptions,
+
+    /// Custom function translations (mongo format only)
+    #[serde(default)]
+    pub mongo: MongoOptions,
+
+    /// Nested field paths (elasticsearch format only)
+    #[serde(default)]
+    pub elasticsearch: ElasticsearchOptions,
 }
 
 fn default_max_paths() -> usize {
@@ -83,6 +120,7 @@ pub struct FilterResult {
     /// The generated filter:
     /// - SQL format: a string (`"owner_id = 'alice' OR visibility = 'public'"`)
     /// - JSON format: a predicate object
+    /// - Mongo / Elasticsearch format: a query object
     pub filter: JsonValue,
 
     /// True when all possible inputs match (e.g. admin sees everything).
@@ -102,26 +140,54 @@ pub struct FilterResult {
 
     /// Fields that remain unknown (appear in the filter as database columns)
     pub unknown_fields: Vec<String>,
+
+    /// Placeholder values in order, for parameterized SQL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<Value>>,
+
+    /// Comparisons across all collected paths, before simplification
+    pub clauses_before: usize,
+
+    /// Comparisons in the generated filter
+    pub clauses_after: usize,
+
+    /// Functions the format cannot translate. Conditions calling them were
+    /// left out, so the filter may miss rows the rules would accept.
+    pub untranslated: Vec<String>,
 }
 
 /// Compiles a RuleSet into a database filter for a given known context
-pub struct FilterCompiler;
+pub struct FilterCompiler {
+    /// Optional resolver for inlining CallRuleSet actions
+    resolver: Option<Arc<dyn RuleSetResolver>>,
+}
 
 impl FilterCompiler {
     pub fn new() -> Self {
-        FilterCompiler
+        FilterCompiler { resolver: None }
+    }
+
+    /// Set a resolver for CallRuleSet actions
+    pub fn set_resolver(&mut self, resolver: Arc<dyn RuleSetResolver>) {
+        self.resolver = Some(resolver);
     }
 
     /// Compile the ruleset + request into a database filter.
     pub fn compile(&self, ruleset: &RuleSet, request: FilterRequest) -> Result<FilterResult> {
         let mut evaluator = PartialEvaluator::new(request.known_input);
+        let mut params = match request.format {
+            FilterFormat::Sql if request.sql.parameterized => Some(Vec::new()),
+            _ => None,
+        };
 
         let (paths, truncated) = collect_paths(
             ruleset,
             &mut evaluator,
             &request.target_results,
             request.max_paths,
+            self.resolver.as_deref(),
         )?;
+        let clauses_before = clause_count(&paths);
 
         // When the path limit was hit, the generated filter would be incomplete
         // (too strict), which could silently hide rows that the rule engine
@@ -130,7 +196,8 @@ impl FilterCompiler {
         if truncated {
             let filter = match request.format {
                 FilterFormat::Mongo => json!({}),
-                _ => JsonValue::String("TRUE".to_string()),
+                FilterFormat::Elasticsearch => json!({ "match_all": {} }),
+                _ => JsonValue::String(request.sql.dialect.constant(true).to_string()),
             };
             return Ok(FilterResult {
                 format: request.format,
@@ -139,9 +206,15 @@ impl FilterCompiler {
                 never_matches: false,
                 truncated: true,
                 unknown_fields: vec![],
+                params,
+                clauses_before,
+                clauses_after: 0,
+                untranslated: vec![],
             });
         }
 
+        // Paths whose conditions contradict each other are dropped here
+        let paths = simplify(paths);
         if paths.is_empty() {
             return Ok(FilterResult {
                 format: request.format,
@@ -150,9 +223,34 @@ impl FilterCompiler {
                 never_matches: true,
                 truncated: false,
                 unknown_fields: vec![],
+                params,
+                clauses_before,
+                clauses_after: 0,
+                untranslated: vec![],
             });
         }
 
+        let mapping = &request.field_mapping;
+        let PushDown {
+            paths,
+            untranslated,
+        } = match request.format {
+            FilterFormat::Sql => push_down(paths, "SQL", |expr| {
+                sql::untranslated(expr, mapping, &request.sql)
+            })?,
+            FilterFormat::Mongo => push_down(paths, "MongoDB", |expr| {
+                mongo::untranslated(expr, mapping, &request.mongo)
+            })?,
+            FilterFormat::Elasticsearch => push_down(paths, "Elasticsearch", |expr| {
+                elasticsearch::untranslated(expr, mapping, &request.elasticsearch)
+            })?,
+            // Calls are passed through as `{ "type": "call" }` nodes
+            FilterFormat::Json => PushDown {
+                paths,
+                untranslated: vec![],
+            },
+        };
+
         let always_matches = paths.iter().any(|p| p.conditions.is_empty());
 
         // Collect all unknown field references across all paths
@@ -166,11 +264,21 @@ impl FilterCompiler {
 
         let filter = match request.format {
             FilterFormat::Sql => {
-                let s = sql::to_sql(&paths, &request.field_mapping)?;
-                JsonValue::String(s)
+                let clause = sql::to_sql_with(&paths, &request.field_mapping, &request.sql)?;
+                if let Some(params) = params.as_mut() {
+                    *params = clause.params;
+                }
+                JsonValue::String(clause.sql)
             }
             FilterFormat::Json => json_predicate::to_json(&paths, &request.field_mapping),
-            FilterFormat::Mongo => mongo::to_mongo(&paths, &request.field_mapping),
+            FilterFormat::Mongo => {
+                mongo::to_mongo_with(&paths, &request.field_mapping, &request.mongo)
+            }
+            FilterFormat::Elasticsearch => elasticsearch::to_elasticsearch(
+                &paths,
+                &request.field_mapping,
+                &request.elasticsearch,
+            ),
         };
 
         Ok(FilterResult {
@@ -180,8 +288,33 @@ impl FilterCompiler {
             never_matches: false,
             truncated: false,
             unknown_fields,
+            params,
+            clauses_before,
+            clauses_after: clause_count(&paths),
+            untranslated,
         })
     }
+
+    /// Check the SQL filter for `request` against rule execution on sample
+    /// rows in an in-memory SQLite database (see [`verify`]).
+    #[cfg(feature = "filter-verify")]
+    pub fn verify(
+        &self,
+        ruleset: &RuleSet,
+        request: &FilterRequest,
+        options: &verify::VerifyOptions,
+    ) -> Result<verify::VerifyReport> {
+        verify::verify(ruleset, request, options, self.resolver.clone())
+    }
+
+    /// Specialize the ruleset to `known_input` instead of rendering a filter.
+    ///
+    /// The residual ruleset can be cached per known input (e.g. per role) and
+    /// executed later with the remaining fields, or compiled with
+    /// `RuleSetCompiler` for distribution.
+    pub fn residual(&self, ruleset: &RuleSet, known_input: Value) -> Result<ResidualRuleSet> {
+        residual_ruleset(ruleset, &mut PartialEvaluator::new(known_input))
+    }
 }
 
 impl Default for FilterCompiler {
@@ -191,7 +324,7 @@ impl Default for FilterCompiler {
 }
 
 /// Recursively collect all Expr::Field / Expr::Exists paths from an expression
-fn collect_fields(expr: &Expr, out: &mut BTreeSet<String>) {
+pub(crate) fn collect_fields(expr: &Expr, out: &mut BTreeSet<String>) {
     match expr {
         Expr::Field(path) | Expr::Exists(path) => {
             out.insert(path.clone());
@@ -237,8 +370,9 @@ fn collect_fields(expr: &Expr, out: &mut BTreeSet<String>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::expr::ExprParser;
     use crate::prelude::*;
-    use crate::rule::{Branch, Condition, StepKind, TerminalResult};
+    use crate::rule::{Action, ActionKind, Branch, Condition, StepKind, TerminalResult};
 
     fn build_access_ruleset() -> RuleSet {
         let mut rs = RuleSet::new("access", "check_access");
@@ -305,6 +439,9 @@ mod tests {
             format: FilterFormat::Sql,
             field_mapping: mapping,
             max_paths: 100,
+            sql: SqlOptions::default(),
+            mongo: MongoOptions::default(),
+            elasticsearch: ElasticsearchOptions::default(),
         };
 
         let result = compiler.compile(&rs, request).unwrap();
@@ -333,6 +470,9 @@ mod tests {
             format: FilterFormat::Sql,
             field_mapping: HashMap::new(),
             max_paths: 100,
+            sql: SqlOptions::default(),
+            mongo: MongoOptions::default(),
+            elasticsearch: ElasticsearchOptions::default(),
         };
 
         let result = compiler.compile(&rs, request).unwrap();
@@ -354,6 +494,9 @@ mod tests {
             format: FilterFormat::Sql,
             field_mapping: HashMap::new(),
             max_paths: 100,
+            sql: SqlOptions::default(),
+            mongo: MongoOptions::default(),
+            elasticsearch: ElasticsearchOptions::default(),
         };
 
         let result = compiler.compile(&rs, request).unwrap();
@@ -378,10 +521,257 @@ mod tests {
             format: FilterFormat::Json,
             field_mapping: mapping,
             max_paths: 100,
+            sql: SqlOptions::default(),
+            mongo: MongoOptions::default(),
+            elasticsearch: ElasticsearchOptions::default(),
         };
 
         let result = compiler.compile(&rs, request).unwrap();
         assert!(result.filter.is_object() || result.filter.is_array());
         assert_eq!(result.filter["type"], "or");
     }
+
+    #[test]
+    fn test_filter_parameterized_sql() {
+        let rs = build_access_ruleset();
+        let compiler = FilterCompiler::new();
+
+        let known: Value =
+            serde_json::from_str(r#"{"user": {"role": "viewer", "id": "alice"}}"#).unwrap();
+
+        let mut mapping = HashMap::new();
+        mapping.insert("resource.owner".to_string(), "owner_id".to_string());
+        mapping.insert("resource.visibility".to_string(), "visibility".to_string());
+
+        let request = FilterRequest {
+            known_input: known,
+            target_results: vec!["APPROVED".to_string()],
+            format: FilterFormat::Sql,
+            field_mapping: mapping,
+            max_paths: 100,
+            sql: SqlOptions {
+                dialect: sql::SqlDialect::Postgres,
+                parameterized: true,
+                strict: true,
+                ..Default::default()
+            },
+            mongo: MongoOptions::default(),
+            elasticsearch: ElasticsearchOptions::default(),
+        };
+
+        let result = compiler.compile(&rs, request).unwrap();
+        assert_eq!(
+            result.filter.as_str().unwrap(),
+            r#"("owner_id" = $1) OR ("visibility" = $2)"#
+        );
+        assert_eq!(
+            result.params,
+            Some(vec![Value::string("alice"), Value::string("public")])
+        );
+    }
+
+    fn set(name: &str, value: &str) -> Action {
+        Action {
+            kind: ActionKind::SetVariable {
+                name: name.to_string(),
+                value: ExprParser::parse(value).unwrap(),
+            },
+            description: String::new(),
+        }
+    }
+
+    /// `prepare` runs `actions`, then `check` approves when `condition` holds
+    fn variable_ruleset(actions: Vec<Action>, condition: &str) -> RuleSet {
+        let mut rs = RuleSet::new("vars", "prepare");
+        rs.add_step(Step::action("prepare", "Prepare", actions, "check"));
+        rs.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string(condition), "approved")
+                .default("denied")
+                .build(),
+        );
+        rs.add_step(Step::terminal(
+            "approved",
+            "Approved",
+            TerminalResult::new("APPROVED"),
+        ));
+        rs.add_step(Step::terminal(
+            "denied",
+            "Denied",
+            TerminalResult::new("DENIED"),
+        ));
+        rs
+    }
+
+    fn compile_sql(compiler: &FilterCompiler, rs: &RuleSet, known: &str) -> Result<FilterResult> {
+        let request = FilterRequest {
+            known_input: serde_json::from_str(known).unwrap(),
+            target_results: vec!["APPROVED".to_string()],
+            format: FilterFormat::Sql,
+            field_mapping: HashMap::new(),
+            max_paths: 100,
+            sql: SqlOptions::default(),
+            mongo: MongoOptions::default(),
+            elasticsearch: ElasticsearchOptions::default(),
+        };
+        compiler.compile(rs, request)
+    }
+
+    #[test]
+    fn test_filter_set_variable() {
+        let rs = variable_ruleset(
+            vec![
+                set("limit", "user.quota * 2"),
+                set("owner", "resource.owner"),
+            ],
+            "resource.size <= $limit && $owner == user.id",
+        );
+        let result = compile_sql(
+            &FilterCompiler::new(),
+            &rs,
+            r#"{"user": {"quota": 50, "id": "alice"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            result.filter,
+            "resource_size <= 100 AND resource_owner = 'alice'"
+        );
+    }
+
+    #[test]
+    fn test_filter_branch_actions() {
+        let mut rs = RuleSet::new("tiers", "tier");
+        rs.add_step(
+            Step::decision("tier", "Tier")
+                .branch_with_actions(
+                    Condition::from_string("user.plan == \"pro\""),
+                    "check",
+                    vec![set("max_size", "1000")],
+                )
+                .default("basic")
+                .build(),
+        );
+        rs.add_step(Step::action(
+            "basic",
+            "Basic",
+            vec![set("max_size", "10")],
+            "check",
+        ));
+        rs.add_step(
+            Step::decision("check", "Check")
+                .branch(
+                    Condition::from_string("resource.size <= $max_size"),
+                    "approved",
+                )
+                .build(),
+        );
+        rs.add_step(Step::terminal(
+            "approved",
+            "Approved",
+            TerminalResult::new("APPROVED"),
+        ));
+
+        let compiler = FilterCompiler::new();
+        let result = compile_sql(&compiler, &rs, r#"{"user": {"plan": "pro"}}"#).unwrap();
+        assert_eq!(result.filter, "resource_size <= 1000");
+        let result = compile_sql(&compiler, &rs, "{}").unwrap();
+        assert_eq!(
+            result.filter,
+            "(user_plan = 'pro' AND resource_size <= 1000) OR (user_plan != 'pro' AND resource_size <= 10)"
+        );
+    }
+
+    #[test]
+    fn test_filter_inlines_call_ruleset() {
+        struct Resolver(Arc<RuleSet>);
+        impl RuleSetResolver for Resolver {
+            fn resolve(&self, name: &str) -> Option<Arc<RuleSet>> {
+                (name == "size_class").then(|| self.0.clone())
+            }
+        }
+
+        let mut callee = RuleSet::new("size_class", "classify");
+        callee.add_step(
+            Step::decision("classify", "Classify")
+                .branch(Condition::from_string("size > 100"), "large")
+                .default("small")
+                .build(),
+        );
+        callee.add_step(Step::terminal(
+            "large",
+            "Large",
+            TerminalResult::new("LARGE"),
+        ));
+        callee.add_step(Step::terminal(
+            "small",
+            "Small",
+            TerminalResult::new("SMALL"),
+        ));
+
+        let call = Action {
+            kind: ActionKind::CallRuleSet {
+                ruleset_name: "size_class".to_string(),
+                input_mapping: Some(ExprParser::parse("{\"size\": resource.bytes}").unwrap()),
+                result_variable: "class".to_string(),
+            },
+            description: String::new(),
+        };
+        let rs = variable_ruleset(
+            vec![call],
+            "object_get($class, \"code\", \"\") == \"SMALL\"",
+        );
+
+        // Without a resolver the result variable cannot be pushed down
+        let err = compile_sql(&FilterCompiler::new(), &rs, "{}").unwrap_err();
+        assert!(err.to_string().contains("no resolver"), "{}", err);
+
+        let mut compiler = FilterCompiler::new();
+        compiler.set_resolver(Arc::new(Resolver(Arc::new(callee))));
+        let result = compile_sql(&compiler, &rs, "{}").unwrap();
+        assert_eq!(result.filter, "NOT (resource_bytes > 100)");
+    }
+
+    #[test]
+    fn test_filter_unset_variable_is_an_error() {
+        let rs = variable_ruleset(vec![], "resource.size <= $limit");
+        let err = compile_sql(&FilterCompiler::new(), &rs, "{}").unwrap_err();
+        assert!(err.to_string().contains("'$limit' is not set"), "{}", err);
+
+        let rs = variable_ruleset(
+            vec![set("limit", "if resource.big then 10 else 5")],
+            "resource.size <= $limit",
+        );
+        let err = compile_sql(&FilterCompiler::new(), &rs, "{}").unwrap_err();
+        assert!(err.to_string().contains("cannot be rendered"), "{}", err);
+    }
+
+    #[test]
+    fn test_filter_untranslatable_function_falls_back() {
+        let rs = variable_ruleset(
+            vec![],
+            r#"lower(resource.name) == "report" && (soundex(resource.owner) == soundex(user.name) || resource.owner == user.id)"#,
+        );
+        let known = r#"{"user": {"name": "Alice", "id": "alice"}}"#;
+        let result = compile_sql(&FilterCompiler::new(), &rs, known).unwrap();
+        // The fuzzy owner match is left out: the filter only narrows
+        assert_eq!(
+            result.filter,
+            "LOWER(resource_name) = 'report' AND resource_owner = 'alice'"
+        );
+        assert_eq!(result.untranslated, vec!["soundex".to_string()]);
+        assert_eq!(
+            result.unknown_fields,
+            vec!["resource.name", "resource.owner"]
+        );
+
+        // Without another disjunct there is nothing safe to fall back to
+        let rs = variable_ruleset(vec![], r#"soundex(resource.owner) == "A400""#);
+        let err = compile_sql(&FilterCompiler::new(), &rs, "{}").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Function 'soundex' is not supported in SQL filter generation"),
+            "{}",
+            err
+        );
+    }
 }
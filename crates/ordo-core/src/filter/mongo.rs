@@ -14,18 +14,58 @@
 //!
 //! An always-matches result is an empty document `{}` (no filter).
 //! A never-matches result is `{ "$expr": false }`.
-
+//!
+//! # Functions
+//!
+//! `starts_with`, `ends_with`, `contains_str` and `regex_match` on a field
+//! become `$regex` queries. Comparisons involving other calls are rendered as
+//! `$expr` aggregation expressions: `lower` → `$toLower`, `len` → `$size` (or
+//! `$strLenBytes` for strings), `coalesce` → `$ifNull`, `now()` → `$$NOW`,
+//! `date_add` / `time_diff` → arithmetic on it. [`MongoOptions::functions`]
+//! adds or overrides translations.
+
+use std::cell::RefCell;
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value as JsonValue};
 
 use crate::context::Value;
 use crate::expr::{BinaryOp, Expr, UnaryOp};
 
 use super::path_collector::FilterPath;
+use super::pushdown::{date_offset, unit_seconds};
+
+/// Options for MongoDB output
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MongoOptions {
+    /// Custom function translations: an aggregation expression per function
+    /// name, in which the strings `"{0}"`, `"{1}"`, … stand for the
+    /// arguments. These take precedence over the built-in translations.
+    #[serde(default)]
+    pub functions: HashMap<String, JsonValue>,
+}
+
+impl MongoOptions {
+    /// Translate calls of `name` with `template`, e.g.
+    /// `with_function("abs", json!({ "$abs": "{0}" }))`
+    pub fn with_function(mut self, name: impl Into<String>, template: JsonValue) -> Self {
+        self.functions.insert(name.into(), template);
+        self
+    }
+}
 
 /// Convert filter paths to a MongoDB `$match` predicate.
 pub fn to_mongo(paths: &[FilterPath], mapping: &HashMap<String, String>) -> JsonValue {
+    to_mongo_with(paths, mapping, &MongoOptions::default())
+}
+
+/// Convert filter paths to a MongoDB `$match` predicate for the given options.
+pub fn to_mongo_with(
+    paths: &[FilterPath],
+    mapping: &HashMap<String, String>,
+    options: &MongoOptions,
+) -> JsonValue {
     if paths.is_empty() {
         return json!({ "$expr": false });
     }
@@ -34,176 +74,354 @@ pub fn to_mongo(paths: &[FilterPath], mapping: &HashMap<String, String>) -> Json
         return json!({});
     }
 
+    let builder = MatchBuilder::new(mapping, options);
+
     if paths.len() == 1 {
-        return path_to_mongo(&paths[0], mapping);
+        return builder.path(&paths[0]);
     }
 
-    let clauses: Vec<JsonValue> = paths.iter().map(|p| path_to_mongo(p, mapping)).collect();
+    let clauses: Vec<JsonValue> = paths.iter().map(|p| builder.path(p)).collect();
     json!({ "$or": clauses })
 }
 
-fn path_to_mongo(path: &FilterPath, mapping: &HashMap<String, String>) -> JsonValue {
-    match path.conditions.len() {
-        0 => json!({}),
-        1 => expr_to_mongo(&path.conditions[0], mapping),
-        _ => {
-            let clauses: Vec<JsonValue> = path
-                .conditions
-                .iter()
-                .map(|c| expr_to_mongo(c, mapping))
-                .collect();
-            json!({ "$and": clauses })
+/// Name of a function called in `expr` that has no MongoDB translation
+pub fn untranslated(
+    expr: &Expr,
+    mapping: &HashMap<String, String>,
+    options: &MongoOptions,
+) -> Option<String> {
+    let builder = MatchBuilder::new(mapping, options);
+    builder.expr(expr);
+    builder.untranslated.into_inner()
+}
+
+struct MatchBuilder<'a> {
+    mapping: &'a HashMap<String, String>,
+    options: &'a MongoOptions,
+    /// First function that could not be translated
+    untranslated: RefCell<Option<String>>,
+}
+
+impl<'a> MatchBuilder<'a> {
+    fn new(mapping: &'a HashMap<String, String>, options: &'a MongoOptions) -> Self {
+        Self {
+            mapping,
+            options,
+            untranslated: RefCell::new(None),
         }
     }
-}
 
-fn expr_to_mongo(expr: &Expr, mapping: &HashMap<String, String>) -> JsonValue {
-    match expr {
-        Expr::Binary { op, left, right } => binary_to_mongo(*op, left, right, mapping),
-        Expr::Unary {
-            op: UnaryOp::Not,
-            operand,
-        } => not_to_mongo(operand, mapping),
-        Expr::Call { name, args } => call_to_mongo(name, args, mapping),
-        _ => json!({ "$expr": false }),
+    fn path(&self, path: &FilterPath) -> JsonValue {
+        match path.conditions.len() {
+            0 => json!({}),
+            1 => self.expr(&path.conditions[0]),
+            _ => {
+                let clauses: Vec<JsonValue> =
+                    path.conditions.iter().map(|c| self.expr(c)).collect();
+                json!({ "$and": clauses })
+            }
+        }
     }
-}
 
-fn binary_to_mongo(
-    op: BinaryOp,
-    left: &Expr,
-    right: &Expr,
-    mapping: &HashMap<String, String>,
-) -> JsonValue {
-    match op {
-        BinaryOp::Eq => {
-            if let Expr::Field(path) = left {
-                return obj1(resolve_col(path, mapping), literal_or_null(right));
+    fn expr(&self, expr: &Expr) -> JsonValue {
+        match expr {
+            Expr::Binary { op, left, right } => self.binary(*op, left, right),
+            Expr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => self.not(operand),
+            Expr::Call { name, args } => self.call(name, args),
+            _ => json!({ "$expr": false }),
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, left: &Expr, right: &Expr) -> JsonValue {
+        let logical = matches!(op, BinaryOp::And | BinaryOp::Or);
+        if !logical && (is_computed(left) || is_computed(right)) {
+            return self.computed(op, left, right);
+        }
+
+        match op {
+            BinaryOp::Eq => {
+                if let Expr::Field(path) = left {
+                    return obj1(self.resolve_col(path), literal_or_null(right));
+                }
+                if let Expr::Field(path) = right {
+                    return obj1(self.resolve_col(path), literal_or_null(left));
+                }
+                json!({ "$expr": false })
             }
-            if let Expr::Field(path) = right {
-                return obj1(resolve_col(path, mapping), literal_or_null(left));
+            BinaryOp::Ne => {
+                if let Expr::Field(path) = left {
+                    return obj1(
+                        self.resolve_col(path),
+                        op_obj("$ne", literal_or_null(right)),
+                    );
+                }
+                if let Expr::Field(path) = right {
+                    return obj1(self.resolve_col(path), op_obj("$ne", literal_or_null(left)));
+                }
+                json!({ "$expr": false })
             }
-            json!({ "$expr": false })
-        }
-        BinaryOp::Ne => {
-            if let Expr::Field(path) = left {
-                return obj1(
-                    resolve_col(path, mapping),
-                    op_obj("$ne", literal_or_null(right)),
-                );
+            BinaryOp::Lt => self.cmp("$lt", "$gt", left, right),
+            BinaryOp::Le => self.cmp("$lte", "$gte", left, right),
+            BinaryOp::Gt => self.cmp("$gt", "$lt", left, right),
+            BinaryOp::Ge => self.cmp("$gte", "$lte", left, right),
+            BinaryOp::And => {
+                json!({ "$and": [self.expr(left), self.expr(right)] })
+            }
+            BinaryOp::Or => {
+                json!({ "$or": [self.expr(left), self.expr(right)] })
+            }
+            BinaryOp::In => {
+                if let (Expr::Field(path), Expr::Literal(Value::Array(arr))) = (left, right) {
+                    let col = self.resolve_col(path);
+                    let values: Vec<JsonValue> = arr.iter().map(value_to_json).collect();
+                    return obj1(col, op_obj("$in", JsonValue::Array(values)));
+                }
+                json!({ "$expr": false })
             }
-            if let Expr::Field(path) = right {
-                return obj1(
-                    resolve_col(path, mapping),
-                    op_obj("$ne", literal_or_null(left)),
-                );
+            BinaryOp::NotIn => {
+                if let (Expr::Field(path), Expr::Literal(Value::Array(arr))) = (left, right) {
+                    let col = self.resolve_col(path);
+                    let values: Vec<JsonValue> = arr.iter().map(value_to_json).collect();
+                    return obj1(col, op_obj("$nin", JsonValue::Array(values)));
+                }
+                json!({ "$expr": false })
             }
-            json!({ "$expr": false })
+            BinaryOp::Contains => {
+                if let (Expr::Field(path), Expr::Literal(Value::String(s))) = (left, right) {
+                    let col = self.resolve_col(path);
+                    return obj1(col, regex(regex_escape(s)));
+                }
+                json!({ "$expr": false })
+            }
+            _ => json!({ "$expr": false }),
+        }
+    }
+
+    /// Comparison operator, handling field on either side.
+    /// If the field is on the right, the operator is flipped.
+    fn cmp(
+        &self,
+        op_field_left: &str,
+        op_field_right: &str,
+        left: &Expr,
+        right: &Expr,
+    ) -> JsonValue {
+        if let Expr::Field(path) = left {
+            return obj1(
+                self.resolve_col(path),
+                op_obj(op_field_left, literal_or_null(right)),
+            );
         }
-        BinaryOp::Lt => cmp_to_mongo("$lt", "$gt", left, right, mapping),
-        BinaryOp::Le => cmp_to_mongo("$lte", "$gte", left, right, mapping),
-        BinaryOp::Gt => cmp_to_mongo("$gt", "$lt", left, right, mapping),
-        BinaryOp::Ge => cmp_to_mongo("$gte", "$lte", left, right, mapping),
-        BinaryOp::And => {
-            json!({ "$and": [expr_to_mongo(left, mapping), expr_to_mongo(right, mapping)] })
+        if let Expr::Field(path) = right {
+            return obj1(
+                self.resolve_col(path),
+                op_obj(op_field_right, literal_or_null(left)),
+            );
         }
-        BinaryOp::Or => {
-            json!({ "$or": [expr_to_mongo(left, mapping), expr_to_mongo(right, mapping)] })
+        json!({ "$expr": false })
+    }
+
+    /// Comparison of function results: `{ "$expr": { "$eq": [a, b] } }`
+    fn computed(&self, op: BinaryOp, left: &Expr, right: &Expr) -> JsonValue {
+        let name = match op {
+            BinaryOp::Eq => "$eq",
+            BinaryOp::Ne => "$ne",
+            BinaryOp::Lt => "$lt",
+            BinaryOp::Le => "$lte",
+            BinaryOp::Gt => "$gt",
+            BinaryOp::Ge => "$gte",
+            BinaryOp::In | BinaryOp::NotIn => "$in",
+            _ => return json!({ "$expr": false }),
+        };
+        let (Some(l), Some(r)) = (self.agg(left), self.agg(right)) else {
+            return json!({ "$expr": false });
+        };
+        let comparison = op_obj(name, json!([l, r]));
+        if matches!(op, BinaryOp::NotIn) {
+            return json!({ "$expr": { "$not": [comparison] } });
         }
-        BinaryOp::In => {
-            if let (Expr::Field(path), Expr::Literal(Value::Array(arr))) = (left, right) {
-                let col = resolve_col(path, mapping);
-                let values: Vec<JsonValue> = arr.iter().map(value_to_json).collect();
-                return obj1(col, op_obj("$in", JsonValue::Array(values)));
+        json!({ "$expr": comparison })
+    }
+
+    fn not(&self, operand: &Expr) -> JsonValue {
+        // NOT(is_null(field)) → { field: { $ne: null, $exists: true } }
+        if let Expr::Call { name, args } = operand {
+            if name == "is_null" && !self.options.functions.contains_key(name) {
+                if let [Expr::Field(path)] = args.as_slice() {
+                    let col = self.resolve_col(path);
+                    let mut inner = Map::new();
+                    inner.insert("$ne".to_string(), JsonValue::Null);
+                    inner.insert("$exists".to_string(), JsonValue::Bool(true));
+                    return obj1(col, JsonValue::Object(inner));
+                }
             }
-            json!({ "$expr": false })
         }
-        BinaryOp::NotIn => {
-            if let (Expr::Field(path), Expr::Literal(Value::Array(arr))) = (left, right) {
-                let col = resolve_col(path, mapping);
-                let values: Vec<JsonValue> = arr.iter().map(value_to_json).collect();
-                return obj1(col, op_obj("$nin", JsonValue::Array(values)));
+        // General NOT → $nor
+        json!({ "$nor": [self.expr(operand)] })
+    }
+
+    /// A call used as a condition
+    fn call(&self, name: &str, args: &[Expr]) -> JsonValue {
+        if !self.options.functions.contains_key(name) {
+            let query = match (name, args) {
+                ("is_null", [Expr::Field(path)]) => Some((path, JsonValue::Null)),
+                ("starts_with", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
+                    Some((path, regex(format!("^{}", regex_escape(s)))))
+                }
+                ("ends_with", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
+                    Some((path, regex(format!("{}$", regex_escape(s)))))
+                }
+                ("contains_str", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
+                    Some((path, regex(regex_escape(s))))
+                }
+                // regex_match(pattern, text)
+                ("regex_match", [Expr::Literal(Value::String(pattern)), Expr::Field(path)]) => {
+                    Some((path, regex(pattern.to_string())))
+                }
+                _ => None,
+            };
+            if let Some((path, query)) = query {
+                return obj1(self.resolve_col(path), query);
             }
-            json!({ "$expr": false })
         }
-        BinaryOp::Contains => {
-            if let (Expr::Field(path), Expr::Literal(Value::String(s))) = (left, right) {
-                let col = resolve_col(path, mapping);
-                return obj1(col, op_obj("$regex", JsonValue::String(regex_escape(s))));
-            }
-            json!({ "$expr": false })
+        match self.call_agg(name, args) {
+            Some(expr) => json!({ "$expr": expr }),
+            None => json!({ "$expr": false }),
         }
-        _ => json!({ "$expr": false }),
     }
-}
 
-/// Comparison operator, handling field on either side.
-/// If the field is on the right, the operator is flipped.
-fn cmp_to_mongo(
-    op_field_left: &str,
-    op_field_right: &str,
-    left: &Expr,
-    right: &Expr,
-    mapping: &HashMap<String, String>,
-) -> JsonValue {
-    if let Expr::Field(path) = left {
-        return obj1(
-            resolve_col(path, mapping),
-            op_obj(op_field_left, literal_or_null(right)),
-        );
-    }
-    if let Expr::Field(path) = right {
-        return obj1(
-            resolve_col(path, mapping),
-            op_obj(op_field_right, literal_or_null(left)),
-        );
-    }
-    json!({ "$expr": false })
-}
+    // ==================== Aggregation expressions ====================
 
-fn not_to_mongo(operand: &Expr, mapping: &HashMap<String, String>) -> JsonValue {
-    // NOT(is_null(field)) → { field: { $ne: null, $exists: true } }
-    if let Expr::Call { name, args } = operand {
-        if name == "is_null" {
-            if let [Expr::Field(path)] = args.as_slice() {
-                let col = resolve_col(path, mapping);
-                let mut inner = Map::new();
-                inner.insert("$ne".to_string(), JsonValue::Null);
-                inner.insert("$exists".to_string(), JsonValue::Bool(true));
-                return obj1(col, JsonValue::Object(inner));
+    /// Aggregation expression for `expr`, or `None` if it has none
+    fn agg(&self, expr: &Expr) -> Option<JsonValue> {
+        match expr {
+            Expr::Field(path) => Some(JsonValue::String(format!("${}", self.resolve_col(path)))),
+            Expr::Literal(value) => Some(match value_to_json(value) {
+                // Strings starting with `$` and arrays would be read as
+                // field paths and expressions
+                json @ (JsonValue::String(_) | JsonValue::Array(_) | JsonValue::Object(_)) => {
+                    json!({ "$literal": json })
+                }
+                json => json,
+            }),
+            Expr::Call { name, args } => self.call_agg(name, args),
+            Expr::Coalesce(exprs) => {
+                let args: Option<Vec<JsonValue>> = exprs.iter().map(|e| self.agg(e)).collect();
+                Some(json!({ "$ifNull": args? }))
             }
+            _ => None,
         }
     }
-    // General NOT → $nor
-    json!({ "$nor": [expr_to_mongo(operand, mapping)] })
-}
 
-fn call_to_mongo(name: &str, args: &[Expr], mapping: &HashMap<String, String>) -> JsonValue {
-    match (name, args) {
-        ("is_null", [Expr::Field(path)]) => obj1(resolve_col(path, mapping), JsonValue::Null),
-        ("starts_with", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
-            let col = resolve_col(path, mapping);
-            let pattern = format!("^{}", regex_escape(s));
-            obj1(col, op_obj("$regex", JsonValue::String(pattern)))
+    fn call_agg(&self, name: &str, args: &[Expr]) -> Option<JsonValue> {
+        if let Some(template) = self.options.functions.get(name) {
+            return self.template(template, args);
         }
-        ("ends_with", [Expr::Field(path), Expr::Literal(Value::String(s))]) => {
-            let col = resolve_col(path, mapping);
-            let pattern = format!("{}$", regex_escape(s));
-            obj1(col, op_obj("$regex", JsonValue::String(pattern)))
+
+        let translated = match (name, args) {
+            ("is_null", [arg]) => {
+                Some(json!({ "$eq": [{ "$ifNull": [self.agg(arg)?, null] }, null] }))
+            }
+            ("lower", [arg]) => Some(json!({ "$toLower": self.agg(arg)? })),
+            ("upper", [arg]) => Some(json!({ "$toUpper": self.agg(arg)? })),
+            ("trim", [arg]) => Some(json!({ "$trim": { "input": self.agg(arg)? } })),
+            ("len", [arg]) => {
+                let arg = self.agg(arg)?;
+                Some(json!({ "$cond": [
+                    { "$isArray": arg },
+                    { "$size": arg },
+                    { "$strLenBytes": arg }
+                ] }))
+            }
+            ("starts_with", [text, Expr::Literal(Value::String(s))]) => {
+                self.regex_match(text, format!("^{}", regex_escape(s)))
+            }
+            ("ends_with", [text, Expr::Literal(Value::String(s))]) => {
+                self.regex_match(text, format!("{}$", regex_escape(s)))
+            }
+            ("contains_str", [text, Expr::Literal(Value::String(s))]) => {
+                self.regex_match(text, regex_escape(s))
+            }
+            ("regex_match", [pattern, text]) => Some(json!({ "$regexMatch": {
+                "input": self.agg(text)?,
+                "regex": self.agg(pattern)?
+            } })),
+            ("now", []) => Some(json!({ "$floor": { "$divide": [{ "$toLong": "$$NOW" }, 1000] } })),
+            ("now_millis", []) => Some(json!({ "$toLong": "$$NOW" })),
+            ("date_add", [ts, amount, unit]) => {
+                let seconds = date_offset(amount, unit)?;
+                Some(json!({ "$add": [self.agg(ts)?, seconds] }))
+            }
+            ("time_diff", [t1, t2, unit]) => {
+                let seconds = unit_seconds(unit)?;
+                let diff = json!({ "$subtract": [self.agg(t1)?, self.agg(t2)?] });
+                Some(match seconds {
+                    1 => diff,
+                    // $trunc rounds toward zero, like integer division
+                    _ => json!({ "$trunc": { "$divide": [diff, seconds] } }),
+                })
+            }
+            _ => None,
+        };
+
+        if translated.is_none() {
+            self.untranslated
+                .borrow_mut()
+                .get_or_insert_with(|| name.to_string());
         }
-        _ => json!({ "$expr": false }),
+        translated
     }
-}
 
-// --- helpers ---
+    fn regex_match(&self, text: &Expr, pattern: String) -> Option<JsonValue> {
+        Some(json!({ "$regexMatch": { "input": self.agg(text)?, "regex": pattern } }))
+    }
+
+    /// Replace the `"{N}"` strings of a custom translation with arguments
+    fn template(&self, template: &JsonValue, args: &[Expr]) -> Option<JsonValue> {
+        Some(match template {
+            JsonValue::String(s) => {
+                let index = s
+                    .strip_prefix('{')
+                    .and_then(|s| s.strip_suffix('}'))
+                    .and_then(|i| i.parse::<usize>().ok());
+                match index {
+                    Some(index) => self.agg(args.get(index)?)?,
+                    None => template.clone(),
+                }
+            }
+            JsonValue::Array(items) => JsonValue::Array(
+                items
+                    .iter()
+                    .map(|item| self.template(item, args))
+                    .collect::<Option<_>>()?,
+            ),
+            JsonValue::Object(map) => JsonValue::Object(
+                map.iter()
+                    .map(|(k, v)| Some((k.clone(), self.template(v, args)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    fn resolve_col(&self, path: &str) -> String {
+        self.mapping
+            .get(path)
+            .cloned()
+            .unwrap_or_else(|| path.replace('.', "_"))
+    }
+}
 
-fn resolve_col(path: &str, mapping: &HashMap<String, String>) -> String {
-    mapping
-        .get(path)
-        .cloned()
-        .unwrap_or_else(|| path.replace('.', "_"))
+/// Whether an operand needs an aggregation expression
+fn is_computed(expr: &Expr) -> bool {
+    matches!(expr, Expr::Call { .. } | Expr::Coalesce(_))
 }
 
+// --- helpers ---
+
 /// Build `{ key: val }` with a dynamic key.
 fn obj1(key: String, val: JsonValue) -> JsonValue {
     let mut map = Map::new();
@@ -218,6 +436,11 @@ fn op_obj(op: &str, val: JsonValue) -> JsonValue {
     JsonValue::Object(map)
 }
 
+/// Build `{ "$regex": pattern }`.
+fn regex(pattern: String) -> JsonValue {
+    op_obj("$regex", JsonValue::String(pattern))
+}
+
 /// Extract the JSON value from a literal expression, or `null` for non-literals.
 fn literal_or_null(expr: &Expr) -> JsonValue {
     if let Expr::Literal(val) = expr {
@@ -459,4 +682,83 @@ mod tests {
         let result = to_mongo(&paths, &HashMap::new());
         assert_eq!(result, json!({ "filename": { "$regex": "\\.rs$" } }));
     }
+
+    fn parsed(conditions: &[&str]) -> Vec<FilterPath> {
+        vec![path_with(
+            conditions
+                .iter()
+                .map(|c| crate::expr::ExprParser::parse(c).unwrap())
+                .collect(),
+        )]
+    }
+
+    #[test]
+    fn test_mongo_function_translations() {
+        let result = to_mongo(
+            &parsed(&[
+                r#"lower(owner) == "alice""#,
+                "len(tags) >= 2",
+                r#"regex_match("^[a-z]+$", code)"#,
+            ]),
+            &HashMap::new(),
+        );
+        assert_eq!(
+            result,
+            json!({ "$and": [
+                { "$expr": { "$eq": [{ "$toLower": "$owner" }, { "$literal": "alice" }] } },
+                { "$expr": { "$gte": [
+                    { "$cond": [
+                        { "$isArray": "$tags" },
+                        { "$size": "$tags" },
+                        { "$strLenBytes": "$tags" }
+                    ] },
+                    2
+                ] } },
+                { "code": { "$regex": "^[a-z]+$" } }
+            ] })
+        );
+
+        let result = to_mongo(
+            &parsed(&[
+                r#"coalesce(region, "eu") == "us""#,
+                r#"created > date_add(now(), -7, "d")"#,
+            ]),
+            &HashMap::new(),
+        );
+        assert_eq!(
+            result,
+            json!({ "$and": [
+                { "$expr": { "$eq": [
+                    { "$ifNull": ["$region", { "$literal": "eu" }] },
+                    { "$literal": "us" }
+                ] } },
+                { "$expr": { "$gt": [
+                    "$created",
+                    { "$add": [
+                        { "$floor": { "$divide": [{ "$toLong": "$$NOW" }, 1000] } },
+                        -604800
+                    ] }
+                ] } }
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_mongo_custom_translations() {
+        let options = MongoOptions::default().with_function("abs", json!({ "$abs": "{0}" }));
+        let mapping = HashMap::from([("balance".to_string(), "acct_balance".to_string())]);
+        let result = to_mongo_with(&parsed(&["abs(balance) < 100"]), &mapping, &options);
+        assert_eq!(
+            result,
+            json!({ "$expr": { "$lt": [{ "$abs": "$acct_balance" }, 100] } })
+        );
+
+        let soundex = crate::expr::ExprParser::parse(r#"soundex(name) == "A1""#).unwrap();
+        assert_eq!(
+            untranslated(&soundex, &mapping, &options),
+            Some("soundex".to_string())
+        );
+        let abs = crate::expr::ExprParser::parse("abs(balance) < 100").unwrap();
+        assert_eq!(untranslated(&abs, &mapping, &options), None);
+    }
 }
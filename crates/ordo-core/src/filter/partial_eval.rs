@@ -2,6 +2,11 @@
 //!
 //! Substitutes known field values into expressions and simplifies them.
 //! Unknown fields remain as symbolic variables for SQL/JSON generation.
+//!
+//! Variables (`$name`) set along the current path are substituted by their
+//! symbolic definitions, so conditions reading them can be pushed down too.
+
+use std::collections::HashMap;
 
 use crate::context::Value;
 use crate::expr::{Expr, ExprOptimizer};
@@ -16,9 +21,21 @@ pub enum ExprClass {
     Unknown(Expr),
 }
 
+/// Symbolic value of a variable on the current path
+#[derive(Debug, Clone)]
+pub(super) enum Binding {
+    /// Definition in terms of literals and unknown fields
+    Expr(Expr),
+    /// Set by an action whose result cannot be modelled (the reason)
+    Opaque(String),
+}
+
 /// Partial evaluator: substitutes known fields and simplifies via ExprOptimizer
 pub struct PartialEvaluator {
     known: Value,
+    /// Input of an inlined ruleset, in terms of the caller's fields
+    input: Option<Expr>,
+    variables: HashMap<String, Binding>,
     optimizer: ExprOptimizer,
 }
 
@@ -26,6 +43,21 @@ impl PartialEvaluator {
     pub fn new(known: Value) -> Self {
         Self {
             known,
+            input: None,
+            variables: HashMap::new(),
+            optimizer: ExprOptimizer::new(),
+        }
+    }
+
+    /// Evaluator for a ruleset called from the current path.
+    ///
+    /// `input` is the resolved input mapping of the call; without one the
+    /// called ruleset reads the caller's input. Variables start empty.
+    pub fn call_scope(&self, input: Option<Expr>) -> Self {
+        Self {
+            known: self.known.clone(),
+            input: input.or_else(|| self.input.clone()),
+            variables: HashMap::new(),
             optimizer: ExprOptimizer::new(),
         }
     }
@@ -34,8 +66,7 @@ impl PartialEvaluator {
     /// Returns AlwaysTrue/AlwaysFalse if expression resolves to a constant,
     /// or Unknown(expr) with unresolved field references remaining.
     pub fn eval(&mut self, expr: &Expr) -> ExprClass {
-        let substituted = self.substitute(expr);
-        let optimized = self.optimizer.optimize(substituted);
+        let optimized = self.resolve(expr);
 
         match &optimized {
             Expr::Literal(Value::Bool(true)) => ExprClass::AlwaysTrue,
@@ -51,11 +82,76 @@ impl PartialEvaluator {
         }
     }
 
+    /// Substitute known fields and variables, then simplify
+    pub fn resolve(&mut self, expr: &Expr) -> Expr {
+        let substituted = self.substitute(expr);
+        self.optimizer.optimize(substituted)
+    }
+
+    /// Set a variable to the resolved value of `expr`
+    pub fn define(&mut self, name: &str, expr: &Expr) {
+        let value = self.resolve(expr);
+        self.bind(name, value);
+    }
+
+    /// Set a variable to an already resolved value
+    pub fn bind(&mut self, name: &str, value: Expr) {
+        self.variables
+            .insert(name.to_string(), Binding::Expr(value));
+    }
+
+    /// Mark a variable as set to a value that cannot be modelled
+    pub fn set_opaque(&mut self, name: &str, reason: impl Into<String>) {
+        self.variables
+            .insert(name.to_string(), Binding::Opaque(reason.into()));
+    }
+
+    /// Variables on the current path, to restore when backtracking
+    pub(super) fn save(&self) -> HashMap<String, Binding> {
+        self.variables.clone()
+    }
+
+    pub(super) fn restore(&mut self, variables: HashMap<String, Binding>) {
+        self.variables = variables;
+    }
+
+    /// Why a resolved condition cannot be pushed down, if it cannot.
+    ///
+    /// `original` is the condition before resolution. A condition fails when
+    /// it still reads a variable (unset on this path, or opaque), or when a
+    /// variable it read resolved to an expression no filter format renders.
+    pub fn unrenderable(&self, original: &Expr, resolved: &Expr) -> Option<String> {
+        if let Some(name) = first_variable(resolved) {
+            return Some(match self.variables.get(name) {
+                Some(Binding::Opaque(reason)) => format!("variable '${}' {}", name, reason),
+                _ => format!("variable '${}' is not set on this path", name),
+            });
+        }
+        let mut read = Vec::new();
+        collect_variables(original, &mut read);
+        if !read.is_empty() && !renderable(resolved) {
+            let names: Vec<String> = read.iter().map(|n| format!("'${}'", n)).collect();
+            return Some(format!(
+                "the value of {} cannot be rendered as a filter: {:?}",
+                names.join(", "),
+                resolved
+            ));
+        }
+        None
+    }
+
     /// Recursively substitute known fields into the expression tree
     fn substitute(&self, expr: &Expr) -> Expr {
         match expr {
             Expr::Field(path) => {
-                if let Some(val) = self.lookup(path) {
+                if let Some(name) = path.strip_prefix('$') {
+                    match self.variables.get(name) {
+                        Some(Binding::Expr(value)) => value.clone(),
+                        _ => expr.clone(),
+                    }
+                } else if let Some(input) = &self.input {
+                    project(input, path)
+                } else if let Some(val) = self.lookup(path) {
                     Expr::Literal(val.clone())
                 } else {
                     expr.clone()
@@ -64,7 +160,11 @@ impl PartialEvaluator {
             Expr::Exists(path) => {
                 // If we can confirm existence from known_input, fold to true.
                 // If absent from known_input, leave as Exists (it may be in DB).
-                if self.lookup(path).is_some() {
+                let exists = match path.strip_prefix('$') {
+                    Some(name) => matches!(self.variables.get(name), Some(Binding::Expr(_))),
+                    None => self.input.is_none() && self.lookup(path).is_some(),
+                };
+                if exists {
                     Expr::Literal(Value::bool(true))
                 } else {
                     expr.clone()
@@ -79,10 +179,21 @@ impl PartialEvaluator {
                 op: *op,
                 operand: Box::new(self.substitute(operand)),
             },
-            Expr::Call { name, args } => Expr::Call {
-                name: name.clone(),
-                args: args.iter().map(|a| self.substitute(a)).collect(),
-            },
+            Expr::Call { name, args } => {
+                let args: Vec<Expr> = args.iter().map(|a| self.substitute(a)).collect();
+                // Read members of objects built by variables and called rulesets
+                if let ("object_get", [object, Expr::Literal(Value::String(key)), default]) =
+                    (name.as_str(), args.as_slice())
+                {
+                    if let Some(member) = member(object, key, default) {
+                        return member;
+                    }
+                }
+                Expr::Call {
+                    name: name.clone(),
+                    args,
+                }
+            }
             Expr::Conditional {
                 condition,
                 then_branch,
@@ -124,3 +235,98 @@ impl PartialEvaluator {
         }
     }
 }
+
+/// Read `path` from the input expression of an inlined ruleset
+fn project(input: &Expr, path: &str) -> Expr {
+    let mut current = input.clone();
+    let mut segments = path.split('.');
+    while let Some(key) = segments.next() {
+        current = match current {
+            Expr::Object(pairs) => pairs
+                .into_iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v)
+                .unwrap_or(Expr::Literal(Value::Null)),
+            Expr::Literal(value) => Expr::Literal(value.get_path(key).cloned().unwrap_or_default()),
+            Expr::Field(base) => {
+                let rest: Vec<&str> = std::iter::once(key).chain(segments).collect();
+                return Expr::Field(format!("{}.{}", base, rest.join(".")));
+            }
+            other => Expr::Call {
+                name: "object_get".to_string(),
+                args: vec![
+                    other,
+                    Expr::Literal(Value::string(key)),
+                    Expr::Literal(Value::Null),
+                ],
+            },
+        };
+    }
+    current
+}
+
+/// `object_get(object, key, default)` of a symbolic or literal object
+fn member(object: &Expr, key: &str, default: &Expr) -> Option<Expr> {
+    match object {
+        Expr::Object(pairs) => Some(
+            pairs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or_else(|| default.clone(), |(_, v)| v.clone()),
+        ),
+        Expr::Literal(Value::Object(map)) => Some(
+            map.get(key)
+                .map_or_else(|| default.clone(), |v| Expr::Literal(v.clone())),
+        ),
+        _ => None,
+    }
+}
+
+/// First `$variable` read by an expression
+fn first_variable(expr: &Expr) -> Option<&str> {
+    let mut read = Vec::new();
+    collect_variables(expr, &mut read);
+    read.into_iter().next()
+}
+
+fn collect_variables<'e>(expr: &'e Expr, out: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Field(path) | Expr::Exists(path) => {
+            if let Some(name) = path.strip_prefix('$') {
+                if !out.contains(&name) {
+                    out.push(name);
+                }
+            }
+        }
+        Expr::Binary { left, right, .. } => {
+            collect_variables(left, out);
+            collect_variables(right, out);
+        }
+        Expr::Unary { operand, .. } => collect_variables(operand, out),
+        Expr::Call { args: exprs, .. } | Expr::Array(exprs) | Expr::Coalesce(exprs) => {
+            exprs.iter().for_each(|e| collect_variables(e, out))
+        }
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            collect_variables(condition, out);
+            collect_variables(then_branch, out);
+            collect_variables(else_branch, out);
+        }
+        Expr::Object(pairs) => pairs.iter().for_each(|(_, v)| collect_variables(v, out)),
+        Expr::Literal(_) => {}
+    }
+}
+
+/// Whether an expression only uses constructs the filter formats render
+fn renderable(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::Field(_) => true,
+        Expr::Binary { left, right, .. } => renderable(left) && renderable(right),
+        Expr::Unary { operand, .. } => renderable(operand),
+        Expr::Call { args: exprs, .. } | Expr::Array(exprs) => exprs.iter().all(renderable),
+        Expr::Conditional { .. } | Expr::Object(_) | Expr::Exists(_) | Expr::Coalesce(_) => false,
+    }
+}
@@ -3,15 +3,25 @@
 //! Collects all paths through the rule graph that lead to target result codes.
 //! Each path is a sequence of conditions that must all hold (AND).
 //! Multiple paths are combined with OR in the final filter.
+//!
+//! Actions on a path are executed symbolically: `SetVariable` binds the
+//! variable to its resolved expression, and `CallRuleSet` inlines the called
+//! ruleset (via a [`RuleSetResolver`]), forking the path once per terminal the
+//! callee can reach, with the result variable bound to that terminal's
+//! `{code, message, output}`.
 
 use super::partial_eval::{ExprClass, PartialEvaluator};
-use crate::error::Result;
+use crate::context::Value;
+use crate::error::{OrdoError, Result};
 use crate::expr::{Expr, ExprParser, UnaryOp};
-use crate::rule::{Condition, RuleSet, StepKind};
+use crate::rule::{Action, ActionKind, Condition, RuleSet, RuleSetResolver, StepKind};
 
 /// Hard limit on traversal depth to prevent infinite loops in cyclic graphs
 const MAX_DEPTH: usize = 50;
 
+/// Maximum nesting of inlined `CallRuleSet` actions (matches the executor)
+const MAX_CALL_DEPTH: usize = 10;
+
 /// A single execution path that leads to a target result
 #[derive(Debug)]
 pub struct FilterPath {
@@ -28,184 +38,330 @@ pub struct FilterPath {
 /// treat the result as `always_matches` to avoid false negatives (hiding rows
 /// that the rule engine would have accepted).
 ///
-/// A `max_paths` of 0 means no limit.
+/// A `max_paths` of 0 means no limit. Without a `resolver`, variables set by
+/// `CallRuleSet` cannot be read by pushed-down conditions.
 pub fn collect_paths(
     ruleset: &RuleSet,
     evaluator: &mut PartialEvaluator,
     target_results: &[String],
     max_paths: usize,
+    resolver: Option<&dyn RuleSetResolver>,
 ) -> Result<(Vec<FilterPath>, bool)> {
-    let entry = ruleset.config.entry_step.clone();
-    let mut paths = Vec::new();
+    let mut collector = Collector {
+        resolver,
+        targets: Some(target_results),
+        max_paths,
+        call_depth: 0,
+        paths: Vec::new(),
+        results: Vec::new(),
+        truncated: false,
+    };
     let mut conditions: Vec<Expr> = Vec::new();
-    let mut truncated = false;
 
-    collect_recursive(
+    collector.walk(
         ruleset,
         evaluator,
-        &entry,
+        &ruleset.config.entry_step,
         &mut conditions,
-        target_results,
-        max_paths,
         0,
-        &mut paths,
-        &mut truncated,
     )?;
 
-    Ok((paths, truncated))
+    Ok((collector.paths, collector.truncated))
 }
 
-#[allow(clippy::too_many_arguments)]
-fn collect_recursive(
-    ruleset: &RuleSet,
-    evaluator: &mut PartialEvaluator,
-    step_id: &str,
-    conditions: &mut Vec<Expr>,
-    target_results: &[String],
+struct Collector<'a> {
+    resolver: Option<&'a dyn RuleSetResolver>,
+    /// Result codes to collect; `None` collects every terminal (called rulesets)
+    targets: Option<&'a [String]>,
     max_paths: usize,
-    depth: usize,
-    paths: &mut Vec<FilterPath>,
-    truncated: &mut bool,
-) -> Result<()> {
-    if depth > MAX_DEPTH {
-        return Ok(());
-    }
-    if max_paths > 0 && paths.len() >= max_paths {
-        *truncated = true;
-        return Ok(());
-    }
+    call_depth: usize,
+    paths: Vec<FilterPath>,
+    /// Result object of each path, when collecting every terminal
+    results: Vec<Expr>,
+    truncated: bool,
+}
 
-    let step = match ruleset.get_step(step_id) {
-        Some(s) => s,
-        None => return Ok(()), // dangling reference — skip silently
-    };
+impl Collector<'_> {
+    fn full(&self) -> bool {
+        self.max_paths > 0 && self.paths.len() >= self.max_paths
+    }
 
-    match &step.kind {
-        StepKind::Terminal { result } => {
-            if target_results.contains(&result.code) {
-                paths.push(FilterPath {
-                    conditions: conditions.clone(),
-                    result_code: result.code.clone(),
-                });
-            }
+    fn walk(
+        &mut self,
+        ruleset: &RuleSet,
+        evaluator: &mut PartialEvaluator,
+        step_id: &str,
+        conditions: &mut Vec<Expr>,
+        depth: usize,
+    ) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Ok(());
         }
-
-        StepKind::Action { next_step, .. } => {
-            // V1: transparent — variable mutations not tracked
-            collect_recursive(
-                ruleset,
-                evaluator,
-                next_step,
-                conditions,
-                target_results,
-                max_paths,
-                depth + 1,
-                paths,
-                truncated,
-            )?;
+        if self.full() {
+            self.truncated = true;
+            return Ok(());
         }
 
-        StepKind::Decision {
-            branches,
-            default_next,
-        } => {
-            // Track negations of taken branches for the default path
-            let mut negations: Vec<Expr> = Vec::new();
-
-            for branch in branches {
-                if max_paths > 0 && paths.len() >= max_paths {
-                    *truncated = true;
-                    break;
+        let step = match ruleset.get_step(step_id) {
+            Some(s) => s,
+            None => return Ok(()), // dangling reference — skip silently
+        };
+
+        match &step.kind {
+            StepKind::Terminal { result } => match self.targets {
+                Some(targets) => {
+                    if targets.contains(&result.code) {
+                        self.paths.push(FilterPath {
+                            conditions: conditions.clone(),
+                            result_code: result.code.clone(),
+                        });
+                    }
                 }
+                None => {
+                    // Same shape as the executor's CallRuleSet result variable
+                    let mut output = result.output.clone();
+                    if let Value::Object(data) = &result.data {
+                        for (k, v) in data {
+                            output.retain(|(key, _)| key.as_str() != &**k);
+                            output.push((k.to_string(), Expr::Literal(v.clone())));
+                        }
+                    }
+                    let object = Expr::Object(vec![
+                        ("code".to_string(), Expr::literal(result.code.as_str())),
+                        (
+                            "message".to_string(),
+                            Expr::literal(result.message.as_str()),
+                        ),
+                        ("output".to_string(), Expr::Object(output)),
+                    ]);
+                    self.results.push(evaluator.resolve(&object));
+                    self.paths.push(FilterPath {
+                        conditions: conditions.clone(),
+                        result_code: result.code.clone(),
+                    });
+                }
+            },
+
+            StepKind::Action { actions, next_step } => {
+                self.run_actions(ruleset, evaluator, actions, next_step, conditions, depth)?;
+            }
 
-                let (expr_opt, always_true, always_false) =
-                    evaluate_condition(&branch.condition, evaluator);
+            StepKind::Decision {
+                branches,
+                default_next,
+            } => {
+                // Track negations of taken branches for the default path
+                let mut negations: Vec<Expr> = Vec::new();
 
-                if always_false {
-                    // Branch never taken — accumulate its negation if we have an expr
-                    if let Some(e) = expr_opt {
-                        negations.push(negate(e));
+                for branch in branches {
+                    if self.full() {
+                        self.truncated = true;
+                        break;
+                    }
+
+                    let (expr_opt, always_true, always_false) =
+                        evaluate_condition(&branch.condition, evaluator)
+                            .map_err(|reason| condition_error(&step.id, reason))?;
+
+                    if always_false {
+                        // Branch never taken — accumulate its negation if we have an expr
+                        if let Some(e) = expr_opt {
+                            negations.push(negate(e));
+                        }
+                        continue;
                     }
-                    continue;
-                }
 
-                if always_true {
-                    // Branch always taken — recurse with no extra condition, done
-                    collect_recursive(
+                    if always_true {
+                        // Branch always taken — recurse with no extra condition, done
+                        self.run_actions(
+                            ruleset,
+                            evaluator,
+                            &branch.actions,
+                            &branch.next_step,
+                            conditions,
+                            depth,
+                        )?;
+                        return Ok(()); // subsequent branches are dead code
+                    }
+
+                    // Unknown condition — recurse with this condition added
+                    let cond_expr = expr_opt.unwrap();
+                    conditions.push(cond_expr.clone());
+                    self.run_actions(
                         ruleset,
                         evaluator,
+                        &branch.actions,
                         &branch.next_step,
                         conditions,
-                        target_results,
-                        max_paths,
-                        depth + 1,
-                        paths,
-                        truncated,
+                        depth,
                     )?;
-                    return Ok(()); // subsequent branches are dead code
-                }
+                    conditions.pop();
 
-                // Unknown condition — recurse with this condition added
-                let cond_expr = expr_opt.unwrap();
-                conditions.push(cond_expr.clone());
-                collect_recursive(
-                    ruleset,
-                    evaluator,
-                    &branch.next_step,
-                    conditions,
-                    target_results,
-                    max_paths,
-                    depth + 1,
-                    paths,
-                    truncated,
-                )?;
-                conditions.pop();
+                    // For subsequent branches / default: this condition must be false
+                    negations.push(negate(cond_expr));
+                }
 
-                // For subsequent branches / default: this condition must be false
-                negations.push(negate(cond_expr));
+                // Follow the default path with all branch-condition negations
+                if let Some(default) = default_next.as_deref() {
+                    if !self.full() {
+                        let neg_count = negations.len();
+                        conditions.extend(negations);
+                        self.walk(ruleset, evaluator, default, conditions, depth + 1)?;
+                        conditions.truncate(conditions.len() - neg_count);
+                    } else {
+                        self.truncated = true;
+                    }
+                }
             }
+        }
 
-            // Follow the default path with all branch-condition negations
-            if let Some(default) = default_next.as_deref() {
-                if max_paths == 0 || paths.len() < max_paths {
-                    let neg_count = negations.len();
-                    conditions.extend(negations);
-                    collect_recursive(
-                        ruleset,
-                        evaluator,
-                        default,
-                        conditions,
-                        target_results,
-                        max_paths,
-                        depth + 1,
-                        paths,
-                        truncated,
-                    )?;
-                    conditions.truncate(conditions.len() - neg_count);
-                } else {
-                    *truncated = true;
+        Ok(())
+    }
+
+    /// Execute `actions` symbolically, then continue at `next_step`.
+    /// Variables set here are unset again when the traversal backtracks.
+    fn run_actions(
+        &mut self,
+        ruleset: &RuleSet,
+        evaluator: &mut PartialEvaluator,
+        actions: &[Action],
+        next_step: &str,
+        conditions: &mut Vec<Expr>,
+        depth: usize,
+    ) -> Result<()> {
+        let saved = evaluator.save();
+        let result = self.apply(ruleset, evaluator, actions, next_step, conditions, depth);
+        evaluator.restore(saved);
+        result
+    }
+
+    fn apply(
+        &mut self,
+        ruleset: &RuleSet,
+        evaluator: &mut PartialEvaluator,
+        actions: &[Action],
+        next_step: &str,
+        conditions: &mut Vec<Expr>,
+        depth: usize,
+    ) -> Result<()> {
+        let Some((action, rest)) = actions.split_first() else {
+            return self.walk(ruleset, evaluator, next_step, conditions, depth + 1);
+        };
+
+        match &action.kind {
+            ActionKind::SetVariable { name, value } => evaluator.define(name, value),
+            ActionKind::CallRuleSet {
+                ruleset_name,
+                input_mapping,
+                result_variable,
+            } => {
+                let Some(resolver) = self.resolver else {
+                    evaluator.set_opaque(
+                        result_variable,
+                        format!(
+                            "is set by calling ruleset '{}', but no resolver is configured",
+                            ruleset_name
+                        ),
+                    );
+                    return self.apply(ruleset, evaluator, rest, next_step, conditions, depth);
+                };
+                if self.call_depth >= MAX_CALL_DEPTH {
+                    return Err(OrdoError::eval_error(format!(
+                        "CallRuleSet max nesting depth ({}) exceeded calling '{}'",
+                        MAX_CALL_DEPTH, ruleset_name
+                    )));
                 }
+                let target =
+                    resolver
+                        .resolve(ruleset_name)
+                        .ok_or_else(|| OrdoError::RuleSetNotFound {
+                            name: ruleset_name.clone(),
+                        })?;
+
+                // Every terminal of the callee, in terms of this path's fields
+                let input = input_mapping.as_ref().map(|m| evaluator.resolve(m));
+                let mut callee_evaluator = evaluator.call_scope(input);
+                let mut callee = Collector {
+                    resolver: self.resolver,
+                    targets: None,
+                    max_paths: self.max_paths,
+                    call_depth: self.call_depth + 1,
+                    paths: Vec::new(),
+                    results: Vec::new(),
+                    truncated: false,
+                };
+                callee.walk(
+                    &target,
+                    &mut callee_evaluator,
+                    &target.config.entry_step,
+                    &mut Vec::new(),
+                    0,
+                )?;
+                if callee.truncated {
+                    self.truncated = true;
+                    return Ok(());
+                }
+
+                for (path, result) in callee.paths.into_iter().zip(callee.results) {
+                    if self.full() {
+                        self.truncated = true;
+                        break;
+                    }
+                    let saved = evaluator.save();
+                    evaluator.bind(result_variable, result);
+                    let added = path.conditions.len();
+                    conditions.extend(path.conditions);
+                    let outcome =
+                        self.apply(ruleset, evaluator, rest, next_step, conditions, depth);
+                    conditions.truncate(conditions.len() - added);
+                    evaluator.restore(saved);
+                    outcome?;
+                }
+                return Ok(());
             }
+            // Side effects only
+            ActionKind::Log { .. }
+            | ActionKind::Metric { .. }
+            | ActionKind::ExternalCall { .. } => {}
         }
-    }
 
-    Ok(())
+        self.apply(ruleset, evaluator, rest, next_step, conditions, depth)
+    }
 }
 
 /// Evaluate a branch condition against known inputs.
-/// Returns `(expr, always_true, always_false)`.
+/// Returns `(expr, always_true, always_false)`, or why the condition cannot
+/// be pushed down.
 fn evaluate_condition(
     condition: &Condition,
     evaluator: &mut PartialEvaluator,
-) -> (Option<Expr>, bool, bool) {
-    match condition {
-        Condition::Always => (None, true, false),
-        Condition::Expression(expr) => classify(evaluator.eval(expr)),
+) -> std::result::Result<(Option<Expr>, bool, bool), String> {
+    let parsed;
+    let expr = match condition {
+        Condition::Always => return Ok((None, true, false)),
+        Condition::Expression(expr) => expr,
         Condition::ExpressionString(s) => match ExprParser::parse(s) {
-            Ok(expr) => classify(evaluator.eval(&expr)),
-            Err(_) => (None, false, true), // parse error → treat as never
+            Ok(expr) => {
+                parsed = expr;
+                &parsed
+            }
+            Err(_) => return Ok((None, false, true)), // parse error → treat as never
         },
+    };
+    let class = evaluator.eval(expr);
+    if let ExprClass::Unknown(resolved) = &class {
+        if let Some(reason) = evaluator.unrenderable(expr, resolved) {
+            return Err(reason);
+        }
     }
+    Ok(classify(class))
+}
+
+fn condition_error(step_id: &str, reason: String) -> OrdoError {
+    OrdoError::eval_error(format!(
+        "Cannot compile the condition of a branch in step '{}' to a filter: {}",
+        step_id, reason
+    ))
 }
 
 fn classify(class: ExprClass) -> (Option<Expr>, bool, bool) {
@@ -0,0 +1,215 @@
+//! Conservative fallback for conditions a filter format cannot express
+//!
+//! Each renderer translates the function calls it knows (built-in or
+//! registered through its options) and reports the first call it cannot
+//! translate in a condition. [`push_down`] then leaves out the smallest part
+//! of the filter that contains such a call and is safe to remove:
+//!
+//! - a disjunct of an `||` written in a rule, as long as another one remains
+//! - otherwise the whole path, as long as another path remains
+//!
+//! Leaving out a disjunct only narrows the filter: rows that reach a target
+//! result through the left-out condition are not selected, but no row is
+//! selected that the rules would reject. Negations are never narrowed into,
+//! since leaving out a disjunct under `!` would widen the filter instead.
+//! When every path needs an untranslatable call the filter is rejected.
+
+use crate::context::Value;
+use crate::error::{OrdoError, Result};
+use crate::expr::{BinaryOp, Expr, UnaryOp};
+
+use super::path_collector::FilterPath;
+
+/// Paths that can be rendered, and the functions that were left out
+#[derive(Debug)]
+pub struct PushDown {
+    pub paths: Vec<FilterPath>,
+    /// Untranslatable functions whose conditions were left out, in order of
+    /// first use
+    pub untranslated: Vec<String>,
+}
+
+/// Leave out the conditions `untranslated` reports a function for.
+///
+/// `untranslated` is called with leaf conditions (anything but `&&`/`||`)
+/// and returns the name of a function in it the format cannot translate.
+/// `target` names the format in the error raised when nothing is left.
+pub fn push_down(
+    paths: Vec<FilterPath>,
+    target: &str,
+    untranslated: impl Fn(&Expr) -> Option<String>,
+) -> Result<PushDown> {
+    let mut dropped = Vec::new();
+    let total = paths.len();
+    let mut kept = Vec::with_capacity(total);
+
+    for path in paths {
+        let conditions: Option<Vec<Expr>> = path
+            .conditions
+            .iter()
+            .map(|c| reduce(c, &untranslated, &mut dropped))
+            .collect();
+        if let Some(conditions) = conditions {
+            kept.push(FilterPath {
+                conditions,
+                result_code: path.result_code,
+            });
+        }
+    }
+
+    if total > 0 && kept.is_empty() {
+        return Err(OrdoError::parse_error(format!(
+            "Function '{}' is not supported in {} filter generation",
+            dropped[0], target
+        )));
+    }
+
+    Ok(PushDown {
+        paths: kept,
+        untranslated: dropped,
+    })
+}
+
+/// The condition without untranslatable disjuncts, or `None` when it needs
+/// one in every disjunct
+fn reduce(
+    expr: &Expr,
+    untranslated: &impl Fn(&Expr) -> Option<String>,
+    dropped: &mut Vec<String>,
+) -> Option<Expr> {
+    match expr {
+        Expr::Binary {
+            op: op @ (BinaryOp::And | BinaryOp::Or),
+            left,
+            right,
+        } => {
+            let l = reduce(left, untranslated, dropped);
+            let r = reduce(right, untranslated, dropped);
+            match (op, l, r) {
+                (_, Some(l), Some(r)) => Some(Expr::binary(*op, l, r)),
+                (BinaryOp::Or, Some(side), None) | (BinaryOp::Or, None, Some(side)) => Some(side),
+                _ => None,
+            }
+        }
+        _ => match untranslated(expr) {
+            Some(name) => {
+                if !dropped.contains(&name) {
+                    dropped.push(name);
+                }
+                None
+            }
+            None => Some(expr.clone()),
+        },
+    }
+}
+
+/// Seconds in a literal `date_add` / `time_diff` unit, as the built-in
+/// functions read it
+pub(super) fn unit_seconds(unit: &Expr) -> Option<i64> {
+    match unit {
+        Expr::Literal(Value::String(unit)) => match unit.as_ref() {
+            "seconds" | "s" => Some(1),
+            "minutes" | "m" => Some(60),
+            "hours" | "h" => Some(3600),
+            "days" | "d" => Some(86400),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Seconds added by `date_add(ts, amount, unit)` with literal arguments
+pub(super) fn date_offset(amount: &Expr, unit: &Expr) -> Option<i64> {
+    let amount = match amount {
+        Expr::Literal(Value::Int(n)) => *n,
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => match operand.as_ref() {
+            Expr::Literal(Value::Int(n)) => n.checked_neg()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    unit_seconds(unit)?.checked_mul(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
+
+    fn paths(paths: &[&[&str]]) -> Vec<FilterPath> {
+        paths
+            .iter()
+            .map(|conditions| FilterPath {
+                conditions: conditions
+                    .iter()
+                    .map(|c| ExprParser::parse(c).unwrap())
+                    .collect(),
+                result_code: "OK".to_string(),
+            })
+            .collect()
+    }
+
+    /// `soundex` is the only function the test format cannot translate
+    fn soundex(expr: &Expr) -> Option<String> {
+        format!("{:?}", expr)
+            .contains("\"soundex\"")
+            .then(|| "soundex".to_string())
+    }
+
+    fn conditions(result: &PushDown) -> Vec<Vec<Expr>> {
+        result.paths.iter().map(|p| p.conditions.clone()).collect()
+    }
+
+    #[test]
+    fn test_untranslatable_disjunct_is_left_out() {
+        let result = push_down(
+            paths(&[&[
+                "tier == \"gold\"",
+                "soundex(name) == \"A1\" || owner == \"alice\"",
+            ]]),
+            "SQL",
+            soundex,
+        )
+        .unwrap();
+        assert_eq!(
+            conditions(&result),
+            vec![vec![
+                ExprParser::parse("tier == \"gold\"").unwrap(),
+                ExprParser::parse("owner == \"alice\"").unwrap(),
+            ]]
+        );
+        assert_eq!(result.untranslated, vec!["soundex".to_string()]);
+    }
+
+    #[test]
+    fn test_untranslatable_path_is_left_out() {
+        // A conjunct or a negation cannot be narrowed: the path goes
+        let result = push_down(
+            paths(&[
+                &["tier == \"gold\" && soundex(name) == \"A1\""],
+                &["!(soundex(name) == \"A1\" || owner == \"bob\")"],
+                &["owner == \"alice\""],
+            ]),
+            "SQL",
+            soundex,
+        )
+        .unwrap();
+        assert_eq!(
+            conditions(&result),
+            vec![vec![ExprParser::parse("owner == \"alice\"").unwrap()]]
+        );
+        assert_eq!(result.untranslated, vec!["soundex".to_string()]);
+
+        let err = push_down(paths(&[&["soundex(name) == \"A1\""]]), "SQL", soundex).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Function 'soundex' is not supported in SQL filter generation"));
+
+        // Nothing to translate is not an error
+        let result = push_down(Vec::new(), "SQL", soundex).unwrap();
+        assert!(result.paths.is_empty());
+    }
+}
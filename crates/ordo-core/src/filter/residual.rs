@@ -0,0 +1,415 @@
+//! Residual rulesets from partial evaluation
+//!
+//! Specializes a ruleset to the known part of its input. In the residual:
+//! - Known fields are substituted into conditions, action expressions and
+//!   terminal outputs, and the results constant-folded
+//! - Branches whose condition folds to false are removed; a branch that
+//!   folds to true becomes the last one (later branches and the default are
+//!   dead)
+//! - Steps left with nothing to decide (a decision with only a default, an
+//!   action step without actions) are short-circuited: references to them
+//!   point straight at their successor
+//! - Steps no longer reachable from the entry step are removed
+//!
+//! Executing the residual with the remaining fields gives the same result as
+//! executing the original with the full input. Variables are not folded, as
+//! a step may be reached with different values on different paths; their
+//! definitions are specialized instead.
+//!
+//! `CallRuleSet` actions without an input mapping pass the execution input to
+//! the called ruleset unchanged, so a residual containing them must still be
+//! executed with the full input.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::partial_eval::{ExprClass, PartialEvaluator};
+use crate::error::Result;
+use crate::expr::{Expr, ExprParser};
+use crate::rule::{Action, ActionKind, Branch, Condition, RuleSet, RuleSetConfig, Step, StepKind};
+
+/// A ruleset specialized to known input
+#[derive(Debug, Clone)]
+pub struct ResidualRuleSet {
+    /// The specialized rule graph (expressions pre-parsed)
+    pub ruleset: RuleSet,
+    /// Number of branch conditions folded to a constant
+    pub folded_branches: usize,
+    /// Number of steps bypassed because nothing was left to decide
+    pub short_circuited_steps: usize,
+    /// Number of steps of the original missing from the residual
+    pub removed_steps: usize,
+}
+
+/// Specialize `ruleset` to the fields known to `evaluator`
+pub fn residual_ruleset(
+    ruleset: &RuleSet,
+    evaluator: &mut PartialEvaluator,
+) -> Result<ResidualRuleSet> {
+    let mut folded_branches = 0;
+    let mut steps: HashMap<&str, Step> = HashMap::with_capacity(ruleset.steps.len());
+    // Short-circuited steps and where they lead
+    let mut jumps: HashMap<&str, String> = HashMap::new();
+
+    for (id, step) in &ruleset.steps {
+        let kind = match &step.kind {
+            StepKind::Decision {
+                branches,
+                default_next,
+            } => {
+                let mut kept = Vec::with_capacity(branches.len());
+                let mut default_next = default_next.clone();
+                for branch in branches {
+                    let condition = match &branch.condition {
+                        Condition::Always => None,
+                        Condition::Expression(expr) => Some(evaluator.eval(expr)),
+                        Condition::ExpressionString(s) => {
+                            Some(evaluator.eval(&ExprParser::parse(s)?))
+                        }
+                    };
+                    let actions = specialize_actions(&branch.actions, evaluator);
+                    match condition {
+                        Some(ExprClass::AlwaysFalse) => {
+                            folded_branches += 1;
+                            continue;
+                        }
+                        Some(ExprClass::Unknown(expr)) => {
+                            kept.push(Branch {
+                                condition: Condition::Expression(expr),
+                                next_step: branch.next_step.clone(),
+                                actions,
+                            });
+                        }
+                        always => {
+                            if always.is_some() {
+                                folded_branches += 1;
+                            }
+                            if actions.is_empty() {
+                                default_next = Some(branch.next_step.clone());
+                            } else {
+                                kept.push(Branch {
+                                    condition: Condition::Always,
+                                    next_step: branch.next_step.clone(),
+                                    actions,
+                                });
+                                default_next = None;
+                            }
+                            break;
+                        }
+                    }
+                }
+                if kept.is_empty() {
+                    if let Some(next) = &default_next {
+                        jumps.insert(id, next.clone());
+                    }
+                }
+                StepKind::Decision {
+                    branches: kept,
+                    default_next,
+                }
+            }
+            StepKind::Action { actions, next_step } => {
+                if actions.is_empty() {
+                    jumps.insert(id, next_step.clone());
+                }
+                StepKind::Action {
+                    actions: specialize_actions(actions, evaluator),
+                    next_step: next_step.clone(),
+                }
+            }
+            StepKind::Terminal { result } => {
+                let mut result = result.clone();
+                for (_, expr) in &mut result.output {
+                    *expr = evaluator.resolve(expr);
+                }
+                StepKind::Terminal { result }
+            }
+        };
+        steps.insert(
+            id,
+            Step {
+                id: step.id.clone(),
+                name: step.name.clone(),
+                kind,
+            },
+        );
+    }
+
+    // Point references past short-circuited steps. A cycle of them is kept
+    // as is: it never terminates, in the original as in the residual.
+    let target = |id: &str| -> String {
+        let mut current = id;
+        let mut seen = HashSet::new();
+        while let Some(next) = jumps.get(current) {
+            if !seen.insert(current) {
+                break;
+            }
+            current = next;
+        }
+        current.to_string()
+    };
+    for step in steps.values_mut() {
+        match &mut step.kind {
+            StepKind::Decision {
+                branches,
+                default_next,
+            } => {
+                for branch in branches {
+                    branch.next_step = target(&branch.next_step);
+                }
+                if let Some(next) = default_next {
+                    *next = target(next);
+                }
+            }
+            StepKind::Action { next_step, .. } => *next_step = target(next_step),
+            StepKind::Terminal { .. } => {}
+        }
+    }
+
+    let entry_step = target(&ruleset.config.entry_step);
+    let mut residual = RuleSet::new(ruleset.config.name.clone(), entry_step.clone());
+    residual.config = RuleSetConfig {
+        entry_step,
+        ..ruleset.config.clone()
+    };
+
+    // Keep what is reachable from the entry step
+    let mut queue = VecDeque::from([residual.config.entry_step.clone()]);
+    let mut reachable = HashSet::new();
+    while let Some(id) = queue.pop_front() {
+        if !reachable.insert(id.clone()) {
+            continue;
+        }
+        if let Some(step) = steps.get(id.as_str()) {
+            queue.extend(step.referenced_steps());
+        }
+    }
+    let short_circuited_steps = jumps.keys().filter(|id| !reachable.contains(**id)).count();
+    for (id, step) in steps {
+        if reachable.contains(id) {
+            residual.add_step(step);
+        }
+    }
+    residual.compile()?;
+
+    Ok(ResidualRuleSet {
+        removed_steps: ruleset.steps.len() - residual.steps.len(),
+        ruleset: residual,
+        folded_branches,
+        short_circuited_steps,
+    })
+}
+
+/// Actions with known fields substituted into their expressions
+fn specialize_actions(actions: &[Action], evaluator: &mut PartialEvaluator) -> Vec<Action> {
+    let mut resolve = |expr: &Expr| evaluator.resolve(expr);
+    actions
+        .iter()
+        .map(|action| {
+            let kind = match &action.kind {
+                ActionKind::SetVariable { name, value } => ActionKind::SetVariable {
+                    name: name.clone(),
+                    value: resolve(value),
+                },
+                ActionKind::Metric { name, value, tags } => ActionKind::Metric {
+                    name: name.clone(),
+                    value: resolve(value),
+                    tags: tags.clone(),
+                },
+                ActionKind::CallRuleSet {
+                    ruleset_name,
+                    input_mapping,
+                    result_variable,
+                } => ActionKind::CallRuleSet {
+                    ruleset_name: ruleset_name.clone(),
+                    input_mapping: input_mapping.as_ref().map(&mut resolve),
+                    result_variable: result_variable.clone(),
+                },
+                ActionKind::ExternalCall {
+                    service,
+                    method,
+                    params,
+                    timeout_ms,
+                    result_variable,
+                } => ActionKind::ExternalCall {
+                    service: service.clone(),
+                    method: method.clone(),
+                    params: params
+                        .iter()
+                        .map(|(k, v)| (k.clone(), resolve(v)))
+                        .collect(),
+                    timeout_ms: *timeout_ms,
+                    result_variable: result_variable.clone(),
+                },
+                ActionKind::Log { .. } => action.kind.clone(),
+            };
+            Action {
+                kind,
+                description: action.description.clone(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Value;
+    use crate::rule::{CompiledRuleExecutor, RuleExecutor, RuleSetCompiler, TerminalResult};
+
+    fn access_ruleset() -> RuleSet {
+        let mut rs = RuleSet::new("access", "check_access");
+        rs.add_step(
+            Step::decision("check_access", "Check Access")
+                .branch(Condition::from_string("user.role == \"admin\""), "approved")
+                .branch(
+                    Condition::from_string("resource.owner == user.id"),
+                    "approved",
+                )
+                .branch(
+                    Condition::from_string("resource.visibility == \"public\""),
+                    "approved",
+                )
+                .default("denied")
+                .build(),
+        );
+        rs.add_step(Step::terminal(
+            "approved",
+            "Approved",
+            TerminalResult::new("APPROVED"),
+        ));
+        rs.add_step(Step::terminal(
+            "denied",
+            "Denied",
+            TerminalResult::new("DENIED"),
+        ));
+        rs
+    }
+
+    fn json(s: &str) -> Value {
+        serde_json::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_residual_matches_original() {
+        let original = access_ruleset();
+        let user = r#"{"role": "viewer", "id": "alice"}"#;
+        let residual = residual_ruleset(
+            &original,
+            &mut PartialEvaluator::new(json(&format!(r#"{{"user": {}}}"#, user))),
+        )
+        .unwrap();
+        assert_eq!(residual.folded_branches, 1);
+        assert_eq!(residual.removed_steps, 0);
+        match &residual.ruleset.get_step("check_access").unwrap().kind {
+            StepKind::Decision { branches, .. } => assert_eq!(branches.len(), 2),
+            kind => panic!("unexpected step {:?}", kind),
+        }
+
+        // Survives serialization, both as JSON and as a compiled ruleset
+        let reloaded = RuleSet::from_json_compiled(&residual.ruleset.to_json().unwrap()).unwrap();
+        let compiled = RuleSetCompiler::compile(&residual.ruleset).unwrap();
+        let compiled = crate::rule::CompiledRuleSet::deserialize(&compiled.serialize()).unwrap();
+
+        let executor = RuleExecutor::new();
+        for resource in [
+            r#"{"owner": "alice", "visibility": "private"}"#,
+            r#"{"owner": "bob", "visibility": "public"}"#,
+            r#"{"owner": "bob", "visibility": "private"}"#,
+        ] {
+            let full = json(&format!(
+                r#"{{"user": {}, "resource": {}}}"#,
+                user, resource
+            ));
+            let remaining = json(&format!(r#"{{"resource": {}}}"#, resource));
+            let expected = executor.execute(&original, full).unwrap().code;
+            for rs in [&residual.ruleset, &reloaded] {
+                let result = executor.execute(rs, remaining.clone()).unwrap();
+                assert_eq!(result.code, expected, "{}", resource);
+            }
+            let result = CompiledRuleExecutor::new()
+                .execute(&compiled, remaining)
+                .unwrap();
+            assert_eq!(result.code, expected, "{}", resource);
+        }
+    }
+
+    #[test]
+    fn test_residual_short_circuits_decided_steps() {
+        let residual = residual_ruleset(
+            &access_ruleset(),
+            &mut PartialEvaluator::new(json(r#"{"user": {"role": "admin", "id": "bob"}}"#)),
+        )
+        .unwrap();
+        assert_eq!(residual.ruleset.config.entry_step, "approved");
+        assert_eq!(residual.ruleset.steps.len(), 1);
+        assert_eq!(residual.folded_branches, 1);
+        assert_eq!(residual.short_circuited_steps, 1);
+        assert_eq!(residual.removed_steps, 2);
+
+        let result = RuleExecutor::new()
+            .execute(&residual.ruleset, json("{}"))
+            .unwrap();
+        assert_eq!(result.code, "APPROVED");
+    }
+
+    #[test]
+    fn test_residual_specializes_actions_and_outputs() {
+        let mut rs = RuleSet::new("quota", "prepare");
+        rs.add_step(Step::action(
+            "prepare",
+            "Prepare",
+            vec![Action::set_var(
+                "limit",
+                ExprParser::parse("user.quota * 2").unwrap(),
+            )],
+            "check",
+        ));
+        rs.add_step(
+            Step::decision("check", "Check")
+                .branch(
+                    Condition::from_string("resource.size <= $limit"),
+                    "approved",
+                )
+                .default("denied")
+                .build(),
+        );
+        rs.add_step(Step::terminal(
+            "approved",
+            "Approved",
+            TerminalResult::new("APPROVED")
+                .with_output("owner", ExprParser::parse("user.id").unwrap()),
+        ));
+        rs.add_step(Step::terminal(
+            "denied",
+            "Denied",
+            TerminalResult::new("DENIED"),
+        ));
+
+        let residual = residual_ruleset(
+            &rs,
+            &mut PartialEvaluator::new(json(r#"{"user": {"quota": 50, "id": "alice"}}"#)),
+        )
+        .unwrap()
+        .ruleset;
+        match &residual.get_step("prepare").unwrap().kind {
+            StepKind::Action { actions, .. } => match &actions[0].kind {
+                ActionKind::SetVariable { value, .. } => {
+                    assert_eq!(*value, Expr::Literal(Value::Int(100)))
+                }
+                kind => panic!("unexpected action {:?}", kind),
+            },
+            kind => panic!("unexpected step {:?}", kind),
+        }
+
+        let executor = RuleExecutor::new();
+        let result = executor
+            .execute(&residual, json(r#"{"resource": {"size": 80}}"#))
+            .unwrap();
+        assert_eq!(result.code, "APPROVED");
+        assert_eq!(result.output, json(r#"{"owner": "alice"}"#));
+        let result = executor
+            .execute(&residual, json(r#"{"resource": {"size": 120}}"#))
+            .unwrap();
+        assert_eq!(result.code, "DENIED");
+    }
+}
@@ -0,0 +1,820 @@
+//! Boolean minimization of collected paths
+//!
+//! The collected paths form a disjunction of conjunctions, one per route
+//! through the rule graph. Rule graphs with shared prefixes repeat the same
+//! conditions on every path, so before rendering [`simplify`]:
+//!
+//! 1. Normalizes each path: flattens `&&`, pushes `!` into `&&`/`||`,
+//!    (in)equalities and `in` lists, and drops duplicate conditions
+//! 2. Merges the comparisons of each field against literals into one domain
+//!    (a numeric interval, a set of allowed values and a set of excluded
+//!    values); a path whose domain is empty can never match and is dropped
+//! 3. Removes paths implied by another path (`a` makes `a && b` redundant)
+//! 4. Merges paths that differ in one field's domain into a single path when
+//!    the union is expressible, e.g. `x == 1 || x == 2` becomes `x in [1, 2]`
+//! 5. Factors conditions shared by every remaining path out of the disjunction
+//!
+//! Range reasoning is only applied to numeric literals, and a merge that
+//! would leave a field unconstrained (`x < 5 || x >= 5`) is skipped, as the
+//! original filter still rejects rows where the field is null.
+
+use std::cmp::Ordering;
+
+use super::path_collector::FilterPath;
+use crate::context::Value;
+use crate::expr::{BinaryOp, Expr, UnaryOp};
+
+/// Simplify a disjunction of paths without changing which rows it matches
+pub fn simplify(paths: Vec<FilterPath>) -> Vec<FilterPath> {
+    let mut paths: Vec<FilterPath> = paths
+        .into_iter()
+        .filter_map(|path| {
+            Some(FilterPath {
+                conditions: normalize(path.conditions)?,
+                result_code: path.result_code,
+            })
+        })
+        .collect();
+
+    loop {
+        remove_subsumed(&mut paths);
+        if !merge_pair(&mut paths) {
+            break;
+        }
+    }
+
+    factor_common(paths)
+}
+
+/// Number of leaf comparisons across all paths (`&&`, `||` and `!` excluded)
+pub fn clause_count(paths: &[FilterPath]) -> usize {
+    fn leaves(expr: &Expr) -> usize {
+        match expr {
+            Expr::Binary {
+                op: BinaryOp::And | BinaryOp::Or,
+                left,
+                right,
+            } => leaves(left) + leaves(right),
+            Expr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => leaves(operand),
+            _ => 1,
+        }
+    }
+    paths.iter().flat_map(|p| &p.conditions).map(leaves).sum()
+}
+
+// ==================== Per-path normalization ====================
+
+/// Normalize one path's conditions, or `None` when they contradict each other
+fn normalize(conditions: Vec<Expr>) -> Option<Vec<Expr>> {
+    let mut flat = Vec::new();
+    for cond in conditions {
+        flatten(cond, false, &mut flat);
+    }
+
+    // Conditions in order; each constrained field keeps the slot of its first comparison
+    let mut slots: Vec<Slot> = Vec::new();
+    let mut domains: Vec<(String, Domain)> = Vec::new();
+    for cond in flat {
+        match cond {
+            Expr::Literal(Value::Bool(true)) => continue,
+            Expr::Literal(Value::Bool(false)) => return None,
+            _ => {}
+        }
+        if let Some((field, domain)) = constraint(&cond) {
+            match domains.iter_mut().find(|(f, _)| *f == field) {
+                Some((_, existing)) => existing.intersect(domain),
+                None => {
+                    slots.push(Slot::Field(domains.len()));
+                    domains.push((field, domain));
+                }
+            }
+        } else if !slots
+            .iter()
+            .any(|s| matches!(s, Slot::Expr(e) if *e == cond))
+        {
+            slots.push(Slot::Expr(cond));
+        }
+    }
+
+    // `c && !c`
+    for slot in &slots {
+        if let Slot::Expr(Expr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        }) = slot
+        {
+            if slots
+                .iter()
+                .any(|s| matches!(s, Slot::Expr(e) if e == operand.as_ref()))
+            {
+                return None;
+            }
+        }
+    }
+
+    let mut conditions = Vec::with_capacity(slots.len());
+    for slot in slots {
+        match slot {
+            Slot::Expr(e) => conditions.push(e),
+            Slot::Field(i) => {
+                let (field, domain) = &domains[i];
+                conditions.extend(domain.to_exprs(field)?);
+            }
+        }
+    }
+    Some(conditions)
+}
+
+enum Slot {
+    Expr(Expr),
+    /// Index into the field domains
+    Field(usize),
+}
+
+/// Split conjunctions and push negation inwards where it is lossless
+fn flatten(expr: Expr, negated: bool, out: &mut Vec<Expr>) {
+    match expr {
+        Expr::Binary {
+            op: BinaryOp::And,
+            left,
+            right,
+        } if !negated => {
+            flatten(*left, false, out);
+            flatten(*right, false, out);
+        }
+        // !(a || b) == !a && !b
+        Expr::Binary {
+            op: BinaryOp::Or,
+            left,
+            right,
+        } if negated => {
+            flatten(*left, true, out);
+            flatten(*right, true, out);
+        }
+        Expr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } => flatten(*operand, !negated, out),
+        Expr::Binary { op, left, right } if negated && complement(op).is_some() => {
+            out.push(Expr::Binary {
+                op: complement(op).unwrap(),
+                left,
+                right,
+            });
+        }
+        Expr::Literal(Value::Bool(b)) => out.push(Expr::Literal(Value::Bool(b != negated))),
+        expr if negated => out.push(Expr::Unary {
+            op: UnaryOp::Not,
+            operand: Box::new(expr),
+        }),
+        expr => out.push(expr),
+    }
+}
+
+/// Operator equivalent to `!(a op b)`, for operators where every backend
+/// agrees on missing and null values
+fn complement(op: BinaryOp) -> Option<BinaryOp> {
+    match op {
+        BinaryOp::Eq => Some(BinaryOp::Ne),
+        BinaryOp::Ne => Some(BinaryOp::Eq),
+        BinaryOp::In => Some(BinaryOp::NotIn),
+        BinaryOp::NotIn => Some(BinaryOp::In),
+        _ => None,
+    }
+}
+
+// ==================== Field domains ====================
+
+/// Values of one field admitted by a set of comparisons against literals
+#[derive(Debug, Clone, Default)]
+struct Domain {
+    lower: Option<Bound>,
+    upper: Option<Bound>,
+    /// Only these values (from `==` and `in`)
+    allowed: Option<Vec<Value>>,
+    /// Never these values (from `!=` and `not in`)
+    excluded: Vec<Value>,
+}
+
+#[derive(Debug, Clone)]
+struct Bound {
+    value: Value,
+    inclusive: bool,
+}
+
+/// The domain a comparison between a field and a literal restricts it to
+fn constraint(expr: &Expr) -> Option<(String, Domain)> {
+    let Expr::Binary { op, left, right } = expr else {
+        return None;
+    };
+    let (field, value, op) = match (left.as_ref(), right.as_ref()) {
+        (Expr::Field(f), v) => (f, literal(v)?, *op),
+        (v, Expr::Field(f)) => (f, literal(v)?, flip(*op)?),
+        _ => return None,
+    };
+    let value = &value;
+
+    let mut domain = Domain::default();
+    match op {
+        BinaryOp::Eq if is_scalar(value) => domain.allowed = Some(vec![value.clone()]),
+        BinaryOp::Ne if is_scalar(value) => domain.excluded.push(value.clone()),
+        BinaryOp::In | BinaryOp::NotIn => {
+            let Value::Array(items) = value else {
+                return None;
+            };
+            if items.is_empty() || !items.iter().all(is_scalar) {
+                return None;
+            }
+            let mut values = Vec::with_capacity(items.len());
+            for v in items.iter() {
+                if !values.iter().any(|u| same(u, v)) {
+                    values.push(v.clone());
+                }
+            }
+            if op == BinaryOp::In {
+                domain.allowed = Some(values);
+            } else {
+                domain.excluded = values;
+            }
+        }
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge if value.is_number() => {
+            let bound = Bound {
+                value: value.clone(),
+                inclusive: matches!(op, BinaryOp::Le | BinaryOp::Ge),
+            };
+            if matches!(op, BinaryOp::Lt | BinaryOp::Le) {
+                domain.upper = Some(bound);
+            } else {
+                domain.lower = Some(bound);
+            }
+        }
+        _ => return None,
+    }
+    Some((field.clone(), domain))
+}
+
+/// Value of a literal, or of an array literal the optimizer has not folded
+fn literal(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Literal(v) => Some(v.clone()),
+        Expr::Array(elems) => elems
+            .iter()
+            .map(|e| match e {
+                Expr::Literal(v) => Some(v.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Value::array),
+        _ => None,
+    }
+}
+
+/// `a op b` rewritten as `b op' a`
+fn flip(op: BinaryOp) -> Option<BinaryOp> {
+    match op {
+        BinaryOp::Eq | BinaryOp::Ne => Some(op),
+        BinaryOp::Lt => Some(BinaryOp::Gt),
+        BinaryOp::Le => Some(BinaryOp::Ge),
+        BinaryOp::Gt => Some(BinaryOp::Lt),
+        BinaryOp::Ge => Some(BinaryOp::Le),
+        _ => None,
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Bool(_) | Value::Int(_) | Value::Float(_) | Value::String(_)
+    )
+}
+
+/// Literal equality, with `1 == 1.0`
+fn same(a: &Value, b: &Value) -> bool {
+    match a.compare(b) {
+        Some(ord) => ord == Ordering::Equal,
+        None => a == b,
+    }
+}
+
+impl Domain {
+    /// Restrict to values admitted by both domains
+    fn intersect(&mut self, other: Domain) {
+        if let Some(bound) = other.lower {
+            self.lower = Some(tighter(self.lower.take(), bound, Ordering::Greater));
+        }
+        if let Some(bound) = other.upper {
+            self.upper = Some(tighter(self.upper.take(), bound, Ordering::Less));
+        }
+        self.allowed = match (self.allowed.take(), other.allowed) {
+            (Some(a), Some(b)) => Some(
+                a.into_iter()
+                    .filter(|v| b.iter().any(|u| same(u, v)))
+                    .collect(),
+            ),
+            (a, b) => a.or(b),
+        };
+        for v in other.excluded {
+            if !self.excluded.iter().any(|u| same(u, &v)) {
+                self.excluded.push(v);
+            }
+        }
+    }
+
+    /// Whether `value` lies within the bounds
+    fn in_bounds(&self, value: &Value) -> bool {
+        let within = |bound: &Option<Bound>, side: Ordering| match bound {
+            None => true,
+            Some(b) => match value.compare(&b.value) {
+                Some(Ordering::Equal) => b.inclusive,
+                Some(ord) => ord == side,
+                None => false,
+            },
+        };
+        within(&self.lower, Ordering::Greater) && within(&self.upper, Ordering::Less)
+    }
+
+    fn admits(&self, value: &Value) -> bool {
+        self.allowed
+            .as_ref()
+            .map_or(true, |a| a.iter().any(|u| same(u, value)))
+            && !self.excluded.iter().any(|u| same(u, value))
+            && self.in_bounds(value)
+    }
+
+    /// Values the domain admits when it is finite
+    fn values(&self) -> Option<Vec<Value>> {
+        if let Some(allowed) = &self.allowed {
+            return Some(allowed.iter().filter(|v| self.admits(v)).cloned().collect());
+        }
+        // A closed interval of a single point
+        match (&self.lower, &self.upper) {
+            (Some(lo), Some(hi)) if lo.inclusive && hi.inclusive => {
+                (lo.value.compare(&hi.value) == Some(Ordering::Equal)).then(|| {
+                    [lo.value.clone()]
+                        .into_iter()
+                        .filter(|v| self.admits(v))
+                        .collect()
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        if let Some(values) = self.values() {
+            return values.is_empty();
+        }
+        match (&self.lower, &self.upper) {
+            (Some(lo), Some(hi)) => match lo.value.compare(&hi.value) {
+                Some(Ordering::Less) => false,
+                Some(Ordering::Equal) => !(lo.inclusive && hi.inclusive),
+                _ => true,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether every value admitted here is admitted by `other`
+    fn subset_of(&self, other: &Domain) -> bool {
+        if let Some(values) = self.values() {
+            return values.iter().all(|v| other.admits(v));
+        }
+        if other.allowed.is_some() {
+            return false;
+        }
+        covers(&other.lower, &self.lower, Ordering::Greater)
+            && covers(&other.upper, &self.upper, Ordering::Less)
+            && other.excluded.iter().all(|v| !self.admits(v))
+    }
+
+    /// Domain admitting exactly the values of either, if one exists that
+    /// still constrains the field
+    fn union(&self, other: &Domain) -> Option<Domain> {
+        if let (Some(a), Some(b)) = (self.values(), other.values()) {
+            let mut allowed = a;
+            for v in b {
+                if !allowed.iter().any(|u| same(u, &v)) {
+                    allowed.push(v);
+                }
+            }
+            return Some(Domain {
+                allowed: Some(allowed),
+                ..Domain::default()
+            });
+        }
+
+        // Two overlapping or adjacent intervals
+        let plain = |d: &Domain| d.allowed.is_none() && d.excluded.is_empty();
+        if !plain(self) || !plain(other) || disjoint(self, other) || disjoint(other, self) {
+            return None;
+        }
+        let lower = looser(&self.lower, &other.lower, Ordering::Less);
+        let upper = looser(&self.upper, &other.upper, Ordering::Greater);
+        if lower.is_none() && upper.is_none() {
+            return None;
+        }
+        Some(Domain {
+            lower,
+            upper,
+            ..Domain::default()
+        })
+    }
+
+    /// Conditions on `field` equivalent to this domain, or `None` when empty
+    fn to_exprs(&self, field: &str) -> Option<Vec<Expr>> {
+        if self.is_empty() {
+            return None;
+        }
+        if let Some(values) = self.values() {
+            return Some(vec![match values.len() {
+                1 => comparison(field, BinaryOp::Eq, values[0].clone()),
+                _ => comparison(field, BinaryOp::In, Value::array(values)),
+            }]);
+        }
+
+        let mut exprs = Vec::new();
+        if let Some(lo) = &self.lower {
+            let op = if lo.inclusive {
+                BinaryOp::Ge
+            } else {
+                BinaryOp::Gt
+            };
+            exprs.push(comparison(field, op, lo.value.clone()));
+        }
+        if let Some(hi) = &self.upper {
+            let op = if hi.inclusive {
+                BinaryOp::Le
+            } else {
+                BinaryOp::Lt
+            };
+            exprs.push(comparison(field, op, hi.value.clone()));
+        }
+        // Exclusions outside the interval are implied by it
+        let excluded: Vec<Value> = self
+            .excluded
+            .iter()
+            .filter(|v| self.in_bounds(v))
+            .cloned()
+            .collect();
+        match excluded.len() {
+            0 => {}
+            1 => exprs.push(comparison(field, BinaryOp::Ne, excluded[0].clone())),
+            _ => exprs.push(comparison(field, BinaryOp::NotIn, Value::array(excluded))),
+        }
+        Some(exprs)
+    }
+}
+
+/// The stricter of two bounds on the same side (`side` is the direction
+/// values must lie in relative to the bound)
+fn tighter(current: Option<Bound>, new: Bound, side: Ordering) -> Bound {
+    let Some(current) = current else {
+        return new;
+    };
+    match new.value.compare(&current.value) {
+        Some(Ordering::Equal) => Bound {
+            inclusive: current.inclusive && new.inclusive,
+            ..current
+        },
+        Some(ord) if ord == side => new,
+        _ => current,
+    }
+}
+
+/// The more permissive of two bounds; no bound on either side means none
+fn looser(a: &Option<Bound>, b: &Option<Bound>, side: Ordering) -> Option<Bound> {
+    let (a, b) = (a.as_ref()?, b.as_ref()?);
+    match a.value.compare(&b.value) {
+        Some(Ordering::Equal) => Some(Bound {
+            value: a.value.clone(),
+            inclusive: a.inclusive || b.inclusive,
+        }),
+        Some(ord) if ord == side => Some(a.clone()),
+        _ => Some(b.clone()),
+    }
+}
+
+/// Whether `outer` is no stricter than `inner` on one side
+fn covers(outer: &Option<Bound>, inner: &Option<Bound>, side: Ordering) -> bool {
+    let Some(outer) = outer else {
+        return true;
+    };
+    let Some(inner) = inner else {
+        return false;
+    };
+    match inner.value.compare(&outer.value) {
+        Some(Ordering::Equal) => outer.inclusive || !inner.inclusive,
+        Some(ord) => ord == side,
+        None => false,
+    }
+}
+
+/// Whether all of `a` lies below all of `b` with a gap between them
+fn disjoint(a: &Domain, b: &Domain) -> bool {
+    let (Some(hi), Some(lo)) = (&a.upper, &b.lower) else {
+        return false;
+    };
+    match hi.value.compare(&lo.value) {
+        Some(Ordering::Less) => true,
+        Some(Ordering::Equal) => !hi.inclusive && !lo.inclusive,
+        _ => false,
+    }
+}
+
+fn comparison(field: &str, op: BinaryOp, value: Value) -> Expr {
+    Expr::Binary {
+        op,
+        left: Box::new(Expr::Field(field.to_string())),
+        right: Box::new(Expr::Literal(value)),
+    }
+}
+
+/// Domain of `field` over all of a path's conditions
+fn field_domain(conditions: &[Expr], field: &str) -> Option<Domain> {
+    let mut result: Option<Domain> = None;
+    for (f, domain) in conditions.iter().filter_map(constraint) {
+        if f == field {
+            match result.as_mut() {
+                Some(d) => d.intersect(domain),
+                None => result = Some(domain),
+            }
+        }
+    }
+    result
+}
+
+// ==================== Across paths ====================
+
+/// Whether every row matching `conditions` also satisfies `cond`
+fn implies(conditions: &[Expr], cond: &Expr) -> bool {
+    if conditions.contains(cond) {
+        return true;
+    }
+    match constraint(cond) {
+        Some((field, domain)) => {
+            field_domain(conditions, &field).is_some_and(|d| d.subset_of(&domain))
+        }
+        None => false,
+    }
+}
+
+/// Drop every path that implies an earlier-kept path (`a || (a && b)` is `a`)
+fn remove_subsumed(paths: &mut Vec<FilterPath>) {
+    let mut i = 0;
+    while i < paths.len() {
+        let redundant = (0..paths.len()).any(|j| {
+            j != i
+                && paths[j]
+                    .conditions
+                    .iter()
+                    .all(|c| implies(&paths[i].conditions, c))
+                // Of two equivalent paths keep the first
+                && (j < i
+                    || !paths[i]
+                        .conditions
+                        .iter()
+                        .all(|c| implies(&paths[j].conditions, c)))
+        });
+        if redundant {
+            paths.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Merge the first pair of paths that differ only in one field's domain.
+/// Returns whether a merge happened.
+fn merge_pair(paths: &mut Vec<FilterPath>) -> bool {
+    for i in 0..paths.len() {
+        for j in i + 1..paths.len() {
+            if let Some(conditions) = merge(&paths[i].conditions, &paths[j].conditions) {
+                paths[i].conditions = conditions;
+                paths.remove(j);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn merge(a: &[Expr], b: &[Expr]) -> Option<Vec<Expr>> {
+    let only_a: Vec<&Expr> = a.iter().filter(|c| !b.contains(c)).collect();
+    let only_b: Vec<&Expr> = b.iter().filter(|c| !a.contains(c)).collect();
+    let (first, _) = constraint(only_a.first()?)?;
+    let on_field = |conds: &[&Expr]| {
+        conds
+            .iter()
+            .all(|c| constraint(c).is_some_and(|(f, _)| f == first))
+    };
+    if !on_field(&only_a) || !on_field(&only_b) || only_b.is_empty() {
+        return None;
+    }
+    // Shared conditions on the field must not restrict either side further
+    if a.iter()
+        .any(|c| b.contains(c) && constraint(c).is_some_and(|(f, _)| f == first))
+    {
+        return None;
+    }
+
+    let union = field_domain(a, &first)?.union(&field_domain(b, &first)?)?;
+    let mut merged = Vec::with_capacity(a.len());
+    let mut placed = false;
+    for cond in a {
+        if only_a.contains(&cond) {
+            if !placed {
+                merged.extend(union.to_exprs(&first)?);
+                placed = true;
+            }
+        } else {
+            merged.push(cond.clone());
+        }
+    }
+    Some(merged)
+}
+
+/// Rewrite `(c && a) || (c && b)` as `c && (a || b)`
+fn factor_common(mut paths: Vec<FilterPath>) -> Vec<FilterPath> {
+    if paths.len() < 2 {
+        return paths;
+    }
+    let common: Vec<Expr> = paths[0]
+        .conditions
+        .iter()
+        .filter(|c| paths[1..].iter().all(|p| p.conditions.contains(c)))
+        .cloned()
+        .collect();
+    // A path made of the common conditions alone would have subsumed the rest
+    if common.is_empty() || paths.iter().any(|p| p.conditions.len() == common.len()) {
+        return paths;
+    }
+
+    let alternatives = paths
+        .iter_mut()
+        .map(|p| {
+            let rest: Vec<Expr> = std::mem::take(&mut p.conditions)
+                .into_iter()
+                .filter(|c| !common.contains(c))
+                .collect();
+            join(rest, BinaryOp::And)
+        })
+        .collect();
+    let mut conditions = common;
+    conditions.push(join(alternatives, BinaryOp::Or));
+    vec![FilterPath {
+        conditions,
+        result_code: paths.swap_remove(0).result_code,
+    }]
+}
+
+/// Left-nested chain of `op` over `exprs` (`exprs` must not be empty)
+fn join(exprs: Vec<Expr>, op: BinaryOp) -> Expr {
+    exprs
+        .into_iter()
+        .reduce(|left, right| Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+        .expect("non-empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
+    use crate::filter::sql::to_sql;
+
+    fn paths(conditions: &[&[&str]]) -> Vec<FilterPath> {
+        conditions
+            .iter()
+            .map(|conds| FilterPath {
+                conditions: conds
+                    .iter()
+                    .map(|c| ExprParser::parse(c).unwrap())
+                    .collect(),
+                result_code: "OK".to_string(),
+            })
+            .collect()
+    }
+
+    fn sql(paths: Vec<FilterPath>) -> String {
+        let paths = simplify(paths);
+        to_sql(&paths, &Default::default()).unwrap()
+    }
+
+    #[test]
+    fn test_factor_common_conjuncts() {
+        let input = paths(&[
+            &["tenant_id == \"t1\"", "owner == \"alice\""],
+            &["tenant_id == \"t1\"", "visibility == \"public\""],
+            &["tenant_id == \"t1\"", "shared == true"],
+        ]);
+        assert_eq!(clause_count(&input), 6);
+        let output = simplify(input);
+        assert_eq!(clause_count(&output), 4);
+        assert_eq!(
+            to_sql(&output, &Default::default()).unwrap(),
+            "tenant_id = 't1' AND ((owner = 'alice' OR visibility = 'public') OR shared = TRUE)"
+        );
+    }
+
+    #[test]
+    fn test_remove_subsumed_paths() {
+        assert_eq!(
+            sql(paths(&[
+                &["status == \"open\"", "priority > 3"],
+                &["status == \"open\""],
+                &["status == \"open\""],
+            ])),
+            "status = 'open'"
+        );
+        // `age >= 21` implies `age >= 18`
+        assert_eq!(
+            sql(paths(&[&["age >= 18", "vip"], &["age >= 21", "vip"]])),
+            "age >= 18 AND vip"
+        );
+    }
+
+    #[test]
+    fn test_merge_equalities_into_in() {
+        assert_eq!(
+            sql(paths(&[
+                &["region == \"eu\"", "tier == \"gold\""],
+                &["region == \"eu\"", "tier == \"silver\""],
+                &["region == \"eu\"", "tier in [\"silver\", \"bronze\"]"],
+            ])),
+            "region = 'eu' AND tier IN ('gold', 'silver', 'bronze')"
+        );
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        // Within a path
+        assert_eq!(
+            sql(paths(&[&[
+                "size > 5",
+                "size >= 10",
+                "size < 100",
+                "size <= 100"
+            ]])),
+            "size >= 10 AND size < 100"
+        );
+        // Across paths, overlapping intervals
+        assert_eq!(
+            sql(paths(&[
+                &["size >= 0", "size < 10"],
+                &["size >= 10", "size < 20"]
+            ])),
+            "size >= 0 AND size < 20"
+        );
+        // A merge that would drop the field entirely is skipped
+        assert_eq!(
+            sql(paths(&[&["size < 10"], &["size >= 10"]])),
+            "(size < 10) OR (size >= 10)"
+        );
+        // A point interval becomes an equality
+        assert_eq!(sql(paths(&[&["10 <= size", "size <= 10"]])), "size = 10");
+    }
+
+    #[test]
+    fn test_contradictions() {
+        let never = |conds: &[&str]| simplify(paths(&[conds])).is_empty();
+        assert!(never(&["size > 10", "size < 5"]));
+        assert!(never(&["size > 10", "size <= 10"]));
+        assert!(never(&["status == \"a\"", "status == \"b\""]));
+        assert!(never(&["status == \"a\"", "status != \"a\""]));
+        assert!(never(&[
+            "status in [\"a\", \"b\"]",
+            "not (status in [\"a\", \"b\"])"
+        ]));
+        assert!(never(&["size == 3", "size > 5"]));
+        assert!(never(&["active", "not active"]));
+        assert!(!never(&["size >= 5", "size <= 5"]));
+
+        // The remaining path keeps only the surviving values
+        assert_eq!(
+            sql(paths(&[
+                &["size > 10", "size < 5"],
+                &["tier in [\"a\", \"b\", \"c\"]", "tier != \"b\""],
+            ])),
+            "tier IN ('a', 'c')"
+        );
+    }
+
+    #[test]
+    fn test_negation_pushed_into_comparisons() {
+        assert_eq!(
+            sql(paths(&[&[
+                "not (plan == \"pro\" || plan == \"team\")",
+                "size > 1"
+            ]])),
+            "plan NOT IN ('pro', 'team') AND size > 1"
+        );
+        // Range negations keep their form
+        assert_eq!(sql(paths(&[&["not (size > 1)"]])), "NOT (size > 1)");
+    }
+}
@@ -1,208 +1,1038 @@
 //! SQL WHERE clause generator for filter compilation
+//!
+//! The generic dialect renders mapped columns as given and inlines literals.
+//! A concrete dialect quotes identifiers, escapes literals the way that
+//! database expects and can emit placeholders with an ordered parameter list
+//! instead of inlined literals.
+//!
+//! # Column resolution
+//!
+//! A field path resolves to the column of its longest mapped prefix; the rest
+//! of the path is read from that column as JSON:
+//!
+//! | Dialect    | `data.customer.tier` with `{"data": "attrs"}`      |
+//! |------------|----------------------------------------------------|
+//! | PostgreSQL | `"attrs"->'customer'->>'tier'`                     |
+//! | MySQL      | `` `attrs`->>'$.customer.tier' ``                  |
+//! | SQLite     | `json_extract("attrs", '$.customer.tier')`         |
+//! | SQL Server | `JSON_VALUE([attrs], N'$.customer.tier')`          |
+//!
+//! JSON values compared with numbers or booleans are cast accordingly.
+//! Unmapped fields fall back to `path.replace('.', "_")`, or are rejected in
+//! strict mode.
+//!
+//! # Functions
+//!
+//! String functions map to their SQL counterparts (`starts_with` to `LIKE`,
+//! `lower` to `LOWER`, `len` to `LENGTH`, `coalesce` to `COALESCE`), and
+//! `regex_match` to `~` (PostgreSQL) or `REGEXP` (MySQL, SQLite). `now()` and
+//! `now_millis()` become the dialect's current Unix time, `date_add` and
+//! `time_diff` arithmetic on it (`time_diff` truncating toward zero like the
+//! rules; generic SQL has no translation). [`SqlOptions::functions`] adds or overrides
+//! translations; calls without one are left out of the filter where that is
+//! safe (see [`super::pushdown`]).
 
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use crate::context::Value;
 use crate::error::{OrdoError, Result};
 use crate::expr::{BinaryOp, Expr, UnaryOp};
 
 use super::path_collector::FilterPath;
+use super::pushdown::{date_offset, unit_seconds};
+
+/// SQL dialect of the generated clause
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SqlDialect {
+    /// Unquoted identifiers, inlined literals (no JSON column access)
+    #[default]
+    Generic,
+    #[serde(alias = "postgresql")]
+    Postgres,
+    #[serde(alias = "mariadb")]
+    MySql,
+    Sqlite,
+    #[serde(alias = "mssql")]
+    SqlServer,
+}
+
+impl SqlDialect {
+    /// Clause that is always true or always false
+    pub fn constant(self, value: bool) -> &'static str {
+        match (self, value) {
+            (Self::SqlServer, true) => "1 = 1",
+            (Self::SqlServer, false) => "1 = 0",
+            (_, true) => "TRUE",
+            (_, false) => "FALSE",
+        }
+    }
+}
+
+/// Options for SQL generation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SqlOptions {
+    /// Target dialect (default: generic)
+    #[serde(default)]
+    pub dialect: SqlDialect,
+    /// Emit placeholders (`$1` for PostgreSQL, `@p1` for SQL Server, `?`
+    /// otherwise) and return the values as an ordered parameter list
+    #[serde(default)]
+    pub parameterized: bool,
+    /// Reject fields without a column mapping instead of deriving a name
+    #[serde(default)]
+    pub strict: bool,
+    /// Custom function translations: a SQL template per function name, with
+    /// `{0}`, `{1}`, … standing for the rendered arguments. These take
+    /// precedence over the built-in translations.
+    #[serde(default)]
+    pub functions: HashMap<String, String>,
+}
+
+impl SqlOptions {
+    /// Translate calls of `name` with `template`, e.g.
+    /// `with_function("unaccent", "unaccent({0})")`
+    pub fn with_function(mut self, name: impl Into<String>, template: impl Into<String>) -> Self {
+        self.functions.insert(name.into(), template.into());
+        self
+    }
+}
 
-/// Convert collected paths to a SQL WHERE clause.
+/// A generated WHERE clause and its parameters
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SqlClause {
+    pub sql: String,
+    /// Values of the placeholders, in order (empty unless parameterized)
+    pub params: Vec<Value>,
+}
+
+/// Convert collected paths to a SQL WHERE clause with inlined literals.
 ///
 /// Multiple paths are combined with OR; conditions within a single path with AND.
 pub fn to_sql(paths: &[FilterPath], mapping: &HashMap<String, String>) -> Result<String> {
-    if paths.is_empty() {
-        return Ok("FALSE".to_string());
+    to_sql_with(paths, mapping, &SqlOptions::default()).map(|clause| clause.sql)
+}
+
+/// Convert collected paths to a SQL WHERE clause for the given options.
+pub fn to_sql_with(
+    paths: &[FilterPath],
+    mapping: &HashMap<String, String>,
+    options: &SqlOptions,
+) -> Result<SqlClause> {
+    let mut writer = SqlWriter::new(mapping, options);
+    let sql = writer.paths(paths)?;
+    Ok(SqlClause {
+        sql,
+        params: writer.params,
+    })
+}
+
+/// Name of a function called in `expr` that has no SQL translation
+pub fn untranslated(
+    expr: &Expr,
+    mapping: &HashMap<String, String>,
+    options: &SqlOptions,
+) -> Option<String> {
+    let mut writer = SqlWriter::new(mapping, options);
+    let _ = writer.expr(expr);
+    writer.untranslated
+}
+
+/// How a literal compared with a JSON-extracted value is typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsonCast {
+    Text,
+    Number,
+    Bool,
+}
+
+impl JsonCast {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Int(_) | Value::Float(_) => Self::Number,
+            Value::Bool(_) => Self::Bool,
+            Value::Array(items) => items.first().map_or(Self::Text, Self::of),
+            _ => Self::Text,
+        }
     }
+}
 
-    // Any path with no conditions means "always matches"
-    if paths.iter().any(|p| p.conditions.is_empty()) {
-        return Ok("TRUE".to_string());
+/// A rendered operand; `json` marks text extracted from a JSON column
+struct Operand {
+    sql: String,
+    json: bool,
+}
+
+struct SqlWriter<'a> {
+    mapping: &'a HashMap<String, String>,
+    options: &'a SqlOptions,
+    params: Vec<Value>,
+    /// First function that could not be translated
+    untranslated: Option<String>,
+}
+
+impl<'a> SqlWriter<'a> {
+    fn new(mapping: &'a HashMap<String, String>, options: &'a SqlOptions) -> Self {
+        Self {
+            mapping,
+            options,
+            params: Vec::new(),
+            untranslated: None,
+        }
     }
 
-    let clauses: Result<Vec<String>> = paths.iter().map(|p| path_to_sql(p, mapping)).collect();
-    let clauses = clauses?;
+    fn dialect(&self) -> SqlDialect {
+        self.options.dialect
+    }
 
-    if clauses.len() == 1 {
-        Ok(clauses.into_iter().next().unwrap())
-    } else {
-        let parts: Vec<String> = clauses.into_iter().map(|c| format!("({})", c)).collect();
-        Ok(parts.join(" OR "))
+    fn constant(&self, value: bool) -> String {
+        self.dialect().constant(value).to_string()
     }
-}
 
-fn path_to_sql(path: &FilterPath, mapping: &HashMap<String, String>) -> Result<String> {
-    match path.conditions.len() {
-        0 => Ok("TRUE".to_string()),
-        1 => expr_to_sql(&path.conditions[0], mapping),
-        _ => {
-            let parts: Result<Vec<String>> = path
-                .conditions
-                .iter()
-                .map(|c| expr_to_sql(c, mapping))
-                .collect();
-            Ok(parts?.join(" AND "))
+    fn paths(&mut self, paths: &[FilterPath]) -> Result<String> {
+        if paths.is_empty() {
+            return Ok(self.constant(false));
+        }
+
+        // Any path with no conditions means "always matches"
+        if paths.iter().any(|p| p.conditions.is_empty()) {
+            return Ok(self.constant(true));
+        }
+
+        let mut clauses = Vec::with_capacity(paths.len());
+        for path in paths {
+            clauses.push(self.path(path)?);
+        }
+
+        if clauses.len() == 1 {
+            Ok(clauses.into_iter().next().unwrap())
+        } else {
+            let parts: Vec<String> = clauses.into_iter().map(|c| format!("({})", c)).collect();
+            Ok(parts.join(" OR "))
         }
     }
-}
 
-fn expr_to_sql(expr: &Expr, mapping: &HashMap<String, String>) -> Result<String> {
-    match expr {
-        Expr::Field(path) => {
-            let col = mapping
-                .get(path)
-                .cloned()
-                .unwrap_or_else(|| path.replace('.', "_"));
-            Ok(col)
-        }
-        Expr::Literal(val) => Ok(value_to_sql(val)),
-        Expr::Binary { op, left, right } => binary_to_sql(*op, left, right, mapping),
-        Expr::Unary { op, operand } => unary_to_sql(*op, operand, mapping),
-        Expr::Call { name, args } => call_to_sql(name, args, mapping),
-        Expr::Array(elems) => {
-            let parts: Result<Vec<String>> =
-                elems.iter().map(|e| expr_to_sql(e, mapping)).collect();
-            Ok(format!("({})", parts?.join(", ")))
-        }
-        other => Err(OrdoError::parse_error(format!(
-            "Cannot convert expression to SQL: {:?}",
-            other
-        ))),
+    fn path(&mut self, path: &FilterPath) -> Result<String> {
+        let mut parts = Vec::with_capacity(path.conditions.len());
+        for condition in &path.conditions {
+            parts.push(self.expr(condition)?);
+        }
+        match parts.len() {
+            0 => Ok(self.constant(true)),
+            _ => Ok(parts.join(" AND ")),
+        }
     }
-}
 
-fn binary_to_sql(
-    op: BinaryOp,
-    left: &Expr,
-    right: &Expr,
-    mapping: &HashMap<String, String>,
-) -> Result<String> {
-    let l = || expr_to_sql(left, mapping);
-    let r = || expr_to_sql(right, mapping);
-
-    match op {
-        BinaryOp::Eq => {
-            if matches!(right, Expr::Literal(Value::Null)) {
-                Ok(format!("{} IS NULL", l()?))
-            } else if matches!(left, Expr::Literal(Value::Null)) {
-                Ok(format!("{} IS NULL", r()?))
-            } else {
-                Ok(format!("{} = {}", l()?, r()?))
+    fn expr(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Field(path) => Ok(self.column(path)?.sql),
+            Expr::Literal(val) => self.value(val),
+            Expr::Binary { op, left, right } => self.binary(*op, left, right),
+            Expr::Unary { op, operand } => self.unary(*op, operand),
+            Expr::Call { name, args } => self.call(name, args),
+            Expr::Coalesce(exprs) => Ok(format!("COALESCE({})", self.list(exprs)?)),
+            Expr::Array(elems) => Ok(format!("({})", self.list(elems)?)),
+            other => Err(OrdoError::parse_error(format!(
+                "Cannot convert expression to SQL: {:?}",
+                other
+            ))),
+        }
+    }
+
+    /// Operand of a comparison, cast when JSON text meets a typed literal
+    fn operand(&mut self, expr: &Expr, other: &Expr) -> Result<String> {
+        match expr {
+            Expr::Field(path) => {
+                let column = self.column(path)?;
+                match other {
+                    Expr::Literal(value) if column.json => {
+                        Ok(self.cast_json(column.sql, JsonCast::of(value)))
+                    }
+                    Expr::Array(items) if column.json => {
+                        let cast = match items.first() {
+                            Some(Expr::Literal(value)) => JsonCast::of(value),
+                            _ => JsonCast::Text,
+                        };
+                        Ok(self.cast_json(column.sql, cast))
+                    }
+                    _ => Ok(column.sql),
+                }
             }
+            // Booleans compared with JSON text are compared as 'true' / 'false'
+            Expr::Literal(Value::Bool(b)) if self.is_json_text(other) => match self.dialect() {
+                SqlDialect::MySql | SqlDialect::SqlServer => {
+                    self.value(&Value::string(if *b { "true" } else { "false" }))
+                }
+                _ => self.value(&Value::Bool(*b)),
+            },
+            _ => self.expr(expr),
         }
-        BinaryOp::Ne => {
-            if matches!(right, Expr::Literal(Value::Null)) {
-                Ok(format!("{} IS NOT NULL", l()?))
-            } else if matches!(left, Expr::Literal(Value::Null)) {
-                Ok(format!("{} IS NOT NULL", r()?))
-            } else {
-                Ok(format!("{} != {}", l()?, r()?))
-            }
-        }
-        BinaryOp::Lt => Ok(format!("{} < {}", l()?, r()?)),
-        BinaryOp::Le => Ok(format!("{} <= {}", l()?, r()?)),
-        BinaryOp::Gt => Ok(format!("{} > {}", l()?, r()?)),
-        BinaryOp::Ge => Ok(format!("{} >= {}", l()?, r()?)),
-        BinaryOp::And => Ok(format!("({} AND {})", l()?, r()?)),
-        BinaryOp::Or => Ok(format!("({} OR {})", l()?, r()?)),
-        BinaryOp::In => Ok(format!("{} IN {}", l()?, r()?)),
-        BinaryOp::NotIn => Ok(format!("{} NOT IN {}", l()?, r()?)),
-        BinaryOp::Contains => {
-            if let Expr::Literal(Value::String(s)) = right {
-                Ok(format!(
-                    "{} LIKE '%{}%' ESCAPE '!'",
-                    l()?,
-                    escape_like_pattern(s)
+    }
+
+    fn is_json_text(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Field(path) => self.resolve(path).is_some_and(|(_, rest)| !rest.is_empty()),
+            _ => false,
+        }
+    }
+
+    fn cast_json(&self, sql: String, cast: JsonCast) -> String {
+        match (self.dialect(), cast) {
+            (SqlDialect::Postgres, JsonCast::Number) => format!("({})::numeric", sql),
+            (SqlDialect::Postgres, JsonCast::Bool) => format!("({})::boolean", sql),
+            (SqlDialect::MySql, JsonCast::Number) => format!("CAST({} AS DECIMAL(65, 30))", sql),
+            (SqlDialect::SqlServer, JsonCast::Number) => format!("CAST({} AS FLOAT)", sql),
+            // SQLite's json_extract returns native values; MySQL and SQL
+            // Server booleans are compared as text (see `operand`)
+            _ => sql,
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> Result<String> {
+        let ne = match self.dialect() {
+            SqlDialect::Generic => "!=",
+            _ => "<>",
+        };
+
+        match op {
+            BinaryOp::Eq | BinaryOp::Ne => {
+                let null = if op == BinaryOp::Eq {
+                    "IS NULL"
+                } else {
+                    "IS NOT NULL"
+                };
+                if matches!(right, Expr::Literal(Value::Null)) {
+                    Ok(format!("{} {}", self.expr(left)?, null))
+                } else if matches!(left, Expr::Literal(Value::Null)) {
+                    Ok(format!("{} {}", self.expr(right)?, null))
+                } else {
+                    let sql_op = if op == BinaryOp::Eq { "=" } else { ne };
+                    self.comparison(sql_op, left, right)
+                }
+            }
+            BinaryOp::Lt => self.comparison("<", left, right),
+            BinaryOp::Le => self.comparison("<=", left, right),
+            BinaryOp::Gt => self.comparison(">", left, right),
+            BinaryOp::Ge => self.comparison(">=", left, right),
+            BinaryOp::And => Ok(format!("({} AND {})", self.expr(left)?, self.expr(right)?)),
+            BinaryOp::Or => Ok(format!("({} OR {})", self.expr(left)?, self.expr(right)?)),
+            BinaryOp::In => self.comparison("IN", left, right),
+            BinaryOp::NotIn => self.comparison("NOT IN", left, right),
+            BinaryOp::Contains => {
+                if let Expr::Literal(Value::String(s)) = right {
+                    let pattern = format!("%{}%", self.escape_like(s));
+                    self.like(left, &pattern)
+                } else {
+                    Err(OrdoError::parse_error(
+                        "SQL LIKE requires a string literal for 'contains'",
+                    ))
+                }
+            }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                Err(OrdoError::parse_error(format!(
+                    "Arithmetic operator {:?} is not supported in SQL filter generation",
+                    op
+                )))
+            }
+        }
+    }
+
+    fn comparison(&mut self, op: &str, left: &Expr, right: &Expr) -> Result<String> {
+        let l = self.operand(left, right)?;
+        let r = self.operand(right, left)?;
+        Ok(format!("{} {} {}", l, op, r))
+    }
+
+    /// `field LIKE pattern ESCAPE '!'`; `pattern` is already escaped for LIKE
+    fn like(&mut self, field: &Expr, pattern: &str) -> Result<String> {
+        let field = self.expr(field)?;
+        let pattern = self.value(&Value::string(pattern))?;
+        Ok(format!("{} LIKE {} ESCAPE '!'", field, pattern))
+    }
+
+    fn unary(&mut self, op: UnaryOp, operand: &Expr) -> Result<String> {
+        match op {
+            UnaryOp::Not => {
+                // Special case: NOT(is_null(field)) → field IS NOT NULL
+                if let Expr::Call { name, args } = operand {
+                    if name == "is_null" && args.len() == 1 {
+                        return Ok(format!("{} IS NOT NULL", self.expr(&args[0])?));
+                    }
+                }
+                Ok(format!("NOT ({})", self.expr(operand)?))
+            }
+            UnaryOp::Neg => Err(OrdoError::parse_error(
+                "Unary negation is not supported in SQL filter generation",
+            )),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<String> {
+        if let Some(template) = self.options.functions.get(name) {
+            return self.template(name, template, args);
+        }
+
+        let dialect = self.dialect();
+        match (name, args) {
+            ("is_null", [field]) => Ok(format!("{} IS NULL", self.expr(field)?)),
+            ("starts_with", [field, Expr::Literal(Value::String(s))]) => {
+                let pattern = format!("{}%", self.escape_like(s));
+                self.like(field, &pattern)
+            }
+            ("ends_with", [field, Expr::Literal(Value::String(s))]) => {
+                let pattern = format!("%{}", self.escape_like(s));
+                self.like(field, &pattern)
+            }
+            ("contains_str", [field, Expr::Literal(Value::String(s))]) => {
+                let pattern = format!("%{}%", self.escape_like(s));
+                self.like(field, &pattern)
+            }
+            ("lower", [arg]) => Ok(format!("LOWER({})", self.expr(arg)?)),
+            ("upper", [arg]) => Ok(format!("UPPER({})", self.expr(arg)?)),
+            ("trim", [arg]) => Ok(format!("TRIM({})", self.expr(arg)?)),
+            ("len", [arg]) => match dialect {
+                SqlDialect::SqlServer => Ok(format!("LEN({})", self.expr(arg)?)),
+                _ => Ok(format!("LENGTH({})", self.expr(arg)?)),
+            },
+            // regex_match(pattern, text)
+            ("regex_match", [pattern, text]) => {
+                let op = match dialect {
+                    SqlDialect::Postgres => "~",
+                    SqlDialect::MySql | SqlDialect::Sqlite => "REGEXP",
+                    _ => return self.untranslatable(name),
+                };
+                let text = self.expr(text)?;
+                Ok(format!("{} {} {}", text, op, self.expr(pattern)?))
+            }
+            ("now", []) => match self.now(false) {
+                Some(sql) => Ok(sql.to_string()),
+                None => self.untranslatable(name),
+            },
+            ("now_millis", []) => match self.now(true) {
+                Some(sql) => Ok(sql.to_string()),
+                None => self.untranslatable(name),
+            },
+            ("date_add", [ts, amount, unit]) => {
+                let Some(seconds) = date_offset(amount, unit) else {
+                    return self.untranslatable(name);
+                };
+                let ts = self.number(ts)?;
+                let op = if seconds < 0 { "-" } else { "+" };
+                let seconds = self.value(&Value::Int(seconds.abs()))?;
+                Ok(format!("({} {} {})", ts, op, seconds))
+            }
+            ("time_diff", [t1, t2, unit]) => {
+                // Generic SQL has no portable truncation of non-integer columns
+                let (Some(seconds), false) = (unit_seconds(unit), dialect == SqlDialect::Generic)
+                else {
+                    return self.untranslatable(name);
+                };
+                let diff = format!("({} - {})", self.integer(t1)?, self.integer(t2)?);
+                // The rules divide integers, truncating toward zero
+                Ok(match (seconds, dialect) {
+                    (1, _) => diff,
+                    (_, SqlDialect::Postgres) => format!("TRUNC({} / {})", diff, seconds),
+                    (_, SqlDialect::MySql) => format!("({} DIV {})", diff, seconds),
+                    _ => format!("({} / {})", diff, seconds),
+                })
+            }
+            _ => self.untranslatable(name),
+        }
+    }
+
+    /// Record a call without translation
+    fn untranslatable(&mut self, name: &str) -> Result<String> {
+        self.untranslated.get_or_insert_with(|| name.to_string());
+        Err(OrdoError::parse_error(format!(
+            "Function '{}' is not supported in SQL filter generation",
+            name
+        )))
+    }
+
+    /// Render a custom translation, arguments in the order they appear
+    fn template(&mut self, name: &str, template: &str, args: &[Expr]) -> Result<String> {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').map(|e| start + e);
+            let index = end.and_then(|end| rest[start + 1..end].parse::<usize>().ok());
+            let (Some(end), Some(index)) = (end, index) else {
+                out.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+                continue;
+            };
+            let arg = args.get(index).ok_or_else(|| {
+                OrdoError::parse_error(format!(
+                    "Translation of '{}' uses argument {{{}}}, but the call has {}",
+                    name,
+                    index,
+                    args.len()
                 ))
+            })?;
+            out.push_str(&rest[..start]);
+            out.push_str(&self.expr(arg)?);
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Current Unix time in seconds, or milliseconds
+    fn now(&self, millis: bool) -> Option<&'static str> {
+        Some(match (self.dialect(), millis) {
+            (SqlDialect::Generic, _) => return None,
+            (SqlDialect::Postgres, false) => {
+                "CAST(FLOOR(EXTRACT(EPOCH FROM CURRENT_TIMESTAMP)) AS BIGINT)"
+            }
+            (SqlDialect::Postgres, true) => {
+                "CAST(FLOOR(EXTRACT(EPOCH FROM CURRENT_TIMESTAMP) * 1000) AS BIGINT)"
+            }
+            (SqlDialect::MySql, false) => "UNIX_TIMESTAMP()",
+            (SqlDialect::MySql, true) => "CAST(UNIX_TIMESTAMP(NOW(3)) * 1000 AS SIGNED)",
+            (SqlDialect::Sqlite, false) => "CAST(strftime('%s', 'now') AS INTEGER)",
+            (SqlDialect::Sqlite, true) => {
+                "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)"
+            }
+            (SqlDialect::SqlServer, false) => {
+                "DATEDIFF_BIG(SECOND, '1970-01-01', SYSUTCDATETIME())"
+            }
+            (SqlDialect::SqlServer, true) => {
+                "DATEDIFF_BIG(MILLISECOND, '1970-01-01', SYSUTCDATETIME())"
+            }
+        })
+    }
+
+    /// Operand of arithmetic, cast when read from JSON
+    fn number(&mut self, expr: &Expr) -> Result<String> {
+        match expr {
+            Expr::Field(path) => {
+                let column = self.column(path)?;
+                Ok(match column.json {
+                    true => self.cast_json(column.sql, JsonCast::Number),
+                    false => column.sql,
+                })
+            }
+            _ => self.expr(expr),
+        }
+    }
+
+    /// A timestamp operand truncated toward zero, as the rules read it
+    ///
+    /// JSON numbers and non-integer columns may have a fraction; integer
+    /// literals and the current time do not.
+    fn integer(&mut self, expr: &Expr) -> Result<String> {
+        let sql = self.number(expr)?;
+        let exact = match expr {
+            Expr::Literal(Value::Int(_)) => true,
+            Expr::Call { name, args } => {
+                args.is_empty() && matches!(name.as_str(), "now" | "now_millis")
+            }
+            _ => false,
+        };
+        if exact {
+            return Ok(sql);
+        }
+        Ok(match self.dialect() {
+            SqlDialect::Postgres => format!("TRUNC({})", sql),
+            SqlDialect::MySql => format!("TRUNCATE({}, 0)", sql),
+            SqlDialect::SqlServer => format!("CAST({} AS BIGINT)", sql),
+            SqlDialect::Sqlite => format!("CAST({} AS INTEGER)", sql),
+            SqlDialect::Generic => sql,
+        })
+    }
+
+    fn list(&mut self, exprs: &[Expr]) -> Result<String> {
+        let mut parts = Vec::with_capacity(exprs.len());
+        for expr in exprs {
+            parts.push(self.expr(expr)?);
+        }
+        Ok(parts.join(", "))
+    }
+
+    // ==================== Columns ====================
+
+    /// Column of the longest mapped prefix of `path` and the remaining keys
+    fn resolve<'p>(&self, path: &'p str) -> Option<(&str, Vec<&'p str>)> {
+        if let Some(column) = self.mapping.get(path) {
+            return Some((column, Vec::new()));
+        }
+        // Generic SQL has no JSON access: only exact mappings apply
+        if self.dialect() == SqlDialect::Generic {
+            return None;
+        }
+        let mut prefix = path;
+        while let Some((head, _)) = prefix.rsplit_once('.') {
+            if let Some(column) = self.mapping.get(head) {
+                let rest = path[head.len() + 1..].split('.').collect();
+                return Some((column, rest));
+            }
+            prefix = head;
+        }
+        None
+    }
+
+    fn column(&self, path: &str) -> Result<Operand> {
+        let Some((column, rest)) = self.resolve(path) else {
+            if self.options.strict {
+                return Err(OrdoError::parse_error(format!(
+                    "Field '{}' has no column mapping",
+                    path
+                )));
+            }
+            return Ok(Operand {
+                sql: self.identifier(&path.replace('.', "_")),
+                json: false,
+            });
+        };
+
+        let column = self.qualified_identifier(column);
+        if rest.is_empty() {
+            return Ok(Operand {
+                sql: column,
+                json: false,
+            });
+        }
+
+        let sql = match self.dialect() {
+            SqlDialect::Postgres => {
+                let (last, init) = rest.split_last().expect("non-empty path");
+                let mut sql = column;
+                for key in init {
+                    sql.push_str(&format!("->{}", self.string_literal(key)));
+                }
+                sql.push_str(&format!("->>{}", self.string_literal(last)));
+                sql
+            }
+            SqlDialect::MySql => format!("{}->>{}", column, self.json_path(&rest)?),
+            SqlDialect::Sqlite => format!("json_extract({}, {})", column, self.json_path(&rest)?),
+            SqlDialect::SqlServer => format!("JSON_VALUE({}, {})", column, self.json_path(&rest)?),
+            SqlDialect::Generic => unreachable!("generic SQL resolves exact mappings only"),
+        };
+        Ok(Operand { sql, json: true })
+    }
+
+    /// `'$.a.b'` path literal (keys that are not plain identifiers are quoted)
+    fn json_path(&self, keys: &[&str]) -> Result<String> {
+        let mut path = String::from("$");
+        for key in keys {
+            if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                path.push('.');
+                path.push_str(key);
+            } else if key.contains(['"', '\\']) {
+                return Err(OrdoError::parse_error(format!(
+                    "Key '{}' cannot be used in a JSON path",
+                    key
+                )));
             } else {
-                Err(OrdoError::parse_error(
-                    "SQL LIKE requires a string literal for 'contains'",
-                ))
+                path.push_str(&format!(".\"{}\"", key));
             }
         }
-        BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
-            Err(OrdoError::parse_error(format!(
-                "Arithmetic operator {:?} is not supported in SQL filter generation",
-                op
-            )))
+        Ok(self.string_literal(&path))
+    }
+
+    /// Quote a mapped column; dots separate qualifiers (`table.column`)
+    fn qualified_identifier(&self, name: &str) -> String {
+        match self.dialect() {
+            SqlDialect::Generic => name.to_string(),
+            _ => name
+                .split('.')
+                .map(|part| self.identifier(part))
+                .collect::<Vec<_>>()
+                .join("."),
         }
     }
-}
 
-fn unary_to_sql(op: UnaryOp, operand: &Expr, mapping: &HashMap<String, String>) -> Result<String> {
-    match op {
-        UnaryOp::Not => {
-            // Special case: NOT(is_null(field)) → field IS NOT NULL
-            if let Expr::Call { name, args } = operand {
-                if name == "is_null" && args.len() == 1 {
-                    return Ok(format!("{} IS NOT NULL", expr_to_sql(&args[0], mapping)?));
+    fn identifier(&self, name: &str) -> String {
+        match self.dialect() {
+            SqlDialect::Generic => name.to_string(),
+            SqlDialect::Postgres | SqlDialect::Sqlite => {
+                format!("\"{}\"", name.replace('"', "\"\""))
+            }
+            SqlDialect::MySql => format!("`{}`", name.replace('`', "``")),
+            SqlDialect::SqlServer => format!("[{}]", name.replace(']', "]]")),
+        }
+    }
+
+    // ==================== Literals ====================
+
+    /// A value, inlined or as a placeholder
+    fn value(&mut self, val: &Value) -> Result<String> {
+        match val {
+            // NULL is never parameterized (comparisons with it become IS NULL)
+            Value::Null => Ok("NULL".to_string()),
+            Value::Array(items) => {
+                let mut parts = Vec::with_capacity(items.len());
+                for item in items {
+                    parts.push(self.value(item)?);
                 }
+                Ok(format!("({})", parts.join(", ")))
             }
-            Ok(format!("NOT ({})", expr_to_sql(operand, mapping)?))
+            _ if self.options.parameterized => {
+                if let Value::Object(_) = val {
+                    return Err(OrdoError::parse_error(
+                        "Object literals are not supported in SQL filter generation",
+                    ));
+                }
+                self.params.push(val.clone());
+                Ok(match self.dialect() {
+                    SqlDialect::Postgres => format!("${}", self.params.len()),
+                    SqlDialect::SqlServer => format!("@p{}", self.params.len()),
+                    _ => "?".to_string(),
+                })
+            }
+            Value::Bool(b) => Ok(match self.dialect() {
+                SqlDialect::SqlServer => if *b { "1" } else { "0" }.to_string(),
+                _ => if *b { "TRUE" } else { "FALSE" }.to_string(),
+            }),
+            Value::Int(n) => Ok(n.to_string()),
+            Value::Float(f) if f.is_finite() => Ok(f.to_string()),
+            Value::Float(f) => Err(OrdoError::parse_error(format!(
+                "Cannot convert {} to SQL",
+                f
+            ))),
+            Value::String(s) => Ok(self.string_literal(s)),
+            Value::Object(_) => match self.dialect() {
+                SqlDialect::Generic => Ok("NULL".to_string()),
+                _ => Err(OrdoError::parse_error(
+                    "Object literals are not supported in SQL filter generation",
+                )),
+            },
         }
-        UnaryOp::Neg => Err(OrdoError::parse_error(
-            "Unary negation is not supported in SQL filter generation",
-        )),
     }
-}
 
-fn call_to_sql(name: &str, args: &[Expr], mapping: &HashMap<String, String>) -> Result<String> {
-    match (name, args) {
-        ("is_null", [field]) => Ok(format!("{} IS NULL", expr_to_sql(field, mapping)?)),
-        ("starts_with", [field, Expr::Literal(Value::String(s))]) => Ok(format!(
-            "{} LIKE '{}%' ESCAPE '!'",
-            expr_to_sql(field, mapping)?,
-            escape_like_pattern(s)
-        )),
-        ("ends_with", [field, Expr::Literal(Value::String(s))]) => Ok(format!(
-            "{} LIKE '%{}' ESCAPE '!'",
-            expr_to_sql(field, mapping)?,
-            escape_like_pattern(s)
-        )),
-        _ => Err(OrdoError::parse_error(format!(
-            "Function '{}' is not supported in SQL filter generation",
-            name
-        ))),
+    fn string_literal(&self, s: &str) -> String {
+        match self.dialect() {
+            // Backslashes are escapes unless NO_BACKSLASH_ESCAPES is set
+            SqlDialect::MySql => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "''")),
+            SqlDialect::SqlServer => format!("N'{}'", s.replace('\'', "''")),
+            _ => format!("'{}'", s.replace('\'', "''")),
+        }
     }
-}
 
-fn value_to_sql(val: &Value) -> String {
-    match val {
-        Value::Null => "NULL".to_string(),
-        Value::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
-        Value::Int(n) => n.to_string(),
-        Value::Float(f) => f.to_string(),
-        Value::String(s) => format!("'{}'", escape_sql(s)),
-        Value::Array(arr) => {
-            let parts: Vec<String> = arr.iter().map(value_to_sql).collect();
-            format!("({})", parts.join(", "))
+    /// Escape a string for use inside a SQL LIKE pattern with `ESCAPE '!'`.
+    ///
+    /// Escapes:
+    /// - `!` → `!!`  (the escape character itself)
+    /// - `%` → `!%`  (SQL wildcard: any sequence of characters)
+    /// - `_` → `!_`  (SQL wildcard: any single character)
+    /// - `[` → `![`  (SQL Server only: character class)
+    ///
+    /// String delimiters are escaped when the pattern is rendered as a literal.
+    fn escape_like(&self, s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '!' => out.push_str("!!"),
+                '%' => out.push_str("!%"),
+                '_' => out.push_str("!_"),
+                '[' if self.dialect() == SqlDialect::SqlServer => out.push_str("!["),
+                other => out.push(other),
+            }
         }
-        _ => "NULL".to_string(),
+        out
     }
 }
 
-fn escape_sql(s: &str) -> String {
-    s.replace('\'', "''")
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
 
-/// Escape a string for use inside a SQL LIKE pattern with `ESCAPE '!'`.
-///
-/// Escapes:
-/// - `!` → `!!`  (the escape character itself)
-/// - `%` → `!%`  (SQL wildcard: any sequence of characters)
-/// - `_` → `!_`  (SQL wildcard: any single character)
-/// - `'` → `''`  (SQL string delimiter)
-fn escape_like_pattern(s: &str) -> String {
-    let mut out = String::with_capacity(s.len());
-    for c in s.chars() {
-        match c {
-            '!' => out.push_str("!!"),
-            '%' => out.push_str("!%"),
-            '_' => out.push_str("!_"),
-            '\'' => out.push_str("''"),
-            other => out.push(other),
-        }
-    }
-    out
+    fn paths(conditions: &[&str]) -> Vec<FilterPath> {
+        vec![FilterPath {
+            conditions: conditions
+                .iter()
+                .map(|c| ExprParser::parse(c).unwrap())
+                .collect(),
+            result_code: "OK".to_string(),
+        }]
+    }
+
+    fn mapping() -> HashMap<String, String> {
+        HashMap::from([
+            ("resource.owner".to_string(), "owner_id".to_string()),
+            ("resource.attrs".to_string(), "r.attrs".to_string()),
+        ])
+    }
+
+    fn render(conditions: &[&str], dialect: SqlDialect, parameterized: bool) -> SqlClause {
+        let options = SqlOptions {
+            dialect,
+            parameterized,
+            ..Default::default()
+        };
+        to_sql_with(&paths(conditions), &mapping(), &options).unwrap()
+    }
+
+    #[test]
+    fn test_generic_is_unchanged() {
+        let sql = to_sql(
+            &paths(&[
+                r#"resource.owner == "o'neil""#,
+                "resource.size != 3",
+                r#"starts_with(resource.name, "a_b")"#,
+            ]),
+            &mapping(),
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "owner_id = 'o''neil' AND resource_size != 3 AND resource_name LIKE 'a!_b%' ESCAPE '!'"
+        );
+    }
+
+    #[test]
+    fn test_dialect_quoting() {
+        let conditions = [r#"resource.owner == "o'neil""#, "resource.active == true"];
+        assert_eq!(
+            render(&conditions, SqlDialect::Postgres, false).sql,
+            r#""owner_id" = 'o''neil' AND "resource_active" = TRUE"#
+        );
+        assert_eq!(
+            render(&conditions, SqlDialect::MySql, false).sql,
+            "`owner_id` = 'o''neil' AND `resource_active` = TRUE"
+        );
+        assert_eq!(
+            render(&conditions, SqlDialect::SqlServer, false).sql,
+            "[owner_id] = N'o''neil' AND [resource_active] = 1"
+        );
+        assert_eq!(
+            render(&[r#"resource.owner == "a\\b""#], SqlDialect::MySql, false).sql,
+            r"`owner_id` = 'a\\b'"
+        );
+    }
+
+    #[test]
+    fn test_parameterized() {
+        let conditions = [
+            r#"resource.owner == "alice""#,
+            r#"resource.kind in ["a", "b"]"#,
+            r#"resource.name contains "50%""#,
+            "resource.deleted == null",
+        ];
+        let clause = render(&conditions, SqlDialect::Postgres, true);
+        assert_eq!(
+            clause.sql,
+            r#""owner_id" = $1 AND "resource_kind" IN ($2, $3) AND "resource_name" LIKE $4 ESCAPE '!' AND "resource_deleted" IS NULL"#
+        );
+        assert_eq!(
+            clause.params,
+            vec![
+                Value::string("alice"),
+                Value::string("a"),
+                Value::string("b"),
+                Value::string("%50!%%"),
+            ]
+        );
+        assert_eq!(
+            render(&conditions[..2], SqlDialect::SqlServer, true).sql,
+            "[owner_id] = @p1 AND [resource_kind] IN (@p2, @p3)"
+        );
+        assert_eq!(
+            render(&conditions[..1], SqlDialect::Sqlite, true).sql,
+            r#""owner_id" = ?"#
+        );
+    }
+
+    #[test]
+    fn test_json_column_access() {
+        let conditions = [
+            r#"resource.attrs.color == "red""#,
+            "resource.attrs.size.width > 10",
+            "resource.attrs.public == true",
+        ];
+        assert_eq!(
+            render(&conditions, SqlDialect::Postgres, true).sql,
+            r#""r"."attrs"->>'color' = $1 AND ("r"."attrs"->'size'->>'width')::numeric > $2 AND ("r"."attrs"->>'public')::boolean = $3"#
+        );
+        let clause = render(&conditions, SqlDialect::MySql, true);
+        assert_eq!(
+            clause.sql,
+            "`r`.`attrs`->>'$.color' = ? AND CAST(`r`.`attrs`->>'$.size.width' AS DECIMAL(65, 30)) > ? AND `r`.`attrs`->>'$.public' = ?"
+        );
+        assert_eq!(clause.params[2], Value::string("true"));
+        assert_eq!(
+            render(&conditions[1..2], SqlDialect::Sqlite, false).sql,
+            r#"json_extract("r"."attrs", '$.size.width') > 10"#
+        );
+        assert_eq!(
+            render(&conditions[1..2], SqlDialect::SqlServer, false).sql,
+            "CAST(JSON_VALUE([r].[attrs], N'$.size.width') AS FLOAT) > 10"
+        );
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let options = SqlOptions {
+            dialect: SqlDialect::Postgres,
+            parameterized: true,
+            strict: true,
+            ..Default::default()
+        };
+        assert!(to_sql_with(&paths(&["resource.owner == 1"]), &mapping(), &options).is_ok());
+        let err = to_sql_with(&paths(&["resource.size > 1"]), &mapping(), &options).unwrap_err();
+        assert!(err.to_string().contains("resource.size"), "{}", err);
+    }
+
+    #[test]
+    fn test_constants() {
+        let options = SqlOptions {
+            dialect: SqlDialect::SqlServer,
+            ..Default::default()
+        };
+        assert_eq!(to_sql_with(&[], &mapping(), &options).unwrap().sql, "1 = 0");
+        let always = vec![FilterPath {
+            conditions: vec![],
+            result_code: "OK".to_string(),
+        }];
+        assert_eq!(
+            to_sql_with(&always, &mapping(), &options).unwrap().sql,
+            "1 = 1"
+        );
+    }
+
+    #[test]
+    fn test_function_translations() {
+        let conditions = [
+            r#"lower(resource.owner) == "alice""#,
+            "len(resource.name) > 3",
+            r#"coalesce(resource.region, "eu") == "us""#,
+            r#"regex_match("^a+$", resource.code)"#,
+        ];
+        assert_eq!(
+            render(&conditions, SqlDialect::Postgres, true).sql,
+            r#"LOWER("owner_id") = $1 AND LENGTH("resource_name") > $2 AND COALESCE("resource_region", $3) = $4 AND "resource_code" ~ $5"#
+        );
+        assert_eq!(
+            render(&conditions, SqlDialect::MySql, false).sql,
+            "LOWER(`owner_id`) = 'alice' AND LENGTH(`resource_name`) > 3 AND COALESCE(`resource_region`, 'eu') = 'us' AND `resource_code` REGEXP '^a+$'"
+        );
+        assert_eq!(
+            render(&["len(resource.name) > 3"], SqlDialect::SqlServer, false).sql,
+            "LEN([resource_name]) > 3"
+        );
+
+        // No regular expressions in generic SQL or SQL Server
+        let options = SqlOptions::default();
+        let regex = ExprParser::parse(r#"regex_match("^a", resource.code)"#).unwrap();
+        assert_eq!(
+            untranslated(&regex, &mapping(), &options),
+            Some("regex_match".to_string())
+        );
+        assert_eq!(
+            untranslated(
+                &ExprParser::parse("lower(a) == \"b\"").unwrap(),
+                &mapping(),
+                &options
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_time_translations() {
+        let conditions = [
+            r#"resource.created > date_add(now(), -7, "d")"#,
+            r#"time_diff(now(), resource.attrs.seen, "h") < 2"#,
+        ];
+        assert_eq!(
+            render(&conditions, SqlDialect::Postgres, false).sql,
+            r#""resource_created" > (CAST(FLOOR(EXTRACT(EPOCH FROM CURRENT_TIMESTAMP)) AS BIGINT) - 604800) AND TRUNC((CAST(FLOOR(EXTRACT(EPOCH FROM CURRENT_TIMESTAMP)) AS BIGINT) - TRUNC(("r"."attrs"->>'seen')::numeric)) / 3600) < 2"#
+        );
+        assert_eq!(
+            render(&conditions, SqlDialect::MySql, false).sql,
+            "`resource_created` > (UNIX_TIMESTAMP() - 604800) AND ((UNIX_TIMESTAMP() - TRUNCATE(CAST(`r`.`attrs`->>'$.seen' AS DECIMAL(65, 30)), 0)) DIV 3600) < 2"
+        );
+        assert_eq!(
+            render(
+                &["resource.expires < now_millis()"],
+                SqlDialect::SqlServer,
+                false
+            )
+            .sql,
+            "[resource_expires] < DATEDIFF_BIG(MILLISECOND, '1970-01-01', SYSUTCDATETIME())"
+        );
+
+        // Generic SQL has no portable Unix time
+        let now = ExprParser::parse("resource.created < now()").unwrap();
+        assert_eq!(
+            untranslated(&now, &mapping(), &SqlOptions::default()),
+            Some("now".to_string())
+        );
+    }
+
+    #[test]
+    fn test_time_diff_truncates_like_the_rules() {
+        // JSON numbers may have a fraction: operands and quotient are
+        // truncated, so `== 1` holds for the same rows as `time_diff`
+        let conditions = [r#"time_diff(resource.attrs.checked, resource.attrs.seen, "h") == 1"#];
+        let cases = [
+            (
+                SqlDialect::Postgres,
+                r#"TRUNC((TRUNC(("r"."attrs"->>'checked')::numeric) - TRUNC(("r"."attrs"->>'seen')::numeric)) / 3600) = 1"#,
+            ),
+            (
+                SqlDialect::MySql,
+                "((TRUNCATE(CAST(`r`.`attrs`->>'$.checked' AS DECIMAL(65, 30)), 0) - TRUNCATE(CAST(`r`.`attrs`->>'$.seen' AS DECIMAL(65, 30)), 0)) DIV 3600) = 1",
+            ),
+            (
+                SqlDialect::Sqlite,
+                r#"((CAST(json_extract("r"."attrs", '$.checked') AS INTEGER) - CAST(json_extract("r"."attrs", '$.seen') AS INTEGER)) / 3600) = 1"#,
+            ),
+            (
+                SqlDialect::SqlServer,
+                "((CAST(CAST(JSON_VALUE([r].[attrs], N'$.checked') AS FLOAT) AS BIGINT) - CAST(CAST(JSON_VALUE([r].[attrs], N'$.seen') AS FLOAT) AS BIGINT)) / 3600) = 1",
+            ),
+        ];
+        for (dialect, expected) in cases {
+            assert_eq!(render(&conditions, dialect, false).sql, expected);
+        }
+
+        let diff = ExprParser::parse(conditions[0]).unwrap();
+        assert_eq!(
+            untranslated(&diff, &mapping(), &SqlOptions::default()),
+            Some("time_diff".to_string())
+        );
+    }
+
+    #[test]
+    fn test_custom_translations() {
+        let options = SqlOptions {
+            dialect: SqlDialect::Postgres,
+            parameterized: true,
+            ..Default::default()
+        }
+        .with_function("similarity", "similarity({1}, {0})")
+        .with_function("lower", "lower(unaccent({0}))");
+        let clause = to_sql_with(
+            &paths(&[
+                r#"similarity("alice", resource.owner) > 0.5"#,
+                r#"lower(resource.city) == "zurich""#,
+            ]),
+            &mapping(),
+            &options,
+        )
+        .unwrap();
+        // Placeholders are numbered in the order the arguments appear
+        assert_eq!(
+            clause.sql,
+            r#"similarity("owner_id", $1) > $2 AND lower(unaccent("resource_city")) = $3"#
+        );
+        assert_eq!(
+            clause.params,
+            vec![
+                Value::string("alice"),
+                Value::Float(0.5),
+                Value::string("zurich")
+            ]
+        );
+
+        let options = SqlOptions::default().with_function("f", "f({1})");
+        let err = to_sql_with(&paths(&["f(a) == 1"]), &mapping(), &options).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("uses argument {1}, but the call has 1"));
+    }
 }
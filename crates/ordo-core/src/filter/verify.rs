@@ -0,0 +1,803 @@
+//! Differential verification of generated SQL filters
+//!
+//! Checks a compiled filter against rule execution on sample rows. The rows
+//! are loaded into an in-memory SQLite table and selected with the filter
+//! (rendered for the SQLite dialect); each row is also executed by
+//! [`RuleExecutor`] with the known input merged in. Rows where the two
+//! disagree are reported together with the leaf predicates whose SQL value
+//! differs from the rule engine's, e.g. `owner != 'alice'`, which is NULL in
+//! SQL but true for the rule engine when `owner` is null.
+//!
+//! Rows come from a dataset, or are generated: for every field the filter
+//! reads, the literals it is compared with, their numeric neighbours, a few
+//! values of the field's type, null and a missing field are combined.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+
+use super::partial_eval::PartialEvaluator;
+use super::path_collector::{collect_paths, FilterPath};
+use super::pushdown::{push_down, PushDown};
+use super::simplify::simplify;
+use super::sql::{self, SqlDialect, SqlOptions};
+use super::{collect_fields, FilterRequest};
+use crate::context::{Context, Value};
+use crate::error::{OrdoError, Result};
+use crate::expr::regex_cache;
+use crate::expr::{BinaryOp, Evaluator, Expr, UnaryOp};
+use crate::rule::{FieldMissingBehavior, RuleExecutor, RuleSet, RuleSetResolver};
+
+/// Table the sample rows are loaded into
+const TABLE: &str = "sample";
+
+/// Row number column of the sample table
+const ROW_COLUMN: &str = "__row";
+
+/// Where the rows to verify come from
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerifyOptions {
+    /// Rows to check, shaped like rule input. Known fields may be omitted.
+    #[serde(default)]
+    pub rows: Vec<Value>,
+
+    /// Types of the fields to generate rows for, when `rows` is empty.
+    /// Other fields are typed after the literals they are compared with.
+    #[serde(default)]
+    pub schema: HashMap<String, SampleType>,
+
+    /// Maximum number of generated rows (default: 1000). All combinations
+    /// are generated when they fit, otherwise a deterministic sample.
+    #[serde(default = "default_max_rows")]
+    pub max_rows: usize,
+}
+
+fn default_max_rows() -> usize {
+    1000
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            rows: Vec::new(),
+            schema: HashMap::new(),
+            max_rows: default_max_rows(),
+        }
+    }
+}
+
+/// Type of a generated field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleType {
+    String,
+    #[serde(alias = "integer")]
+    Int,
+    #[serde(alias = "number")]
+    Float,
+    #[serde(alias = "boolean")]
+    Bool,
+}
+
+/// Outcome of a verification run
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    /// The verified WHERE clause (SQLite dialect)
+    pub filter: String,
+
+    /// Placeholder values, when the request asked for parameterized SQL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<Value>>,
+
+    /// The path limit was hit, so the filter matches everything
+    pub truncated: bool,
+
+    /// Functions without a SQL translation whose conditions were left out
+    pub untranslated: Vec<String>,
+
+    /// Number of rows checked
+    pub rows_checked: usize,
+
+    /// Rows the rules send to a target result
+    pub rule_matches: usize,
+
+    /// Rows the filter selects
+    pub filter_matches: usize,
+
+    /// Every row on which the filter and the rules disagree
+    pub mismatches: Vec<RowMismatch>,
+}
+
+/// A row on which the filter and the rules disagree
+#[derive(Debug, Clone, Serialize)]
+pub struct RowMismatch {
+    /// Position of the row in the checked rows
+    pub index: usize,
+
+    /// The row, without the known input
+    pub row: Value,
+
+    /// Result code of rule execution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_code: Option<String>,
+
+    /// Rule execution error, counted as no match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    pub rule_matches: bool,
+    pub filter_matches: bool,
+
+    /// Leaf predicates of the filter whose SQL value differs from the rule
+    /// engine's on this row. Empty when every leaf agrees and the difference
+    /// comes from how they are combined.
+    pub predicates: Vec<PredicateMismatch>,
+}
+
+/// A leaf predicate evaluated differently by SQLite and the rule engine
+#[derive(Debug, Clone, Serialize)]
+pub struct PredicateMismatch {
+    /// The predicate as rendered in the filter
+    pub sql: String,
+
+    /// Value in SQLite; `None` is SQL NULL (unknown)
+    pub sql_value: Option<bool>,
+
+    /// Value for the rule engine; `None` when evaluation failed
+    pub rule_value: Option<bool>,
+}
+
+/// Compare the SQL filter for `request` with rule execution
+///
+/// The request's format and dialect are ignored: the filter is rendered for
+/// SQLite, with one column per field it reads (named after its mapping when
+/// that is a plain column name).
+pub fn verify(
+    ruleset: &RuleSet,
+    request: &FilterRequest,
+    options: &VerifyOptions,
+    resolver: Option<Arc<dyn RuleSetResolver>>,
+) -> Result<VerifyReport> {
+    let mut evaluator = PartialEvaluator::new(request.known_input.clone());
+    let (paths, truncated) = collect_paths(
+        ruleset,
+        &mut evaluator,
+        &request.target_results,
+        request.max_paths,
+        resolver.as_deref(),
+    )?;
+    let sql_options = SqlOptions {
+        dialect: SqlDialect::Sqlite,
+        parameterized: request.sql.parameterized,
+        strict: false,
+        functions: request.sql.functions.clone(),
+    };
+    let (paths, untranslated) = if truncated {
+        let always = FilterPath {
+            conditions: Vec::new(),
+            result_code: String::new(),
+        };
+        (vec![always], Vec::new())
+    } else {
+        let PushDown {
+            paths,
+            untranslated,
+        } = push_down(simplify(paths), "SQL", |expr| {
+            sql::untranslated(expr, &request.field_mapping, &sql_options)
+        })?;
+        (paths, untranslated)
+    };
+
+    // Leaf predicates and the fields they read
+    let mut leaves = Vec::new();
+    for path in &paths {
+        for cond in &path.conditions {
+            collect_leaves(cond, &mut leaves);
+        }
+    }
+    let mut fields = BTreeSet::new();
+    for leaf in &leaves {
+        collect_fields(leaf, &mut fields);
+    }
+    let fields: Vec<String> = fields.into_iter().collect();
+
+    let columns: Vec<String> = fields
+        .iter()
+        .map(|f| match request.field_mapping.get(f) {
+            Some(column) if !column.contains('.') => column.clone(),
+            _ => f.replace('.', "_"),
+        })
+        .collect();
+    let mapping: HashMap<String, String> = fields
+        .iter()
+        .cloned()
+        .zip(columns.iter().cloned())
+        .collect();
+    let clause = sql::to_sql_with(&paths, &mapping, &sql_options)?;
+
+    let rows = if options.rows.is_empty() {
+        generate_rows(&fields, &leaves, options)
+    } else {
+        options.rows.clone()
+    };
+
+    // Load the rows, with the known input merged in
+    let inputs: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let mut input = row.clone();
+            merge(&mut input, &request.known_input);
+            input
+        })
+        .collect();
+    let conn = load(&columns, &fields, &inputs)?;
+
+    let selected: BTreeSet<usize> = query(
+        &conn,
+        &format!("SELECT {} FROM {} WHERE {}", ROW_COLUMN, TABLE, clause.sql),
+        &clause.params,
+    )?
+    .into_iter()
+    .filter_map(|v| match v {
+        SqlValue::Integer(i) => Some(i as usize),
+        _ => None,
+    })
+    .collect();
+
+    let mut executor = RuleExecutor::new();
+    if let Some(resolver) = resolver {
+        executor.set_resolver(resolver);
+    }
+    let expr_evaluator = Evaluator::new();
+    let lenient = ruleset.config.field_missing == FieldMissingBehavior::Lenient;
+
+    let mut rule_matches = 0;
+    let mut mismatches = Vec::new();
+    for (index, input) in inputs.iter().enumerate() {
+        let (result_code, error) = match executor.execute(ruleset, input.clone()) {
+            Ok(result) => (Some(result.code), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let matches = result_code
+            .as_ref()
+            .is_some_and(|code| request.target_results.contains(code));
+        let filter_matches = selected.contains(&index);
+        if matches {
+            rule_matches += 1;
+        }
+        if matches == filter_matches {
+            continue;
+        }
+
+        let ctx = Context::new(input.clone());
+        let mut predicates = Vec::new();
+        for leaf in &leaves {
+            let rule_value = match expr_evaluator.eval(leaf, &ctx) {
+                Ok(value) => Some(value.is_truthy()),
+                Err(OrdoError::FieldNotFound { .. }) if lenient => Some(false),
+                Err(_) => None,
+            };
+            let leaf_clause = sql::to_sql_with(
+                &[FilterPath {
+                    conditions: vec![leaf.clone()],
+                    result_code: String::new(),
+                }],
+                &mapping,
+                &sql_options,
+            )?;
+            let mut params = leaf_clause.params;
+            params.push(Value::Int(index as i64));
+            let sql_value = query(
+                &conn,
+                &format!(
+                    "SELECT ({}) FROM {} WHERE {} = ?",
+                    leaf_clause.sql, TABLE, ROW_COLUMN
+                ),
+                &params,
+            )?
+            .into_iter()
+            .next()
+            .and_then(sql_truth);
+            if sql_value != rule_value {
+                predicates.push(PredicateMismatch {
+                    sql: leaf_clause.sql,
+                    sql_value,
+                    rule_value,
+                });
+            }
+        }
+
+        mismatches.push(RowMismatch {
+            index,
+            row: rows[index].clone(),
+            result_code,
+            error,
+            rule_matches: matches,
+            filter_matches,
+            predicates,
+        });
+    }
+
+    Ok(VerifyReport {
+        filter: clause.sql,
+        params: request.sql.parameterized.then_some(clause.params),
+        truncated,
+        untranslated,
+        rows_checked: rows.len(),
+        rule_matches,
+        filter_matches: selected.len(),
+        mismatches,
+    })
+}
+
+/// Comparisons and other non-boolean-operator nodes of a condition
+fn collect_leaves(expr: &Expr, out: &mut Vec<Expr>) {
+    match expr {
+        Expr::Binary {
+            op: BinaryOp::And | BinaryOp::Or,
+            left,
+            right,
+        } => {
+            collect_leaves(left, out);
+            collect_leaves(right, out);
+        }
+        Expr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } => collect_leaves(operand, out),
+        _ => {
+            if !out.contains(expr) {
+                out.push(expr.clone());
+            }
+        }
+    }
+}
+
+// ==================== Row generation ====================
+
+/// Rows combining candidate values of every field
+fn generate_rows(fields: &[String], leaves: &[Expr], options: &VerifyOptions) -> Vec<Value> {
+    let mut literals: HashMap<&str, Vec<Value>> = HashMap::new();
+    for leaf in leaves {
+        collect_literals(leaf, &mut literals);
+    }
+
+    // `None` leaves the field out of the row
+    let candidates: Vec<Vec<Option<Value>>> = fields
+        .iter()
+        .map(|field| {
+            let literals = literals.get(field.as_str()).map_or(&[][..], |v| v);
+            let sample_type = options
+                .schema
+                .get(field)
+                .copied()
+                .or_else(|| literals.iter().find_map(type_of))
+                .unwrap_or(SampleType::String);
+            candidates(literals, sample_type)
+        })
+        .collect();
+
+    let total = candidates
+        .iter()
+        .try_fold(1usize, |acc, c| acc.checked_mul(c.len()));
+    let row_count = total.map_or(options.max_rows, |t| t.min(options.max_rows));
+    let exhaustive = total.is_some_and(|t| t <= options.max_rows);
+
+    // xorshift64, seeded so that reports are reproducible
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..row_count)
+        .map(|i| {
+            let mut row = Value::object_optimized(Default::default());
+            let mut rest = i;
+            for (field, values) in fields.iter().zip(&candidates) {
+                let pick = if exhaustive {
+                    let pick = rest % values.len();
+                    rest /= values.len();
+                    pick
+                } else {
+                    (next() % values.len() as u64) as usize
+                };
+                if let Some(value) = &values[pick] {
+                    insert_path(&mut row, field, value.clone());
+                }
+            }
+            row
+        })
+        .collect()
+}
+
+/// Literals each field is compared with or passed alongside to a function
+fn collect_literals<'a>(expr: &'a Expr, out: &mut HashMap<&'a str, Vec<Value>>) {
+    let operands: Vec<&Expr> = match expr {
+        Expr::Binary { left, right, .. } => vec![left, right],
+        Expr::Call { args, .. } => args.iter().collect(),
+        _ => return,
+    };
+    let values: Vec<&Value> = operands
+        .iter()
+        .flat_map(|e| match e {
+            Expr::Literal(Value::Array(items)) => items.iter().collect(),
+            Expr::Literal(v) => vec![v],
+            Expr::Array(items) => items
+                .iter()
+                .filter_map(|e| match e {
+                    Expr::Literal(v) => Some(v),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        })
+        .collect();
+    for operand in &operands {
+        match operand {
+            Expr::Field(field) => out
+                .entry(field.as_str())
+                .or_default()
+                .extend(values.iter().map(|v| (*v).clone())),
+            nested => collect_literals(nested, out),
+        }
+    }
+}
+
+fn type_of(value: &Value) -> Option<SampleType> {
+    match value {
+        Value::String(_) => Some(SampleType::String),
+        Value::Int(_) => Some(SampleType::Int),
+        Value::Float(_) => Some(SampleType::Float),
+        Value::Bool(_) => Some(SampleType::Bool),
+        _ => None,
+    }
+}
+
+/// Values to try for a field: missing, null, the literals and their numeric
+/// neighbours, and a few values of its type
+fn candidates(literals: &[Value], sample_type: SampleType) -> Vec<Option<Value>> {
+    let mut values = vec![Value::Null];
+    for literal in literals {
+        values.push(literal.clone());
+        match literal {
+            Value::Int(i) => {
+                values.push(Value::Int(i.saturating_sub(1)));
+                values.push(Value::Int(i.saturating_add(1)));
+            }
+            Value::Float(f) => {
+                values.push(Value::Float(f - 0.5));
+                values.push(Value::Float(f + 0.5));
+            }
+            _ => {}
+        }
+    }
+    match sample_type {
+        SampleType::String => {
+            values.push(Value::string(""));
+            values.push(Value::string("~"));
+        }
+        SampleType::Int => values.push(Value::Int(0)),
+        SampleType::Float => values.push(Value::Float(0.0)),
+        SampleType::Bool => {
+            values.push(Value::Bool(true));
+            values.push(Value::Bool(false));
+        }
+    }
+
+    let mut unique: Vec<Option<Value>> = vec![None];
+    for value in values {
+        if !unique.iter().any(|u| u.as_ref() == Some(&value)) {
+            unique.push(Some(value));
+        }
+    }
+    unique
+}
+
+/// Set `path` in `target`, creating intermediate objects
+fn insert_path(target: &mut Value, path: &str, value: Value) {
+    let (head, rest) = match path.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (path, None),
+    };
+    let Value::Object(map) = target else {
+        return;
+    };
+    match rest {
+        None => {
+            map.insert(head.into(), value);
+        }
+        Some(rest) => {
+            let child = map
+                .entry(head.into())
+                .or_insert_with(|| Value::object_optimized(Default::default()));
+            insert_path(child, rest, value);
+        }
+    }
+}
+
+/// Deep-merge `overlay` into `base`; `overlay` wins on conflicts
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+// ==================== SQLite ====================
+
+/// In-memory table with one row per input
+fn load(columns: &[String], fields: &[String], inputs: &[Value]) -> Result<Connection> {
+    let conn = Connection::open_in_memory().map_err(sqlite_error)?;
+    // `text REGEXP pattern` (from `regex_match`) calls regexp(pattern, text)
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let (ValueRef::Text(pattern), ValueRef::Text(text)) = (ctx.get_raw(0), ctx.get_raw(1))
+            else {
+                return Ok(None);
+            };
+            let (Ok(pattern), Ok(text)) = (std::str::from_utf8(pattern), std::str::from_utf8(text))
+            else {
+                return Ok(None);
+            };
+            let re = regex_cache::get(pattern)
+                .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))?;
+            Ok(Some(re.is_match(text)))
+        },
+    )
+    .map_err(sqlite_error)?;
+    // Untyped columns store every value as given
+    let mut definitions = vec![format!("{} INTEGER PRIMARY KEY", ROW_COLUMN)];
+    definitions.extend(columns.iter().map(|c| quote(c)));
+    conn.execute(
+        &format!("CREATE TABLE {} ({})", TABLE, definitions.join(", ")),
+        [],
+    )
+    .map_err(sqlite_error)?;
+
+    let placeholders = vec!["?"; columns.len() + 1].join(", ");
+    let mut insert = conn
+        .prepare(&format!("INSERT INTO {} VALUES ({})", TABLE, placeholders))
+        .map_err(sqlite_error)?;
+    for (index, input) in inputs.iter().enumerate() {
+        let mut values = vec![SqlValue::Integer(index as i64)];
+        values.extend(
+            fields
+                .iter()
+                .map(|f| input.get_path(f).map_or(SqlValue::Null, to_sql)),
+        );
+        insert
+            .execute(params_from_iter(values))
+            .map_err(sqlite_error)?;
+    }
+    drop(insert);
+    Ok(conn)
+}
+
+/// First column of every row returned by `sql`
+fn query(conn: &Connection, sql: &str, params: &[Value]) -> Result<Vec<SqlValue>> {
+    let mut stmt = conn.prepare(sql).map_err(sqlite_error)?;
+    let rows = stmt
+        .query_map(params_from_iter(params.iter().map(to_sql)), |row| {
+            row.get::<_, SqlValue>(0)
+        })
+        .map_err(sqlite_error)?;
+    rows.collect::<std::result::Result<_, _>>()
+        .map_err(sqlite_error)
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Int(i) => SqlValue::Integer(*i),
+        Value::Float(f) => SqlValue::Real(*f),
+        Value::String(s) => SqlValue::Text(s.to_string()),
+        other => SqlValue::Text(serde_json::to_string(other).unwrap_or_default()),
+    }
+}
+
+/// Truth of an SQLite value in a WHERE clause; NULL is unknown
+fn sql_truth(value: SqlValue) -> Option<bool> {
+    match value {
+        SqlValue::Null => None,
+        SqlValue::Integer(i) => Some(i != 0),
+        SqlValue::Real(f) => Some(f != 0.0),
+        SqlValue::Text(s) => Some(s.trim().parse::<f64>().is_ok_and(|f| f != 0.0)),
+        SqlValue::Blob(_) => Some(false),
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn sqlite_error(e: rusqlite::Error) -> OrdoError {
+    OrdoError::eval_error(format!("SQLite could not run the filter: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterFormat;
+    use crate::rule::{Condition, Step, TerminalResult};
+
+    fn ruleset(conditions: &[&str]) -> RuleSet {
+        let mut builder = Step::decision("check", "Check");
+        for condition in conditions {
+            builder = builder.branch(Condition::from_string(*condition), "approved");
+        }
+        let mut rs = RuleSet::new("access", "check");
+        rs.add_step(builder.default("denied").build());
+        rs.add_step(Step::terminal(
+            "approved",
+            "Approved",
+            TerminalResult::new("APPROVED"),
+        ));
+        rs.add_step(Step::terminal(
+            "denied",
+            "Denied",
+            TerminalResult::new("DENIED"),
+        ));
+        rs
+    }
+
+    fn request(known: &str) -> FilterRequest {
+        FilterRequest {
+            known_input: serde_json::from_str(known).unwrap(),
+            target_results: vec!["APPROVED".to_string()],
+            format: FilterFormat::Sql,
+            field_mapping: HashMap::from([("resource.owner".to_string(), "owner_id".to_string())]),
+            max_paths: 100,
+            sql: SqlOptions::default(),
+            mongo: Default::default(),
+            elasticsearch: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_verify_generated_rows_agree() {
+        let rs = ruleset(&[
+            "user.role == \"admin\"",
+            "resource.owner == user.id",
+            "resource.visibility == \"public\"",
+        ]);
+        let report = verify(
+            &rs,
+            &request(r#"{"user": {"role": "viewer", "id": "alice"}}"#),
+            &VerifyOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            report.filter,
+            r#"("owner_id" = 'alice') OR ("resource_visibility" = 'public')"#
+        );
+        // missing, null, the literal and two strings for each of two fields
+        assert_eq!(report.rows_checked, 25);
+        assert_eq!(report.rule_matches, 9);
+        assert_eq!(report.filter_matches, 9);
+        assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+    }
+
+    #[test]
+    fn test_verify_reports_null_semantics() {
+        let rs = ruleset(&["resource.owner != user.id"]);
+        let report = verify(
+            &rs,
+            &request(r#"{"user": {"id": "alice"}}"#),
+            &VerifyOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.filter, r#""owner_id" <> 'alice'"#);
+
+        // Only an explicit null disagrees: a missing field fails the rule too
+        assert_eq!(report.mismatches.len(), 1, "{:?}", report.mismatches);
+        let mismatch = &report.mismatches[0];
+        assert_eq!(
+            mismatch.row,
+            serde_json::from_str::<Value>(r#"{"resource": {"owner": null}}"#).unwrap()
+        );
+        assert!(mismatch.rule_matches && !mismatch.filter_matches);
+        assert_eq!(mismatch.predicates.len(), 1);
+        assert_eq!(mismatch.predicates[0].sql, r#""owner_id" <> 'alice'"#);
+        assert_eq!(mismatch.predicates[0].sql_value, None);
+        assert_eq!(mismatch.predicates[0].rule_value, Some(true));
+    }
+
+    #[test]
+    fn test_verify_dataset_with_parameters() {
+        let rs = ruleset(&["resource.size > user.quota && resource.owner == user.id"]);
+        let mut request = request(r#"{"user": {"id": "alice", "quota": 10}}"#);
+        request.sql.parameterized = true;
+        let options = VerifyOptions {
+            rows: serde_json::from_str(
+                r#"[
+                    {"resource": {"owner": "alice", "size": 20}},
+                    {"resource": {"owner": "alice", "size": 10.5}},
+                    {"resource": {"owner": "bob", "size": 20}},
+                    {"resource": {"owner": "alice", "size": 5}}
+                ]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let report = verify(&rs, &request, &options, None).unwrap();
+        assert_eq!(report.filter, r#""resource_size" > ? AND "owner_id" = ?"#);
+        assert_eq!(
+            report.params,
+            Some(vec![Value::Int(10), Value::string("alice")])
+        );
+        assert_eq!(report.rows_checked, 4);
+        assert_eq!(report.rule_matches, 2);
+        assert_eq!(report.filter_matches, 2);
+        assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+    }
+
+    #[test]
+    fn test_verify_regex_match() {
+        let rs = ruleset(&[r#"regex_match("^a", resource.owner)"#]);
+        let options = VerifyOptions {
+            rows: serde_json::from_str(
+                r#"[
+                    {"resource": {"owner": "alice"}},
+                    {"resource": {"owner": "bob"}},
+                    {"resource": {"owner": null}},
+                    {}
+                ]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let report = verify(&rs, &request("{}"), &options, None).unwrap();
+        assert_eq!(report.filter, r#""owner_id" REGEXP '^a'"#);
+        assert_eq!(report.rule_matches, 1);
+        assert_eq!(report.filter_matches, 1);
+        assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+    }
+
+    #[test]
+    fn test_verify_time_diff_with_fractional_timestamps() {
+        let options = VerifyOptions {
+            rows: serde_json::from_str(
+                r#"[
+                    {"resource": {"checked": 10000, "seen": 4600.5}},
+                    {"resource": {"checked": 10000, "seen": 6400.5}},
+                    {"resource": {"checked": 10000.9, "seen": 2800}},
+                    {"resource": {"checked": 0, "seen": 5400}},
+                    {"resource": {"checked": 10000, "seen": 10000}}
+                ]"#,
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        for (condition, matches) in [
+            (r#"time_diff(resource.checked, resource.seen, "h") <= 1"#, 4),
+            (r#"time_diff(resource.checked, resource.seen, "h") == 1"#, 2),
+        ] {
+            let report = verify(&ruleset(&[condition]), &request("{}"), &options, None).unwrap();
+            assert_eq!(report.rule_matches, matches, "{}", condition);
+            assert_eq!(report.filter_matches, matches, "{}", condition);
+            assert!(report.mismatches.is_empty(), "{:?}", report.mismatches);
+        }
+    }
+}
@@ -50,10 +50,12 @@
 #![allow(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod budget;
 pub mod context;
 pub mod error;
 pub mod expr;
 pub mod filter;
+pub mod replay;
 pub mod rule;
 #[cfg(feature = "signature")]
 pub mod signature;
@@ -62,6 +64,7 @@ pub mod trace;
 
 /// Prelude module for convenient imports
 pub mod prelude {
+    pub use crate::budget::ResourceBudget;
     pub use crate::context::{Context, Value};
     pub use crate::error::{OrdoError, Result};
     pub use crate::expr::{
@@ -69,12 +72,14 @@ pub mod prelude {
         ExprCompiler, ExprOptimizer, ExprParser, FunctionRegistry, Opcode, OptimizationStats,
         UnaryOp, VectorizedEvaluator,
     };
+    pub use crate::replay::ExecutionRecording;
     pub use crate::rule::{
         Action, ActionKind, BatchExecutionResult, Branch, CompiledAction, CompiledBranch,
         CompiledCondition, CompiledMetadata, CompiledOutput, CompiledRuleExecutor, CompiledRuleSet,
-        CompiledStep, Condition, ExecutionOptions, ExecutionResult, LoggingMetricSink, MetricSink,
-        MetricType, NoOpMetricSink, RuleExecutor, RuleSet, RuleSetCompiler, RuleSetConfig,
-        RuleSetResolver, SingleExecutionResult, Step, StepKind, TerminalResult,
+        CompiledStep, Condition, ExecutionObserver, ExecutionOptions, ExecutionResult,
+        ExternalCallHandler, LoggingMetricSink, MetricSink, MetricType, NoOpMetricSink,
+        RuleExecutor, RuleSet, RuleSetCompiler, RuleSetConfig, RuleSetOptimizer, RuleSetResolver,
+        RuleStepper, SingleExecutionResult, Step, StepKind, TerminalResult,
     };
     #[cfg(feature = "signature")]
     pub use crate::signature::signer::RuleSigner;
@@ -0,0 +1,873 @@
+//! Execution recording and deterministic replay
+//!
+//! An [`ExecutionRecording`] captures what is needed to reproduce one rule
+//! execution and to inspect it afterwards:
+//! - the input (including external data injected into it)
+//! - the rule set name, declared version and a hash of its definition
+//! - versions of the external data the caller supplied
+//! - every value `now()` and `now_millis()` returned
+//! - responses of external calls
+//! - per step: the branch taken, the variables set and the terminal code
+//!
+//! [`record`] runs an execution and records it; [`replay`] runs it again
+//! from the recording, feeding back the recorded clock readings and external
+//! call responses, and reports the first step where the two runs differ.
+//! Recordings serialize to JSON with empty fields omitted.
+//!
+//! Clock readings and external calls are recorded and replayed through a
+//! [`RecordingScope`] on the executing thread. A replay never calls out: the
+//! executor's `ExternalCallHandler` is not used and need not be configured.
+//! Calls skipped for lack of a handler are recorded as such.
+
+use crate::context::Value;
+use crate::error::{OrdoError, Result};
+use crate::rule::{
+    ExecutionObserver, ExecutionOptions, ExecutionResult, RuleExecutor, RuleSet, StepRef,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Version of the recording format written by [`record`]
+pub const RECORDING_FORMAT: u32 = 1;
+
+/// Recording of one rule execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionRecording {
+    /// Format version ([`RECORDING_FORMAT`])
+    pub format: u32,
+    /// Rule set name
+    pub ruleset: String,
+    /// Declared rule set version
+    pub ruleset_version: String,
+    /// Hash of the rule set definition ([`ruleset_hash`])
+    pub ruleset_hash: String,
+    /// Input
+    pub input: Value,
+    /// Versions of external data, by name (filled in by the caller)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub data_versions: BTreeMap<String, String>,
+    /// Values returned by `now()` and `now_millis()`, in call order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clock: Vec<i64>,
+    /// Responses of external calls, in call order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub external_calls: Vec<ExternalCallRecord>,
+    /// Steps in the order they started
+    pub steps: Vec<StepRecord>,
+    /// How the execution ended
+    pub outcome: RecordedOutcome,
+}
+
+/// Response of an external call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalCallRecord {
+    /// Service called
+    pub service: String,
+    /// Method called
+    pub method: String,
+    /// Response received (null if the call failed)
+    #[serde(default)]
+    pub response: Value,
+    /// Why the call failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The call was skipped, as no handler was configured
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub skipped: bool,
+}
+
+impl ExternalCallRecord {
+    fn new(
+        service: &str,
+        method: &str,
+        result: Option<std::result::Result<Value, String>>,
+    ) -> Self {
+        let skipped = result.is_none();
+        let (response, error) = match result {
+            Some(Ok(response)) => (response, None),
+            Some(Err(message)) => (Value::Null, Some(message)),
+            None => (Value::Null, None),
+        };
+        Self {
+            service: service.to_string(),
+            method: method.to_string(),
+            response,
+            error,
+            skipped,
+        }
+    }
+}
+
+/// What one step did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRecord {
+    /// Step ID
+    pub step: String,
+    /// Rule set of the step (only for steps of called rule sets)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<String>,
+    /// `CallRuleSet` nesting depth
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub depth: usize,
+    /// Branch taken (decision steps)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<usize>,
+    /// Variables set by the step, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub set: Vec<(String, Value)>,
+    /// Result code (terminal steps)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// How a recorded execution ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedOutcome {
+    /// Execution produced a result
+    Result { code: String, output: Value },
+    /// Execution failed
+    Error { message: String },
+}
+
+impl RecordedOutcome {
+    fn new(result: &Result<ExecutionResult>) -> Self {
+        match result {
+            Ok(result) => RecordedOutcome::Result {
+                code: result.code.clone(),
+                output: result.output.clone(),
+            },
+            Err(e) => RecordedOutcome::Error {
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl ExecutionRecording {
+    /// Variables of the frame of step `index`, once that step ran
+    ///
+    /// Built from the per-step deltas; steps of called rule sets see only
+    /// their own frame.
+    pub fn variables_at(&self, index: usize) -> Option<HashMap<String, Value>> {
+        let steps = self.steps.get(..=index)?;
+        let mut frames: Vec<HashMap<String, Value>> = Vec::new();
+        for (i, step) in steps.iter().enumerate() {
+            frames.truncate(step.depth + 1);
+            frames.resize_with(step.depth + 1, HashMap::new);
+            let frame = &mut frames[step.depth];
+            for (name, value) in &step.set {
+                frame.insert(name.clone(), value.clone());
+            }
+            // A called rule set ends at its terminal step
+            if step.code.is_some() && step.depth > 0 && i < index {
+                frames.truncate(step.depth);
+            }
+        }
+        frames.pop()
+    }
+}
+
+/// Hash identifying a rule set definition
+///
+/// Compiled and uncompiled definitions hash alike.
+pub fn ruleset_hash(ruleset: &RuleSet) -> String {
+    let mut canonical = ruleset.clone();
+    // Keep the uncompiled form if compilation fails: the hash stays stable
+    let _ = canonical.compile();
+    fingerprint(&canonical)
+}
+
+/// Hash of a serializable value, e.g. to version external data
+///
+/// 64-bit FNV-1a of the value's JSON with object keys sorted, as hex.
+pub fn fingerprint(value: &impl Serialize) -> String {
+    // Going through `serde_json::Value` sorts object keys
+    let json = serde_json::to_value(value)
+        .and_then(|value| serde_json::to_vec(&value))
+        .unwrap_or_default();
+    let hash = json.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Execute `ruleset` and record the execution
+///
+/// `options` apply as for [`RuleExecutor::execute_with_options`]; an
+/// observer in them is still notified.
+pub fn record(
+    executor: &RuleExecutor,
+    ruleset: &RuleSet,
+    input: Value,
+    options: Option<&ExecutionOptions>,
+) -> (Result<ExecutionResult>, ExecutionRecording) {
+    let recorder = Arc::new(StepRecorder::new(options.and_then(|o| o.observer.clone())));
+    let options = options
+        .cloned()
+        .unwrap_or_default()
+        .observer(recorder.clone());
+
+    let scope = RecordingScope::record();
+    let result = executor.execute_with_options(ruleset, input.clone(), Some(&options));
+    let (readings, calls) = (scope.readings(), scope.external_calls());
+    drop(scope);
+
+    let recording = ExecutionRecording {
+        format: RECORDING_FORMAT,
+        ruleset: ruleset.config.name.clone(),
+        ruleset_version: ruleset.config.version.clone(),
+        ruleset_hash: ruleset_hash(ruleset),
+        input,
+        data_versions: BTreeMap::new(),
+        clock: readings,
+        external_calls: calls,
+        steps: recorder.take(),
+        outcome: RecordedOutcome::new(&result),
+    };
+    (result, recording)
+}
+
+/// Outcome of replaying a recording
+#[derive(Debug)]
+pub struct Replay {
+    /// Result of the replayed execution
+    pub result: Result<ExecutionResult>,
+    /// Recording of the replayed execution
+    pub recording: ExecutionRecording,
+    /// Index of the first step that differs from the original recording
+    /// (the number of steps if only the outcome differs)
+    pub divergence: Option<usize>,
+}
+
+impl Replay {
+    /// Whether the replay reproduced the recording exactly
+    pub fn is_faithful(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Re-execute a recorded execution
+///
+/// `ruleset` must be the definition the recording was made with (same name
+/// and [`ruleset_hash`]). The recorded input is used as is, `now()` returns
+/// the recorded readings and external calls the recorded responses; running
+/// out of them is an error.
+pub fn replay(
+    executor: &RuleExecutor,
+    ruleset: &RuleSet,
+    recording: &ExecutionRecording,
+) -> Result<Replay> {
+    if recording.format != RECORDING_FORMAT {
+        return Err(OrdoError::config_error(format!(
+            "Unsupported recording format {}",
+            recording.format
+        )));
+    }
+    if recording.ruleset != ruleset.config.name {
+        return Err(OrdoError::config_error(format!(
+            "Recording is of rule set '{}', not '{}'",
+            recording.ruleset, ruleset.config.name
+        )));
+    }
+    let hash = ruleset_hash(ruleset);
+    if hash != recording.ruleset_hash {
+        return Err(OrdoError::config_error(format!(
+            "Recording was made with another definition of rule set '{}' (hash {}, now {})",
+            recording.ruleset, recording.ruleset_hash, hash
+        )));
+    }
+
+    let recorder = Arc::new(StepRecorder::new(None));
+    let options = ExecutionOptions::default().observer(recorder.clone());
+    let scope = RecordingScope::replay(recording.clock.clone(), recording.external_calls.clone());
+    let result = executor.execute_with_options(ruleset, recording.input.clone(), Some(&options));
+    let (readings, calls) = (scope.readings(), scope.external_calls());
+    drop(scope);
+
+    let replayed = ExecutionRecording {
+        clock: readings,
+        external_calls: calls,
+        steps: recorder.take(),
+        outcome: RecordedOutcome::new(&result),
+        ..recording.clone()
+    };
+    let divergence = replayed
+        .steps
+        .iter()
+        .zip(&recording.steps)
+        .position(|(replayed, recorded)| replayed != recorded)
+        .or_else(|| {
+            (replayed.steps.len() != recording.steps.len() || replayed.outcome != recording.outcome)
+                .then(|| replayed.steps.len().min(recording.steps.len()))
+        });
+    Ok(Replay {
+        result,
+        recording: replayed,
+        divergence,
+    })
+}
+
+/// Observer building the step records of an execution
+struct StepRecorder {
+    /// Observer of the caller, notified as well
+    inner: Option<Arc<dyn ExecutionObserver>>,
+    state: Mutex<RecorderState>,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    steps: Vec<StepRecord>,
+    /// Records of the steps in progress, innermost last
+    open: Vec<usize>,
+}
+
+impl StepRecorder {
+    fn new(inner: Option<Arc<dyn ExecutionObserver>>) -> Self {
+        Self {
+            inner,
+            state: Mutex::new(RecorderState::default()),
+        }
+    }
+
+    fn take(&self) -> Vec<StepRecord> {
+        std::mem::take(&mut self.state.lock().steps)
+    }
+
+    /// Update the record of the innermost step in progress
+    fn update(&self, update: impl FnOnce(&mut StepRecord)) {
+        let mut state = self.state.lock();
+        if let Some(&index) = state.open.last() {
+            update(&mut state.steps[index]);
+        }
+    }
+}
+
+impl ExecutionObserver for StepRecorder {
+    fn before_step(&self, step: StepRef<'_>) {
+        {
+            let mut state = self.state.lock();
+            let depth = state.open.len();
+            let index = state.steps.len();
+            state.steps.push(StepRecord {
+                step: step
+                    .id
+                    .name()
+                    .map_or_else(|| format!("#{}", step.id.hash()), str::to_string),
+                ruleset: (depth > 0).then(|| step.ruleset.to_string()),
+                depth,
+                branch: None,
+                set: Vec::new(),
+                code: None,
+            });
+            state.open.push(index);
+        }
+        if let Some(inner) = &self.inner {
+            inner.before_step(step);
+        }
+    }
+
+    fn after_step(&self, step: StepRef<'_>) {
+        self.state.lock().open.pop();
+        if let Some(inner) = &self.inner {
+            inner.after_step(step);
+        }
+    }
+
+    fn branch_evaluated(&self, step: StepRef<'_>, branch: usize, value: bool) {
+        if value {
+            self.update(|record| record.branch = Some(branch));
+        }
+        if let Some(inner) = &self.inner {
+            inner.branch_evaluated(step, branch, value);
+        }
+    }
+
+    fn action_executed(&self, step: StepRef<'_>, action: crate::rule::ActionRef) {
+        if let Some(inner) = &self.inner {
+            inner.action_executed(step, action);
+        }
+    }
+
+    fn variable_set(&self, step: StepRef<'_>, name: &str, value: &Value) {
+        self.update(|record| record.set.push((name.to_string(), value.clone())));
+        if let Some(inner) = &self.inner {
+            inner.variable_set(step, name, value);
+        }
+    }
+
+    fn terminal_reached(&self, step: StepRef<'_>, code: &str, output: &Value) {
+        self.update(|record| record.code = Some(code.to_string()));
+        if let Some(inner) = &self.inner {
+            inner.terminal_reached(step, code, output);
+        }
+    }
+
+    fn error(&self, step: StepRef<'_>, error: &OrdoError) {
+        self.state.lock().open.pop();
+        if let Some(inner) = &self.inner {
+            inner.error(step, error);
+        }
+    }
+}
+
+/// Nondeterministic inputs of the current thread's scope
+///
+/// While recording, readings and calls are appended and the cursors stay at
+/// the end; while replaying, the cursors count what was handed out.
+struct Tape {
+    replaying: bool,
+    clock: Vec<i64>,
+    next_reading: usize,
+    calls: Vec<ExternalCallRecord>,
+    next_call: usize,
+}
+
+thread_local! {
+    static TAPE: RefCell<Option<Tape>> = const { RefCell::new(None) };
+}
+
+/// Records or replays clock readings and external calls on the current
+/// thread until dropped
+///
+/// Scopes do not nest: while one is active, entering another is a no-op and
+/// the outer scope keeps recording or replaying.
+#[must_use = "nothing is recorded or replayed once the scope is dropped"]
+pub struct RecordingScope {
+    owner: bool,
+}
+
+impl RecordingScope {
+    /// Record the readings `now()` and `now_millis()` return and the
+    /// responses of external calls
+    pub fn record() -> Self {
+        Self::enter(false, Vec::new(), Vec::new())
+    }
+
+    /// Make `now()` and `now_millis()` return `clock` and external calls
+    /// return the responses of `calls`, in order
+    pub fn replay(clock: Vec<i64>, calls: Vec<ExternalCallRecord>) -> Self {
+        Self::enter(true, clock, calls)
+    }
+
+    fn enter(replaying: bool, clock: Vec<i64>, calls: Vec<ExternalCallRecord>) -> Self {
+        let owner = TAPE.with(|slot| {
+            let mut slot = slot.borrow_mut();
+            if slot.is_some() {
+                return false;
+            }
+            *slot = Some(Tape {
+                replaying,
+                next_reading: if replaying { 0 } else { clock.len() },
+                next_call: if replaying { 0 } else { calls.len() },
+                clock,
+                calls,
+            });
+            true
+        });
+        Self { owner }
+    }
+
+    /// Clock readings recorded or replayed so far (empty for a nested scope)
+    pub fn readings(&self) -> Vec<i64> {
+        self.with_tape(|tape| tape.clock[..tape.next_reading].to_vec())
+    }
+
+    /// External calls recorded or replayed so far (empty for a nested scope)
+    pub fn external_calls(&self) -> Vec<ExternalCallRecord> {
+        self.with_tape(|tape| tape.calls[..tape.next_call].to_vec())
+    }
+
+    fn with_tape<T: Default>(&self, f: impl FnOnce(&Tape) -> T) -> T {
+        if !self.owner {
+            return T::default();
+        }
+        TAPE.with(|slot| slot.borrow().as_ref().map(f).unwrap_or_default())
+    }
+}
+
+impl Drop for RecordingScope {
+    fn drop(&mut self) {
+        if self.owner {
+            TAPE.with(|slot| slot.borrow_mut().take());
+        }
+    }
+}
+
+/// Read the clock through the active [`RecordingScope`], if any
+///
+/// `read` takes the actual reading; it is not called while replaying.
+pub(crate) fn read_clock(read: impl FnOnce() -> i64) -> Result<i64> {
+    TAPE.with(|slot| match slot.borrow_mut().as_mut() {
+        None => Ok(read()),
+        Some(tape) if !tape.replaying => {
+            let reading = read();
+            tape.clock.push(reading);
+            tape.next_reading += 1;
+            Ok(reading)
+        }
+        Some(tape) => {
+            let reading = tape.clock.get(tape.next_reading).copied().ok_or_else(|| {
+                OrdoError::eval_error_static("Recording has no more clock readings")
+            })?;
+            tape.next_reading += 1;
+            Ok(reading)
+        }
+    })
+}
+
+/// Make an external call through the active [`RecordingScope`], if any
+///
+/// `call` performs the actual call, or returns `None` when there is nothing
+/// to call it with; the call is then skipped (`Ok(None)`). `call` is not
+/// called while replaying: the next recorded call, which must be to the same
+/// service and method, is handed back instead.
+pub(crate) fn external_call(
+    service: &str,
+    method: &str,
+    call: impl FnOnce() -> Option<std::result::Result<Value, String>>,
+) -> std::result::Result<Option<Value>, String> {
+    // `call` runs without the tape borrowed: the handler may read the clock
+    let replaying = TAPE.with(|slot| slot.borrow().as_ref().map(|tape| tape.replaying));
+    match replaying {
+        None => call().transpose(),
+        Some(false) => {
+            let result = call();
+            TAPE.with(|slot| {
+                if let Some(tape) = slot.borrow_mut().as_mut() {
+                    tape.calls
+                        .push(ExternalCallRecord::new(service, method, result.clone()));
+                    tape.next_call += 1;
+                }
+            });
+            result.transpose()
+        }
+        Some(true) => TAPE.with(|slot| {
+            let mut slot = slot.borrow_mut();
+            let tape = slot.as_mut().expect("replaying tape");
+            let record = tape
+                .calls
+                .get(tape.next_call)
+                .ok_or("Recording has no more external call responses")?;
+            if record.service != service || record.method != method {
+                return Err(format!(
+                    "Recording has a call to {}.{} here",
+                    record.service, record.method
+                ));
+            }
+            tape.next_call += 1;
+            match (&record.error, record.skipped) {
+                (Some(message), _) => Err(message.clone()),
+                (None, true) => Ok(None),
+                (None, false) => Ok(Some(record.response.clone())),
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
+    use crate::rule::{
+        Action, ActionKind, Condition, ExternalCallHandler, RuleSetResolver, Step, TerminalResult,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stamps the time, calls `score` and decides on its result
+    fn setup() -> (RuleExecutor, RuleSet) {
+        struct Resolver(Arc<RuleSet>);
+        impl RuleSetResolver for Resolver {
+            fn resolve(&self, name: &str) -> Option<Arc<RuleSet>> {
+                (name == "score").then(|| self.0.clone())
+            }
+        }
+
+        let parse = |source: &str| ExprParser::parse(source).unwrap();
+        let mut score = RuleSet::new("score", "compute");
+        score.add_step(Step::action(
+            "compute",
+            "Compute",
+            vec![Action::set_var("points", parse("amount / 10"))],
+            "done",
+        ));
+        score.add_step(Step::terminal(
+            "done",
+            "Done",
+            TerminalResult::new("SCORED").with_output("points", parse("$points")),
+        ));
+
+        let mut main = RuleSet::new("main", "stamp");
+        main.add_step(Step::action(
+            "stamp",
+            "Stamp",
+            vec![
+                Action::set_var("at", parse("now_millis()")),
+                Action {
+                    kind: ActionKind::CallRuleSet {
+                        ruleset_name: "score".to_string(),
+                        input_mapping: None,
+                        result_variable: "score".to_string(),
+                    },
+                    description: String::new(),
+                },
+            ],
+            "check",
+        ));
+        main.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string("amount > 1000"), "review")
+                .default("approve")
+                .build(),
+        );
+        main.add_step(Step::terminal(
+            "review",
+            "Review",
+            TerminalResult::new("REVIEW").with_output("at", parse("$at")),
+        ));
+        main.add_step(Step::terminal(
+            "approve",
+            "Approve",
+            TerminalResult::new("APPROVE").with_output("at", parse("$at")),
+        ));
+
+        let mut executor = RuleExecutor::new();
+        executor.set_resolver(Arc::new(Resolver(Arc::new(score))));
+        (executor, main)
+    }
+
+    fn input(amount: i64) -> Value {
+        serde_json::from_str(&format!(r#"{{"amount": {amount}}}"#)).unwrap()
+    }
+
+    #[test]
+    fn test_record_captures_steps_and_clock() {
+        let (executor, main) = setup();
+        let (result, recording) = record(&executor, &main, input(2000), None);
+        let result = result.unwrap();
+        assert_eq!(result.code, "REVIEW");
+
+        assert_eq!(recording.ruleset, "main");
+        assert_eq!(recording.ruleset_hash, ruleset_hash(&main));
+        assert_eq!(recording.clock.len(), 1);
+        assert_eq!(
+            result.output.get_path("at"),
+            Some(&Value::int(recording.clock[0]))
+        );
+        let path: Vec<_> = recording
+            .steps
+            .iter()
+            .map(|step| (step.step.as_str(), step.depth))
+            .collect();
+        assert_eq!(
+            path,
+            vec![
+                ("stamp", 0),
+                ("compute", 1),
+                ("done", 1),
+                ("check", 0),
+                ("review", 0)
+            ]
+        );
+        assert_eq!(recording.steps[1].ruleset.as_deref(), Some("score"));
+        assert_eq!(recording.steps[2].code.as_deref(), Some("SCORED"));
+        assert_eq!(recording.steps[3].branch, Some(0));
+        assert_eq!(
+            recording.outcome,
+            RecordedOutcome::Result {
+                code: "REVIEW".to_string(),
+                output: result.output.clone()
+            }
+        );
+
+        // Round-trips through JSON, omitting empty fields
+        let json = serde_json::to_string(&recording).unwrap();
+        assert!(!json.contains("external_calls"));
+        assert!(!json.contains(r#""depth":0"#));
+        let parsed: ExecutionRecording = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, recording);
+    }
+
+    #[test]
+    fn test_replay_reproduces_recording() {
+        let (executor, main) = setup();
+        let (_, recording) = record(&executor, &main, input(500), None);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        let replayed = replay(&executor, &main, &recording).unwrap();
+        assert!(replayed.is_faithful());
+        assert_eq!(replayed.recording, recording);
+        assert_eq!(
+            replayed.result.unwrap().output.get_path("at"),
+            Some(&Value::int(recording.clock[0]))
+        );
+    }
+
+    #[test]
+    fn test_replay_detects_divergence_and_mismatches() {
+        let (executor, mut main) = setup();
+        let (_, recording) = record(&executor, &main, input(500), None);
+
+        // A tampered recording diverges at the step whose effect changed
+        let mut tampered = recording.clone();
+        tampered.input = input(5000);
+        let replayed = replay(&executor, &main, &tampered).unwrap();
+        assert_eq!(replayed.divergence, Some(0));
+
+        // Missing clock readings fail the replay
+        let mut tampered = recording.clone();
+        tampered.clock.clear();
+        let replayed = replay(&executor, &main, &tampered).unwrap();
+        assert!(replayed.result.is_err());
+        assert_eq!(replayed.divergence, Some(0));
+
+        // Another definition of the rule set is refused
+        main.add_step(Step::terminal("extra", "Extra", TerminalResult::new("X")));
+        assert!(replay(&executor, &main, &recording).is_err());
+    }
+
+    #[test]
+    fn test_variables_at_follows_frames() {
+        let (executor, main) = setup();
+        let (_, recording) = record(&executor, &main, input(500), None);
+
+        let stamp = recording.variables_at(0).unwrap();
+        assert!(stamp.contains_key("at"));
+        assert!(stamp.contains_key("score"));
+        let compute = recording.variables_at(1).unwrap();
+        assert_eq!(compute.keys().collect::<Vec<_>>(), vec!["points"]);
+        let check = recording.variables_at(3).unwrap();
+        assert_eq!(check.len(), 2);
+        assert!(recording.variables_at(5).is_none());
+
+        // Stable across compilation
+        let mut compiled = main.clone();
+        compiled.compile().unwrap();
+        assert_eq!(ruleset_hash(&compiled), recording.ruleset_hash);
+        assert_eq!(fingerprint(&input(1)), fingerprint(&input(1)));
+        assert_ne!(fingerprint(&input(1)), fingerprint(&input(2)));
+    }
+
+    /// Answers `crm.lookup` with `tier`, or fails without one
+    struct Crm {
+        tier: Option<&'static str>,
+        calls: AtomicUsize,
+    }
+
+    impl ExternalCallHandler for Crm {
+        fn call(&self, _: &str, _: &str, params: &Value, _: u64) -> Result<Value> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            assert_eq!(params.get_path("id"), Some(&Value::int(7)));
+            let tier = self
+                .tier
+                .ok_or_else(|| OrdoError::eval_error_static("crm unavailable"))?;
+            Ok(Value::string(tier))
+        }
+    }
+
+    /// Looks up the customer and decides on its tier
+    fn crm_setup(tier: Option<&'static str>) -> (RuleExecutor, RuleSet, Arc<Crm>) {
+        let mut main = RuleSet::new("crm", "lookup");
+        main.add_step(Step::action(
+            "lookup",
+            "Lookup",
+            vec![Action {
+                kind: ActionKind::ExternalCall {
+                    service: "crm".to_string(),
+                    method: "lookup".to_string(),
+                    params: vec![("id".to_string(), ExprParser::parse("customer").unwrap())],
+                    timeout_ms: 100,
+                    result_variable: Some("tier".to_string()),
+                },
+                description: String::new(),
+            }],
+            "check",
+        ));
+        main.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string(r#"$tier == "gold""#), "vip")
+                .default("standard")
+                .build(),
+        );
+        main.add_step(Step::terminal("vip", "VIP", TerminalResult::new("VIP")));
+        main.add_step(Step::terminal(
+            "standard",
+            "Standard",
+            TerminalResult::new("STANDARD"),
+        ));
+
+        let crm = Arc::new(Crm {
+            tier,
+            calls: AtomicUsize::new(0),
+        });
+        let mut executor = RuleExecutor::new();
+        executor.set_external_handler(crm.clone());
+        (executor, main, crm)
+    }
+
+    #[test]
+    fn test_replay_uses_recorded_external_calls() {
+        let customer: Value = serde_json::from_str(r#"{"customer": 7}"#).unwrap();
+        let (executor, main, _) = crm_setup(Some("gold"));
+        let (result, recording) = record(&executor, &main, customer.clone(), None);
+        assert_eq!(result.unwrap().code, "VIP");
+        assert_eq!(recording.external_calls.len(), 1);
+        assert_eq!(recording.external_calls[0].service, "crm");
+        assert_eq!(recording.external_calls[0].response, Value::string("gold"));
+        assert_eq!(
+            recording.steps[0].set,
+            vec![(
+                "tier".to_string(),
+                recording.external_calls[0].response.clone()
+            )]
+        );
+
+        // The service now answers differently; the replay never calls it
+        let (executor, _, crm) = crm_setup(Some("silver"));
+        let replayed = replay(&executor, &main, &recording).unwrap();
+        assert!(replayed.is_faithful());
+        assert_eq!(replayed.result.unwrap().code, "VIP");
+        assert_eq!(crm.calls.load(Ordering::Relaxed), 0);
+
+        // An executor without a handler replays the response as well
+        let replayed = replay(&RuleExecutor::new(), &main, &recording).unwrap();
+        assert!(replayed.is_faithful());
+        assert_eq!(replayed.result.unwrap().code, "VIP");
+
+        // A call skipped for lack of a handler stays skipped
+        let (result, skipped) = record(&RuleExecutor::new(), &main, customer.clone(), None);
+        assert_eq!(result.unwrap().code, "STANDARD");
+        assert!(skipped.external_calls[0].skipped);
+        let replayed = replay(&executor, &main, &skipped).unwrap();
+        assert!(replayed.is_faithful());
+        assert_eq!(crm.calls.load(Ordering::Relaxed), 0);
+
+        // A failed call is recorded and fails the replay the same way
+        let (executor, main, _) = crm_setup(None);
+        let (result, failed) = record(&executor, &main, customer, None);
+        assert!(result.is_err());
+        assert!(failed.external_calls[0].error.is_some());
+        let (executor, _, crm) = crm_setup(Some("gold"));
+        let replayed = replay(&executor, &main, &failed).unwrap();
+        assert!(replayed.is_faithful());
+        assert_eq!(crm.calls.load(Ordering::Relaxed), 0);
+
+        // Running out of recorded calls fails instead of calling out
+        let mut tampered = recording.clone();
+        tampered.external_calls.clear();
+        let replayed = replay(&executor, &main, &tampered).unwrap();
+        assert!(replayed.result.is_err());
+        assert_eq!(crm.calls.load(Ordering::Relaxed), 0);
+    }
+}
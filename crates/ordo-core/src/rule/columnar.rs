@@ -0,0 +1,629 @@
+//! Columnar execution of rule sets over Arrow record batches
+//!
+//! [`ColumnarExecutor`] routes all rows of a [`RecordBatch`] through the step
+//! graph together. Rows that reach a step at the same depth are evaluated as
+//! one selection: each branch condition produces a bitmap that partitions the
+//! selection into rows taking the branch and rows trying the next one.
+//! Terminal outputs are evaluated column-wise too, and results come back as
+//! Arrow arrays.
+//!
+//! Results match [`RuleExecutor::execute_batch`] on the rows converted with
+//! [`row_value`]. Rows that reach an action are handed to a [`RuleExecutor`],
+//! since variables, metrics, logs and sub-rulesets need per-row state.
+//! Timeouts are not applied.
+
+use super::executor::{BatchExecutionResult, RuleExecutor};
+use super::model::{FieldMissingBehavior, RuleSet};
+use super::step::{Condition, StepKind, TerminalResult};
+use crate::context::Value;
+use crate::error::{OrdoError, Result};
+use crate::expr::{check_schema, row_value, ColumnarEvaluator, ConditionMask, ExprParser};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, NullArray, RecordBatch, RecordBatchOptions,
+    StringArray,
+};
+use arrow_buffer::BooleanBuffer;
+use arrow_schema::{Field, Schema};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Result of executing a rule set over a record batch
+///
+/// Output values are laid out one column per output key. A column holds
+/// `Int64`, `Float64`, `Boolean` or `Utf8` values when every row agrees on the
+/// type, and JSON text otherwise; rows without the key are null.
+#[derive(Debug, Clone)]
+pub struct ColumnarExecutionResult {
+    /// Result code per row (`"error"` for failed rows)
+    pub codes: StringArray,
+    /// Result message per row (the error message for failed rows)
+    pub messages: StringArray,
+    /// Error message per row, null for rows that succeeded
+    pub errors: StringArray,
+    /// Output values, one column per output key in key order
+    pub outputs: RecordBatch,
+    /// Total number of rows
+    pub total: usize,
+    /// Number of successful rows
+    pub success: usize,
+    /// Number of failed rows
+    pub failed: usize,
+    /// Number of rows executed one at a time by the interpreter
+    pub interpreted: usize,
+    /// Total execution time in microseconds
+    pub total_duration_us: u64,
+}
+
+impl ColumnarExecutionResult {
+    /// Lay out row-wise batch results the same way
+    pub fn from_batch_result(result: &BatchExecutionResult) -> Result<Self> {
+        let mut rows = RowResults::new(result.total);
+        for (row, single) in result.results.iter().enumerate() {
+            match &single.error {
+                Some(error) => rows.fail(row, error.clone()),
+                None => rows.finish_with(
+                    row,
+                    single.code.clone(),
+                    single.message.clone(),
+                    &single.output,
+                ),
+            }
+        }
+        rows.into_result(result.total, result.total_duration_us)
+    }
+
+    /// Check if all rows were successful
+    pub fn all_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// Rule executor that evaluates whole record batches column by column
+pub struct ColumnarExecutor {
+    evaluator: ColumnarEvaluator,
+    /// Executes rows that reach actions
+    fallback: RuleExecutor,
+}
+
+impl Default for ColumnarExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColumnarExecutor {
+    /// Create a new columnar executor
+    pub fn new() -> Self {
+        Self::with_executor(RuleExecutor::new())
+    }
+
+    /// Create a columnar executor that runs rows reaching actions on `executor`
+    ///
+    /// Use this to give those rows a metric sink or sub-ruleset resolver.
+    pub fn with_executor(executor: RuleExecutor) -> Self {
+        Self {
+            evaluator: ColumnarEvaluator::new(),
+            fallback: executor,
+        }
+    }
+
+    /// Execute a rule set against every row of a batch
+    ///
+    /// Fails only if a column type cannot be converted to values; errors of
+    /// individual rows are reported per row.
+    pub fn execute(
+        &self,
+        ruleset: &RuleSet,
+        batch: &RecordBatch,
+    ) -> Result<ColumnarExecutionResult> {
+        let start_time = Instant::now();
+        check_schema(batch.schema_ref())?;
+
+        let total = batch.num_rows();
+        let lenient = ruleset.config.field_missing == FieldMissingBehavior::Lenient;
+        let max_depth = ruleset.config.max_depth;
+        let mut rows = RowResults::new(total);
+        let mut interpret: Vec<u32> = Vec::new();
+
+        // Selections waiting at a step, shallowest first so that rows arriving
+        // at a step along different paths are evaluated together
+        let mut pending: BTreeMap<(usize, &str), Vec<u32>> = BTreeMap::new();
+        if total > 0 {
+            pending.insert(
+                (0, ruleset.config.entry_step.as_str()),
+                (0..total as u32).collect(),
+            );
+        }
+
+        while let Some(((depth, step_id), selection)) = pending.pop_first() {
+            if depth >= max_depth {
+                rows.fail_all(&selection, &OrdoError::MaxDepthExceeded { max_depth });
+                continue;
+            }
+            let Some(step) = ruleset.get_step(step_id) else {
+                let error = OrdoError::StepNotFound {
+                    step_id: step_id.to_string(),
+                };
+                rows.fail_all(&selection, &error);
+                continue;
+            };
+
+            match &step.kind {
+                StepKind::Decision {
+                    branches,
+                    default_next,
+                } => {
+                    let mut remaining = selection;
+                    for branch in branches {
+                        if remaining.is_empty() {
+                            break;
+                        }
+                        let mask = self.condition(&branch.condition, batch, &remaining);
+                        let mut failed = vec![false; remaining.len()];
+                        for (pos, error) in mask.errors {
+                            match error {
+                                OrdoError::FieldNotFound { .. } if lenient => {}
+                                error => {
+                                    rows.fail(remaining[pos] as usize, error.to_string());
+                                    failed[pos] = true;
+                                }
+                            }
+                        }
+
+                        let taken: Vec<u32> = mask
+                            .matched
+                            .set_indices()
+                            .map(|pos| remaining[pos])
+                            .collect();
+                        if !taken.is_empty() {
+                            if branch.actions.is_empty() {
+                                pending
+                                    .entry((depth + 1, branch.next_step.as_str()))
+                                    .or_default()
+                                    .extend(taken);
+                            } else {
+                                interpret.extend(taken);
+                            }
+                        }
+                        remaining = remaining
+                            .iter()
+                            .enumerate()
+                            .filter(|(pos, _)| !mask.matched.value(*pos) && !failed[*pos])
+                            .map(|(_, &row)| row)
+                            .collect();
+                    }
+
+                    if remaining.is_empty() {
+                        continue;
+                    }
+                    match default_next {
+                        Some(next) => pending
+                            .entry((depth + 1, next.as_str()))
+                            .or_default()
+                            .extend(remaining),
+                        None => rows.fail_all(
+                            &remaining,
+                            &OrdoError::eval_error(format!(
+                                "No matching branch in step '{}' and no default",
+                                step.id
+                            )),
+                        ),
+                    }
+                }
+
+                StepKind::Action { actions, next_step } => {
+                    if actions.is_empty() {
+                        pending
+                            .entry((depth + 1, next_step.as_str()))
+                            .or_default()
+                            .extend(selection);
+                    } else {
+                        interpret.extend(selection);
+                    }
+                }
+
+                StepKind::Terminal { result } => {
+                    self.terminal(result, batch, &selection, &mut rows)
+                }
+            }
+        }
+
+        // Actions run from the start of the rule set; conditions have no side
+        // effects, so the rows take the same path again
+        for &row in &interpret {
+            let row = row as usize;
+            match self.fallback.execute(ruleset, row_value(batch, row)?) {
+                Ok(result) => rows.finish_with(row, result.code, result.message, &result.output),
+                Err(e) => rows.fail(row, e.to_string()),
+            }
+        }
+
+        let mut result = rows.into_result(total, 0)?;
+        result.interpreted = interpret.len();
+        result.total_duration_us = start_time.elapsed().as_micros() as u64;
+        Ok(result)
+    }
+
+    fn condition(&self, condition: &Condition, batch: &RecordBatch, rows: &[u32]) -> ConditionMask {
+        match condition {
+            Condition::Always => ConditionMask {
+                matched: BooleanBuffer::new_set(rows.len()),
+                errors: Vec::new(),
+            },
+            Condition::Expression(expr) => self.evaluator.eval_condition(expr, batch, rows),
+            Condition::ExpressionString(s) => match ExprParser::parse(s) {
+                Ok(expr) => self.evaluator.eval_condition(&expr, batch, rows),
+                Err(e) => ConditionMask {
+                    matched: BooleanBuffer::new_unset(rows.len()),
+                    errors: (0..rows.len()).map(|pos| (pos, e.clone())).collect(),
+                },
+            },
+        }
+    }
+
+    /// Evaluate a terminal's outputs for a selection, like `build_output`
+    fn terminal<'a>(
+        &self,
+        result: &'a TerminalResult,
+        batch: &RecordBatch,
+        selection: &[u32],
+        rows: &mut RowResults<'a>,
+    ) {
+        let columns: Vec<Vec<Result<Value>>> = result
+            .output
+            .iter()
+            .map(|(_, expr)| self.evaluator.eval_values(expr, batch, selection))
+            .collect();
+
+        // The first failing output (in declaration order) fails the row
+        let mut failed = vec![false; selection.len()];
+        for (pos, &row) in selection.iter().enumerate() {
+            if let Some(error) = columns.iter().find_map(|c| c[pos].as_ref().err()) {
+                rows.fail(row as usize, error.to_string());
+                failed[pos] = true;
+            }
+        }
+
+        for ((key, _), values) in result.output.iter().zip(columns) {
+            let column = rows.output(key);
+            for ((value, &row), failed) in values.into_iter().zip(selection).zip(&failed) {
+                if let (Ok(value), false) = (value, failed) {
+                    column[row as usize] = Some(value);
+                }
+            }
+        }
+        if let Value::Object(data) = &result.data {
+            for (key, value) in data {
+                let column = rows.output(key);
+                for (&row, failed) in selection.iter().zip(&failed) {
+                    if !failed {
+                        column[row as usize] = Some(value.clone());
+                    }
+                }
+            }
+        }
+        for (&row, failed) in selection.iter().zip(&failed) {
+            if !failed {
+                rows.codes[row as usize] = Cow::Borrowed(&result.code);
+                rows.messages[row as usize] = Cow::Borrowed(&result.message);
+            }
+        }
+    }
+}
+
+/// Per-row results, filled in as rows finish
+struct RowResults<'a> {
+    codes: Vec<Cow<'a, str>>,
+    messages: Vec<Cow<'a, str>>,
+    errors: Vec<Option<String>>,
+    /// Output key → value per row (`None` where the row has no such key)
+    outputs: BTreeMap<String, Vec<Option<Value>>>,
+}
+
+impl<'a> RowResults<'a> {
+    fn new(total: usize) -> Self {
+        Self {
+            codes: vec![Cow::Borrowed(""); total],
+            messages: vec![Cow::Borrowed(""); total],
+            errors: vec![None; total],
+            outputs: BTreeMap::new(),
+        }
+    }
+
+    fn output(&mut self, key: &str) -> &mut Vec<Option<Value>> {
+        let total = self.codes.len();
+        self.outputs
+            .entry(key.to_string())
+            .or_insert_with(|| vec![None; total])
+    }
+
+    fn fail(&mut self, row: usize, error: String) {
+        self.codes[row] = Cow::Borrowed("error");
+        self.messages[row] = Cow::Owned(error.clone());
+        self.errors[row] = Some(error);
+    }
+
+    fn fail_all(&mut self, selection: &[u32], error: &OrdoError) {
+        let message = error.to_string();
+        for &row in selection {
+            self.fail(row as usize, message.clone());
+        }
+    }
+
+    fn finish_with(&mut self, row: usize, code: String, message: String, output: &Value) {
+        self.codes[row] = Cow::Owned(code);
+        self.messages[row] = Cow::Owned(message);
+        if let Value::Object(map) = output {
+            for (key, value) in map {
+                self.output(key)[row] = Some(value.clone());
+            }
+        }
+    }
+
+    fn into_result(self, total: usize, total_duration_us: u64) -> Result<ColumnarExecutionResult> {
+        let failed = self.errors.iter().filter(|e| e.is_some()).count();
+        let mut fields = Vec::with_capacity(self.outputs.len());
+        let mut columns = Vec::with_capacity(self.outputs.len());
+        for (key, values) in self.outputs {
+            let column = output_column(values)?;
+            fields.push(Field::new(key, column.data_type().clone(), true));
+            columns.push(column);
+        }
+        let outputs = RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(fields)),
+            columns,
+            &RecordBatchOptions::new().with_row_count(Some(total)),
+        )
+        .map_err(|e| OrdoError::InternalError {
+            message: e.to_string().into(),
+        })?;
+
+        Ok(ColumnarExecutionResult {
+            codes: StringArray::from_iter_values(&self.codes),
+            messages: StringArray::from_iter_values(&self.messages),
+            errors: StringArray::from(self.errors),
+            outputs,
+            total,
+            success: total - failed,
+            failed,
+            interpreted: total,
+            total_duration_us,
+        })
+    }
+}
+
+/// Build the narrowest Arrow column that holds every value of an output key
+fn output_column(values: Vec<Option<Value>>) -> Result<ArrayRef> {
+    let present = || values.iter().flatten().filter(|v| !v.is_null());
+    let all = |f: fn(&Value) -> bool| present().all(f);
+
+    if present().next().is_none() {
+        return Ok(Arc::new(NullArray::new(values.len())));
+    }
+    let column: ArrayRef = if all(|v| matches!(v, Value::Int(_))) {
+        Arc::new(Int64Array::from_iter(values.iter().map(|v| match v {
+            Some(Value::Int(i)) => Some(*i),
+            _ => None,
+        })))
+    } else if all(|v| matches!(v, Value::Float(_))) {
+        Arc::new(Float64Array::from_iter(values.iter().map(|v| match v {
+            Some(Value::Float(f)) => Some(*f),
+            _ => None,
+        })))
+    } else if all(|v| matches!(v, Value::Bool(_))) {
+        Arc::new(BooleanArray::from_iter(values.iter().map(|v| match v {
+            Some(Value::Bool(b)) => Some(*b),
+            _ => None,
+        })))
+    } else if all(|v| matches!(v, Value::String(_))) {
+        Arc::new(StringArray::from_iter(values.iter().map(|v| match v {
+            Some(Value::String(s)) => Some(s.as_ref()),
+            _ => None,
+        })))
+    } else {
+        // Mixed types: JSON text, with object keys sorted so equal values
+        // always render the same way
+        let json = values
+            .iter()
+            .map(|v| match v {
+                Some(v) if !v.is_null() => serde_json::to_value(v)
+                    .map(|json| Some(json.to_string()))
+                    .map_err(|e| OrdoError::eval_error(e.to_string())),
+                _ => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Arc::new(StringArray::from(json))
+    };
+    Ok(column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{Action, ActionKind, Step};
+    use arrow_array::{Array, Float64Array, Int64Array};
+    use std::collections::HashMap;
+
+    fn expr(s: &str) -> crate::expr::Expr {
+        ExprParser::parse(s).unwrap()
+    }
+
+    fn accounts() -> RecordBatch {
+        let n = 64;
+        let balance: ArrayRef = Arc::new(Float64Array::from_iter((0..n).map(|i| {
+            if i % 13 == 0 {
+                None
+            } else {
+                Some((i as f64 - 20.0) * 250.5)
+            }
+        })));
+        let score: ArrayRef = Arc::new(Int64Array::from_iter_values(
+            (0..n).map(|i| (i * 37 % 900) as i64),
+        ));
+        let country: ArrayRef = Arc::new(StringArray::from_iter_values(
+            (0..n).map(|i| ["US", "DE", "FR", "CN"][i % 4]),
+        ));
+        RecordBatch::try_from_iter(vec![
+            ("balance", balance),
+            ("score", score),
+            ("country", country),
+        ])
+        .unwrap()
+    }
+
+    fn scoring_rules() -> RuleSet {
+        let mut ruleset = RuleSet::new("rescore", "region");
+        ruleset.add_step(
+            Step::decision("region", "Region")
+                .branch(
+                    Condition::from_string("country in [\"US\", \"CN\"]"),
+                    "risk",
+                )
+                .branch(Condition::from_string("upper(country) == \"DE\""), "eu")
+                .branch(Condition::from_string("missing_field > 0"), "reject")
+                .default("flag")
+                .build(),
+        );
+        ruleset.add_step(
+            Step::decision("risk", "Risk")
+                .branch(Condition::from_string("balance < 0.0"), "reject")
+                .branch(
+                    Condition::from_string("score >= 600 && balance > 1000.0"),
+                    "approve",
+                )
+                .branch(
+                    Condition::from_string("score / (score % 5) > 100"),
+                    "review",
+                )
+                .default("review")
+                .build(),
+        );
+        ruleset.add_step(Step::action("eu", "EU", vec![], "risk"));
+        ruleset.add_step(Step::action(
+            "flag",
+            "Flag",
+            vec![Action {
+                kind: ActionKind::SetVariable {
+                    name: "flagged".to_string(),
+                    value: expr("score * 2"),
+                },
+                description: String::new(),
+            }],
+            "flagged",
+        ));
+        ruleset.add_step(
+            Step::decision("flagged", "Flagged")
+                .branch(Condition::from_string("$flagged > 800"), "review")
+                .build(),
+        );
+        ruleset.add_step(Step::terminal(
+            "approve",
+            "Approve",
+            TerminalResult::new("APPROVED")
+                .with_message("approved")
+                .with_output("limit", expr("balance * 0.5"))
+                .with_output("score", expr("score")),
+        ));
+        ruleset.add_step(Step::terminal(
+            "review",
+            "Review",
+            TerminalResult::new("REVIEW")
+                .with_output("score", expr("if score > 450 then score else 0.5"))
+                .with_data(Value::object(HashMap::from([(
+                    "queue".to_string(),
+                    Value::string("manual"),
+                )]))),
+        ));
+        ruleset.add_step(Step::terminal(
+            "reject",
+            "Reject",
+            TerminalResult::new("REJECTED").with_output("score", expr("score - 1000")),
+        ));
+        ruleset.compile().unwrap();
+        ruleset
+    }
+
+    fn assert_same(actual: &ColumnarExecutionResult, expected: &ColumnarExecutionResult) {
+        assert_eq!(actual.codes, expected.codes);
+        assert_eq!(actual.messages, expected.messages);
+        assert_eq!(actual.errors, expected.errors);
+        assert_eq!(actual.outputs.schema(), expected.outputs.schema());
+        for (a, e) in actual
+            .outputs
+            .columns()
+            .iter()
+            .zip(expected.outputs.columns())
+        {
+            assert_eq!(a.to_data(), e.to_data());
+        }
+        assert_eq!(actual.success, expected.success);
+        assert_eq!(actual.failed, expected.failed);
+    }
+
+    #[test]
+    fn test_matches_execute_batch() {
+        let batch = accounts();
+        let ruleset = scoring_rules();
+        let inputs: Vec<Value> = (0..batch.num_rows())
+            .map(|row| row_value(&batch, row).unwrap())
+            .collect();
+        let expected = RuleExecutor::new().execute_batch(&ruleset, inputs, false);
+        let expected = ColumnarExecutionResult::from_batch_result(&expected).unwrap();
+
+        let actual = ColumnarExecutor::new().execute(&ruleset, &batch).unwrap();
+        assert_same(&actual, &expected);
+        assert!(actual.failed > 0 && actual.success > 0);
+        // Only the FR rows reach the action step
+        assert_eq!(actual.interpreted, 16);
+    }
+
+    #[test]
+    fn test_errors_match_execute_batch() {
+        let batch = accounts();
+        let mut ruleset = RuleSet::new("strict", "check");
+        ruleset.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string("score > 800"), "loop")
+                .branch(Condition::from_string("balance > 0.0"), "missing")
+                .branch(Condition::from_string("nope == 1"), "done")
+                .build(),
+        );
+        ruleset.add_step(Step::action("loop", "Loop", vec![], "check"));
+        ruleset.add_step(Step::action("missing", "Missing", vec![], "gone"));
+        ruleset.add_step(Step::terminal("done", "Done", TerminalResult::new("DONE")));
+        ruleset.config.max_depth = 7;
+        ruleset.config.field_missing = FieldMissingBehavior::Strict;
+        ruleset.compile().unwrap();
+
+        let inputs: Vec<Value> = (0..batch.num_rows())
+            .map(|row| row_value(&batch, row).unwrap())
+            .collect();
+        let expected = RuleExecutor::new().execute_batch(&ruleset, inputs, false);
+        let expected = ColumnarExecutionResult::from_batch_result(&expected).unwrap();
+
+        let actual = ColumnarExecutor::new().execute(&ruleset, &batch).unwrap();
+        assert_same(&actual, &expected);
+        assert_eq!(actual.failed, batch.num_rows());
+    }
+
+    #[test]
+    fn test_output_columns() {
+        let column = output_column(vec![Some(Value::Int(1)), None, Some(Value::Null)]).unwrap();
+        assert_eq!(column.data_type(), &arrow_schema::DataType::Int64);
+        assert_eq!(column.null_count(), 2);
+
+        let mixed = output_column(vec![Some(Value::Int(1)), Some(Value::string("a"))]).unwrap();
+        let mixed = mixed.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(mixed.value(0), "1");
+        assert_eq!(mixed.value(1), "\"a\"");
+
+        let empty = RecordBatch::new_empty(Arc::new(Schema::empty()));
+        let result = ColumnarExecutor::new()
+            .execute(&scoring_rules(), &empty)
+            .unwrap();
+        assert_eq!(result.total, 0);
+        assert_eq!(result.outputs.num_columns(), 0);
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Provides binary format support for compiled rulesets to protect rule logic.
 
-use crate::context::Value;
+use crate::context::{IString, Value, VariableLayout};
 use crate::error::{OrdoError, Result};
 use crate::expr::CompiledExpr;
 #[cfg(feature = "signature")]
@@ -140,6 +140,12 @@ pub struct CompiledRuleSet {
     pub string_pool: Vec<String>,
     pub signature: Option<CompiledSignature>,
     step_index: HashMap<u32, usize>,
+    /// Shared copies of the string pool, used as output object keys
+    keys: Vec<IString>,
+    /// Slot layout of the variables set by `SetVariable` actions
+    variable_layout: Arc<VariableLayout>,
+    /// Variable slot by string pool index (for variable names)
+    variable_slots: Vec<Option<u32>>,
 }
 
 #[derive(Debug, Clone)]
@@ -164,6 +170,9 @@ impl CompiledRuleSet {
             string_pool,
             signature: None,
             step_index: HashMap::new(),
+            keys: Vec::new(),
+            variable_layout: Arc::default(),
+            variable_slots: Vec::new(),
         };
         ruleset.rebuild_index();
         ruleset
@@ -191,11 +200,65 @@ impl CompiledRuleSet {
             .ok_or_else(|| OrdoError::parse_error("String pool index out of range"))
     }
 
+    /// Shared key for string pool entry `index`
+    #[inline]
+    pub fn get_key(&self, index: u32) -> Result<IString> {
+        self.keys
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| OrdoError::parse_error("String pool index out of range"))
+    }
+
+    /// Slot layout of the variables set by this ruleset
+    #[inline]
+    pub fn variable_layout(&self) -> &Arc<VariableLayout> {
+        &self.variable_layout
+    }
+
+    /// Variable slot for the name at string pool entry `name`
+    #[inline]
+    pub fn variable_slot(&self, name: u32) -> Option<usize> {
+        self.variable_slots
+            .get(name as usize)
+            .copied()
+            .flatten()
+            .map(|slot| slot as usize)
+    }
+
+    /// Rebuild the step index, keys and variable slots
+    ///
+    /// Must be called after modifying `steps` or `string_pool`.
     pub fn rebuild_index(&mut self) {
         self.step_index.clear();
         for (idx, step) in self.steps.iter().enumerate() {
             self.step_index.insert(step.id_hash(), idx);
         }
+
+        self.keys = self
+            .string_pool
+            .iter()
+            .map(|s| IString::from(s.as_str()))
+            .collect();
+
+        let mut layout = VariableLayout::default();
+        let mut slots = vec![None; self.string_pool.len()];
+        let actions = self.steps.iter().flat_map(|step| match step {
+            CompiledStep::Decision { branches, .. } => branches
+                .iter()
+                .flat_map(|branch| &branch.actions)
+                .collect::<Vec<_>>(),
+            CompiledStep::Action { actions, .. } => actions.iter().collect(),
+            CompiledStep::Terminal { .. } => Vec::new(),
+        });
+        for action in actions {
+            if let CompiledAction::SetVariable { name, .. } = action {
+                if let Some(name_str) = self.string_pool.get(*name as usize) {
+                    slots[*name as usize] = Some(layout.insert(name_str) as u32);
+                }
+            }
+        }
+        self.variable_slots = slots;
+        self.variable_layout = Arc::new(layout);
     }
 
     pub fn serialize(&self) -> Vec<u8> {
@@ -1083,6 +1146,22 @@ mod tests {
         assert_eq!(result.code, "ADULT");
     }
 
+    #[test]
+    fn test_compiled_ruleset_variable_slots() {
+        let compiled = RuleSetCompiler::compile(&build_ruleset()).unwrap();
+        let decoded = CompiledRuleSet::deserialize(&compiled.serialize()).unwrap();
+
+        let name = decoded
+            .string_pool
+            .iter()
+            .position(|s| s == "discount")
+            .unwrap() as u32;
+        assert_eq!(decoded.variable_layout().names().len(), 1);
+        assert_eq!(decoded.variable_slot(name), Some(0));
+        assert_eq!(decoded.variable_slot(decoded.metadata.name), None);
+        assert_eq!(&*decoded.get_key(name).unwrap(), "discount");
+    }
+
     #[test]
     fn test_compiled_ruleset_invalid_magic() {
         let mut bytes = b"XXXX".to_vec();
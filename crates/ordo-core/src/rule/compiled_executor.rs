@@ -4,8 +4,10 @@ use super::compiled::{
     CompiledAction, CompiledCondition, CompiledRuleSet, CompiledStep, FIELD_MISSING_LENIENT,
 };
 use super::metrics::{MetricSink, NoOpMetricSink};
-use super::{ExecutionResult, TerminalResult};
-use crate::context::{Context, IString, Value};
+use super::observer::{ActionRef, Both, ExecutionObserver, StepId, StepRef};
+use super::{ExecutionOptions, ExecutionResult};
+use crate::budget::BudgetScope;
+use crate::context::{Context, IString, PooledContext, Value};
 use crate::error::{OrdoError, Result};
 use crate::expr::BytecodeVM;
 use std::sync::Arc;
@@ -36,6 +38,7 @@ use wasm_time::Instant;
 pub struct CompiledRuleExecutor {
     vm: BytecodeVM,
     metric_sink: Arc<dyn MetricSink>,
+    observer: Option<Arc<dyn ExecutionObserver>>,
 }
 
 impl Default for CompiledRuleExecutor {
@@ -49,6 +52,7 @@ impl CompiledRuleExecutor {
         Self {
             vm: BytecodeVM::new(),
             metric_sink: Arc::new(NoOpMetricSink),
+            observer: None,
         }
     }
 
@@ -56,35 +60,114 @@ impl CompiledRuleExecutor {
         Self {
             vm: BytecodeVM::new(),
             metric_sink,
+            observer: None,
         }
     }
 
+    /// Set an observer notified during every execution
+    ///
+    /// Compiled rule sets only keep hashes of step IDs, so observers see
+    /// steps as [`StepId::Hashed`].
+    pub fn set_observer(&mut self, observer: Arc<dyn ExecutionObserver>) {
+        self.observer = Some(observer);
+    }
+
     pub fn execute(&self, ruleset: &CompiledRuleSet, input: Value) -> Result<ExecutionResult> {
-        let start_time = Instant::now();
-        let mut ctx = Context::new(input);
+        self.execute_with_options(ruleset, input, None)
+    }
+
+    /// Execute with runtime options
+    ///
+    /// Honours the timeout, depth, budget and observer options. Compiled rule
+    /// sets don't record traces or open spans.
+    pub fn execute_with_options(
+        &self,
+        ruleset: &CompiledRuleSet,
+        input: Value,
+        options: Option<&ExecutionOptions>,
+    ) -> Result<ExecutionResult> {
+        let timeout_ms = options
+            .and_then(|o| o.timeout_ms)
+            .filter(|&t| t > 0)
+            .unwrap_or(ruleset.metadata.timeout_ms);
+        let max_depth = options
+            .and_then(|o| o.max_depth)
+            .unwrap_or(ruleset.metadata.max_depth as usize);
+        let _budget = options
+            .and_then(|o| o.budget.as_ref())
+            .map(BudgetScope::enter);
+        let both;
+        let observer = match (
+            self.observer.as_deref(),
+            options.and_then(|o| o.observer.as_deref()),
+        ) {
+            (Some(first), Some(second)) => {
+                both = Both(first, second);
+                Some(&both as &dyn ExecutionObserver)
+            }
+            (first, second) => first.or(second),
+        };
+
         let mut current_step = ruleset.entry_step;
+        match observer {
+            Some(observer) => {
+                let name = ruleset.get_string(ruleset.metadata.name)?;
+                let result = self.run(
+                    ruleset,
+                    input,
+                    (timeout_ms, max_depth),
+                    &mut current_step,
+                    Some((observer, name)),
+                );
+                if let Err(e) = &result {
+                    observer.error(step_ref(name, current_step), e);
+                }
+                result
+            }
+            None => self.run(
+                ruleset,
+                input,
+                (timeout_ms, max_depth),
+                &mut current_step,
+                None,
+            ),
+        }
+    }
+
+    /// Run the steps of `ruleset`, keeping `current_step` up to date
+    fn run(
+        &self,
+        ruleset: &CompiledRuleSet,
+        input: Value,
+        (timeout_ms, max_depth): (u64, usize),
+        current_step: &mut u32,
+        observer: Option<(&dyn ExecutionObserver, &str)>,
+    ) -> Result<ExecutionResult> {
+        let start_time = Instant::now();
+        let mut ctx = PooledContext::acquire(input, Some(ruleset.variable_layout().clone()));
         let mut depth = 0usize;
 
         loop {
             // Amortized timeout: skip the first 16 steps, then check every 16 steps.
             // Avoids Instant::elapsed() syscall overhead for short rules.
-            if ruleset.metadata.timeout_ms > 0
+            if timeout_ms > 0
                 && depth >= 16
                 && depth & 15 == 0
-                && start_time.elapsed().as_millis() as u64 >= ruleset.metadata.timeout_ms
+                && start_time.elapsed().as_millis() as u64 >= timeout_ms
             {
-                return Err(OrdoError::Timeout {
-                    timeout_ms: ruleset.metadata.timeout_ms,
-                });
+                return Err(OrdoError::Timeout { timeout_ms });
             }
 
-            if depth >= ruleset.metadata.max_depth as usize {
-                return Err(OrdoError::MaxDepthExceeded {
-                    max_depth: ruleset.metadata.max_depth as usize,
-                });
+            if depth >= max_depth {
+                return Err(OrdoError::MaxDepthExceeded { max_depth });
             }
 
-            let step = ruleset.get_step(current_step)?;
+            let step = ruleset.get_step(*current_step)?;
+            let site = observer.map(|(observer, name)| {
+                let site = step_ref(name, *current_step);
+                observer.before_step(site);
+                (observer, site)
+            });
             match step {
                 CompiledStep::Decision {
                     branches,
@@ -92,14 +175,24 @@ impl CompiledRuleExecutor {
                     ..
                 } => {
                     let mut matched = false;
-                    for branch in branches {
+                    for (index, branch) in branches.iter().enumerate() {
                         let condition =
                             self.evaluate_condition(ruleset, &branch.condition, &ctx)?;
+                        if let Some((observer, site)) = site {
+                            observer.branch_evaluated(site, index, condition);
+                        }
                         if condition {
-                            for action in &branch.actions {
-                                self.execute_action(ruleset, action, &mut ctx)?;
+                            self.execute_actions(
+                                ruleset,
+                                &branch.actions,
+                                Some(index),
+                                site,
+                                &mut ctx,
+                            )?;
+                            if let Some((observer, site)) = site {
+                                observer.after_step(site);
                             }
-                            current_step = branch.next_step;
+                            *current_step = branch.next_step;
                             matched = true;
                             break;
                         }
@@ -109,7 +202,10 @@ impl CompiledRuleExecutor {
                         continue;
                     }
                     if let Some(next) = default_next {
-                        current_step = *next;
+                        if let Some((observer, site)) = site {
+                            observer.after_step(site);
+                        }
+                        *current_step = *next;
                         depth += 1;
                         continue;
                     }
@@ -120,10 +216,11 @@ impl CompiledRuleExecutor {
                 CompiledStep::Action {
                     actions, next_step, ..
                 } => {
-                    for action in actions {
-                        self.execute_action(ruleset, action, &mut ctx)?;
+                    self.execute_actions(ruleset, actions, None, site, &mut ctx)?;
+                    if let Some((observer, site)) = site {
+                        observer.after_step(site);
                     }
-                    current_step = *next_step;
+                    *current_step = *next_step;
                     depth += 1;
                 }
                 CompiledStep::Terminal {
@@ -133,16 +230,15 @@ impl CompiledRuleExecutor {
                     data,
                     ..
                 } => {
-                    let result = TerminalResult {
-                        code: ruleset.get_string(*code)?.to_string(), // Code needs to be owned per interface
-                        message: ruleset.get_string(*message)?.to_string(),
-                        output: Vec::new(),
-                        data: data.clone(),
-                    };
-                    let output = self.build_output(ruleset, outputs, &result, &ctx)?;
+                    let output = self.build_output(ruleset, outputs, data, &ctx)?;
+                    let code = ruleset.get_string(*code)?;
+                    if let Some((observer, site)) = site {
+                        observer.terminal_reached(site, code, &output);
+                        observer.after_step(site);
+                    }
                     return Ok(ExecutionResult {
-                        code: result.code,
-                        message: result.message,
+                        code: code.to_string(), // Code needs to be owned per interface
+                        message: ruleset.get_string(*message)?.to_string(),
                         output,
                         trace: None,
                         duration_us: start_time.elapsed().as_micros() as u64,
@@ -178,10 +274,29 @@ impl CompiledRuleExecutor {
         }
     }
 
+    /// Execute the actions of a step or of one of its branches
+    fn execute_actions(
+        &self,
+        ruleset: &CompiledRuleSet,
+        actions: &[CompiledAction],
+        branch: Option<usize>,
+        site: Option<(&dyn ExecutionObserver, StepRef<'_>)>,
+        ctx: &mut Context,
+    ) -> Result<()> {
+        for (index, action) in actions.iter().enumerate() {
+            self.execute_action(ruleset, action, site, ctx)?;
+            if let Some((observer, site)) = site {
+                observer.action_executed(site, ActionRef { branch, index });
+            }
+        }
+        Ok(())
+    }
+
     fn execute_action(
         &self,
         ruleset: &CompiledRuleSet,
         action: &CompiledAction,
+        site: Option<(&dyn ExecutionObserver, StepRef<'_>)>,
         ctx: &mut Context,
     ) -> Result<()> {
         match action {
@@ -191,8 +306,13 @@ impl CompiledRuleExecutor {
                     .get(*value as usize)
                     .ok_or_else(|| OrdoError::parse_error("Expression index out of range"))?;
                 let val = self.vm.execute(expr, ctx)?;
-                let name = ruleset.get_string(*name)?;
-                ctx.set_variable(name, val);
+                if let Some((observer, site)) = site {
+                    observer.variable_set(site, ruleset.get_string(*name)?, &val);
+                }
+                match ruleset.variable_slot(*name) {
+                    Some(slot) => ctx.set_slot(slot, val),
+                    None => ctx.set_variable(ruleset.get_string(*name)?, val),
+                }
             }
             CompiledAction::Log { message, level } => {
                 let msg = ruleset.get_string(*message)?;
@@ -244,10 +364,10 @@ impl CompiledRuleExecutor {
         &self,
         ruleset: &CompiledRuleSet,
         outputs: &[super::compiled::CompiledOutput],
-        result: &TerminalResult,
+        data: &Value,
         ctx: &Context,
     ) -> Result<Value> {
-        let data_len = match &result.data {
+        let data_len = match data {
             Value::Object(map) => map.len(),
             _ => 0,
         };
@@ -260,11 +380,10 @@ impl CompiledRuleExecutor {
                 .get(item.expr as usize)
                 .ok_or_else(|| OrdoError::parse_error("Expression index out of range"))?;
             let value = self.vm.execute(expr, ctx)?;
-            let key = ruleset.get_string(item.key)?;
-            output.insert(Arc::from(key), value);
+            output.insert(ruleset.get_key(item.key)?, value);
         }
 
-        if let Value::Object(data) = &result.data {
+        if let Value::Object(data) = data {
             for (k, v) in data {
                 output.insert(k.clone(), v.clone());
             }
@@ -273,3 +392,12 @@ impl CompiledRuleExecutor {
         Ok(Value::object_optimized(output))
     }
 }
+
+/// A step of a compiled rule set, for observers
+#[inline]
+fn step_ref(ruleset: &str, step: u32) -> StepRef<'_> {
+    StepRef {
+        ruleset,
+        id: StepId::Hashed(step),
+    }
+}
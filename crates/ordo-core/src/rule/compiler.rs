@@ -8,12 +8,17 @@ use super::model::{FieldMissingBehavior, RuleSet};
 use super::step::{ActionKind, Condition, LogLevel, StepKind, TerminalResult};
 use crate::context::Value;
 use crate::error::{OrdoError, Result};
-use crate::expr::{Expr, ExprCompiler, ExprParser};
+use crate::expr::{precompile_regexes, Expr, ExprCompiler, ExprParser};
 use std::collections::HashMap;
 
 pub struct RuleSetCompiler;
 
 impl RuleSetCompiler {
+    /// Hash of a step ID, as compiled rule sets identify steps
+    pub fn step_hash(step_id: &str) -> u32 {
+        hash_step_id(step_id)
+    }
+
     pub fn compile(ruleset: &RuleSet) -> Result<CompiledRuleSet> {
         let mut string_pool = StringPool::new();
         let metadata = CompiledMetadata {
@@ -137,21 +142,24 @@ fn compile_condition(
     match condition {
         Condition::Always => Ok(CompiledCondition::Always),
         Condition::Expression(expr) => {
-            let idx = compile_expr(expr, expressions);
+            let idx = compile_expr(expr, expressions)?;
             Ok(CompiledCondition::Expr(idx))
         }
         Condition::ExpressionString(s) => {
             let expr = ExprParser::parse(s)?;
-            let idx = compile_expr(&expr, expressions);
+            let idx = compile_expr(&expr, expressions)?;
             Ok(CompiledCondition::Expr(idx))
         }
     }
 }
 
-fn compile_expr(expr: &Expr, expressions: &mut Vec<crate::expr::CompiledExpr>) -> u32 {
+fn compile_expr(expr: &Expr, expressions: &mut Vec<crate::expr::CompiledExpr>) -> Result<u32> {
+    // Literal regex patterns live in the constant pool; compile them now so
+    // invalid patterns fail here rather than per execution
+    precompile_regexes(expr)?;
     let compiled = ExprCompiler::new().compile(expr);
     expressions.push(compiled);
-    (expressions.len() - 1) as u32
+    Ok((expressions.len() - 1) as u32)
 }
 
 fn compile_actions(
@@ -164,7 +172,7 @@ fn compile_actions(
         match &action.kind {
             ActionKind::SetVariable { name, value } => {
                 let name_idx = string_pool.intern(name);
-                let expr_idx = compile_expr(value, expressions);
+                let expr_idx = compile_expr(value, expressions)?;
                 compiled.push(CompiledAction::SetVariable {
                     name: name_idx,
                     value: expr_idx,
@@ -179,7 +187,7 @@ fn compile_actions(
             }
             ActionKind::Metric { name, value, tags } => {
                 let name_idx = string_pool.intern(name);
-                let expr_idx = compile_expr(value, expressions);
+                let expr_idx = compile_expr(value, expressions)?;
                 let mut compiled_tags = Vec::with_capacity(tags.len());
                 for (k, v) in tags {
                     compiled_tags.push((string_pool.intern(k), string_pool.intern(v)));
@@ -222,7 +230,7 @@ fn compile_terminal(
     let mut outputs = Vec::with_capacity(result.output.len());
     for (key, expr) in &result.output {
         let key_idx = string_pool.intern(key);
-        let expr_idx = compile_expr(expr, expressions);
+        let expr_idx = compile_expr(expr, expressions)?;
         outputs.push(CompiledOutput {
             key: key_idx,
             expr: expr_idx,
@@ -256,7 +264,7 @@ fn log_level_tag(value: LogLevel) -> u8 {
 /// Hash step_id using FNV-1a algorithm.
 /// Note: This is a 32-bit hash, collision is possible but unlikely for typical step counts.
 /// For production use with many steps, consider using a collision detection mechanism.
-fn hash_step_id(value: &str) -> u32 {
+pub(crate) fn hash_step_id(value: &str) -> u32 {
     let mut hash: u32 = 0x811C9DC5;
     for byte in value.as_bytes() {
         hash ^= u32::from(*byte);
@@ -4,14 +4,29 @@
 
 use super::metrics::{MetricSink, NoOpMetricSink};
 use super::model::{FieldMissingBehavior, RuleSet};
+use super::observer::{ActionRef, Both, ExecutionObserver, StepId, StepRef};
+#[cfg(not(target_arch = "wasm32"))]
+use super::optimizer::condition_key;
+use super::optimizer::{ExecutionPlan, PlannedCondition, TempState};
+use super::spans;
 use super::step::{ActionKind, Condition, LogLevel, Step, StepKind, TerminalResult};
-use crate::context::{Context, Value};
+use crate::budget::{BudgetScope, ResourceBudget};
+use crate::context::{intern, Context, PooledContext, Value};
 use crate::error::{OrdoError, Result};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::expr::Profiler;
 use crate::expr::{Evaluator, ExprParser};
 use crate::trace::{ExecutionTrace, StepTrace, TraceConfig};
 use rayon::prelude::*;
 use std::sync::Arc;
 
+#[cfg(not(target_arch = "wasm32"))]
+use super::tiering::{TierFrame, TierManager, TieringConfig};
+
+/// Tiered execution is not available on WASM
+#[cfg(target_arch = "wasm32")]
+struct TierFrame<'a>(std::marker::PhantomData<&'a ()>);
+
 // Use web_time for WASM, std::time for native
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
@@ -45,7 +60,7 @@ use wasm_time::Instant;
 /// Runtime execution options that can override RuleSet config.
 ///
 /// This allows passing execution-specific options without cloning the entire RuleSet.
-#[derive(Debug, Clone, Default)]
+#[derive(Clone, Default)]
 pub struct ExecutionOptions {
     /// Override timeout in milliseconds (0 = use RuleSet config)
     pub timeout_ms: Option<u64>,
@@ -53,6 +68,25 @@ pub struct ExecutionOptions {
     pub enable_trace: Option<bool>,
     /// Override max execution depth
     pub max_depth: Option<usize>,
+    /// Resource budget for this execution (fuel, memory, value sizes, regex size)
+    pub budget: Option<ResourceBudget>,
+    /// Open a tracing span per step, `CallRuleSet` and external call
+    pub spans: bool,
+    /// Observer notified during this execution, in addition to the executor's
+    pub observer: Option<Arc<dyn ExecutionObserver>>,
+}
+
+impl std::fmt::Debug for ExecutionOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutionOptions")
+            .field("timeout_ms", &self.timeout_ms)
+            .field("enable_trace", &self.enable_trace)
+            .field("max_depth", &self.max_depth)
+            .field("budget", &self.budget)
+            .field("spans", &self.spans)
+            .field("observer", &self.observer.is_some())
+            .finish()
+    }
 }
 
 impl ExecutionOptions {
@@ -78,6 +112,30 @@ impl ExecutionOptions {
         self.enable_trace = Some(enabled);
         self
     }
+
+    /// Set the resource budget
+    #[inline]
+    pub fn budget(mut self, budget: ResourceBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Open tracing spans for this execution
+    ///
+    /// Set this when the caller's trace is sampled; see the `spans` module
+    /// docs for the spans and their attributes.
+    #[inline]
+    pub fn spans(mut self, enabled: bool) -> Self {
+        self.spans = enabled;
+        self
+    }
+
+    /// Set an observer for this execution
+    #[inline]
+    pub fn observer(mut self, observer: Arc<dyn ExecutionObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
 }
 
 /// Rule executor
@@ -90,8 +148,18 @@ pub struct RuleExecutor {
     metric_sink: Arc<dyn MetricSink>,
     /// Optional resolver for CallRuleSet actions
     resolver: Option<Arc<dyn super::RuleSetResolver>>,
+    /// Optional handler for ExternalCall actions
+    external_handler: Option<Arc<dyn super::ExternalCallHandler>>,
+    /// Observer notified during every execution
+    observer: Option<Arc<dyn ExecutionObserver>>,
     /// Maximum nesting depth for CallRuleSet (prevents unbounded recursion)
     max_call_depth: usize,
+    /// Tiered execution for compiled conditions (disabled by default)
+    #[cfg(not(target_arch = "wasm32"))]
+    tiering: Option<Arc<TierManager>>,
+    /// Profiler recording rule paths and condition timings (disabled by default)
+    #[cfg(not(target_arch = "wasm32"))]
+    profiler: Option<Arc<Profiler>>,
 }
 
 impl Default for RuleExecutor {
@@ -108,7 +176,13 @@ impl RuleExecutor {
             trace_config: TraceConfig::default(),
             metric_sink: Arc::new(NoOpMetricSink),
             resolver: None,
+            external_handler: None,
+            observer: None,
             max_call_depth: 10,
+            #[cfg(not(target_arch = "wasm32"))]
+            tiering: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiler: None,
         }
     }
 
@@ -119,7 +193,13 @@ impl RuleExecutor {
             trace_config,
             metric_sink: Arc::new(NoOpMetricSink),
             resolver: None,
+            external_handler: None,
+            observer: None,
             max_call_depth: 10,
+            #[cfg(not(target_arch = "wasm32"))]
+            tiering: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiler: None,
         }
     }
 
@@ -130,7 +210,13 @@ impl RuleExecutor {
             trace_config: TraceConfig::default(),
             metric_sink,
             resolver: None,
+            external_handler: None,
+            observer: None,
             max_call_depth: 10,
+            #[cfg(not(target_arch = "wasm32"))]
+            tiering: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiler: None,
         }
     }
 
@@ -144,7 +230,13 @@ impl RuleExecutor {
             trace_config,
             metric_sink,
             resolver: None,
+            external_handler: None,
+            observer: None,
             max_call_depth: 10,
+            #[cfg(not(target_arch = "wasm32"))]
+            tiering: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            profiler: None,
         }
     }
 
@@ -153,16 +245,72 @@ impl RuleExecutor {
         self.resolver = Some(resolver);
     }
 
+    /// Set a handler for ExternalCall actions
+    ///
+    /// Without one, ExternalCall actions are skipped with a warning.
+    pub fn set_external_handler(&mut self, handler: Arc<dyn super::ExternalCallHandler>) {
+        self.external_handler = Some(handler);
+    }
+
+    /// Set an observer notified during every execution
+    ///
+    /// An observer passed in `ExecutionOptions` is notified as well.
+    pub fn set_observer(&mut self, observer: Arc<dyn ExecutionObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Get the observer, if one is set
+    pub fn observer(&self) -> Option<&Arc<dyn ExecutionObserver>> {
+        self.observer.as_ref()
+    }
+
     /// Get the metric sink
     pub fn metric_sink(&self) -> &Arc<dyn MetricSink> {
         &self.metric_sink
     }
 
+    /// Get the expression evaluator
+    pub fn evaluator(&self) -> &Evaluator {
+        &self.evaluator
+    }
+
     /// Get evaluator for customization
     pub fn evaluator_mut(&mut self) -> &mut Evaluator {
         &mut self.evaluator
     }
 
+    /// Enable tiered execution of decision conditions
+    ///
+    /// Compiled conditions (see `RuleSet::compile`) start interpreted and are
+    /// promoted to bytecode and then to JIT as they get hot. Results are
+    /// identical on every tier.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn enable_tiering(&mut self, config: TieringConfig) {
+        self.tiering = Some(Arc::new(TierManager::new(config)));
+    }
+
+    /// Get the tier manager, if tiered execution is enabled
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tiering(&self) -> Option<&TierManager> {
+        self.tiering.as_deref()
+    }
+
+    /// Record execution profiles into a profiler
+    ///
+    /// Every successful execution records its path of step IDs, and every
+    /// branch condition its evaluation time. `RuleSetOptimizer` reads these
+    /// profiles to order branches by selectivity and cost.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_profiler(&mut self, profiler: Arc<Profiler>) {
+        self.profiler = Some(profiler);
+    }
+
+    /// Get the profiler, if profiling is enabled
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn profiler(&self) -> Option<&Arc<Profiler>> {
+        self.profiler.as_ref()
+    }
+
     /// Execute a rule set
     #[inline]
     pub fn execute(&self, ruleset: &RuleSet, input: Value) -> Result<ExecutionResult> {
@@ -198,6 +346,24 @@ impl RuleExecutor {
         let enable_trace = options
             .and_then(|o| o.enable_trace)
             .unwrap_or(ruleset.config.enable_trace);
+        let _budget = options
+            .and_then(|o| o.budget.as_ref())
+            .map(BudgetScope::enter);
+        let both;
+        let observer = match (
+            self.observer.as_deref(),
+            options.and_then(|o| o.observer.as_deref()),
+        ) {
+            (Some(first), Some(second)) => {
+                both = Both(first, second);
+                Some(&both as &dyn ExecutionObserver)
+            }
+            (first, second) => first.or(second),
+        };
+        let hooks = Hooks {
+            spans: options.is_some_and(|o| o.spans),
+            observer,
+        };
 
         self.execute_internal(
             ruleset,
@@ -205,11 +371,13 @@ impl RuleExecutor {
             timeout_ms,
             max_depth,
             enable_trace,
+            hooks,
             self.max_call_depth,
         )
     }
 
     /// Internal execute implementation with explicit config parameters
+    #[allow(clippy::too_many_arguments)]
     fn execute_internal(
         &self,
         ruleset: &RuleSet,
@@ -217,10 +385,11 @@ impl RuleExecutor {
         timeout_ms: u64,
         max_depth: usize,
         enable_trace: bool,
+        hooks: Hooks<'_>,
         remaining_call_depth: usize,
     ) -> Result<ExecutionResult> {
         let start_time = Instant::now();
-        let mut ctx = Context::new(input);
+        let mut ctx = PooledContext::acquire(input, Some(ruleset.variable_layout()));
         let tracing = self.trace_config.enabled || enable_trace;
         let mut trace = if tracing {
             Some(ExecutionTrace::new(&ruleset.config.name))
@@ -231,7 +400,25 @@ impl RuleExecutor {
         let mut current_step_id = ruleset.config.entry_step.as_str();
         let mut depth: usize = 0;
 
-        loop {
+        #[cfg(not(target_arch = "wasm32"))]
+        let frame = self.tiering.as_ref().map(|tiering| {
+            tiering.frame(ruleset.config.tenant_id.as_deref(), &ruleset.config.name)
+        });
+        #[cfg(target_arch = "wasm32")]
+        let frame: Option<TierFrame<'_>> = None;
+
+        let plan = ruleset.plan.as_deref();
+        let mut run = Run {
+            ruleset,
+            frame,
+            plan,
+            temps: plan.map_or_else(Vec::new, |plan| vec![TempState::Pending; plan.temps.len()]),
+            hooks,
+            #[cfg(not(target_arch = "wasm32"))]
+            path: self.profiler.as_ref().map(|_| Vec::new()),
+        };
+
+        let result = (|| loop {
             // Amortized timeout: skip the first 16 steps entirely, then check every 16 steps.
             // Rationale: 16 steps at ~100ns each = ~1.6µs worst-case detection delay,
             // negligible vs a 5000ms timeout. This eliminates syscall overhead for short rules
@@ -257,24 +444,40 @@ impl RuleExecutor {
                         step_id: current_step_id.to_string(),
                     })?;
 
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(path) = &mut run.path {
+                path.push(step.id.clone());
+            }
+
+            if let Some(observer) = hooks.observer {
+                observer.before_step(run.step_ref(&step.id));
+            }
+
             // Execute step — branch on tracing to avoid Instant syscalls in the hot path.
-            // When tracing is off (default), zero Instant calls per step.
-            let (step_result, step_duration) = if tracing {
+            // When tracing and spans are off (default), zero Instant calls per step.
+            let (step_result, step_duration) = if hooks.spans {
+                let span = spans::step(step);
                 let step_start = Instant::now();
-                let result = self.execute_step(
-                    step,
-                    &mut ctx,
-                    &ruleset.config.field_missing,
-                    remaining_call_depth,
-                )?;
+                let result = span
+                    .in_scope(|| self.execute_step(&mut run, step, &mut ctx, remaining_call_depth));
+                let duration = step_start.elapsed().as_micros() as u64;
+                spans::finish(&span, duration, &result);
+                match &result {
+                    Ok(StepResult::Continue { next_step }) => {
+                        span.record("ordo.next_step", *next_step);
+                    }
+                    Ok(StepResult::Terminal { result }) => {
+                        span.record("ordo.result.code", result.code.as_str());
+                    }
+                    Err(_) => {}
+                }
+                (result?, duration)
+            } else if tracing {
+                let step_start = Instant::now();
+                let result = self.execute_step(&mut run, step, &mut ctx, remaining_call_depth)?;
                 (result, step_start.elapsed().as_micros() as u64)
             } else {
-                let result = self.execute_step(
-                    step,
-                    &mut ctx,
-                    &ruleset.config.field_missing,
-                    remaining_call_depth,
-                )?;
+                let result = self.execute_step(&mut run, step, &mut ctx, remaining_call_depth)?;
                 (result, 0)
             };
 
@@ -288,7 +491,7 @@ impl RuleExecutor {
                             st.input_snapshot = Some(ctx.data().clone());
                         }
                         if self.trace_config.capture_variables {
-                            st.variables_snapshot = Some(ctx.variables().clone());
+                            st.variables_snapshot = Some(ctx.variables());
                         }
                         st
                     }
@@ -298,7 +501,7 @@ impl RuleExecutor {
                             st.input_snapshot = Some(ctx.data().clone());
                         }
                         if self.trace_config.capture_variables {
-                            st.variables_snapshot = Some(ctx.variables().clone());
+                            st.variables_snapshot = Some(ctx.variables());
                         }
                         st
                     }
@@ -309,21 +512,38 @@ impl RuleExecutor {
             // Handle step result
             match step_result {
                 StepResult::Continue { next_step } => {
+                    if let Some(observer) = hooks.observer {
+                        observer.after_step(run.step_ref(&step.id));
+                    }
                     current_step_id = next_step;
                     depth += 1;
                 }
                 StepResult::Terminal { result } => {
                     let output = self.build_output(result, &ctx)?;
+                    if let Some(observer) = hooks.observer {
+                        let step = run.step_ref(&step.id);
+                        observer.terminal_reached(step, &result.code, &output);
+                        observer.after_step(step);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let (Some(profiler), Some(path)) = (&self.profiler, &run.path) {
+                        profiler.record_rule_path(&ruleset.config.name, path, start_time.elapsed());
+                    }
                     return Ok(ExecutionResult {
                         code: result.code.clone(),
                         message: result.message.clone(),
                         output,
-                        trace,
+                        trace: trace.take(),
                         duration_us: start_time.elapsed().as_micros() as u64,
                     });
                 }
             }
+        })();
+
+        if let (Err(e), Some(observer)) = (&result, hooks.observer) {
+            observer.error(run.step_ref(current_step_id), e);
         }
+        result
     }
 
     /// Execute a rule set against multiple inputs (batch execution)
@@ -402,9 +622,9 @@ impl RuleExecutor {
     /// Execute a single step
     fn execute_step<'a>(
         &self,
+        run: &mut Run<'_>,
         step: &'a Step,
         ctx: &mut Context,
-        field_missing: &FieldMissingBehavior,
         remaining_call_depth: usize,
     ) -> Result<StepResult<'a>> {
         match &step.kind {
@@ -412,16 +632,63 @@ impl RuleExecutor {
                 branches,
                 default_next,
             } => {
-                // Evaluate branches in order
-                for branch in branches {
-                    let condition_result =
-                        self.evaluate_condition(&branch.condition, ctx, field_missing)?;
+                let field_missing = &run.ruleset.config.field_missing;
+                let plan = run.plan;
+                let planned = plan
+                    .and_then(|plan| plan.steps.get(&step.id))
+                    .filter(|planned| planned.order.len() == branches.len());
+
+                // Evaluate branches in order (the optimized order if planned)
+                for position in 0..branches.len() {
+                    let index = planned.map_or(position, |planned| planned.order[position]);
+                    let branch = &branches[index];
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let condition_start = self.profiler.as_ref().map(|_| Instant::now());
+
+                    let rewritten = plan.zip(planned.and_then(|p| p.conditions[index].as_ref()));
+                    let condition_result = match rewritten {
+                        Some((plan, rewritten)) => self.evaluate_planned(
+                            plan,
+                            rewritten,
+                            &branch.condition,
+                            (&step.id, index),
+                            run,
+                            ctx,
+                        )?,
+                        None => self.evaluate_condition(
+                            &branch.condition,
+                            (run.frame.as_ref(), &step.id, index),
+                            ctx,
+                            field_missing,
+                        )?,
+                    };
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let (Some(profiler), Some(start)) = (&self.profiler, condition_start) {
+                        profiler.record_expr(
+                            condition_key(&run.ruleset.config.name, &step.id, index),
+                            start.elapsed(),
+                        );
+                    }
+
+                    if let Some(observer) = run.hooks.observer {
+                        observer.branch_evaluated(run.step_ref(&step.id), index, condition_result);
+                    }
 
                     if condition_result {
-                        // Execute branch actions
-                        for action in &branch.actions {
-                            self.execute_action(action, ctx, remaining_call_depth)?;
+                        if run.hooks.spans {
+                            tracing::Span::current().record("ordo.branch.index", index as i64);
                         }
+                        // Execute branch actions
+                        self.execute_actions(
+                            &branch.actions,
+                            Some(index),
+                            run,
+                            step,
+                            ctx,
+                            remaining_call_depth,
+                        )?;
                         return Ok(StepResult::Continue {
                             next_step: branch.next_step.as_str(),
                         });
@@ -443,9 +710,7 @@ impl RuleExecutor {
 
             StepKind::Action { actions, next_step } => {
                 // Execute all actions
-                for action in actions {
-                    self.execute_action(action, ctx, remaining_call_depth)?;
-                }
+                self.execute_actions(actions, None, run, step, ctx, remaining_call_depth)?;
                 Ok(StepResult::Continue {
                     next_step: next_step.as_str(),
                 })
@@ -459,9 +724,14 @@ impl RuleExecutor {
     ///
     /// NOTE: For best performance, call `RuleSet::compile()` after loading to pre-compile
     /// all expression strings. If not compiled, expressions will be parsed on each evaluation.
+    ///
+    /// `site` identifies the branch (execution frame, step ID, branch index) for
+    /// tiered execution.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
     fn evaluate_condition(
         &self,
         condition: &Condition,
+        site: (Option<&TierFrame<'_>>, &str, usize),
         ctx: &Context,
         field_missing: &FieldMissingBehavior,
     ) -> Result<bool> {
@@ -469,6 +739,12 @@ impl RuleExecutor {
             Condition::Always => Ok(true),
 
             Condition::Expression(expr) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                if let (Some(tiering), (Some(frame), step_id, branch)) = (&self.tiering, site) {
+                    let result =
+                        tiering.eval_condition(frame, &self.evaluator, step_id, branch, expr, ctx);
+                    return Self::apply_field_missing(result, field_missing);
+                }
                 self.eval_expr_with_field_missing(expr, ctx, field_missing)
             }
 
@@ -480,6 +756,47 @@ impl RuleExecutor {
         }
     }
 
+    /// Evaluate a branch condition rewritten by `RuleSetOptimizer`
+    ///
+    /// Temporaries are computed on first use and kept for the rest of the
+    /// execution. If one fails to evaluate, the original condition runs instead,
+    /// so errors surface exactly as they would without the plan.
+    fn evaluate_planned(
+        &self,
+        plan: &ExecutionPlan,
+        rewritten: &PlannedCondition,
+        original: &Condition,
+        (step_id, branch): (&str, usize),
+        run: &mut Run<'_>,
+        ctx: &mut Context,
+    ) -> Result<bool> {
+        let field_missing = &run.ruleset.config.field_missing;
+        let mut ready = true;
+        for &temp in &rewritten.temps {
+            if run.temps[temp] == TempState::Pending {
+                run.temps[temp] = match self.evaluator.eval(&plan.temps[temp], ctx) {
+                    Ok(value) => {
+                        ctx.set_variable(plan.temp_names[temp].as_str(), value);
+                        TempState::Ready
+                    }
+                    Err(_) => TempState::Failed,
+                };
+            }
+            ready &= run.temps[temp] == TempState::Ready;
+        }
+
+        if ready {
+            self.eval_expr_with_field_missing(&rewritten.expr, ctx, field_missing)
+        } else {
+            self.evaluate_condition(
+                original,
+                (run.frame.as_ref(), step_id, branch),
+                ctx,
+                field_missing,
+            )
+        }
+    }
+
     /// Helper to evaluate expression with field missing behavior
     #[inline]
     fn eval_expr_with_field_missing(
@@ -488,8 +805,21 @@ impl RuleExecutor {
         ctx: &Context,
         field_missing: &FieldMissingBehavior,
     ) -> Result<bool> {
-        match self.evaluator.eval(expr, ctx) {
-            Ok(value) => Ok(value.is_truthy()),
+        let result = self
+            .evaluator
+            .eval(expr, ctx)
+            .map(|value| value.is_truthy());
+        Self::apply_field_missing(result, field_missing)
+    }
+
+    /// Map a missing field to `false` under lenient field handling
+    #[inline]
+    fn apply_field_missing(
+        result: Result<bool>,
+        field_missing: &FieldMissingBehavior,
+    ) -> Result<bool> {
+        match result {
+            Ok(value) => Ok(value),
             Err(OrdoError::FieldNotFound { .. })
                 if *field_missing == FieldMissingBehavior::Lenient =>
             {
@@ -499,16 +829,41 @@ impl RuleExecutor {
         }
     }
 
+    /// Execute the actions of a step or of one of its branches
+    fn execute_actions(
+        &self,
+        actions: &[super::step::Action],
+        branch: Option<usize>,
+        run: &Run<'_>,
+        step: &Step,
+        ctx: &mut Context,
+        remaining_call_depth: usize,
+    ) -> Result<()> {
+        let site = run.step_ref(&step.id);
+        for (index, action) in actions.iter().enumerate() {
+            self.execute_action(action, ctx, run.hooks, site, remaining_call_depth)?;
+            if let Some(observer) = run.hooks.observer {
+                observer.action_executed(site, ActionRef { branch, index });
+            }
+        }
+        Ok(())
+    }
+
     /// Execute an action
     fn execute_action(
         &self,
         action: &super::step::Action,
         ctx: &mut Context,
+        hooks: Hooks<'_>,
+        site: StepRef<'_>,
         remaining_call_depth: usize,
     ) -> Result<()> {
         match &action.kind {
             ActionKind::SetVariable { name, value } => {
                 let val = self.evaluator.eval(value, ctx)?;
+                if let Some(observer) = hooks.observer {
+                    observer.variable_set(site, name, &val);
+                }
                 ctx.set_variable(name, val);
             }
 
@@ -554,61 +909,181 @@ impl RuleExecutor {
                 input_mapping,
                 result_variable,
             } => {
-                if remaining_call_depth == 0 {
-                    return Err(OrdoError::eval_error(format!(
-                        "CallRuleSet max nesting depth ({}) exceeded calling '{}'",
-                        self.max_call_depth, ruleset_name
-                    )));
-                }
+                let (target, sub_input) = self.prepare_call(
+                    ruleset_name,
+                    input_mapping.as_ref(),
+                    ctx,
+                    remaining_call_depth,
+                )?;
 
-                let resolver = self.resolver.as_ref().ok_or_else(|| {
-                    OrdoError::eval_error("CallRuleSet requires a resolver to be configured")
-                })?;
-                let target =
-                    resolver
-                        .resolve(ruleset_name)
-                        .ok_or_else(|| OrdoError::RuleSetNotFound {
-                            name: ruleset_name.clone(),
-                        })?;
-
-                // Build input for the sub-ruleset
-                let sub_input = if let Some(mapping) = input_mapping {
-                    self.evaluator.eval(mapping, ctx)?
+                // Execute sub-ruleset with decremented call depth
+                let execute = || {
+                    self.execute_internal(
+                        &target,
+                        sub_input,
+                        target.config.timeout_ms,
+                        target.config.max_depth,
+                        false,
+                        hooks,
+                        remaining_call_depth - 1,
+                    )
+                };
+                let sub_result = if hooks.spans {
+                    let span = spans::call_ruleset(ruleset_name);
+                    let start = Instant::now();
+                    let result = span.in_scope(execute);
+                    spans::finish(&span, start.elapsed().as_micros() as u64, &result);
+                    if let Ok(result) = &result {
+                        span.record("ordo.result.code", result.code.as_str());
+                    }
+                    result?
                 } else {
-                    ctx.data().clone()
+                    execute()?
                 };
 
-                // Execute sub-ruleset with decremented call depth
-                let sub_result = self.execute_internal(
-                    &target,
-                    sub_input,
-                    target.config.timeout_ms,
-                    target.config.max_depth,
-                    false,
-                    remaining_call_depth - 1,
-                )?;
-
                 // Store result as a variable
-                let result_obj = Value::object({
-                    let mut m = std::collections::HashMap::new();
-                    m.insert("code".to_string(), Value::string(&sub_result.code));
-                    m.insert("message".to_string(), Value::string(&sub_result.message));
-                    m.insert("output".to_string(), sub_result.output);
-                    m
-                });
+                let result_obj = Self::call_result(sub_result);
+                if let Some(observer) = hooks.observer {
+                    observer.variable_set(site, result_variable, &result_obj);
+                }
                 ctx.set_variable(result_variable, result_obj);
             }
 
-            ActionKind::ExternalCall { .. } => {
-                // TODO: Implement external calls
-                tracing::warn!("External calls not yet implemented");
+            ActionKind::ExternalCall {
+                service,
+                method,
+                params,
+                timeout_ms,
+                result_variable,
+            } => {
+                let call = || -> Result<Option<Value>> {
+                    let mut args = std::collections::HashMap::with_capacity(params.len());
+                    for (name, expr) in params {
+                        args.insert(name.clone(), self.evaluator.eval(expr, ctx)?);
+                    }
+                    let args = Value::object(args);
+                    // Responses are recorded and replayed like clock readings;
+                    // a replay needs no handler
+                    let response = crate::replay::external_call(service, method, || {
+                        let handler = self.external_handler.as_ref()?;
+                        Some(
+                            handler
+                                .call(service, method, &args, *timeout_ms)
+                                .map_err(|e| e.to_string()),
+                        )
+                    })
+                    .map_err(|message| {
+                        OrdoError::eval_error(format!(
+                            "External call {}.{} failed: {}",
+                            service, method, message
+                        ))
+                    })?;
+                    if response.is_none() {
+                        tracing::warn!(
+                            service = %service,
+                            method = %method,
+                            "No external call handler configured, skipping call"
+                        );
+                    }
+                    Ok(response)
+                };
+                let response = if hooks.spans {
+                    let span = spans::external_call(service, method);
+                    let start = Instant::now();
+                    let result = span.in_scope(call);
+                    spans::finish_external_call(&span, start.elapsed().as_micros() as u64, &result);
+                    result?
+                } else {
+                    call()?
+                };
+
+                if let (Some(response), Some(variable)) = (response, result_variable) {
+                    if let Some(observer) = hooks.observer {
+                        observer.variable_set(site, variable, &response);
+                    }
+                    ctx.set_variable(variable, response);
+                }
             }
         }
         Ok(())
     }
 
+    /// Evaluate a branch condition outside of a run (for `RuleStepper`)
+    pub(super) fn eval_branch(
+        &self,
+        condition: &Condition,
+        ctx: &Context,
+        field_missing: &FieldMissingBehavior,
+    ) -> Result<bool> {
+        self.evaluate_condition(condition, (None, "", 0), ctx, field_missing)
+    }
+
+    /// Execute an action other than `CallRuleSet` outside of a run (for
+    /// `RuleStepper`)
+    pub(super) fn run_action(
+        &self,
+        action: &super::step::Action,
+        ctx: &mut Context,
+        site: StepRef<'_>,
+    ) -> Result<()> {
+        let hooks = Hooks {
+            spans: false,
+            observer: None,
+        };
+        self.execute_action(action, ctx, hooks, site, self.max_call_depth)
+    }
+
+    /// Maximum nesting depth for CallRuleSet
+    pub(super) fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Resolve the rule set a `CallRuleSet` action calls and build its input
+    pub(super) fn prepare_call(
+        &self,
+        ruleset_name: &str,
+        input_mapping: Option<&crate::expr::Expr>,
+        ctx: &Context,
+        remaining_call_depth: usize,
+    ) -> Result<(Arc<RuleSet>, Value)> {
+        if remaining_call_depth == 0 {
+            return Err(OrdoError::eval_error(format!(
+                "CallRuleSet max nesting depth ({}) exceeded calling '{}'",
+                self.max_call_depth, ruleset_name
+            )));
+        }
+
+        let resolver = self.resolver.as_ref().ok_or_else(|| {
+            OrdoError::eval_error("CallRuleSet requires a resolver to be configured")
+        })?;
+        let target = resolver
+            .resolve(ruleset_name)
+            .ok_or_else(|| OrdoError::RuleSetNotFound {
+                name: ruleset_name.to_string(),
+            })?;
+
+        // Build input for the sub-ruleset
+        let sub_input = if let Some(mapping) = input_mapping {
+            self.evaluator.eval(mapping, ctx)?
+        } else {
+            ctx.data().clone()
+        };
+        Ok((target, sub_input))
+    }
+
+    /// The value a `CallRuleSet` action stores in its result variable
+    pub(super) fn call_result(result: ExecutionResult) -> Value {
+        Value::object({
+            let mut m = std::collections::HashMap::new();
+            m.insert("code".to_string(), Value::string(&result.code));
+            m.insert("message".to_string(), Value::string(&result.message));
+            m.insert("output".to_string(), result.output);
+            m
+        })
+    }
+
     /// Build output from terminal result
-    fn build_output(&self, result: &TerminalResult, ctx: &Context) -> Result<Value> {
+    pub(super) fn build_output(&self, result: &TerminalResult, ctx: &Context) -> Result<Value> {
         use crate::context::IString;
 
         // Pre-allocate capacity: output expressions + static data fields
@@ -622,7 +1097,7 @@ impl RuleExecutor {
         // Evaluate output expressions
         for (key, expr) in &result.output {
             let value = self.evaluator.eval(expr, ctx)?;
-            output.insert(Arc::from(key.as_str()), value);
+            output.insert(intern(key), value);
         }
 
         // Merge with static data
@@ -636,6 +1111,42 @@ impl RuleExecutor {
     }
 }
 
+/// Per-execution state shared by the steps of one run
+struct Run<'r> {
+    ruleset: &'r RuleSet,
+    /// Tiering state (only when tiering is enabled)
+    frame: Option<TierFrame<'r>>,
+    /// Plan from `RuleSetOptimizer`, if any
+    plan: Option<&'r ExecutionPlan>,
+    /// State of each hoisted temporary of the plan
+    temps: Vec<TempState>,
+    /// Spans and observer of this execution
+    hooks: Hooks<'r>,
+    /// Step IDs visited so far (only when profiling)
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<Vec<String>>,
+}
+
+impl Run<'_> {
+    /// A step of this run's rule set, for observers
+    #[inline]
+    fn step_ref<'s>(&'s self, step_id: &'s str) -> StepRef<'s> {
+        StepRef {
+            ruleset: &self.ruleset.config.name,
+            id: StepId::Named(step_id),
+        }
+    }
+}
+
+/// Instrumentation of an execution, passed on to `CallRuleSet` sub-executions
+#[derive(Clone, Copy)]
+struct Hooks<'o> {
+    /// Open tracing spans
+    spans: bool,
+    /// Executor and per-call observers, if any
+    observer: Option<&'o dyn ExecutionObserver>,
+}
+
 /// Step execution result
 #[derive(Debug, Clone)]
 pub enum StepResult<'a> {
@@ -1105,4 +1616,79 @@ mod tests {
         let result = executor.execute(&main, input);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_execute_with_tiering() {
+        use crate::expr::ProfilerConfig;
+        use crate::rule::ExecutionTier;
+
+        let mut ruleset = create_test_ruleset();
+        ruleset.compile().unwrap();
+
+        let mut executor = RuleExecutor::new();
+        executor.enable_tiering(TieringConfig {
+            profiler: ProfilerConfig {
+                hot_threshold: 0,
+                bytecode_threshold: 2,
+                ..Default::default()
+            },
+            background_compilation: false,
+            ..Default::default()
+        });
+
+        for _ in 0..5 {
+            for (age, code) in [(25, "ADULT"), (15, "TEEN"), (10, "CHILD")] {
+                let input = serde_json::from_str(&format!(r#"{{"age": {}}}"#, age)).unwrap();
+                let result = executor.execute(&ruleset, input).unwrap();
+                assert_eq!(result.code, code);
+            }
+        }
+
+        let snapshot = executor.tiering().unwrap().snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].step_id, "check_age");
+        assert!(snapshot
+            .iter()
+            .all(|info| info.tier >= ExecutionTier::Bytecode));
+    }
+
+    #[test]
+    fn test_execute_with_budget() {
+        use crate::budget::ResourceBudget;
+        use crate::expr::ExprParser;
+
+        let mut ruleset = RuleSet::new("budget", "done");
+        ruleset.add_step(Step::terminal(
+            "done",
+            "Done",
+            TerminalResult::new("OK")
+                .with_output("count", ExprParser::parse("len(range(0, 100000))").unwrap()),
+        ));
+        let executor = RuleExecutor::new();
+
+        let result = executor
+            .execute_with_options(&ruleset, Value::Null, None)
+            .unwrap();
+        assert_eq!(result.output.get_path("count"), Some(&Value::int(100000)));
+
+        let options = ExecutionOptions::default()
+            .budget(ResourceBudget::unlimited().with_max_array_len(1000));
+        let err = executor
+            .execute_with_options(&ruleset, Value::Null, Some(&options))
+            .unwrap_err();
+        assert!(
+            matches!(err, OrdoError::BudgetExceeded { ref resource, limit: 1000 } if resource == "array_length")
+        );
+
+        let options = ExecutionOptions::default().budget(ResourceBudget::unlimited().with_fuel(3));
+        let err = executor
+            .execute_with_options(&ruleset, Value::Null, Some(&options))
+            .unwrap_err();
+        assert!(
+            matches!(err, OrdoError::BudgetExceeded { ref resource, .. } if resource == "fuel")
+        );
+
+        // The budget is released once the execution finishes
+        assert!(crate::budget::usage().is_none());
+    }
 }
@@ -0,0 +1,441 @@
+//! Field usage analysis
+//!
+//! Determines which input paths a ruleset can read, so callers can
+//! materialize only those parts of a large request payload. Paths are
+//! collected from every expression of the ruleset (conditions, actions,
+//! terminal outputs) and merged into a prefix tree: a referenced path keeps
+//! its whole subtree, so functions that inspect entire objects (`keys`,
+//! `object_get`, ...) still see everything below the field they are given.
+//!
+//! A ruleset that can observe the input as a whole, e.g. a `CallRuleSet`
+//! action without input mapping (the sub-ruleset receives the full input),
+//! requires full materialization.
+
+use super::model::RuleSet;
+use super::step::{Action, ActionKind, Condition, StepKind};
+use crate::context::Value;
+use crate::expr::ExprParser;
+use crate::filter::collect_fields;
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// A node of the referenced-path tree
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathNode {
+    /// The whole subtree below this node is referenced
+    whole: bool,
+    children: BTreeMap<String, PathNode>,
+}
+
+impl PathNode {
+    /// Whether the whole value at this node is needed
+    #[inline]
+    pub fn is_whole(&self) -> bool {
+        self.whole
+    }
+
+    /// Node for an object key below this one, if any path goes through it
+    #[inline]
+    pub fn child(&self, key: &str) -> Option<&PathNode> {
+        self.children.get(key)
+    }
+
+    fn insert(&mut self, path: &str) {
+        let mut node = self;
+        for segment in path.split('.') {
+            if node.whole {
+                return;
+            }
+            node = node.children.entry(segment.to_string()).or_default();
+        }
+        node.whole = true;
+        node.children.clear();
+    }
+
+    fn collect(&self, prefix: &str, out: &mut Vec<String>) {
+        for (key, child) in &self.children {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            if child.whole {
+                out.push(path);
+            } else {
+                child.collect(&path, out);
+            }
+        }
+    }
+
+    /// Copy of `value` restricted to the referenced paths
+    ///
+    /// Arrays are kept whole: paths address their elements by index.
+    fn project(&self, value: &Value) -> Value {
+        if self.whole {
+            return value.clone();
+        }
+        match value {
+            Value::Object(map) => Value::object_optimized(
+                map.iter()
+                    .filter_map(|(key, item)| {
+                        self.child(key)
+                            .map(|child| (key.clone(), child.project(item)))
+                    })
+                    .collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    /// Decode the referenced paths of a simd-json tape value
+    #[cfg(feature = "lazy-input")]
+    fn project_tape(&self, value: simd_json::tape::Value<'_, '_>) -> Value {
+        if self.whole {
+            return Value::from_tape(value);
+        }
+        match value.as_object() {
+            Some(map) => {
+                let mut obj = hashbrown::HashMap::new();
+                for (key, item) in map.iter() {
+                    if let Some(child) = self.child(key) {
+                        obj.insert(std::sync::Arc::from(key), child.project_tape(item));
+                    }
+                }
+                Value::object_optimized(obj)
+            }
+            None => Value::from_tape(value),
+        }
+    }
+}
+
+/// Input paths read by a ruleset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldUsage {
+    root: PathNode,
+    full: bool,
+}
+
+impl FieldUsage {
+    /// Analyze all expressions of `ruleset`
+    pub fn analyze(ruleset: &RuleSet) -> Self {
+        let mut usage = Self::default();
+        let mut fields = BTreeSet::new();
+        for step in ruleset.steps.values() {
+            match &step.kind {
+                StepKind::Decision { branches, .. } => {
+                    for branch in branches {
+                        match &branch.condition {
+                            Condition::Always => {}
+                            Condition::Expression(expr) => collect_fields(expr, &mut fields),
+                            Condition::ExpressionString(s) => match ExprParser::parse(s) {
+                                Ok(expr) => collect_fields(&expr, &mut fields),
+                                // Fails at execution anyway; stay conservative
+                                Err(_) => usage.full = true,
+                            },
+                        }
+                        for action in &branch.actions {
+                            usage.add_action(action, &mut fields);
+                        }
+                    }
+                }
+                StepKind::Action { actions, .. } => {
+                    for action in actions {
+                        usage.add_action(action, &mut fields);
+                    }
+                }
+                StepKind::Terminal { result } => {
+                    for (_, expr) in &result.output {
+                        collect_fields(expr, &mut fields);
+                    }
+                }
+            }
+        }
+        for field in &fields {
+            if let Some(path) = input_path(field) {
+                usage.root.insert(path);
+            }
+        }
+        usage
+    }
+
+    fn add_action(&mut self, action: &Action, fields: &mut BTreeSet<String>) {
+        match &action.kind {
+            ActionKind::SetVariable { value, .. } | ActionKind::Metric { value, .. } => {
+                collect_fields(value, fields)
+            }
+            ActionKind::CallRuleSet {
+                input_mapping: Some(expr),
+                ..
+            } => collect_fields(expr, fields),
+            // The sub-ruleset receives the whole input
+            ActionKind::CallRuleSet {
+                input_mapping: None,
+                ..
+            } => self.full = true,
+            ActionKind::ExternalCall { params, .. } => {
+                for (_, expr) in params {
+                    collect_fields(expr, fields);
+                }
+            }
+            ActionKind::Log { .. } => {}
+        }
+    }
+
+    /// Whether the ruleset may observe the whole input
+    #[inline]
+    pub fn requires_full_input(&self) -> bool {
+        self.full
+    }
+
+    /// Root of the referenced-path tree
+    #[inline]
+    pub fn root(&self) -> &PathNode {
+        &self.root
+    }
+
+    /// Referenced input paths (minimal prefixes, sorted)
+    pub fn paths(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.root.whole {
+            out.push(String::new());
+        } else {
+            self.root.collect("", &mut out);
+        }
+        out
+    }
+
+    /// Copy of `input` restricted to the referenced paths
+    ///
+    /// Returns a full copy when the ruleset requires the full input.
+    pub fn project(&self, input: &Value) -> Value {
+        if self.full {
+            return input.clone();
+        }
+        self.root.project(input)
+    }
+
+    /// Seed decoding an input from any serde format, materializing only the
+    /// referenced paths (everything when the full input is required)
+    ///
+    /// Unreferenced subtrees are skipped as `IgnoredAny` without being converted.
+    pub fn input_seed(&self) -> InputSeed<'_> {
+        InputSeed {
+            node: (!self.full).then_some(&self.root),
+        }
+    }
+
+    /// Decode `input` from a simd-json tape, materializing only the
+    /// referenced paths (everything when the full input is required)
+    ///
+    /// Unreferenced subtrees are skipped on the tape without being converted.
+    #[cfg(feature = "lazy-input")]
+    pub fn project_tape(&self, input: simd_json::tape::Value<'_, '_>) -> Value {
+        if self.full {
+            return Value::from_tape(input);
+        }
+        self.root.project_tape(input)
+    }
+}
+
+/// Projecting deserializer for an input value, see [`FieldUsage::input_seed`]
+#[derive(Clone, Copy)]
+pub struct InputSeed<'a> {
+    /// `None` when the whole value is needed
+    node: Option<&'a PathNode>,
+}
+
+impl<'de> DeserializeSeed<'de> for InputSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        match self.node {
+            Some(node) if !node.whole => deserializer.deserialize_any(PartialVisitor(node)),
+            _ => Value::deserialize(deserializer),
+        }
+    }
+}
+
+/// Visits a partially referenced value: objects keep referenced keys only,
+/// everything else is kept whole (as in `PathNode::project`)
+struct PartialVisitor<'a>(&'a PathNode);
+
+impl<'de> Visitor<'de> for PartialVisitor<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an input value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from_u64(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(Arc::from(v)))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut arr = Vec::new();
+        while let Some(elem) = seq.next_element()? {
+            arr.push(elem);
+        }
+        Ok(Value::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut obj = hashbrown::HashMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match self.0.child(&key) {
+                Some(child) => {
+                    let value = map.next_value_seed(InputSeed { node: Some(child) })?;
+                    obj.insert(Arc::from(key), value);
+                }
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(Value::object_optimized(obj))
+    }
+}
+
+/// Input data path of a field reference (`None` for variables and iteration state)
+fn input_path(field: &str) -> Option<&str> {
+    if field.starts_with('$') || field == "item" || field.starts_with("item.") || field == "_index"
+    {
+        return None;
+    }
+    Some(field.strip_prefix("data.").unwrap_or(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::{Step, TerminalResult};
+
+    fn ruleset() -> RuleSet {
+        let mut ruleset = RuleSet::new("usage", "check");
+        ruleset.add_step(
+            Step::decision("check", "Check")
+                .branch(
+                    Condition::from_string("user.age >= 18 && exists(user.profile)"),
+                    "done",
+                )
+                .branch(Condition::from_string("len(keys(order)) > $limit"), "done")
+                .default("done")
+                .build(),
+        );
+        ruleset.add_step(Step::terminal(
+            "done",
+            "Done",
+            TerminalResult::new("OK")
+                .with_output("tier", ExprParser::parse("data.user.profile.tier").unwrap()),
+        ));
+        ruleset
+    }
+
+    #[test]
+    fn test_analyze_paths() {
+        let usage = FieldUsage::analyze(&ruleset());
+        assert!(!usage.requires_full_input());
+        assert_eq!(usage.paths(), vec!["order", "user.age", "user.profile"]);
+        assert!(usage.root().child("order").unwrap().is_whole());
+        assert!(!usage.root().child("user").unwrap().is_whole());
+    }
+
+    #[test]
+    fn test_project() {
+        let usage = FieldUsage::analyze(&ruleset());
+        let input: Value = serde_json::from_str(
+            r#"{"user": {"age": 30, "name": "x", "profile": {"tier": "gold"}},
+                "order": {"a": 1}, "payload": [1, 2, 3]}"#,
+        )
+        .unwrap();
+        let expected: Value = serde_json::from_str(
+            r#"{"user": {"age": 30, "profile": {"tier": "gold"}}, "order": {"a": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(usage.project(&input), expected);
+    }
+
+    #[cfg(feature = "lazy-input")]
+    #[test]
+    fn test_project_tape_matches_project() {
+        let usage = FieldUsage::analyze(&ruleset());
+        let json = r#"{"user": {"age": 30, "name": "x", "profile": {"tier": "gold"}},
+                       "order": {"a": [1, 2.5, null], "n": 18446744073709551615},
+                       "payload": {"big": true}}"#;
+        let mut buf = json.as_bytes().to_vec();
+        let tape = simd_json::to_tape(&mut buf).unwrap();
+        let input: Value = serde_json::from_str(json).unwrap();
+        assert_eq!(usage.project_tape(tape.as_value()), usage.project(&input));
+        assert_eq!(Value::from_tape(tape.as_value()), input);
+    }
+
+    #[test]
+    fn test_input_seed_matches_project() {
+        let usage = FieldUsage::analyze(&ruleset());
+        let json = r#"{"user": {"age": 30, "name": "x", "profile": {"tier": "gold"}},
+                       "order": {"a": [1, 2.5, null]}, "payload": {"big": true}}"#;
+        let input: Value = serde_json::from_str(json).unwrap();
+        let mut de = serde_json::Deserializer::from_str(json);
+        let seeded = usage.input_seed().deserialize(&mut de).unwrap();
+        assert_eq!(seeded, usage.project(&input));
+    }
+
+    #[test]
+    fn test_input_seed_keeps_large_unsigned_integers() {
+        let usage = FieldUsage::analyze(&ruleset());
+        let json = format!(r#"{{"user": {{"age": {}}}, "payload": 1}}"#, u64::MAX);
+        let input: Value = serde_json::from_str(&json).unwrap();
+        let mut de = serde_json::Deserializer::from_str(&json);
+        let seeded = usage.input_seed().deserialize(&mut de).unwrap();
+        assert_eq!(seeded, usage.project(&input));
+        assert_eq!(
+            seeded.get_path("user.age"),
+            Some(&Value::Float(u64::MAX as f64))
+        );
+    }
+
+    #[test]
+    fn test_call_ruleset_requires_full_input() {
+        let mut ruleset = ruleset();
+        ruleset.add_step(Step::action(
+            "call",
+            "Call",
+            vec![Action {
+                kind: ActionKind::CallRuleSet {
+                    ruleset_name: "other".to_string(),
+                    input_mapping: None,
+                    result_variable: "sub".to_string(),
+                },
+                description: String::new(),
+            }],
+            "done",
+        ));
+        assert!(FieldUsage::analyze(&ruleset).requires_full_input());
+    }
+}
@@ -5,15 +5,33 @@
 //! - Step flow model (Decision Step, Action Step, Terminal Step)
 //! - Condition and branch definitions
 //! - Metric sink abstraction for custom metrics
+//! - Tracing spans per step for distributed tracing
+//! - Execution observer hooks for custom tracing, debugging and coverage
+//! - Step-through execution for debuggers
+//! - Field usage analysis for partial input materialization
+//! - Cross-branch optimization (common subexpressions, branch ordering)
+//! - Tiered execution (interpreter → bytecode → JIT) for hot conditions
+//! - Columnar execution over Arrow record batches (requires `arrow` feature)
 
+#[cfg(all(feature = "arrow", not(target_arch = "wasm32")))]
+mod columnar;
 mod compiled;
 mod compiled_executor;
 mod compiler;
 mod executor;
+mod field_usage;
 mod metrics;
 mod model;
+mod observer;
+mod optimizer;
+mod spans;
 mod step;
+mod stepper;
+#[cfg(not(target_arch = "wasm32"))]
+mod tiering;
 
+#[cfg(all(feature = "arrow", not(target_arch = "wasm32")))]
+pub use columnar::{ColumnarExecutionResult, ColumnarExecutor};
 pub use compiled::{
     get_enterprise_plugin,
     register_enterprise_plugin,
@@ -36,9 +54,15 @@ pub use compiler::RuleSetCompiler;
 pub use executor::{
     BatchExecutionResult, ExecutionOptions, ExecutionResult, RuleExecutor, SingleExecutionResult,
 };
+pub use field_usage::{FieldUsage, InputSeed, PathNode};
 pub use metrics::{LoggingMetricSink, MetricSink, MetricType, NoOpMetricSink};
-pub use model::{RuleSet, RuleSetConfig};
+pub use model::{FieldMissingBehavior, RuleSet, RuleSetConfig};
+pub use observer::{ActionRef, ExecutionObserver, StepId, StepRef};
+pub use optimizer::{RuleSetOptimizationStats, RuleSetOptimizer, TEMP_VARIABLE_PREFIX};
 pub use step::{Action, ActionKind, Branch, Condition, Step, StepKind, TerminalResult};
+pub use stepper::{Point, Position, RuleStepper};
+#[cfg(not(target_arch = "wasm32"))]
+pub use tiering::{ConditionTierInfo, ExecutionTier, TierManager, TieringConfig, TieringStats};
 
 use std::sync::Arc;
 
@@ -47,3 +71,16 @@ use std::sync::Arc;
 pub trait RuleSetResolver: Send + Sync {
     fn resolve(&self, name: &str) -> Option<Arc<RuleSet>>;
 }
+
+/// Trait for performing the calls of ExternalCall actions.
+/// `params` is an object of the evaluated parameters; the response is
+/// stored in the action's result variable.
+pub trait ExternalCallHandler: Send + Sync {
+    fn call(
+        &self,
+        service: &str,
+        method: &str,
+        params: &crate::context::Value,
+        timeout_ms: u64,
+    ) -> crate::error::Result<crate::context::Value>;
+}
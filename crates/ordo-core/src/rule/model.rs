@@ -2,11 +2,15 @@
 //!
 //! Defines the structure of rule sets
 
-use super::step::Step;
+use super::field_usage::FieldUsage;
+use super::optimizer::ExecutionPlan;
+use super::step::{Action, ActionKind, Step, StepKind};
+use crate::context::VariableLayout;
 use crate::error::Result;
 use hashbrown::HashMap as FastMap;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 
 /// RuleSet configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +87,18 @@ pub struct RuleSet {
 
     /// Steps by ID (hashbrown for faster lookup in the execution hot loop)
     pub steps: FastMap<String, Step>,
+
+    /// Cross-branch execution plan from `RuleSetOptimizer` (never serialized)
+    #[serde(skip)]
+    pub(crate) plan: Option<Arc<ExecutionPlan>>,
+
+    /// Input paths read by the ruleset, analyzed on first use (never serialized)
+    #[serde(skip)]
+    field_usage: OnceLock<Arc<FieldUsage>>,
+
+    /// Slot layout of the variables the ruleset sets (never serialized)
+    #[serde(skip)]
+    variable_layout: OnceLock<Arc<VariableLayout>>,
 }
 
 impl RuleSet {
@@ -102,15 +118,86 @@ impl RuleSet {
                 metadata: HashMap::new(),
             },
             steps: FastMap::new(),
+            plan: None,
+            field_usage: OnceLock::new(),
+            variable_layout: OnceLock::new(),
         }
     }
 
     /// Add a step
+    ///
+    /// Discards any plan from `RuleSetOptimizer`.
     pub fn add_step(&mut self, step: Step) -> &mut Self {
         self.steps.insert(step.id.clone(), step);
+        self.plan = None;
+        self.field_usage = OnceLock::new();
+        self.variable_layout = OnceLock::new();
         self
     }
 
+    /// Input paths read by this ruleset (see [`FieldUsage`])
+    ///
+    /// Analyzed once and cached; steps modified through the public `steps`
+    /// map are not tracked.
+    pub fn field_usage(&self) -> Arc<FieldUsage> {
+        self.field_usage
+            .get_or_init(|| Arc::new(FieldUsage::analyze(self)))
+            .clone()
+    }
+
+    /// Slot layout of the variables set by this ruleset's actions and
+    /// execution plan, resolved once and cached
+    pub fn variable_layout(&self) -> Arc<VariableLayout> {
+        self.variable_layout
+            .get_or_init(|| {
+                let mut layout = VariableLayout::default();
+                let mut add = |action: &Action| match &action.kind {
+                    ActionKind::SetVariable { name, .. } => {
+                        layout.insert(name);
+                    }
+                    ActionKind::CallRuleSet {
+                        result_variable, ..
+                    } => {
+                        layout.insert(result_variable);
+                    }
+                    _ => {}
+                };
+                for step in self.steps.values() {
+                    match &step.kind {
+                        StepKind::Decision { branches, .. } => branches
+                            .iter()
+                            .flat_map(|branch| &branch.actions)
+                            .for_each(&mut add),
+                        StepKind::Action { actions, .. } => actions.iter().for_each(&mut add),
+                        StepKind::Terminal { .. } => {}
+                    }
+                }
+                if let Some(plan) = &self.plan {
+                    for name in &plan.temp_names {
+                        layout.insert(name);
+                    }
+                }
+                Arc::new(layout)
+            })
+            .clone()
+    }
+
+    /// Attach (or discard) the plan from `RuleSetOptimizer`
+    pub(crate) fn set_plan(&mut self, plan: Option<Arc<ExecutionPlan>>) {
+        self.plan = plan;
+        self.variable_layout = OnceLock::new();
+    }
+
+    /// Whether an optimized execution plan is attached (see `RuleSetOptimizer`)
+    pub fn is_optimized(&self) -> bool {
+        self.plan.is_some()
+    }
+
+    /// Discard the optimized execution plan
+    pub fn clear_optimization(&mut self) {
+        self.set_plan(None);
+    }
+
     /// Get a step by ID
     pub fn get_step(&self, id: &str) -> Option<&Step> {
         self.steps.get(id)
@@ -209,6 +296,7 @@ impl RuleSet {
         for step in self.steps.values_mut() {
             step.compile()?;
         }
+        self.variable_layout();
         Ok(())
     }
 
@@ -0,0 +1,380 @@
+//! Execution observer hooks
+//!
+//! An [`ExecutionObserver`] is notified as an executor walks a rule set:
+//! before and after each step, for each branch condition evaluated, action
+//! executed and variable set, when a terminal step is reached and when
+//! execution fails. It is the extension point for custom tracing, debugging,
+//! coverage and metrics.
+//!
+//! Observers attach to an executor (`RuleExecutor::set_observer`,
+//! `CompiledRuleExecutor::set_observer`) or to a single call
+//! ([`ExecutionOptions::observer`](super::ExecutionOptions::observer)); when
+//! both are set, both are notified. Executors check for an observer before
+//! building any callback argument, so execution without one does no extra
+//! work beyond that check.
+//!
+//! `CallRuleSet` sub-executions report to the same observers; their steps
+//! carry the name of the called rule set.
+
+use super::compiler::hash_step_id;
+use crate::context::Value;
+use crate::error::OrdoError;
+
+/// Identifies a step
+///
+/// Rule sets identify steps by ID. Compiled rule sets only keep a hash of
+/// the ID (see [`RuleSetCompiler::step_hash`](super::RuleSetCompiler::step_hash)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepId<'a> {
+    /// Step ID
+    Named(&'a str),
+    /// Hash of the step ID
+    Hashed(u32),
+}
+
+impl<'a> StepId<'a> {
+    /// The step ID, if known
+    pub fn name(&self) -> Option<&'a str> {
+        match self {
+            StepId::Named(id) => Some(id),
+            StepId::Hashed(_) => None,
+        }
+    }
+
+    /// The hash of the step ID
+    pub fn hash(&self) -> u32 {
+        match self {
+            StepId::Named(id) => hash_step_id(id),
+            StepId::Hashed(hash) => *hash,
+        }
+    }
+
+    /// Whether this is the step with the given ID
+    pub fn matches(&self, id: &str) -> bool {
+        match self {
+            StepId::Named(name) => *name == id,
+            StepId::Hashed(hash) => *hash == hash_step_id(id),
+        }
+    }
+}
+
+/// A step of the rule set being executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepRef<'a> {
+    /// Name of the rule set the step belongs to
+    pub ruleset: &'a str,
+    /// The step
+    pub id: StepId<'a>,
+}
+
+/// An action of a step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionRef {
+    /// Branch whose actions the action belongs to (`None` in action steps)
+    pub branch: Option<usize>,
+    /// Position of the action in its list
+    pub index: usize,
+}
+
+/// Callbacks invoked during rule execution
+///
+/// Every method has an empty default, so implementations only override the
+/// events they need. Callbacks run synchronously on the executing thread.
+#[allow(unused_variables)]
+pub trait ExecutionObserver: Send + Sync {
+    /// A step is about to run
+    fn before_step(&self, step: StepRef<'_>) {}
+
+    /// A step finished without error
+    fn after_step(&self, step: StepRef<'_>) {}
+
+    /// A branch condition of a decision step was evaluated
+    ///
+    /// Branches are reported in evaluation order, which may differ from
+    /// definition order for optimized rule sets; `branch` is the index in
+    /// the definition. `value` is the condition's truth value.
+    fn branch_evaluated(&self, step: StepRef<'_>, branch: usize, value: bool) {}
+
+    /// An action ran without error
+    fn action_executed(&self, step: StepRef<'_>, action: ActionRef) {}
+
+    /// A variable was set, by a `SetVariable` action or as the result of a
+    /// `CallRuleSet`
+    fn variable_set(&self, step: StepRef<'_>, name: &str, value: &Value) {}
+
+    /// A terminal step produced its result
+    fn terminal_reached(&self, step: StepRef<'_>, code: &str, output: &Value) {}
+
+    /// Execution failed at a step
+    ///
+    /// An error in a `CallRuleSet` sub-execution is reported for the failing
+    /// step of the called rule set, then for the calling step.
+    fn error(&self, step: StepRef<'_>, error: &OrdoError) {}
+}
+
+/// Forwards every callback to two observers
+pub(crate) struct Both<'a>(
+    pub(crate) &'a dyn ExecutionObserver,
+    pub(crate) &'a dyn ExecutionObserver,
+);
+
+impl ExecutionObserver for Both<'_> {
+    fn before_step(&self, step: StepRef<'_>) {
+        self.0.before_step(step);
+        self.1.before_step(step);
+    }
+
+    fn after_step(&self, step: StepRef<'_>) {
+        self.0.after_step(step);
+        self.1.after_step(step);
+    }
+
+    fn branch_evaluated(&self, step: StepRef<'_>, branch: usize, value: bool) {
+        self.0.branch_evaluated(step, branch, value);
+        self.1.branch_evaluated(step, branch, value);
+    }
+
+    fn action_executed(&self, step: StepRef<'_>, action: ActionRef) {
+        self.0.action_executed(step, action);
+        self.1.action_executed(step, action);
+    }
+
+    fn variable_set(&self, step: StepRef<'_>, name: &str, value: &Value) {
+        self.0.variable_set(step, name, value);
+        self.1.variable_set(step, name, value);
+    }
+
+    fn terminal_reached(&self, step: StepRef<'_>, code: &str, output: &Value) {
+        self.0.terminal_reached(step, code, output);
+        self.1.terminal_reached(step, code, output);
+    }
+
+    fn error(&self, step: StepRef<'_>, error: &OrdoError) {
+        self.0.error(step, error);
+        self.1.error(step, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::Expr;
+    use crate::rule::{
+        Action, ActionKind, CompiledRuleExecutor, Condition, ExecutionOptions, RuleExecutor,
+        RuleSet, RuleSetCompiler, RuleSetResolver, Step, TerminalResult,
+    };
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    /// Records events as strings
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Recorder {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut *self.0.lock())
+        }
+
+        fn push(&self, step: StepRef<'_>, event: String) {
+            let id = match step.id {
+                StepId::Named(id) => id.to_string(),
+                StepId::Hashed(hash) => format!("#{hash}"),
+            };
+            self.0
+                .lock()
+                .push(format!("{}/{} {}", step.ruleset, id, event));
+        }
+    }
+
+    impl ExecutionObserver for Recorder {
+        fn before_step(&self, step: StepRef<'_>) {
+            self.push(step, "before".into());
+        }
+
+        fn after_step(&self, step: StepRef<'_>) {
+            self.push(step, "after".into());
+        }
+
+        fn branch_evaluated(&self, step: StepRef<'_>, branch: usize, value: bool) {
+            self.push(step, format!("branch {branch} = {value}"));
+        }
+
+        fn action_executed(&self, step: StepRef<'_>, action: ActionRef) {
+            self.push(step, format!("action {:?} {}", action.branch, action.index));
+        }
+
+        fn variable_set(&self, step: StepRef<'_>, name: &str, value: &Value) {
+            self.push(step, format!("set {name} = {value}"));
+        }
+
+        fn terminal_reached(&self, step: StepRef<'_>, code: &str, _output: &Value) {
+            self.push(step, format!("terminal {code}"));
+        }
+
+        fn error(&self, step: StepRef<'_>, error: &OrdoError) {
+            self.push(step, format!("error {error}"));
+        }
+    }
+
+    fn ruleset() -> RuleSet {
+        let mut ruleset = RuleSet::new("main", "check");
+        ruleset.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string("age < 18"), "minor")
+                .branch_with_actions(
+                    Condition::from_string("age >= 18"),
+                    "adult",
+                    vec![Action::set_var("tier", Expr::literal("adult"))],
+                )
+                .build(),
+        );
+        ruleset.add_step(Step::terminal(
+            "minor",
+            "Minor",
+            TerminalResult::new("MINOR"),
+        ));
+        ruleset.add_step(Step::terminal(
+            "adult",
+            "Adult",
+            TerminalResult::new("ADULT"),
+        ));
+        ruleset
+    }
+
+    fn input(age: i64) -> Value {
+        serde_json::from_str(&format!(r#"{{"age": {age}}}"#)).unwrap()
+    }
+
+    #[test]
+    fn test_observer_sees_every_event() {
+        let recorder = Arc::new(Recorder::default());
+        let mut executor = RuleExecutor::new();
+        executor.set_observer(recorder.clone());
+
+        let result = executor.execute(&ruleset(), input(30)).unwrap();
+        assert_eq!(result.code, "ADULT");
+        assert_eq!(
+            recorder.take(),
+            vec![
+                "main/check before",
+                "main/check branch 0 = false",
+                "main/check branch 1 = true",
+                "main/check set tier = \"adult\"",
+                "main/check action Some(1) 0",
+                "main/check after",
+                "main/adult before",
+                "main/adult terminal ADULT",
+                "main/adult after",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_executor_and_call_observers_are_both_notified() {
+        let attached = Arc::new(Recorder::default());
+        let per_call = Arc::new(Recorder::default());
+        let mut executor = RuleExecutor::new();
+        executor.set_observer(attached.clone());
+
+        let options = ExecutionOptions::default().observer(per_call.clone());
+        executor
+            .execute_with_options(&ruleset(), input(10), Some(&options))
+            .unwrap();
+        let events = attached.take();
+        assert_eq!(events.len(), 6);
+        assert_eq!(per_call.take(), events);
+
+        // Without options only the executor's observer hears about it
+        executor.execute(&ruleset(), input(10)).unwrap();
+        assert_eq!(attached.take().len(), 6);
+        assert!(per_call.take().is_empty());
+    }
+
+    #[test]
+    fn test_observer_follows_call_ruleset_and_errors() {
+        struct Resolver(Arc<RuleSet>);
+        impl RuleSetResolver for Resolver {
+            fn resolve(&self, name: &str) -> Option<Arc<RuleSet>> {
+                (name == "score").then(|| self.0.clone())
+            }
+        }
+
+        let mut score = RuleSet::new("score", "compute");
+        score.add_step(Step::action(
+            "compute",
+            "Compute",
+            vec![Action::set_var("score", Expr::field("missing"))],
+            "done",
+        ));
+        score.add_step(Step::terminal("done", "Done", TerminalResult::new("OK")));
+
+        let mut main = RuleSet::new("main", "call");
+        main.add_step(Step::action(
+            "call",
+            "Call",
+            vec![Action {
+                kind: ActionKind::CallRuleSet {
+                    ruleset_name: "score".to_string(),
+                    input_mapping: None,
+                    result_variable: "scored".to_string(),
+                },
+                description: String::new(),
+            }],
+            "done",
+        ));
+        main.add_step(Step::terminal("done", "Done", TerminalResult::new("OK")));
+
+        let recorder = Arc::new(Recorder::default());
+        let mut executor = RuleExecutor::new();
+        executor.set_resolver(Arc::new(Resolver(Arc::new(score))));
+        let options = ExecutionOptions::default().observer(recorder.clone());
+        let err = executor
+            .execute_with_options(&main, input(1), Some(&options))
+            .unwrap_err();
+
+        let message = format!("error {err}");
+        assert_eq!(
+            recorder.take(),
+            vec![
+                "main/call before".to_string(),
+                "score/compute before".to_string(),
+                format!("score/compute {message}"),
+                format!("main/call {message}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compiled_executor_reports_hashed_steps() {
+        let compiled = RuleSetCompiler::compile(&ruleset()).unwrap();
+        let recorder = Arc::new(Recorder::default());
+        let executor = CompiledRuleExecutor::new();
+        let options = ExecutionOptions::default().observer(recorder.clone());
+
+        let result = executor
+            .execute_with_options(&compiled, input(30), Some(&options))
+            .unwrap();
+        assert_eq!(result.code, "ADULT");
+
+        let check = RuleSetCompiler::step_hash("check");
+        let adult = RuleSetCompiler::step_hash("adult");
+        assert_eq!(
+            recorder.take(),
+            vec![
+                format!("main/#{check} before"),
+                format!("main/#{check} branch 0 = false"),
+                format!("main/#{check} branch 1 = true"),
+                format!("main/#{check} set tier = \"adult\""),
+                format!("main/#{check} action Some(1) 0"),
+                format!("main/#{check} after"),
+                format!("main/#{adult} before"),
+                format!("main/#{adult} terminal ADULT"),
+                format!("main/#{adult} after"),
+            ]
+        );
+
+        assert!(StepId::Hashed(check).matches("check"));
+        assert!(!StepId::Hashed(check).matches("adult"));
+        assert_eq!(StepId::Named("check").hash(), check);
+    }
+}
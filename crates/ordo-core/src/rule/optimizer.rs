@@ -0,0 +1,907 @@
+//! RuleSet optimizer
+//!
+//! Optimizes decision steps across their branches:
+//! - Common subexpression elimination: pure subexpressions shared by several
+//!   branch conditions are hoisted into per-execution temporaries
+//! - Branch ordering: branches that are mutually exclusive are reordered by
+//!   observed selectivity and cost, cheapest likely match first
+//!
+//! The result is an execution plan attached to the RuleSet. Step definitions
+//! are left untouched, so a serialized RuleSet is identical before and after.
+
+use super::model::RuleSet;
+use super::step::{Branch, Condition, StepKind};
+use crate::context::Value;
+use crate::expr::{BinaryOp, Expr, Profiler};
+use hashbrown::HashMap as FastMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Prefix of the context variables holding hoisted temporaries
+///
+/// Temporaries are stored as `$__cse0`, `$__cse1`, ... for the duration of one
+/// execution (and show up in trace variable snapshots). Rules must not set
+/// variables with this prefix.
+pub const TEMP_VARIABLE_PREFIX: &str = "__cse";
+
+/// Functions whose result may differ between two calls with the same arguments
+const IMPURE_FUNCTIONS: &[&str] = &["now", "now_millis", "uuid_v4", "jwt_verify"];
+
+/// Statistics about a ruleset optimization
+#[derive(Debug, Default, Clone)]
+pub struct RuleSetOptimizationStats {
+    /// Number of hoisted subexpressions (temporaries)
+    pub hoisted_subexpressions: usize,
+    /// Number of branch conditions rewritten to read temporaries
+    pub rewritten_conditions: usize,
+    /// Number of decision steps with at least one pair of exclusive branches
+    pub exclusive_steps: usize,
+    /// Number of decision steps whose branch order changed
+    pub reordered_steps: usize,
+}
+
+/// Cross-branch optimizer for rule sets
+///
+/// # Example
+///
+/// ```ignore
+/// let profiler = Arc::new(Profiler::new());
+/// executor.set_profiler(profiler.clone());
+/// // ... run production traffic ...
+///
+/// let stats = RuleSetOptimizer::new()
+///     .with_profiler(profiler)
+///     .with_exclusive_step("route")
+///     .optimize(&mut ruleset);
+/// ```
+///
+/// Branches are only reordered when they are mutually exclusive: either
+/// declared so with [`with_exclusive_step`](Self::with_exclusive_step), or
+/// proven so because both compare the same field against disjoint literal
+/// ranges (`score < 500` / `score >= 500 && score < 700`). All other branches
+/// keep first-match order relative to each other.
+#[derive(Clone)]
+pub struct RuleSetOptimizer {
+    /// Profiler fed by `RuleExecutor::set_profiler`
+    profiler: Option<Arc<Profiler>>,
+    /// Steps whose branches the author declared mutually exclusive
+    exclusive_steps: HashSet<String>,
+    /// Extra functions that must not be hoisted
+    impure_functions: HashSet<String>,
+    /// Whether to hoist common subexpressions
+    eliminate_common_subexpressions: bool,
+    /// Whether to reorder exclusive branches
+    reorder_branches: bool,
+    /// Minimum executions observed at a step before reordering it
+    min_samples: u64,
+}
+
+impl Default for RuleSetOptimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuleSetOptimizer {
+    /// Create a new optimizer
+    pub fn new() -> Self {
+        Self {
+            profiler: None,
+            exclusive_steps: HashSet::new(),
+            impure_functions: HashSet::new(),
+            eliminate_common_subexpressions: true,
+            reorder_branches: true,
+            min_samples: 100,
+        }
+    }
+
+    /// Use execution profiles for branch selectivity and cost
+    pub fn with_profiler(mut self, profiler: Arc<Profiler>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Declare the branches of a decision step mutually exclusive
+    ///
+    /// At most one branch condition of the step can be true for any input,
+    /// so the branches may be evaluated in any order.
+    pub fn with_exclusive_step(mut self, step_id: impl Into<String>) -> Self {
+        self.exclusive_steps.insert(step_id.into());
+        self
+    }
+
+    /// Mark a custom function as impure (never hoisted)
+    pub fn with_impure_function(mut self, name: impl Into<String>) -> Self {
+        self.impure_functions.insert(name.into());
+        self
+    }
+
+    /// Enable or disable common subexpression elimination
+    pub fn with_cse(mut self, enabled: bool) -> Self {
+        self.eliminate_common_subexpressions = enabled;
+        self
+    }
+
+    /// Enable or disable branch reordering
+    pub fn with_reordering(mut self, enabled: bool) -> Self {
+        self.reorder_branches = enabled;
+        self
+    }
+
+    /// Set the minimum number of profiled executions of a step before reordering it
+    pub fn with_min_samples(mut self, min_samples: u64) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Optimize a ruleset, replacing any previous plan
+    ///
+    /// Only compiled conditions (see `RuleSet::compile`) take part in
+    /// subexpression elimination. Run again after modifying the steps.
+    pub fn optimize(&self, ruleset: &mut RuleSet) -> RuleSetOptimizationStats {
+        let mut stats = RuleSetOptimizationStats::default();
+        let mut step_ids: Vec<&String> = ruleset
+            .steps
+            .iter()
+            .filter(|(_, step)| matches!(step.kind, StepKind::Decision { .. }))
+            .map(|(id, _)| id)
+            .collect();
+        step_ids.sort();
+
+        let mut hoister = Hoister::new(self);
+        if self.eliminate_common_subexpressions {
+            for id in &step_ids {
+                for branch in branches(ruleset, id) {
+                    if let Condition::Expression(expr) = &branch.condition {
+                        hoister.count(expr);
+                    }
+                }
+            }
+        }
+
+        let paths = match &self.profiler {
+            Some(profiler) if self.reorder_branches => {
+                profiler.get_rule_paths(&ruleset.config.name)
+            }
+            _ => Vec::new(),
+        };
+
+        let mut plan = ExecutionPlan::default();
+        for id in step_ids {
+            let branches = branches(ruleset, id);
+            let conditions: Vec<Option<PlannedCondition>> = branches
+                .iter()
+                .map(|branch| match &branch.condition {
+                    Condition::Expression(expr) if self.eliminate_common_subexpressions => {
+                        hoister.rewrite_condition(expr)
+                    }
+                    _ => None,
+                })
+                .collect();
+            stats.rewritten_conditions += conditions.iter().flatten().count();
+
+            let order = if self.reorder_branches {
+                self.plan_order(ruleset, id, branches, &paths, &mut stats)
+            } else {
+                (0..branches.len()).collect()
+            };
+
+            let reordered = order.iter().enumerate().any(|(pos, &index)| pos != index);
+            if reordered || conditions.iter().any(Option::is_some) {
+                plan.steps
+                    .insert(id.clone(), StepPlan { order, conditions });
+            }
+        }
+
+        plan.temp_names = (0..hoister.temps.len())
+            .map(|i| format!("{}{}", TEMP_VARIABLE_PREFIX, i))
+            .collect();
+        plan.temps = hoister.temps;
+        stats.hoisted_subexpressions = plan.temps.len();
+
+        ruleset.set_plan((!plan.steps.is_empty()).then(|| Arc::new(plan)));
+        stats
+    }
+
+    /// Choose the evaluation order of a decision step's branches
+    fn plan_order(
+        &self,
+        ruleset: &RuleSet,
+        step_id: &str,
+        branches: &[Branch],
+        paths: &[(Vec<String>, u64, u64)],
+        stats: &mut RuleSetOptimizationStats,
+    ) -> Vec<usize> {
+        let n = branches.len();
+        let identity: Vec<usize> = (0..n).collect();
+        if n < 2 {
+            return identity;
+        }
+
+        let declared = self.exclusive_steps.contains(step_id);
+        let exclusive: Vec<Vec<bool>> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| {
+                        i != j
+                            && exclusive(&branches[i].condition, &branches[j].condition, declared)
+                    })
+                    .collect()
+            })
+            .collect();
+        if !exclusive.iter().flatten().any(|&e| e) {
+            return identity;
+        }
+        stats.exclusive_steps += 1;
+
+        let (hits, total) = branch_hits(step_id, branches, paths);
+        if total == 0.0 || total < self.min_samples as f64 {
+            return identity;
+        }
+
+        let costs = self.branch_costs(ruleset, step_id, branches);
+        let ranks: Vec<f64> = (0..n)
+            .map(|i| {
+                let selectivity = hits[i] / total;
+                if selectivity > 0.0 {
+                    costs[i] / selectivity
+                } else {
+                    f64::INFINITY
+                }
+            })
+            .collect();
+
+        let order = order_by_rank(&ranks, &exclusive);
+        if order != identity {
+            stats.reordered_steps += 1;
+        }
+        order
+    }
+
+    /// Evaluation cost of each branch condition
+    ///
+    /// Uses the observed average duration when every condition of the step has
+    /// been profiled, otherwise a static estimate from the expression shape.
+    fn branch_costs(&self, ruleset: &RuleSet, step_id: &str, branches: &[Branch]) -> Vec<f64> {
+        let observed: Option<Vec<f64>> = self.profiler.as_ref().and_then(|profiler| {
+            (0..branches.len())
+                .map(|i| {
+                    profiler
+                        .get_expr_profile(condition_key(&ruleset.config.name, step_id, i))
+                        .filter(|(count, _, _)| *count > 0)
+                        .map(|(_, avg_ns, _)| avg_ns.max(1) as f64)
+                })
+                .collect()
+        });
+        observed.unwrap_or_else(|| {
+            branches
+                .iter()
+                .map(|branch| condition_cost(&branch.condition) as f64)
+                .collect()
+        })
+    }
+}
+
+/// Cross-branch execution plan produced by [`RuleSetOptimizer`]
+#[derive(Debug, Default)]
+pub(crate) struct ExecutionPlan {
+    /// Hoisted subexpressions, evaluated at most once per execution
+    pub(crate) temps: Vec<Expr>,
+    /// Variable names the temporaries are stored under
+    pub(crate) temp_names: Vec<String>,
+    /// Plans of the decision steps that changed
+    pub(crate) steps: FastMap<String, StepPlan>,
+}
+
+/// Plan of a single decision step
+#[derive(Debug)]
+pub(crate) struct StepPlan {
+    /// Branch evaluation order (original branch indices)
+    pub(crate) order: Vec<usize>,
+    /// Rewritten conditions, by original branch index
+    pub(crate) conditions: Vec<Option<PlannedCondition>>,
+}
+
+/// A branch condition rewritten to read temporaries
+#[derive(Debug)]
+pub(crate) struct PlannedCondition {
+    /// Condition with hoisted subexpressions replaced by variable references
+    pub(crate) expr: Expr,
+    /// Temporaries the condition reads
+    pub(crate) temps: Vec<usize>,
+}
+
+/// Per-execution state of a temporary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TempState {
+    /// Not evaluated yet
+    Pending,
+    /// Stored in the context
+    Ready,
+    /// Evaluation failed; conditions reading it fall back to their original form
+    Failed,
+}
+
+/// Profiler key of a branch condition
+///
+/// `RuleExecutor` records condition timings under this key when a profiler is set.
+pub(crate) fn condition_key(ruleset: &str, step_id: &str, branch: usize) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (ruleset, step_id, branch).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn branches<'a>(ruleset: &'a RuleSet, step_id: &str) -> &'a [Branch] {
+    match ruleset.steps.get(step_id).map(|step| &step.kind) {
+        Some(StepKind::Decision { branches, .. }) => branches,
+        _ => &[],
+    }
+}
+
+// ==================== Common subexpression elimination ====================
+
+/// Collects shared subexpressions and rewrites conditions to use temporaries
+struct Hoister<'a> {
+    optimizer: &'a RuleSetOptimizer,
+    /// Occurrences of each candidate subexpression, keyed by its debug form
+    counts: HashMap<String, usize>,
+    /// Temporary index of each hoisted subexpression
+    assigned: HashMap<String, usize>,
+    temps: Vec<Expr>,
+}
+
+impl<'a> Hoister<'a> {
+    fn new(optimizer: &'a RuleSetOptimizer) -> Self {
+        Self {
+            optimizer,
+            counts: HashMap::new(),
+            assigned: HashMap::new(),
+            temps: Vec::new(),
+        }
+    }
+
+    /// Count every candidate subexpression of a condition
+    fn count(&mut self, expr: &Expr) {
+        if self.is_candidate(expr) {
+            *self.counts.entry(format!("{:?}", expr)).or_default() += 1;
+        }
+        for child in children(expr) {
+            self.count(child);
+        }
+    }
+
+    /// Rewrite a condition, returning `None` if nothing was hoisted
+    fn rewrite_condition(&mut self, expr: &Expr) -> Option<PlannedCondition> {
+        let mut temps = Vec::new();
+        let rewritten = self.rewrite(expr, &mut temps);
+        if temps.is_empty() {
+            return None;
+        }
+        temps.sort_unstable();
+        temps.dedup();
+        Some(PlannedCondition {
+            expr: rewritten,
+            temps,
+        })
+    }
+
+    /// Replace the outermost shared subexpressions with temporaries
+    fn rewrite(&mut self, expr: &Expr, temps: &mut Vec<usize>) -> Expr {
+        if self.is_candidate(expr) {
+            let key = format!("{:?}", expr);
+            if self.counts.get(&key).copied().unwrap_or(0) >= 2 {
+                let next = self.temps.len();
+                let index = *self.assigned.entry(key).or_insert(next);
+                if index == next {
+                    self.temps.push(expr.clone());
+                }
+                temps.push(index);
+                return Expr::Field(format!("${}{}", TEMP_VARIABLE_PREFIX, index));
+            }
+        }
+
+        match expr {
+            Expr::Literal(_) | Expr::Field(_) | Expr::Exists(_) => expr.clone(),
+            Expr::Binary { op, left, right } => {
+                Expr::binary(*op, self.rewrite(left, temps), self.rewrite(right, temps))
+            }
+            Expr::Unary { op, operand } => Expr::Unary {
+                op: *op,
+                operand: Box::new(self.rewrite(operand, temps)),
+            },
+            Expr::Call { name, args } => Expr::Call {
+                name: name.clone(),
+                args: args.iter().map(|arg| self.rewrite(arg, temps)).collect(),
+            },
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => Expr::Conditional {
+                condition: Box::new(self.rewrite(condition, temps)),
+                then_branch: Box::new(self.rewrite(then_branch, temps)),
+                else_branch: Box::new(self.rewrite(else_branch, temps)),
+            },
+            Expr::Array(items) => {
+                Expr::Array(items.iter().map(|e| self.rewrite(e, temps)).collect())
+            }
+            Expr::Object(pairs) => Expr::Object(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.clone(), self.rewrite(v, temps)))
+                    .collect(),
+            ),
+            Expr::Coalesce(items) => {
+                Expr::Coalesce(items.iter().map(|e| self.rewrite(e, temps)).collect())
+            }
+        }
+    }
+
+    /// Whether a subexpression is worth hoisting and safe to evaluate once
+    ///
+    /// It must do some work beyond a lookup, read the input (constants are
+    /// left to `ExprOptimizer`) and nothing else: variables change between
+    /// steps and the current item is only bound inside collection functions.
+    fn is_candidate(&self, expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Binary { .. }
+                | Expr::Unary { .. }
+                | Expr::Call { .. }
+                | Expr::Conditional { .. }
+                | Expr::Coalesce(_)
+        ) && self.is_pure(expr)
+            && reads_input(expr)
+    }
+
+    fn is_pure(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Field(path) | Expr::Exists(path) => !is_context_path(path),
+            Expr::Call { name, .. }
+                if IMPURE_FUNCTIONS.contains(&name.as_str())
+                    || self.optimizer.impure_functions.contains(name) =>
+            {
+                false
+            }
+            _ => children(expr).all(|child| self.is_pure(child)),
+        }
+    }
+}
+
+/// Paths resolved from execution state rather than the input
+fn is_context_path(path: &str) -> bool {
+    path.starts_with('$') || path == "item" || path.starts_with("item.") || path == "_index"
+}
+
+fn reads_input(expr: &Expr) -> bool {
+    matches!(expr, Expr::Field(_) | Expr::Exists(_)) || children(expr).any(reads_input)
+}
+
+fn children(expr: &Expr) -> Box<dyn Iterator<Item = &Expr> + '_> {
+    match expr {
+        Expr::Literal(_) | Expr::Field(_) | Expr::Exists(_) => Box::new(std::iter::empty()),
+        Expr::Binary { left, right, .. } => Box::new([&**left, &**right].into_iter()),
+        Expr::Unary { operand, .. } => Box::new(std::iter::once(&**operand)),
+        Expr::Call { args, .. } => Box::new(args.iter()),
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => Box::new([&**condition, &**then_branch, &**else_branch].into_iter()),
+        Expr::Array(items) | Expr::Coalesce(items) => Box::new(items.iter()),
+        Expr::Object(pairs) => Box::new(pairs.iter().map(|(_, v)| v)),
+    }
+}
+
+// ==================== Branch ordering ====================
+
+/// Observed number of times each branch was taken, and executions of the step
+///
+/// A path takes a branch when the step is followed by the branch's target.
+/// Branches sharing a target split its hits evenly.
+fn branch_hits(
+    step_id: &str,
+    branches: &[Branch],
+    paths: &[(Vec<String>, u64, u64)],
+) -> (Vec<f64>, f64) {
+    let mut hits = vec![0.0; branches.len()];
+    let mut total = 0.0;
+    for (step_ids, count, _) in paths {
+        for window in step_ids.windows(2) {
+            if window[0] != step_id {
+                continue;
+            }
+            let count = *count as f64;
+            total += count;
+            let taken: Vec<usize> = (0..branches.len())
+                .filter(|&i| branches[i].next_step == window[1])
+                .collect();
+            for &i in &taken {
+                hits[i] += count / taken.len() as f64;
+            }
+        }
+    }
+    (hits, total)
+}
+
+/// Order branches by ascending rank, never moving a branch ahead of an
+/// earlier branch it is not exclusive with
+fn order_by_rank(ranks: &[f64], exclusive: &[Vec<bool>]) -> Vec<usize> {
+    let n = ranks.len();
+    let mut placed = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    while order.len() < n {
+        let next = (0..n)
+            .filter(|&i| !placed[i] && (0..i).all(|j| placed[j] || exclusive[j][i]))
+            .min_by(|&a, &b| {
+                ranks[a]
+                    .partial_cmp(&ranks[b])
+                    .unwrap_or(Ordering::Equal)
+                    .then(a.cmp(&b))
+            })
+            .expect("the first unplaced branch is always ready");
+        placed[next] = true;
+        order.push(next);
+    }
+    order
+}
+
+/// Static cost estimate of a condition
+fn condition_cost(condition: &Condition) -> u64 {
+    match condition {
+        Condition::Always => 1,
+        Condition::Expression(expr) => expr_cost(expr),
+        // Parsed on every evaluation
+        Condition::ExpressionString(s) => 50 + s.len() as u64,
+    }
+}
+
+fn expr_cost(expr: &Expr) -> u64 {
+    let own = match expr {
+        Expr::Literal(_) => 0,
+        Expr::Call { .. } => 8,
+        _ => 1,
+    };
+    own + children(expr).map(expr_cost).sum::<u64>()
+}
+
+/// Whether two branch conditions can never both be true
+fn exclusive(a: &Condition, b: &Condition, declared: bool) -> bool {
+    match (a, b) {
+        (Condition::Always, _) | (_, Condition::Always) => false,
+        _ if declared => true,
+        (Condition::Expression(a), Condition::Expression(b)) => {
+            match (FieldConstraint::of(a), FieldConstraint::of(b)) {
+                (Some(a), Some(b)) => a.disjoint(&b),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// A conjunction of comparisons between one field and literals of one type
+///
+/// Two such conditions on the same field can be evaluated in either order
+/// without changing the result: for a value of another type (or a missing
+/// field) neither is true and both fail with the same error, if at all.
+struct FieldConstraint<'a> {
+    field: &'a str,
+    kind: &'static str,
+    /// Normalized to `field <op> literal`; `In` holds every listed literal
+    atoms: Vec<(BinaryOp, Vec<&'a Value>)>,
+}
+
+impl<'a> FieldConstraint<'a> {
+    fn of(expr: &'a Expr) -> Option<Self> {
+        let mut conjuncts = Vec::new();
+        flatten_and(expr, &mut conjuncts);
+
+        let mut field = None;
+        let mut kind = None;
+        let mut atoms = Vec::with_capacity(conjuncts.len());
+        for conjunct in conjuncts {
+            let Expr::Binary { op, left, right } = conjunct else {
+                return None;
+            };
+            let Expr::Field(path) = &**left else {
+                return None;
+            };
+            let values: Vec<&Value> = match (op, &**right) {
+                (
+                    BinaryOp::Eq | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge,
+                    Expr::Literal(value),
+                ) => vec![value],
+                (BinaryOp::In, Expr::Literal(Value::Array(items))) => items.iter().collect(),
+                (BinaryOp::In, Expr::Array(items)) => items
+                    .iter()
+                    .map(|item| match item {
+                        Expr::Literal(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+                _ => return None,
+            };
+            for value in &values {
+                let value_kind = literal_kind(value)?;
+                if *kind.get_or_insert(value_kind) != value_kind {
+                    return None;
+                }
+            }
+            if *field.get_or_insert(path.as_str()) != path.as_str() {
+                return None;
+            }
+            atoms.push((*op, values));
+        }
+
+        Some(Self {
+            field: field?,
+            kind: kind?,
+            atoms,
+        })
+    }
+
+    /// Whether no value satisfies both constraints
+    fn disjoint(&self, other: &Self) -> bool {
+        if self.field != other.field || self.kind != other.kind {
+            return false;
+        }
+
+        // Tightest bounds as (value, inclusive), and the allowed points if any
+        let mut lower: Option<(&Value, bool)> = None;
+        let mut upper: Option<(&Value, bool)> = None;
+        let mut points: Option<Vec<&Value>> = None;
+        for (op, values) in self.atoms.iter().chain(&other.atoms) {
+            match op {
+                BinaryOp::Gt | BinaryOp::Ge | BinaryOp::Lt | BinaryOp::Le => {
+                    let value = values[0];
+                    let inclusive = matches!(op, BinaryOp::Ge | BinaryOp::Le);
+                    let (bound, tighter) = if matches!(op, BinaryOp::Gt | BinaryOp::Ge) {
+                        (&mut lower, Ordering::Greater)
+                    } else {
+                        (&mut upper, Ordering::Less)
+                    };
+                    *bound = match *bound {
+                        None => Some((value, inclusive)),
+                        Some((current, current_inclusive)) => match value.compare(current) {
+                            Some(Ordering::Equal) => {
+                                Some((current, current_inclusive && inclusive))
+                            }
+                            Some(ordering) if ordering == tighter => Some((value, inclusive)),
+                            Some(_) => Some((current, current_inclusive)),
+                            None => return false,
+                        },
+                    };
+                }
+                _ => {
+                    points = Some(match points {
+                        Some(points) => points
+                            .into_iter()
+                            .filter(|point| values.contains(point))
+                            .collect(),
+                        None => values.clone(),
+                    });
+                }
+            }
+        }
+
+        let excluded = |point: &Value| {
+            let below_lower = lower.is_some_and(|(bound, inclusive)| {
+                matches!(point.compare(bound), Some(Ordering::Less))
+                    || (!inclusive && matches!(point.compare(bound), Some(Ordering::Equal)))
+            });
+            let above_upper = upper.is_some_and(|(bound, inclusive)| {
+                matches!(point.compare(bound), Some(Ordering::Greater))
+                    || (!inclusive && matches!(point.compare(bound), Some(Ordering::Equal)))
+            });
+            below_lower || above_upper
+        };
+
+        match (points, lower, upper) {
+            (Some(points), _, _) => points.into_iter().all(excluded),
+            (None, Some((low, low_inclusive)), Some((high, high_inclusive))) => {
+                match low.compare(high) {
+                    Some(Ordering::Greater) => true,
+                    Some(Ordering::Equal) => !(low_inclusive && high_inclusive),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+fn flatten_and<'a>(expr: &'a Expr, out: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::Binary {
+            op: BinaryOp::And,
+            left,
+            right,
+        } => {
+            flatten_and(left, out);
+            flatten_and(right, out);
+        }
+        _ => out.push(expr),
+    }
+}
+
+/// Type of a literal usable in a field constraint
+fn literal_kind(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Int(_) | Value::String(_) | Value::Bool(_) => Some(value.type_name()),
+        Value::Float(f) if !f.is_nan() => Some(value.type_name()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::ExprParser;
+    use crate::rule::{RuleExecutor, Step, TerminalResult};
+
+    fn cond(s: &str) -> Condition {
+        Condition::expr(ExprParser::parse(s).unwrap())
+    }
+
+    fn ruleset(branches: &[(&str, &str)]) -> RuleSet {
+        let mut ruleset = RuleSet::new("opt", "route");
+        let mut step = Step::decision("route", "Route");
+        for (condition, target) in branches {
+            step = step.branch(cond(condition), *target);
+        }
+        ruleset.add_step(step.default("none").build());
+        for target in branches
+            .iter()
+            .map(|(_, target)| *target)
+            .chain(std::iter::once("none"))
+        {
+            ruleset.add_step(Step::terminal(target, target, TerminalResult::new(target)));
+        }
+        ruleset
+    }
+
+    fn outcome(executor: &RuleExecutor, ruleset: &RuleSet, input: &str) -> String {
+        match executor.execute(ruleset, serde_json::from_str(input).unwrap()) {
+            Ok(result) => result.code,
+            Err(e) => format!("error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_hoists_shared_subexpressions() {
+        let plain = ruleset(&[
+            ("len(orders) > 5 && vip == true", "vip"),
+            ("len(orders) > 5", "loyal"),
+            ("len(orders) > 0 && upper(name) == \"BOB\"", "bob"),
+            ("upper(name) == \"ALICE\"", "alice"),
+            ("$seen == true", "seen"),
+        ]);
+        let mut optimized = plain.clone();
+        let stats = RuleSetOptimizer::new().optimize(&mut optimized);
+
+        // `len(orders) > 5`, `len(orders)` and `upper(name)`; `$seen` is never hoisted
+        assert_eq!(stats.hoisted_subexpressions, 3);
+        assert_eq!(stats.rewritten_conditions, 4);
+        assert!(optimized.is_optimized());
+        assert_eq!(optimized.to_json().unwrap(), plain.to_json().unwrap());
+
+        let executor = RuleExecutor::new();
+        for input in [
+            r#"{"orders": [1, 2, 3, 4, 5, 6], "vip": true, "name": "x"}"#,
+            r#"{"orders": [1, 2, 3, 4, 5, 6], "vip": false, "name": "x"}"#,
+            r#"{"orders": [1], "name": "bob"}"#,
+            r#"{"orders": [], "name": "alice"}"#,
+            r#"{"name": "alice"}"#,
+            r#"{"orders": 7, "name": "alice"}"#,
+            r#"{"orders": [1], "name": 3}"#,
+        ] {
+            assert_eq!(
+                outcome(&executor, &optimized, input),
+                outcome(&executor, &plain, input),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_proves_exclusive_ranges() {
+        let exclusive_pair = |a: &str, b: &str| exclusive(&cond(a), &cond(b), false);
+
+        assert!(exclusive_pair("score < 500", "score >= 500 && score < 700"));
+        assert!(exclusive_pair(
+            "score >= 700",
+            "score >= 500 && score < 700"
+        ));
+        assert!(exclusive_pair(
+            "tier == \"gold\"",
+            "tier in [\"silver\", \"bronze\"]"
+        ));
+        assert!(exclusive_pair("score == 5", "score > 5"));
+        assert!(!exclusive_pair("score == 5", "score >= 5"));
+        assert!(!exclusive_pair("score < 500", "score <= 500"));
+        // Different fields, literal types or shapes prove nothing
+        assert!(!exclusive_pair("score < 500", "other >= 500"));
+        assert!(!exclusive_pair("score < 500", "score >= 500.0"));
+        assert!(!exclusive_pair("score < 500 || vip", "score >= 500"));
+        assert!(!exclusive(&Condition::Always, &cond("score > 1"), true));
+    }
+
+    #[test]
+    fn test_reorders_exclusive_branches_by_profile() {
+        let plain = ruleset(&[
+            ("score < 300", "low"),
+            ("score >= 300 && score < 700", "mid"),
+            ("score >= 700", "high"),
+            ("vip == true", "vip"),
+        ]);
+
+        let profiler = Arc::new(Profiler::new());
+        let mut executor = RuleExecutor::new();
+        executor.set_profiler(profiler.clone());
+        for i in 0..100 {
+            let score = if i % 10 == 0 { 100 } else { 800 };
+            outcome(&executor, &plain, &format!(r#"{{"score": {}}}"#, score));
+        }
+
+        let mut optimized = plain.clone();
+        let stats = RuleSetOptimizer::new()
+            .with_profiler(profiler)
+            .with_min_samples(50)
+            .optimize(&mut optimized);
+        assert_eq!(stats.exclusive_steps, 1);
+        assert_eq!(stats.reordered_steps, 1);
+
+        // `high` first; `vip` is not exclusive with anything and stays last
+        let plan = optimized.plan.as_ref().unwrap();
+        assert_eq!(plan.steps["route"].order, vec![2, 0, 1, 3]);
+
+        for input in [
+            r#"{"score": 100}"#,
+            r#"{"score": 500, "vip": true}"#,
+            r#"{"score": 900}"#,
+            r#"{"score": "high"}"#,
+            r#"{"vip": true}"#,
+        ] {
+            assert_eq!(
+                outcome(&executor, &optimized, input),
+                outcome(&executor, &plain, input),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_keeps_order_without_exclusivity() {
+        let plain = ruleset(&[("a > 1", "first"), ("b > 1", "second")]);
+
+        let profiler = Arc::new(Profiler::new());
+        let mut executor = RuleExecutor::new();
+        executor.set_profiler(profiler.clone());
+        for _ in 0..10 {
+            outcome(&executor, &plain, r#"{"a": 0, "b": 5}"#);
+        }
+
+        let optimizer = RuleSetOptimizer::new()
+            .with_profiler(profiler)
+            .with_min_samples(1);
+        let mut optimized = plain.clone();
+        assert_eq!(optimizer.optimize(&mut optimized).reordered_steps, 0);
+        assert!(!optimized.is_optimized());
+
+        // The author vouches for exclusivity
+        let mut optimized = plain.clone();
+        let stats = optimizer
+            .with_exclusive_step("route")
+            .optimize(&mut optimized);
+        assert_eq!(stats.reordered_steps, 1);
+        assert_eq!(
+            outcome(&executor, &optimized, r#"{"a": 0, "b": 5}"#),
+            "second"
+        );
+    }
+}
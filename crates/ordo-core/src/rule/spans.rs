@@ -0,0 +1,101 @@
+//! Tracing spans for rule execution
+//!
+//! With [`ExecutionOptions::spans`](super::ExecutionOptions::spans) the
+//! executor opens a span per step, per `CallRuleSet` sub-execution and per
+//! external call. They nest under the span that is current when execution
+//! starts, so a `tracing-opentelemetry` layer exports them as child spans of
+//! the caller's trace. Callers decide per execution, typically from the
+//! sampling flag of the trace context; without the option no span is created
+//! and the executor only checks a flag per step.
+//!
+//! | Span                 | Attributes                                                    |
+//! |----------------------|---------------------------------------------------------------|
+//! | `ordo.step`          | `ordo.step.id`, `ordo.step.kind`, `ordo.branch.index` (branch |
+//! |                      | taken), `ordo.next_step` or `ordo.result.code` (terminal)     |
+//! | `ordo.call_ruleset`  | `ordo.ruleset`, `ordo.result.code`                            |
+//! | `ordo.external_call` | `ordo.external.service`, `ordo.external.method`,              |
+//! |                      | `ordo.external.outcome` (`ok`, `error` or `skipped`)          |
+//!
+//! All spans also record `ordo.duration_us`, and `otel.status_code` /
+//! `otel.status_message` when they end in an error. They use the `ordo::rule`
+//! target at `INFO` level.
+
+use super::step::{Step, StepKind};
+use crate::context::Value;
+use crate::error::OrdoError;
+use tracing::field::Empty;
+use tracing::Span;
+
+const SPAN_TARGET: &str = "ordo::rule";
+
+/// Open the span of a step
+pub(crate) fn step(step: &Step) -> Span {
+    let kind = match step.kind {
+        StepKind::Decision { .. } => "decision",
+        StepKind::Action { .. } => "action",
+        StepKind::Terminal { .. } => "terminal",
+    };
+    tracing::info_span!(
+        target: SPAN_TARGET,
+        "ordo.step",
+        ordo.step.id = %step.id,
+        ordo.step.kind = kind,
+        ordo.branch.index = Empty,
+        ordo.next_step = Empty,
+        ordo.result.code = Empty,
+        ordo.duration_us = Empty,
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+    )
+}
+
+/// Open the span of a `CallRuleSet` sub-execution
+pub(crate) fn call_ruleset(ruleset: &str) -> Span {
+    tracing::info_span!(
+        target: SPAN_TARGET,
+        "ordo.call_ruleset",
+        ordo.ruleset = %ruleset,
+        ordo.result.code = Empty,
+        ordo.duration_us = Empty,
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+    )
+}
+
+/// Open the span of an external call
+pub(crate) fn external_call(service: &str, method: &str) -> Span {
+    tracing::info_span!(
+        target: SPAN_TARGET,
+        "ordo.external_call",
+        ordo.external.service = %service,
+        ordo.external.method = %method,
+        ordo.external.outcome = Empty,
+        ordo.duration_us = Empty,
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+    )
+}
+
+/// Record the outcome of the work a span covered
+pub(crate) fn finish<T>(span: &Span, duration_us: u64, result: &Result<T, OrdoError>) {
+    span.record("ordo.duration_us", duration_us as i64);
+    if let Err(e) = result {
+        span.record("otel.status_code", "ERROR");
+        span.record("otel.status_message", tracing::field::display(e));
+    }
+}
+
+/// Record the outcome of an external call (`None` = no call was made)
+pub(crate) fn finish_external_call(
+    span: &Span,
+    duration_us: u64,
+    result: &Result<Option<Value>, OrdoError>,
+) {
+    finish(span, duration_us, result);
+    let outcome = match result {
+        Ok(Some(_)) => "ok",
+        Ok(None) => "skipped",
+        Err(_) => "error",
+    };
+    span.record("ordo.external.outcome", outcome);
+}
@@ -9,7 +9,7 @@
 
 use crate::context::Value;
 use crate::error::Result;
-use crate::expr::{Expr, ExprParser};
+use crate::expr::{precompile_regexes, Expr, ExprParser};
 use serde::{Deserialize, Serialize};
 
 /// A step in the rule flow
@@ -86,11 +86,24 @@ impl Step {
     }
 
     /// Compile all expression strings in this step to expression ASTs.
-    /// This pre-parses conditions for faster evaluation at runtime.
+    /// This pre-parses conditions and precompiles literal regex patterns
+    /// for faster evaluation at runtime.
     pub fn compile(&mut self) -> Result<()> {
-        if let StepKind::Decision { branches, .. } = &mut self.kind {
-            for branch in branches {
-                branch.compile()?;
+        match &mut self.kind {
+            StepKind::Decision { branches, .. } => {
+                for branch in branches {
+                    branch.compile()?;
+                }
+            }
+            StepKind::Action { actions, .. } => {
+                for action in actions.iter() {
+                    action.compile()?;
+                }
+            }
+            StepKind::Terminal { result } => {
+                for (_, expr) in &result.output {
+                    precompile_regexes(expr)?;
+                }
             }
         }
         Ok(())
@@ -192,9 +205,13 @@ pub struct Branch {
 }
 
 impl Branch {
-    /// Compile the condition expression in this branch
+    /// Compile the condition expression and actions in this branch
     pub fn compile(&mut self) -> Result<()> {
-        self.condition.compile()
+        self.condition.compile()?;
+        for action in &self.actions {
+            action.compile()?;
+        }
+        Ok(())
     }
 }
 
@@ -233,9 +250,16 @@ impl Condition {
     /// This converts `ExpressionString` to `Expression` for faster evaluation.
     /// Returns Ok(()) if already compiled or compilation succeeds.
     pub fn compile(&mut self) -> Result<()> {
-        if let Condition::ExpressionString(s) = self {
-            let expr = ExprParser::parse(s)?;
-            *self = Condition::Expression(expr);
+        match self {
+            Condition::Always => {}
+            Condition::Expression(expr) => {
+                precompile_regexes(expr)?;
+            }
+            Condition::ExpressionString(s) => {
+                let expr = ExprParser::parse(s)?;
+                precompile_regexes(&expr)?;
+                *self = Condition::Expression(expr);
+            }
         }
         Ok(())
     }
@@ -275,6 +299,28 @@ pub struct Action {
 }
 
 impl Action {
+    /// Precompile the literal regex patterns of this action's expressions
+    pub fn compile(&self) -> Result<()> {
+        match &self.kind {
+            ActionKind::SetVariable { value, .. } | ActionKind::Metric { value, .. } => {
+                precompile_regexes(value)?;
+            }
+            ActionKind::CallRuleSet {
+                input_mapping: Some(expr),
+                ..
+            } => {
+                precompile_regexes(expr)?;
+            }
+            ActionKind::ExternalCall { params, .. } => {
+                for (_, expr) in params {
+                    precompile_regexes(expr)?;
+                }
+            }
+            ActionKind::Log { .. } | ActionKind::CallRuleSet { .. } => {}
+        }
+        Ok(())
+    }
+
     /// Create a set variable action
     pub fn set_var(name: impl Into<String>, value: Expr) -> Self {
         Self {
@@ -331,13 +377,15 @@ pub enum ActionKind {
         result_variable: String,
     },
 
-    /// External call (future)
+    /// External call, performed by the executor's `ExternalCallHandler`
     #[serde(skip)]
     ExternalCall {
         service: String,
         method: String,
         params: Vec<(String, Expr)>,
         timeout_ms: u64,
+        /// Variable to store the response (accessible as $variable)
+        result_variable: Option<String>,
     },
 }
 
@@ -0,0 +1,647 @@
+//! Step-through execution
+//!
+//! A [`RuleStepper`] runs a rule set one unit at a time and can be inspected
+//! between units, which is what a debugger needs. A unit is entering a step,
+//! evaluating one branch condition or running one action; each
+//! [`advance`](RuleStepper::advance) runs one and stops at the next
+//! [`Position`]. `CallRuleSet` actions push a frame for the called rule set,
+//! so stepping can follow execution into it.
+//!
+//! Units are the finest granularity: a condition or action expression runs to
+//! completion within its unit, and stepping does not stop between bytecode
+//! instructions. To see how a single expression evaluates, trace it with
+//! [`BytecodeVM::execute_with_trace`](crate::expr::BytecodeVM::execute_with_trace).
+//!
+//! The stepper reuses the executor's evaluator, resolver and action handling
+//! and produces the same results as [`RuleExecutor::execute`], with these
+//! differences:
+//! - branches are evaluated in definition order, ignoring optimizer plans
+//!   (their reordering never changes which branch is taken);
+//! - timeouts, tiering, profiling, tracing and observers do not apply;
+//! - results carry no duration (`duration_us` is 0), as wall time would
+//!   include the pauses.
+
+use super::executor::{ExecutionResult, RuleExecutor};
+use super::model::RuleSet;
+use super::observer::{ActionRef, StepId, StepRef};
+use super::step::{Action, ActionKind, StepKind};
+use crate::context::{Context, Value};
+use crate::error::{OrdoError, Result};
+use crate::expr::Expr;
+use hashbrown::HashMap;
+use std::sync::Arc;
+
+/// Where in a step execution stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Point {
+    /// Before the step runs
+    Step,
+    /// Before a branch condition of a decision step is evaluated
+    Branch(usize),
+    /// Before an action runs
+    Action(ActionRef),
+}
+
+/// The next unit a stepper will run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position<'a> {
+    /// Step the unit belongs to
+    pub step: StepRef<'a>,
+    /// The unit within the step
+    pub point: Point,
+    /// Number of `CallRuleSet` frames entered (0 in the rule set being run)
+    pub call_depth: usize,
+}
+
+/// Execution state of one rule set
+struct Frame {
+    ruleset: Arc<RuleSet>,
+    ctx: Context,
+    /// Current step ID
+    step: String,
+    point: Point,
+    /// Steps taken so far (checked against `max_depth`)
+    depth: usize,
+    /// Variable of the calling frame receiving this frame's result
+    result_variable: Option<String>,
+}
+
+impl Frame {
+    fn new(ruleset: Arc<RuleSet>, input: Value, result_variable: Option<String>) -> Self {
+        let ctx = Context::with_layout(input, ruleset.variable_layout());
+        let step = ruleset.config.entry_step.clone();
+        Self {
+            ruleset,
+            ctx,
+            step,
+            point: Point::Step,
+            depth: 0,
+            result_variable,
+        }
+    }
+
+    /// Move on to the start of `next_step`
+    fn goto(&mut self, next_step: &str) {
+        self.step = next_step.to_string();
+        self.point = Point::Step;
+        self.depth += 1;
+    }
+
+    /// Move past `action`, one of `count` actions followed by `next_step`
+    fn after_action(&mut self, action: ActionRef, count: usize, next_step: &str) {
+        if action.index + 1 < count {
+            self.point = Point::Action(ActionRef {
+                index: action.index + 1,
+                ..action
+            });
+        } else {
+            self.goto(next_step);
+        }
+    }
+}
+
+/// Pausable, resumable rule execution
+///
+/// # Example
+///
+/// ```ignore
+/// let mut stepper = RuleStepper::new(executor, ruleset, input);
+/// while stepper.step_into() {
+///     let position = stepper.position().unwrap();
+///     println!("{:?}: {:?}", position.step, stepper.variables());
+/// }
+/// let result = stepper.outcome().unwrap();
+/// ```
+pub struct RuleStepper {
+    executor: Arc<RuleExecutor>,
+    /// Frames of the running rule set and its `CallRuleSet` callees
+    frames: Vec<Frame>,
+    /// Result, once execution finished
+    outcome: Option<Result<ExecutionResult>>,
+    /// Steps entered so far, across frames
+    steps: usize,
+}
+
+impl RuleStepper {
+    /// Prepare to run `ruleset` against `input`, paused before its entry step
+    pub fn new(executor: Arc<RuleExecutor>, ruleset: Arc<RuleSet>, input: Value) -> Self {
+        Self {
+            executor,
+            frames: vec![Frame::new(ruleset, input, None)],
+            outcome: None,
+            steps: 0,
+        }
+    }
+
+    /// The next unit to run, or `None` once execution finished
+    pub fn position(&self) -> Option<Position<'_>> {
+        if self.outcome.is_some() {
+            return None;
+        }
+        let frame = self.frames.last()?;
+        Some(Position {
+            step: StepRef {
+                ruleset: &frame.ruleset.config.name,
+                id: StepId::Named(&frame.step),
+            },
+            point: frame.point,
+            call_depth: self.frames.len() - 1,
+        })
+    }
+
+    /// Whether execution finished
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Result of the execution, once finished
+    pub fn outcome(&self) -> Option<&Result<ExecutionResult>> {
+        self.outcome.as_ref()
+    }
+
+    /// Number of steps entered so far, including those of called rule sets
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Variables of the current frame
+    ///
+    /// After execution finished, these are the variables where it ended.
+    pub fn variables(&self) -> HashMap<String, Value> {
+        self.frames
+            .last()
+            .map(|frame| frame.ctx.variables())
+            .unwrap_or_default()
+    }
+
+    /// Input of the current frame
+    pub fn input(&self) -> &Value {
+        self.frames
+            .last()
+            .map_or(&Value::Null, |frame| frame.ctx.data())
+    }
+
+    /// Context of the current frame
+    pub fn context(&self) -> Option<&Context> {
+        self.frames.last().map(|frame| &frame.ctx)
+    }
+
+    /// Evaluate an expression in the current frame
+    pub fn eval(&self, expr: &Expr) -> Result<Value> {
+        let frame = self.frames.last().expect("a stepper always has a frame");
+        self.executor.evaluator().eval(expr, &frame.ctx)
+    }
+
+    /// Set a variable of the current frame
+    ///
+    /// Lets a debugger try out a different value before resuming.
+    pub fn set_variable(&mut self, name: &str, value: Value) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.ctx.set_variable(name, value);
+        }
+    }
+
+    /// Run one unit
+    ///
+    /// Returns `false` once execution finished (successfully or not).
+    pub fn advance(&mut self) -> bool {
+        if self.outcome.is_some() {
+            return false;
+        }
+        if let Err(e) = self.run_unit() {
+            self.outcome = Some(Err(e));
+        }
+        self.outcome.is_none()
+    }
+
+    /// Run at least one unit, then until `stop` holds or execution finished
+    ///
+    /// Returns `false` if execution finished.
+    pub fn run_until(&mut self, mut stop: impl FnMut(&Self) -> bool) -> bool {
+        while self.advance() {
+            if stop(self) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Run until the start of the next step, entering called rule sets
+    pub fn step_into(&mut self) -> bool {
+        self.run_until(|stepper| {
+            stepper
+                .position()
+                .is_some_and(|position| position.point == Point::Step)
+        })
+    }
+
+    /// Run until the start of the next step of this or a calling rule set
+    pub fn step_over(&mut self) -> bool {
+        let call_depth = self.frames.len() - 1;
+        self.run_until(|stepper| {
+            stepper.position().is_some_and(|position| {
+                position.point == Point::Step && position.call_depth <= call_depth
+            })
+        })
+    }
+
+    /// Run until the execution finishes
+    pub fn finish(&mut self) -> Option<&Result<ExecutionResult>> {
+        while self.advance() {}
+        self.outcome()
+    }
+
+    /// Run the unit at the current position
+    fn run_unit(&mut self) -> Result<()> {
+        let executor = Arc::clone(&self.executor);
+        let call_depth = self.frames.len() - 1;
+        let frame = self
+            .frames
+            .last_mut()
+            .expect("a running stepper has a frame");
+        let ruleset = Arc::clone(&frame.ruleset);
+        let step = ruleset
+            .get_step(&frame.step)
+            .ok_or_else(|| OrdoError::StepNotFound {
+                step_id: frame.step.clone(),
+            })?;
+
+        match (frame.point, &step.kind) {
+            (Point::Step, kind) => {
+                if frame.depth >= ruleset.config.max_depth {
+                    return Err(OrdoError::MaxDepthExceeded {
+                        max_depth: ruleset.config.max_depth,
+                    });
+                }
+                self.steps += 1;
+                match kind {
+                    StepKind::Decision { branches, .. } if !branches.is_empty() => {
+                        frame.point = Point::Branch(0);
+                    }
+                    StepKind::Decision { default_next, .. } => {
+                        let next = default_next.as_deref().ok_or_else(|| no_branch(&step.id))?;
+                        frame.goto(next);
+                    }
+                    StepKind::Action { actions, .. } if !actions.is_empty() => {
+                        frame.point = Point::Action(ActionRef {
+                            branch: None,
+                            index: 0,
+                        });
+                    }
+                    StepKind::Action { next_step, .. } => frame.goto(next_step),
+                    StepKind::Terminal { result } => {
+                        let output = executor.build_output(result, &frame.ctx)?;
+                        self.return_from_frame(ExecutionResult {
+                            code: result.code.clone(),
+                            message: result.message.clone(),
+                            output,
+                            trace: None,
+                            duration_us: 0,
+                        });
+                    }
+                }
+            }
+
+            (
+                Point::Branch(index),
+                StepKind::Decision {
+                    branches,
+                    default_next,
+                },
+            ) => {
+                let branch = &branches[index];
+                let field_missing = &ruleset.config.field_missing;
+                if executor.eval_branch(&branch.condition, &frame.ctx, field_missing)? {
+                    if branch.actions.is_empty() {
+                        frame.goto(&branch.next_step);
+                    } else {
+                        frame.point = Point::Action(ActionRef {
+                            branch: Some(index),
+                            index: 0,
+                        });
+                    }
+                } else if index + 1 < branches.len() {
+                    frame.point = Point::Branch(index + 1);
+                } else {
+                    let next = default_next.as_deref().ok_or_else(|| no_branch(&step.id))?;
+                    frame.goto(next);
+                }
+            }
+
+            (Point::Action(action_ref), kind) => {
+                let (actions, next_step) = actions_of(kind, action_ref)?;
+                let action = &actions[action_ref.index];
+                if let ActionKind::CallRuleSet {
+                    ruleset_name,
+                    input_mapping,
+                    result_variable,
+                } = &action.kind
+                {
+                    let remaining_call_depth = executor.max_call_depth().saturating_sub(call_depth);
+                    let (target, input) = executor.prepare_call(
+                        ruleset_name,
+                        input_mapping.as_ref(),
+                        &frame.ctx,
+                        remaining_call_depth,
+                    )?;
+                    self.frames
+                        .push(Frame::new(target, input, Some(result_variable.clone())));
+                    return Ok(());
+                }
+
+                let site = StepRef {
+                    ruleset: &ruleset.config.name,
+                    id: StepId::Named(&step.id),
+                };
+                executor.run_action(action, &mut frame.ctx, site)?;
+                frame.after_action(action_ref, actions.len(), next_step);
+            }
+
+            (Point::Branch(_), _) => {
+                return Err(OrdoError::internal_error(format!(
+                    "Step '{}' has no branches",
+                    step.id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Hand the result of the current frame to its caller, or finish
+    fn return_from_frame(&mut self, result: ExecutionResult) {
+        if self.frames.len() == 1 {
+            self.outcome = Some(Ok(result));
+            return;
+        }
+
+        let callee = self.frames.pop().expect("checked above");
+        let caller = self.frames.last_mut().expect("checked above");
+        if let Some(name) = callee.result_variable {
+            caller
+                .ctx
+                .set_variable(name, RuleExecutor::call_result(result));
+        }
+
+        // The call was the action at the caller's position
+        let Point::Action(action_ref) = caller.point else {
+            unreachable!("a call frame is entered from an action");
+        };
+        let ruleset = Arc::clone(&caller.ruleset);
+        let step = ruleset
+            .get_step(&caller.step)
+            .expect("the calling step exists");
+        let (actions, next_step) =
+            actions_of(&step.kind, action_ref).expect("the calling action exists");
+        caller.after_action(action_ref, actions.len(), next_step);
+    }
+}
+
+/// Actions of a step (or one of its branches) and the step that follows them
+fn actions_of(kind: &StepKind, action: ActionRef) -> Result<(&[Action], &str)> {
+    match (kind, action.branch) {
+        (StepKind::Action { actions, next_step }, None) => Ok((actions, next_step)),
+        (StepKind::Decision { branches, .. }, Some(branch)) => {
+            let branch = &branches[branch];
+            Ok((&branch.actions, &branch.next_step))
+        }
+        _ => Err(OrdoError::internal_error_static(
+            "Action position does not match its step",
+        )),
+    }
+}
+
+fn no_branch(step_id: &str) -> OrdoError {
+    OrdoError::eval_error(format!(
+        "No matching branch in step '{}' and no default",
+        step_id
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::{BinaryOp, ExprParser};
+    use crate::rule::{Condition, RuleSetResolver, Step, TerminalResult};
+
+    struct Resolver(Arc<RuleSet>);
+
+    impl RuleSetResolver for Resolver {
+        fn resolve(&self, name: &str) -> Option<Arc<RuleSet>> {
+            (name == "score").then(|| self.0.clone())
+        }
+    }
+
+    /// `main` calls `score` (which sets `points`), then branches on the result
+    fn setup() -> (Arc<RuleExecutor>, Arc<RuleSet>) {
+        let mut score = RuleSet::new("score", "compute");
+        score.add_step(Step::action(
+            "compute",
+            "Compute",
+            vec![Action::set_var(
+                "points",
+                Expr::binary(BinaryOp::Mul, Expr::field("age"), Expr::literal(10)),
+            )],
+            "done",
+        ));
+        score.add_step(Step::terminal(
+            "done",
+            "Done",
+            TerminalResult::new("SCORED").with_output("points", Expr::field("$points")),
+        ));
+
+        let mut main = RuleSet::new("main", "call");
+        main.add_step(Step::action(
+            "call",
+            "Call",
+            vec![
+                Action {
+                    kind: ActionKind::CallRuleSet {
+                        ruleset_name: "score".to_string(),
+                        input_mapping: None,
+                        result_variable: "scored".to_string(),
+                    },
+                    description: String::new(),
+                },
+                Action::set_var("called", Expr::literal(true)),
+            ],
+            "check",
+        ));
+        main.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string("age < 18"), "minor")
+                .branch_with_actions(
+                    Condition::from_string("age >= 18"),
+                    "adult",
+                    vec![Action::set_var("tier", Expr::literal("adult"))],
+                )
+                .build(),
+        );
+        main.add_step(Step::terminal(
+            "minor",
+            "Minor",
+            TerminalResult::new("MINOR"),
+        ));
+        main.add_step(Step::terminal(
+            "adult",
+            "Adult",
+            TerminalResult::new("ADULT").with_output("tier", Expr::field("$tier")),
+        ));
+
+        let mut executor = RuleExecutor::new();
+        executor.set_resolver(Arc::new(Resolver(Arc::new(score))));
+        (Arc::new(executor), Arc::new(main))
+    }
+
+    fn input(age: i64) -> Value {
+        serde_json::from_str(&format!(r#"{{"age": {age}}}"#)).unwrap()
+    }
+
+    /// `ruleset/step` of the current position
+    fn at(stepper: &RuleStepper) -> String {
+        let position = stepper.position().unwrap();
+        format!(
+            "{}/{}",
+            position.step.ruleset,
+            position.step.id.name().unwrap()
+        )
+    }
+
+    #[test]
+    fn test_step_into_follows_calls() {
+        let (executor, main) = setup();
+        let mut stepper = RuleStepper::new(executor, main, input(30));
+        assert_eq!(at(&stepper), "main/call");
+
+        let mut path = Vec::new();
+        while stepper.step_into() {
+            path.push((at(&stepper), stepper.position().unwrap().call_depth));
+        }
+        assert_eq!(
+            path,
+            vec![
+                ("score/compute".to_string(), 1),
+                ("score/done".to_string(), 1),
+                ("main/check".to_string(), 0),
+                ("main/adult".to_string(), 0),
+            ]
+        );
+
+        let result = stepper.outcome().unwrap().as_ref().unwrap();
+        assert_eq!(result.code, "ADULT");
+        assert_eq!(
+            result.output.get_path("tier"),
+            Some(&Value::string("adult"))
+        );
+        assert_eq!(stepper.steps(), 5);
+        assert!(stepper.position().is_none());
+        assert!(!stepper.advance());
+    }
+
+    #[test]
+    fn test_step_over_skips_calls() {
+        let (executor, main) = setup();
+        let mut stepper = RuleStepper::new(executor, main, input(30));
+
+        assert!(stepper.step_over());
+        assert_eq!(at(&stepper), "main/check");
+        let variables = stepper.variables();
+        assert_eq!(variables.get("called"), Some(&Value::Bool(true)));
+        assert_eq!(
+            variables["scored"].get_path("output.points"),
+            Some(&Value::int(300))
+        );
+
+        // Stepping over from inside a call returns to the caller
+        let (executor, main) = setup();
+        let mut stepper = RuleStepper::new(executor, main, input(30));
+        stepper.step_into();
+        assert_eq!(at(&stepper), "score/compute");
+        stepper.step_over();
+        assert_eq!(at(&stepper), "score/done");
+        stepper.step_over();
+        assert_eq!(at(&stepper), "main/check");
+    }
+
+    #[test]
+    fn test_advance_stops_at_branches_and_actions() {
+        let (executor, main) = setup();
+        let mut stepper = RuleStepper::new(executor, main, input(30));
+        stepper.step_over();
+
+        let mut points = Vec::new();
+        while stepper.advance() {
+            let position = stepper.position().unwrap();
+            points.push(position.point);
+            if position.point == Point::Step {
+                break;
+            }
+        }
+        assert_eq!(
+            points,
+            vec![
+                Point::Branch(0),
+                Point::Branch(1),
+                Point::Action(ActionRef {
+                    branch: Some(1),
+                    index: 0
+                }),
+                Point::Step,
+            ]
+        );
+        assert_eq!(at(&stepper), "main/adult");
+        assert_eq!(
+            stepper.variables().get("tier"),
+            Some(&Value::string("adult"))
+        );
+    }
+
+    #[test]
+    fn test_stepper_matches_executor() {
+        let (executor, main) = setup();
+        for age in [10, 30] {
+            let expected = executor.execute(&main, input(age)).unwrap();
+            let mut stepper = RuleStepper::new(executor.clone(), main.clone(), input(age));
+            let result = stepper.finish().unwrap().as_ref().unwrap();
+            assert_eq!(result.code, expected.code);
+            assert_eq!(result.output, expected.output);
+        }
+    }
+
+    #[test]
+    fn test_run_until_and_errors() {
+        let (executor, main) = setup();
+        let mut stepper = RuleStepper::new(executor, main, input(30));
+        assert!(stepper.run_until(|stepper| stepper
+            .position()
+            .is_some_and(|position| position.step.id.matches("adult"))));
+        assert_eq!(stepper.input(), &input(30));
+
+        // Calls fail without a resolver, leaving the failing frame inspectable
+        let (_, main) = setup();
+        let mut stepper = RuleStepper::new(Arc::new(RuleExecutor::new()), main, input(30));
+        assert!(!stepper.step_into());
+        assert!(matches!(stepper.outcome(), Some(Err(_))));
+        assert!(stepper.position().is_none());
+        assert!(stepper.variables().is_empty());
+    }
+
+    #[test]
+    fn test_set_variable_changes_the_outcome() {
+        let (executor, main) = setup();
+        let mut stepper = RuleStepper::new(executor, main, input(30));
+        stepper.step_into();
+        stepper.step_into();
+        assert_eq!(at(&stepper), "score/done");
+        let points = ExprParser::parse("$points + 1").unwrap();
+        assert_eq!(stepper.eval(&points).unwrap(), Value::int(301));
+
+        // What if the score had been lower?
+        stepper.set_variable("points", Value::int(5));
+        stepper.step_into();
+        assert_eq!(at(&stepper), "main/check");
+        assert_eq!(
+            stepper.variables()["scored"].get_path("output.points"),
+            Some(&Value::int(5))
+        );
+    }
+}
@@ -0,0 +1,1563 @@
+//! Tiered execution for decision conditions
+//!
+//! When enabled on a [`RuleExecutor`](super::RuleExecutor), every compiled
+//! branch condition starts in the tree-walking interpreter and is promoted as
+//! it gets hot:
+//!
+//! ```text
+//! Interpreter ──(bytecode_threshold runs)──► Bytecode ──(hot score ≥ hot_threshold)──► JIT
+//!                                                ▲                                      │
+//!                                                └───── deoptimize (type guard fails) ──┘
+//! ```
+//!
+//! - Bytecode compilation is cheap and happens inline on the executing thread.
+//! - JIT compilation runs on a background worker thread (hottest first). The
+//!   compiled form is published into the condition's slot before its tier is
+//!   switched, so concurrent evaluations never observe a half-built state.
+//! - JIT code runs on JSON inputs through a per-ruleset [`InferredShape`]:
+//!   when a condition gets hot, the fields it reads are added to the shape,
+//!   typed after the current input. Each execution packs its input into that
+//!   layout once; packing is the shape guard, and an input that does not fit
+//!   runs its JIT conditions on bytecode instead. A field that fails the guard
+//!   `max_guard_failures` times is dropped from the shape and the conditions
+//!   reading it are demoted to bytecode.
+//! - Each JIT condition owns its native code and literals. They are freed
+//!   once the condition is demoted, its expression changes (e.g. the ruleset
+//!   is replaced) or the manager is reset, and no evaluation still runs them.
+//!
+//! Only conditions whose JIT result provably matches the interpreter are
+//! promoted; for example integer arithmetic (which can overflow) and division
+//! (which can fail) stay on bytecode.
+
+use crate::context::Context;
+use crate::error::Result;
+#[cfg(feature = "jit")]
+use crate::expr::InferredShape;
+use crate::expr::{
+    BytecodeVM, CompiledExpr, Evaluator, Expr, ExprCompiler, FunctionRegistry, Profiler,
+    ProfilerConfig,
+};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Expressions larger than this stay interpreted (the VM has 256 registers)
+const MAX_BYTECODE_NODES: usize = 64;
+
+thread_local! {
+    /// The bytecode VM reuses a register file, so each thread gets its own
+    static TIER_VM: BytecodeVM = BytecodeVM::new();
+}
+
+/// Execution tier of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionTier {
+    /// Tree-walking evaluator
+    Interpreter = 0,
+    /// Register-based bytecode VM
+    Bytecode = 1,
+    /// Native code compiled by Cranelift
+    Jit = 2,
+}
+
+impl ExecutionTier {
+    /// Tier name as used in metrics labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Interpreter => "interpreter",
+            Self::Bytecode => "bytecode",
+            Self::Jit => "jit",
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Interpreter,
+            1 => Self::Bytecode,
+            _ => Self::Jit,
+        }
+    }
+}
+
+/// Configuration for tiered execution
+#[derive(Debug, Clone)]
+pub struct TieringConfig {
+    /// Promotion thresholds: `bytecode_threshold` executions move a condition
+    /// to bytecode, a hot score of `hot_threshold` queues it for JIT
+    pub profiler: ProfilerConfig,
+    /// Guard failures tolerated per shape field (or per condition, for guards
+    /// checked by native code) before the affected conditions are demoted
+    pub max_guard_failures: u64,
+    /// Compile on a background worker thread instead of the executing thread
+    pub background_compilation: bool,
+}
+
+impl Default for TieringConfig {
+    fn default() -> Self {
+        Self {
+            profiler: ProfilerConfig::default(),
+            max_guard_failures: 16,
+            background_compilation: true,
+        }
+    }
+}
+
+/// Tier state of a single condition
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionTierInfo {
+    /// Tenant the ruleset belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    /// RuleSet name
+    pub ruleset: String,
+    /// Decision step ID
+    pub step_id: String,
+    /// Branch index within the step
+    pub branch: usize,
+    /// Current tier
+    pub tier: ExecutionTier,
+    /// Number of evaluations
+    pub evaluations: u64,
+    /// Profiler hot score
+    pub hot_score: u64,
+    /// Number of JIT type guard failures
+    pub guard_failures: u64,
+    /// Whether the condition was demoted from JIT back to bytecode
+    pub deoptimized: bool,
+    /// Whether a JIT compilation is queued or running
+    pub jit_pending: bool,
+}
+
+/// Aggregate tiering statistics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TieringStats {
+    /// Conditions currently interpreted
+    pub interpreter_conditions: usize,
+    /// Conditions currently running on bytecode
+    pub bytecode_conditions: usize,
+    /// Conditions currently running as native code
+    pub jit_conditions: usize,
+    /// Total promotions to bytecode
+    pub bytecode_promotions: u64,
+    /// Total promotions to JIT
+    pub jit_promotions: u64,
+    /// JIT compilations that failed (condition stays on bytecode)
+    pub jit_compile_failures: u64,
+    /// Total JIT type guard failures
+    pub guard_failures: u64,
+    /// Total demotions from JIT to bytecode
+    pub deoptimizations: u64,
+    /// Native code modules currently held (one per JIT condition)
+    pub jit_modules: usize,
+    /// Native code size of those modules, in bytes
+    pub jit_code_bytes: usize,
+}
+
+/// Counters shared with the compilation worker
+#[derive(Default)]
+struct TierCounters {
+    bytecode_promotions: AtomicU64,
+    jit_promotions: AtomicU64,
+    jit_compile_failures: AtomicU64,
+    guard_failures: AtomicU64,
+    deoptimizations: AtomicU64,
+}
+
+/// Per-execution tiering state
+///
+/// Created once per `RuleExecutor::execute` call. The input is packed into the
+/// ruleset's shape the first time a JIT condition needs it.
+pub(crate) struct TierFrame<'a> {
+    tenant: Option<&'a str>,
+    ruleset: &'a str,
+    #[cfg(feature = "jit")]
+    packed: std::cell::OnceCell<Option<jit::PackedFrame>>,
+}
+
+/// Per-condition tier state
+struct ConditionSlot {
+    tenant: Option<String>,
+    ruleset: String,
+    step_id: String,
+    branch: usize,
+    /// Source expression (used to detect a replaced ruleset)
+    expr: Expr,
+    /// Profiler key
+    hash: u64,
+    /// Fuel the interpreter charges for the expression, less argument work
+    #[cfg(feature = "jit")]
+    fuel: u64,
+    tier: AtomicU8,
+    evaluations: AtomicU64,
+    guard_failures: AtomicU64,
+    /// The expression cannot run on bytecode; it stays interpreted
+    interpreter_only: AtomicBool,
+    /// A JIT compilation was requested
+    jit_requested: AtomicBool,
+    /// Never promote to JIT (ineligible, failed or deoptimized)
+    jit_disabled: AtomicBool,
+    deoptimized: AtomicBool,
+    bytecode: OnceLock<CompiledExpr>,
+    #[cfg(feature = "jit")]
+    jit: OnceLock<jit::JitCondition>,
+}
+
+impl ConditionSlot {
+    fn new(frame: &TierFrame<'_>, step_id: &str, branch: usize, expr: &Expr, hash: u64) -> Self {
+        Self {
+            tenant: frame.tenant.map(str::to_string),
+            ruleset: frame.ruleset.to_string(),
+            step_id: step_id.to_string(),
+            branch,
+            expr: expr.clone(),
+            hash,
+            #[cfg(feature = "jit")]
+            fuel: 1 + count_calls(expr),
+            tier: AtomicU8::new(ExecutionTier::Interpreter as u8),
+            evaluations: AtomicU64::new(0),
+            guard_failures: AtomicU64::new(0),
+            interpreter_only: AtomicBool::new(false),
+            jit_requested: AtomicBool::new(false),
+            jit_disabled: AtomicBool::new(!cfg!(feature = "jit")),
+            deoptimized: AtomicBool::new(false),
+            bytecode: OnceLock::new(),
+            #[cfg(feature = "jit")]
+            jit: OnceLock::new(),
+        }
+    }
+
+    #[inline]
+    fn tier(&self) -> ExecutionTier {
+        ExecutionTier::from_u8(self.tier.load(Ordering::Acquire))
+    }
+
+    #[inline]
+    fn matches(&self, frame: &TierFrame<'_>, step_id: &str, branch: usize, expr: &Expr) -> bool {
+        self.branch == branch
+            && self.step_id == step_id
+            && self.ruleset == frame.ruleset
+            && self.tenant.as_deref() == frame.tenant
+            && self.expr == *expr
+    }
+
+    /// Whether profiling can still lead to a JIT promotion
+    #[inline]
+    fn jit_candidate(&self) -> bool {
+        !self.jit_disabled.load(Ordering::Relaxed) && !self.jit_requested.load(Ordering::Relaxed)
+    }
+
+    /// A copy of this deoptimized slot without its native code
+    #[cfg(feature = "jit")]
+    fn demoted(&self) -> Self {
+        let load = |counter: &AtomicU64| AtomicU64::new(counter.load(Ordering::Relaxed));
+        Self {
+            tenant: self.tenant.clone(),
+            ruleset: self.ruleset.clone(),
+            step_id: self.step_id.clone(),
+            branch: self.branch,
+            expr: self.expr.clone(),
+            hash: self.hash,
+            fuel: self.fuel,
+            tier: AtomicU8::new(ExecutionTier::Bytecode as u8),
+            evaluations: load(&self.evaluations),
+            guard_failures: load(&self.guard_failures),
+            interpreter_only: AtomicBool::new(false),
+            jit_requested: AtomicBool::new(true),
+            jit_disabled: AtomicBool::new(true),
+            deoptimized: AtomicBool::new(true),
+            bytecode: self.bytecode.clone(),
+            jit: OnceLock::new(),
+        }
+    }
+}
+
+/// Tier manager for the conditions executed by a `RuleExecutor`
+///
+/// Conditions are identified by (tenant, ruleset name, step ID, branch index);
+/// a slot is reset when the expression at that position changes. Same-named
+/// rulesets of different tenants get separate slots and input shapes.
+pub struct TierManager {
+    config: TieringConfig,
+    profiler: Profiler,
+    slots: DashMap<u64, Arc<ConditionSlot>>,
+    hasher: hashbrown::hash_map::DefaultHashBuilder,
+    counters: Arc<TierCounters>,
+    /// Inferred input shape per (tenant, ruleset)
+    #[cfg(feature = "jit")]
+    shapes: DashMap<(Option<String>, String), Arc<jit::RulesetShape>>,
+    #[cfg(feature = "jit")]
+    jit: jit::JitBackend,
+}
+
+impl TierManager {
+    /// Create a tier manager
+    pub fn new(config: TieringConfig) -> Self {
+        Self {
+            profiler: Profiler::with_config(config.profiler.clone()),
+            config,
+            slots: DashMap::new(),
+            hasher: Default::default(),
+            counters: Arc::new(TierCounters::default()),
+            #[cfg(feature = "jit")]
+            shapes: DashMap::new(),
+            #[cfg(feature = "jit")]
+            jit: jit::JitBackend::default(),
+        }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &TieringConfig {
+        &self.config
+    }
+
+    /// Start tracking one execution of `ruleset` (of `tenant`, if any)
+    pub(crate) fn frame<'a>(&self, tenant: Option<&'a str>, ruleset: &'a str) -> TierFrame<'a> {
+        TierFrame {
+            tenant,
+            ruleset,
+            #[cfg(feature = "jit")]
+            packed: std::cell::OnceCell::new(),
+        }
+    }
+
+    /// Evaluate a condition on its current tier and return its truthiness
+    pub(crate) fn eval_condition(
+        &self,
+        frame: &TierFrame<'_>,
+        evaluator: &Evaluator,
+        step_id: &str,
+        branch: usize,
+        expr: &Expr,
+        ctx: &Context,
+    ) -> Result<bool> {
+        let slot = self.slot(frame, step_id, branch, expr);
+        let count = slot.evaluations.fetch_add(1, Ordering::Relaxed) + 1;
+
+        match slot.tier() {
+            ExecutionTier::Interpreter => {
+                let result = evaluator.eval(expr, ctx).map(|v| v.is_truthy());
+                if count >= self.config.profiler.bytecode_threshold {
+                    self.promote_to_bytecode(&slot);
+                }
+                result
+            }
+            ExecutionTier::Bytecode => self.eval_bytecode(frame, &slot, ctx),
+            ExecutionTier::Jit => {
+                #[cfg(feature = "jit")]
+                if let Some(jit) = slot.jit.get() {
+                    let packed = frame.packed.get_or_init(|| {
+                        jit::PackedFrame::pack(jit.ruleset(), ctx, self.config.max_guard_failures)
+                    });
+                    match jit.eval(packed.as_ref(), self.config.max_guard_failures) {
+                        jit::JitOutcome::Value(result) => {
+                            crate::budget::charge_fuel(slot.fuel)?;
+                            return Ok(result);
+                        }
+                        jit::JitOutcome::GuardFailed => self.guard_failed(&slot, false),
+                        jit::JitOutcome::Deoptimize => self.guard_failed(&slot, true),
+                    }
+                }
+                self.run_bytecode(&slot, ctx)
+            }
+        }
+    }
+
+    /// Find the slot for a condition, creating or resetting it as needed
+    fn slot(
+        &self,
+        frame: &TierFrame<'_>,
+        step_id: &str,
+        branch: usize,
+        expr: &Expr,
+    ) -> Arc<ConditionSlot> {
+        let key = self
+            .hasher
+            .hash_one((frame.tenant, frame.ruleset, step_id, branch));
+        if let Some(slot) = self.slots.get(&key) {
+            if slot.matches(frame, step_id, branch, expr) {
+                return Arc::clone(&slot);
+            }
+            // The profile of the replaced expression must not hold the new one back
+            self.profiler.remove_expr(key);
+        }
+
+        let slot = Arc::new(ConditionSlot::new(frame, step_id, branch, expr, key));
+        self.slots.insert(key, Arc::clone(&slot));
+        slot
+    }
+
+    fn promote_to_bytecode(&self, slot: &ConditionSlot) {
+        if slot.interpreter_only.load(Ordering::Relaxed) {
+            return;
+        }
+        if !is_bytecode_safe(&slot.expr) {
+            slot.interpreter_only.store(true, Ordering::Relaxed);
+            return;
+        }
+
+        slot.bytecode
+            .get_or_init(|| ExprCompiler::new().compile(&slot.expr));
+        if slot
+            .tier
+            .compare_exchange(
+                ExecutionTier::Interpreter as u8,
+                ExecutionTier::Bytecode as u8,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            self.counters
+                .bytecode_promotions
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    fn run_bytecode(&self, slot: &ConditionSlot, ctx: &Context) -> Result<bool> {
+        let compiled = slot
+            .bytecode
+            .get()
+            .expect("bytecode is compiled before a condition leaves the interpreter tier");
+        TIER_VM
+            .with(|vm| vm.execute(compiled, ctx))
+            .map(|v| v.is_truthy())
+    }
+
+    fn eval_bytecode(
+        &self,
+        frame: &TierFrame<'_>,
+        slot: &Arc<ConditionSlot>,
+        ctx: &Context,
+    ) -> Result<bool> {
+        if !slot.jit_candidate() {
+            return self.run_bytecode(slot, ctx);
+        }
+
+        let start = Instant::now();
+        let result = self.run_bytecode(slot, ctx);
+        self.profiler.record_expr(slot.hash, start.elapsed());
+
+        #[cfg(feature = "jit")]
+        if result.is_ok() {
+            self.maybe_request_jit(frame, slot, ctx);
+        }
+        #[cfg(not(feature = "jit"))]
+        let _ = frame;
+
+        result
+    }
+
+    #[cfg(feature = "jit")]
+    fn maybe_request_jit(&self, frame: &TierFrame<'_>, slot: &Arc<ConditionSlot>, ctx: &Context) {
+        let decision = self.profiler.should_jit_expr(slot.hash);
+        if !decision.should_jit || slot.jit_requested.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.profiler.mark_expr_jit_triggered(slot.hash);
+
+        let ruleset = self
+            .shapes
+            .entry((frame.tenant.map(str::to_string), frame.ruleset.to_string()))
+            .or_insert_with(|| Arc::new(jit::RulesetShape::new(frame.ruleset)))
+            .clone();
+        let priority = decision.priority.unwrap_or(crate::expr::JITPriority::Low);
+        let Some(request) = jit::JitRequest::new(slot, ruleset, ctx, priority, &self.counters)
+        else {
+            slot.jit_disabled.store(true, Ordering::Relaxed);
+            return;
+        };
+
+        if self.config.background_compilation {
+            self.jit.submit(request);
+        } else {
+            self.jit.compile(request);
+        }
+    }
+
+    /// Record a failed guard, demoting the condition to bytecode if `deoptimize` is set
+    #[cfg(feature = "jit")]
+    fn guard_failed(&self, slot: &ConditionSlot, deoptimize: bool) {
+        self.counters.guard_failures.fetch_add(1, Ordering::Relaxed);
+        let failures = slot.guard_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if !deoptimize {
+            return;
+        }
+
+        if slot
+            .tier
+            .compare_exchange(
+                ExecutionTier::Jit as u8,
+                ExecutionTier::Bytecode as u8,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            slot.jit_disabled.store(true, Ordering::Relaxed);
+            slot.deoptimized.store(true, Ordering::Relaxed);
+            self.counters
+                .deoptimizations
+                .fetch_add(1, Ordering::Relaxed);
+            // Swap in a slot without the native code; it is freed once the
+            // evaluations still holding this one finish
+            if let Some(mut current) = self.slots.get_mut(&slot.hash) {
+                if std::ptr::eq(&**current, slot) {
+                    *current = Arc::new(slot.demoted());
+                }
+            }
+            tracing::debug!(
+                ruleset = %slot.ruleset,
+                step = %slot.step_id,
+                branch = slot.branch,
+                failures,
+                "Condition deoptimized from JIT to bytecode"
+            );
+        }
+    }
+
+    /// The input shape inferred for a ruleset of `tenant`, if any of its
+    /// conditions got hot
+    #[cfg(feature = "jit")]
+    pub fn shape(&self, tenant: Option<&str>, ruleset: &str) -> Option<Arc<InferredShape>> {
+        self.shapes
+            .get(&(tenant.map(str::to_string), ruleset.to_string()))
+            .map(|shape| shape.current())
+    }
+
+    /// Tenants and names of the rulesets with an inferred shape, sorted
+    #[cfg(feature = "jit")]
+    pub fn shaped_rulesets(&self) -> Vec<(Option<String>, String)> {
+        let mut names: Vec<(Option<String>, String)> =
+            self.shapes.iter().map(|e| e.key().clone()).collect();
+        names.sort();
+        names
+    }
+
+    /// Snapshot of every tracked condition, ordered by tenant, ruleset, step and branch
+    pub fn snapshot(&self) -> Vec<ConditionTierInfo> {
+        let mut infos: Vec<ConditionTierInfo> = self
+            .slots
+            .iter()
+            .map(|slot| {
+                let tier = slot.tier();
+                ConditionTierInfo {
+                    tenant_id: slot.tenant.clone(),
+                    ruleset: slot.ruleset.clone(),
+                    step_id: slot.step_id.clone(),
+                    branch: slot.branch,
+                    tier,
+                    evaluations: slot.evaluations.load(Ordering::Relaxed),
+                    hot_score: self
+                        .profiler
+                        .get_expr_profile(slot.hash)
+                        .map_or(0, |(_, _, score)| score),
+                    guard_failures: slot.guard_failures.load(Ordering::Relaxed),
+                    deoptimized: slot.deoptimized.load(Ordering::Relaxed),
+                    jit_pending: tier != ExecutionTier::Jit
+                        && slot.jit_requested.load(Ordering::Relaxed)
+                        && !slot.jit_disabled.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        infos.sort_by(|a, b| {
+            (&a.tenant_id, &a.ruleset, &a.step_id, a.branch).cmp(&(
+                &b.tenant_id,
+                &b.ruleset,
+                &b.step_id,
+                b.branch,
+            ))
+        });
+        infos
+    }
+
+    /// Aggregate statistics
+    pub fn stats(&self) -> TieringStats {
+        let mut stats = TieringStats {
+            bytecode_promotions: self.counters.bytecode_promotions.load(Ordering::Relaxed),
+            jit_promotions: self.counters.jit_promotions.load(Ordering::Relaxed),
+            jit_compile_failures: self.counters.jit_compile_failures.load(Ordering::Relaxed),
+            guard_failures: self.counters.guard_failures.load(Ordering::Relaxed),
+            deoptimizations: self.counters.deoptimizations.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+        for slot in self.slots.iter() {
+            match slot.tier() {
+                ExecutionTier::Interpreter => stats.interpreter_conditions += 1,
+                ExecutionTier::Bytecode => stats.bytecode_conditions += 1,
+                ExecutionTier::Jit => stats.jit_conditions += 1,
+            }
+            #[cfg(feature = "jit")]
+            if let Some(jit) = slot.jit.get() {
+                stats.jit_modules += 1;
+                stats.jit_code_bytes += jit.code_size();
+            }
+        }
+        stats
+    }
+
+    /// Forget all tier state (conditions restart in the interpreter)
+    ///
+    /// Native code is freed as soon as running evaluations finish.
+    pub fn reset(&self) {
+        self.slots.clear();
+        self.profiler.clear();
+        #[cfg(feature = "jit")]
+        self.shapes.clear();
+    }
+}
+
+/// Check whether the bytecode VM evaluates `expr` exactly like the interpreter
+fn is_bytecode_safe(expr: &Expr) -> bool {
+    fn visit(expr: &Expr, nodes: &mut usize) -> bool {
+        *nodes += 1;
+        if *nodes > MAX_BYTECODE_NODES {
+            return false;
+        }
+        match expr {
+            Expr::Literal(_) | Expr::Field(_) | Expr::Exists(_) => true,
+            Expr::Binary { left, right, .. } => visit(left, nodes) && visit(right, nodes),
+            Expr::Unary { operand, .. } => visit(operand, nodes),
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => visit(condition, nodes) && visit(then_branch, nodes) && visit(else_branch, nodes),
+            // Custom functions are only registered on the evaluator
+            Expr::Call { name, args } => {
+                FunctionRegistry::is_builtin(name) && args.iter().all(|a| visit(a, nodes))
+            }
+            Expr::Coalesce(exprs) => exprs.iter().all(|e| visit(e, nodes)),
+            // The VM only materializes literal arrays and no objects
+            Expr::Array(items) => items.iter().all(|e| matches!(e, Expr::Literal(_))),
+            Expr::Object(_) => false,
+        }
+    }
+
+    let mut nodes = 0;
+    visit(expr, &mut nodes)
+}
+
+/// Number of function calls in `expr`
+#[cfg(feature = "jit")]
+fn count_calls(expr: &Expr) -> u64 {
+    match expr {
+        Expr::Literal(_) | Expr::Field(_) | Expr::Exists(_) => 0,
+        Expr::Binary { left, right, .. } => count_calls(left) + count_calls(right),
+        Expr::Unary { operand, .. } => count_calls(operand),
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => count_calls(condition) + count_calls(then_branch) + count_calls(else_branch),
+        Expr::Call { args, .. } => 1 + args.iter().map(count_calls).sum::<u64>(),
+        Expr::Coalesce(exprs) | Expr::Array(exprs) => exprs.iter().map(count_calls).sum(),
+        Expr::Object(entries) => entries.iter().map(|(_, e)| count_calls(e)).sum(),
+    }
+}
+
+/// Collect the data fields read by `expr`, or `None` if it reads anything
+/// that cannot be packed once per execution (variables, array items)
+#[cfg(feature = "jit")]
+fn collect_fields(expr: &Expr, fields: &mut Vec<String>) -> Option<()> {
+    match expr {
+        Expr::Literal(_) => {}
+        Expr::Field(path) => {
+            if path.starts_with('$') || path.starts_with("item.") || path == "item" {
+                return None;
+            }
+            if !fields.contains(path) {
+                fields.push(path.clone());
+            }
+        }
+        Expr::Binary { left, right, .. } => {
+            collect_fields(left, fields)?;
+            collect_fields(right, fields)?;
+        }
+        Expr::Unary { operand, .. } => collect_fields(operand, fields)?,
+        Expr::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            collect_fields(condition, fields)?;
+            collect_fields(then_branch, fields)?;
+            collect_fields(else_branch, fields)?;
+        }
+        Expr::Call { args, .. } | Expr::Coalesce(args) | Expr::Array(args) => {
+            for arg in args {
+                collect_fields(arg, fields)?;
+            }
+        }
+        Expr::Object(_) | Expr::Exists(_) => return None,
+    }
+    Some(())
+}
+
+#[cfg(feature = "jit")]
+mod jit {
+    //! JIT backend: ruleset shapes, compiled conditions and the worker
+
+    use super::{collect_fields, ConditionSlot, ExecutionTier, TierCounters};
+    use crate::context::Context;
+    use crate::expr::{
+        InferredShape, JITPriority, PackedInput, SchemaCompiledFunction, SchemaJITCompiler,
+        ShapeKind,
+    };
+    use parking_lot::{Mutex, RwLock};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, OnceLock};
+
+    /// The code module and literal pool of one condition
+    ///
+    /// Freed on drop. A [`JitCondition`] lives in its slot, and evaluations
+    /// hold the slot while they run its code, so nothing can still call into
+    /// the module by then.
+    struct JitModule(Mutex<Option<SchemaJITCompiler>>);
+
+    impl Drop for JitModule {
+        fn drop(&mut self) {
+            if let Some(compiler) = self.0.get_mut().take() {
+                // Safety: see above
+                unsafe { compiler.free_memory() };
+            }
+        }
+    }
+
+    /// The shape inferred for one ruleset
+    ///
+    /// Fields are appended as conditions become hot, typed after the input
+    /// that made them hot. A field whose type keeps changing is unguarded and
+    /// the conditions reading it are deoptimized.
+    pub(super) struct RulesetShape {
+        shape: RwLock<Arc<InferredShape>>,
+        /// Guard failures per field index
+        mismatches: Mutex<Vec<u64>>,
+    }
+
+    impl RulesetShape {
+        pub(super) fn new(ruleset: &str) -> Self {
+            Self {
+                shape: RwLock::new(Arc::new(InferredShape::new(ruleset))),
+                mismatches: Mutex::new(Vec::new()),
+            }
+        }
+
+        pub(super) fn current(&self) -> Arc<InferredShape> {
+            Arc::clone(&self.shape.read())
+        }
+
+        /// Add the fields of `expr` that are not in the shape yet, typed after `ctx`
+        ///
+        /// Returns the shape and the indices of the fields `expr` reads.
+        fn extend(
+            &self,
+            expr: &crate::expr::Expr,
+            ctx: &Context,
+        ) -> Option<(Arc<InferredShape>, Vec<usize>)> {
+            let mut paths = Vec::new();
+            collect_fields(expr, &mut paths)?;
+
+            let mut guard = self.shape.write();
+            let mut shape = (**guard).clone();
+            let mut fields = Vec::with_capacity(paths.len());
+            for path in &paths {
+                let index = match shape.field(path) {
+                    Some((index, field)) if field.guarded => index,
+                    Some(_) => return None,
+                    None => {
+                        let kind = ShapeKind::of(ctx.get(path)?)?;
+                        shape = shape.with_field(path, kind)?;
+                        shape.fields().len() - 1
+                    }
+                };
+                fields.push(index);
+            }
+
+            if shape.fields().len() != guard.fields().len() {
+                *guard = Arc::new(shape);
+            }
+            Some((Arc::clone(&guard), fields))
+        }
+
+        /// Count a guard failure on a field; unguard it once it keeps failing
+        fn record_mismatch(&self, index: usize, max_failures: u64) {
+            let mut mismatches = self.mismatches.lock();
+            if mismatches.len() <= index {
+                mismatches.resize(index + 1, 0);
+            }
+            mismatches[index] += 1;
+            if mismatches[index] < max_failures {
+                return;
+            }
+
+            let mut shape = self.shape.write();
+            if shape.fields().get(index).is_some_and(|f| f.guarded) {
+                tracing::debug!(
+                    shape = %shape.name(),
+                    field = %shape.fields()[index].path,
+                    "Field type is unstable, removing it from the JIT shape"
+                );
+                *shape = Arc::new(shape.without_guard(index));
+            }
+        }
+    }
+
+    /// The current input packed into a ruleset's shape
+    pub(super) struct PackedFrame {
+        shape: Arc<InferredShape>,
+        input: PackedInput,
+    }
+
+    impl PackedFrame {
+        /// Pack `ctx`; on a guard failure the mismatch is recorded and `None` returned
+        pub(super) fn pack(
+            ruleset: &RulesetShape,
+            ctx: &Context,
+            max_failures: u64,
+        ) -> Option<Self> {
+            let shape = ruleset.current();
+            match shape.pack(ctx) {
+                Ok(input) => Some(Self { shape, input }),
+                Err(index) => {
+                    ruleset.record_mismatch(index, max_failures);
+                    None
+                }
+            }
+        }
+    }
+
+    /// Result of running a JIT condition
+    pub(super) enum JitOutcome {
+        /// The condition's truthiness
+        Value(bool),
+        /// This input failed a guard; evaluate it on bytecode
+        GuardFailed,
+        /// A field the condition reads is no longer guarded, or the native code
+        /// keeps failing; demote it
+        Deoptimize,
+    }
+
+    /// A JIT-compiled condition guarded by its ruleset's shape
+    pub(super) struct JitCondition {
+        /// Shape field indices the condition reads
+        fields: Vec<usize>,
+        function: SchemaCompiledFunction,
+        ruleset: Arc<RulesetShape>,
+        /// Runtime guard failures reported by the native code
+        native_failures: AtomicU64,
+        /// Owns the code and its literals
+        _module: JitModule,
+    }
+
+    impl JitCondition {
+        pub(super) fn ruleset(&self) -> &RulesetShape {
+            &self.ruleset
+        }
+
+        /// Native code size in bytes
+        pub(super) fn code_size(&self) -> usize {
+            self.function.code_size
+        }
+
+        fn guarded_in(&self, shape: &InferredShape) -> bool {
+            self.fields
+                .iter()
+                .all(|&index| shape.fields().get(index).is_some_and(|f| f.guarded))
+        }
+
+        #[inline]
+        pub(super) fn eval(&self, packed: Option<&PackedFrame>, max_failures: u64) -> JitOutcome {
+            let Some(packed) = packed else {
+                return if self.guarded_in(&self.ruleset.current()) {
+                    JitOutcome::GuardFailed
+                } else {
+                    JitOutcome::Deoptimize
+                };
+            };
+            if !self.guarded_in(&packed.shape) {
+                return JitOutcome::Deoptimize;
+            }
+
+            // Safety: shapes are append-only, so the packed layout contains every
+            // field at the offset the function was compiled for
+            match unsafe { self.function.call_ptr_checked(packed.input.as_ptr()) } {
+                Ok(raw) => JitOutcome::Value(raw != 0.0),
+                Err(_)
+                    if self.native_failures.fetch_add(1, Ordering::Relaxed) + 1 >= max_failures =>
+                {
+                    JitOutcome::Deoptimize
+                }
+                Err(_) => JitOutcome::GuardFailed,
+            }
+        }
+    }
+
+    /// A request to compile one condition
+    pub(super) struct JitRequest {
+        pub(super) slot: Arc<ConditionSlot>,
+        pub(super) ruleset: Arc<RulesetShape>,
+        pub(super) shape: Arc<InferredShape>,
+        pub(super) fields: Vec<usize>,
+        pub(super) priority: JITPriority,
+        pub(super) counters: Arc<TierCounters>,
+    }
+
+    impl JitRequest {
+        /// Build a request if the condition can run on JIT with interpreter semantics
+        pub(super) fn new(
+            slot: &Arc<ConditionSlot>,
+            ruleset: Arc<RulesetShape>,
+            ctx: &Context,
+            priority: JITPriority,
+            counters: &Arc<TierCounters>,
+        ) -> Option<Self> {
+            let (shape, fields) = ruleset.extend(&slot.expr, ctx)?;
+            ShapeKind::of_expr(&slot.expr, &|path| {
+                shape
+                    .field(path)
+                    .filter(|(_, field)| field.guarded)
+                    .map(|(_, field)| field.kind)
+            })?;
+            Some(Self {
+                slot: Arc::clone(slot),
+                ruleset,
+                shape,
+                fields,
+                priority,
+                counters: Arc::clone(counters),
+            })
+        }
+    }
+
+    /// The lazily started worker thread
+    #[derive(Default)]
+    pub(super) struct JitBackend {
+        worker: OnceLock<Option<crossbeam_channel::Sender<JitRequest>>>,
+    }
+
+    impl JitBackend {
+        /// Queue a request for the background worker
+        pub(super) fn submit(&self, request: JitRequest) {
+            let worker = self.worker.get_or_init(|| {
+                let (tx, rx) = crossbeam_channel::unbounded::<JitRequest>();
+                let spawned = std::thread::Builder::new()
+                    .name("ordo-jit".to_string())
+                    .spawn(move || run_worker(rx));
+                match spawned {
+                    Ok(_) => Some(tx),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Failed to start JIT worker, compiling inline");
+                        None
+                    }
+                }
+            });
+
+            match worker {
+                Some(tx) => {
+                    if let Err(e) = tx.send(request) {
+                        self.compile(e.into_inner());
+                    }
+                }
+                None => self.compile(request),
+            }
+        }
+
+        /// Compile on the calling thread
+        pub(super) fn compile(&self, request: JitRequest) {
+            compile_request(request);
+        }
+    }
+
+    /// Worker loop: batch pending requests and compile the hottest first
+    fn run_worker(rx: crossbeam_channel::Receiver<JitRequest>) {
+        while let Ok(first) = rx.recv() {
+            let mut batch = vec![first];
+            batch.extend(rx.try_iter());
+            batch.sort_by_key(|request| std::cmp::Reverse(request.priority));
+            for request in batch {
+                compile_request(request);
+            }
+        }
+    }
+
+    /// Compile a condition into its own module
+    fn compile_request(request: JitRequest) {
+        let slot = &request.slot;
+        let compiled = SchemaJITCompiler::new().and_then(|mut compiler| {
+            let cache = crate::expr::jit::SchemaJITCache::default();
+            let function =
+                compiler.compile_with_schema(&slot.expr, slot.hash, request.shape.schema(), &cache);
+            let module = JitModule(Mutex::new(Some(compiler)));
+            function.map(|function| (function, module))
+        });
+
+        match compiled {
+            Ok((function, module)) => {
+                let jit = JitCondition {
+                    fields: request.fields,
+                    function,
+                    ruleset: request.ruleset,
+                    native_failures: AtomicU64::new(0),
+                    _module: module,
+                };
+                // Publish the code first, then switch the tier
+                if slot.jit.set(jit).is_ok()
+                    && slot
+                        .tier
+                        .compare_exchange(
+                            ExecutionTier::Bytecode as u8,
+                            ExecutionTier::Jit as u8,
+                            Ordering::AcqRel,
+                            Ordering::Relaxed,
+                        )
+                        .is_ok()
+                {
+                    request
+                        .counters
+                        .jit_promotions
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Err(e) => {
+                tracing::debug!(
+                    ruleset = %slot.ruleset,
+                    step = %slot.step_id,
+                    branch = slot.branch,
+                    error = %e,
+                    "JIT compilation failed, condition stays on bytecode"
+                );
+                fail(&request);
+            }
+        }
+    }
+
+    fn fail(request: &JitRequest) {
+        request.slot.jit_disabled.store(true, Ordering::Relaxed);
+        request
+            .counters
+            .jit_compile_failures
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::Value;
+    use crate::expr::ExprParser;
+    use serde_json::json;
+
+    fn eager_config(bytecode_threshold: u64) -> TieringConfig {
+        TieringConfig {
+            profiler: ProfilerConfig {
+                hot_threshold: 0,
+                bytecode_threshold,
+                ..Default::default()
+            },
+            max_guard_failures: 2,
+            background_compilation: false,
+        }
+    }
+
+    fn ctx(value: serde_json::Value) -> Context {
+        Context::new(serde_json::from_value(value).unwrap())
+    }
+
+    fn tier_of(manager: &TierManager) -> ExecutionTier {
+        manager.snapshot()[0].tier
+    }
+
+    #[test]
+    fn test_promotes_through_tiers() {
+        let manager = TierManager::new(eager_config(2));
+        let evaluator = Evaluator::new();
+        let expr = ExprParser::parse("score > 600.5 && active").unwrap();
+        let input = ctx(json!({"score": 700.0, "active": true}));
+
+        let eval = || {
+            manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "check",
+                    0,
+                    &expr,
+                    &input,
+                )
+                .unwrap()
+        };
+
+        assert!(eval());
+        assert_eq!(tier_of(&manager), ExecutionTier::Interpreter);
+        assert!(eval());
+        assert_eq!(tier_of(&manager), ExecutionTier::Bytecode);
+        assert!(eval());
+
+        #[cfg(feature = "jit")]
+        {
+            assert_eq!(tier_of(&manager), ExecutionTier::Jit);
+            assert!(eval());
+            let stats = manager.stats();
+            assert_eq!(stats.jit_conditions, 1);
+            assert_eq!(stats.jit_promotions, 1);
+        }
+        assert_eq!(manager.stats().bytecode_promotions, 1);
+    }
+
+    #[test]
+    fn test_results_match_interpreter() {
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let sources = [
+            "amount * 1.1 >= limit",
+            "age >= 18 && !(vip == false)",
+            "(if ratio > 0.5 then score else -score) > 0",
+            "age == 30",
+            "age + 1 > 30",
+        ];
+        let inputs = [
+            json!({"amount": 100.0, "limit": 110, "age": 30, "vip": true, "ratio": 0.7, "score": 1.5}),
+            json!({"amount": 99.0, "limit": 110, "age": 17, "vip": false, "ratio": 0.2, "score": 1.5}),
+            json!({"amount": 0.0, "limit": -1, "age": 31, "vip": true, "ratio": 0.5, "score": -2.0}),
+        ];
+
+        for (branch, source) in sources.iter().enumerate() {
+            let expr = ExprParser::parse(source).unwrap();
+            // Several rounds so later inputs run on the promoted tiers
+            for _ in 0..3 {
+                for input in &inputs {
+                    let input = ctx(input.clone());
+                    let expected = evaluator.eval(&expr, &input).unwrap().is_truthy();
+                    let actual = manager
+                        .eval_condition(
+                            &manager.frame(None, "rs"),
+                            &evaluator,
+                            "step",
+                            branch,
+                            &expr,
+                            &input,
+                        )
+                        .unwrap();
+                    assert_eq!(actual, expected, "{} on {:?}", source, input.data());
+                }
+            }
+        }
+        assert_eq!(manager.stats().guard_failures, 0);
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_guard_failure_deoptimizes() {
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let expr = ExprParser::parse("score > 10.5").unwrap();
+        let float_input = ctx(json!({"score": 20.0}));
+        let int_input = ctx(json!({"score": 5}));
+
+        for _ in 0..2 {
+            manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &expr,
+                    &float_input,
+                )
+                .unwrap();
+        }
+        assert_eq!(tier_of(&manager), ExecutionTier::Jit);
+
+        // An int where a float was observed fails the guard but still evaluates
+        let eval_int = || {
+            manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &expr,
+                    &int_input,
+                )
+                .unwrap()
+        };
+        assert!(!eval_int());
+        assert_eq!(tier_of(&manager), ExecutionTier::Jit);
+        assert!(!eval_int());
+
+        let info = &manager.snapshot()[0];
+        assert_eq!(info.tier, ExecutionTier::Bytecode);
+        assert!(info.deoptimized);
+        assert_eq!(info.guard_failures, 2);
+        let stats = manager.stats();
+        assert_eq!(stats.deoptimizations, 1);
+        assert_eq!(stats.jit_modules, 0);
+
+        // Stays on bytecode afterwards
+        assert!(manager
+            .eval_condition(
+                &manager.frame(None, "rs"),
+                &evaluator,
+                "s",
+                0,
+                &expr,
+                &float_input
+            )
+            .unwrap());
+        assert_eq!(tier_of(&manager), ExecutionTier::Bytecode);
+    }
+
+    #[test]
+    fn test_ineligible_conditions_stay_on_lower_tiers() {
+        let manager = TierManager::new(eager_config(1));
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .functions_mut()
+            .register("always", |_: &[Value]| Ok(Value::bool(true)));
+        let input = ctx(json!({"name": "alice", "age": 40}));
+
+        // Integer arithmetic can overflow: bytecode only
+        let int_math = ExprParser::parse("age + 1 > 30").unwrap();
+        // Custom function: interpreter only
+        let custom = ExprParser::parse("always()").unwrap();
+        for _ in 0..3 {
+            assert!(manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &int_math,
+                    &input
+                )
+                .unwrap());
+            assert!(manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    1,
+                    &custom,
+                    &input
+                )
+                .unwrap());
+        }
+
+        let snapshot = manager.snapshot();
+        assert_eq!(snapshot[0].tier, ExecutionTier::Bytecode);
+        assert!(!snapshot[0].jit_pending);
+        assert_eq!(snapshot[1].tier, ExecutionTier::Interpreter);
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_ruleset_shape_is_shared() {
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let conditions = [
+            ExprParser::parse(r#"user.tier == "gold" && amount > 100"#).unwrap(),
+            ExprParser::parse(r#"country in ["US", "CA"]"#).unwrap(),
+            ExprParser::parse("amount * 0.5 > limit").unwrap(),
+        ];
+        let hot =
+            ctx(json!({"user": {"tier": "gold"}, "country": "US", "amount": 250, "limit": 80.0}));
+        let cold =
+            ctx(json!({"user": {"tier": "basic"}, "country": "FR", "amount": 20, "limit": 80.0}));
+
+        for input in [&hot, &hot, &cold, &hot] {
+            let frame = manager.frame(None, "orders");
+            for (branch, expr) in conditions.iter().enumerate() {
+                let expected = evaluator.eval(expr, input).unwrap().is_truthy();
+                let actual = manager
+                    .eval_condition(&frame, &evaluator, "s", branch, expr, input)
+                    .unwrap();
+                assert_eq!(actual, expected);
+            }
+        }
+        assert_eq!(manager.stats().jit_conditions, 3);
+
+        // One layout for the whole ruleset, fields in the order they got hot
+        let shape = manager.shape(None, "orders").unwrap();
+        let paths: Vec<&str> = shape.fields().iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["user.tier", "amount", "country", "limit"]);
+        assert_eq!(
+            shape.field("amount").unwrap().1.kind,
+            crate::expr::ShapeKind::Int
+        );
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_unstable_field_only_deoptimizes_its_readers() {
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let on_score = ExprParser::parse("score > 0.5").unwrap();
+        let on_flag = ExprParser::parse("flag").unwrap();
+
+        let run = |input: &Context| {
+            let frame = manager.frame(None, "rs");
+            let a = manager
+                .eval_condition(&frame, &evaluator, "s", 0, &on_score, input)
+                .unwrap();
+            let b = manager
+                .eval_condition(&frame, &evaluator, "s", 1, &on_flag, input)
+                .unwrap();
+            (a, b)
+        };
+
+        for _ in 0..2 {
+            assert_eq!(run(&ctx(json!({"score": 0.9, "flag": true}))), (true, true));
+        }
+        assert_eq!(manager.stats().jit_conditions, 2);
+
+        // `score` turns into an int: both conditions fall back while the
+        // shape still guards it, then only its reader is demoted
+        for _ in 0..3 {
+            assert_eq!(run(&ctx(json!({"score": 1, "flag": true}))), (true, true));
+        }
+        let snapshot = manager.snapshot();
+        assert_eq!(snapshot[0].tier, ExecutionTier::Bytecode);
+        assert!(snapshot[0].deoptimized);
+        assert_eq!(snapshot[1].tier, ExecutionTier::Jit);
+        assert!(!manager.shape(None, "rs").unwrap().fields()[0].guarded);
+    }
+
+    #[test]
+    fn test_changed_expression_resets_slot() {
+        let manager = TierManager::new(eager_config(2));
+        let evaluator = Evaluator::new();
+        let input = ctx(json!({"age": 40}));
+        let old = ExprParser::parse("age > 18").unwrap();
+        let new = ExprParser::parse("age > 50").unwrap();
+
+        for _ in 0..2 {
+            assert!(manager
+                .eval_condition(&manager.frame(None, "rs"), &evaluator, "s", 0, &old, &input)
+                .unwrap());
+        }
+        assert!(!manager
+            .eval_condition(&manager.frame(None, "rs"), &evaluator, "s", 0, &new, &input)
+            .unwrap());
+        let info = &manager.snapshot()[0];
+        assert_eq!(info.tier, ExecutionTier::Interpreter);
+        assert_eq!(info.evaluations, 1);
+
+        manager.reset();
+        assert!(manager.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_tenants_get_separate_slots() {
+        let manager = TierManager::new(eager_config(2));
+        let evaluator = Evaluator::new();
+        let input = ctx(json!({"age": 40}));
+        let acme = ExprParser::parse("age > 18").unwrap();
+        let globex = ExprParser::parse("age > 50").unwrap();
+
+        // Same ruleset name, different definitions: neither resets the other
+        for _ in 0..3 {
+            assert!(manager
+                .eval_condition(
+                    &manager.frame(Some("acme"), "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &acme,
+                    &input
+                )
+                .unwrap());
+            assert!(!manager
+                .eval_condition(
+                    &manager.frame(Some("globex"), "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &globex,
+                    &input
+                )
+                .unwrap());
+        }
+        let snapshot = manager.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].tenant_id.as_deref(), Some("acme"));
+        assert_eq!(snapshot[1].tenant_id.as_deref(), Some("globex"));
+        for info in &snapshot {
+            assert_eq!(info.evaluations, 3);
+            assert!(info.tier > ExecutionTier::Interpreter);
+        }
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_jit_charges_fuel_like_the_interpreter() {
+        use crate::budget::{BudgetScope, ResourceBudget};
+
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let expr = ExprParser::parse(r#"starts_with(name, "a") && score > 0.5"#).unwrap();
+        let input = ctx(json!({"name": "ada", "score": 0.9}));
+        let fuel = |eval: &dyn Fn() -> bool| {
+            let scope = BudgetScope::enter(&ResourceBudget::unlimited());
+            assert!(eval());
+            scope.usage().fuel
+        };
+
+        let interpreted = fuel(&|| evaluator.eval(&expr, &input).unwrap().is_truthy());
+        for _ in 0..3 {
+            manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &expr,
+                    &input,
+                )
+                .unwrap();
+        }
+        assert_eq!(tier_of_or_none(&manager), Some(ExecutionTier::Jit));
+        let jit = fuel(&|| {
+            manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &expr,
+                    &input,
+                )
+                .unwrap()
+        });
+        assert_eq!(interpreted, 2);
+        assert_eq!(jit, interpreted);
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_tenants_get_separate_shapes() {
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let expr = ExprParser::parse("score > 0").unwrap();
+
+        for _ in 0..2 {
+            for (tenant, input) in [
+                ("acme", ctx(json!({"score": 0.5}))),
+                ("globex", ctx(json!({"score": 5}))),
+            ] {
+                manager
+                    .eval_condition(
+                        &manager.frame(Some(tenant), "rs"),
+                        &evaluator,
+                        "s",
+                        0,
+                        &expr,
+                        &input,
+                    )
+                    .unwrap();
+            }
+        }
+        let kind = |tenant| {
+            manager
+                .shape(Some(tenant), "rs")
+                .unwrap()
+                .field("score")
+                .unwrap()
+                .1
+                .kind
+        };
+        assert_eq!(kind("acme"), crate::expr::ShapeKind::Float);
+        assert_eq!(kind("globex"), crate::expr::ShapeKind::Int);
+        assert!(manager.shape(None, "rs").is_none());
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_background_compilation() {
+        let config = TieringConfig {
+            background_compilation: true,
+            ..eager_config(1)
+        };
+        let manager = TierManager::new(config);
+        let evaluator = Evaluator::new();
+        let expr = ExprParser::parse("rate * 2.0 < 1.0").unwrap();
+        let input = ctx(json!({"rate": 0.25}));
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        while tier_of_or_none(&manager) != Some(ExecutionTier::Jit) {
+            assert!(
+                Instant::now() < deadline,
+                "condition was not promoted to JIT"
+            );
+            assert!(manager
+                .eval_condition(
+                    &manager.frame(None, "rs"),
+                    &evaluator,
+                    "s",
+                    0,
+                    &expr,
+                    &input
+                )
+                .unwrap());
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(manager
+            .eval_condition(
+                &manager.frame(None, "rs"),
+                &evaluator,
+                "s",
+                0,
+                &expr,
+                &input
+            )
+            .unwrap());
+    }
+
+    #[cfg(feature = "jit")]
+    #[test]
+    fn test_replaced_and_reset_conditions_release_native_code() {
+        let manager = TierManager::new(eager_config(1));
+        let evaluator = Evaluator::new();
+        let input = ctx(json!({"score": 0.9}));
+        let promote = |source: &str| {
+            let expr = ExprParser::parse(source).unwrap();
+            for _ in 0..2 {
+                manager
+                    .eval_condition(
+                        &manager.frame(None, "rs"),
+                        &evaluator,
+                        "s",
+                        0,
+                        &expr,
+                        &input,
+                    )
+                    .unwrap();
+            }
+            manager.stats()
+        };
+
+        let stats = promote("score > 0.5");
+        assert_eq!(stats.jit_conditions, 1);
+        assert_eq!(stats.jit_modules, 1);
+        assert!(stats.jit_code_bytes > 0);
+
+        // A replaced ruleset resets the slot and drops the old module
+        let slot = Arc::downgrade(&manager.slots.iter().next().unwrap());
+        let stats = promote("score > 0.7");
+        assert!(slot.upgrade().is_none());
+        assert_eq!(stats.jit_modules, 1);
+
+        manager.reset();
+        assert_eq!(manager.stats().jit_modules, 0);
+        assert_eq!(manager.stats().jit_code_bytes, 0);
+    }
+
+    #[cfg(feature = "jit")]
+    fn tier_of_or_none(manager: &TierManager) -> Option<ExecutionTier> {
+        manager.snapshot().first().map(|info| info.tier)
+    }
+}
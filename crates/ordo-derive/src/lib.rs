@@ -152,6 +152,11 @@ pub fn derive_typed_context(input: TokenStream) -> TokenStream {
 
 /// Convert a Rust type to the corresponding FieldType expression
 fn rust_type_to_field_type(ty: &Type) -> proc_macro2::TokenStream {
+    if let Some(inner) = option_inner_type(ty) {
+        let inner = rust_type_to_field_type(inner);
+        return quote!(ordo_core::context::FieldType::Optional(::std::boxed::Box::new(#inner)));
+    }
+
     let type_str = quote!(#ty).to_string().replace(' ', "");
 
     match type_str.as_str() {
@@ -180,6 +185,24 @@ fn rust_type_to_field_type(ty: &Type) -> proc_macro2::TokenStream {
     }
 }
 
+/// Extract `T` from `Option<T>` (including `std::option::Option<T>`)
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// Derive macro for generating TypedContext for prost-generated types
 ///
 /// This is similar to TypedContext but specifically handles prost attributes
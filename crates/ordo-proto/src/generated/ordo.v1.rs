@@ -213,7 +213,8 @@ pub struct TypedExecuteRequest {
     /// Name of the ruleset to execute
     #[prost(string, tag = "1")]
     pub ruleset_name: ::prost::alloc::string::String,
-    /// Schema name for the context (must be registered on server)
+    /// Fully-qualified message type of the context (e.g. "loan.LoanRequest"),
+    /// from the tenant's uploaded descriptor set
     #[prost(string, tag = "2")]
     pub schema_name: ::prost::alloc::string::String,
     /// Context data as protobuf bytes
@@ -230,7 +231,7 @@ pub struct TypedEvalRequest {
     /// Expression to evaluate
     #[prost(string, tag = "1")]
     pub expression: ::prost::alloc::string::String,
-    /// Schema name for the context
+    /// Fully-qualified message type of the context
     #[prost(string, tag = "2")]
     pub schema_name: ::prost::alloc::string::String,
     /// Context data as protobuf bytes
@@ -551,6 +552,54 @@ pub mod ordo_service_client {
                 .insert(GrpcMethod::new("ordo.v1.OrdoService", "Health"));
             self.inner.unary(req, path, codec).await
         }
+        /// Execute a ruleset with a protobuf-encoded input
+        /// The message type must be part of the tenant's uploaded descriptor set
+        pub async fn typed_execute(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TypedExecuteRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExecuteResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ordo.v1.OrdoService/TypedExecute",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ordo.v1.OrdoService", "TypedExecute"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Evaluate an expression against a protobuf-encoded context
+        /// using Schema-Aware JIT with direct field access
+        pub async fn typed_eval(
+            &mut self,
+            request: impl tonic::IntoRequest<super::TypedEvalRequest>,
+        ) -> std::result::Result<tonic::Response<super::TypedEvalResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/ordo.v1.OrdoService/TypedEval",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("ordo.v1.OrdoService", "TypedEval"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -603,6 +652,18 @@ pub mod ordo_service_server {
             &self,
             request: tonic::Request<super::HealthRequest>,
         ) -> std::result::Result<tonic::Response<super::HealthResponse>, tonic::Status>;
+        /// Execute a ruleset with a protobuf-encoded input
+        /// The message type must be part of the tenant's uploaded descriptor set
+        async fn typed_execute(
+            &self,
+            request: tonic::Request<super::TypedExecuteRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExecuteResponse>, tonic::Status>;
+        /// Evaluate an expression against a protobuf-encoded context
+        /// using Schema-Aware JIT with direct field access
+        async fn typed_eval(
+            &self,
+            request: tonic::Request<super::TypedEvalRequest>,
+        ) -> std::result::Result<tonic::Response<super::TypedEvalResponse>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct OrdoServiceServer<T: OrdoService> {
@@ -957,6 +1018,98 @@ pub mod ordo_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/ordo.v1.OrdoService/TypedExecute" => {
+                    #[allow(non_camel_case_types)]
+                    struct TypedExecuteSvc<T: OrdoService>(pub Arc<T>);
+                    impl<
+                        T: OrdoService,
+                    > tonic::server::UnaryService<super::TypedExecuteRequest>
+                    for TypedExecuteSvc<T> {
+                        type Response = super::ExecuteResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TypedExecuteRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OrdoService>::typed_execute(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TypedExecuteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/ordo.v1.OrdoService/TypedEval" => {
+                    #[allow(non_camel_case_types)]
+                    struct TypedEvalSvc<T: OrdoService>(pub Arc<T>);
+                    impl<
+                        T: OrdoService,
+                    > tonic::server::UnaryService<super::TypedEvalRequest>
+                    for TypedEvalSvc<T> {
+                        type Response = super::TypedEvalResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::TypedEvalRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OrdoService>::typed_eval(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = TypedEvalSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
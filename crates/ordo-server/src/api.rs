@@ -6,8 +6,10 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use ordo_core::budget::{BudgetScope, ResourceBudget};
+use ordo_core::context::{JsonSchemaSet, MessageSchema};
 use ordo_core::prelude::*;
-use ordo_core::rule::ExecutionOptions;
+use ordo_core::rule::{ExecutionOptions, FieldUsage, InputSeed};
 use ordo_core::signature::{strip_signature, SignatureAlgorithm, SignatureConfig};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
@@ -15,9 +17,10 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use crate::error::ApiError;
-use crate::json::SimdJson;
+use crate::json::{Accept, BodyFormat, Encoded, SimdJson, SimdJsonBody};
 use crate::metrics;
 use crate::middleware::tenant::TenantContext;
+use crate::telemetry;
 use crate::AppState;
 
 /// API Result type
@@ -99,6 +102,106 @@ pub struct ExecuteRequest {
     pub trace: bool,
 }
 
+impl ExecuteRequest {
+    /// Decode a request body, materializing only the input paths in `usage`
+    ///
+    /// Traced requests keep the whole input so trace snapshots are complete.
+    /// MessagePack and CBOR bodies are decoded in full.
+    fn decode(
+        buf: &mut [u8],
+        format: BodyFormat,
+        usage: &FieldUsage,
+    ) -> std::result::Result<Self, String> {
+        use simd_json::prelude::*;
+
+        if format != BodyFormat::Json {
+            return Self::decode_streaming(buf, format, usage);
+        }
+
+        let tape = simd_json::to_tape(buf).map_err(|e| e.to_string())?;
+        let body = tape.as_value();
+        if !body.is_object() {
+            return Err("expected an object".to_string());
+        }
+        let trace = match body.get("trace") {
+            None => false,
+            Some(value) => value
+                .as_bool()
+                .ok_or("invalid type for `trace`, expected a boolean")?,
+        };
+        let input = body.get("input").ok_or("missing field `input`")?;
+        let input = if trace || usage.requires_full_input() {
+            Value::from_tape(input)
+        } else {
+            usage.project_tape(input)
+        };
+        Ok(Self { input, trace })
+    }
+
+    /// Decode a MessagePack or CBOR body
+    ///
+    /// These formats cannot be indexed like a tape, so a first pass reads
+    /// only `trace` (skipping the input) to decide whether to project.
+    fn decode_streaming(
+        buf: &mut [u8],
+        format: BodyFormat,
+        usage: &FieldUsage,
+    ) -> std::result::Result<Self, String> {
+        #[derive(Deserialize)]
+        struct TraceFlag {
+            #[serde(default)]
+            trace: bool,
+        }
+
+        let TraceFlag { trace } = format.decode(buf)?;
+        if trace || usage.requires_full_input() {
+            return format.decode(buf);
+        }
+        format.decode_seed(buf, ProjectedExecuteRequest(usage.input_seed()))
+    }
+}
+
+/// Decodes an [`ExecuteRequest`] whose input is projected by a [`InputSeed`]
+struct ProjectedExecuteRequest<'a>(InputSeed<'a>);
+
+impl<'de> serde::de::DeserializeSeed<'de> for ProjectedExecuteRequest<'_> {
+    type Value = ExecuteRequest;
+
+    fn deserialize<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<ExecuteRequest, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for ProjectedExecuteRequest<'_> {
+    type Value = ExecuteRequest;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an execute request")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<ExecuteRequest, A::Error> {
+        let mut input = None;
+        let mut trace = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "input" => input = Some(map.next_value_seed(self.0)?),
+                "trace" => trace = map.next_value()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        let input = input.ok_or_else(|| serde::de::Error::missing_field("input"))?;
+        Ok(ExecuteRequest { input, trace })
+    }
+}
+
 /// Execute response
 #[derive(Serialize)]
 pub struct ExecuteResponse {
@@ -225,6 +328,9 @@ pub struct BatchExecuteResponse {
 /// Maximum batch size limit
 const MAX_BATCH_SIZE: usize = 1000;
 
+/// Maximum number of sample rows (given or generated) of a filter verification
+const MAX_VERIFY_ROWS: usize = 10_000;
+
 // ==================== Helper Functions ====================
 
 /// Build trace info from execution trace (extracted to avoid code duplication)
@@ -334,9 +440,13 @@ pub async fn create_ruleset(
         None
     };
 
-    store
-        .put_for_tenant(&tenant.id, ruleset)
-        .map_err(|errors| ApiError::bad_request(format!("Validation errors: {:?}", errors)))?;
+    // Compile under the tenant's budget so literal regex patterns are
+    // checked against its limits at upload time
+    let put = {
+        let _budget = BudgetScope::enter(&tenant.config.resource_budget);
+        store.put_for_tenant(&tenant.id, ruleset)
+    };
+    put.map_err(|errors| ApiError::bad_request(format!("Validation errors: {:?}", errors)))?;
 
     metrics::set_tenant_rules_count(&tenant.id, store.list_for_tenant(&tenant.id).len() as i64);
 
@@ -427,8 +537,9 @@ pub async fn execute_ruleset(
     Extension(tenant): Extension<TenantContext>,
     connect_info: Option<ConnectInfo<SocketAddr>>,
     Path(name): Path<String>,
-    SimdJson(request): SimdJson<ExecuteRequest>,
-) -> ApiResult<Json<ExecuteResponse>> {
+    Accept(response_format): Accept,
+    SimdJsonBody(mut body, format): SimdJsonBody,
+) -> ApiResult<Encoded<ExecuteResponse>> {
     let start = Instant::now();
 
     // Track active executions
@@ -446,6 +557,13 @@ pub async fn execute_ruleset(
         // Lock is released here when store goes out of scope
     };
 
+    // Decode only the input fields the ruleset reads
+    let request =
+        ExecuteRequest::decode(&mut body, format, &ruleset.field_usage()).map_err(|e| {
+            metrics::dec_active_executions();
+            ApiError::bad_request(format!("{} parse error: {}", format.name(), e))
+        })?;
+
     // Inject external data as $data field in input
     let mut input = request.input;
     if !external_data.is_null() {
@@ -455,19 +573,25 @@ pub async fn execute_ruleset(
     }
 
     // Build execution options for tenant-specific overrides (avoids cloning RuleSet)
-    let exec_options = if tenant.config.execution_timeout_ms > 0 || request.trace {
-        Some(ExecutionOptions {
-            timeout_ms: if tenant.config.execution_timeout_ms > 0 {
-                Some(tenant.config.execution_timeout_ms)
-            } else {
-                None
-            },
-            enable_trace: if request.trace { Some(true) } else { None },
-            max_depth: None,
-        })
-    } else {
-        None
-    };
+    let budget = tenant.config.execution_budget();
+    let spans = telemetry::sampled();
+    let exec_options =
+        if tenant.config.execution_timeout_ms > 0 || request.trace || budget.is_some() || spans {
+            Some(ExecutionOptions {
+                timeout_ms: if tenant.config.execution_timeout_ms > 0 {
+                    Some(tenant.config.execution_timeout_ms)
+                } else {
+                    None
+                },
+                enable_trace: if request.trace { Some(true) } else { None },
+                max_depth: None,
+                budget,
+                spans,
+                observer: None,
+            })
+        } else {
+            None
+        };
 
     // Execute without holding the lock and without cloning RuleSet
     let result = match state
@@ -536,13 +660,16 @@ pub async fn execute_ruleset(
         )
         .await;
 
-    Ok(Json(ExecuteResponse {
-        code: result.code,
-        message: result.message,
-        output: result.output,
-        duration_us: result.duration_us,
-        trace,
-    }))
+    Ok(Encoded(
+        response_format,
+        ExecuteResponse {
+            code: result.code,
+            message: result.message,
+            output: result.output,
+            duration_us: result.duration_us,
+            trace,
+        },
+    ))
 }
 
 /// Execute a ruleset with multiple inputs (batch execution)
@@ -556,8 +683,9 @@ pub async fn execute_ruleset_batch(
     State(state): State<AppState>,
     Extension(tenant): Extension<TenantContext>,
     Path(name): Path<String>,
+    Accept(response_format): Accept,
     SimdJson(request): SimdJson<BatchExecuteRequest>,
-) -> ApiResult<Json<BatchExecuteResponse>> {
+) -> ApiResult<Encoded<BatchExecuteResponse>> {
     let start = Instant::now();
 
     // Validate batch size
@@ -602,6 +730,9 @@ pub async fn execute_ruleset_batch(
             None
         },
         max_depth: None,
+        budget: tenant.config.execution_budget(),
+        spans: telemetry::sampled(),
+        observer: None,
     });
 
     let executor = state.executor.clone();
@@ -640,11 +771,15 @@ pub async fn execute_ruleset_batch(
         let par_executor = executor.clone();
         let par_ruleset = Arc::clone(&ruleset);
         let par_exec_options = Arc::clone(&exec_options);
+        // Rayon workers don't inherit the request span; re-enter it so
+        // execution spans stay in the request's trace
+        let request_span = tracing::Span::current();
         tokio::task::spawn_blocking(move || {
             use rayon::prelude::*;
             inputs
                 .into_par_iter()
                 .map(|input| {
+                    let _span = request_span.enter();
                     let start_one = Instant::now();
                     match par_executor.execute_with_options(
                         &par_ruleset,
@@ -713,15 +848,18 @@ pub async fn execute_ruleset_batch(
 
     metrics::dec_active_executions();
 
-    Ok(Json(BatchExecuteResponse {
-        results,
-        summary: BatchExecuteSummary {
-            total: batch_size,
-            success,
-            failed,
-            total_duration_us,
+    Ok(Encoded(
+        response_format,
+        BatchExecuteResponse {
+            results,
+            summary: BatchExecuteSummary {
+                total: batch_size,
+                success,
+                failed,
+                total_duration_us,
+            },
         },
-    }))
+    ))
 }
 /// Evaluate an expression (debug endpoint)
 pub async fn eval_expression(
@@ -955,6 +1093,9 @@ pub struct CreateTenantRequest {
     /// Maximum number of rules (None = unlimited)
     #[serde(default)]
     pub max_rules: Option<usize>,
+    /// Per-execution resource budget (None = server default)
+    #[serde(default)]
+    pub resource_budget: Option<ResourceBudget>,
 }
 
 fn default_enabled() -> bool {
@@ -982,6 +1123,9 @@ pub struct UpdateTenantRequest {
     /// Maximum number of rules (None = unlimited)
     #[serde(default)]
     pub max_rules: Option<Option<usize>>,
+    /// Per-execution resource budget
+    #[serde(default)]
+    pub resource_budget: Option<ResourceBudget>,
 }
 
 /// Tenant info response
@@ -994,6 +1138,7 @@ pub struct TenantInfo {
     pub burst_limit: Option<u32>,
     pub execution_timeout_ms: u64,
     pub max_rules: Option<usize>,
+    pub resource_budget: ResourceBudget,
     pub rules_count: usize,
 }
 
@@ -1014,6 +1159,7 @@ pub async fn list_tenants(State(state): State<AppState>) -> ApiResult<Json<Vec<T
                 burst_limit: config.burst_limit,
                 execution_timeout_ms: config.execution_timeout_ms,
                 max_rules: config.max_rules,
+                resource_budget: config.resource_budget,
                 rules_count,
             }
         })
@@ -1044,6 +1190,7 @@ pub async fn get_tenant(
         burst_limit: config.burst_limit,
         execution_timeout_ms: config.execution_timeout_ms,
         max_rules: config.max_rules,
+        resource_budget: config.resource_budget,
         rules_count,
     }))
 }
@@ -1068,15 +1215,20 @@ pub async fn create_tenant(
         )));
     }
 
-    let default_timeout_ms = state.tenant_manager.defaults().default_timeout_ms;
+    let defaults = state.tenant_manager.defaults();
     let config = crate::tenant::TenantConfig {
         id: request.id.clone(),
         name: request.name,
         enabled: request.enabled,
         qps_limit: request.qps_limit,
         burst_limit: request.burst_limit,
-        execution_timeout_ms: request.execution_timeout_ms.unwrap_or(default_timeout_ms),
+        execution_timeout_ms: request
+            .execution_timeout_ms
+            .unwrap_or(defaults.default_timeout_ms),
         max_rules: request.max_rules,
+        resource_budget: request
+            .resource_budget
+            .unwrap_or_else(|| defaults.default_budget.clone()),
         metadata: std::collections::HashMap::new(),
     };
 
@@ -1099,6 +1251,7 @@ pub async fn create_tenant(
             burst_limit: config.burst_limit,
             execution_timeout_ms: config.execution_timeout_ms,
             max_rules: config.max_rules,
+            resource_budget: config.resource_budget,
             rules_count,
         }),
     ))
@@ -1135,6 +1288,9 @@ pub async fn update_tenant(
     if let Some(max_rules) = request.max_rules {
         config.max_rules = max_rules;
     }
+    if let Some(resource_budget) = request.resource_budget {
+        config.resource_budget = resource_budget;
+    }
 
     state
         .tenant_manager
@@ -1153,6 +1309,7 @@ pub async fn update_tenant(
         burst_limit: config.burst_limit,
         execution_timeout_ms: config.execution_timeout_ms,
         max_rules: config.max_rules,
+        resource_budget: config.resource_budget,
         rules_count,
     }))
 }
@@ -1204,6 +1361,12 @@ pub struct FilterRequest {
     pub field_mapping: std::collections::HashMap<String, String>,
     #[serde(default = "default_max_paths")]
     pub max_paths: usize,
+    #[serde(default)]
+    pub sql: ordo_core::filter::sql::SqlOptions,
+    #[serde(default)]
+    pub mongo: ordo_core::filter::mongo::MongoOptions,
+    #[serde(default)]
+    pub elasticsearch: ordo_core::filter::elasticsearch::ElasticsearchOptions,
 }
 
 fn default_max_paths() -> usize {
@@ -1218,6 +1381,35 @@ pub struct FilterResponse {
     pub always_matches: bool,
     pub never_matches: bool,
     pub unknown_fields: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<Value>>,
+    pub clauses_before: usize,
+    pub clauses_after: usize,
+    pub untranslated: Vec<String>,
+}
+
+impl From<FilterRequest> for ordo_core::filter::FilterRequest {
+    fn from(request: FilterRequest) -> Self {
+        Self {
+            known_input: request.known_input,
+            target_results: request.target_results,
+            format: request.format,
+            field_mapping: request.field_mapping,
+            max_paths: request.max_paths,
+            sql: request.sql,
+            mongo: request.mongo,
+            elasticsearch: request.elasticsearch,
+        }
+    }
+}
+
+/// Filter verification request: a filter request plus the sample rows
+#[derive(Deserialize)]
+pub struct VerifyFilterRequest {
+    #[serde(flatten)]
+    pub filter: FilterRequest,
+    #[serde(default)]
+    pub verify: ordo_core::filter::verify::VerifyOptions,
 }
 
 // ==================== External Data API ====================
@@ -1272,6 +1464,171 @@ pub async fn delete_data(
     }
 }
 
+// ==================== Protobuf Schema API ====================
+
+/// Message types of a tenant's descriptor set
+#[derive(Serialize)]
+pub struct SchemaSetResponse {
+    pub messages: Vec<String>,
+}
+
+/// List the protobuf message types of a tenant
+pub async fn list_schemas(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+) -> ApiResult<Json<SchemaSetResponse>> {
+    let store = state.store.read().await;
+    let messages = store
+        .get_schemas_for_tenant(&tenant.id)
+        .map(|set| set.message_names().into_iter().map(String::from).collect())
+        .unwrap_or_default();
+    Ok(Json(SchemaSetResponse { messages }))
+}
+
+/// Upload a serialized `FileDescriptorSet`, replacing the tenant's schemas
+pub async fn put_schemas(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    body: axum::body::Bytes,
+) -> ApiResult<Json<SchemaSetResponse>> {
+    let mut store = state.store.write().await;
+    let set = store
+        .put_schemas_for_tenant(&tenant.id, &body)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidData => ApiError::bad_request(e.to_string()),
+            _ => ApiError::internal(format!("Failed to store schemas: {}", e)),
+        })?;
+    Ok(Json(SchemaSetResponse {
+        messages: set.message_names().into_iter().map(String::from).collect(),
+    }))
+}
+
+/// Delete a tenant's protobuf schemas
+pub async fn delete_schemas(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+) -> ApiResult<StatusCode> {
+    let mut store = state.store.write().await;
+    if store.delete_schemas_for_tenant(&tenant.id) {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::not_found("No schemas uploaded"))
+    }
+}
+
+/// Get the typed layout of a message type
+pub async fn get_schema(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<MessageSchema>> {
+    let store = state.store.read().await;
+    let schema = store
+        .get_schemas_for_tenant(&tenant.id)
+        .and_then(|set| set.schema(&name))
+        .ok_or_else(|| ApiError::not_found(format!("Message type '{}' not found", name)))?;
+    Ok(Json(schema.as_ref().clone()))
+}
+
+// ==================== Input JSON Schema API ====================
+
+/// A ruleset's input JSON Schema and the message schemas derived from it
+///
+/// Serialized as `{"schema": <document>, "root", "messages", "enums"}`.
+fn input_schema_response(set: &JsonSchemaSet) -> ApiResult<Json<serde_json::Value>> {
+    let mut body = serde_json::to_value(set)
+        .map_err(|e| ApiError::internal(format!("Serialization error: {}", e)))?;
+    if let Some(obj) = body.as_object_mut() {
+        obj.insert("schema".to_string(), set.document().clone());
+    }
+    Ok(Json(body))
+}
+
+/// Get the input JSON Schema of a ruleset
+pub async fn get_input_schema(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let store = state.store.read().await;
+    let set = store
+        .get_input_schema_for_tenant(&tenant.id, &name)
+        .ok_or_else(|| ApiError::not_found(format!("No input schema for RuleSet '{}'", name)))?;
+    input_schema_response(&set)
+}
+
+/// Set the input JSON Schema of a ruleset
+pub async fn put_input_schema(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(name): Path<String>,
+    Json(document): Json<serde_json::Value>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let mut store = state.store.write().await;
+    if !store.exists_for_tenant(&tenant.id, &name) {
+        return Err(ApiError::not_found(format!("RuleSet '{}' not found", name)));
+    }
+    let set = store
+        .put_input_schema_for_tenant(&tenant.id, &name, document)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidData => ApiError::bad_request(e.to_string()),
+            _ => ApiError::internal(format!("Failed to store input schema: {}", e)),
+        })?;
+    input_schema_response(&set)
+}
+
+/// Delete the input JSON Schema of a ruleset
+pub async fn delete_input_schema(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(name): Path<String>,
+) -> ApiResult<StatusCode> {
+    let mut store = state.store.write().await;
+    if store.delete_input_schema_for_tenant(&tenant.id, &name) {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::not_found(format!(
+            "No input schema for RuleSet '{}'",
+            name
+        )))
+    }
+}
+
+/// Load a ruleset together with a resolver over the tenant's other rulesets,
+/// so that CallRuleSet actions can be inlined.
+async fn filter_snapshot(
+    state: &AppState,
+    tenant_id: &str,
+    name: &str,
+) -> ApiResult<(Arc<RuleSet>, SnapshotResolver)> {
+    let store = state.store.read().await;
+    let ruleset = store
+        .get_for_tenant(tenant_id, name)
+        .ok_or_else(|| ApiError::not_found(format!("RuleSet '{}' not found", name)))?;
+    let rulesets = store
+        .list_for_tenant(tenant_id)
+        .into_iter()
+        .filter_map(|info| {
+            store
+                .get_for_tenant(tenant_id, &info.name)
+                .map(|rs| (info.name, rs))
+        })
+        .collect();
+    Ok((ruleset, SnapshotResolver { rulesets }))
+}
+
+fn filter_error(context: &str, e: OrdoError) -> ApiError {
+    match e {
+        // Unsupported expressions, unmapped fields in strict mode and
+        // variables that cannot be pushed down
+        OrdoError::ParseError { .. } | OrdoError::EvalError { .. } => {
+            ApiError::bad_request(format!("{}: {}", context, e))
+        }
+        OrdoError::RuleSetNotFound { .. } => ApiError::from(e),
+        _ => ApiError::internal(format!("{}: {}", context, e)),
+    }
+}
+
 /// Generate a database filter from a ruleset via partial evaluation.
 pub async fn compile_filter(
     State(state): State<AppState>,
@@ -1285,25 +1642,13 @@ pub async fn compile_filter(
         ));
     }
 
-    let ruleset = {
-        let store = state.store.read().await;
-        store
-            .get_for_tenant(&tenant.id, &name)
-            .ok_or_else(|| ApiError::not_found(format!("RuleSet '{}' not found", name)))?
-    };
-
-    let core_request = ordo_core::filter::FilterRequest {
-        known_input: request.known_input,
-        target_results: request.target_results,
-        format: request.format,
-        field_mapping: request.field_mapping,
-        max_paths: request.max_paths,
-    };
+    let (ruleset, resolver) = filter_snapshot(&state, &tenant.id, &name).await?;
 
-    let compiler = ordo_core::filter::FilterCompiler::new();
+    let mut compiler = ordo_core::filter::FilterCompiler::new();
+    compiler.set_resolver(Arc::new(resolver));
     let result = compiler
-        .compile(&ruleset, core_request)
-        .map_err(|e| ApiError::internal(format!("Filter compilation failed: {}", e)))?;
+        .compile(&ruleset, request.into())
+        .map_err(|e| filter_error("Filter compilation failed", e))?;
 
     Ok(Json(FilterResponse {
         format: result.format,
@@ -1311,13 +1656,55 @@ pub async fn compile_filter(
         always_matches: result.always_matches,
         never_matches: result.never_matches,
         unknown_fields: result.unknown_fields,
+        params: result.params,
+        clauses_before: result.clauses_before,
+        clauses_after: result.clauses_after,
+        untranslated: result.untranslated,
     }))
 }
 
+/// Run the generated SQL filter and the ruleset against the same sample rows
+/// and report every row on which they disagree.
+pub async fn verify_filter(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(name): Path<String>,
+    SimdJson(request): SimdJson<VerifyFilterRequest>,
+) -> ApiResult<Json<ordo_core::filter::verify::VerifyReport>> {
+    if request.filter.target_results.is_empty() {
+        return Err(ApiError::bad_request(
+            "target_results cannot be empty".to_string(),
+        ));
+    }
+
+    let verify = request.verify;
+    if verify.rows.len() > MAX_VERIFY_ROWS || verify.max_rows > MAX_VERIFY_ROWS {
+        return Err(ApiError::bad_request(format!(
+            "verification is limited to {} rows",
+            MAX_VERIFY_ROWS
+        )));
+    }
+
+    let (ruleset, resolver) = filter_snapshot(&state, &tenant.id, &name).await?;
+
+    // Loading SQLite and executing the ruleset per row is blocking work
+    let filter = request.filter.into();
+    let report = tokio::task::spawn_blocking(move || {
+        let mut compiler = ordo_core::filter::FilterCompiler::new();
+        compiler.set_resolver(Arc::new(resolver));
+        compiler.verify(&ruleset, &filter, &verify)
+    })
+    .await
+    .map_err(|e| ApiError::internal(format!("Filter verification task failed: {}", e)))?
+    .map_err(|e| filter_error("Filter verification failed", e))?;
+
+    Ok(Json(report))
+}
+
 // ==================== Rule Composition / Pipeline API ====================
 
 /// Resolver backed by an in-memory snapshot of rulesets for a given tenant.
-/// Used with CallRuleSet actions in the rule executor.
+/// Used with CallRuleSet actions in the rule executor and filter compiler.
 struct SnapshotResolver {
     rulesets: std::collections::HashMap<String, Arc<RuleSet>>,
 }
@@ -1365,8 +1752,9 @@ pub struct PipelineStageResult {
 pub async fn execute_pipeline(
     State(state): State<AppState>,
     Extension(tenant): Extension<TenantContext>,
+    Accept(response_format): Accept,
     SimdJson(request): SimdJson<PipelineRequest>,
-) -> ApiResult<Json<PipelineResponse>> {
+) -> ApiResult<Encoded<PipelineResponse>> {
     let start = Instant::now();
 
     if request.rulesets.is_empty() {
@@ -1439,11 +1827,14 @@ pub async fn execute_pipeline(
         }
     }
 
-    Ok(Json(PipelineResponse {
-        stages,
-        output: ordo_core::context::Value::object(final_output),
-        duration_us: start.elapsed().as_micros() as u64,
-    }))
+    Ok(Encoded(
+        response_format,
+        PipelineResponse {
+            stages,
+            output: ordo_core::context::Value::object(final_output),
+            duration_us: start.elapsed().as_micros() as u64,
+        },
+    ))
 }
 
 // ==================== Webhook Management ====================
@@ -1644,6 +2035,34 @@ pub async fn admin_reload(State(state): State<AppState>) -> ApiResult<Json<serde
     })))
 }
 
+/// Tiered execution state: aggregate statistics, the tier of every tracked condition
+/// and the input shapes inferred for JIT.
+pub async fn admin_tiers(State(state): State<AppState>) -> ApiResult<Json<serde_json::Value>> {
+    let tiering = state.executor.tiering().ok_or_else(|| {
+        ApiError::bad_request("Tiered execution is disabled (--tiered-execution)".to_string())
+    })?;
+
+    // Input shapes inferred for JIT-compiled conditions, per ruleset
+    let shapes: Vec<serde_json::Value> = tiering
+        .shaped_rulesets()
+        .into_iter()
+        .filter_map(|(tenant_id, name)| {
+            let shape = tiering.shape(tenant_id.as_deref(), &name)?;
+            Some(serde_json::json!({
+                "tenant_id": tenant_id,
+                "ruleset": name,
+                "fields": shape.fields(),
+            }))
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "stats": tiering.stats(),
+        "conditions": tiering.snapshot(),
+        "shapes": shapes,
+    })))
+}
+
 // ==================== Rule Testing API ====================
 
 /// Run a test suite against a named ruleset.
@@ -49,6 +49,7 @@ async fn build_full_test_app() -> Router {
         default_qps_limit: None,
         default_burst_limit: None,
         default_timeout_ms: 100,
+        default_budget: Default::default(),
     };
     let tenant_manager = Arc::new(TenantManager::new(None, defaults).await.unwrap());
     tenant_manager.ensure_default("default").await.unwrap();
@@ -105,6 +106,10 @@ async fn build_full_test_app() -> Router {
         )
         .route("/api/v1/execute-pipeline", post(api::execute_pipeline))
         .route("/api/v1/rulesets/:name/filter", post(api::compile_filter))
+        .route(
+            "/api/v1/rulesets/:name/filter/verify",
+            post(api::verify_filter),
+        )
         .route("/api/v1/eval", post(api::eval_expression))
         .route(
             "/api/v1/config/audit-sample-rate",
@@ -117,6 +122,19 @@ async fn build_full_test_app() -> Router {
                 .put(api::put_data)
                 .delete(api::delete_data),
         )
+        .route(
+            "/api/v1/schemas",
+            get(api::list_schemas)
+                .put(api::put_schemas)
+                .delete(api::delete_schemas),
+        )
+        .route("/api/v1/schemas/:name", get(api::get_schema))
+        .route(
+            "/api/v1/rulesets/:name/schema",
+            get(api::get_input_schema)
+                .put(api::put_input_schema)
+                .delete(api::delete_input_schema),
+        )
         .route(
             "/api/v1/tenants",
             get(api::list_tenants).post(api::create_tenant),
@@ -450,6 +468,250 @@ async fn test_execute_invalid_body() {
     assert_eq!(status, StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn test_execute_decodes_only_referenced_fields() {
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("sparse_input")).await;
+
+    // Unreferenced fields are skipped, whatever their shape
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/execute/sparse_input",
+        &json!({
+            "input": {
+                "value": 75,
+                "payload": { "items": [1, 2, 3], "nested": { "deep": [null, true] } },
+                "notes": "ignored"
+            }
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["code"], "HIGH");
+
+    let (status, _) = post_json(
+        &app,
+        "/api/v1/execute/sparse_input",
+        &json!({ "input": { "value": 75 }, "trace": "yes" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+// ==================== MessagePack / CBOR ====================
+
+/// POST an encoded body, returning the status, response content type and raw body
+async fn post_encoded(
+    app: &Router,
+    uri: &str,
+    content_type: &str,
+    accept: Option<&str>,
+    payload: Vec<u8>,
+) -> (StatusCode, String, Vec<u8>) {
+    let mut request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", content_type);
+    if let Some(accept) = accept {
+        request = request.header("accept", accept);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(payload)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let response_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, response_type, bytes.to_vec())
+}
+
+#[tokio::test]
+async fn test_execute_msgpack() {
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("msgpack")).await;
+
+    let payload = rmp_serde::to_vec_named(&json!({ "input": { "value": 75 } })).unwrap();
+    let (status, content_type, body) = post_encoded(
+        &app,
+        "/api/v1/execute/msgpack",
+        "application/msgpack",
+        None,
+        payload,
+    )
+    .await;
+
+    // Without Accept, the response mirrors the request encoding
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/msgpack");
+    let body: Value = rmp_serde::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "HIGH");
+
+    let (status, content_type, body) = post_encoded(
+        &app,
+        "/api/v1/execute/msgpack",
+        "application/msgpack",
+        None,
+        vec![0xc1],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(content_type, "application/json");
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("MessagePack parse error"));
+}
+
+#[tokio::test]
+async fn test_execute_cbor_with_accept() {
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("cbor")).await;
+
+    let payload = cbor4ii::serde::to_vec(Vec::new(), &json!({ "input": { "value": 10 } })).unwrap();
+    let (status, content_type, body) = post_encoded(
+        &app,
+        "/api/v1/execute/cbor",
+        "application/cbor",
+        Some("application/json"),
+        payload,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "LOW");
+
+    let (status, _, _) = post_encoded(
+        &app,
+        "/api/v1/execute/cbor",
+        "text/plain",
+        None,
+        b"value=10".to_vec(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_batch_and_pipeline_encodings() {
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("enc_batch")).await;
+
+    let payload = json!({ "inputs": [{ "value": 75 }, { "value": 5 }] });
+    let (status, content_type, body) = post_encoded(
+        &app,
+        "/api/v1/execute/enc_batch/batch",
+        "application/json",
+        Some("application/cbor"),
+        serde_json::to_vec(&payload).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/cbor");
+    let body: Value = cbor4ii::serde::from_slice(&body).unwrap();
+    assert_eq!(body["results"][0]["code"], "HIGH");
+    assert_eq!(body["results"][1]["code"], "LOW");
+
+    let payload = json!({ "rulesets": ["enc_batch"], "input": { "value": 75 } });
+    let (status, content_type, body) = post_encoded(
+        &app,
+        "/api/v1/execute-pipeline",
+        "application/cbor",
+        Some("application/msgpack"),
+        cbor4ii::serde::to_vec(Vec::new(), &payload).unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/msgpack");
+    let body: Value = rmp_serde::from_slice(&body).unwrap();
+    assert_eq!(body["stages"][0]["code"], "HIGH");
+}
+
+/// Compares request/response encodings for `/api/v1/execute/:name`.
+///
+/// Runs the full router in-process (no network), so the numbers isolate
+/// body decoding, execution and response encoding. Prints the median of
+/// `RUNS` runs per encoding (the figures in `benchmark/BENCHMARK_REPORT_*.md`).
+/// Run with
+/// `cargo test --release -p ordo-server bench_execute_encodings -- --ignored --nocapture`.
+#[tokio::test]
+#[ignore]
+async fn bench_execute_encodings() {
+    const ITERATIONS: u32 = 20_000;
+    const RUNS: usize = 3;
+
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("bench")).await;
+
+    let items: Vec<Value> = (0..10)
+        .map(|i| json!({ "sku": format!("SKU-{:04}", i), "qty": i + 1, "price": 9.99 * i as f64 }))
+        .collect();
+    let request = json!({
+        "input": {
+            "value": 75,
+            "order_id": "ORD-2026-000123",
+            "customer": { "id": 42, "tier": "gold", "country": "DE", "verified": true },
+            "items": items,
+            "total": 449.55,
+        }
+    });
+
+    let cbor = cbor4ii::serde::to_vec(Vec::new(), &request).unwrap();
+    let payloads = [
+        ("application/json", serde_json::to_vec(&request).unwrap()),
+        (
+            "application/msgpack",
+            rmp_serde::to_vec_named(&request).unwrap(),
+        ),
+        ("application/cbor", cbor),
+    ];
+
+    for (content_type, payload) in payloads {
+        let (_, _, response) = post_encoded(
+            &app,
+            "/api/v1/execute/bench",
+            content_type,
+            None,
+            payload.clone(),
+        )
+        .await;
+        let mut runs = Vec::with_capacity(RUNS);
+        for _ in 0..RUNS {
+            let start = std::time::Instant::now();
+            for _ in 0..ITERATIONS {
+                let (status, _, _) = post_encoded(
+                    &app,
+                    "/api/v1/execute/bench",
+                    content_type,
+                    None,
+                    payload.clone(),
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK);
+            }
+            runs.push(start.elapsed());
+        }
+        runs.sort();
+        let elapsed = runs[RUNS / 2];
+        println!(
+            "{:<20} request {:>4} B  response {:>4} B  {:>7.2} us/req  {:>8.0} req/s",
+            content_type,
+            payload.len(),
+            response.len(),
+            elapsed.as_secs_f64() * 1e6 / ITERATIONS as f64,
+            ITERATIONS as f64 / elapsed.as_secs_f64(),
+        );
+    }
+}
+
 // ==================== Batch Execution ====================
 
 #[tokio::test]
@@ -698,6 +960,100 @@ async fn test_data_delete_nonexistent() {
     assert_eq!(status, StatusCode::NOT_FOUND);
 }
 
+// ==================== Protobuf Schema API ====================
+
+async fn put_bytes(app: &Router, uri: &str, payload: Vec<u8>) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("PUT")
+                .uri(uri)
+                .header("content-type", "application/octet-stream")
+                .body(Body::from(payload))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_schema_upload() {
+    let app = build_full_test_app().await;
+
+    let (status, body) = get_request(&app, "/api/v1/schemas").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["messages"], json!([]));
+
+    let (status, _) = put_bytes(&app, "/api/v1/schemas", vec![0xff, 0xff]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let descriptor_set = crate::grpc::tests::input_descriptor_set();
+    let (status, body) = put_bytes(&app, "/api/v1/schemas", descriptor_set).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["messages"], json!(["test.Input"]));
+
+    let (status, body) = get_request(&app, "/api/v1/schemas/test.Input").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["fields"][0]["name"], "value");
+    assert_eq!(body["fields"][0]["type"], "int32");
+    assert_eq!(body["fields"][0]["proto_tag"], 1);
+    assert_eq!(body["fields"][1]["type"], "string");
+
+    let (status, _) = get_request(&app, "/api/v1/schemas/test.Missing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = delete_request(&app, "/api/v1/schemas").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = delete_request(&app, "/api/v1/schemas").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_ruleset_input_schema() {
+    let app = build_full_test_app().await;
+    let document = json!({
+        "type": "object",
+        "required": ["amount"],
+        "properties": {
+            "amount": { "type": "number" },
+            "tier": { "enum": ["gold", "silver"] }
+        }
+    });
+
+    let (status, _) = put_json(&app, "/api/v1/rulesets/loan/schema", &document).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    post_json(&app, "/api/v1/rulesets", &simple_ruleset("loan")).await;
+    let (status, _) = put_json(
+        &app,
+        "/api/v1/rulesets/loan/schema",
+        &json!({ "type": "string" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = put_json(&app, "/api/v1/rulesets/loan/schema", &document).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["root"], "loan");
+    assert_eq!(body["messages"][0]["fields"][0]["type"], "double");
+    assert_eq!(body["enums"]["loan"]["tier"], json!(["gold", "silver"]));
+
+    let (status, body) = get_request(&app, "/api/v1/rulesets/loan/schema").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["schema"], document);
+    assert_eq!(body["messages"][0]["fields"][1]["type"], "optional string");
+
+    let (status, _) = delete_request(&app, "/api/v1/rulesets/loan/schema").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get_request(&app, "/api/v1/rulesets/loan/schema").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
 // ==================== Pipeline Execution ====================
 
 #[tokio::test]
@@ -938,6 +1294,8 @@ async fn test_compile_filter() {
     assert!(body["filter"].is_object() || body["filter"].is_string());
     assert!(body.get("always_matches").is_some());
     assert!(body.get("never_matches").is_some());
+    assert_eq!(body["clauses_before"], 1);
+    assert_eq!(body["clauses_after"], 1);
 }
 
 #[tokio::test]
@@ -972,6 +1330,164 @@ async fn test_compile_filter_empty_targets() {
     assert_eq!(status, StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn test_compile_filter_parameterized_sql() {
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("filter_sql")).await;
+
+    let request = |strict: bool| {
+        json!({
+            "known_input": {},
+            "target_results": ["HIGH"],
+            "field_mapping": { "value": "amount" },
+            "sql": { "dialect": "postgres", "parameterized": true, "strict": strict }
+        })
+    };
+    let (status, body) =
+        post_json(&app, "/api/v1/rulesets/filter_sql/filter", &request(true)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["filter"], r#""amount" > $1"#);
+    assert_eq!(body["params"], json!([50]));
+
+    let mut unmapped = request(true);
+    unmapped["field_mapping"] = json!({});
+    let (status, _) = post_json(&app, "/api/v1/rulesets/filter_sql/filter", &unmapped).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Inline output has no parameter list
+    let (_, body) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_sql/filter",
+        &json!({ "known_input": {}, "target_results": ["HIGH"] }),
+    )
+    .await;
+    assert_eq!(body["filter"], "value > 50");
+    assert!(body.get("params").is_none());
+}
+
+#[tokio::test]
+async fn test_compile_filter_elasticsearch() {
+    let app = build_full_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &threshold_ruleset("filter_es")).await;
+
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_es/filter",
+        &json!({
+            "known_input": {},
+            "target_results": ["HIGH"],
+            "format": "elasticsearch",
+            "field_mapping": { "value": "lines.amount" },
+            "elasticsearch": { "nested_paths": ["lines"] }
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["format"], "elasticsearch");
+    assert_eq!(
+        body["filter"],
+        json!({ "nested": {
+            "path": "lines",
+            "query": { "range": { "lines.amount": { "gt": 50 } } }
+        } })
+    );
+}
+
+#[tokio::test]
+async fn test_compile_filter_functions() {
+    let app = build_full_test_app().await;
+    let mut ruleset = threshold_ruleset("filter_functions");
+    ruleset["steps"]["decide"]["branches"][0]["condition"] =
+        json!(r#"starts_with(lower(code), "vip") || soundex(name) == "A400" || value > 50"#);
+    post_json(&app, "/api/v1/rulesets", &ruleset).await;
+
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_functions/filter",
+        &json!({
+            "known_input": {},
+            "target_results": ["HIGH"],
+            "sql": { "dialect": "postgres", "functions": { "lower": "lower(unaccent({0}))" } }
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["filter"],
+        r#"(lower(unaccent("code")) LIKE 'vip%' ESCAPE '!' OR "value" > 50)"#
+    );
+    assert_eq!(body["untranslated"], json!(["soundex"]));
+}
+
+#[tokio::test]
+async fn test_verify_filter() {
+    let app = build_full_test_app().await;
+    post_json(
+        &app,
+        "/api/v1/rulesets",
+        &threshold_ruleset("filter_verify"),
+    )
+    .await;
+
+    // Generated rows around the literal in `value > 50`
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_verify/filter/verify",
+        &json!({ "known_input": {}, "target_results": ["HIGH"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["filter"], r#""value" > 50"#);
+    assert!(body["rows_checked"].as_u64().unwrap() > 1);
+    assert_eq!(body["mismatches"], json!([]));
+
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_verify/filter/verify",
+        &json!({
+            "known_input": {},
+            "target_results": ["HIGH"],
+            "sql": { "parameterized": true },
+            "verify": { "rows": [{ "value": 80 }, { "value": 20 }, {}] }
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["params"], json!([50]));
+    assert_eq!(body["rows_checked"], 3);
+    assert_eq!(body["rule_matches"], 1);
+    assert_eq!(body["filter_matches"], 1);
+
+    // Oversized samples are rejected before any work is done
+    let (status, _) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_verify/filter/verify",
+        &json!({
+            "known_input": {},
+            "target_results": ["HIGH"],
+            "verify": { "max_rows": 1_000_000 }
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let rows = vec![json!({ "value": 1 }); 10_001];
+    let (status, _) = post_json(
+        &app,
+        "/api/v1/rulesets/filter_verify/filter/verify",
+        &json!({ "known_input": {}, "target_results": ["HIGH"], "verify": { "rows": rows } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = post_json(
+        &app,
+        "/api/v1/rulesets/ghost/filter/verify",
+        &json!({ "known_input": {}, "target_results": ["HIGH"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
 // ==================== Ruleset CRUD Edge Cases ====================
 
 #[tokio::test]
@@ -983,6 +1499,17 @@ async fn test_create_ruleset_invalid_json() {
     assert!(status.is_client_error());
 }
 
+#[tokio::test]
+async fn test_create_ruleset_invalid_regex_rejected() {
+    let app = build_full_test_app().await;
+
+    let mut ruleset = threshold_ruleset("bad_regex");
+    ruleset["steps"]["decide"]["branches"][0]["condition"] = json!(r#"regex_match("([a-z", name)"#);
+    let (status, body) = post_json(&app, "/api/v1/rulesets", &ruleset).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("invalid regex"));
+}
+
 #[tokio::test]
 async fn test_create_multiple_rulesets_and_list() {
     let app = build_full_test_app().await;
@@ -16,7 +16,7 @@ use tower_http::trace::TraceLayer;
 use crate::{
     api,
     audit::AuditLogger,
-    debug::DebugSessionManager,
+    debug::{self, DebugSessionManager},
     metrics::PrometheusMetricSink,
     middleware,
     rate_limiter::RateLimiter,
@@ -37,6 +37,7 @@ async fn build_test_app() -> Router {
         default_qps_limit: None,
         default_burst_limit: None,
         default_timeout_ms: 100,
+        default_budget: Default::default(),
     };
     let tenant_manager = Arc::new(TenantManager::new(None, defaults).await.unwrap());
     tenant_manager.ensure_default("default").await.unwrap();
@@ -75,6 +76,31 @@ async fn build_test_app() -> Router {
         )
         .route("/api/v1/eval", post(api::eval_expression))
         .route("/api/v1/admin/reload", post(api::admin_reload))
+        .route(
+            "/api/v1/debug/sessions",
+            post(debug::api::create_debug_session),
+        )
+        .route(
+            "/api/v1/debug/stream/:session_id",
+            get(debug::api::debug_stream),
+        )
+        .route(
+            "/api/v1/debug/control/:session_id",
+            post(debug::api::debug_control),
+        )
+        .route(
+            "/api/v1/debug/record/:name",
+            post(debug::api::record_execution),
+        )
+        .route("/api/v1/debug/replays", post(debug::api::load_recording))
+        .route(
+            "/api/v1/debug/replays/:replay_id",
+            get(debug::api::get_replay).delete(debug::api::delete_replay),
+        )
+        .route(
+            "/api/v1/debug/replays/:replay_id/control",
+            post(debug::api::replay_control),
+        )
         .layer(TraceLayer::new_for_http())
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
@@ -380,6 +406,7 @@ async fn test_admin_reload_with_persistence() {
         default_qps_limit: None,
         default_burst_limit: None,
         default_timeout_ms: 100,
+        default_budget: Default::default(),
     };
     let tenant_manager = Arc::new(TenantManager::new(None, defaults).await.unwrap());
     tenant_manager.ensure_default("default").await.unwrap();
@@ -416,3 +443,312 @@ async fn test_admin_reload_with_persistence() {
     assert_eq!(body["status"], "reloaded");
     assert_eq!(body["rules_loaded"], 1);
 }
+
+#[tokio::test]
+async fn test_debug_session_pauses_at_steps_and_breakpoints() {
+    let app = build_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &test_ruleset("debug_test")).await;
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/debug/sessions",
+        &json!({
+            "ruleset_name": "debug_test",
+            "input": { "value": 80 },
+            "breakpoints": ["high"]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+    let control = format!("/api/v1/debug/control/{session_id}");
+
+    // Subscribe to events before running
+    let stream = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/debug/stream/{session_id}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut events = stream.into_body();
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_into" })).await;
+    assert_eq!(body["state"], "paused");
+    assert_eq!(body["location"]["step"], "decide");
+    assert_eq!(body["location"]["point"], "step");
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "continue" })).await;
+    assert_eq!(body["state"], "paused");
+    assert_eq!(body["location"]["step"], "high");
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "continue" })).await;
+    assert_eq!(body["state"], "completed");
+    assert_eq!(body["result"]["code"], "HIGH");
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_into" })).await;
+    assert_eq!(body["success"], false);
+
+    // Each pause streamed a snapshot
+    let mut text = String::new();
+    while !text.contains("event: execution_complete") {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), events.frame())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        if let Ok(data) = frame.into_data() {
+            text.push_str(std::str::from_utf8(&data).unwrap());
+        }
+    }
+    assert_eq!(text.matches("event: paused").count(), 2);
+    assert_eq!(text.matches("event: breakpoint_hit").count(), 1);
+    assert!(text.contains(r#""input":{"value":80}"#));
+}
+
+#[tokio::test]
+async fn test_debug_session_step_over_and_stop() {
+    let app = build_test_app().await;
+    post_json(&app, "/api/v1/rulesets", &test_ruleset("debug_stop")).await;
+    let (_, body) = post_json(
+        &app,
+        "/api/v1/debug/sessions",
+        &json!({ "ruleset_name": "debug_stop", "input": { "value": 10 } }),
+    )
+    .await;
+    let control = format!(
+        "/api/v1/debug/control/{}",
+        body["session_id"].as_str().unwrap()
+    );
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_over" })).await;
+    assert_eq!(body["location"]["step"], "decide");
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_over" })).await;
+    assert_eq!(body["location"]["step"], "low");
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "stop" })).await;
+    assert_eq!(body["state"], "terminated");
+    let (_, body) = post_json(&app, &control, &json!({ "command": "continue" })).await;
+    assert_eq!(body["success"], false);
+}
+
+/// Counts `n` up to 3, then branches on `$score` (computed from `value`)
+fn debug_loop_ruleset(name: &str) -> Value {
+    use ordo_core::prelude::{Action, Condition, ExprParser, RuleSet, Step, TerminalResult};
+
+    let parse = |source: &str| ExprParser::parse(source).unwrap();
+    let mut ruleset = RuleSet::new(name, "init");
+    ruleset.add_step(Step::action(
+        "init",
+        "Init",
+        vec![
+            Action::set_var("n", parse("0")),
+            Action::set_var("score", parse("value * 10")),
+        ],
+        "inc",
+    ));
+    ruleset.add_step(Step::action(
+        "inc",
+        "Increment",
+        vec![Action::set_var("n", parse("$n + 1"))],
+        "loop",
+    ));
+    ruleset.add_step(
+        Step::decision("loop", "Loop")
+            .branch(Condition::from_string("$n < 3"), "inc")
+            .default("check")
+            .build(),
+    );
+    ruleset.add_step(
+        Step::decision("check", "Check")
+            .branch(Condition::from_string("$score < 300"), "low")
+            .default("high")
+            .build(),
+    );
+    ruleset.add_step(Step::terminal("low", "Low", TerminalResult::new("LOW")));
+    ruleset.add_step(Step::terminal("high", "High", TerminalResult::new("HIGH")));
+    serde_json::to_value(&ruleset).unwrap()
+}
+
+#[tokio::test]
+async fn test_debug_conditional_breakpoints_watches_and_variables() {
+    let app = build_test_app().await;
+    let (status, _) = post_json(&app, "/api/v1/rulesets", &debug_loop_ruleset("debug_watch")).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = post_json(
+        &app,
+        "/api/v1/debug/sessions",
+        &json!({ "ruleset_name": "debug_watch", "input": { "value": 20 } }),
+    )
+    .await;
+    let session_id = body["session_id"].as_str().unwrap().to_string();
+    let control = format!("/api/v1/debug/control/{session_id}");
+    let command = |command: Value| {
+        let app = app.clone();
+        let control = control.clone();
+        async move { post_json(&app, &control, &command).await.1 }
+    };
+
+    let stream = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/v1/debug/stream/{session_id}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let mut events = stream.into_body();
+
+    // Third time at the increment action
+    let body = command(json!({
+        "command": "set_breakpoint",
+        "location": "inc",
+        "action": 0,
+        "hit_count": 3
+    }))
+    .await;
+    assert_eq!(body["message"], "Breakpoint set at action 0 of step 'inc'");
+    // At the score check, only for low scores
+    command(json!({
+        "command": "set_breakpoint",
+        "location": "check",
+        "branch": 0,
+        "condition": "$score < 300"
+    }))
+    .await;
+    let (status, _) = post_json(
+        &app,
+        &control,
+        &json!({ "command": "set_breakpoint", "location": "check", "condition": "$score <" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    command(json!({ "command": "add_watch", "expression": "$n * 100" })).await;
+
+    let body = command(json!({ "command": "continue" })).await;
+    assert_eq!(body["location"]["step"], "inc");
+    assert_eq!(body["location"]["point"], "action");
+    assert_eq!(body["location"]["action"], 0);
+
+    let body = command(json!({ "command": "continue" })).await;
+    assert_eq!(body["location"]["step"], "check");
+    assert_eq!(body["location"]["point"], "branch");
+    assert_eq!(body["location"]["branch"], 0);
+
+    // What if the score were higher?
+    let body = command(json!({ "command": "set_variable", "name": "score", "value": 500 })).await;
+    assert_eq!(body["success"], true);
+    assert_eq!(body["location"]["step"], "check");
+    let body = command(json!({ "command": "continue" })).await;
+    assert_eq!(body["state"], "completed");
+    assert_eq!(body["result"]["code"], "HIGH");
+
+    let body = command(json!({ "command": "set_variable", "name": "score", "value": 1 })).await;
+    assert_eq!(body["success"], false);
+
+    let mut text = String::new();
+    while !text.contains("event: execution_complete") {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), events.frame())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        if let Ok(data) = frame.into_data() {
+            text.push_str(std::str::from_utf8(&data).unwrap());
+        }
+    }
+    // The watch was evaluated at each pause: n = 2 before the third increment, 3 at the check
+    assert!(text.contains(r#""watches":[{"expression":"$n * 100","value":200}]"#));
+    assert!(text.contains(r#""watches":[{"expression":"$n * 100","value":300}]"#));
+    assert!(text.contains("Breakpoint at action 0 of step 'inc' (hit 3)"));
+    assert!(text.contains(r#""score":500"#));
+}
+
+#[tokio::test]
+async fn test_debug_record_and_replay_steps_both_ways() {
+    let app = build_test_app().await;
+    post_json(
+        &app,
+        "/api/v1/rulesets",
+        &debug_loop_ruleset("debug_replay"),
+    )
+    .await;
+
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/debug/record/debug_replay",
+        &json!({ "input": { "value": 20 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"]["code"], "LOW");
+    let recording = body["recording"].clone();
+    assert_eq!(recording["steps"].as_array().unwrap().len(), 9);
+    assert_eq!(recording["outcome"]["result"]["code"], "LOW");
+
+    let (status, body) = post_json(
+        &app,
+        "/api/v1/debug/replays",
+        &json!({ "recording": recording }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["faithful"], true);
+    assert_eq!(body["snapshot"]["location"]["step"], "init");
+    assert_eq!(body["snapshot"]["variables"]["score"], 200);
+    let replay = format!(
+        "/api/v1/debug/replays/{}",
+        body["replay_id"].as_str().unwrap()
+    );
+    let control = format!("{replay}/control");
+
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_backward" })).await;
+    assert_eq!(body["success"], false);
+    assert_eq!(body["snapshot"]["index"], 0);
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_forward" })).await;
+    assert_eq!(body["snapshot"]["location"]["step"], "inc");
+    assert_eq!(body["snapshot"]["set"], json!([["n", 1]]));
+    let (_, body) = post_json(&app, &control, &json!({ "command": "seek", "index": 6 })).await;
+    assert_eq!(body["snapshot"]["location"]["step"], "loop");
+    assert_eq!(body["snapshot"]["branch"], Value::Null);
+    assert_eq!(body["snapshot"]["variables"]["n"], 3);
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_backward" })).await;
+    assert_eq!(body["snapshot"]["variables"]["n"], 3);
+    assert_eq!(body["snapshot"]["location"]["step"], "inc");
+    let (_, body) = post_json(&app, &control, &json!({ "command": "step_backward" })).await;
+    assert_eq!(body["snapshot"]["variables"]["n"], 2);
+    assert_eq!(body["snapshot"]["branch"], 0);
+
+    let (_, body) = get_request(&app, &replay).await;
+    assert_eq!(body["snapshot"]["index"], 4);
+    assert_eq!(delete_request(&app, &replay).await, StatusCode::OK);
+    let (status, _) = get_request(&app, &replay).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // An edited recording no longer reproduces
+    let mut edited = recording.clone();
+    edited["input"]["value"] = json!(40);
+    let (_, body) = post_json(
+        &app,
+        "/api/v1/debug/replays",
+        &json!({ "recording": edited }),
+    )
+    .await;
+    assert_eq!(body["faithful"], false);
+    assert_eq!(body["divergence"], 0);
+
+    // A recording of another definition is refused
+    let mut other = recording;
+    other["ruleset_hash"] = json!("0000000000000000");
+    let (status, _) = post_json(
+        &app,
+        "/api/v1/debug/replays",
+        &json!({ "recording": other }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,175 @@
+use clap::{Parser, Subcommand};
+use ordo_core::context::{FieldSchema, FieldType, MessageSchema};
+use ordo_core::expr::aot::AotCompiler;
+use ordo_core::prelude::RuleSet;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "ordo-aot",
+    about = "Compile Ordo rulesets ahead of time into native objects"
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compile a ruleset into an object file (or static library), C header and Rust wrapper
+    Build {
+        /// Input ruleset file (.json/.yaml/.yml)
+        #[arg(long)]
+        ruleset: PathBuf,
+        /// Input layout file (JSON, see below)
+        ///
+        /// `{"name": "Loan", "fields": [{"name": "amount", "type": "float64", "offset": 0}]}`.
+        /// Types: bool, int32, int64, uint32, uint64, float32, float64, enum,
+        /// string (opaque, 24 bytes) or `{"message": <layout>}`. Offsets default
+        /// to natural C alignment.
+        #[arg(long)]
+        schema: PathBuf,
+        /// Output directory
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+        /// Symbol prefix: the entry point is `ordo_<name>_execute` (default: ruleset name)
+        #[arg(long)]
+        name: Option<String>,
+        /// Target triple (default: host, including its CPU features)
+        #[arg(long)]
+        target: Option<String>,
+        /// Write `lib<name>.a` instead of `<name>.o`
+        #[arg(long)]
+        static_lib: bool,
+    },
+}
+
+/// Struct layout as written in the schema file
+#[derive(Deserialize, Debug)]
+struct Layout {
+    name: String,
+    fields: Vec<LayoutField>,
+}
+
+#[derive(Deserialize, Debug)]
+struct LayoutField {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: LayoutType,
+    #[serde(default)]
+    offset: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum LayoutType {
+    Scalar(String),
+    Message { message: Layout },
+}
+
+fn to_schema(layout: Layout) -> anyhow::Result<MessageSchema> {
+    let mut fields = Vec::with_capacity(layout.fields.len());
+    let mut cursor = 0usize;
+    for field in layout.fields {
+        let (field_type, size, align) = match field.field_type {
+            LayoutType::Message { message } => {
+                let nested = to_schema(message)?;
+                // C pads nested structs to their alignment
+                let size = nested.struct_size.div_ceil(8) * 8;
+                (FieldType::Message(Arc::new(nested)), size, 8)
+            }
+            LayoutType::Scalar(name) => {
+                let field_type = match name.as_str() {
+                    "bool" => FieldType::Bool,
+                    "int32" => FieldType::Int32,
+                    "int64" => FieldType::Int64,
+                    "uint32" => FieldType::UInt32,
+                    "uint64" => FieldType::UInt64,
+                    "float32" => FieldType::Float32,
+                    "float64" => FieldType::Float64,
+                    "enum" => FieldType::Enum(field.name.clone()),
+                    "string" => FieldType::String,
+                    other => anyhow::bail!("Unsupported type '{}' for '{}'", other, field.name),
+                };
+                let size = field_type.primitive_size().unwrap_or(24);
+                let align = size.min(8);
+                (field_type, size, align)
+            }
+        };
+        let offset = field.offset.unwrap_or(cursor.div_ceil(align) * align);
+        cursor = offset + size;
+        fields.push(FieldSchema::new(field.name, field_type, offset).with_size(size));
+    }
+    Ok(MessageSchema::new(layout.name, fields))
+}
+
+fn load_ruleset(path: &Path) -> anyhow::Result<RuleSet> {
+    let content = fs::read_to_string(path)?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let ruleset = match ext.as_str() {
+        "json" => RuleSet::from_json_compiled(&content)?,
+        "yaml" | "yml" => RuleSet::from_yaml_compiled(&content)?,
+        _ => return Err(anyhow::anyhow!("Unsupported ruleset file type: {}", ext)),
+    };
+    Ok(ruleset)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    match args.command {
+        Command::Build {
+            ruleset,
+            schema,
+            out_dir,
+            name,
+            target,
+            static_lib,
+        } => {
+            let ruleset = load_ruleset(&ruleset)?;
+            let layout: Layout = serde_json::from_str(&fs::read_to_string(&schema)?)?;
+            let schema = to_schema(layout)?;
+
+            let mut compiler = AotCompiler::new();
+            if let Some(name) = name {
+                compiler = compiler.with_symbol(name);
+            }
+            if let Some(target) = target {
+                compiler = compiler.with_target(target);
+            }
+            let artifact = compiler.compile(&ruleset, &schema)?;
+
+            let stem = artifact
+                .entry_symbol
+                .trim_start_matches("ordo_")
+                .trim_end_matches("_execute")
+                .to_string();
+            fs::create_dir_all(&out_dir)?;
+            let object_path = if static_lib {
+                let path = out_dir.join(format!("lib{stem}.a"));
+                fs::write(&path, artifact.static_library())?;
+                path
+            } else {
+                let path = out_dir.join(format!("{stem}.o"));
+                fs::write(&path, &artifact.object)?;
+                path
+            };
+            let header_path = out_dir.join(format!("{stem}.h"));
+            let rust_path = out_dir.join(format!("{stem}.rs"));
+            fs::write(&header_path, &artifact.header)?;
+            fs::write(&rust_path, &artifact.rust)?;
+
+            println!("Entry point: {}", artifact.entry_symbol);
+            println!("Object: {}", object_path.display());
+            println!("Header: {}", header_path.display());
+            println!("Rust wrapper: {}", rust_path.display());
+        }
+    }
+    Ok(())
+}
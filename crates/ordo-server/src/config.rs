@@ -41,6 +41,7 @@
 //! | `ORDO_MAX_TOTAL_RULES` | Max rulesets across all tenants | unlimited |
 
 use clap::Parser;
+use ordo_core::budget::ResourceBudget;
 use std::fmt;
 use std::net::SocketAddr;
 use std::path::PathBuf;
@@ -215,6 +216,30 @@ pub struct ServerConfig {
     #[arg(long, default_value = "100", env = "ORDO_DEFAULT_TENANT_TIMEOUT_MS")]
     pub default_tenant_timeout_ms: u64,
 
+    /// Default tenant instruction fuel per execution (optional)
+    #[arg(long, env = "ORDO_DEFAULT_TENANT_FUEL")]
+    pub default_tenant_fuel: Option<u64>,
+
+    /// Default tenant memory budget per execution, in bytes (optional)
+    #[arg(long, env = "ORDO_DEFAULT_TENANT_MAX_MEMORY_BYTES")]
+    pub default_tenant_max_memory_bytes: Option<u64>,
+
+    /// Default tenant maximum array length (optional)
+    #[arg(long, env = "ORDO_DEFAULT_TENANT_MAX_ARRAY_LEN")]
+    pub default_tenant_max_array_len: Option<usize>,
+
+    /// Default tenant maximum string length in bytes (optional)
+    #[arg(long, env = "ORDO_DEFAULT_TENANT_MAX_STRING_LEN")]
+    pub default_tenant_max_string_len: Option<usize>,
+
+    /// Default tenant maximum compiled regex size in bytes (optional)
+    #[arg(long, env = "ORDO_DEFAULT_TENANT_MAX_REGEX_SIZE")]
+    pub default_tenant_max_regex_size: Option<usize>,
+
+    /// Default tenant maximum regex DFA cache size in bytes (optional)
+    #[arg(long, env = "ORDO_DEFAULT_TENANT_MAX_REGEX_DFA_SIZE")]
+    pub default_tenant_max_regex_dfa_size: Option<usize>,
+
     /// Tenant configuration directory (optional)
     #[arg(long, env = "ORDO_TENANTS_DIR")]
     pub tenants_dir: Option<PathBuf>,
@@ -275,6 +300,13 @@ pub struct ServerConfig {
     /// When this limit is reached, new PUT requests are rejected with 422.
     #[arg(long, env = "ORDO_MAX_TOTAL_RULES")]
     pub max_total_rules: Option<usize>,
+
+    /// Enable tiered execution of rule conditions.
+    /// Hot conditions are promoted from the interpreter to bytecode and then to
+    /// JIT-compiled native code in the background. Tier state is exposed at
+    /// `/api/v1/admin/tiers` and in the `ordo_tier_*` metrics.
+    #[arg(long, default_value = "false", env = "ORDO_TIERED_EXECUTION")]
+    pub tiered_execution: bool,
 }
 
 impl ServerConfig {
@@ -322,6 +354,18 @@ impl ServerConfig {
         self.debug_mode
     }
 
+    /// Default per-execution resource budget for tenants
+    pub fn default_tenant_budget(&self) -> ResourceBudget {
+        ResourceBudget {
+            fuel: self.default_tenant_fuel,
+            max_memory_bytes: self.default_tenant_max_memory_bytes,
+            max_array_len: self.default_tenant_max_array_len,
+            max_string_len: self.default_tenant_max_string_len,
+            max_regex_size: self.default_tenant_max_regex_size,
+            max_regex_dfa_size: self.default_tenant_max_regex_dfa_size,
+        }
+    }
+
     /// Returns true when this instance should reject write operations.
     pub fn is_read_only(&self) -> bool {
         self.role == InstanceRole::Reader
@@ -440,6 +484,12 @@ impl Default for ServerConfig {
             default_tenant_qps: None,
             default_tenant_burst: None,
             default_tenant_timeout_ms: 100,
+            default_tenant_fuel: None,
+            default_tenant_max_memory_bytes: None,
+            default_tenant_max_array_len: None,
+            default_tenant_max_string_len: None,
+            default_tenant_max_regex_size: None,
+            default_tenant_max_regex_dfa_size: None,
             tenants_dir: None,
             signature_enabled: false,
             signature_require: false,
@@ -453,6 +503,7 @@ impl Default for ServerConfig {
             request_timeout_secs: 30,
             max_rules_per_tenant: None,
             max_total_rules: None,
+            tiered_execution: false,
         }
     }
 }
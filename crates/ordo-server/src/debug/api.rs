@@ -2,24 +2,30 @@
 //!
 //! These endpoints are only available when debug mode is enabled.
 
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use axum::{
     extract::{Path, State},
     response::sse::{Event, KeepAlive, Sse},
-    Json,
+    Extension, Json,
 };
 use futures::stream::Stream;
 use ordo_core::{
     context::Context,
-    expr::{BytecodeVM, Expr, ExprCompiler, ExprParser, TraceLevel as CoreTraceLevel},
-    prelude::{ExecutionResult, Value},
+    expr::{
+        BytecodeVM, Expr, ExprCompiler, ExprParser, TraceLevel as CoreTraceLevel, VMExecutionTrace,
+    },
+    prelude::{ExecutionResult, RuleStepper, Value},
+    replay,
 };
 
 use crate::error::ApiError;
+use crate::middleware::tenant::TenantContext;
 use crate::AppState;
 
+use super::session::{Breakpoint, DebugSession, Resume};
 use super::types::*;
 
 type ApiResult<T> = std::result::Result<T, ApiError>;
@@ -55,7 +61,7 @@ pub async fn debug_execute_ruleset(
             // Build VM trace if requested
             let vm_trace = if request.trace_level >= TraceLevel::Standard {
                 // For now, return a placeholder - full VM tracing requires core changes
-                Some(VMTrace {
+                Some(VMExecutionTrace {
                     instructions: vec!["(VM tracing requires core integration)".to_string()],
                     constants: vec![],
                     fields: vec![],
@@ -219,7 +225,7 @@ pub async fn debug_execute_inline(
             // Build VM trace if requested
             let vm_trace = if request.trace_level >= TraceLevel::Standard {
                 // For now, return a placeholder - full VM tracing requires core changes
-                Some(VMTrace {
+                Some(VMExecutionTrace {
                     instructions: vec!["(VM tracing requires core integration)".to_string()],
                     constants: vec![],
                     fields: vec![],
@@ -549,6 +555,7 @@ pub async fn debug_stream(
                                 DebugEvent::StateChange { .. } => "state_change",
                                 DebugEvent::VMState { .. } => "vm_state",
                                 DebugEvent::BreakpointHit { .. } => "breakpoint_hit",
+                                DebugEvent::Paused { .. } => "paused",
                                 DebugEvent::ExecutionComplete { .. } => "execution_complete",
                                 DebugEvent::Error { .. } => "error",
                                 DebugEvent::Heartbeat { .. } => "heartbeat",
@@ -583,94 +590,355 @@ pub async fn debug_stream(
 // ==================== Debug Control ====================
 
 /// Send control command to debug session
+///
+/// Step and continue commands run the session's execution until it pauses
+/// (at the next unit or a breakpoint) or completes, then answer. The first
+/// one starts the execution.
 pub async fn debug_control(
     State(state): State<AppState>,
     Path(session_id): Path<String>,
     Json(command): Json<DebugCommand>,
 ) -> ApiResult<Json<DebugControlResponse>> {
-    let session = state
-        .debug_sessions
-        .get_session(&session_id)
-        .ok_or_else(|| ApiError::not_found(format!("Session '{}' not found", session_id)))?;
+    let resume = match command {
+        DebugCommand::StepInto => Resume::StepInto,
+        DebugCommand::StepOver => Resume::StepOver,
+        DebugCommand::Continue => Resume::Continue,
+        command => return control_session(&state, &session_id, command),
+    };
+
+    let (current_state, ruleset_name, started) = {
+        let session = find_session(&state, &session_id)?;
+        (
+            session.get_state(),
+            session.ruleset_name.clone(),
+            session.is_started(),
+        )
+    };
+    if current_state != SessionState::Paused && current_state != SessionState::Created {
+        let action = if resume == Resume::Continue {
+            "continue"
+        } else {
+            "step"
+        };
+        return Ok(Json(DebugControlResponse {
+            success: false,
+            state: current_state,
+            message: Some(format!("Session must be paused or created to {}", action)),
+            location: None,
+            result: None,
+        }));
+    }
+
+    // Fetch the ruleset before locking the session, the store lock is async
+    let ruleset =
+        if started {
+            None
+        } else {
+            let store = state.store.read().await;
+            Some(store.get(&ruleset_name).ok_or_else(|| {
+                ApiError::not_found(format!("RuleSet '{}' not found", ruleset_name))
+            })?)
+        };
+
+    let session = find_session(&state, &session_id)?;
+    let event = match ruleset {
+        Some(ruleset) => {
+            let stepper = RuleStepper::new(state.executor.clone(), ruleset, session.input.clone());
+            Some(session.start(stepper, resume))
+        }
+        None => session.resume(resume),
+    };
 
+    let (message, location, result) = match event {
+        Some(DebugEvent::Paused { location, .. }) => (
+            format!("Paused at step '{}'", location.step),
+            Some(location),
+            None,
+        ),
+        Some(DebugEvent::ExecutionComplete { result, .. }) => {
+            ("Execution completed".to_string(), None, Some(result))
+        }
+        Some(DebugEvent::Error { message }) => (message, None, None),
+        _ => ("Execution has not started".to_string(), None, None),
+    };
+    Ok(Json(DebugControlResponse {
+        success: true,
+        state: session.get_state(),
+        message: Some(message),
+        location,
+        result,
+    }))
+}
+
+/// Handle a control command that does not run the execution
+fn control_session(
+    state: &AppState,
+    session_id: &str,
+    command: DebugCommand,
+) -> ApiResult<Json<DebugControlResponse>> {
+    let session = find_session(state, session_id)?;
     let current_state = session.get_state();
 
-    match command {
-        DebugCommand::StepInto | DebugCommand::StepOver => {
-            if current_state != SessionState::Paused && current_state != SessionState::Created {
-                return Ok(Json(DebugControlResponse {
-                    success: false,
-                    state: current_state,
-                    message: Some("Session must be paused or created to step".to_string()),
-                }));
-            }
-            // Step-by-step execution requires integration with the core executor
-            // to pause at each step and send intermediate state via SSE events.
-            // For now, we transition to running state for the full execution.
-            session.set_state(SessionState::Running);
-            Ok(Json(DebugControlResponse {
-                success: true,
-                state: SessionState::Running,
-                message: Some("Stepping...".to_string()),
-            }))
+    let mut location = None;
+    let (success, new_state, message) = match command {
+        DebugCommand::Stop => {
+            session.stop();
+            (
+                true,
+                SessionState::Terminated,
+                "Session terminated".to_string(),
+            )
         }
-        DebugCommand::Continue => {
-            if current_state != SessionState::Paused && current_state != SessionState::Created {
-                return Ok(Json(DebugControlResponse {
-                    success: false,
-                    state: current_state,
-                    message: Some("Session must be paused or created to continue".to_string()),
-                }));
-            }
-            session.set_state(SessionState::Running);
-            Ok(Json(DebugControlResponse {
-                success: true,
-                state: SessionState::Running,
-                message: Some("Continuing execution...".to_string()),
-            }))
+        DebugCommand::SetBreakpoint {
+            location,
+            branch,
+            action,
+            condition,
+            hit_count,
+        } => {
+            let condition = condition
+                .map(|source| {
+                    let expr = ExprParser::parse(&source).map_err(|e| {
+                        ApiError::bad_request(format!("Invalid breakpoint condition: {}", e))
+                    })?;
+                    Ok::<_, ApiError>((source, expr))
+                })
+                .transpose()?;
+            let breakpoint = Breakpoint {
+                condition,
+                hit_count,
+                branch,
+                action,
+                ..Breakpoint::at_step(location)
+            };
+            let message = format!("Breakpoint set at {}", breakpoint.describe());
+            session.add_breakpoint(breakpoint);
+            (true, current_state, message)
         }
-        DebugCommand::Pause => {
-            if current_state != SessionState::Running {
-                return Ok(Json(DebugControlResponse {
-                    success: false,
-                    state: current_state,
-                    message: Some("Session must be running to pause".to_string()),
-                }));
+        DebugCommand::RemoveBreakpoint {
+            location,
+            branch,
+            action,
+        } => {
+            let removed = session.remove_breakpoint(&location, branch, action);
+            let place = Breakpoint {
+                branch,
+                action,
+                ..Breakpoint::at_step(location)
             }
-            session.set_state(SessionState::Paused);
-            Ok(Json(DebugControlResponse {
-                success: true,
-                state: SessionState::Paused,
-                message: Some("Paused".to_string()),
-            }))
+            .describe();
+            let message = if removed {
+                format!("Breakpoint removed from {}", place)
+            } else {
+                format!("No breakpoint at {}", place)
+            };
+            (removed, current_state, message)
         }
-        DebugCommand::Stop => {
-            session.set_state(SessionState::Terminated);
-            Ok(Json(DebugControlResponse {
-                success: true,
-                state: SessionState::Terminated,
-                message: Some("Session terminated".to_string()),
-            }))
+        DebugCommand::AddWatch { expression } => {
+            let expr = ExprParser::parse(&expression)
+                .map_err(|e| ApiError::bad_request(format!("Invalid watch expression: {}", e)))?;
+            session.add_watch(expression.clone(), expr);
+            location = paused_location(session.refresh());
+            (
+                true,
+                session.get_state(),
+                format!("Watching {}", expression),
+            )
         }
-        DebugCommand::SetBreakpoint { location } => {
-            session.add_breakpoint(location.clone());
-            Ok(Json(DebugControlResponse {
-                success: true,
-                state: current_state,
-                message: Some(format!("Breakpoint set at {}", location)),
-            }))
+        DebugCommand::RemoveWatch { expression } => {
+            let removed = session.remove_watch(&expression);
+            let message = if removed {
+                format!("Stopped watching {}", expression)
+            } else {
+                format!("Not watching {}", expression)
+            };
+            (removed, current_state, message)
         }
-        DebugCommand::RemoveBreakpoint { location } => {
-            let removed = session.remove_breakpoint(&location);
-            Ok(Json(DebugControlResponse {
-                success: removed,
-                state: current_state,
-                message: Some(if removed {
-                    format!("Breakpoint removed from {}", location)
-                } else {
-                    format!("No breakpoint at {}", location)
-                }),
-            }))
+        DebugCommand::SetVariable { name, value } => {
+            if current_state != SessionState::Paused {
+                (
+                    false,
+                    current_state,
+                    "Session must be paused to set variables".to_string(),
+                )
+            } else {
+                location = paused_location(session.set_variable(&name, value));
+                (
+                    location.is_some(),
+                    session.get_state(),
+                    format!("Variable '{}' set", name),
+                )
+            }
         }
+        DebugCommand::StepInto | DebugCommand::StepOver | DebugCommand::Continue => {
+            unreachable!("handled by debug_control")
+        }
+    };
+    Ok(Json(DebugControlResponse {
+        success,
+        state: new_state,
+        message: Some(message),
+        location,
+        result: None,
+    }))
+}
+
+/// Location of a `Paused` event
+fn paused_location(event: Option<DebugEvent>) -> Option<DebugLocation> {
+    match event {
+        Some(DebugEvent::Paused { location, .. }) => Some(location),
+        _ => None,
+    }
+}
+
+/// Look up a debug session
+fn find_session<'a>(
+    state: &'a AppState,
+    session_id: &str,
+) -> ApiResult<impl std::ops::Deref<Target = DebugSession> + 'a> {
+    state
+        .debug_sessions
+        .get_session(session_id)
+        .ok_or_else(|| ApiError::not_found(format!("Session '{}' not found", session_id)))
+}
+
+// ==================== Recording & Replay ====================
+
+/// Execute a ruleset and record the execution for replay
+///
+/// External data is injected as `$data` like the execute endpoint does; the
+/// recording keeps a version of each data set.
+pub async fn record_execution(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Path(name): Path<String>,
+    Json(request): Json<RecordExecutionRequest>,
+) -> ApiResult<Json<RecordExecutionResponse>> {
+    let (ruleset, external_data) = {
+        let store = state.store.read().await;
+        let ruleset = store
+            .get_for_tenant(&tenant.id, &name)
+            .ok_or_else(|| ApiError::not_found(format!("RuleSet '{}' not found", name)))?;
+        (ruleset, store.get_all_data_for_tenant(&tenant.id))
+    };
+
+    let mut input = request.input;
+    let mut data_versions = BTreeMap::new();
+    if let (Value::Object(obj), Value::Object(data)) = (&mut input, &external_data) {
+        data_versions = data
+            .iter()
+            .map(|(name, value)| (name.to_string(), replay::fingerprint(value)))
+            .collect();
+        obj.insert(std::sync::Arc::from("$data"), external_data.clone());
+    }
+
+    let start = Instant::now();
+    let (result, mut recording) = replay::record(&state.executor, &ruleset, input, None);
+    let duration_us = start.elapsed().as_micros() as u64;
+    recording.data_versions = data_versions;
+
+    let (result, error) = match result {
+        Ok(result) => (
+            Some(ExecutionResultInfo {
+                code: result.code,
+                message: result.message,
+                output: result.output,
+                duration_us,
+            }),
+            None,
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    Ok(Json(RecordExecutionResponse {
+        result,
+        error,
+        recording,
+    }))
+}
+
+/// Load a recording to step through
+///
+/// The recording is re-executed against the stored ruleset first; the
+/// response says whether that reproduced it.
+pub async fn load_recording(
+    State(state): State<AppState>,
+    Extension(tenant): Extension<TenantContext>,
+    Json(request): Json<LoadRecordingRequest>,
+) -> ApiResult<Json<LoadRecordingResponse>> {
+    let recording = request.recording;
+    let ruleset = {
+        let store = state.store.read().await;
+        store
+            .get_for_tenant(&tenant.id, &recording.ruleset)
+            .ok_or_else(|| {
+                ApiError::not_found(format!("RuleSet '{}' not found", recording.ruleset))
+            })?
+    };
+
+    let replayed = replay::replay(&state.executor, &ruleset, &recording)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+
+    let outcome = recording.outcome.clone();
+    let snapshot = ReplaySnapshot::at(&recording, 0);
+    let replay_id = state.debug_sessions.create_replay(recording);
+    Ok(Json(LoadRecordingResponse {
+        replay_id,
+        faithful: replayed.is_faithful(),
+        divergence: replayed.divergence,
+        outcome,
+        snapshot,
+    }))
+}
+
+/// Get the current step of a loaded recording
+pub async fn get_replay(
+    State(state): State<AppState>,
+    Path(replay_id): Path<String>,
+) -> ApiResult<Json<ReplayControlResponse>> {
+    let snapshot = state
+        .debug_sessions
+        .with_replay(&replay_id, |replay| replay.snapshot())
+        .ok_or_else(|| ApiError::not_found(format!("Replay '{}' not found", replay_id)))?;
+    Ok(Json(ReplayControlResponse {
+        success: true,
+        message: None,
+        snapshot,
+    }))
+}
+
+/// Step backward or forward through a loaded recording
+pub async fn replay_control(
+    State(state): State<AppState>,
+    Path(replay_id): Path<String>,
+    Json(command): Json<ReplayCommand>,
+) -> ApiResult<Json<ReplayControlResponse>> {
+    let (moved, snapshot) = state
+        .debug_sessions
+        .with_replay(&replay_id, |replay| {
+            (replay.navigate(command), replay.snapshot())
+        })
+        .ok_or_else(|| ApiError::not_found(format!("Replay '{}' not found", replay_id)))?;
+    Ok(Json(ReplayControlResponse {
+        success: moved,
+        message: (!moved).then(|| "No recorded step there".to_string()),
+        snapshot,
+    }))
+}
+
+/// Unload a recording
+pub async fn delete_replay(
+    State(state): State<AppState>,
+    Path(replay_id): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    if state.debug_sessions.delete_replay(&replay_id) {
+        Ok(Json(serde_json::json!({
+            "success": true,
+            "message": format!("Replay '{}' deleted", replay_id)
+        })))
+    } else {
+        Err(ApiError::not_found(format!(
+            "Replay '{}' not found",
+            replay_id
+        )))
     }
 }
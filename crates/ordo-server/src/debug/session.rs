@@ -4,11 +4,17 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use ordo_core::prelude::Value;
-use parking_lot::RwLock;
+use ordo_core::expr::Expr;
+use ordo_core::prelude::{RuleStepper, Value};
+use ordo_core::replay::ExecutionRecording;
+use ordo_core::rule::{Point, Position};
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::broadcast;
 
-use super::types::{DebugEvent, SessionState, TraceLevel};
+use super::types::{
+    DebugEvent, DebugLocation, ExecutionResultInfo, ReplayCommand, ReplaySnapshot, SessionState,
+    TraceLevel, WatchValue,
+};
 
 /// Unique session ID counter
 static SESSION_COUNTER: AtomicU64 = AtomicU64::new(1);
@@ -23,6 +29,76 @@ fn generate_session_id() -> String {
     format!("dbg_{:x}_{:04x}", timestamp, counter)
 }
 
+/// How far a step or continue command runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// To the next step, entering called rule sets
+    StepInto,
+    /// To the next step of the current or a calling rule set
+    StepOver,
+    /// To the next breakpoint
+    Continue,
+}
+
+/// Breakpoint
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    /// Step ID
+    pub step: String,
+    /// Branch index (pause at the branch condition or one of its actions)
+    pub branch: Option<usize>,
+    /// Action index (pause at the action)
+    pub action: Option<usize>,
+    /// Condition source and parsed expression
+    pub condition: Option<(String, Expr)>,
+    /// Pause only from this hit on
+    pub hit_count: Option<u64>,
+    /// Times reached with the condition true
+    pub hits: u64,
+}
+
+impl Breakpoint {
+    /// Breakpoint before a step runs
+    pub fn at_step(step: impl Into<String>) -> Self {
+        Self {
+            step: step.into(),
+            branch: None,
+            action: None,
+            condition: None,
+            hit_count: None,
+            hits: 0,
+        }
+    }
+
+    /// Whether this breakpoint is at `(step, branch, action)`
+    fn is_at(&self, step: &str, branch: Option<usize>, action: Option<usize>) -> bool {
+        self.step == step && self.branch == branch && self.action == action
+    }
+
+    /// Whether execution stands where this breakpoint is
+    fn matches(&self, position: Position<'_>) -> bool {
+        let (branch, action) = match position.point {
+            Point::Step => (None, None),
+            Point::Branch(branch) => (Some(branch), None),
+            Point::Action(action) => (action.branch, Some(action.index)),
+        };
+        self.branch == branch && self.action == action && position.step.id.matches(&self.step)
+    }
+
+    /// Human-readable place of the breakpoint
+    pub fn describe(&self) -> String {
+        match (self.branch, self.action) {
+            (None, None) => format!("step '{}'", self.step),
+            (Some(branch), None) => format!("branch {} of step '{}'", branch, self.step),
+            (None, Some(action)) => format!("action {} of step '{}'", action, self.step),
+            (Some(branch), Some(action)) => format!(
+                "action {} of branch {} of step '{}'",
+                action, branch, self.step
+            ),
+        }
+    }
+}
+
 /// Debug session
 pub struct DebugSession {
     /// Session ID
@@ -34,13 +110,17 @@ pub struct DebugSession {
     /// Current state
     pub state: RwLock<SessionState>,
     /// Breakpoints
-    pub breakpoints: RwLock<Vec<String>>,
+    pub breakpoints: RwLock<Vec<Breakpoint>>,
+    /// Watch expressions (source and parsed)
+    pub watches: RwLock<Vec<(String, Expr)>>,
     /// Trace level
     pub trace_level: TraceLevel,
     /// Created timestamp
     pub created_at: String,
     /// Event broadcaster
     pub event_tx: broadcast::Sender<DebugEvent>,
+    /// Execution, once started
+    stepper: Mutex<Option<RuleStepper>>,
 }
 
 impl DebugSession {
@@ -55,9 +135,11 @@ impl DebugSession {
             input,
             state: RwLock::new(SessionState::Created),
             breakpoints: RwLock::new(Vec::new()),
+            watches: RwLock::new(Vec::new()),
             trace_level,
             created_at,
             event_tx,
+            stepper: Mutex::new(None),
         }
     }
 
@@ -74,15 +156,25 @@ impl DebugSession {
             .send(DebugEvent::StateChange { state: new_state });
     }
 
-    /// Add breakpoint
-    pub fn add_breakpoint(&self, location: String) {
-        self.breakpoints.write().push(location);
+    /// Add breakpoint, replacing one at the same place
+    pub fn add_breakpoint(&self, breakpoint: Breakpoint) {
+        let mut breakpoints = self.breakpoints.write();
+        breakpoints.retain(|b| !b.is_at(&breakpoint.step, breakpoint.branch, breakpoint.action));
+        breakpoints.push(breakpoint);
     }
 
     /// Remove breakpoint
-    pub fn remove_breakpoint(&self, location: &str) -> bool {
+    pub fn remove_breakpoint(
+        &self,
+        step: &str,
+        branch: Option<usize>,
+        action: Option<usize>,
+    ) -> bool {
         let mut breakpoints = self.breakpoints.write();
-        if let Some(pos) = breakpoints.iter().position(|b| b == location) {
+        if let Some(pos) = breakpoints
+            .iter()
+            .position(|b| b.is_at(step, branch, action))
+        {
             breakpoints.remove(pos);
             true
         } else {
@@ -95,6 +187,199 @@ impl DebugSession {
         self.breakpoints.read().len()
     }
 
+    /// Add a watch expression
+    pub fn add_watch(&self, expression: String, expr: Expr) {
+        let mut watches = self.watches.write();
+        if !watches.iter().any(|(source, _)| *source == expression) {
+            watches.push((expression, expr));
+        }
+    }
+
+    /// Remove a watch expression
+    pub fn remove_watch(&self, expression: &str) -> bool {
+        let mut watches = self.watches.write();
+        let len = watches.len();
+        watches.retain(|(source, _)| source != expression);
+        watches.len() != len
+    }
+
+    /// Whether execution has started
+    pub fn is_started(&self) -> bool {
+        self.stepper.lock().is_some()
+    }
+
+    /// Start execution and run it as far as `resume` asks
+    ///
+    /// Step commands pause before the entry step; continue runs to the first
+    /// breakpoint (which may be the entry step). If execution already
+    /// started, this resumes it instead.
+    pub fn start(&self, stepper: RuleStepper, resume: Resume) -> DebugEvent {
+        let mut slot = self.stepper.lock();
+        if let Some(stepper) = slot.as_mut() {
+            return self.run(stepper, resume);
+        }
+        let stepper = slot.insert(stepper);
+        let hit = self.hit_breakpoint(stepper);
+        if resume != Resume::Continue || hit.is_some() {
+            self.pause(stepper, hit)
+        } else {
+            self.run(stepper, resume)
+        }
+    }
+
+    /// Resume a paused execution
+    ///
+    /// Returns `None` if execution has not started.
+    pub fn resume(&self, resume: Resume) -> Option<DebugEvent> {
+        let mut slot = self.stepper.lock();
+        let stepper = slot.as_mut()?;
+        Some(self.run(stepper, resume))
+    }
+
+    /// Set a variable of the paused frame and report the new snapshot
+    ///
+    /// Returns `None` unless execution is paused.
+    pub fn set_variable(&self, name: &str, value: Value) -> Option<DebugEvent> {
+        let mut slot = self.stepper.lock();
+        let stepper = slot.as_mut().filter(|stepper| !stepper.is_finished())?;
+        stepper.set_variable(name, value);
+        Some(self.pause(stepper, None))
+    }
+
+    /// Report the snapshot of a paused execution again (e.g. for new watches)
+    pub fn refresh(&self) -> Option<DebugEvent> {
+        let slot = self.stepper.lock();
+        let stepper = slot.as_ref().filter(|stepper| !stepper.is_finished())?;
+        Some(self.pause(stepper, None))
+    }
+
+    /// Discard the execution
+    pub fn stop(&self) {
+        self.stepper.lock().take();
+        self.set_state(SessionState::Terminated);
+    }
+
+    /// Run until the next pause, then report it
+    ///
+    /// Every command stops at breakpoints; stepping also stops at the next
+    /// step it asks for.
+    fn run(&self, stepper: &mut RuleStepper, resume: Resume) -> DebugEvent {
+        self.set_state(SessionState::Running);
+        let call_depth = stepper.position().map_or(0, |position| position.call_depth);
+        let mut hit = None;
+        let paused = stepper.run_until(|stepper| {
+            let Some(position) = stepper.position() else {
+                return false;
+            };
+            hit = self.hit_breakpoint(stepper);
+            hit.is_some()
+                || position.point == Point::Step
+                    && match resume {
+                        Resume::StepInto => true,
+                        Resume::StepOver => position.call_depth <= call_depth,
+                        Resume::Continue => false,
+                    }
+        });
+
+        if paused {
+            self.pause(stepper, hit)
+        } else {
+            self.complete(stepper)
+        }
+    }
+
+    /// Count a hit of the breakpoint at the current position, if any
+    ///
+    /// Returns why execution should pause there: the breakpoint's condition
+    /// held and its hit count was reached, or the condition failed to
+    /// evaluate.
+    fn hit_breakpoint(&self, stepper: &RuleStepper) -> Option<String> {
+        let position = stepper.position()?;
+        let mut breakpoints = self.breakpoints.write();
+        let breakpoint = breakpoints.iter_mut().find(|b| b.matches(position))?;
+        if let Some((source, expr)) = &breakpoint.condition {
+            match stepper.eval(expr) {
+                Ok(value) if !value.is_truthy() => return None,
+                Ok(_) => {}
+                Err(e) => {
+                    return Some(format!(
+                        "Condition '{}' of breakpoint at {} failed: {}",
+                        source,
+                        breakpoint.describe(),
+                        e
+                    ))
+                }
+            }
+        }
+        breakpoint.hits += 1;
+        if breakpoint.hit_count.is_some_and(|n| breakpoint.hits < n) {
+            return None;
+        }
+        Some(format!(
+            "Breakpoint at {} (hit {})",
+            breakpoint.describe(),
+            breakpoint.hits
+        ))
+    }
+
+    /// Report a pause with a snapshot of the current frame
+    fn pause(&self, stepper: &RuleStepper, breakpoint: Option<String>) -> DebugEvent {
+        if let Some(reason) = breakpoint {
+            self.send_event(DebugEvent::BreakpointHit {
+                ip: stepper.steps(),
+                reason,
+            });
+        }
+        let watches = self
+            .watches
+            .read()
+            .iter()
+            .map(|(expression, expr)| match stepper.eval(expr) {
+                Ok(value) => WatchValue {
+                    expression: expression.clone(),
+                    value: Some(value),
+                    error: None,
+                },
+                Err(e) => WatchValue {
+                    expression: expression.clone(),
+                    value: None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect();
+        let event = DebugEvent::Paused {
+            location: DebugLocation::from(stepper.position().expect("paused execution")),
+            variables: stepper.variables().into_iter().collect(),
+            input: stepper.input().clone(),
+            watches,
+        };
+        self.send_event(event.clone());
+        self.set_state(SessionState::Paused);
+        event
+    }
+
+    /// Report the end of execution
+    fn complete(&self, stepper: &RuleStepper) -> DebugEvent {
+        let event = match stepper.outcome() {
+            Some(Ok(result)) => DebugEvent::ExecutionComplete {
+                result: ExecutionResultInfo {
+                    code: result.code.clone(),
+                    message: result.message.clone(),
+                    output: result.output.clone(),
+                    duration_us: result.duration_us,
+                },
+                total_instructions: stepper.steps(),
+            },
+            Some(Err(e)) => DebugEvent::Error {
+                message: format!("Execution error: {}", e),
+            },
+            None => unreachable!("a stepper only stops running when paused or finished"),
+        };
+        self.send_event(event.clone());
+        self.set_state(SessionState::Completed);
+        event
+    }
+
     /// Subscribe to events
     pub fn subscribe(&self) -> broadcast::Receiver<DebugEvent> {
         self.event_tx.subscribe()
@@ -109,6 +394,42 @@ impl DebugSession {
 /// Debug session manager
 pub struct DebugSessionManager {
     sessions: RwLock<HashMap<String, DebugSession>>,
+    replays: RwLock<HashMap<String, ReplaySession>>,
+}
+
+/// A loaded recording and the step being inspected
+pub struct ReplaySession {
+    /// Replay ID
+    pub id: String,
+    /// Recording stepped through
+    pub recording: ExecutionRecording,
+    /// Index of the current step
+    pub position: usize,
+}
+
+impl ReplaySession {
+    /// Snapshot at the current step
+    pub fn snapshot(&self) -> Option<ReplaySnapshot> {
+        ReplaySnapshot::at(&self.recording, self.position)
+    }
+
+    /// Move through the recording, staying within its steps
+    ///
+    /// Returns `false` (and stays put) when the move leaves the recording.
+    pub fn navigate(&mut self, command: ReplayCommand) -> bool {
+        let target = match command {
+            ReplayCommand::StepForward => self.position.checked_add(1),
+            ReplayCommand::StepBackward => self.position.checked_sub(1),
+            ReplayCommand::Seek { index } => Some(index),
+        };
+        match target {
+            Some(index) if index < self.recording.steps.len() => {
+                self.position = index;
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 impl DebugSessionManager {
@@ -116,6 +437,7 @@ impl DebugSessionManager {
     pub fn new() -> Self {
         Self {
             sessions: RwLock::new(HashMap::new()),
+            replays: RwLock::new(HashMap::new()),
         }
     }
 
@@ -132,7 +454,7 @@ impl DebugSessionManager {
 
         // Add initial breakpoints
         for bp in breakpoints {
-            session.add_breakpoint(bp);
+            session.add_breakpoint(Breakpoint::at_step(bp));
         }
 
         self.sessions.write().insert(session_id.clone(), session);
@@ -181,6 +503,32 @@ impl DebugSessionManager {
         self.sessions.read().len()
     }
 
+    /// Load a recording for stepping through
+    pub fn create_replay(&self, recording: ExecutionRecording) -> String {
+        let id = generate_session_id().replacen("dbg", "rpl", 1);
+        let replay = ReplaySession {
+            id: id.clone(),
+            recording,
+            position: 0,
+        };
+        self.replays.write().insert(id.clone(), replay);
+        id
+    }
+
+    /// Run `f` on a loaded recording
+    pub fn with_replay<T>(
+        &self,
+        replay_id: &str,
+        f: impl FnOnce(&mut ReplaySession) -> T,
+    ) -> Option<T> {
+        self.replays.write().get_mut(replay_id).map(f)
+    }
+
+    /// Unload a recording
+    pub fn delete_replay(&self, replay_id: &str) -> bool {
+        self.replays.write().remove(replay_id).is_some()
+    }
+
     /// Clean up terminated sessions
     pub fn cleanup_terminated(&self) {
         self.sessions
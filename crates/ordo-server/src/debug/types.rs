@@ -1,6 +1,11 @@
 //! Debug types for VM visualization
 
+use std::collections::HashMap;
+
+use ordo_core::expr::{RegisterValue, VMExecutionTrace};
 use ordo_core::prelude::Value;
+use ordo_core::replay::{ExecutionRecording, RecordedOutcome};
+use ordo_core::rule::{Point, Position};
 use serde::{Deserialize, Serialize};
 
 /// Trace level for debug execution
@@ -53,7 +58,7 @@ pub struct DebugExecuteResponse {
     pub result: ExecutionResultInfo,
     /// VM execution trace (if trace_level >= Standard)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub vm_trace: Option<VMTrace>,
+    pub vm_trace: Option<VMExecutionTrace>,
     /// Expression evaluation traces
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub expr_traces: Vec<ExprTrace>,
@@ -70,49 +75,6 @@ pub struct ExecutionResultInfo {
     pub duration_us: u64,
 }
 
-/// VM execution trace
-#[derive(Debug, Clone, Serialize)]
-pub struct VMTrace {
-    /// List of instructions (human-readable)
-    pub instructions: Vec<String>,
-    /// Constants pool
-    pub constants: Vec<String>,
-    /// Fields pool
-    pub fields: Vec<String>,
-    /// Functions pool
-    pub functions: Vec<String>,
-    /// Execution snapshots
-    pub snapshots: Vec<VMSnapshot>,
-    /// Total instructions executed
-    pub total_instructions: usize,
-    /// Total execution time in nanoseconds
-    pub total_duration_ns: u64,
-}
-
-/// VM state snapshot at a point in execution
-#[derive(Debug, Clone, Serialize)]
-pub struct VMSnapshot {
-    /// Instruction pointer
-    pub ip: usize,
-    /// Current instruction (human-readable)
-    pub instruction: String,
-    /// Register states (only non-null registers)
-    pub registers: Vec<RegisterValue>,
-    /// Duration of this instruction in nanoseconds
-    pub duration_ns: u64,
-}
-
-/// Register value with type info
-#[derive(Debug, Clone, Serialize)]
-pub struct RegisterValue {
-    /// Register index
-    pub index: u8,
-    /// Value (JSON representation)
-    pub value: Value,
-    /// Type name
-    pub type_name: String,
-}
-
 /// Expression evaluation trace
 #[derive(Debug, Clone, Serialize)]
 pub struct ExprTrace {
@@ -235,23 +197,54 @@ pub struct DebugEvalResponse {
 }
 
 /// Debug control command
+///
+/// Stepping works on rule execution units: entering a step, evaluating one
+/// branch condition or running one action. A step or continue command runs
+/// to the next pause before it answers, so there is no command to pause a
+/// running execution.
 #[derive(Debug, Deserialize)]
 #[serde(tag = "command", rename_all = "snake_case")]
 pub enum DebugCommand {
-    /// Step to next instruction
+    /// Run the next unit, following `CallRuleSet` into the called rule set
     StepInto,
-    /// Step over function calls
+    /// Run the next unit, running `CallRuleSet` to completion
     StepOver,
     /// Continue execution until breakpoint or end
     Continue,
-    /// Pause execution
-    Pause,
     /// Stop and terminate session
     Stop,
-    /// Set breakpoint
-    SetBreakpoint { location: String },
+    /// Set breakpoint (replaces one at the same place)
+    ///
+    /// Without `branch` and `action` it pauses before the step runs. With
+    /// `branch` alone it pauses before that branch's condition is evaluated;
+    /// with `action` before that action (of `branch`, or of an action step).
+    SetBreakpoint {
+        /// Step ID
+        location: String,
+        /// Branch index
+        branch: Option<usize>,
+        /// Action index
+        action: Option<usize>,
+        /// Expression that must be truthy for the breakpoint to pause
+        condition: Option<String>,
+        /// Pause only from the Nth time the breakpoint is reached on
+        hit_count: Option<u64>,
+    },
     /// Remove breakpoint
-    RemoveBreakpoint { location: String },
+    RemoveBreakpoint {
+        /// Step ID
+        location: String,
+        /// Branch index
+        branch: Option<usize>,
+        /// Action index
+        action: Option<usize>,
+    },
+    /// Add an expression evaluated at every pause
+    AddWatch { expression: String },
+    /// Remove a watch expression
+    RemoveWatch { expression: String },
+    /// Set a variable of the current frame while paused
+    SetVariable { name: String, value: Value },
 }
 
 /// Debug control response
@@ -264,6 +257,62 @@ pub struct DebugControlResponse {
     /// Message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    /// Where execution paused (after a step or continue command)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<DebugLocation>,
+    /// Execution result (once execution completed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ExecutionResultInfo>,
+}
+
+/// Position of a paused execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DebugLocation {
+    /// Rule set the step belongs to (differs from the session's inside `CallRuleSet`)
+    pub ruleset: String,
+    /// Step ID
+    pub step: String,
+    /// Unit of the step about to run: `step`, `branch` or `action`
+    pub point: &'static str,
+    /// Branch index (branch conditions and branch actions)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<usize>,
+    /// Action index (actions)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<usize>,
+    /// Number of `CallRuleSet` frames entered
+    pub call_depth: usize,
+}
+
+impl From<Position<'_>> for DebugLocation {
+    fn from(position: Position<'_>) -> Self {
+        let (point, branch, action) = match position.point {
+            Point::Step => ("step", None, None),
+            Point::Branch(branch) => ("branch", Some(branch), None),
+            Point::Action(action) => ("action", action.branch, Some(action.index)),
+        };
+        Self {
+            ruleset: position.step.ruleset.to_string(),
+            step: position.step.id.name().unwrap_or_default().to_string(),
+            point,
+            branch,
+            action,
+            call_depth: position.call_depth,
+        }
+    }
+}
+
+/// Value of a watch expression at a pause
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchValue {
+    /// Watch expression
+    pub expression: String,
+    /// Value (if evaluation succeeded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// Evaluation error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Debug session state
@@ -296,6 +345,16 @@ pub enum DebugEvent {
     },
     /// Breakpoint hit
     BreakpointHit { ip: usize, reason: String },
+    /// Execution paused, with a snapshot of the current frame
+    Paused {
+        location: DebugLocation,
+        /// Variables of the current frame
+        variables: HashMap<String, Value>,
+        /// Input of the current frame
+        input: Value,
+        /// Values of the watch expressions
+        watches: Vec<WatchValue>,
+    },
     /// Execution completed
     ExecutionComplete {
         result: ExecutionResultInfo,
@@ -347,3 +406,119 @@ pub struct CreateSessionResponse {
     /// SSE stream URL
     pub stream_url: String,
 }
+
+/// Record execution request
+#[derive(Debug, Deserialize)]
+pub struct RecordExecutionRequest {
+    /// Input data
+    pub input: Value,
+}
+
+/// Record execution response
+#[derive(Debug, Serialize)]
+pub struct RecordExecutionResponse {
+    /// Execution result (if execution succeeded)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ExecutionResultInfo>,
+    /// Execution error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Recording, to load with the replay endpoint
+    pub recording: ExecutionRecording,
+}
+
+/// Load recording request
+#[derive(Debug, Deserialize)]
+pub struct LoadRecordingRequest {
+    /// Recording made by the record endpoint
+    pub recording: ExecutionRecording,
+}
+
+/// Load recording response
+#[derive(Debug, Serialize)]
+pub struct LoadRecordingResponse {
+    /// Replay ID
+    pub replay_id: String,
+    /// Whether re-executing the recording reproduced it
+    pub faithful: bool,
+    /// First step where re-execution differs from the recording
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub divergence: Option<usize>,
+    /// How the recorded execution ended
+    pub outcome: RecordedOutcome,
+    /// Snapshot at the first step (none if no step ran)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<ReplaySnapshot>,
+}
+
+/// Replay navigation command
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ReplayCommand {
+    /// Move to the next step
+    StepForward,
+    /// Move to the previous step
+    StepBackward,
+    /// Move to a step by index
+    Seek { index: usize },
+}
+
+/// Replay navigation response
+#[derive(Debug, Serialize)]
+pub struct ReplayControlResponse {
+    /// Whether the command was accepted
+    pub success: bool,
+    /// Message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Snapshot at the current step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<ReplaySnapshot>,
+}
+
+/// State of a recorded execution after one of its steps ran
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaySnapshot {
+    /// Step index in the recording
+    pub index: usize,
+    /// Number of recorded steps
+    pub total_steps: usize,
+    /// The step (`point` is always `step`)
+    pub location: DebugLocation,
+    /// Branch taken (decision steps)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<usize>,
+    /// Variables set by the step, in order
+    pub set: Vec<(String, Value)>,
+    /// Result code (terminal steps)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Variables of the step's frame after it ran
+    pub variables: HashMap<String, Value>,
+}
+
+impl ReplaySnapshot {
+    /// Snapshot at step `index` of `recording`
+    pub fn at(recording: &ExecutionRecording, index: usize) -> Option<Self> {
+        let step = recording.steps.get(index)?;
+        Some(Self {
+            index,
+            total_steps: recording.steps.len(),
+            location: DebugLocation {
+                ruleset: step
+                    .ruleset
+                    .clone()
+                    .unwrap_or_else(|| recording.ruleset.clone()),
+                step: step.step.clone(),
+                point: "step",
+                branch: None,
+                action: None,
+                call_depth: step.depth,
+            },
+            branch: step.branch,
+            set: step.set.clone(),
+            code: step.code.clone(),
+            variables: recording.variables_at(index)?,
+        })
+    }
+}
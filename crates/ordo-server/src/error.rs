@@ -47,6 +47,14 @@ impl ApiError {
         }
     }
 
+    pub fn budget_exceeded(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code: "BUDGET_EXCEEDED".to_string(),
+            message: message.into(),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn conflict(message: impl Into<String>) -> Self {
         Self {
@@ -88,6 +96,9 @@ impl From<ordo_core::error::OrdoError> for ApiError {
             ordo_core::error::OrdoError::EvalError { message, .. } => {
                 ApiError::bad_request(format!("Evaluation error: {}", message))
             }
+            ordo_core::error::OrdoError::BudgetExceeded { .. } => {
+                ApiError::budget_exceeded(err.to_string())
+            }
             _ => ApiError::internal(err.to_string()),
         }
     }
@@ -12,11 +12,19 @@
 //!
 //! The `BatchExecute` method allows executing a ruleset with multiple inputs
 //! in a single RPC call, which is more efficient than calling `Execute` multiple times.
+//!
+//! ## Typed Inputs
+//!
+//! `TypedExecute` and `TypedEval` take protobuf-encoded inputs instead of JSON.
+//! The message type is looked up in the tenant's uploaded descriptor set
+//! (`PUT /api/v1/schemas`) and decoded without an intermediate JSON step.
 
 use std::sync::Arc;
 use std::time::Instant;
 
 use futures::future::join_all;
+use ordo_core::context::ProtoSchemaSet;
+use ordo_core::expr::jit::SchemaJITEvaluator;
 use ordo_core::prelude::*;
 use ordo_core::rule::{ExecutionOptions, RuleExecutor};
 use ordo_proto::{
@@ -24,7 +32,7 @@ use ordo_proto::{
     BatchExecuteResponse, BatchExecuteResultItem, BatchExecuteSummary, EvalRequest, EvalResponse,
     ExecuteRequest, ExecuteResponse, ExecutionTrace, GetRuleSetRequest, GetRuleSetResponse,
     HealthRequest, HealthResponse, ListRuleSetsRequest, ListRuleSetsResponse, RuleSetSummary,
-    StepTrace,
+    StepTrace, TypedEvalRequest, TypedEvalResponse, TypedExecuteRequest,
 };
 use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
@@ -32,6 +40,7 @@ use tonic::{Request, Response, Status};
 use crate::config::InstanceRole;
 use crate::rate_limiter::RateLimiter;
 use crate::store::RuleStore;
+use crate::telemetry;
 use crate::tenant::{TenantConfig, TenantManager};
 
 /// Metadata key for tenant ID
@@ -51,6 +60,8 @@ pub struct OrdoGrpcService {
     multi_tenancy_enabled: bool,
     role: InstanceRole,
     writer_addr: Option<String>,
+    /// Schema-Aware JIT evaluator for `TypedEval` (None if JIT is unavailable)
+    schema_evaluator: Option<Arc<SchemaJITEvaluator>>,
 }
 
 impl OrdoGrpcService {
@@ -73,6 +84,7 @@ impl OrdoGrpcService {
             multi_tenancy_enabled,
             role: InstanceRole::Standalone,
             writer_addr: None,
+            schema_evaluator: SchemaJITEvaluator::simple().ok().map(Arc::new),
         }
     }
 
@@ -136,6 +148,96 @@ impl OrdoGrpcService {
 
         Ok(config)
     }
+
+    /// Get the tenant's protobuf schemas, checking the message type exists
+    async fn proto_schemas(
+        &self,
+        tenant_id: &str,
+        message_type: &str,
+    ) -> std::result::Result<Arc<ProtoSchemaSet>, Status> {
+        let schemas = self
+            .store
+            .read()
+            .await
+            .get_schemas_for_tenant(tenant_id)
+            .ok_or_else(|| {
+                Status::failed_precondition(format!(
+                    "No protobuf schemas uploaded for tenant '{}'",
+                    tenant_id
+                ))
+            })?;
+        if schemas.schema(message_type).is_none() {
+            return Err(Status::invalid_argument(format!(
+                "Unknown message type '{}'",
+                message_type
+            )));
+        }
+        Ok(schemas)
+    }
+
+    /// Execute a ruleset for a decoded input with the tenant's execution options
+    async fn execute_input(
+        &self,
+        tenant_id: &str,
+        tenant_config: &TenantConfig,
+        ruleset_name: &str,
+        input: Value,
+        include_trace: bool,
+    ) -> std::result::Result<Response<ExecuteResponse>, Status> {
+        // Get ruleset for the tenant
+        let ruleset = {
+            let store = self.store.read().await;
+            store
+                .get_for_tenant(tenant_id, ruleset_name)
+                .ok_or_else(|| Status::not_found(format!("RuleSet '{}' not found", ruleset_name)))?
+        };
+
+        // Build execution options for tenant-specific overrides
+        let budget = tenant_config.execution_budget();
+        let spans = telemetry::sampled();
+        let exec_options =
+            if tenant_config.execution_timeout_ms > 0 || include_trace || budget.is_some() || spans
+            {
+                Some(ExecutionOptions {
+                    timeout_ms: if tenant_config.execution_timeout_ms > 0 {
+                        Some(tenant_config.execution_timeout_ms)
+                    } else {
+                        None
+                    },
+                    enable_trace: if include_trace { Some(true) } else { None },
+                    max_depth: None,
+                    budget,
+                    spans,
+                    observer: None,
+                })
+            } else {
+                None
+            };
+
+        // Execute
+        let result = self
+            .executor
+            .execute_with_options(&ruleset, input, exec_options.as_ref())
+            .map_err(|e| match e {
+                OrdoError::BudgetExceeded { .. } => Status::resource_exhausted(e.to_string()),
+                _ => Status::internal(format!("Execution error: {}", e)),
+            })?;
+
+        // Build response
+        let trace = build_execution_trace(result.trace.as_ref(), include_trace);
+
+        // Serialize output
+        let output_json = serde_json::to_string(&result.output)
+            .map_err(|e| Status::internal(format!("Failed to serialize output: {}", e)))?;
+
+        Ok(Response::new(ExecuteResponse {
+            code: result.code,
+            message: result.message,
+            output_json,
+            duration_us: result.duration_us,
+            trace,
+        }))
+    }
 }
 
 /// Build execution trace for gRPC response
@@ -182,51 +284,14 @@ impl OrdoService for OrdoGrpcService {
         let input: Value = simd_json::from_slice(&mut req.input_json.into_bytes())
             .map_err(|e| Status::invalid_argument(format!("Invalid input JSON: {}", e)))?;
 
-        // Get ruleset for the tenant
-        let ruleset = {
-            let store = self.store.read().await;
-            store
-                .get_for_tenant(&tenant_id, &req.ruleset_name)
-                .ok_or_else(|| {
-                    Status::not_found(format!("RuleSet '{}' not found", req.ruleset_name))
-                })?
-        };
-
-        // Build execution options for tenant-specific overrides
-        let exec_options = if tenant_config.execution_timeout_ms > 0 || req.include_trace {
-            Some(ExecutionOptions {
-                timeout_ms: if tenant_config.execution_timeout_ms > 0 {
-                    Some(tenant_config.execution_timeout_ms)
-                } else {
-                    None
-                },
-                enable_trace: if req.include_trace { Some(true) } else { None },
-                max_depth: None,
-            })
-        } else {
-            None
-        };
-
-        // Execute
-        let result = self
-            .executor
-            .execute_with_options(&ruleset, input, exec_options.as_ref())
-            .map_err(|e| Status::internal(format!("Execution error: {}", e)))?;
-
-        // Build response
-        let trace = build_execution_trace(result.trace.as_ref(), req.include_trace);
-
-        // Serialize output
-        let output_json = serde_json::to_string(&result.output)
-            .map_err(|e| Status::internal(format!("Failed to serialize output: {}", e)))?;
-
-        Ok(Response::new(ExecuteResponse {
-            code: result.code,
-            message: result.message,
-            output_json,
-            duration_us: result.duration_us,
-            trace,
-        }))
+        self.execute_input(
+            &tenant_id,
+            &tenant_config,
+            &req.ruleset_name,
+            input,
+            req.include_trace,
+        )
+        .await
     }
 
     /// Execute a ruleset with multiple inputs (batch execution) with multi-tenancy support
@@ -283,6 +348,9 @@ impl OrdoService for OrdoGrpcService {
                 None
             },
             max_depth: None,
+            budget: tenant_config.execution_budget(),
+            spans: telemetry::sampled(),
+            observer: None,
         });
 
         let executor = self.executor.clone();
@@ -295,8 +363,10 @@ impl OrdoService for OrdoGrpcService {
                 let ruleset = Arc::clone(&ruleset);
                 let executor = executor.clone();
                 let exec_options = Arc::clone(&exec_options);
+                let request_span = tracing::Span::current();
 
                 tokio::task::spawn_blocking(move || {
+                    let _span = request_span.enter();
                     let start_one = Instant::now();
 
                     // Parse input (simd-json for speed)
@@ -577,10 +647,91 @@ impl OrdoService for OrdoGrpcService {
             })),
         }
     }
+
+    /// Execute a ruleset with a protobuf-encoded input
+    async fn typed_execute(
+        &self,
+        request: Request<TypedExecuteRequest>,
+    ) -> std::result::Result<Response<ExecuteResponse>, Status> {
+        let tenant_id = self.extract_tenant_id(&request);
+        let tenant_config = self.validate_tenant(&tenant_id).await?;
+
+        let req = request.into_inner();
+
+        // Decode the message straight into a Value
+        let schemas = self.proto_schemas(&tenant_id, &req.schema_name).await?;
+        let input = schemas
+            .decode(&req.schema_name, &req.context_bytes)
+            .map_err(|e| Status::invalid_argument(format!("Invalid context bytes: {}", e)))?;
+
+        self.execute_input(
+            &tenant_id,
+            &tenant_config,
+            &req.ruleset_name,
+            input,
+            req.include_trace,
+        )
+        .await
+    }
+
+    /// Evaluate an expression against a protobuf-encoded context with Schema-Aware JIT
+    async fn typed_eval(
+        &self,
+        request: Request<TypedEvalRequest>,
+    ) -> std::result::Result<Response<TypedEvalResponse>, Status> {
+        let tenant_id = self.extract_tenant_id(&request);
+        let _tenant_config = self.validate_tenant(&tenant_id).await?;
+
+        let req = request.into_inner();
+
+        let evaluator = self
+            .schema_evaluator
+            .as_ref()
+            .ok_or_else(|| Status::unavailable("Schema-Aware JIT is not available"))?;
+
+        let expr = ExprParser::parse(&req.expression)
+            .map_err(|e| Status::invalid_argument(format!("Invalid expression: {}", e)))?;
+
+        // Decode the message into its typed layout for direct field access
+        let schemas = self.proto_schemas(&tenant_id, &req.schema_name).await?;
+        let buffer = schemas
+            .decode_typed(&req.schema_name, &req.context_bytes)
+            .map_err(|e| Status::invalid_argument(format!("Invalid context bytes: {}", e)))?;
+
+        let start = Instant::now();
+        let (value, jit_used) = evaluator
+            .eval_dynamic(&expr, &buffer.typed_context())
+            .map_err(|e| Status::internal(format!("Evaluation error: {}", e)))?;
+        let duration_ns = start.elapsed().as_nanos() as u64;
+
+        let result = match value {
+            Value::Float(f) => f,
+            Value::Int(i) => i as f64,
+            Value::Bool(b) => {
+                if b {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            other => {
+                return Err(Status::invalid_argument(format!(
+                    "Expression result is not numeric: {}",
+                    other
+                )))
+            }
+        };
+
+        Ok(Response::new(TypedEvalResponse {
+            result,
+            jit_used,
+            duration_ns,
+        }))
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::rate_limiter::RateLimiter;
     use crate::tenant::{TenantDefaults, TenantManager};
@@ -592,6 +743,7 @@ mod tests {
             default_qps_limit: Some(1000),
             default_burst_limit: Some(100),
             default_timeout_ms: 100,
+            default_budget: Default::default(),
         };
         let tenant_manager = Arc::new(TenantManager::new(None, defaults).await.unwrap());
         tenant_manager.ensure_default("default").await.unwrap();
@@ -851,4 +1003,131 @@ mod tests {
         assert_eq!(resp.status, health_response::Status::Serving as i32);
         assert_eq!(resp.ruleset_count, 1);
     }
+
+    /// Descriptor set for `test.Input { int32 value = 1; string region = 2; }`
+    pub(crate) fn input_descriptor_set() -> Vec<u8> {
+        use prost::Message;
+        use prost_types::field_descriptor_proto::{Label, Type};
+        use prost_types::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        };
+
+        let field = |name: &str, number: i32, ty: Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(ty as i32),
+            label: Some(Label::Optional as i32),
+            ..Default::default()
+        };
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("input.proto".to_string()),
+                package: Some("test".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("Input".to_string()),
+                    field: vec![
+                        field("value", 1, Type::Int32),
+                        field("region", 2, Type::String),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+        .encode_to_vec()
+    }
+
+    /// Wire encoding of `test.Input { value, region: "eu" }` (value < 128)
+    fn encode_input(value: u8) -> Vec<u8> {
+        vec![0x08, value, 0x12, 0x02, b'e', b'u']
+    }
+
+    async fn create_typed_test_service() -> OrdoGrpcService {
+        let service = create_test_service().await;
+        service
+            .store
+            .write()
+            .await
+            .put_schemas_for_tenant("default", &input_descriptor_set())
+            .unwrap();
+        service
+    }
+
+    #[tokio::test]
+    async fn test_typed_execute() {
+        let service = create_typed_test_service().await;
+
+        let request = Request::new(TypedExecuteRequest {
+            ruleset_name: "test_rule".to_string(),
+            schema_name: "test.Input".to_string(),
+            context_bytes: encode_input(75),
+            include_trace: true,
+        });
+
+        let resp = service.typed_execute(request).await.unwrap().into_inner();
+        assert_eq!(resp.code, "HIGH");
+        assert!(resp.trace.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_typed_execute_errors() {
+        let service = create_test_service().await;
+        let request = || {
+            Request::new(TypedExecuteRequest {
+                ruleset_name: "test_rule".to_string(),
+                schema_name: "test.Input".to_string(),
+                context_bytes: encode_input(75),
+                include_trace: false,
+            })
+        };
+
+        // No descriptor set uploaded yet
+        let status = service.typed_execute(request()).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+
+        let service = create_typed_test_service().await;
+        let mut req = request();
+        req.get_mut().schema_name = "test.Missing".to_string();
+        let status = service.typed_execute(req).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let mut req = request();
+        req.get_mut().context_bytes = vec![0x12, 0x09];
+        let status = service.typed_execute(req).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_typed_eval() {
+        let service = create_typed_test_service().await;
+
+        let request = Request::new(TypedEvalRequest {
+            expression: "value * 2 + 1".to_string(),
+            schema_name: "test.Input".to_string(),
+            context_bytes: encode_input(20),
+        });
+        let resp = service.typed_eval(request).await.unwrap().into_inner();
+        assert_eq!(resp.result, 41.0);
+        assert!(resp.jit_used);
+
+        // String functions are not JIT-compiled and run on the VM
+        let request = Request::new(TypedEvalRequest {
+            expression: "len(region) + value".to_string(),
+            schema_name: "test.Input".to_string(),
+            context_bytes: encode_input(20),
+        });
+        let resp = service.typed_eval(request).await.unwrap().into_inner();
+        assert_eq!(resp.result, 22.0);
+        assert!(!resp.jit_used);
+
+        // Non-numeric results are rejected
+        let request = Request::new(TypedEvalRequest {
+            expression: "region".to_string(),
+            schema_name: "test.Input".to_string(),
+            context_bytes: encode_input(20),
+        });
+        let status = service.typed_eval(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
 }
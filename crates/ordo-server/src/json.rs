@@ -3,16 +3,144 @@
 //! Drop-in replacement for `axum::Json` that uses simd-json for parsing
 //! request bodies (~2-4x faster than serde_json on modern CPUs).
 //! Response serialization still uses serde_json.
+//!
+//! [`SimdJsonBody`] hands the raw body to handlers that decode it themselves,
+//! e.g. from a simd-json tape restricted to the fields a ruleset reads.
+//!
+//! Both extractors also accept MessagePack (`application/msgpack`) and CBOR
+//! (`application/cbor`) bodies, selected by `Content-Type`. Handlers that
+//! return [`Encoded`] responses pick the response encoding with [`Accept`].
 
 use axum::{
     async_trait,
     body::Bytes,
-    extract::{FromRequest, Request},
-    http::{header, StatusCode},
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed},
+    Serialize,
+};
+
+/// Body encodings supported by the execution endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyFormat {
+    #[default]
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl BodyFormat {
+    /// Format for a media type (without parameters)
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime.trim().to_ascii_lowercase().as_str() {
+            "application/json" => Some(Self::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Self::MsgPack)
+            }
+            "application/cbor" => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Format of a request body; a missing `Content-Type` means JSON
+    fn from_content_type(headers: &HeaderMap) -> Result<Self, SimdJsonRejection> {
+        match headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+        {
+            // Match axum::Json behaviour: parameters such as charset are ignored
+            Some(ct) => Self::from_mime(ct.split(';').next().unwrap_or(""))
+                .ok_or(SimdJsonRejection::InvalidContentType),
+            None => Ok(Self::Json),
+        }
+    }
+
+    /// Preferred supported format of an `Accept` header
+    ///
+    /// Entries are ranked by their `q` parameter, then by order. Wildcards and
+    /// unsupported types select nothing.
+    fn from_accept(headers: &HeaderMap) -> Option<Self> {
+        let accept = headers.get(header::ACCEPT)?.to_str().ok()?;
+        let mut best: Option<(Self, f32)> = None;
+        for entry in accept.split(',') {
+            let mut parts = entry.split(';');
+            let Some(format) = Self::from_mime(parts.next().unwrap_or("")) else {
+                continue;
+            };
+            let q = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if q > 0.0 && best.map_or(true, |(_, best_q)| q > best_q) {
+                best = Some((format, q));
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+
+    /// Media type used for responses
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MsgPack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// Human-readable name, used in error messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::MsgPack => "MessagePack",
+            Self::Cbor => "CBOR",
+        }
+    }
+
+    /// Decode a body (JSON is parsed in place by simd-json)
+    pub fn decode<T: DeserializeOwned>(self, buf: &mut [u8]) -> Result<T, String> {
+        match self {
+            Self::Json => simd_json::from_slice(buf).map_err(|e| e.to_string()),
+            Self::MsgPack => rmp_serde::from_slice(buf).map_err(|e| e.to_string()),
+            Self::Cbor => cbor4ii::serde::from_slice(buf).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Decode a body with a stateful seed
+    pub fn decode_seed<'de, S: DeserializeSeed<'de>>(
+        self,
+        buf: &'de mut [u8],
+        seed: S,
+    ) -> Result<S::Value, String> {
+        match self {
+            Self::Json => {
+                let mut de = simd_json::Deserializer::from_slice(buf).map_err(|e| e.to_string())?;
+                seed.deserialize(&mut de).map_err(|e| e.to_string())
+            }
+            Self::MsgPack => {
+                let mut de = rmp_serde::Deserializer::from_read_ref(&buf[..]);
+                seed.deserialize(&mut de).map_err(|e| e.to_string())
+            }
+            Self::Cbor => {
+                let reader = cbor4ii::core::utils::SliceReader::new(buf);
+                let mut de = cbor4ii::serde::Deserializer::new(reader);
+                seed.deserialize(&mut de).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// Encode a body (MessagePack structs are encoded as maps)
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+            Self::MsgPack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+            Self::Cbor => cbor4ii::serde::to_vec(Vec::new(), value).map_err(|e| e.to_string()),
+        }
+    }
+}
 
 /// Fast JSON extractor that uses simd-json for deserialization.
 ///
@@ -31,18 +159,30 @@ where
     type Rejection = SimdJsonRejection;
 
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
-        // Validate content type — match axum::Json behaviour:
-        // accept "application/json" or "application/json; charset=utf-8" etc.
-        if let Some(ct) = req
-            .headers()
-            .get(header::CONTENT_TYPE)
-            .and_then(|v| v.to_str().ok())
-        {
-            let mime = ct.split(';').next().unwrap_or("").trim();
-            if mime != "application/json" {
-                return Err(SimdJsonRejection::InvalidContentType);
-            }
-        }
+        let SimdJsonBody(mut buf, format) = SimdJsonBody::from_request(req, state).await?;
+
+        let value = format
+            .decode::<T>(&mut buf)
+            .map_err(|e| SimdJsonRejection::DeserializeError(format, e))?;
+
+        Ok(SimdJson(value))
+    }
+}
+
+/// Raw request body and its format, content type checked but not yet parsed
+///
+/// The buffer is owned and mutable, as simd-json parses in place.
+pub struct SimdJsonBody(pub Vec<u8>, pub BodyFormat);
+
+#[async_trait]
+impl<S> FromRequest<S> for SimdJsonBody
+where
+    S: Send + Sync,
+{
+    type Rejection = SimdJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = BodyFormat::from_content_type(req.headers())?;
 
         // Extract body bytes
         let bytes = Bytes::from_request(req, state)
@@ -52,12 +192,7 @@ where
         // simd-json needs a mutable slice (it modifies in-place for speed).
         // For small payloads (<512 bytes), the copy overhead may outweigh
         // simd-json gains, but for typical batch/execute payloads this is a net win.
-        let mut buf = bytes.to_vec();
-
-        let value = simd_json::from_slice::<T>(&mut buf)
-            .map_err(|e| SimdJsonRejection::DeserializeError(e.to_string()))?;
-
-        Ok(SimdJson(value))
+        Ok(SimdJsonBody(bytes.to_vec(), format))
     }
 }
 
@@ -69,11 +204,58 @@ impl<T: Serialize> IntoResponse for SimdJson<T> {
     }
 }
 
+/// Response format requested by the client
+///
+/// Taken from the `Accept` header; without a supported type there, responses
+/// use the request's `Content-Type` format, or JSON.
+pub struct Accept(pub BodyFormat);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Accept
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let format = BodyFormat::from_accept(&parts.headers)
+            .or_else(|| BodyFormat::from_content_type(&parts.headers).ok())
+            .unwrap_or_default();
+        Ok(Accept(format))
+    }
+}
+
+/// Response body encoded in a negotiated format
+pub struct Encoded<T>(pub BodyFormat, pub T);
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        let Encoded(format, value) = self;
+        match format.encode(&value) {
+            Ok(body) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(format.content_type()),
+                )],
+                body,
+            )
+                .into_response(),
+            Err(e) => {
+                let body = serde_json::json!({
+                    "code": "INTERNAL_ERROR",
+                    "message": format!("Failed to encode {} response: {}", format.name(), e),
+                });
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+            }
+        }
+    }
+}
+
 /// Rejection type for SimdJson extractor
 pub enum SimdJsonRejection {
     InvalidContentType,
     BodyReadError(String),
-    DeserializeError(String),
+    DeserializeError(BodyFormat, String),
 }
 
 impl IntoResponse for SimdJsonRejection {
@@ -81,15 +263,17 @@ impl IntoResponse for SimdJsonRejection {
         let (status, message) = match self {
             Self::InvalidContentType => (
                 StatusCode::UNSUPPORTED_MEDIA_TYPE,
-                "Expected content-type: application/json".to_string(),
+                "Expected content-type: application/json, application/msgpack or application/cbor"
+                    .to_string(),
             ),
             Self::BodyReadError(e) => (
                 StatusCode::BAD_REQUEST,
                 format!("Failed to read request body: {}", e),
             ),
-            Self::DeserializeError(e) => {
-                (StatusCode::BAD_REQUEST, format!("JSON parse error: {}", e))
-            }
+            Self::DeserializeError(format, e) => (
+                StatusCode::BAD_REQUEST,
+                format!("{} parse error: {}", format.name(), e),
+            ),
         };
 
         let body = serde_json::json!({
@@ -100,3 +284,60 @@ impl IntoResponse for SimdJsonRejection {
         (status, Json(body)).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_accept_negotiation() {
+        let accept = |v| BodyFormat::from_accept(&headers(header::ACCEPT, v));
+        assert_eq!(accept("application/msgpack"), Some(BodyFormat::MsgPack));
+        assert_eq!(
+            accept("application/json;q=0.5, application/cbor"),
+            Some(BodyFormat::Cbor)
+        );
+        assert_eq!(
+            accept("application/cbor;q=0.2, application/x-msgpack;q=0.9"),
+            Some(BodyFormat::MsgPack)
+        );
+        assert_eq!(accept("application/cbor;q=0"), None);
+        assert_eq!(accept("*/*"), None);
+        assert_eq!(accept("text/html"), None);
+    }
+
+    #[test]
+    fn test_content_type() {
+        let content_type = |v| BodyFormat::from_content_type(&headers(header::CONTENT_TYPE, v));
+        assert_eq!(
+            content_type("application/json; charset=utf-8").ok(),
+            Some(BodyFormat::Json)
+        );
+        assert_eq!(
+            content_type("application/cbor").ok(),
+            Some(BodyFormat::Cbor)
+        );
+        assert!(content_type("text/plain").is_err());
+        assert_eq!(
+            BodyFormat::from_content_type(&HeaderMap::new()).ok(),
+            Some(BodyFormat::Json)
+        );
+    }
+
+    #[test]
+    fn test_value_roundtrip() {
+        let value: ordo_core::context::Value =
+            serde_json::from_str(r#"{"a": 1, "b": [true, null, 2.5], "c": {"d": "x"}}"#).unwrap();
+        for format in [BodyFormat::Json, BodyFormat::MsgPack, BodyFormat::Cbor] {
+            let mut bytes = format.encode(&value).unwrap();
+            let decoded: ordo_core::context::Value = format.decode(&mut bytes).unwrap();
+            assert_eq!(decoded, value, "{}", format.name());
+        }
+    }
+}
@@ -70,6 +70,7 @@ use config::ServerConfig;
 use grpc::OrdoGrpcService;
 use metrics::PrometheusMetricSink;
 use ordo_core::prelude::{RuleExecutor, TraceConfig};
+use ordo_core::rule::TieringConfig;
 use ordo_core::signature::ed25519::decode_public_key;
 use ordo_core::signature::RuleVerifier;
 use rate_limiter::RateLimiter;
@@ -181,10 +182,13 @@ async fn main() -> anyhow::Result<()> {
     info!("Initialized Prometheus metric sink for custom rule metrics");
 
     // Initialize shared executor (moved out of RuleStore for lock-free execution)
-    let executor = Arc::new(RuleExecutor::with_trace_and_metrics(
-        TraceConfig::minimal(),
-        metric_sink.clone(),
-    ));
+    let mut executor =
+        RuleExecutor::with_trace_and_metrics(TraceConfig::minimal(), metric_sink.clone());
+    if config.tiered_execution {
+        executor.enable_tiering(TieringConfig::default());
+        info!("Tiered execution enabled (interpreter -> bytecode -> JIT)");
+    }
+    let executor = Arc::new(executor);
 
     let signature_verifier = build_signature_verifier(&config)?;
 
@@ -245,6 +249,30 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
+        // Load protobuf descriptor sets from schemas/ subdirectories
+        match store.load_schemas_from_dir() {
+            Ok(count) => {
+                if count > 0 {
+                    info!("Loaded protobuf schemas for {} tenant(s)", count);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to load protobuf schemas: {}", e);
+            }
+        }
+
+        // Load per-ruleset input JSON Schemas from schemas/ subdirectories
+        match store.load_input_schemas_from_dir() {
+            Ok(count) => {
+                if count > 0 {
+                    info!("Loaded {} input schemas", count);
+                }
+            }
+            Err(e) => {
+                warn!("Failed to load input schemas: {}", e);
+            }
+        }
+
         Arc::new(RwLock::new(store))
     } else {
         info!("Initializing in-memory store (no persistence)");
@@ -296,6 +324,7 @@ async fn main() -> anyhow::Result<()> {
         default_qps_limit: config.default_tenant_qps,
         default_burst_limit: config.default_tenant_burst,
         default_timeout_ms: config.default_tenant_timeout_ms,
+        default_budget: config.default_tenant_budget(),
     };
     let tenant_store = config.tenants_dir.clone().or_else(|| {
         config
@@ -667,6 +696,10 @@ async fn start_http_server(
             "/api/v1/rulesets/:name/filter",
             post(api::compile_filter),
         )
+        .route(
+            "/api/v1/rulesets/:name/filter/verify",
+            post(api::verify_filter),
+        )
         // Rule testing
         .route(
             "/api/v1/rulesets/:name/test",
@@ -687,6 +720,21 @@ async fn start_http_server(
                 .put(api::put_data)
                 .delete(api::delete_data),
         )
+        // Protobuf schemas (descriptor sets for typed gRPC inputs)
+        .route(
+            "/api/v1/schemas",
+            get(api::list_schemas)
+                .put(api::put_schemas)
+                .delete(api::delete_schemas),
+        )
+        .route("/api/v1/schemas/:name", get(api::get_schema))
+        // Per-ruleset input JSON Schemas
+        .route(
+            "/api/v1/rulesets/:name/schema",
+            get(api::get_input_schema)
+                .put(api::put_input_schema)
+                .delete(api::delete_input_schema),
+        )
         // Webhook management
         .route(
             "/api/v1/webhooks",
@@ -700,6 +748,7 @@ async fn start_http_server(
         )
         // Admin API
         .route("/api/v1/admin/reload", post(api::admin_reload))
+        .route("/api/v1/admin/tiers", get(api::admin_tiers))
         // Metrics
         .route("/metrics", get(prometheus_metrics))
         // Tenant management
@@ -748,6 +797,20 @@ async fn start_http_server(
             .route(
                 "/api/v1/debug/control/:session_id",
                 post(debug::api::debug_control),
+            )
+            // Execution recording and time-travel replay
+            .route(
+                "/api/v1/debug/record/:name",
+                post(debug::api::record_execution),
+            )
+            .route("/api/v1/debug/replays", post(debug::api::load_recording))
+            .route(
+                "/api/v1/debug/replays/:replay_id",
+                get(debug::api::get_replay).delete(debug::api::delete_replay),
+            )
+            .route(
+                "/api/v1/debug/replays/:replay_id/control",
+                post(debug::api::replay_control),
             );
     }
 
@@ -926,6 +989,9 @@ async fn prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse
     let store = state.store.read().await;
     metrics::set_rules_count(store.len() as i64);
     drop(store);
+    if let Some(tiering) = state.executor.tiering() {
+        metrics::set_tiering_stats(&tiering.stats());
+    }
 
     // Combine standard metrics with custom rule metrics
     let standard_metrics = metrics::encode_metrics();
@@ -9,8 +9,8 @@ use ordo_core::prelude::MetricSink;
 use parking_lot::RwLock;
 use prometheus::{
     register_counter_vec, register_gauge, register_gauge_vec, register_histogram_vec,
-    register_int_gauge, Counter, CounterVec, Encoder, Gauge, GaugeVec, HistogramVec, IntGauge,
-    Opts, Registry, TextEncoder,
+    register_int_gauge, register_int_gauge_vec, Counter, CounterVec, Encoder, Gauge, GaugeVec,
+    HistogramVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -219,6 +219,28 @@ lazy_static! {
         "Total number of individual results in batch executions",
         &["ruleset", "result"]
     ).unwrap();
+
+    // ==================== Tiered Execution Metrics ====================
+
+    /// Number of conditions currently running on each execution tier
+    pub static ref TIER_CONDITIONS: IntGaugeVec = register_int_gauge_vec!(
+        "ordo_tier_conditions",
+        "Number of rule conditions per execution tier",
+        &["tier"]
+    ).unwrap();
+
+    /// Cumulative tier transitions and guard failures (mirrored from the executor)
+    pub static ref TIER_EVENTS: IntGaugeVec = register_int_gauge_vec!(
+        "ordo_tier_events",
+        "Cumulative tiered execution events (promotions, compile failures, guard failures, deoptimizations)",
+        &["event"]
+    ).unwrap();
+
+    /// Native code held by JIT conditions
+    pub static ref TIER_JIT_CODE_BYTES: IntGauge = register_int_gauge!(
+        "ordo_tier_jit_code_bytes",
+        "Native code size of the JIT-compiled conditions, in bytes"
+    ).unwrap();
 }
 
 /// Initialize metrics (call once at startup)
@@ -367,6 +389,27 @@ pub fn record_hot_reload(kind: &str, success: bool) {
     }
 }
 
+/// Update tiered execution metrics from executor statistics
+pub fn set_tiering_stats(stats: &ordo_core::rule::TieringStats) {
+    for (tier, count) in [
+        ("interpreter", stats.interpreter_conditions),
+        ("bytecode", stats.bytecode_conditions),
+        ("jit", stats.jit_conditions),
+    ] {
+        TIER_CONDITIONS.with_label_values(&[tier]).set(count as i64);
+    }
+    for (event, count) in [
+        ("bytecode_promotion", stats.bytecode_promotions),
+        ("jit_promotion", stats.jit_promotions),
+        ("jit_compile_failure", stats.jit_compile_failures),
+        ("guard_failure", stats.guard_failures),
+        ("deoptimization", stats.deoptimizations),
+    ] {
+        TIER_EVENTS.with_label_values(&[event]).set(count as i64);
+    }
+    TIER_JIT_CODE_BYTES.set(stats.jit_code_bytes as i64);
+}
+
 /// Record batch execution metrics
 pub fn record_batch_execution(
     ruleset: &str,
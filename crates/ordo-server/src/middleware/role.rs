@@ -57,6 +57,7 @@ fn is_write_request(method: &Method, path: &str) -> bool {
         "config" => true,
         "webhooks" => true,
         "admin" => true,
+        "schemas" => true,
         "execute" => false,
         "eval" => false,
         "debug" => false,
@@ -128,6 +129,13 @@ mod tests {
         assert!(!is_write_request(&Method::GET, "/api/v1/webhooks"));
     }
 
+    #[test]
+    fn test_schema_upload_is_blocked() {
+        assert!(is_write_request(&Method::PUT, "/api/v1/schemas"));
+        assert!(is_write_request(&Method::DELETE, "/api/v1/schemas"));
+        assert!(!is_write_request(&Method::GET, "/api/v1/schemas/loan.Loan"));
+    }
+
     #[test]
     fn test_config_write_is_blocked() {
         assert!(is_write_request(
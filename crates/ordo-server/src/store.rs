@@ -8,6 +8,7 @@ use crate::metrics;
 use crate::sync::event::SyncEvent;
 use crate::sync::file_watcher::RecentWrites;
 use once_cell::sync::Lazy;
+use ordo_core::context::{JsonSchemaSet, ProtoSchemaSet};
 use ordo_core::prelude::{MetricSink, RuleExecutor, RuleSet, TraceConfig};
 use ordo_core::signature::{strip_signature, RuleVerifier};
 use regex::Regex;
@@ -25,6 +26,11 @@ use tracing::{debug, error, info, warn};
 static VERSION_FILE_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\.v\d+$").expect("Invalid version file regex pattern"));
 
+/// File name of a tenant's persisted protobuf descriptor set
+const DESCRIPTOR_SET_FILE: &str = "descriptor_set.pb";
+/// File suffix of a ruleset's persisted input JSON Schema (in `schemas/`)
+const INPUT_SCHEMA_SUFFIX: &str = ".schema.json";
+
 /// Supported file formats for rule persistence
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileFormat {
@@ -83,6 +89,10 @@ pub struct RuleStore {
     max_total_rules: Option<usize>,
     /// External reference data store (keyed by tenant:name)
     data: HashMap<String, Arc<ordo_core::context::Value>>,
+    /// Protobuf message types per tenant, from uploaded descriptor sets
+    schemas: HashMap<String, Arc<ProtoSchemaSet>>,
+    /// Input JSON Schemas per ruleset (keyed like `rulesets`)
+    input_schemas: HashMap<String, Arc<JsonSchemaSet>>,
     /// Self-write tracker — paths written by this process are recorded here
     /// so the file watcher can skip redundant reloads.
     recent_writes: Option<Arc<RecentWrites>>,
@@ -127,6 +137,8 @@ impl RuleStore {
             max_rules_per_tenant: None,
             max_total_rules: None,
             data: HashMap::new(),
+            schemas: HashMap::new(),
+            input_schemas: HashMap::new(),
             recent_writes: None,
         }
     }
@@ -147,6 +159,8 @@ impl RuleStore {
             max_rules_per_tenant: None,
             max_total_rules: None,
             data: HashMap::new(),
+            schemas: HashMap::new(),
+            input_schemas: HashMap::new(),
             recent_writes: None,
         }
     }
@@ -173,6 +187,8 @@ impl RuleStore {
             max_rules_per_tenant: None,
             max_total_rules: None,
             data: HashMap::new(),
+            schemas: HashMap::new(),
+            input_schemas: HashMap::new(),
             recent_writes: None,
         }
     }
@@ -197,6 +213,8 @@ impl RuleStore {
             max_rules_per_tenant: None,
             max_total_rules: None,
             data: HashMap::new(),
+            schemas: HashMap::new(),
+            input_schemas: HashMap::new(),
             recent_writes: None,
         }
     }
@@ -745,7 +763,7 @@ impl RuleStore {
         }
 
         // Sort by seq descending (newest first)
-        versions.sort_by(|a, b| b.0.cmp(&a.0));
+        versions.sort_by_key(|b| std::cmp::Reverse(b.0));
         Ok(versions)
     }
 
@@ -1086,6 +1104,8 @@ impl RuleStore {
                 error!("Failed to delete version files for '{}': {}", name, e);
             }
 
+            self.delete_input_schema_for_tenant(tenant_id, name);
+
             // Publish sync event (non-blocking, best-effort)
             if let Some(tx) = &self.sync_tx {
                 let _ = tx.send(SyncEvent::RuleDeleted {
@@ -1445,6 +1465,224 @@ impl RuleStore {
         }
         Ok(loaded)
     }
+
+    // ==================== Protobuf Schemas ====================
+
+    /// Path of a tenant's persisted descriptor set
+    fn descriptor_set_path(&self, tenant_id: &str) -> Option<PathBuf> {
+        self.tenant_rules_dir(tenant_id)
+            .map(|dir| dir.join("schemas").join(DESCRIPTOR_SET_FILE))
+    }
+
+    /// Replace a tenant's protobuf schemas with a serialized `FileDescriptorSet`
+    pub fn put_schemas_for_tenant(
+        &mut self,
+        tenant_id: &str,
+        descriptor_set: &[u8],
+    ) -> io::Result<Arc<ProtoSchemaSet>> {
+        let set = ProtoSchemaSet::from_descriptor_set(descriptor_set)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        // Persist to disk first, so a failed write doesn't leave stale in-memory schemas
+        if let Some(path) = self.descriptor_set_path(tenant_id) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, descriptor_set)?;
+            info!(
+                "Persisted {} protobuf message types for tenant '{}' to {:?}",
+                set.len(),
+                tenant_id,
+                path
+            );
+        }
+
+        let set = Arc::new(set);
+        self.schemas.insert(tenant_id.to_string(), set.clone());
+        Ok(set)
+    }
+
+    /// Get a tenant's protobuf schemas
+    pub fn get_schemas_for_tenant(&self, tenant_id: &str) -> Option<Arc<ProtoSchemaSet>> {
+        self.schemas.get(tenant_id).cloned()
+    }
+
+    /// Delete a tenant's protobuf schemas
+    pub fn delete_schemas_for_tenant(&mut self, tenant_id: &str) -> bool {
+        let existed = self.schemas.remove(tenant_id).is_some();
+
+        if existed {
+            if let Some(path) = self.descriptor_set_path(tenant_id) {
+                if path.exists() {
+                    if let Err(e) = fs::remove_file(&path) {
+                        warn!("Failed to delete descriptor set {:?}: {}", path, e);
+                    }
+                }
+            }
+        }
+
+        existed
+    }
+
+    /// Tenants that may have persisted schemas
+    fn schema_tenants(&self) -> io::Result<Vec<String>> {
+        let rules_dir = match &self.rules_dir {
+            Some(dir) => dir,
+            None => return Ok(Vec::new()),
+        };
+
+        if self.multi_tenancy_enabled {
+            Ok(fs::read_dir(rules_dir)?
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect())
+        } else {
+            Ok(vec![self.default_tenant.clone()])
+        }
+    }
+
+    /// Load persisted descriptor sets during startup
+    pub fn load_schemas_from_dir(&mut self) -> io::Result<usize> {
+        let mut loaded = 0;
+        for tenant_id in self.schema_tenants()? {
+            let Some(path) = self.descriptor_set_path(&tenant_id) else {
+                continue;
+            };
+            if !path.is_file() {
+                continue;
+            }
+            let bytes = fs::read(&path)?;
+            let set = ProtoSchemaSet::from_descriptor_set(&bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+            info!(
+                "Loaded {} protobuf message types for tenant '{}' from {:?}",
+                set.len(),
+                tenant_id,
+                path
+            );
+            self.schemas.insert(tenant_id, Arc::new(set));
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    // ==================== Input JSON Schemas ====================
+
+    /// Path of a ruleset's persisted input JSON Schema
+    fn input_schema_path(&self, tenant_id: &str, name: &str) -> Option<PathBuf> {
+        self.tenant_rules_dir(tenant_id).map(|dir| {
+            dir.join("schemas")
+                .join(format!("{}{}", name, INPUT_SCHEMA_SUFFIX))
+        })
+    }
+
+    /// Set the JSON Schema describing a ruleset's input
+    ///
+    /// The root message is named after the ruleset.
+    pub fn put_input_schema_for_tenant(
+        &mut self,
+        tenant_id: &str,
+        name: &str,
+        document: serde_json::Value,
+    ) -> io::Result<Arc<JsonSchemaSet>> {
+        let set = JsonSchemaSet::from_document(name, document)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        // Persist to disk first, so a failed write doesn't leave a stale in-memory schema
+        if let Some(path) = self.input_schema_path(tenant_id, name) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let json = serde_json::to_string_pretty(set.document())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            fs::write(&path, json)?;
+            if let Some(ref rw) = self.recent_writes {
+                rw.record(path.clone());
+            }
+            info!(
+                "Persisted input schema for '{}' (tenant '{}') to {:?}",
+                name, tenant_id, path
+            );
+        }
+
+        let set = Arc::new(set);
+        self.input_schemas
+            .insert(self.make_key(tenant_id, name), set.clone());
+        Ok(set)
+    }
+
+    /// Get the JSON Schema describing a ruleset's input
+    pub fn get_input_schema_for_tenant(
+        &self,
+        tenant_id: &str,
+        name: &str,
+    ) -> Option<Arc<JsonSchemaSet>> {
+        self.input_schemas
+            .get(&self.make_key(tenant_id, name))
+            .cloned()
+    }
+
+    /// Delete the JSON Schema describing a ruleset's input
+    pub fn delete_input_schema_for_tenant(&mut self, tenant_id: &str, name: &str) -> bool {
+        let key = self.make_key(tenant_id, name);
+        let existed = self.input_schemas.remove(&key).is_some();
+
+        if existed {
+            if let Some(path) = self.input_schema_path(tenant_id, name) {
+                if path.exists() {
+                    if let Err(e) = fs::remove_file(&path) {
+                        warn!("Failed to delete input schema {:?}: {}", path, e);
+                    } else if let Some(ref rw) = self.recent_writes {
+                        rw.record(path);
+                    }
+                }
+            }
+        }
+
+        existed
+    }
+
+    /// Load persisted input JSON Schemas during startup
+    pub fn load_input_schemas_from_dir(&mut self) -> io::Result<usize> {
+        let mut loaded = 0;
+        for tenant_id in self.schema_tenants()? {
+            let Some(schemas_dir) = self.tenant_rules_dir(&tenant_id).map(|d| d.join("schemas"))
+            else {
+                continue;
+            };
+            if !schemas_dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&schemas_dir)?.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                let Some(name) = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_suffix(INPUT_SCHEMA_SUFFIX))
+                else {
+                    continue;
+                };
+                let document = fs::read_to_string(&path).and_then(|json| {
+                    serde_json::from_str(&json)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                });
+                let set = document.and_then(|document| {
+                    JsonSchemaSet::from_document(name, document)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+                });
+                match set {
+                    Ok(set) => {
+                        let key = self.make_key(&tenant_id, name);
+                        self.input_schemas.insert(key, Arc::new(set));
+                        loaded += 1;
+                    }
+                    Err(e) => warn!("Failed to load input schema {:?}: {}", path, e),
+                }
+            }
+        }
+        Ok(loaded)
+    }
 }
 
 impl Default for RuleStore {
@@ -1899,6 +2137,80 @@ steps:
         assert_eq!(store2.list_for_tenant("tenant-b").len(), 1);
     }
 
+    #[test]
+    fn test_schema_persistence() {
+        let temp_dir = TempDir::new().unwrap();
+        let rules_dir = temp_dir.path().join("tenants");
+        let descriptor_set = crate::grpc::tests::input_descriptor_set();
+
+        let mut store = RuleStore::new_with_persistence_and_versions(rules_dir.clone(), 10);
+        store.enable_multi_tenancy("default".to_string());
+        let set = store
+            .put_schemas_for_tenant("tenant-a", &descriptor_set)
+            .unwrap();
+        assert_eq!(set.message_names(), vec!["test.Input"]);
+        assert!(rules_dir
+            .join("tenant-a")
+            .join("schemas")
+            .join(DESCRIPTOR_SET_FILE)
+            .exists());
+
+        // Invalid descriptor sets are rejected without touching the stored ones
+        let err = store
+            .put_schemas_for_tenant("tenant-a", &[0xff, 0xff])
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut store2 = RuleStore::new_with_persistence_and_versions(rules_dir.clone(), 10);
+        store2.enable_multi_tenancy("default".to_string());
+        assert_eq!(store2.load_schemas_from_dir().unwrap(), 1);
+        assert!(store2.get_schemas_for_tenant("tenant-a").is_some());
+        assert!(store2.get_schemas_for_tenant("tenant-b").is_none());
+
+        assert!(store2.delete_schemas_for_tenant("tenant-a"));
+        assert!(!rules_dir
+            .join("tenant-a")
+            .join("schemas")
+            .join(DESCRIPTOR_SET_FILE)
+            .exists());
+    }
+
+    #[test]
+    fn test_input_schema_persistence() {
+        let temp_dir = TempDir::new().unwrap();
+        let document = serde_json::json!({
+            "type": "object",
+            "properties": { "amount": { "type": "number" } }
+        });
+
+        let mut store = RuleStore::new_with_persistence(temp_dir.path().to_path_buf());
+        store.put(create_test_ruleset("loan")).unwrap();
+        let err = store
+            .put_input_schema_for_tenant("default", "loan", serde_json::json!([]))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let set = store
+            .put_input_schema_for_tenant("default", "loan", document)
+            .unwrap();
+        assert_eq!(set.message_names(), vec!["loan"]);
+        let path = temp_dir.path().join("schemas").join("loan.schema.json");
+        assert!(path.exists());
+
+        let mut store2 = RuleStore::new_with_persistence(temp_dir.path().to_path_buf());
+        store2.load_from_dir().unwrap();
+        assert_eq!(store2.load_input_schemas_from_dir().unwrap(), 1);
+        assert!(store2
+            .get_input_schema_for_tenant("default", "loan")
+            .is_some());
+
+        // Deleting the ruleset drops its input schema
+        assert!(store2.delete("loan"));
+        assert!(store2
+            .get_input_schema_for_tenant("default", "loan")
+            .is_none());
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_max_total_rules_limit() {
         let mut store = RuleStore::new();
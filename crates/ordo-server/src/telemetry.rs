@@ -3,9 +3,14 @@
 //! If `ORDO_OTLP_ENDPOINT` is set, spans are exported via OTLP/HTTP.
 //! Otherwise only stdout logging is active (zero runtime overhead).
 
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use opentelemetry_sdk::trace::TracerProvider;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+/// Set once an OTLP exporter is installed
+static OTLP_ENABLED: AtomicBool = AtomicBool::new(false);
+
 /// Initialize the global tracing subscriber.
 ///
 /// Returns a `TracerProvider` if OTLP was configured — caller must call
@@ -33,6 +38,7 @@ pub fn init(
                     .with(fmt_layer)
                     .with(otel_layer)
                     .init();
+                OTLP_ENABLED.store(true, Ordering::Relaxed);
 
                 tracing::info!(
                     endpoint = %endpoint,
@@ -64,6 +70,26 @@ pub fn init(
     }
 }
 
+/// Whether the trace of the current span is sampled for export.
+///
+/// Rule executions open spans per step only when this is true (see
+/// `ExecutionOptions::spans`), so unsampled requests and servers without
+/// OTLP don't pay for them.
+pub fn sampled() -> bool {
+    OTLP_ENABLED.load(Ordering::Relaxed) && current_span_sampled()
+}
+
+fn current_span_sampled() -> bool {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    tracing::Span::current()
+        .context()
+        .span()
+        .span_context()
+        .is_sampled()
+}
+
 /// Flush and shut down the OTLP exporter. Call this before process exit.
 pub fn shutdown(provider: TracerProvider) {
     opentelemetry::global::shutdown_tracer_provider();
@@ -101,3 +127,223 @@ fn build_otel_provider(service_name: &str, endpoint: &str) -> anyhow::Result<Tra
 
     Ok(provider)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use opentelemetry::trace::{SpanId, TracerProvider as _};
+    use opentelemetry::Value as OtelValue;
+    use opentelemetry_sdk::export::trace::{ExportResult, SpanData, SpanExporter};
+    use opentelemetry_sdk::trace::{Config, Sampler};
+    use ordo_core::context::Value;
+    use ordo_core::expr::Expr;
+    use ordo_core::rule::{
+        Action, ActionKind, Condition, ExecutionOptions, ExternalCallHandler, RuleExecutor,
+        RuleSet, RuleSetResolver, Step, TerminalResult,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Collects exported spans in memory
+    #[derive(Debug, Clone, Default)]
+    struct MemoryExporter(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for MemoryExporter {
+        fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+            self.0.lock().unwrap().extend(batch);
+            Box::pin(std::future::ready(Ok(())))
+        }
+    }
+
+    struct Resolver(Arc<RuleSet>);
+
+    impl RuleSetResolver for Resolver {
+        fn resolve(&self, name: &str) -> Option<Arc<RuleSet>> {
+            (name == "score").then(|| self.0.clone())
+        }
+    }
+
+    struct Crm;
+
+    impl ExternalCallHandler for Crm {
+        fn call(&self, _: &str, _: &str, _: &Value, _: u64) -> ordo_core::error::Result<Value> {
+            Ok(Value::Null)
+        }
+    }
+
+    /// `main` calls `score` and branches on its result
+    fn executor() -> (RuleExecutor, RuleSet) {
+        let mut score = RuleSet::new("score", "compute");
+        score.add_step(Step::terminal(
+            "compute",
+            "Compute",
+            TerminalResult::new("SCORED").with_output("score", Expr::literal(95)),
+        ));
+
+        let mut main = RuleSet::new("main", "call_score");
+        main.add_step(Step::action(
+            "call_score",
+            "Call Score",
+            vec![
+                Action {
+                    kind: ActionKind::CallRuleSet {
+                        ruleset_name: "score".to_string(),
+                        input_mapping: None,
+                        result_variable: "scored".to_string(),
+                    },
+                    description: String::new(),
+                },
+                Action {
+                    kind: ActionKind::ExternalCall {
+                        service: "crm".to_string(),
+                        method: "lookup".to_string(),
+                        params: Vec::new(),
+                        timeout_ms: 100,
+                        result_variable: None,
+                    },
+                    description: String::new(),
+                },
+            ],
+            "check",
+        ));
+        main.add_step(
+            Step::decision("check", "Check")
+                .branch(Condition::from_string("$scored == null"), "deny")
+                .branch(Condition::from_string("$scored != null"), "allow")
+                .default("deny")
+                .build(),
+        );
+        main.add_step(Step::terminal(
+            "allow",
+            "Allow",
+            TerminalResult::new("ALLOW"),
+        ));
+        main.add_step(Step::terminal("deny", "Deny", TerminalResult::new("DENY")));
+
+        let mut executor = RuleExecutor::new();
+        executor.set_resolver(Arc::new(Resolver(Arc::new(score))));
+        executor.set_external_handler(Arc::new(Crm));
+        (executor, main)
+    }
+
+    /// Execute `main` inside a request span traced with `sampler`
+    fn execute(sampler: Sampler) -> (Vec<SpanData>, String) {
+        let exporter = MemoryExporter::default();
+        let provider = TracerProvider::builder()
+            .with_config(Config::default().with_sampler(sampler))
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_opentelemetry::OpenTelemetryLayer::new(provider.tracer("test")),
+        );
+        OTLP_ENABLED.store(true, Ordering::Relaxed);
+
+        let (executor, ruleset) = executor();
+        let code = tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request").in_scope(|| {
+                let options = ExecutionOptions::default().spans(sampled());
+                executor
+                    .execute_with_options(
+                        &ruleset,
+                        Value::object(Default::default()),
+                        Some(&options),
+                    )
+                    .unwrap()
+                    .code
+            })
+        });
+        provider.force_flush();
+        let spans = exporter.0.lock().unwrap().clone();
+        (spans, code)
+    }
+
+    fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a OtelValue> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| &kv.value)
+    }
+
+    fn find<'a>(spans: &'a [SpanData], name: &str, step: Option<&str>) -> &'a SpanData {
+        spans
+            .iter()
+            .find(|s| {
+                s.name == name
+                    && step.map_or(true, |step| {
+                        attribute(s, "ordo.step.id") == Some(&OtelValue::from(step.to_string()))
+                    })
+            })
+            .unwrap_or_else(|| panic!("no span {} {:?}", name, step))
+    }
+
+    #[test]
+    fn test_sampled_execution_emits_step_spans() {
+        let (spans, code) = execute(Sampler::AlwaysOn);
+        assert_eq!(code, "ALLOW");
+
+        let request = find(&spans, "request", None);
+        let call_step = find(&spans, "ordo.step", Some("call_score"));
+        let call = find(&spans, "ordo.call_ruleset", None);
+        let external = find(&spans, "ordo.external_call", None);
+        let compute = find(&spans, "ordo.step", Some("compute"));
+        let check = find(&spans, "ordo.step", Some("check"));
+        let allow = find(&spans, "ordo.step", Some("allow"));
+
+        // Steps are children of the request; the sub-execution's of the call
+        let trace_id = request.span_context.trace_id();
+        assert!(spans.iter().all(|s| s.span_context.trace_id() == trace_id));
+        assert_eq!(request.parent_span_id, SpanId::INVALID);
+        for step in [call_step, check, allow] {
+            assert_eq!(step.parent_span_id, request.span_context.span_id());
+        }
+        assert_eq!(call.parent_span_id, call_step.span_context.span_id());
+        assert_eq!(compute.parent_span_id, call.span_context.span_id());
+        assert_eq!(external.parent_span_id, call_step.span_context.span_id());
+
+        assert_eq!(
+            attribute(call, "ordo.ruleset"),
+            Some(&OtelValue::from("score"))
+        );
+        assert_eq!(
+            attribute(call, "ordo.result.code"),
+            Some(&OtelValue::from("SCORED"))
+        );
+        assert_eq!(
+            attribute(check, "ordo.step.kind"),
+            Some(&OtelValue::from("decision"))
+        );
+        assert_eq!(
+            attribute(check, "ordo.branch.index"),
+            Some(&OtelValue::I64(1))
+        );
+        assert_eq!(
+            attribute(check, "ordo.next_step"),
+            Some(&OtelValue::from("allow"))
+        );
+        assert_eq!(
+            attribute(allow, "ordo.result.code"),
+            Some(&OtelValue::from("ALLOW"))
+        );
+        assert_eq!(
+            attribute(external, "ordo.external.service"),
+            Some(&OtelValue::from("crm"))
+        );
+        assert_eq!(
+            attribute(external, "ordo.external.outcome"),
+            Some(&OtelValue::from("ok"))
+        );
+        for span in [check, call, external] {
+            assert!(matches!(
+                attribute(span, "ordo.duration_us"),
+                Some(OtelValue::I64(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unsampled_execution_emits_no_spans() {
+        let (spans, code) = execute(Sampler::AlwaysOff);
+        assert_eq!(code, "ALLOW");
+        assert!(spans.is_empty());
+    }
+}
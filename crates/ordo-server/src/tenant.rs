@@ -3,6 +3,7 @@
 //! Provides tenant configs with optional file persistence.
 
 use crate::sync::event::SyncEvent;
+use ordo_core::budget::ResourceBudget;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io;
@@ -19,6 +20,9 @@ pub struct TenantConfig {
     pub burst_limit: Option<u32>,
     pub execution_timeout_ms: u64,
     pub max_rules: Option<usize>,
+    /// Per-execution resource budget (fuel, memory, value sizes, regex size)
+    #[serde(default)]
+    pub resource_budget: ResourceBudget,
     #[serde(default)]
     pub metadata: HashMap<String, String>,
 }
@@ -33,9 +37,15 @@ impl TenantConfig {
             burst_limit: defaults.default_burst_limit,
             execution_timeout_ms: defaults.default_timeout_ms,
             max_rules: None,
+            resource_budget: defaults.default_budget.clone(),
             metadata: HashMap::new(),
         }
     }
+
+    /// Budget to execute this tenant's rules under (`None` when unlimited)
+    pub fn execution_budget(&self) -> Option<ResourceBudget> {
+        (!self.resource_budget.is_unlimited()).then(|| self.resource_budget.clone())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +53,7 @@ pub struct TenantDefaults {
     pub default_qps_limit: Option<u32>,
     pub default_burst_limit: Option<u32>,
     pub default_timeout_ms: u64,
+    pub default_budget: ResourceBudget,
 }
 
 #[derive(Debug, Clone)]
@@ -230,6 +241,7 @@ mod tests {
             default_qps_limit: Some(100),
             default_burst_limit: Some(10),
             default_timeout_ms: 100,
+            default_budget: ResourceBudget::default(),
         };
 
         let manager = TenantManager::new(Some(store.clone()), defaults)
@@ -257,6 +269,7 @@ mod tests {
             default_qps_limit: None,
             default_burst_limit: None,
             default_timeout_ms: 100,
+            default_budget: ResourceBudget::default(),
         };
 
         let manager = TenantManager::new(Some(store), defaults).await.unwrap();
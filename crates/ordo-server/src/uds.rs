@@ -99,6 +99,7 @@ mod tests {
             default_qps_limit: Some(1000),
             default_burst_limit: Some(100),
             default_timeout_ms: 100,
+            default_budget: Default::default(),
         };
         let tenant_manager = Arc::new(TenantManager::new(None, defaults).await.unwrap());
         tenant_manager.ensure_default("default").await.unwrap();
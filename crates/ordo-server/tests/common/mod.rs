@@ -109,7 +109,7 @@ use ordo_proto::{
     BatchExecuteResponse, BatchExecuteResultItem, BatchExecuteSummary, EvalRequest, EvalResponse,
     ExecuteRequest, ExecuteResponse, ExecutionTrace, GetRuleSetRequest, GetRuleSetResponse,
     HealthRequest, HealthResponse, ListRuleSetsRequest, ListRuleSetsResponse, RuleSetSummary,
-    StepTrace,
+    StepTrace, TypedEvalRequest, TypedEvalResponse, TypedExecuteRequest,
 };
 use tonic::{Request, Response, Status};
 
@@ -386,6 +386,24 @@ impl OrdoService for OrdoGrpcServiceImpl {
             uptime_seconds: self.start_time.elapsed().as_secs(),
         }))
     }
+
+    async fn typed_execute(
+        &self,
+        _request: Request<TypedExecuteRequest>,
+    ) -> std::result::Result<Response<ExecuteResponse>, Status> {
+        Err(Status::unimplemented(
+            "Typed inputs are not supported by the test server",
+        ))
+    }
+
+    async fn typed_eval(
+        &self,
+        _request: Request<TypedEvalRequest>,
+    ) -> std::result::Result<Response<TypedEvalResponse>, Status> {
+        Err(Status::unimplemented(
+            "Typed inputs are not supported by the test server",
+        ))
+    }
 }
 
 /// Wait for gRPC server to be available over TCP
@@ -288,6 +288,26 @@ pub fn analyze_jit_compatibility(expression: &str) -> std::result::Result<String
         .map_err(|e| JsValue::from_str(&format!("Failed to serialize result: {}", e)))
 }
 
+/// Derive message schemas from a JSON Schema document
+///
+/// # Arguments
+/// * `name` - Name of the root message (usually the ruleset name)
+/// * `schema_json` - JSON Schema document describing the rule input
+///
+/// # Returns
+/// JSON string containing `{"root": ..., "messages": [...], "enums": {...}}`
+#[wasm_bindgen]
+pub fn json_schema_to_message_schema(
+    name: &str,
+    schema_json: &str,
+) -> std::result::Result<String, JsValue> {
+    let set = ordo_core::context::JsonSchemaSet::from_json(name, schema_json)
+        .map_err(|e| JsValue::from_str(&format!("Failed to convert JSON Schema: {}", e)))?;
+
+    serde_json::to_string(&set)
+        .map_err(|e| JsValue::from_str(&format!("Failed to serialize result: {}", e)))
+}
+
 /// Analyze an entire ruleset for JIT compatibility
 ///
 /// # Arguments